@@ -36,7 +36,7 @@ use std::fmt::Debug;
 use std::sync::Arc;
 
 use chrono::offset::Utc;
-use chrono::DateTime;
+use chrono::{DateTime, SecondsFormat};
 use reqwest::{header, Client, Method, Request, StatusCode, Url};
 use schemars::JsonSchema;
 use serde::de::DeserializeOwned;
@@ -46,13 +46,17 @@ use serde::{Deserialize, Deserializer, Serialize};
 /// Endpoint for the Airtable API.
 const ENDPOINT: &str = "https://api.airtable.com/v0/";
 
+/// The most records that can be created, updated or deleted in one request.
+pub const MAX_RECORDS_PER_REQUEST: usize = 10;
+
 /// Entrypoint for interacting with the Airtable API.
 pub struct Airtable {
     key: String,
     base_id: String,
     enterprise_account_id: String,
+    base_url: String,
 
-    client: Arc<Client>,
+    client: Arc<cio_http::Client>,
 }
 
 /// Get the API key from the AIRTABLE_API_KEY env variable.
@@ -66,6 +70,8 @@ impl Airtable {
     /// given a valid API Key and Base ID your requests will work.
     /// You can leave the Enterprise Account ID empty if you are not using the
     /// Enterprise API features.
+    /// The client talks to the base URL in the AIRTABLE_BASE_URL env variable
+    /// if it is set, see `with_base_url`.
     pub fn new<K, B, E>(key: K, base_id: B, enterprise_account_id: E) -> Self
     where
         K: ToString,
@@ -78,8 +84,9 @@ impl Airtable {
                 key: key.to_string(),
                 base_id: base_id.to_string(),
                 enterprise_account_id: enterprise_account_id.to_string(),
+                base_url: env::var("AIRTABLE_BASE_URL").unwrap_or_default(),
 
-                client: Arc::new(c),
+                client: Arc::new(cio_http::Client::new(c)),
             },
             Err(e) => panic!("creating client failed: {:?}", e),
         }
@@ -96,6 +103,16 @@ impl Airtable {
         Airtable::new(api_key_from_env(), base_id, enterprise_account_id)
     }
 
+    /// Send requests to another base URL, like a fake server in tests, instead
+    /// of the Airtable API.
+    pub fn with_base_url<U>(mut self, base_url: U) -> Self
+    where
+        U: ToString,
+    {
+        self.base_url = base_url.to_string();
+        self
+    }
+
     /// Get the currently set API key.
     pub fn get_key(&self) -> &str {
         &self.key
@@ -105,7 +122,7 @@ impl Airtable {
     where
         B: Serialize,
     {
-        let base = Url::parse(ENDPOINT).unwrap();
+        let base = Url::parse(&cio_http::rebase(ENDPOINT, &self.base_url)).unwrap();
         let url = base.join(&(self.base_id.to_string() + "/" + &path)).unwrap();
 
         let bt = format!("Bearer {}", self.key);
@@ -136,43 +153,32 @@ impl Airtable {
 
     /// List records in a table for a particular view.
     pub async fn list_records<T: DeserializeOwned>(&self, table: &str, view: &str, fields: Vec<&str>) -> Result<Vec<Record<T>>, APIError> {
-        let mut params = vec![("pageSize", "100".to_string()), ("view", view.to_string())];
-        for field in fields {
-            params.push(("fields", field.to_string()));
-        }
-
-        // Build the request.
-        let mut request = self.request(Method::GET, table.to_string(), (), Some(params));
-
-        let mut resp = self.client.execute(request).await.unwrap();
-        match resp.status() {
-            StatusCode::OK => (),
-            s => {
-                return Err(APIError {
-                    status_code: s,
-                    body: resp.text().await.unwrap(),
-                })
-            }
+        let options = ListOptions {
+            view: view.to_string(),
+            fields: fields.iter().map(|f| f.to_string()).collect(),
+            ..Default::default()
         };
 
-        // Try to deserialize the response.
-        let mut r: APICall<T> = resp.json().await.unwrap();
-
-        let mut records = r.records;
+        self.list_records_with_options(table, &options).await
+    }
 
-        let mut offset = r.offset;
+    /// List records in a table, filtered and sorted by the given options.
+    pub async fn list_records_with_options<T: DeserializeOwned>(&self, table: &str, options: &ListOptions) -> Result<Vec<Record<T>>, APIError> {
+        let mut records: Vec<Record<T>> = Default::default();
+        let mut offset = String::new();
 
-        // Paginate if we should.
-        // TODO: make this more DRY
-        while !offset.is_empty() {
-            request = self.request(
-                Method::GET,
-                table.to_string(),
-                (),
-                Some(vec![("pageSize", "100".to_string()), ("view", view.to_string()), ("offset", offset)]),
-            );
+        // Paginate until Airtable stops returning an offset.
+        loop {
+            let mut request = self.request(Method::GET, table.to_string(), (), None);
+            {
+                let mut query = request.url_mut().query_pairs_mut();
+                query.extend_pairs(options.query());
+                if !offset.is_empty() {
+                    query.append_pair("offset", &offset);
+                }
+            }
 
-            resp = self.client.execute(request).await.unwrap();
+            let resp = self.client.execute(request).await.unwrap();
             match resp.status() {
                 StatusCode::OK => (),
                 s => {
@@ -184,11 +190,13 @@ impl Airtable {
             };
 
             // Try to deserialize the response.
-            r = resp.json().await.unwrap();
-
+            let mut r: APICall<T> = resp.json().await.unwrap();
             records.append(&mut r.records);
 
             offset = r.offset;
+            if offset.is_empty() {
+                break;
+            }
         }
 
         Ok(records)
@@ -235,6 +243,29 @@ impl Airtable {
         Ok(())
     }
 
+    /// Bulk delete records from a table.
+    ///
+    /// Due to limitations on the Airtable API, you can only bulk delete 10
+    /// records at a time.
+    pub async fn delete_records(&self, table: &str, record_ids: &[String]) -> Result<(), APIError> {
+        // Build the request.
+        let query = record_ids.iter().map(|id| ("records[]", id.to_string())).collect();
+        let request = self.request(Method::DELETE, table.to_string(), (), Some(query));
+
+        let resp = self.client.execute(request).await.unwrap();
+        match resp.status() {
+            StatusCode::OK => (),
+            s => {
+                return Err(APIError {
+                    status_code: s,
+                    body: resp.text().await.unwrap(),
+                })
+            }
+        };
+
+        Ok(())
+    }
+
     /// Bulk create records in a table.
     ///
     /// Due to limitations on the Airtable API, you can only bulk create 10
@@ -389,6 +420,116 @@ impl Airtable {
     }
 }
 
+/// Options for listing records in a table.
+///
+/// The defaults list every record in the table with every field.
+#[derive(Debug, Default, Clone)]
+pub struct ListOptions {
+    /// The name or id of the view to list records from. Records are filtered
+    /// and sorted the same way they are in the view.
+    pub view: String,
+    /// Only return these fields. All fields are returned when this is empty.
+    pub fields: Vec<String>,
+    /// Only return records for which this formula is truthy, for example
+    /// `{Status} = 'Done'`.
+    pub filter_by_formula: String,
+    /// How to sort the records. This takes precedence over the sort of the view.
+    pub sort: Vec<Sort>,
+    /// The most records to return across all pages.
+    pub max_records: Option<u32>,
+    /// The format of cell values, `CellFormat::String` requires `time_zone` and
+    /// `user_locale` to be set.
+    pub cell_format: Option<CellFormat>,
+    pub time_zone: String,
+    pub user_locale: String,
+}
+
+impl ListOptions {
+    /// List every record that was modified after the given time.
+    pub fn modified_since(since: DateTime<Utc>) -> Self {
+        ListOptions {
+            filter_by_formula: modified_since_formula(since),
+            ..Default::default()
+        }
+    }
+
+    fn query(&self) -> Vec<(String, String)> {
+        let mut query = vec![("pageSize".to_string(), "100".to_string())];
+        if !self.view.is_empty() {
+            query.push(("view".to_string(), self.view.to_string()));
+        }
+        for field in &self.fields {
+            query.push(("fields[]".to_string(), field.to_string()));
+        }
+        if !self.filter_by_formula.is_empty() {
+            query.push(("filterByFormula".to_string(), self.filter_by_formula.to_string()));
+        }
+        for (i, sort) in self.sort.iter().enumerate() {
+            query.push((format!("sort[{}][field]", i), sort.field.to_string()));
+            query.push((format!("sort[{}][direction]", i), sort.direction.to_string()));
+        }
+        if let Some(max_records) = self.max_records {
+            query.push(("maxRecords".to_string(), max_records.to_string()));
+        }
+        if let Some(cell_format) = &self.cell_format {
+            query.push(("cellFormat".to_string(), cell_format.to_string()));
+        }
+        if !self.time_zone.is_empty() {
+            query.push(("timeZone".to_string(), self.time_zone.to_string()));
+        }
+        if !self.user_locale.is_empty() {
+            query.push(("userLocale".to_string(), self.user_locale.to_string()));
+        }
+
+        query
+    }
+}
+
+/// A formula that matches records modified after the given time.
+pub fn modified_since_formula(since: DateTime<Utc>) -> String {
+    format!("IS_AFTER(LAST_MODIFIED_TIME(), DATETIME_PARSE('{}'))", since.to_rfc3339_opts(SecondsFormat::Millis, true))
+}
+
+/// A field to sort records by.
+#[derive(Debug, Clone)]
+pub struct Sort {
+    pub field: String,
+    pub direction: SortDirection,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+impl fmt::Display for SortDirection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SortDirection::Asc => write!(f, "asc"),
+            SortDirection::Desc => write!(f, "desc"),
+        }
+    }
+}
+
+/// The format of cell values in listed records.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CellFormat {
+    /// Cell values are returned as JSON, this is the default.
+    Json,
+    /// Cell values are returned as the strings shown in the Airtable UI.
+    String,
+}
+
+impl fmt::Display for CellFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CellFormat::Json => write!(f, "json"),
+            CellFormat::String => write!(f, "string"),
+        }
+    }
+}
+
 /// Error type returned by our library.
 pub struct APIError {
     pub status_code: StatusCode,
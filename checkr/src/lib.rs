@@ -39,14 +39,17 @@ const ENDPOINT: &str = "https://api.checkr.com/v1/";
 /// Entrypoint for interacting with the Checker API.
 pub struct Checkr {
     key: String,
+    base_url: String,
 
-    client: Arc<Client>,
+    client: Arc<cio_http::Client>,
 }
 
 impl Checkr {
     /// Create a new Checker client struct. It takes a type that can convert into
     /// an &str (`String` or `Vec<u8>` for example). As long as the function is
     /// given a valid API key your requests will work.
+    /// The client talks to the base URL in the CHECKR_BASE_URL env variable
+    /// if it is set, see `with_base_url`.
     pub fn new<K>(key: K) -> Self
     where
         K: ToString,
@@ -55,8 +58,9 @@ impl Checkr {
         match client {
             Ok(c) => Self {
                 key: key.to_string(),
+                base_url: env::var("CHECKR_BASE_URL").unwrap_or_default(),
 
-                client: Arc::new(c),
+                client: Arc::new(cio_http::Client::new(c)),
             },
             Err(e) => panic!("creating client failed: {:?}", e),
         }
@@ -72,11 +76,21 @@ impl Checkr {
         Checkr::new(key)
     }
 
+    /// Send requests to another base URL, like a fake server in tests, instead
+    /// of the Checkr API.
+    pub fn with_base_url<U>(mut self, base_url: U) -> Self
+    where
+        U: ToString,
+    {
+        self.base_url = base_url.to_string();
+        self
+    }
+
     fn request<B>(&self, method: Method, path: &str, body: B, query: Option<Vec<(&str, String)>>) -> Request
     where
         B: Serialize,
     {
-        let base = Url::parse(ENDPOINT).unwrap();
+        let base = Url::parse(&cio_http::rebase(ENDPOINT, &self.base_url)).unwrap();
         let url = base.join(path).unwrap();
 
         // Set the default headers.
@@ -125,7 +139,7 @@ impl Checkr {
         // Paginate if we should.
         // TODO: make this more DRY
         while !next_href.is_empty() {
-            request = self.request(Method::GET, next_href.trim_start_matches(ENDPOINT), (), None);
+            request = self.request(Method::GET, next_href.trim_start_matches(&cio_http::rebase(ENDPOINT, &self.base_url)), (), None);
 
             resp = self.client.execute(request).await.unwrap();
             match resp.status() {
@@ -0,0 +1,409 @@
+/*!
+ * A wrapper around a reqwest client that is shared by the API clients in this
+ * repo, so they all handle rate limits and flaky upstreams the same way.
+ *
+ * The client:
+ *
+ * - retries `429 Too Many Requests` responses, waiting for as long as the
+ *   `Retry-After` or `X-RateLimit-Reset` headers ask,
+ * - retries `5xx` responses and connection errors with jittered exponential
+ *   backoff, for idempotent requests only so we never send a `POST` twice,
+ * - holds back every request to a host that told us it is out of quota until
+ *   the quota resets, and
+ * - limits the number of requests in flight to each host across every client
+ *   in the process.
+ *
+ * The API clients also build their URLs with `rebase`, so each of them can be
+ * pointed at a fake server in tests by setting its base URL.
+ *
+ * Example:
+ *
+ * ```
+ * use std::sync::Arc;
+ *
+ * async fn get_thing() {
+ *     let client = Arc::new(cio_http::Client::new(reqwest::Client::new()));
+ *
+ *     // `Client` dereferences to the reqwest client, so requests are built the
+ *     // same way as before.
+ *     let request = client.get("https://example.com/thing").build().unwrap();
+ *
+ *     // Sending the request through `execute` is what retries it.
+ *     let resp = client.execute(request).await.unwrap();
+ *
+ *     println!("{}", resp.status());
+ * }
+ * ```
+ */
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use lazy_static::lazy_static;
+use rand::Rng;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{Method, Request, RequestBuilder, Response, StatusCode, Url};
+use tokio::sync::Semaphore;
+
+/// Timestamps in `X-RateLimit-Reset` above this are seconds since the epoch,
+/// anything below it is the number of seconds until the reset.
+const EPOCH_THRESHOLD: i64 = 1_000_000_000;
+
+/// How a `Client` retries requests and how many it sends to a host at once.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// The number of times a request is retried before the last response or
+    /// error is returned to the caller.
+    pub max_retries: u32,
+    /// The backoff before the first retry, this doubles with every attempt.
+    pub base_delay: Duration,
+    /// The longest backoff between two attempts.
+    pub max_delay: Duration,
+    /// The longest we will wait when a host asks us to back off. If a host
+    /// asks for longer, the response is returned instead of blocking the caller.
+    pub max_retry_after: Duration,
+    /// The number of requests in flight to a single host. The limit is shared
+    /// by every client in the process and set by the first client that talks
+    /// to the host.
+    pub max_concurrent_requests_per_host: usize,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 5,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(60),
+            max_retry_after: Duration::from_secs(5 * 60),
+            max_concurrent_requests_per_host: 8,
+        }
+    }
+}
+
+/// A reqwest client that retries and rate limits the requests sent through
+/// `execute` and `send`.
+///
+/// It dereferences to the wrapped `reqwest::Client` so requests are built with
+/// the usual `request`, `get` and `post` methods.
+#[derive(Debug, Clone)]
+pub struct Client {
+    inner: reqwest::Client,
+    policy: RetryPolicy,
+}
+
+impl From<reqwest::Client> for Client {
+    fn from(inner: reqwest::Client) -> Self {
+        Client::new(inner)
+    }
+}
+
+impl Deref for Client {
+    type Target = reqwest::Client;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl Client {
+    /// Wrap a reqwest client with the default retry policy.
+    pub fn new(inner: reqwest::Client) -> Self {
+        Client::with_policy(inner, RetryPolicy::default())
+    }
+
+    /// Wrap a reqwest client with a custom retry policy.
+    pub fn with_policy(inner: reqwest::Client, policy: RetryPolicy) -> Self {
+        Client { inner, policy }
+    }
+
+    /// Get the retry policy of the client.
+    pub fn policy(&self) -> &RetryPolicy {
+        &self.policy
+    }
+
+    /// Build and execute a request.
+    pub async fn send(&self, rb: RequestBuilder) -> Result<Response, reqwest::Error> {
+        self.execute(rb.build()?).await
+    }
+
+    /// Execute a request, retrying it if the host is rate limiting us or is
+    /// having trouble.
+    ///
+    /// Once the retries run out the last response is returned, so callers
+    /// still see the status code of a request that kept failing.
+    pub async fn execute(&self, mut request: Request) -> Result<Response, reqwest::Error> {
+        let host = Host::get(request.url(), self.policy.max_concurrent_requests_per_host);
+
+        let mut attempt: u32 = 0;
+        loop {
+            // Keep a copy of the request in case we need to send it again.
+            // Requests with a streaming body can't be copied, so those are only
+            // sent once.
+            let next = if attempt < self.policy.max_retries { request.try_clone() } else { None };
+            let method = request.method().clone();
+            let url = request.url().clone();
+
+            host.wait_until_ready().await;
+            let result = {
+                let _permit = host.permits.acquire().await.expect("the host semaphore is never closed");
+                self.inner.execute(request).await
+            };
+
+            let delay = match &result {
+                Ok(resp) => {
+                    let delay = self.retry_delay_for_response(&method, resp.status(), resp.headers(), attempt);
+                    host.observe(resp.status(), resp.headers(), delay);
+                    delay
+                }
+                Err(e) if is_idempotent(&method) && (e.is_connect() || e.is_timeout()) => Some(self.backoff(attempt)),
+                Err(_) => None,
+            };
+
+            let (next, delay) = match (next, delay) {
+                (Some(next), Some(delay)) if delay <= self.policy.max_retry_after => (next, delay),
+                _ => return result,
+            };
+
+            println!(
+                "[http] {} {} {}, retrying in {}ms (attempt {} of {})",
+                method,
+                url_without_query(&url),
+                match &result {
+                    Ok(resp) => format!("returned {}", resp.status()),
+                    Err(e) => format!("failed: {}", e),
+                },
+                delay.as_millis(),
+                attempt + 1,
+                self.policy.max_retries
+            );
+
+            tokio::time::sleep(delay).await;
+            request = next;
+            attempt += 1;
+        }
+    }
+
+    /// How long to wait before retrying a request that got this response, or
+    /// `None` if it should not be retried.
+    fn retry_delay_for_response(&self, method: &Method, status: StatusCode, headers: &HeaderMap, attempt: u32) -> Option<Duration> {
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            // The request was not processed, so it is safe to send again
+            // whatever the method.
+            return Some(retry_after(headers).or_else(|| rate_limit_reset(headers)).unwrap_or_else(|| self.backoff(attempt)));
+        }
+
+        if status.is_server_error() && is_idempotent(method) {
+            return Some(retry_after(headers).unwrap_or_else(|| self.backoff(attempt)));
+        }
+
+        None
+    }
+
+    /// Exponential backoff with full jitter.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let ceiling = backoff_ceiling(&self.policy, attempt);
+        Duration::from_millis(rand::thread_rng().gen_range(0..=ceiling.as_millis() as u64))
+    }
+}
+
+lazy_static! {
+    /// The rate limit state of every host we talk to, shared by every client.
+    static ref HOSTS: Mutex<HashMap<String, Arc<Host>>> = Mutex::new(HashMap::new());
+}
+
+/// The rate limit state of a host.
+struct Host {
+    permits: Semaphore,
+    /// Requests to the host wait until this time, because the host told us we
+    /// are out of quota.
+    blocked_until: Mutex<Option<Instant>>,
+}
+
+impl Host {
+    fn get(url: &Url, max_concurrent_requests: usize) -> Arc<Host> {
+        let key = format!("{}://{}", url.scheme(), url.host_str().unwrap_or_default());
+        let mut hosts = HOSTS.lock().unwrap();
+        hosts
+            .entry(key)
+            .or_insert_with(|| {
+                Arc::new(Host {
+                    permits: Semaphore::new(max_concurrent_requests.max(1)),
+                    blocked_until: Mutex::new(None),
+                })
+            })
+            .clone()
+    }
+
+    async fn wait_until_ready(&self) {
+        loop {
+            let blocked_until = *self.blocked_until.lock().unwrap();
+            match blocked_until {
+                Some(until) if until > Instant::now() => tokio::time::sleep(until - Instant::now()).await,
+                _ => return,
+            }
+        }
+    }
+
+    /// Hold back requests to the host if it rate limited us or told us we
+    /// just used up our quota.
+    fn observe(&self, status: StatusCode, headers: &HeaderMap, delay: Option<Duration>) {
+        let wait = if status == StatusCode::TOO_MANY_REQUESTS {
+            delay
+        } else if header_str(headers, "x-ratelimit-remaining") == Some("0") {
+            rate_limit_reset(headers)
+        } else {
+            None
+        };
+
+        if let Some(wait) = wait {
+            let until = Instant::now() + wait;
+            let mut blocked_until = self.blocked_until.lock().unwrap();
+            if blocked_until.map(|b| b < until).unwrap_or(true) {
+                *blocked_until = Some(until);
+            }
+        }
+    }
+}
+
+/// Requests that can be sent twice without doing the work twice.
+fn is_idempotent(method: &Method) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::PUT | Method::DELETE | Method::OPTIONS | Method::TRACE)
+}
+
+/// The most we back off before the given attempt.
+fn backoff_ceiling(policy: &RetryPolicy, attempt: u32) -> Duration {
+    match 2u32.checked_pow(attempt).and_then(|m| policy.base_delay.checked_mul(m)) {
+        Some(delay) if delay < policy.max_delay => delay,
+        _ => policy.max_delay,
+    }
+}
+
+/// Parse the `Retry-After` header, which is either a number of seconds or an
+/// HTTP date.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    Some((date.with_timezone(&Utc) - Utc::now()).to_std().unwrap_or_default())
+}
+
+/// Parse the `X-RateLimit-Reset` header, which some hosts send as seconds since
+/// the epoch and others as seconds from now.
+fn rate_limit_reset(headers: &HeaderMap) -> Option<Duration> {
+    let reset = header_str(headers, "x-ratelimit-reset")?.parse::<i64>().ok()?;
+    let seconds = if reset > EPOCH_THRESHOLD { reset - Utc::now().timestamp() } else { reset };
+    Some(Duration::from_secs(seconds.max(0) as u64))
+}
+
+/// Get the URL of an API endpoint on another base URL, keeping the path and
+/// query of the endpoint. The endpoint is returned unchanged when the base URL
+/// is empty, which is how the API clients talk to the real APIs.
+///
+/// For example, `https://api.airtable.com/v0/` on `http://127.0.0.1:8080` is
+/// `http://127.0.0.1:8080/v0/`.
+pub fn rebase(endpoint: &str, base_url: &str) -> String {
+    if base_url.is_empty() {
+        return endpoint.to_string();
+    }
+
+    let url = Url::parse(endpoint).expect("API endpoints are valid URLs");
+    let mut rebased = format!("{}{}", base_url.trim_end_matches('/'), url.path());
+    if let Some(query) = url.query() {
+        rebased = format!("{}?{}", rebased, query);
+    }
+
+    rebased
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name)?.to_str().ok().map(|v| v.trim())
+}
+
+/// Don't log query strings, they sometimes carry API keys.
+fn url_without_query(url: &Url) -> String {
+    let mut url = url.clone();
+    url.set_query(None);
+    url.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chrono::Utc;
+    use reqwest::header::{HeaderMap, HeaderValue};
+    use reqwest::{Method, StatusCode};
+
+    use crate::{backoff_ceiling, rate_limit_reset, rebase, retry_after, Client, RetryPolicy};
+
+    #[test]
+    fn test_retry_after() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+
+        headers.insert("retry-after", HeaderValue::from_static("120"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(120)));
+
+        // Dates in the past mean we can retry right away.
+        headers.insert("retry-after", HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(0)));
+
+        let date = (Utc::now() + chrono::Duration::seconds(90)).to_rfc2822();
+        headers.insert("retry-after", HeaderValue::from_str(&date).unwrap());
+        let delay = retry_after(&headers).unwrap();
+        assert!(delay > Duration::from_secs(85) && delay <= Duration::from_secs(90));
+    }
+
+    #[test]
+    fn test_rate_limit_reset() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-reset", HeaderValue::from_static("30"));
+        assert_eq!(rate_limit_reset(&headers), Some(Duration::from_secs(30)));
+
+        let reset = (Utc::now().timestamp() + 60).to_string();
+        headers.insert("x-ratelimit-reset", HeaderValue::from_str(&reset).unwrap());
+        let delay = rate_limit_reset(&headers).unwrap();
+        assert!(delay > Duration::from_secs(55) && delay <= Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_retry_delay_for_response() {
+        let client = Client::new(reqwest::Client::new());
+        let mut headers = HeaderMap::new();
+
+        assert_eq!(client.retry_delay_for_response(&Method::GET, StatusCode::OK, &headers, 0), None);
+        assert_eq!(client.retry_delay_for_response(&Method::GET, StatusCode::NOT_FOUND, &headers, 0), None);
+        assert!(client.retry_delay_for_response(&Method::GET, StatusCode::BAD_GATEWAY, &headers, 0).is_some());
+        // We never send a POST twice if the host might have processed it.
+        assert_eq!(client.retry_delay_for_response(&Method::POST, StatusCode::BAD_GATEWAY, &headers, 0), None);
+
+        headers.insert("retry-after", HeaderValue::from_static("7"));
+        assert_eq!(client.retry_delay_for_response(&Method::POST, StatusCode::TOO_MANY_REQUESTS, &headers, 0), Some(Duration::from_secs(7)));
+    }
+
+    #[test]
+    fn test_backoff_ceiling() {
+        let policy = RetryPolicy::default();
+        assert_eq!(backoff_ceiling(&policy, 0), Duration::from_millis(500));
+        assert_eq!(backoff_ceiling(&policy, 1), Duration::from_secs(1));
+        assert_eq!(backoff_ceiling(&policy, 3), Duration::from_secs(4));
+        assert_eq!(backoff_ceiling(&policy, 7), Duration::from_secs(60));
+        assert_eq!(backoff_ceiling(&policy, 100), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_rebase() {
+        assert_eq!(rebase("https://api.airtable.com/v0/", ""), "https://api.airtable.com/v0/");
+        assert_eq!(rebase("https://api.airtable.com/v0/", "http://127.0.0.1:8080"), "http://127.0.0.1:8080/v0/");
+        assert_eq!(rebase("https://api.airtable.com/v0/", "http://127.0.0.1:8080/"), "http://127.0.0.1:8080/v0/");
+        assert_eq!(
+            rebase("https://maps.google.com/maps/api/geocode/json?sensor=false", "http://localhost:9000"),
+            "http://localhost:9000/maps/api/geocode/json?sensor=false"
+        );
+    }
+}
@@ -0,0 +1,666 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use hyper::{Method, StatusCode};
+use serde_json::{json, Map, Value};
+
+use crate::server::{FakeServer, Request, Response};
+
+/// The most records Airtable creates, updates or deletes in one request.
+const MAX_RECORDS_PER_REQUEST: usize = 10;
+
+#[derive(Debug, Clone)]
+struct StoredRecord {
+    id: String,
+    fields: Map<String, Value>,
+    created_time: DateTime<Utc>,
+    modified_time: DateTime<Utc>,
+}
+
+impl StoredRecord {
+    fn to_json(&self, only: &[&str]) -> Value {
+        let fields: Map<String, Value> = self
+            .fields
+            .iter()
+            .filter(|(k, _)| only.is_empty() || only.contains(&k.as_str()))
+            .map(|(k, v)| (k.to_string(), v.clone()))
+            .collect();
+
+        json!({
+            "id": self.id,
+            "fields": fields,
+            "createdTime": self.created_time.to_rfc3339_opts(SecondsFormat::Millis, true),
+        })
+    }
+
+    /// Set the fields, leaving out the empty ones like Airtable does.
+    fn set_fields(&mut self, fields: &Map<String, Value>, replace: bool) {
+        if replace {
+            self.fields.clear();
+        }
+        for (k, v) in fields {
+            if is_empty(v) {
+                self.fields.remove(k);
+            } else {
+                self.fields.insert(k.to_string(), v.clone());
+            }
+        }
+        self.modified_time = Utc::now();
+    }
+}
+
+#[derive(Default)]
+struct Tables {
+    /// The records of each table, by base id and table name.
+    tables: BTreeMap<(String, String), Vec<StoredRecord>>,
+    next_id: usize,
+}
+
+impl Tables {
+    fn table(&mut self, base_id: &str, table: &str) -> &mut Vec<StoredRecord> {
+        self.tables.entry((base_id.to_string(), table.to_string())).or_default()
+    }
+
+    fn create(&mut self, base_id: &str, table: &str, fields: &Map<String, Value>) -> StoredRecord {
+        self.next_id += 1;
+        let now = Utc::now();
+        let mut record = StoredRecord {
+            id: format!("rec{:014}", self.next_id),
+            fields: Default::default(),
+            created_time: now,
+            modified_time: now,
+        };
+        record.set_fields(fields, true);
+
+        self.table(base_id, table).push(record.clone());
+        record
+    }
+}
+
+/// A fake of the Airtable API that keeps records in memory.
+///
+/// It supports listing records with `filterByFormula`, `fields[]`, `sort`,
+/// `maxRecords` and pagination, getting a single record, and creating,
+/// updating and deleting records in batches of at most ten. Formulas can use
+/// field comparisons, `AND`, `OR`, `NOT`, `IS_AFTER`, `IS_BEFORE`,
+/// `LAST_MODIFIED_TIME`, `CREATED_TIME`, `DATETIME_PARSE` and `RECORD_ID`.
+pub struct FakeAirtable {
+    server: FakeServer,
+    tables: Arc<Mutex<Tables>>,
+}
+
+impl FakeAirtable {
+    pub fn start() -> Self {
+        let server = FakeServer::start();
+        let tables: Arc<Mutex<Tables>> = Default::default();
+
+        let t = tables.clone();
+        server.route(Method::GET, "/v0/{base}/{table}", move |req| authorized(req).unwrap_or_else(|| list(&t, req)));
+        let t = tables.clone();
+        server.route(Method::GET, "/v0/{base}/{table}/{id}", move |req| {
+            authorized(req).unwrap_or_else(|| {
+                let mut tables = t.lock().unwrap();
+                match tables.table(req.param("base"), req.param("table")).iter().find(|r| r.id == req.param("id")) {
+                    Some(record) => Response::ok(record.to_json(&[])),
+                    None => Response::not_found(),
+                }
+            })
+        });
+        let t = tables.clone();
+        server.route(Method::POST, "/v0/{base}/{table}", move |req| authorized(req).unwrap_or_else(|| create(&t, req)));
+        let t = tables.clone();
+        server.route(Method::PATCH, "/v0/{base}/{table}", move |req| authorized(req).unwrap_or_else(|| update(&t, req, false)));
+        let t = tables.clone();
+        server.route(Method::PUT, "/v0/{base}/{table}", move |req| authorized(req).unwrap_or_else(|| update(&t, req, true)));
+        let t = tables.clone();
+        server.route(Method::DELETE, "/v0/{base}/{table}", move |req| {
+            authorized(req).unwrap_or_else(|| {
+                let ids: Vec<String> = req.query_all("records[]").iter().map(|s| s.to_string()).collect();
+                delete(&t, req, &ids)
+            })
+        });
+        let t = tables.clone();
+        server.route(Method::DELETE, "/v0/{base}/{table}/{id}", move |req| {
+            authorized(req).unwrap_or_else(|| delete(&t, req, &[req.param("id").to_string()]))
+        });
+
+        FakeAirtable { server, tables }
+    }
+
+    /// The URL to pass to `Airtable::with_base_url`.
+    pub fn base_url(&self) -> String {
+        self.server.base_url()
+    }
+
+    /// Every request the fake has received, oldest first.
+    pub fn requests(&self) -> Vec<Request> {
+        self.server.requests()
+    }
+
+    /// Add a record to a table, as if someone created it in Airtable, and
+    /// return its id.
+    pub fn insert(&self, base_id: &str, table: &str, fields: Value) -> String {
+        let fields = fields.as_object().cloned().unwrap_or_default();
+        self.tables.lock().unwrap().create(base_id, table, &fields).id
+    }
+
+    /// Change the fields of a record, as if someone edited it in Airtable.
+    /// This bumps the last modified time of the record.
+    pub fn edit(&self, base_id: &str, table: &str, record_id: &str, fields: Value) {
+        let fields = fields.as_object().cloned().unwrap_or_default();
+        let mut tables = self.tables.lock().unwrap();
+        if let Some(record) = tables.table(base_id, table).iter_mut().find(|r| r.id == record_id) {
+            record.set_fields(&fields, false);
+        }
+    }
+
+    /// The records in a table, in the same format the API returns them.
+    pub fn records(&self, base_id: &str, table: &str) -> Vec<Value> {
+        let mut tables = self.tables.lock().unwrap();
+        tables.table(base_id, table).iter().map(|r| r.to_json(&[])).collect()
+    }
+}
+
+fn authorized(req: &Request) -> Option<Response> {
+    match req.header("authorization") {
+        Some(a) if a.starts_with("Bearer ") && a.len() > "Bearer ".len() => None,
+        _ => Some(error(StatusCode::UNAUTHORIZED, "AUTHENTICATION_REQUIRED", "Authentication required")),
+    }
+}
+
+fn error(status: StatusCode, kind: &str, message: &str) -> Response {
+    Response::json(status, json!({ "error": { "type": kind, "message": message } }))
+}
+
+fn list(tables: &Mutex<Tables>, req: &Request) -> Response {
+    let mut records = {
+        let mut tables = tables.lock().unwrap();
+        tables.table(req.param("base"), req.param("table")).clone()
+    };
+
+    if let Some(formula) = req.query("filterByFormula") {
+        let mut filtered = Vec::new();
+        for record in records {
+            match Formula::parse(formula).and_then(|f| f.eval(&record)) {
+                Ok(v) if v.is_truthy() => filtered.push(record),
+                Ok(_) => (),
+                Err(e) => return error(StatusCode::UNPROCESSABLE_ENTITY, "INVALID_FILTER_BY_FORMULA", &e),
+            }
+        }
+        records = filtered;
+    }
+
+    let mut sorts = Vec::new();
+    while let Some(field) = req.query(&format!("sort[{}][field]", sorts.len())) {
+        let desc = req.query(&format!("sort[{}][direction]", sorts.len())) == Some("desc");
+        sorts.push((field.to_string(), desc));
+    }
+    records.sort_by(|a, b| {
+        for (field, desc) in &sorts {
+            let ordering = compare_values(a.fields.get(field), b.fields.get(field));
+            if ordering != Ordering::Equal {
+                return if *desc { ordering.reverse() } else { ordering };
+            }
+        }
+        Ordering::Equal
+    });
+
+    if let Some(max) = req.query("maxRecords").and_then(|m| m.parse().ok()) {
+        records.truncate(max);
+    }
+
+    let page_size = req.query("pageSize").and_then(|p| p.parse().ok()).unwrap_or(100usize).clamp(1, 100);
+    let start: usize = req.query("offset").and_then(|o| o.trim_start_matches("itr").parse().ok()).unwrap_or(0);
+    let fields = req.query_all("fields[]");
+
+    let page: Vec<Value> = records.iter().skip(start).take(page_size).map(|r| r.to_json(&fields)).collect();
+    let mut body = json!({ "records": page });
+    if start + page_size < records.len() {
+        body["offset"] = json!(format!("itr{}", start + page_size));
+    }
+
+    Response::ok(body)
+}
+
+/// The records in a request body, either a `records` array or a single record.
+fn records_in_body(req: &Request) -> Result<Vec<Value>, Response> {
+    let body = req.json();
+    let records = match body.get("records") {
+        Some(Value::Array(records)) => records.clone(),
+        _ if body.get("fields").is_some() => vec![body],
+        _ => return Err(error(StatusCode::UNPROCESSABLE_ENTITY, "INVALID_REQUEST_MISSING_FIELDS", "Could not find field \"records\"")),
+    };
+
+    if records.len() > MAX_RECORDS_PER_REQUEST {
+        return Err(error(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "INVALID_RECORDS",
+            &format!("Only {} records can be sent in one request", MAX_RECORDS_PER_REQUEST),
+        ));
+    }
+
+    Ok(records)
+}
+
+fn create(tables: &Mutex<Tables>, req: &Request) -> Response {
+    let records = match records_in_body(req) {
+        Ok(r) => r,
+        Err(e) => return e,
+    };
+
+    let mut tables = tables.lock().unwrap();
+    let created: Vec<Value> = records
+        .iter()
+        .map(|r| {
+            let fields = r["fields"].as_object().cloned().unwrap_or_default();
+            tables.create(req.param("base"), req.param("table"), &fields).to_json(&[])
+        })
+        .collect();
+
+    Response::ok(json!({ "records": created }))
+}
+
+fn update(tables: &Mutex<Tables>, req: &Request, replace: bool) -> Response {
+    let records = match records_in_body(req) {
+        Ok(r) => r,
+        Err(e) => return e,
+    };
+
+    let mut tables = tables.lock().unwrap();
+    let table = tables.table(req.param("base"), req.param("table"));
+    let mut updated = Vec::new();
+    for r in &records {
+        let id = r["id"].as_str().unwrap_or_default();
+        let existing = match table.iter_mut().find(|e| e.id == id) {
+            Some(e) => e,
+            None => return error(StatusCode::NOT_FOUND, "NOT_FOUND", &format!("Record {} not found", id)),
+        };
+        existing.set_fields(&r["fields"].as_object().cloned().unwrap_or_default(), replace);
+        updated.push(existing.to_json(&[]));
+    }
+
+    Response::ok(json!({ "records": updated }))
+}
+
+fn delete(tables: &Mutex<Tables>, req: &Request, ids: &[String]) -> Response {
+    if ids.len() > MAX_RECORDS_PER_REQUEST {
+        return error(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "INVALID_RECORDS",
+            &format!("Only {} records can be sent in one request", MAX_RECORDS_PER_REQUEST),
+        );
+    }
+
+    let mut tables = tables.lock().unwrap();
+    let table = tables.table(req.param("base"), req.param("table"));
+    let mut deleted = Vec::new();
+    for id in ids {
+        match table.iter().position(|r| r.id == *id) {
+            Some(i) => {
+                table.remove(i);
+                deleted.push(json!({ "id": id, "deleted": true }));
+            }
+            None => return error(StatusCode::NOT_FOUND, "NOT_FOUND", &format!("Record {} not found", id)),
+        }
+    }
+
+    if req.param("id").is_empty() {
+        Response::ok(json!({ "records": deleted }))
+    } else {
+        Response::ok(deleted.remove(0))
+    }
+}
+
+/// Airtable leaves empty cells out of the fields of a record.
+fn is_empty(v: &Value) -> bool {
+    match v {
+        Value::Null => true,
+        Value::Bool(b) => !b,
+        Value::String(s) => s.is_empty(),
+        Value::Array(a) => a.is_empty(),
+        _ => false,
+    }
+}
+
+fn compare_values(a: Option<&Value>, b: Option<&Value>) -> Ordering {
+    FormulaValue::from_field(a).compare(&FormulaValue::from_field(b))
+}
+
+/// A value in a formula.
+#[derive(Debug, Clone, PartialEq)]
+enum FormulaValue {
+    Blank,
+    Bool(bool),
+    Number(f64),
+    Text(String),
+    Time(DateTime<Utc>),
+}
+
+impl FormulaValue {
+    fn from_field(v: Option<&Value>) -> Self {
+        match v {
+            None | Some(Value::Null) => FormulaValue::Blank,
+            Some(Value::Bool(b)) => FormulaValue::Bool(*b),
+            Some(Value::Number(n)) => FormulaValue::Number(n.as_f64().unwrap_or_default()),
+            Some(Value::String(s)) => FormulaValue::Text(s.to_string()),
+            // Linked records and multiple selects compare as the text Airtable
+            // shows for them.
+            Some(Value::Array(a)) => FormulaValue::Text(
+                a.iter()
+                    .map(|v| match v {
+                        Value::String(s) => s.to_string(),
+                        v => v.to_string(),
+                    })
+                    .collect::<Vec<String>>()
+                    .join(", "),
+            ),
+            Some(v) => FormulaValue::Text(v.to_string()),
+        }
+    }
+
+    fn is_truthy(&self) -> bool {
+        match self {
+            FormulaValue::Blank => false,
+            FormulaValue::Bool(b) => *b,
+            FormulaValue::Number(n) => *n != 0.0,
+            FormulaValue::Text(s) => !s.is_empty(),
+            FormulaValue::Time(_) => true,
+        }
+    }
+
+    fn as_text(&self) -> String {
+        match self {
+            FormulaValue::Blank => String::new(),
+            FormulaValue::Bool(b) => if *b { "1" } else { "0" }.to_string(),
+            FormulaValue::Number(n) => n.to_string(),
+            FormulaValue::Text(s) => s.to_string(),
+            FormulaValue::Time(t) => t.to_rfc3339_opts(SecondsFormat::Millis, true),
+        }
+    }
+
+    fn as_number(&self) -> Option<f64> {
+        match self {
+            FormulaValue::Blank => Some(0.0),
+            FormulaValue::Bool(b) => Some(if *b { 1.0 } else { 0.0 }),
+            FormulaValue::Number(n) => Some(*n),
+            FormulaValue::Text(s) => s.trim().parse().ok(),
+            FormulaValue::Time(t) => Some(t.timestamp_millis() as f64),
+        }
+    }
+
+    fn compare(&self, other: &FormulaValue) -> Ordering {
+        match (self, other) {
+            (FormulaValue::Time(a), FormulaValue::Time(b)) => a.cmp(b),
+            (FormulaValue::Text(_), _) | (_, FormulaValue::Text(_)) if self.as_number().is_none() || other.as_number().is_none() => self.as_text().cmp(&other.as_text()),
+            _ => self.as_number().partial_cmp(&other.as_number()).unwrap_or(Ordering::Equal),
+        }
+    }
+}
+
+/// A parsed `filterByFormula`.
+#[derive(Debug, Clone)]
+enum Formula {
+    Field(String),
+    Literal(FormulaValue),
+    Call(String, Vec<Formula>),
+    Compare(Box<Formula>, String, Box<Formula>),
+}
+
+impl Formula {
+    fn parse(s: &str) -> Result<Formula, String> {
+        let tokens = tokenize(s)?;
+        let mut pos = 0;
+        let formula = parse_expr(&tokens, &mut pos)?;
+        if pos != tokens.len() {
+            return Err(format!("Invalid formula, unexpected {:?}", tokens[pos]));
+        }
+
+        Ok(formula)
+    }
+
+    fn eval(&self, record: &StoredRecord) -> Result<FormulaValue, String> {
+        match self {
+            Formula::Field(name) => Ok(FormulaValue::from_field(record.fields.get(name))),
+            Formula::Literal(v) => Ok(v.clone()),
+            Formula::Compare(a, op, b) => {
+                let (a, b) = (a.eval(record)?, b.eval(record)?);
+                let ordering = a.compare(&b);
+                Ok(FormulaValue::Bool(match op.as_str() {
+                    "=" => ordering == Ordering::Equal,
+                    "!=" => ordering != Ordering::Equal,
+                    "<" => ordering == Ordering::Less,
+                    "<=" => ordering != Ordering::Greater,
+                    ">" => ordering == Ordering::Greater,
+                    ">=" => ordering != Ordering::Less,
+                    _ => return Err(format!("Unknown operator {}", op)),
+                }))
+            }
+            Formula::Call(name, args) => {
+                let args = args.iter().map(|a| a.eval(record)).collect::<Result<Vec<FormulaValue>, String>>()?;
+                match (name.to_uppercase().as_str(), args.as_slice()) {
+                    ("AND", args) => Ok(FormulaValue::Bool(args.iter().all(|a| a.is_truthy()))),
+                    ("OR", args) => Ok(FormulaValue::Bool(args.iter().any(|a| a.is_truthy()))),
+                    ("NOT", [a]) => Ok(FormulaValue::Bool(!a.is_truthy())),
+                    ("TRUE", []) => Ok(FormulaValue::Bool(true)),
+                    ("FALSE", []) => Ok(FormulaValue::Bool(false)),
+                    ("BLANK", []) => Ok(FormulaValue::Blank),
+                    ("RECORD_ID", []) => Ok(FormulaValue::Text(record.id.to_string())),
+                    ("LAST_MODIFIED_TIME", []) => Ok(FormulaValue::Time(record.modified_time)),
+                    ("CREATED_TIME", []) => Ok(FormulaValue::Time(record.created_time)),
+                    ("DATETIME_PARSE", [a]) => DateTime::parse_from_rfc3339(&a.as_text())
+                        .map(|t| FormulaValue::Time(t.with_timezone(&Utc)))
+                        .map_err(|e| format!("Invalid date {}: {}", a.as_text(), e)),
+                    ("IS_AFTER", [a, b]) => Ok(FormulaValue::Bool(a.compare(b) == Ordering::Greater)),
+                    ("IS_BEFORE", [a, b]) => Ok(FormulaValue::Bool(a.compare(b) == Ordering::Less)),
+                    (name, args) => Err(format!("Unknown function {} with {} arguments", name, args.len())),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Field(String),
+    Text(String),
+    Number(f64),
+    Name(String),
+    Op(String),
+    Open,
+    Close,
+    Comma,
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = s.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            c if c.is_whitespace() => i += 1,
+            '(' => {
+                tokens.push(Token::Open);
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token::Close);
+                i += 1;
+            }
+            ',' => {
+                tokens.push(Token::Comma);
+                i += 1;
+            }
+            '{' | '\'' | '"' => {
+                let end = if c == '{' { '}' } else { c };
+                let close = chars[i + 1..].iter().position(|x| *x == end).ok_or_else(|| format!("Invalid formula, unclosed {}", c))?;
+                let value: String = chars[i + 1..i + 1 + close].iter().collect();
+                tokens.push(if c == '{' { Token::Field(value) } else { Token::Text(value) });
+                i += close + 2;
+            }
+            '=' | '!' | '<' | '>' => {
+                let mut op = c.to_string();
+                if chars.get(i + 1) == Some(&'=') {
+                    op.push('=');
+                }
+                i += op.len();
+                tokens.push(Token::Op(op));
+            }
+            c if c.is_ascii_digit() || c == '-' || c == '.' => {
+                let len = chars[i..].iter().take_while(|x| x.is_ascii_digit() || **x == '-' || **x == '.').count();
+                let number: String = chars[i..i + len].iter().collect();
+                tokens.push(Token::Number(number.parse().map_err(|_| format!("Invalid number {}", number))?));
+                i += len;
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let len = chars[i..].iter().take_while(|x| x.is_ascii_alphanumeric() || **x == '_').count();
+                tokens.push(Token::Name(chars[i..i + len].iter().collect()));
+                i += len;
+            }
+            c => return Err(format!("Invalid formula, unexpected {}", c)),
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn parse_expr(tokens: &[Token], pos: &mut usize) -> Result<Formula, String> {
+    let left = parse_primary(tokens, pos)?;
+    if let Some(Token::Op(op)) = tokens.get(*pos) {
+        *pos += 1;
+        let right = parse_primary(tokens, pos)?;
+        return Ok(Formula::Compare(Box::new(left), op.to_string(), Box::new(right)));
+    }
+
+    Ok(left)
+}
+
+fn parse_primary(tokens: &[Token], pos: &mut usize) -> Result<Formula, String> {
+    let token = tokens.get(*pos).cloned().ok_or_else(|| "Invalid formula, unexpected end".to_string())?;
+    *pos += 1;
+    match token {
+        Token::Field(name) => Ok(Formula::Field(name)),
+        Token::Text(s) => Ok(Formula::Literal(FormulaValue::Text(s))),
+        Token::Number(n) => Ok(Formula::Literal(FormulaValue::Number(n))),
+        Token::Open => {
+            let inner = parse_expr(tokens, pos)?;
+            expect(tokens, pos, Token::Close)?;
+            Ok(inner)
+        }
+        Token::Name(name) => {
+            expect(tokens, pos, Token::Open)?;
+            let mut args = Vec::new();
+            if tokens.get(*pos) == Some(&Token::Close) {
+                *pos += 1;
+                return Ok(Formula::Call(name, args));
+            }
+            loop {
+                args.push(parse_expr(tokens, pos)?);
+                match tokens.get(*pos) {
+                    Some(Token::Comma) => *pos += 1,
+                    Some(Token::Close) => {
+                        *pos += 1;
+                        return Ok(Formula::Call(name, args));
+                    }
+                    t => return Err(format!("Invalid formula, unexpected {:?}", t)),
+                }
+            }
+        }
+        t => Err(format!("Invalid formula, unexpected {:?}", t)),
+    }
+}
+
+fn expect(tokens: &[Token], pos: &mut usize, token: Token) -> Result<(), String> {
+    if tokens.get(*pos) != Some(&token) {
+        return Err(format!("Invalid formula, expected {:?}", token));
+    }
+    *pos += 1;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use std::time::Duration;
+
+    use airtable_api::{Airtable, ListOptions, Record};
+    use chrono::Utc;
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+
+    use crate::airtable::FakeAirtable;
+
+    #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+    struct Swag {
+        #[serde(default)]
+        id: i32,
+        #[serde(default, skip_serializing_if = "String::is_empty")]
+        name: String,
+        #[serde(default)]
+        current_stock: i32,
+    }
+
+    #[tokio::test]
+    async fn test_fake_airtable() {
+        let fake = FakeAirtable::start();
+        let airtable = Airtable::new("key", "appSwag", "").with_base_url(fake.base_url());
+
+        // Create more records than fit in one page.
+        for batch in (1..=150).collect::<Vec<i32>>().chunks(10) {
+            let records = batch
+                .iter()
+                .map(|i| Record {
+                    id: String::new(),
+                    fields: Swag {
+                        id: *i,
+                        name: format!("Sticker {}", i),
+                        current_stock: *i % 7,
+                    },
+                    created_time: None,
+                })
+                .collect();
+            airtable.create_records("Swag", records).await.unwrap();
+        }
+
+        let records: Vec<Record<Swag>> = airtable.list_records("Swag", "Grid view", vec![]).await.unwrap();
+        assert_eq!(records.len(), 150);
+
+        // Batches are limited to ten records, like in Airtable.
+        let too_many = (0..11)
+            .map(|_| Record {
+                id: String::new(),
+                fields: Swag::default(),
+                created_time: None,
+            })
+            .collect();
+        assert!(airtable.create_records("Swag", too_many).await.is_err());
+
+        let options = ListOptions {
+            filter_by_formula: "{id} = 42".to_string(),
+            ..Default::default()
+        };
+        let mut found: Vec<Record<Swag>> = airtable.list_records_with_options("Swag", &options).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].fields.name, "Sticker 42");
+
+        // Someone edits the record in Airtable after we last looked at the table.
+        thread::sleep(Duration::from_millis(5));
+        let since = Utc::now();
+        assert!(airtable.list_records_with_options::<Swag>("Swag", &ListOptions::modified_since(since)).await.unwrap().is_empty());
+        thread::sleep(Duration::from_millis(5));
+        fake.edit("appSwag", "Swag", &found[0].id, json!({"name": "Sticker forty two"}));
+        let modified: Vec<Record<Swag>> = airtable.list_records_with_options("Swag", &ListOptions::modified_since(since)).await.unwrap();
+        assert_eq!(modified.len(), 1);
+        assert_eq!(modified[0].fields.name, "Sticker forty two");
+
+        found[0].fields.current_stock = 100;
+        airtable.update_records("Swag", found.clone()).await.unwrap();
+        let record: Record<Swag> = airtable.get_record("Swag", &found[0].id).await.unwrap();
+        assert_eq!(record.fields.current_stock, 100);
+
+        airtable.delete_record("Swag", &found[0].id).await.unwrap();
+        assert_eq!(fake.records("appSwag", "Swag").len(), 149);
+    }
+}
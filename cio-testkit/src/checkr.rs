@@ -0,0 +1,187 @@
+use chrono::{Duration, Utc};
+use hyper::{Method, StatusCode};
+use serde_json::{json, Value};
+
+use crate::server::{FakeServer, Request, Response};
+use crate::store::Store;
+
+/// The number of candidates in each page of the list.
+const PAGE_SIZE: usize = 25;
+
+/// A fake of the Checkr API.
+///
+/// Invitations start out `pending`. Use `complete_invitation` to have the
+/// candidate fill it out, which creates a report that is `pending` until it
+/// is given a result with `complete_report`.
+pub struct FakeCheckr {
+    server: FakeServer,
+    candidates: Store,
+    invitations: Store,
+    reports: Store,
+}
+
+impl FakeCheckr {
+    pub fn start() -> Self {
+        let server = FakeServer::start();
+        let candidates = Store::new("id", "cand");
+        let invitations = Store::new("id", "inv");
+        let reports = Store::new("id", "rpt");
+
+        let c = candidates.clone();
+        server.route(Method::GET, "/v1/candidates", move |req| {
+            let all = c.all();
+            let page: usize = req.query("page").and_then(|p| p.parse().ok()).unwrap_or(1).max(1);
+            let candidates: Vec<Value> = all.iter().skip((page - 1) * PAGE_SIZE).take(PAGE_SIZE).cloned().collect();
+
+            let href = |page: usize| json!(format!("http://{}/v1/candidates?page={}", req.header("host").unwrap_or_default(), page));
+            Response::ok(json!({
+                "object": "list",
+                "next_href": if page * PAGE_SIZE < all.len() { href(page + 1) } else { Value::Null },
+                "previous_href": if page > 1 { href(page - 1) } else { Value::Null },
+                "count": all.len(),
+                "data": candidates,
+            }))
+        });
+        let c = candidates.clone();
+        server.route(Method::GET, "/v1/candidates/{id}", move |req| c.get(req.param("id")).map(Response::ok).unwrap_or_else(not_found));
+        let c = candidates.clone();
+        server.route(Method::POST, "/v1/candidates", move |req| {
+            let email = req.json()["email"].as_str().unwrap_or_default().to_string();
+            if email.is_empty() {
+                return Response::json(StatusCode::BAD_REQUEST, json!({ "error": "Email is required" }));
+            }
+            Response::created(c.insert(json!({
+                "object": "candidate",
+                "email": email,
+                "no_middle_name": false,
+                "copy_requested": false,
+                "report_ids": [],
+                "geo_ids": [],
+                "metadata": {},
+                "created_at": Utc::now(),
+            })))
+        });
+
+        let i = invitations.clone();
+        server.route(Method::GET, "/v1/invitations", move |_| {
+            let all = i.all();
+            Response::ok(json!({ "object": "list", "next_href": null, "previous_href": null, "count": all.len(), "data": all }))
+        });
+        let (i, c) = (invitations.clone(), candidates.clone());
+        server.route(Method::POST, "/v1/invitations", move |req| {
+            let body = req.json();
+            let candidate_id = body["candidate_id"].as_str().unwrap_or_default();
+            if c.get(candidate_id).is_none() {
+                return not_found();
+            }
+            let now = Utc::now();
+            let invitation = i.insert(json!({
+                "object": "invitation",
+                "status": "pending",
+                "created_at": now,
+                "expires_at": now + Duration::days(7),
+                "completed_at": null,
+                "deleted_at": null,
+                "package": body["package"],
+                "candidate_id": candidate_id,
+            }));
+            let id = invitation["id"].as_str().unwrap_or_default();
+            Response::created(
+                i.update(
+                    id,
+                    &json!({ "invitation_url": format!("https://apply.checkr.com/invite/fake/{}", id), "uri": format!("/v1/invitations/{}", id) }),
+                )
+                .unwrap(),
+            )
+        });
+
+        let r = reports.clone();
+        server.route(Method::GET, "/v1/reports/{id}", move |req| r.get(req.param("id")).map(Response::ok).unwrap_or_else(not_found));
+
+        FakeCheckr {
+            server,
+            candidates,
+            invitations,
+            reports,
+        }
+    }
+
+    /// The URL to pass to `Checkr::with_base_url`.
+    pub fn base_url(&self) -> String {
+        self.server.base_url()
+    }
+
+    /// Every request the fake has received, oldest first.
+    pub fn requests(&self) -> Vec<Request> {
+        self.server.requests()
+    }
+
+    /// The invitations that were sent.
+    pub fn invitations(&self) -> Vec<Value> {
+        self.invitations.all()
+    }
+
+    /// Have the candidate of an invitation fill it out, and return the id of
+    /// the report that was started for them.
+    pub fn complete_invitation(&self, invitation_id: &str) -> Option<String> {
+        let invitation = self.invitations.get(invitation_id)?;
+        let candidate_id = invitation["candidate_id"].as_str().unwrap_or_default();
+        let report = self.reports.insert(json!({
+            "object": "report",
+            "status": "pending",
+            "created_at": Utc::now(),
+            "package": invitation["package"],
+            "candidate_id": candidate_id,
+            "drug_screening": {},
+        }));
+        let report_id = report["id"].as_str().unwrap_or_default().to_string();
+
+        self.invitations
+            .update(invitation_id, &json!({ "status": "completed", "completed_at": Utc::now(), "report_id": report_id }));
+        let mut report_ids = self.candidates.get(candidate_id)?["report_ids"].as_array().cloned().unwrap_or_default();
+        report_ids.push(json!(report_id));
+        self.candidates.update(candidate_id, &json!({ "report_ids": report_ids }));
+
+        Some(report_id)
+    }
+
+    /// Finish a report with a result, like `clear` or `consider`.
+    pub fn complete_report(&self, report_id: &str, result: &str) {
+        self.reports.update(report_id, &json!({ "status": "complete", "result": result, "completed_at": Utc::now() }));
+    }
+}
+
+fn not_found() -> Response {
+    Response::json(StatusCode::NOT_FOUND, json!({ "error": "Not Found" }))
+}
+
+#[cfg(test)]
+mod tests {
+    use checkr::Checkr;
+
+    use crate::checkr::FakeCheckr;
+
+    #[tokio::test]
+    async fn test_fake_checkr() {
+        let fake = FakeCheckr::start();
+        let checkr = Checkr::new("key").with_base_url(fake.base_url());
+
+        for i in 0..30 {
+            checkr.create_candidate(&format!("candidate{}@example.com", i)).await.unwrap();
+        }
+        // The list is longer than a page.
+        let candidates = checkr.list_candidates().await.unwrap();
+        assert_eq!(candidates.len(), 30);
+
+        let invitation = checkr.create_invitation(&candidates[0].id, "driver_pro").await.unwrap();
+        assert_eq!(invitation.status, "pending");
+
+        let report_id = fake.complete_invitation(&invitation.id).unwrap();
+        fake.complete_report(&report_id, "clear");
+
+        let report = checkr.get_report(&report_id).await.unwrap();
+        assert_eq!(report.status, "complete");
+        assert_eq!(report.result, "clear");
+        assert_eq!(checkr.get_candidate(&candidates[0].id).await.unwrap().report_ids, vec![report_id]);
+    }
+}
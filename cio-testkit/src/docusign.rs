@@ -0,0 +1,184 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use chrono::Utc;
+use hyper::{Method, StatusCode};
+use serde_json::{json, Value};
+
+use crate::server::{FakeServer, Request, Response};
+use crate::store::Store;
+
+/// The contents of documents, by envelope id and document id.
+type Documents = BTreeMap<(String, String), Vec<u8>>;
+
+/// A fake of the DocuSign eSignature API and of its OAuth token endpoint.
+///
+/// The client reads its base URL from `DOCUSIGN_BASE_URL` before it asks for
+/// a token, so set that to `base_url` rather than building the client first.
+/// Envelopes start out `sent`, use `set_envelope_status` to have them signed.
+pub struct FakeDocuSign {
+    server: FakeServer,
+    templates: Store,
+    envelopes: Store,
+    webhooks: Store,
+    documents: Arc<Mutex<Documents>>,
+}
+
+impl FakeDocuSign {
+    pub fn start() -> Self {
+        let server = FakeServer::start();
+        let templates = Store::new("templateId", "tpl-");
+        let envelopes = Store::new("envelopeId", "env-");
+        let webhooks = Store::new("connectId", "");
+        let fields = Store::new("key", "");
+        let documents: Arc<Mutex<Documents>> = Default::default();
+
+        server.route(Method::POST, "/oauth/token", |req| {
+            if req.form().get("assertion").map(|a| a.is_empty()).unwrap_or(true) {
+                return Response::json(StatusCode::BAD_REQUEST, json!({ "error": "invalid_grant" }));
+            }
+            Response::ok(json!({ "access_token": "docusign_tok_fake", "token_type": "Bearer", "expires_in": 3600 }))
+        });
+
+        let t = templates.clone();
+        server.route(Method::GET, "/restapi/v2.1/accounts/{account}/templates", move |_| {
+            let all = t.all();
+            Response::ok(json!({ "envelopeTemplates": all, "resultSetSize": all.len().to_string(), "totalSetSize": all.len().to_string() }))
+        });
+
+        let w = webhooks.clone();
+        server.route(Method::GET, "/restapi/v2.1/accounts/{account}/connect", move |_| {
+            let all = w.all();
+            Response::ok(json!({ "configurations": all, "totalRecords": all.len().to_string() }))
+        });
+        let w = webhooks.clone();
+        server.route(Method::POST, "/restapi/v2.1/accounts/{account}/connect", move |req| {
+            let mut webhook = req.json();
+            webhook["connectId"] = json!((w.all().len() + 1).to_string());
+            Response::created(w.insert(webhook))
+        });
+
+        let e = envelopes.clone();
+        server.route(Method::POST, "/restapi/v2.1/accounts/{account}/envelopes", move |req| {
+            let mut envelope = req.json();
+            let now = Utc::now();
+            envelope["envelopeId"] = json!("");
+            envelope["status"] = json!("sent");
+            envelope["createdDateTime"] = json!(now);
+            envelope["sentDateTime"] = json!(now);
+            envelope["statusChangedDateTime"] = json!(now);
+            let envelope = e.insert(envelope);
+            let id = envelope["envelopeId"].as_str().unwrap_or_default();
+            Response::created(e.update(id, &json!({ "envelopeUri": format!("/envelopes/{}", id) })).unwrap())
+        });
+        let e = envelopes.clone();
+        server.route(Method::GET, "/restapi/v2.1/accounts/{account}/envelopes/{id}", move |req| {
+            e.get(req.param("id")).map(Response::ok).unwrap_or_else(not_found)
+        });
+        let e = envelopes.clone();
+        server.route(Method::GET, "/restapi/v2.1/accounts/{account}/envelopes/{id}/form_data", move |req| match e.get(req.param("id")) {
+            Some(envelope) => Response::ok(json!({
+                "envelopeId": envelope["envelopeId"],
+                "status": envelope["status"],
+                "formData": envelope["formData"].as_array().cloned().unwrap_or_default(),
+                "recipientFormData": [],
+            })),
+            None => not_found(),
+        });
+
+        let d = documents.clone();
+        server.route(Method::GET, "/restapi/v2.1/accounts/{account}/envelopes/{id}/documents/{document}", move |req| {
+            match d.lock().unwrap().get(&(req.param("id").to_string(), req.param("document").to_string())) {
+                Some(contents) => Response {
+                    status: StatusCode::OK,
+                    headers: vec![("content-type".to_string(), "application/pdf".to_string())],
+                    body: contents.clone(),
+                },
+                None => not_found(),
+            }
+        });
+        let f = fields.clone();
+        server.route(Method::GET, "/restapi/v2.1/accounts/{account}/envelopes/{id}/documents/{document}/fields", move |req| {
+            let key = format!("{}/{}", req.param("id"), req.param("document"));
+            Response::ok(f.get(&key).map(|f| f["fields"].clone()).unwrap_or_else(|| json!([])))
+        });
+        let f = fields;
+        server.route(Method::PUT, "/restapi/v2.1/accounts/{account}/envelopes/{id}/documents/{document}/fields", move |req| {
+            let key = format!("{}/{}", req.param("id"), req.param("document"));
+            f.remove(&key);
+            f.insert(json!({ "key": key, "fields": req.json() }));
+            Response::ok(json!({}))
+        });
+
+        FakeDocuSign {
+            server,
+            templates,
+            envelopes,
+            webhooks,
+            documents,
+        }
+    }
+
+    /// The URL to set `DOCUSIGN_BASE_URL` to.
+    pub fn base_url(&self) -> String {
+        self.server.base_url()
+    }
+
+    /// Every request the fake has received, oldest first.
+    pub fn requests(&self) -> Vec<Request> {
+        self.server.requests()
+    }
+
+    /// Add a template and return its id.
+    pub fn add_template(&self, name: &str) -> String {
+        let template = self.templates.insert(json!({ "name": name, "shared": "false", "lastModified": Utc::now() }));
+        template["templateId"].as_str().unwrap_or_default().to_string()
+    }
+
+    /// The envelopes that were sent.
+    pub fn envelopes(&self) -> Vec<Value> {
+        self.envelopes.all()
+    }
+
+    /// The Connect webhooks that were created.
+    pub fn webhooks(&self) -> Vec<Value> {
+        self.webhooks.all()
+    }
+
+    /// Add a document to an envelope, like the ones DocuSign makes from the
+    /// template of the envelope.
+    pub fn set_document(&self, envelope_id: &str, document_id: &str, name: &str, contents: &[u8]) {
+        self.documents.lock().unwrap().insert((envelope_id.to_string(), document_id.to_string()), contents.to_vec());
+        if let Some(envelope) = self.envelopes.get(envelope_id) {
+            let mut documents = envelope["envelopeDocuments"].as_array().cloned().unwrap_or_default();
+            documents.retain(|d| d["documentId"].as_str() != Some(document_id));
+            documents.push(json!({ "documentId": document_id, "name": name, "type": "content" }));
+            self.envelopes.update(envelope_id, &json!({ "envelopeDocuments": documents }));
+        }
+    }
+
+    /// Move an envelope to a new status, like `delivered` or `completed`,
+    /// with the form data the signers filled in.
+    pub fn set_envelope_status(&self, envelope_id: &str, status: &str, form_data: &[(&str, &str)]) {
+        let now = Utc::now();
+        let mut changes = json!({
+            "status": status,
+            "statusChangedDateTime": now,
+            "formData": form_data.iter().map(|(name, value)| json!({ "name": name, "value": value })).collect::<Vec<Value>>(),
+        });
+        match status {
+            "delivered" => changes["deliveredDateTime"] = json!(now),
+            "completed" => changes["completedDateTime"] = json!(now),
+            "declined" => changes["declinedDateTime"] = json!(now),
+            _ => (),
+        }
+        self.envelopes.update(envelope_id, &changes);
+    }
+}
+
+fn not_found() -> Response {
+    Response::json(
+        StatusCode::NOT_FOUND,
+        json!({ "errorCode": "ENVELOPE_DOES_NOT_EXIST", "message": "The envelope specified either does not exist or you have no rights to it." }),
+    )
+}
@@ -0,0 +1,224 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use chrono::Utc;
+use hyper::{Method, StatusCode};
+use serde_json::{json, Value};
+
+use crate::server::{FakeServer, Request, Response};
+use crate::store::Store;
+
+/// A fake of the Google Drive API, and of uploads to Google Cloud Storage.
+///
+/// Files are kept in memory with their contents. Listing files understands
+/// `q` queries of the form `name = '...'`, which is the only kind the client
+/// sends.
+pub struct FakeDrive {
+    server: FakeServer,
+    drives: Store,
+    files: Store,
+    contents: Arc<Mutex<BTreeMap<String, Vec<u8>>>>,
+    objects: Store,
+}
+
+impl FakeDrive {
+    pub fn start() -> Self {
+        let server = FakeServer::start();
+        let drives = Store::new("id", "0AD");
+        let files = Store::new("id", "1F");
+        let contents: Arc<Mutex<BTreeMap<String, Vec<u8>>>> = Default::default();
+        let uploads = Store::new("upload_id", "up");
+        let objects = Store::new("id", "obj");
+
+        let d = drives.clone();
+        server.route(Method::GET, "/drive/v3/drives", move |_| Response::ok(json!({ "kind": "drive#driveList", "drives": d.all() })));
+
+        let f = files.clone();
+        server.route(Method::GET, "/drive/v3/files", move |req| {
+            let name = req.query("q").map(name_in_query);
+            let drive_id = req.query("driveId").unwrap_or_default();
+            let found: Vec<Value> = f
+                .all()
+                .into_iter()
+                .filter(|file| name.as_ref().map(|n| file["name"].as_str() == Some(n)).unwrap_or(true))
+                .filter(|file| drive_id.is_empty() || file["driveId"].as_str() == Some(drive_id))
+                .collect();
+            Response::ok(json!({ "kind": "drive#fileList", "files": found }))
+        });
+
+        let f = files.clone();
+        server.route(Method::POST, "/drive/v3/files", move |req| {
+            Response::ok(f.insert(new_file(&files_store_drive(&f, req.json()), req.json())))
+        });
+
+        let (f, c) = (files.clone(), contents.clone());
+        server.route(Method::GET, "/drive/v3/files/{id}", move |req| match f.get(req.param("id")) {
+            Some(_) if req.query("alt") == Some("media") => Response {
+                status: StatusCode::OK,
+                headers: Default::default(),
+                body: c.lock().unwrap().get(req.param("id")).cloned().unwrap_or_default(),
+            },
+            Some(file) => Response::ok(file),
+            None => not_found(req.param("id")),
+        });
+
+        let (f, c) = (files.clone(), contents.clone());
+        server.route(Method::GET, "/drive/v3/files/{id}/export", move |req| match f.get(req.param("id")) {
+            Some(_) => Response::text(StatusCode::OK, &String::from_utf8_lossy(&c.lock().unwrap().get(req.param("id")).cloned().unwrap_or_default())),
+            None => not_found(req.param("id")),
+        });
+
+        let (f, c) = (files.clone(), contents.clone());
+        server.route(Method::DELETE, "/drive/v3/files/{id}", move |req| match f.remove(req.param("id")) {
+            Some(_) => {
+                c.lock().unwrap().remove(req.param("id"));
+                Response::empty(StatusCode::NO_CONTENT)
+            }
+            None => not_found(req.param("id")),
+        });
+
+        // Resumable uploads start with the metadata of the file, and the
+        // contents are sent to the URL in the `Location` header.
+        let u = uploads.clone();
+        server.route(Method::POST, "/upload/drive/v3/files", move |req| start_upload(&u, req, ""));
+        let u = uploads.clone();
+        server.route(Method::PATCH, "/upload/drive/v3/files/{id}", move |req| start_upload(&u, req, req.param("id")));
+        let (u, f, c) = (uploads.clone(), files.clone(), contents.clone());
+        server.route(Method::PUT, "/upload/drive/v3/files", move |req| finish_upload(&u, &f, &c, req));
+        let (u, f, c) = (uploads, files.clone(), contents.clone());
+        server.route(Method::PUT, "/upload/drive/v3/files/{id}", move |req| finish_upload(&u, &f, &c, req));
+
+        let o = objects.clone();
+        server.route(Method::POST, "/upload/storage/v1/b/{bucket}/o", move |req| {
+            let bucket = req.param("bucket");
+            let name = req.query("name").unwrap_or_default();
+            let now = Utc::now().to_rfc3339();
+            Response::ok(o.insert(json!({
+                "kind": "storage#object",
+                "id": format!("{}/{}", bucket, name),
+                "name": name,
+                "bucket": bucket,
+                "contentType": req.header("content-type").unwrap_or_default(),
+                "size": req.body.len().to_string(),
+                "timeCreated": now,
+                "updated": now,
+                "mediaLink": format!("https://storage.googleapis.com/download/storage/v1/b/{}/o/{}?alt=media", bucket, name),
+                "selfLink": format!("https://www.googleapis.com/storage/v1/b/{}/o/{}", bucket, name),
+            })))
+        });
+
+        FakeDrive {
+            server,
+            drives,
+            files,
+            contents,
+            objects,
+        }
+    }
+
+    /// The URL to pass to `GoogleDrive::with_base_url`.
+    pub fn base_url(&self) -> String {
+        self.server.base_url()
+    }
+
+    /// Every request the fake has received, oldest first.
+    pub fn requests(&self) -> Vec<Request> {
+        self.server.requests()
+    }
+
+    /// Add a shared drive and return its id.
+    pub fn add_drive(&self, name: &str) -> String {
+        let drive = self.drives.insert(json!({ "kind": "drive#drive", "name": name }));
+        drive["id"].as_str().unwrap_or_default().to_string()
+    }
+
+    /// Add a file to a drive and return its id.
+    pub fn add_file(&self, drive_id: &str, parent_id: &str, name: &str, mime_type: &str, contents: &[u8]) -> String {
+        let parent = if parent_id.is_empty() { drive_id } else { parent_id };
+        let file = self.files.insert(new_file(drive_id, json!({ "name": name, "mimeType": mime_type, "parents": [parent] })));
+        let id = file["id"].as_str().unwrap_or_default().to_string();
+        self.contents.lock().unwrap().insert(id.to_string(), contents.to_vec());
+        id
+    }
+
+    /// Every file, with its metadata like the API returns it.
+    pub fn files(&self) -> Vec<Value> {
+        self.files.all()
+    }
+
+    /// The contents of a file.
+    pub fn contents(&self, file_id: &str) -> Option<Vec<u8>> {
+        self.contents.lock().unwrap().get(file_id).cloned()
+    }
+
+    /// The objects that were uploaded to Cloud Storage.
+    pub fn objects(&self) -> Vec<Value> {
+        self.objects.all()
+    }
+}
+
+fn not_found(id: &str) -> Response {
+    Response::json(StatusCode::NOT_FOUND, json!({ "error": { "code": 404, "message": format!("File not found: {}.", id) } }))
+}
+
+/// The name in a query like `name = 'Some File'`.
+fn name_in_query(q: &str) -> String {
+    q.trim().trim_start_matches("name").trim().trim_start_matches('=').trim().trim_matches('\'').replace("\\'", "'")
+}
+
+/// Files created in a folder are in the drive of the folder, files created at
+/// the top of a drive have the drive as their parent.
+fn files_store_drive(files: &Store, metadata: Value) -> String {
+    let parent = metadata["parents"][0].as_str().unwrap_or_default();
+    match files.get(parent) {
+        Some(folder) => folder["driveId"].as_str().unwrap_or_default().to_string(),
+        None => parent.to_string(),
+    }
+}
+
+fn new_file(drive_id: &str, mut metadata: Value) -> Value {
+    metadata["kind"] = json!("drive#file");
+    metadata["driveId"] = json!(drive_id);
+    metadata["modifiedTime"] = json!(Utc::now().to_rfc3339());
+    metadata
+}
+
+fn start_upload(uploads: &Store, req: &Request, file_id: &str) -> Response {
+    let upload = uploads.insert(json!({ "file_id": file_id, "metadata": req.json() }));
+    let mut location = format!("http://{}/upload/drive/v3/files", req.header("host").unwrap_or_default());
+    if !file_id.is_empty() {
+        location = format!("{}/{}", location, file_id);
+    }
+
+    let mut response = Response::empty(StatusCode::OK);
+    response.headers.push((
+        "location".to_string(),
+        format!("{}?uploadType=resumable&upload_id={}", location, upload["upload_id"].as_str().unwrap_or_default()),
+    ));
+    response
+}
+
+fn finish_upload(uploads: &Store, files: &Store, contents: &Mutex<BTreeMap<String, Vec<u8>>>, req: &Request) -> Response {
+    let upload = match uploads.remove(req.query("upload_id").unwrap_or_default()) {
+        Some(u) => u,
+        None => return Response::json(StatusCode::NOT_FOUND, json!({ "error": { "code": 404, "message": "Upload not found." } })),
+    };
+
+    let file_id = upload["file_id"].as_str().unwrap_or_default();
+    let file = if file_id.is_empty() {
+        files.insert(new_file(&files_store_drive(files, upload["metadata"].clone()), upload["metadata"].clone()))
+    } else {
+        let mut changes = json!({ "modifiedTime": Utc::now().to_rfc3339() });
+        if let Some(mime_type) = req.header("content-type") {
+            changes["mimeType"] = json!(mime_type);
+        }
+        match files.update(file_id, &changes) {
+            Some(f) => f,
+            None => return not_found(file_id),
+        }
+    };
+
+    let id = file["id"].as_str().unwrap_or_default().to_string();
+    contents.lock().unwrap().insert(id, req.body.clone());
+    Response::ok(file)
+}
@@ -0,0 +1,87 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use hyper::Method;
+use serde_json::json;
+
+use crate::server::{FakeServer, Request, Response};
+
+/// Where addresses are unless they were given a location with `set_location`.
+const DEFAULT_LOCATION: (f64, f64) = (37.8312, -122.2867);
+
+/// A fake of the Google Geocoding API.
+///
+/// Every address is found, at the location set with `set_location` or at a
+/// default location in Emeryville.
+pub struct FakeGeocode {
+    server: FakeServer,
+    locations: Arc<Mutex<BTreeMap<String, (f64, f64)>>>,
+}
+
+impl FakeGeocode {
+    pub fn start() -> Self {
+        let server = FakeServer::start();
+        let locations: Arc<Mutex<BTreeMap<String, (f64, f64)>>> = Default::default();
+
+        let l = locations.clone();
+        server.route(Method::GET, "/maps/api/geocode/json", move |req| {
+            let address = req.query("address").unwrap_or_default();
+            if address.is_empty() {
+                return Response::ok(json!({ "results": [], "status": "INVALID_REQUEST" }));
+            }
+
+            let (lat, lng) = l.lock().unwrap().get(address).cloned().unwrap_or(DEFAULT_LOCATION);
+            let location = json!({ "lat": lat, "lng": lng });
+            Response::ok(json!({
+                "results": [{
+                    "address_components": [],
+                    "formatted_address": address,
+                    "geometry": {
+                        "location": location,
+                        "location_type": "ROOFTOP",
+                        "viewport": { "northeast": location, "southwest": location },
+                    },
+                    "place_id": format!("place_{}", address.len()),
+                    "types": ["street_address"],
+                }],
+                "status": "OK",
+            }))
+        });
+
+        FakeGeocode { server, locations }
+    }
+
+    /// The URL to pass to `Geocode::with_base_url`.
+    pub fn base_url(&self) -> String {
+        self.server.base_url()
+    }
+
+    /// Every request the fake has received, oldest first.
+    pub fn requests(&self) -> Vec<Request> {
+        self.server.requests()
+    }
+
+    /// Geocode an address to the given latitude and longitude.
+    pub fn set_location(&self, address: &str, lat: f64, lng: f64) {
+        self.locations.lock().unwrap().insert(address.to_string(), (lat, lng));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use google_geocode::Geocode;
+
+    use crate::geocode::FakeGeocode;
+
+    #[tokio::test]
+    async fn test_fake_geocode() {
+        let fake = FakeGeocode::start();
+        fake.set_location("1 Infinite Loop, Cupertino, CA 95014", 37.3318, -122.0312);
+        let geocode = Geocode::new("key").with_base_url(fake.base_url());
+
+        let reply = geocode.get("1 Infinite Loop, Cupertino, CA 95014").await.unwrap();
+        assert_eq!(reply.geometry.location.lat, 37.3318);
+        assert_eq!(reply.geometry.location.lng, -122.0312);
+        assert_eq!(reply.formatted_address.to_string(), "1 Infinite Loop, Cupertino, CA 95014");
+    }
+}
@@ -0,0 +1,314 @@
+use hyper::{Method, StatusCode};
+use serde_json::{json, Value};
+
+use crate::server::{FakeServer, Request, Response};
+use crate::store::Store;
+
+/// A fake of the parts of the G Suite Admin, Groups Settings and Calendar
+/// APIs the `gsuite` client uses.
+///
+/// Users and groups can be looked up by their id or their email address, like
+/// the real API allows. Deletes reply `204 No Content`, like the real API.
+pub struct FakeGSuite {
+    server: FakeServer,
+    users: Store,
+    groups: Store,
+    members: Store,
+    buildings: Store,
+    calendar_resources: Store,
+}
+
+impl FakeGSuite {
+    pub fn start() -> Self {
+        let server = FakeServer::start();
+        let users = Store::new("id", "1");
+        let groups = Store::new("id", "0");
+        let members = Store::new("key", "");
+        let buildings = Store::new("buildingId", "");
+        let calendar_resources = Store::new("resourceId", "");
+        let group_settings = Store::new("email", "");
+
+        let u = users.clone();
+        server.route(Method::GET, "/admin/directory/v1/users", move |_| {
+            Response::ok(json!({ "kind": "admin#directory#users", "users": u.all() }))
+        });
+        let u = users.clone();
+        server.route(Method::POST, "/admin/directory/v1/users", move |req| {
+            let mut user = req.json();
+            let email = user["primaryEmail"].as_str().unwrap_or_default().to_string();
+            if find_by_email(&u, "primaryEmail", &email).is_some() {
+                return conflict("Entity already exists.");
+            }
+            user["id"] = json!("");
+            user["kind"] = json!("admin#directory#user");
+            Response::ok(u.insert(user))
+        });
+        let u = users.clone();
+        server.route(Method::GET, "/admin/directory/v1/users/{key}", move |req| {
+            find_by_email(&u, "primaryEmail", req.param("key")).map(Response::ok).unwrap_or_else(not_found)
+        });
+        let u = users.clone();
+        server.route(Method::PUT, "/admin/directory/v1/users/{key}", move |req| match find_by_email(&u, "primaryEmail", req.param("key")) {
+            Some(user) => Response::ok(u.update(id(&user), &without_id(req.json())).unwrap()),
+            None => not_found(),
+        });
+        let u = users.clone();
+        server.route(Method::DELETE, "/admin/directory/v1/users/{key}", move |req| {
+            match find_by_email(&u, "primaryEmail", req.param("key")) {
+                Some(user) => {
+                    u.remove(id(&user));
+                    Response::empty(StatusCode::NO_CONTENT)
+                }
+                None => not_found(),
+            }
+        });
+        let u = users.clone();
+        server.route(Method::POST, "/admin/directory/v1/users/{key}/aliases", move |req| add_alias(&u, "primaryEmail", req));
+
+        let g = groups.clone();
+        server.route(Method::GET, "/admin/directory/v1/groups", move |_| {
+            Response::ok(json!({ "kind": "admin#directory#groups", "groups": g.all() }))
+        });
+        let g = groups.clone();
+        server.route(Method::POST, "/admin/directory/v1/groups", move |req| {
+            let mut group = req.json();
+            let email = group["email"].as_str().unwrap_or_default().to_string();
+            if find_by_email(&g, "email", &email).is_some() {
+                return conflict("Entity already exists.");
+            }
+            group["id"] = json!("");
+            group["kind"] = json!("admin#directory#group");
+            Response::ok(g.insert(group))
+        });
+        let g = groups.clone();
+        server.route(Method::GET, "/admin/directory/v1/groups/{key}", move |req| {
+            find_by_email(&g, "email", req.param("key")).map(Response::ok).unwrap_or_else(not_found)
+        });
+        let g = groups.clone();
+        server.route(Method::PUT, "/admin/directory/v1/groups/{key}", move |req| match find_by_email(&g, "email", req.param("key")) {
+            Some(group) => Response::ok(g.update(id(&group), &without_id(req.json())).unwrap()),
+            None => not_found(),
+        });
+        let (g, m) = (groups.clone(), members.clone());
+        server.route(Method::DELETE, "/admin/directory/v1/groups/{key}", move |req| match find_by_email(&g, "email", req.param("key")) {
+            Some(group) => {
+                g.remove(id(&group));
+                for member in m.all().iter().filter(|member| member["group"] == group["id"]) {
+                    m.remove(member["key"].as_str().unwrap_or_default());
+                }
+                Response::empty(StatusCode::NO_CONTENT)
+            }
+            None => not_found(),
+        });
+        let g = groups.clone();
+        server.route(Method::POST, "/admin/directory/v1/groups/{key}/aliases", move |req| add_alias(&g, "email", req));
+
+        let (g, m) = (groups.clone(), members.clone());
+        server.route(Method::GET, "/admin/directory/v1/groups/{key}/hasMember/{email}", move |req| {
+            match find_by_email(&g, "email", req.param("key")) {
+                Some(group) => Response::ok(json!({ "isMember": m.get(&member_key(&group, req.param("email"))).is_some() })),
+                None => not_found(),
+            }
+        });
+        let (g, m) = (groups.clone(), members.clone());
+        server.route(Method::GET, "/admin/directory/v1/groups/{key}/members", move |req| match find_by_email(&g, "email", req.param("key")) {
+            Some(group) => Response::ok(json!({ "kind": "admin#directory#members", "members": m.all().into_iter().filter(|member| member["group"] == group["id"]).collect::<Vec<Value>>() })),
+            None => not_found(),
+        });
+        let (g, m) = (groups.clone(), members.clone());
+        server.route(Method::POST, "/admin/directory/v1/groups/{key}/members", move |req| {
+            let group = match find_by_email(&g, "email", req.param("key")) {
+                Some(group) => group,
+                None => return not_found(),
+            };
+            let mut member = req.json();
+            let key = member_key(&group, member["email"].as_str().unwrap_or_default());
+            if m.get(&key).is_some() {
+                return conflict("Member already exists.");
+            }
+            member["key"] = json!(key);
+            member["group"] = group["id"].clone();
+            member["kind"] = json!("admin#directory#member");
+            Response::ok(m.insert(member))
+        });
+        let (g, m) = (groups.clone(), members.clone());
+        server.route(Method::PUT, "/admin/directory/v1/groups/{key}/members/{email}", move |req| {
+            let key = match find_by_email(&g, "email", req.param("key")) {
+                Some(group) => member_key(&group, req.param("email")),
+                None => return not_found(),
+            };
+            m.update(&key, &json!({ "role": req.json()["role"] })).map(Response::ok).unwrap_or_else(not_found)
+        });
+        let (g, m) = (groups.clone(), members.clone());
+        server.route(Method::DELETE, "/admin/directory/v1/groups/{key}/members/{email}", move |req| {
+            let key = match find_by_email(&g, "email", req.param("key")) {
+                Some(group) => member_key(&group, req.param("email")),
+                None => return not_found(),
+            };
+            match m.remove(&key) {
+                Some(_) => Response::empty(StatusCode::NO_CONTENT),
+                None => not_found(),
+            }
+        });
+
+        // Groups settings are keyed by the email of the group, and every group
+        // has the default settings until they are changed.
+        let s = group_settings.clone();
+        server.route(Method::GET, "/groups/v1/groups/{email}", move |req| {
+            Response::ok(s.get(req.param("email")).unwrap_or_else(|| json!({ "kind": "groupsSettings#groups", "email": req.param("email") })))
+        });
+        let s = group_settings;
+        server.route(Method::PUT, "/groups/v1/groups/{email}", move |req| {
+            let mut settings = req.json();
+            settings["email"] = json!(req.param("email"));
+            s.remove(req.param("email"));
+            Response::ok(s.insert(settings))
+        });
+
+        let b = buildings.clone();
+        server.route(Method::GET, "/admin/directory/v1/customer/{customer}/resources/buildings", move |_| {
+            Response::ok(json!({ "buildings": b.all() }))
+        });
+        let b = buildings.clone();
+        server.route(Method::POST, "/admin/directory/v1/customer/{customer}/resources/buildings", move |req| {
+            Response::ok(b.insert(req.json()))
+        });
+        let b = buildings.clone();
+        server.route(Method::PUT, "/admin/directory/v1/customer/{customer}/resources/buildings/{id}", move |req| {
+            b.update(req.param("id"), &req.json()).map(Response::ok).unwrap_or_else(not_found)
+        });
+        let b = buildings.clone();
+        server.route(Method::DELETE, "/admin/directory/v1/customer/{customer}/resources/buildings/{id}", move |req| {
+            match b.remove(req.param("id")) {
+                Some(_) => Response::empty(StatusCode::NO_CONTENT),
+                None => not_found(),
+            }
+        });
+
+        let c = calendar_resources.clone();
+        server.route(Method::GET, "/admin/directory/v1/customer/{customer}/resources/calendars", move |_| {
+            Response::ok(json!({ "items": c.all() }))
+        });
+        let c = calendar_resources.clone();
+        server.route(Method::POST, "/admin/directory/v1/customer/{customer}/resources/calendars", move |req| {
+            Response::ok(c.insert(req.json()))
+        });
+        let c = calendar_resources.clone();
+        server.route(Method::PUT, "/admin/directory/v1/customer/{customer}/resources/calendars/{id}", move |req| {
+            c.update(req.param("id"), &req.json()).map(Response::ok).unwrap_or_else(not_found)
+        });
+        let c = calendar_resources.clone();
+        server.route(Method::DELETE, "/admin/directory/v1/customer/{customer}/resources/calendars/{id}", move |req| {
+            match c.remove(req.param("id")) {
+                Some(_) => Response::empty(StatusCode::NO_CONTENT),
+                None => not_found(),
+            }
+        });
+
+        server.route(Method::GET, "/calendar/v3/users/me/calendarList", |_| {
+            Response::ok(json!({ "kind": "calendar#calendarList", "items": [] }))
+        });
+
+        FakeGSuite {
+            server,
+            users,
+            groups,
+            members,
+            buildings,
+            calendar_resources,
+        }
+    }
+
+    /// The URL to pass to `GSuite::with_base_url`.
+    pub fn base_url(&self) -> String {
+        self.server.base_url()
+    }
+
+    /// Every request the fake has received, oldest first.
+    pub fn requests(&self) -> Vec<Request> {
+        self.server.requests()
+    }
+
+    /// The users in the directory.
+    pub fn users(&self) -> Vec<Value> {
+        self.users.all()
+    }
+
+    /// The groups in the directory.
+    pub fn groups(&self) -> Vec<Value> {
+        self.groups.all()
+    }
+
+    /// The email addresses of the members of a group.
+    pub fn members(&self, group_email: &str) -> Vec<String> {
+        let group = match find_by_email(&self.groups, "email", group_email) {
+            Some(group) => group,
+            None => return Default::default(),
+        };
+        self.members
+            .all()
+            .iter()
+            .filter(|m| m["group"] == group["id"])
+            .filter_map(|m| m["email"].as_str().map(|e| e.to_string()))
+            .collect()
+    }
+
+    /// The buildings of the customer.
+    pub fn buildings(&self) -> Vec<Value> {
+        self.buildings.all()
+    }
+
+    /// The calendar resources, like conference rooms, of the customer.
+    pub fn calendar_resources(&self) -> Vec<Value> {
+        self.calendar_resources.all()
+    }
+}
+
+fn id(item: &Value) -> &str {
+    item["id"].as_str().unwrap_or_default()
+}
+
+/// The body of an update can have an id, but it can't change it.
+fn without_id(mut changes: Value) -> Value {
+    if let Some(changes) = changes.as_object_mut() {
+        changes.remove("id");
+    }
+    changes
+}
+
+/// Find an object by its id, its email address or one of its aliases.
+fn find_by_email(store: &Store, email_field: &str, key: &str) -> Option<Value> {
+    store.get(key).or_else(|| {
+        store
+            .all()
+            .into_iter()
+            .find(|i| i[email_field].as_str() == Some(key) || i["aliases"].as_array().map(|a| a.iter().any(|a| a.as_str() == Some(key))).unwrap_or(false))
+    })
+}
+
+fn member_key(group: &Value, email: &str) -> String {
+    format!("{}/{}", id(group), email.to_lowercase())
+}
+
+fn add_alias(store: &Store, email_field: &str, req: &Request) -> Response {
+    let item = match find_by_email(store, email_field, req.param("key")) {
+        Some(item) => item,
+        None => return not_found(),
+    };
+    let alias = req.json()["alias"].clone();
+    let mut aliases = item["aliases"].as_array().cloned().unwrap_or_default();
+    if aliases.contains(&alias) {
+        return conflict("Entity already exists.");
+    }
+    aliases.push(alias.clone());
+    store.update(id(&item), &json!({ "aliases": aliases }));
+    Response::ok(json!({ "kind": "admin#directory#alias", "alias": alias }))
+}
+
+fn not_found() -> Response {
+    Response::json(StatusCode::NOT_FOUND, json!({ "error": { "code": 404, "message": "Resource Not Found: key", "status": "NOT_FOUND" } }))
+}
+
+fn conflict(message: &str) -> Response {
+    Response::json(StatusCode::CONFLICT, json!({ "error": { "code": 409, "message": message } }))
+}
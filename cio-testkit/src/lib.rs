@@ -0,0 +1,153 @@
+/*!
+ * Fake servers for the APIs the clients in this repo talk to, so the code
+ * that uses them can be tested without credentials or a network.
+ *
+ * Each fake is an HTTP server on a random local port that keeps its state in
+ * memory. It answers the requests its client sends with what the real API
+ * would, and has methods to check what was done through it, like the emails
+ * that were sent or the labels that were printed, and to set up the things
+ * the real service would already have.
+ *
+ * Point a client at a fake with its `with_base_url`, or start them all with
+ * `FakeApis` and set the environment variables the `new_from_env`
+ * constructors read.
+ *
+ * Example:
+ *
+ * ```ignore
+ * #[tokio::test]
+ * async fn test_send_mail() {
+ *     let fake = cio_testkit::sendgrid::FakeSendGrid::start();
+ *     let sendgrid = sendgrid_api::SendGrid::new("key").with_base_url(fake.base_url());
+ *
+ *     sendgrid.send_mail(/* ... */).await;
+ *
+ *     assert_eq!(fake.sent().len(), 1);
+ * }
+ * ```
+ *
+ * The fakes run on the tokio runtime they were started on, so they must be
+ * started from inside one, like a `#[tokio::test]`.
+ */
+mod server;
+mod store;
+
+pub mod airtable;
+pub mod checkr;
+pub mod docusign;
+pub mod drive;
+pub mod geocode;
+pub mod gsuite;
+pub mod okta;
+pub mod printer;
+pub mod ramp;
+pub mod sendgrid;
+pub mod sheets;
+pub mod shippo;
+pub mod slack;
+
+use std::env;
+use std::sync::{Mutex, MutexGuard};
+
+pub use hyper::{Method, StatusCode};
+use lazy_static::lazy_static;
+
+pub use crate::server::{FakeServer, Request, Response};
+pub use crate::store::Store;
+
+lazy_static! {
+    /// Held by the test that has the environment pointed at its fakes.
+    static ref ENV: Mutex<()> = Mutex::new(());
+}
+
+/// Wait for any other test using the environment to finish, for tests that
+/// set variables of their own. Hold on to the returned guard until the test is
+/// done.
+pub fn lock_env() -> MutexGuard<'static, ()> {
+    // A test that panicked while holding the lock is already reported, it
+    // should not fail every test after it.
+    ENV.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Every fake, started together.
+pub struct FakeApis {
+    pub airtable: airtable::FakeAirtable,
+    pub checkr: checkr::FakeCheckr,
+    pub docusign: docusign::FakeDocuSign,
+    pub drive: drive::FakeDrive,
+    pub geocode: geocode::FakeGeocode,
+    pub gsuite: gsuite::FakeGSuite,
+    pub okta: okta::FakeOkta,
+    pub printer: printer::FakePrinter,
+    pub ramp: ramp::FakeRamp,
+    pub sendgrid: sendgrid::FakeSendGrid,
+    pub sheets: sheets::FakeSheets,
+    pub shippo: shippo::FakeShippo,
+    pub slack: slack::FakeSlack,
+}
+
+impl FakeApis {
+    pub fn start() -> Self {
+        FakeApis {
+            airtable: airtable::FakeAirtable::start(),
+            checkr: checkr::FakeCheckr::start(),
+            docusign: docusign::FakeDocuSign::start(),
+            drive: drive::FakeDrive::start(),
+            geocode: geocode::FakeGeocode::start(),
+            gsuite: gsuite::FakeGSuite::start(),
+            okta: okta::FakeOkta::start(),
+            printer: printer::FakePrinter::start(),
+            ramp: ramp::FakeRamp::start(),
+            sendgrid: sendgrid::FakeSendGrid::start(),
+            sheets: sheets::FakeSheets::start(),
+            shippo: shippo::FakeShippo::start(),
+            slack: slack::FakeSlack::start(),
+        }
+    }
+
+    /// Point the clients that are built from the environment at the fakes,
+    /// and give them credentials the fakes accept.
+    ///
+    /// The environment is shared by every test in the process, so this waits
+    /// for any other test using it to finish. Hold on to the returned guard
+    /// until the test is done.
+    pub fn set_env(&self) -> MutexGuard<'static, ()> {
+        let guard = lock_env();
+
+        let vars = vec![
+            ("AIRTABLE_BASE_URL", self.airtable.base_url()),
+            ("AIRTABLE_API_KEY", "key_fake".to_string()),
+            ("CHECKR_BASE_URL", self.checkr.base_url()),
+            ("CHECKR_API_KEY", "checkr_fake".to_string()),
+            ("DOCUSIGN_BASE_URL", self.docusign.base_url()),
+            ("DRIVE_BASE_URL", self.drive.base_url()),
+            ("GOOGLE_GEOCODE_BASE_URL", self.geocode.base_url()),
+            ("GOOGLE_GEOCODE_API_KEY", "geocode_fake".to_string()),
+            ("GSUITE_BASE_URL", self.gsuite.base_url()),
+            ("OKTA_BASE_URL", self.okta.base_url()),
+            ("OKTA_API_TOKEN", "okta_fake".to_string()),
+            ("OKTA_DOMAIN", "fake".to_string()),
+            ("PRINTER_URL", self.printer.printer_url()),
+            ("RAMP_BASE_URL", self.ramp.base_url()),
+            ("RAMP_CLIENT_ID", "ramp_client_fake".to_string()),
+            ("RAMP_CLIENT_SECRET", "ramp_secret_fake".to_string()),
+            ("SENDGRID_BASE_URL", self.sendgrid.base_url()),
+            ("SENDGRID_API_KEY", "sendgrid_fake".to_string()),
+            ("SHEETS_BASE_URL", self.sheets.base_url()),
+            ("SHIPPO_BASE_URL", self.shippo.base_url()),
+            ("SHIPPO_API_TOKEN", "shippo_fake".to_string()),
+            ("SLACK_BASE_URL", self.slack.base_url()),
+            ("SLACK_TOKEN", "xoxb-fake".to_string()),
+            ("SLACK_WORKSPACE_ID", "T000001".to_string()),
+            ("SLACK_CUSTOMERS_CHANNEL_POST_URL", self.slack.webhook_url("customers")),
+            ("SLACK_HIRING_CHANNEL_POST_URL", self.slack.webhook_url("hiring")),
+            ("SLACK_PUBLIC_RELATIONS_CHANNEL_POST_URL", self.slack.webhook_url("public-relations")),
+        ];
+
+        for (key, value) in vars {
+            env::set_var(key, value);
+        }
+
+        guard
+    }
+}
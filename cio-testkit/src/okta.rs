@@ -0,0 +1,205 @@
+use chrono::Utc;
+use hyper::{Method, StatusCode};
+use serde_json::{json, Value};
+
+use crate::server::{FakeServer, Request, Response};
+use crate::store::Store;
+
+/// A fake of the Okta users and groups APIs.
+///
+/// Users can be looked up by their id or their login, and groups can be
+/// searched with the `q` query parameter, which matches the start of their
+/// name.
+pub struct FakeOkta {
+    server: FakeServer,
+    users: Store,
+    groups: Store,
+    memberships: Store,
+}
+
+impl FakeOkta {
+    pub fn start() -> Self {
+        let server = FakeServer::start();
+        let users = Store::new("id", "00u");
+        let groups = Store::new("id", "00g");
+        let memberships = Store::new("key", "");
+
+        let u = users.clone();
+        server.route(Method::GET, "/api/v1/users", move |_| Response::ok(json!(u.all())));
+        let u = users.clone();
+        server.route(Method::POST, "/api/v1/users", move |req| {
+            let profile = req.json()["profile"].clone();
+            let login = profile["login"].as_str().unwrap_or_default();
+            if find_user(&u, login).is_some() {
+                return error(StatusCode::BAD_REQUEST, "E0000001", "login: An object with this field already exists in the current organization");
+            }
+
+            let now = Utc::now();
+            let status = if req.query("activate") == Some("false") { "STAGED" } else { "ACTIVE" };
+            Response::ok(u.insert(json!({
+                "status": status,
+                "created": now,
+                "activated": if status == "ACTIVE" { json!(now) } else { Value::Null },
+                "statusChanged": now,
+                "lastLogin": null,
+                "lastUpdated": now,
+                "passwordChanged": null,
+                "profile": profile,
+            })))
+        });
+        let u = users.clone();
+        server.route(Method::GET, "/api/v1/users/{id}", move |req| find_user(&u, req.param("id")).map(Response::ok).unwrap_or_else(not_found));
+        let u = users.clone();
+        server.route(Method::PUT, "/api/v1/users/{id}", move |req| match find_user(&u, req.param("id")) {
+            Some(user) => Response::ok(u.update(id(&user), &json!({ "profile": req.json()["profile"], "lastUpdated": Utc::now() })).unwrap()),
+            None => not_found(),
+        });
+        let u = users.clone();
+        server.route(Method::POST, "/api/v1/users/{id}/lifecycle/deactivate", move |req| match find_user(&u, req.param("id")) {
+            Some(user) => {
+                let now = Utc::now();
+                u.update(id(&user), &json!({ "status": "DEPROVISIONED", "statusChanged": now, "lastUpdated": now }));
+                Response::ok(json!({}))
+            }
+            None => not_found(),
+        });
+
+        let g = groups.clone();
+        server.route(Method::GET, "/api/v1/groups", move |req| {
+            let q = req.query("q").unwrap_or_default().to_lowercase();
+            let found: Vec<Value> = g
+                .all()
+                .into_iter()
+                .filter(|group| group["profile"]["name"].as_str().unwrap_or_default().to_lowercase().starts_with(&q))
+                .collect();
+            Response::ok(json!(found))
+        });
+        let g = groups.clone();
+        server.route(Method::POST, "/api/v1/groups", move |req| {
+            let now = Utc::now();
+            Response::ok(g.insert(json!({
+                "created": now,
+                "lastUpdated": now,
+                "lastMembershipUpdated": now,
+                "objectClass": ["okta:user_group"],
+                "type": "OKTA_GROUP",
+                "profile": req.json()["profile"],
+            })))
+        });
+        let g = groups.clone();
+        server.route(Method::PUT, "/api/v1/groups/{id}", move |req| {
+            g.update(req.param("id"), &json!({ "profile": req.json()["profile"], "lastUpdated": Utc::now() }))
+                .map(Response::ok)
+                .unwrap_or_else(not_found)
+        });
+
+        let (g, m) = (groups.clone(), memberships.clone());
+        server.route(Method::PUT, "/api/v1/groups/{id}/users/{user}", move |req| {
+            if g.update(req.param("id"), &json!({ "lastMembershipUpdated": Utc::now() })).is_none() {
+                return not_found();
+            }
+            let key = format!("{}/{}", req.param("id"), req.param("user"));
+            if m.get(&key).is_none() {
+                m.insert(json!({ "key": key, "group": req.param("id"), "user": req.param("user") }));
+            }
+            Response::empty(StatusCode::NO_CONTENT)
+        });
+        let (g, m) = (groups.clone(), memberships.clone());
+        server.route(Method::DELETE, "/api/v1/groups/{id}/users/{user}", move |req| {
+            if g.update(req.param("id"), &json!({ "lastMembershipUpdated": Utc::now() })).is_none() {
+                return not_found();
+            }
+            m.remove(&format!("{}/{}", req.param("id"), req.param("user")));
+            Response::empty(StatusCode::NO_CONTENT)
+        });
+
+        FakeOkta { server, users, groups, memberships }
+    }
+
+    /// The URL to pass to `Okta::with_base_url`.
+    pub fn base_url(&self) -> String {
+        self.server.base_url()
+    }
+
+    /// Every request the fake has received, oldest first.
+    pub fn requests(&self) -> Vec<Request> {
+        self.server.requests()
+    }
+
+    /// The users in the organization.
+    pub fn users(&self) -> Vec<Value> {
+        self.users.all()
+    }
+
+    /// The groups in the organization.
+    pub fn groups(&self) -> Vec<Value> {
+        self.groups.all()
+    }
+
+    /// The logins of the members of a group.
+    pub fn members(&self, group_id: &str) -> Vec<String> {
+        self.memberships
+            .all()
+            .iter()
+            .filter(|m| m["group"].as_str() == Some(group_id))
+            .filter_map(|m| self.users.get(m["user"].as_str().unwrap_or_default()))
+            .filter_map(|u| u["profile"]["login"].as_str().map(|l| l.to_string()))
+            .collect()
+    }
+}
+
+fn id(item: &Value) -> &str {
+    item["id"].as_str().unwrap_or_default()
+}
+
+fn find_user(users: &Store, key: &str) -> Option<Value> {
+    users.get(key).or_else(|| users.all().into_iter().find(|u| u["profile"]["login"].as_str() == Some(key)))
+}
+
+fn error(status: StatusCode, code: &str, summary: &str) -> Response {
+    Response::json(status, json!({ "errorCode": code, "errorSummary": summary, "errorCauses": [] }))
+}
+
+fn not_found() -> Response {
+    error(StatusCode::NOT_FOUND, "E0000007", "Not found: Resource not found")
+}
+
+#[cfg(test)]
+mod tests {
+    use okta::{GroupProfile, Okta, Profile};
+
+    use crate::okta::FakeOkta;
+
+    #[tokio::test]
+    async fn test_fake_okta() {
+        let fake = FakeOkta::start();
+        let okta = Okta::new("token", "example").with_base_url(fake.base_url());
+
+        let user = okta
+            .create_user(Profile {
+                first_name: "Jane".to_string(),
+                last_name: "Doe".to_string(),
+                email: "jane@example.com".to_string(),
+                login: "jane@example.com".to_string(),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(okta.get_user("jane@example.com").await.unwrap().id, user.id);
+
+        let group = okta
+            .create_group(GroupProfile {
+                name: "engineering".to_string(),
+                description: "".to_string(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(okta.get_group("engineering").await.unwrap().id, group.id);
+
+        okta.add_user_to_group(&group.id, "jane@example.com").await.unwrap();
+        assert_eq!(fake.members(&group.id), vec!["jane@example.com".to_string()]);
+
+        okta.delete_user_from_group(&group.id, "jane@example.com").await.unwrap();
+        assert!(fake.members(&group.id).is_empty());
+    }
+}
@@ -0,0 +1,117 @@
+use hyper::{Method, StatusCode};
+use serde_json::{json, Value};
+
+use crate::server::{FakeServer, Request, Response};
+use crate::store::Store;
+
+/// A print job that was sent to the fake.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrintJob {
+    pub id: String,
+    /// The printer the job was sent to, `rollo` or `zebra`.
+    pub printer: String,
+    /// The URL of the label to print.
+    pub url: String,
+    /// The ZPL of the label to print, for jobs sent to `/print/zebra/zpl`.
+    pub zpl: String,
+    pub quantity: i64,
+}
+
+/// A fake of the printy server that prints shipping labels on the Rollo
+/// printer and barcode labels, as PDF or ZPL, on the Zebra printer.
+///
+/// Nothing gets printed, the jobs are kept so tests can check them. Like
+/// printy, the print endpoints reply with the queued job, and the jobs can be
+/// listed with `GET /jobs`.
+pub struct FakePrinter {
+    server: FakeServer,
+    jobs: Store,
+}
+
+impl FakePrinter {
+    pub fn start() -> Self {
+        let server = FakeServer::start();
+        let jobs = Store::new("id", "job");
+
+        server.route(Method::GET, "/ping", |_| Response::ok(json!("pong")));
+
+        for printer in &["rollo", "zebra"] {
+            let j = jobs.clone();
+            server.route(Method::POST, &format!("/print/{}", printer), move |req| queue_job(&j, printer, "url", req.json()));
+        }
+        let j = jobs.clone();
+        server.route(Method::POST, "/print/zebra/zpl", move |req| queue_job(&j, "zebra", "zpl", req.json()));
+
+        let j = jobs.clone();
+        server.route(Method::GET, "/jobs", move |_| Response::ok(json!(j.all())));
+        let j = jobs.clone();
+        server.route(Method::GET, "/jobs/{id}", move |req| match j.get(req.param("id")) {
+            Some(job) => Response::ok(job),
+            None => Response::json(StatusCode::NOT_FOUND, json!({ "message": "job not found" })),
+        });
+
+        FakePrinter { server, jobs }
+    }
+
+    /// The URL to set `PRINTER_URL` to.
+    pub fn printer_url(&self) -> String {
+        format!("{}/print", self.server.base_url())
+    }
+
+    /// Every request the fake has received, oldest first.
+    pub fn requests(&self) -> Vec<Request> {
+        self.server.requests()
+    }
+
+    /// The jobs that were sent, oldest first.
+    pub fn jobs(&self) -> Vec<PrintJob> {
+        self.jobs
+            .all()
+            .iter()
+            .map(|j| PrintJob {
+                id: j["id"].as_str().unwrap_or_default().to_string(),
+                printer: j["printer"].as_str().unwrap_or_default().to_string(),
+                url: j["url"].as_str().unwrap_or_default().to_string(),
+                zpl: j["zpl"].as_str().unwrap_or_default().to_string(),
+                quantity: j["copies"].as_i64().unwrap_or_default(),
+            })
+            .collect()
+    }
+}
+
+/// Queue a job for a `PrintLabelsRequest` or a `PrintZplRequest`, with the
+/// label in the `label` field of the body. Rollo labels are printed once.
+/// Like printy, the job gets the `job_id` in the body if there is one.
+fn queue_job(jobs: &Store, printer: &str, label: &str, body: Value) -> Response {
+    let value = match body[label].as_str() {
+        Some(value) if !value.is_empty() => value,
+        _ => return bad_request(),
+    };
+    let id = body["job_id"].as_str().unwrap_or_default();
+    if let Some(job) = jobs.get(id) {
+        return Response::json(StatusCode::ACCEPTED, job);
+    }
+    let (label_type, copies) = if printer == "rollo" {
+        ("shipping", 1)
+    } else {
+        ("barcode", body["quantity"].as_i64().unwrap_or(1))
+    };
+
+    let now = chrono::Utc::now();
+    let job = jobs.insert(json!({
+        "id": id,
+        "printer": printer,
+        "label_type": label_type,
+        label: value,
+        "copies": copies,
+        "status": "queued",
+        "source": body["source"],
+        "created_at": now,
+        "updated_at": now,
+    }));
+    Response::json(StatusCode::ACCEPTED, job)
+}
+
+fn bad_request() -> Response {
+    Response::json(StatusCode::BAD_REQUEST, json!({ "message": "unable to parse body" }))
+}
@@ -0,0 +1,226 @@
+use chrono::Utc;
+use hyper::{Method, StatusCode};
+use serde_json::{json, Value};
+
+use crate::server::{FakeServer, Request, Response};
+use crate::store::Store;
+
+/// The number of objects in each page of a list.
+const PAGE_SIZE: usize = 100;
+
+/// A fake of the Ramp developer API.
+///
+/// Any client id and secret get a token. Lists are paginated like the real
+/// API, with the URL of the next page in `page.next`.
+pub struct FakeRamp {
+    server: FakeServer,
+    users: Store,
+    departments: Store,
+    transactions: Store,
+    cards: Store,
+}
+
+impl FakeRamp {
+    pub fn start() -> Self {
+        let server = FakeServer::start();
+        let users = Store::new("id", "usr_");
+        let departments = Store::new("id", "dep_");
+        let transactions = Store::new("id", "txn_");
+        let cards = Store::new("id", "card_");
+        let receipts = Store::new("id", "rct_");
+
+        server.route(Method::POST, "/v1/public/customer/token", |req| {
+            if req.header("authorization").unwrap_or_default().is_empty() {
+                return Response::json(StatusCode::UNAUTHORIZED, json!({ "error": "invalid_client" }));
+            }
+            Response::ok(json!({ "access_token": "ramp_tok_fake", "token_type": "Bearer", "expires_in": 864000 }))
+        });
+
+        let u = users.clone();
+        server.route(Method::GET, "/developer/v1/users", move |req| page(req, u.all()));
+        let u = users.clone();
+        server.route(Method::GET, "/developer/v1/users/{id}", move |req| u.get(req.param("id")).map(Response::ok).unwrap_or_else(not_found));
+        let u = users.clone();
+        server.route(Method::POST, "/developer/v1/users/deferred", move |req| {
+            let mut user = req.json();
+            if u.find("email", user["email"].as_str().unwrap_or_default()).is_some() {
+                return Response::json(StatusCode::CONFLICT, json!({ "error_v2": { "message": "A user with this email already exists." } }));
+            }
+            user["id"] = json!("");
+            Response::created(u.insert(user))
+        });
+        let u = users.clone();
+        server.route(Method::PATCH, "/developer/v1/users/{id}", move |req| {
+            let mut changes = req.json();
+            if let Some(changes) = changes.as_object_mut() {
+                changes.remove("id");
+            }
+            u.update(req.param("id"), &changes).map(Response::ok).unwrap_or_else(not_found)
+        });
+        let u = users.clone();
+        server.route(Method::PATCH, "/developer/v1/users/{id}/deactivate", move |req| {
+            u.update(req.param("id"), &json!({ "status": "USER_SUSPENDED" }))
+                .map(|_| Response::empty(StatusCode::NO_CONTENT))
+                .unwrap_or_else(not_found)
+        });
+
+        let d = departments.clone();
+        server.route(Method::GET, "/developer/v1/departments", move |req| page(req, d.all()));
+
+        let t = transactions.clone();
+        server.route(Method::GET, "/developer/v1/transactions", move |req| page(req, t.all()));
+
+        let r = receipts;
+        server.route(Method::GET, "/developer/v1/receipts/{id}", move |req| {
+            r.get(req.param("id")).map(Response::ok).unwrap_or_else(not_found)
+        });
+
+        let c = cards.clone();
+        server.route(Method::GET, "/developer/v1/cards", move |req| {
+            let user_id = req.query("user_id").unwrap_or_default();
+            page(req, c.all().into_iter().filter(|card| user_id.is_empty() || card["cardholder_id"].as_str() == Some(user_id)).collect())
+        });
+        let (c, u) = (cards.clone(), users.clone());
+        server.route(Method::POST, "/developer/v1/cards/deferred/physical", move |req| {
+            let mut card = req.json();
+            let user = match u.get(card["user_id"].as_str().unwrap_or_default()) {
+                Some(user) => user,
+                None => return not_found(),
+            };
+            card["is_physical"] = json!(true);
+            card["cardholder_id"] = user["id"].clone();
+            card["cardholder_name"] = json!(format!("{} {}", user["first_name"].as_str().unwrap_or_default(), user["last_name"].as_str().unwrap_or_default()));
+            card["last_four"] = json!(format!("{:04}", c.all().len() + 1));
+            Response::created(c.insert(card))
+        });
+        let c = cards.clone();
+        server.route(Method::POST, "/developer/v1/cards/{id}/deferred/suspension", move |req| {
+            c.update(req.param("id"), &json!({ "state": "SUSPENDED" }))
+                .map(|card| Response::created(json!({ "id": card["id"], "status": "SUCCESS" })))
+                .unwrap_or_else(not_found)
+        });
+
+        FakeRamp {
+            server,
+            users,
+            departments,
+            transactions,
+            cards,
+        }
+    }
+
+    /// The URL to pass to `Ramp::new_with_base_url`.
+    pub fn base_url(&self) -> String {
+        self.server.base_url()
+    }
+
+    /// Every request the fake has received, oldest first.
+    pub fn requests(&self) -> Vec<Request> {
+        self.server.requests()
+    }
+
+    /// Add a department and return its id.
+    pub fn add_department(&self, name: &str) -> String {
+        let department = self.departments.insert(json!({ "name": name }));
+        department["id"].as_str().unwrap_or_default().to_string()
+    }
+
+    /// Add a card transaction by a user and return its id.
+    pub fn add_transaction(&self, user_id: &str, merchant_name: &str, amount: f64) -> String {
+        let user = self.users.get(user_id).unwrap_or_default();
+        let transaction = self.transactions.insert(json!({
+            "amount": amount,
+            "card_holder": {
+                "department_id": user["department_id"],
+                "first_name": user["first_name"],
+                "last_name": user["last_name"],
+            },
+            "merchant_name": merchant_name,
+            "receipts": [],
+            "state": "CLEARED",
+            "user_transaction_time": Utc::now().format("%Y-%m-%dT%H:%M:%S").to_string(),
+        }));
+        transaction["id"].as_str().unwrap_or_default().to_string()
+    }
+
+    /// The users in the business.
+    pub fn users(&self) -> Vec<Value> {
+        self.users.all()
+    }
+
+    /// The cards that were issued.
+    pub fn cards(&self) -> Vec<Value> {
+        self.cards.all()
+    }
+}
+
+/// A page of a list, starting at the object after the id in the `start`
+/// query parameter.
+fn page(req: &Request, all: Vec<Value>) -> Response {
+    let start = match req.query("start") {
+        Some(start) => all.iter().position(|i| i["id"].as_str() == Some(start)).map(|p| p + 1).unwrap_or(all.len()),
+        None => 0,
+    };
+    let data: Vec<Value> = all.iter().skip(start).take(PAGE_SIZE).cloned().collect();
+
+    let mut next = String::new();
+    if start + PAGE_SIZE < all.len() {
+        let host = req.header("host").unwrap_or_default();
+        next = format!("http://{}{}?start={}", host, req.path, data.last().unwrap()["id"].as_str().unwrap_or_default());
+    }
+
+    Response::ok(json!({ "data": data, "page": { "next": next } }))
+}
+
+fn not_found() -> Response {
+    Response::json(StatusCode::NOT_FOUND, json!({ "error_v2": { "message": "Not found." } }))
+}
+
+#[cfg(test)]
+mod tests {
+    use ramp_api::{Ramp, User};
+
+    use crate::ramp::FakeRamp;
+
+    #[tokio::test]
+    async fn test_fake_ramp() {
+        let fake = FakeRamp::start();
+        let ramp = Ramp::new_with_base_url("client", "secret", fake.base_url()).await;
+
+        let department = fake.add_department("Engineering");
+        for i in 0..150 {
+            let user = ramp
+                .invite_new_user(&User {
+                    email: format!("user{}@example.com", i),
+                    first_name: "User".to_string(),
+                    last_name: i.to_string(),
+                    department_id: department.to_string(),
+                    role: "BUSINESS_USER".to_string(),
+                    ..Default::default()
+                })
+                .await
+                .unwrap();
+            fake.add_transaction(&user.id, "Coffee", 4.5);
+        }
+
+        // Both lists are longer than a page.
+        assert_eq!(ramp.list_users().await.unwrap().len(), 150);
+        assert_eq!(ramp.get_transactions().await.unwrap().len(), 150);
+        assert_eq!(ramp.list_departments().await.unwrap()[0].name, "Engineering");
+
+        let user = &fake.users()[0];
+        let id = user["id"].as_str().unwrap();
+        let updated = ramp
+            .update_user(
+                id,
+                &User {
+                    role: "BUSINESS_ADMIN".to_string(),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(updated.role, "BUSINESS_ADMIN");
+        assert_eq!(updated.email, "user0@example.com");
+    }
+}
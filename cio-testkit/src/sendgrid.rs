@@ -0,0 +1,110 @@
+use hyper::{Method, StatusCode};
+use serde_json::Value;
+
+use crate::server::{FakeServer, Request, Response};
+use crate::store::Store;
+
+/// An email that was sent through the fake.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SentMail {
+    pub from: String,
+    pub to: Vec<String>,
+    pub cc: Vec<String>,
+    pub bcc: Vec<String>,
+    pub subject: String,
+    /// The value of the first content of the message.
+    pub content: String,
+}
+
+/// A fake of the SendGrid mail send API that keeps every message it is sent.
+pub struct FakeSendGrid {
+    server: FakeServer,
+    messages: Store,
+}
+
+impl FakeSendGrid {
+    pub fn start() -> Self {
+        let server = FakeServer::start();
+        let messages = Store::new("id", "msg_");
+
+        let m = messages.clone();
+        server.route(Method::POST, "/v3/mail/send", move |req| {
+            let message = req.json();
+            if message["personalizations"].as_array().map(|p| p.is_empty()).unwrap_or(true) {
+                return Response::json(
+                    StatusCode::BAD_REQUEST,
+                    serde_json::json!({ "errors": [{ "message": "The personalizations field is required.", "field": "personalizations" }] }),
+                );
+            }
+
+            m.insert(message);
+            Response::empty(StatusCode::ACCEPTED)
+        });
+
+        FakeSendGrid { server, messages }
+    }
+
+    /// The URL to pass to `SendGrid::with_base_url`.
+    pub fn base_url(&self) -> String {
+        self.server.base_url()
+    }
+
+    /// Every request the fake has received, oldest first.
+    pub fn requests(&self) -> Vec<Request> {
+        self.server.requests()
+    }
+
+    /// The messages that were sent, oldest first.
+    pub fn sent(&self) -> Vec<SentMail> {
+        self.messages
+            .all()
+            .iter()
+            .map(|m| {
+                let personalization = &m["personalizations"][0];
+                SentMail {
+                    from: m["from"]["email"].as_str().unwrap_or_default().to_string(),
+                    to: emails(&personalization["to"]),
+                    cc: emails(&personalization["cc"]),
+                    bcc: emails(&personalization["bcc"]),
+                    subject: m["subject"].as_str().unwrap_or_default().to_string(),
+                    content: m["content"][0]["value"].as_str().unwrap_or_default().to_string(),
+                }
+            })
+            .collect()
+    }
+}
+
+fn emails(v: &Value) -> Vec<String> {
+    v.as_array().map(|a| a.iter().filter_map(|e| e["email"].as_str()).map(|e| e.to_string()).collect()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use sendgrid_api::SendGrid;
+
+    use crate::sendgrid::FakeSendGrid;
+
+    #[tokio::test]
+    async fn test_fake_sendgrid() {
+        let fake = FakeSendGrid::start();
+        let sendgrid = SendGrid::new("key").with_base_url(fake.base_url());
+
+        sendgrid
+            .send_mail(
+                "Your order has been received!".to_string(),
+                "Thanks!".to_string(),
+                vec!["jane@example.com".to_string()],
+                vec![],
+                vec!["packages@example.com".to_string()],
+                "packages@example.com".to_string(),
+            )
+            .await;
+
+        let sent = fake.sent();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].to, vec!["jane@example.com".to_string()]);
+        assert_eq!(sent[0].bcc, vec!["packages@example.com".to_string()]);
+        assert_eq!(sent[0].subject, "Your order has been received!");
+        assert_eq!(sent[0].content, "Thanks!");
+    }
+}
@@ -0,0 +1,268 @@
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Server, StatusCode};
+use percent_encoding::percent_decode_str;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use tokio::sync::oneshot;
+
+type Handler = Arc<dyn Fn(&Request) -> Response + Send + Sync>;
+
+struct Route {
+    method: Method,
+    segments: Vec<String>,
+    handler: Handler,
+}
+
+#[derive(Default)]
+struct Routes {
+    routes: Vec<Route>,
+    requests: Vec<Request>,
+}
+
+/// An HTTP server on a random local port that answers requests with the
+/// handlers registered with `route`.
+///
+/// The server runs on the tokio runtime it was started on and shuts down when
+/// it is dropped.
+pub struct FakeServer {
+    addr: SocketAddr,
+    routes: Arc<Mutex<Routes>>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl FakeServer {
+    /// Start a server. This has to be called from within a tokio runtime.
+    pub fn start() -> Self {
+        let routes: Arc<Mutex<Routes>> = Default::default();
+
+        let r = routes.clone();
+        let make_service = make_service_fn(move |_| {
+            let r = r.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req| {
+                    let r = r.clone();
+                    async move { Ok::<_, Infallible>(handle(r, req).await) }
+                }))
+            }
+        });
+
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service);
+        let addr = server.local_addr();
+
+        let (tx, rx) = oneshot::channel::<()>();
+        tokio::spawn(server.with_graceful_shutdown(async {
+            rx.await.ok();
+        }));
+
+        FakeServer { addr, routes, shutdown: Some(tx) }
+    }
+
+    /// The URL of the server, pass this to the `with_base_url` function of
+    /// an API client.
+    pub fn base_url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Answer requests with the given method and path with the handler.
+    ///
+    /// Segments of the path like `{table}` match any one segment of the request
+    /// path, and the decoded value is available from `Request::param`. A
+    /// trailing slash is ignored. The first route that matches a request wins.
+    pub fn route<F>(&self, method: Method, path: &str, handler: F)
+    where
+        F: Fn(&Request) -> Response + Send + Sync + 'static,
+    {
+        self.routes.lock().unwrap().routes.push(Route {
+            method,
+            segments: split_path(path),
+            handler: Arc::new(handler),
+        });
+    }
+
+    /// Every request the server has received, oldest first.
+    pub fn requests(&self) -> Vec<Request> {
+        self.routes.lock().unwrap().requests.clone()
+    }
+}
+
+impl Drop for FakeServer {
+    fn drop(&mut self) {
+        if let Some(tx) = self.shutdown.take() {
+            tx.send(()).ok();
+        }
+    }
+}
+
+async fn handle(routes: Arc<Mutex<Routes>>, req: hyper::Request<Body>) -> hyper::Response<Body> {
+    let method = req.method().clone();
+    let path = req.uri().path().to_string();
+    let query = req.uri().query().map(|q| url::form_urlencoded::parse(q.as_bytes()).into_owned().collect()).unwrap_or_default();
+    let headers = req.headers().iter().map(|(k, v)| (k.as_str().to_string(), v.to_str().unwrap_or_default().to_string())).collect();
+    let body = hyper::body::to_bytes(req.into_body()).await.map(|b| b.to_vec()).unwrap_or_default();
+
+    let mut request = Request {
+        method,
+        path,
+        query,
+        headers,
+        body,
+        params: Default::default(),
+    };
+
+    let segments = split_path(&request.path);
+    let handler = {
+        let mut routes = routes.lock().unwrap();
+        routes.requests.push(request.clone());
+
+        routes.routes.iter().find_map(|route| {
+            if route.method != request.method {
+                return None;
+            }
+            match_path(&route.segments, &segments).map(|params| (route.handler.clone(), params))
+        })
+    };
+
+    // The lock is not held while the handler runs, so handlers can call back
+    // into the server, for example to look at earlier requests.
+    let response = match handler {
+        Some((handler, params)) => {
+            request.params = params;
+            handler(&request)
+        }
+        None => Response::json(StatusCode::NOT_FOUND, json!({ "error": format!("no fake route for {} {}", request.method, request.path) })),
+    };
+
+    let mut builder = hyper::Response::builder().status(response.status);
+    for (k, v) in &response.headers {
+        builder = builder.header(k.as_str(), v.as_str());
+    }
+    builder.body(Body::from(response.body)).unwrap()
+}
+
+fn split_path(path: &str) -> Vec<String> {
+    path.trim_matches('/').split('/').filter(|s| !s.is_empty()).map(|s| s.to_string()).collect()
+}
+
+fn match_path(pattern: &[String], segments: &[String]) -> Option<BTreeMap<String, String>> {
+    if pattern.len() != segments.len() {
+        return None;
+    }
+
+    let mut params: BTreeMap<String, String> = Default::default();
+    for (p, s) in pattern.iter().zip(segments) {
+        let value = percent_decode_str(s).decode_utf8_lossy().to_string();
+        if p.starts_with('{') && p.ends_with('}') {
+            params.insert(p.trim_start_matches('{').trim_end_matches('}').to_string(), value);
+        } else if *p != value {
+            return None;
+        }
+    }
+
+    Some(params)
+}
+
+/// A request received by a `FakeServer`.
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: Method,
+    pub path: String,
+    pub query: Vec<(String, String)>,
+    /// The headers, with lowercase names.
+    pub headers: BTreeMap<String, String>,
+    pub body: Vec<u8>,
+    params: BTreeMap<String, String>,
+}
+
+impl Request {
+    /// The value of a `{name}` segment in the path of the route.
+    pub fn param(&self, name: &str) -> &str {
+        self.params.get(name).map(|s| s.as_str()).unwrap_or_default()
+    }
+
+    /// The first value of a query parameter.
+    pub fn query(&self, name: &str) -> Option<&str> {
+        self.query.iter().find(|(k, _)| k == name).map(|(_, v)| v.as_str())
+    }
+
+    /// Every value of a query parameter that is repeated, like `records[]`.
+    pub fn query_all(&self, name: &str) -> Vec<&str> {
+        self.query.iter().filter(|(k, _)| k == name).map(|(_, v)| v.as_str()).collect()
+    }
+
+    /// The value of a header.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(&name.to_lowercase()).map(|s| s.as_str())
+    }
+
+    /// The body as JSON, `null` if the body is empty or not JSON.
+    pub fn json(&self) -> Value {
+        serde_json::from_slice(&self.body).unwrap_or(Value::Null)
+    }
+
+    /// The body deserialized into a type.
+    pub fn json_as<T: DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
+        serde_json::from_slice(&self.body)
+    }
+
+    /// The body as a URL encoded form.
+    pub fn form(&self) -> BTreeMap<String, String> {
+        url::form_urlencoded::parse(&self.body).into_owned().collect()
+    }
+}
+
+/// A response from a route of a `FakeServer`.
+#[derive(Debug, Clone)]
+pub struct Response {
+    pub status: StatusCode,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    /// A JSON response with the given status.
+    pub fn json(status: StatusCode, body: Value) -> Self {
+        Response {
+            status,
+            headers: vec![("content-type".to_string(), "application/json".to_string())],
+            body: body.to_string().into_bytes(),
+        }
+    }
+
+    /// A `200 OK` JSON response.
+    pub fn ok(body: Value) -> Self {
+        Response::json(StatusCode::OK, body)
+    }
+
+    /// A `201 Created` JSON response.
+    pub fn created(body: Value) -> Self {
+        Response::json(StatusCode::CREATED, body)
+    }
+
+    /// A response without a body.
+    pub fn empty(status: StatusCode) -> Self {
+        Response {
+            status,
+            headers: Default::default(),
+            body: Default::default(),
+        }
+    }
+
+    /// A `404 Not Found` JSON response.
+    pub fn not_found() -> Self {
+        Response::json(StatusCode::NOT_FOUND, json!({ "error": "NOT_FOUND" }))
+    }
+
+    /// A response with a plain text body.
+    pub fn text(status: StatusCode, body: &str) -> Self {
+        Response {
+            status,
+            headers: vec![("content-type".to_string(), "text/plain".to_string())],
+            body: body.as_bytes().to_vec(),
+        }
+    }
+}
@@ -0,0 +1,456 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use hyper::{Method, StatusCode};
+use serde_json::{json, Value};
+
+use crate::server::{FakeServer, Request, Response};
+
+/// The name of the sheet a range without one refers to.
+const DEFAULT_SHEET: &str = "Sheet1";
+
+type Grids = BTreeMap<(String, String), Vec<Vec<String>>>;
+
+/// A fake of the Google Sheets API that keeps the values of each sheet in a
+/// grid in memory.
+///
+/// It supports getting, updating, appending and clearing the values in an A1
+/// range, like `Sheet1!A2:D`, `B3` or `'Swag Sheet'!A:A`, the batch versions
+/// of getting and updating, and the spreadsheet metadata.
+pub struct FakeSheets {
+    server: FakeServer,
+    grids: Arc<Mutex<Grids>>,
+}
+
+impl FakeSheets {
+    pub fn start() -> Self {
+        let server = FakeServer::start();
+        let grids: Arc<Mutex<Grids>> = Default::default();
+
+        let g = grids.clone();
+        server.route(Method::GET, "/v4/spreadsheets/{id}", move |req| {
+            let grids = g.lock().unwrap();
+            let sheets: Vec<Value> = grids
+                .iter()
+                .filter(|((id, _), _)| id == req.param("id"))
+                .enumerate()
+                .map(|(index, ((_, sheet), grid))| {
+                    // New sheets have 1000 rows and 26 columns, and grow as values are written.
+                    let columns = grid.iter().map(|r| r.len()).max().unwrap_or_default();
+                    json!({
+                        "properties": {
+                            "sheetId": index,
+                            "title": sheet,
+                            "index": index,
+                            "sheetType": "GRID",
+                            "gridProperties": { "rowCount": grid.len().max(1000), "columnCount": columns.max(26) },
+                        }
+                    })
+                })
+                .collect();
+            if sheets.is_empty() {
+                return Response::json(
+                    StatusCode::NOT_FOUND,
+                    json!({ "error": { "code": 404, "message": "Requested entity was not found.", "status": "NOT_FOUND" } }),
+                );
+            }
+
+            Response::ok(json!({ "spreadsheetId": req.param("id"), "properties": { "title": req.param("id"), "locale": "en_US" }, "sheets": sheets }))
+        });
+
+        let g = grids.clone();
+        server.route(Method::GET, "/v4/spreadsheets/{id}/values/{range}", move |req| match get(&g, req.param("id"), req.param("range")) {
+            Ok(body) => Response::ok(body),
+            Err(e) => invalid_argument(&e),
+        });
+
+        let g = grids.clone();
+        server.route(Method::GET, "/v4/spreadsheets/{id}/values:batchGet", move |req| {
+            let mut value_ranges = Vec::new();
+            for range in req.query_all("ranges") {
+                match get(&g, req.param("id"), range) {
+                    Ok(body) => value_ranges.push(body),
+                    Err(e) => return invalid_argument(&e),
+                }
+            }
+            Response::ok(json!({ "spreadsheetId": req.param("id"), "valueRanges": value_ranges }))
+        });
+
+        let g = grids.clone();
+        server.route(Method::PUT, "/v4/spreadsheets/{id}/values/{range}", move |req| {
+            match update(&g, req.param("id"), req.param("range"), &req.json()["values"]) {
+                Ok(body) => Response::ok(body),
+                Err(e) => invalid_argument(&e),
+            }
+        });
+
+        let g = grids.clone();
+        server.route(Method::POST, "/v4/spreadsheets/{id}/values:batchUpdate", move |req| {
+            let body = req.json();
+            let mut responses = Vec::new();
+            let mut sheets = BTreeSet::new();
+            for data in body["data"].as_array().cloned().unwrap_or_default() {
+                let range = data["range"].as_str().unwrap_or_default();
+                match update(&g, req.param("id"), range, &data["values"]) {
+                    Ok(r) => responses.push(r),
+                    Err(e) => return invalid_argument(&e),
+                }
+                sheets.insert(A1Range::parse(range).map(|r| r.sheet).unwrap_or_default());
+            }
+
+            let total = |field: &str| responses.iter().map(|r| r[field].as_u64().unwrap_or_default()).sum::<u64>();
+            Response::ok(json!({
+                "spreadsheetId": req.param("id"),
+                "totalUpdatedRows": total("updatedRows"),
+                "totalUpdatedColumns": total("updatedColumns"),
+                "totalUpdatedCells": total("updatedCells"),
+                "totalUpdatedSheets": sheets.len(),
+                "responses": responses,
+            }))
+        });
+
+        // Appending and clearing are `{range}:append` and `{range}:clear`.
+        let g = grids.clone();
+        server.route(Method::POST, "/v4/spreadsheets/{id}/values/{range}", move |req| {
+            let (range, action) = match req.param("range").rsplit_once(':') {
+                Some((range, action)) if action == "append" || action == "clear" => (range, action),
+                _ => return Response::not_found(),
+            };
+            let range = match A1Range::parse(range) {
+                Ok(r) => r,
+                Err(e) => return invalid_argument(&e),
+            };
+
+            let mut grids = g.lock().unwrap();
+            let grid = grids.entry((req.param("id").to_string(), range.sheet.to_string())).or_default();
+            if action == "clear" {
+                range.clear(grid);
+                return Response::ok(json!({ "spreadsheetId": req.param("id"), "clearedRange": range.to_string() }));
+            }
+
+            let values: Vec<Vec<String>> = match serde_json::from_value(req.json()["values"].clone()) {
+                Ok(v) => v,
+                Err(e) => return invalid_argument(&e.to_string()),
+            };
+            let (table, written) = range.append(grid, &values);
+            Response::ok(json!({
+                "spreadsheetId": req.param("id"),
+                "tableRange": table.map(|t| t.to_string()),
+                "updates": {
+                    "spreadsheetId": req.param("id"),
+                    "updatedRange": written.to_string(),
+                    "updatedRows": values.len(),
+                    "updatedColumns": values.iter().map(|r| r.len()).max().unwrap_or_default(),
+                    "updatedCells": values.iter().map(|r| r.len()).sum::<usize>(),
+                },
+            }))
+        });
+
+        FakeSheets { server, grids }
+    }
+
+    /// The URL to pass to `Sheets::with_base_url`.
+    pub fn base_url(&self) -> String {
+        self.server.base_url()
+    }
+
+    /// Every request the fake has received, oldest first.
+    pub fn requests(&self) -> Vec<Request> {
+        self.server.requests()
+    }
+
+    /// Replace the values of a sheet, as if someone typed them in.
+    pub fn set_values(&self, spreadsheet_id: &str, sheet: &str, values: Vec<Vec<String>>) {
+        self.grids.lock().unwrap().insert((spreadsheet_id.to_string(), sheet.to_string()), values);
+    }
+
+    /// The values of a sheet.
+    pub fn values(&self, spreadsheet_id: &str, sheet: &str) -> Vec<Vec<String>> {
+        self.grids.lock().unwrap().get(&(spreadsheet_id.to_string(), sheet.to_string())).cloned().unwrap_or_default()
+    }
+}
+
+/// Get the values in a range of a sheet, as a `ValueRange`.
+fn get(grids: &Mutex<Grids>, id: &str, range: &str) -> Result<Value, String> {
+    let a1 = A1Range::parse(range)?;
+    let grids = grids.lock().unwrap();
+    let grid = grids.get(&(id.to_string(), a1.sheet.to_string())).cloned().unwrap_or_default();
+
+    let values = a1.read(&grid);
+    let mut body = json!({ "range": range, "majorDimension": "ROWS" });
+    if !values.is_empty() {
+        body["values"] = json!(values);
+    }
+    Ok(body)
+}
+
+/// Write values to a range of a sheet, and return the `UpdateValuesResponse`.
+fn update(grids: &Mutex<Grids>, id: &str, range: &str, values: &Value) -> Result<Value, String> {
+    let a1 = A1Range::parse(range)?;
+    let values: Vec<Vec<String>> = serde_json::from_value(values.clone()).map_err(|e| e.to_string())?;
+
+    let mut grids = grids.lock().unwrap();
+    let grid = grids.entry((id.to_string(), a1.sheet.to_string())).or_default();
+    a1.write(grid, &values);
+
+    Ok(json!({
+        "spreadsheetId": id,
+        "updatedRange": range,
+        "updatedRows": values.len(),
+        "updatedColumns": values.iter().map(|r| r.len()).max().unwrap_or_default(),
+        "updatedCells": values.iter().map(|r| r.len()).sum::<usize>(),
+    }))
+}
+
+fn invalid_argument(message: &str) -> Response {
+    Response::json(StatusCode::BAD_REQUEST, json!({ "error": { "code": 400, "message": message, "status": "INVALID_ARGUMENT" } }))
+}
+
+/// A range in A1 notation, with zero based rows and columns. The end of the
+/// range is inclusive and open when it is `None`.
+#[derive(Debug, Clone, PartialEq)]
+struct A1Range {
+    sheet: String,
+    start_row: usize,
+    start_column: usize,
+    end_row: Option<usize>,
+    end_column: Option<usize>,
+}
+
+impl A1Range {
+    fn parse(s: &str) -> Result<A1Range, String> {
+        let (sheet, cells) = match s.rsplit_once('!') {
+            Some((sheet, cells)) => (sheet.trim_matches('\'').to_string(), cells),
+            None if looks_like_cells(s) => (DEFAULT_SHEET.to_string(), s),
+            // A range that is just a sheet name is the whole sheet.
+            None => (s.trim_matches('\'').to_string(), ""),
+        };
+
+        if cells.is_empty() {
+            return Ok(A1Range {
+                sheet,
+                start_row: 0,
+                start_column: 0,
+                end_row: None,
+                end_column: None,
+            });
+        }
+
+        let (start, end) = cells.split_once(':').unwrap_or((cells, cells));
+        let (start_column, start_row) = parse_cell(start)?;
+        let (end_column, end_row) = parse_cell(end)?;
+
+        Ok(A1Range {
+            sheet,
+            start_row: start_row.unwrap_or(0),
+            start_column: start_column.unwrap_or(0),
+            end_row,
+            end_column,
+        })
+    }
+
+    fn read(&self, grid: &[Vec<String>]) -> Vec<Vec<String>> {
+        let end_row = self.end_row.map(|r| r + 1).unwrap_or_else(|| grid.len()).min(grid.len());
+        let mut rows: Vec<Vec<String>> = grid
+            .get(self.start_row..end_row.max(self.start_row))
+            .unwrap_or_default()
+            .iter()
+            .map(|row| {
+                let end_column = self.end_column.map(|c| c + 1).unwrap_or_else(|| row.len()).min(row.len());
+                let mut cells = row.get(self.start_column..end_column.max(self.start_column)).unwrap_or_default().to_vec();
+                // Trailing empty cells are left out, like the API does.
+                while cells.last().map(|c| c.is_empty()).unwrap_or(false) {
+                    cells.pop();
+                }
+                cells
+            })
+            .collect();
+
+        while rows.last().map(|r| r.is_empty()).unwrap_or(false) {
+            rows.pop();
+        }
+        rows
+    }
+
+    /// Whether a row has a value in the columns of the range.
+    fn has_values(&self, row: &[String]) -> bool {
+        let end_column = self.end_column.map(|c| c + 1).unwrap_or_else(|| row.len()).min(row.len());
+        row.get(self.start_column..end_column.max(self.start_column)).unwrap_or_default().iter().any(|c| !c.is_empty())
+    }
+
+    fn clear(&self, grid: &mut [Vec<String>]) {
+        let end_row = self.end_row.map(|r| r + 1).unwrap_or_else(|| grid.len()).min(grid.len());
+        for row in grid.iter_mut().take(end_row).skip(self.start_row) {
+            let end_column = self.end_column.map(|c| c + 1).unwrap_or_else(|| row.len()).min(row.len());
+            for cell in row.iter_mut().take(end_column).skip(self.start_column) {
+                cell.clear();
+            }
+        }
+    }
+
+    /// Insert the values as new rows after the table in the range, and return
+    /// the range of the table, if there was one, and the range written to.
+    /// The table is the first run of rows with values in the columns of the
+    /// range.
+    fn append(&self, grid: &mut Vec<Vec<String>>, values: &[Vec<String>]) -> (Option<A1Range>, A1Range) {
+        let end_row = self.end_row.map(|r| r + 1).unwrap_or_else(|| grid.len()).min(grid.len());
+        let first = (self.start_row..end_row).find(|r| self.has_values(&grid[*r]));
+        let table = first.map(|first| {
+            let last = (first..end_row).take_while(|r| self.has_values(&grid[*r])).last().unwrap_or(first);
+            A1Range {
+                start_row: first,
+                end_row: Some(last),
+                ..self.clone()
+            }
+        });
+
+        let at = table.as_ref().and_then(|t| t.end_row).map(|r| r + 1).unwrap_or(self.start_row);
+        if grid.len() < at {
+            grid.resize(at, Vec::new());
+        }
+        for i in 0..values.len() {
+            grid.insert(at + i, Vec::new());
+        }
+
+        let written = A1Range {
+            sheet: self.sheet.to_string(),
+            start_row: at,
+            start_column: self.start_column,
+            end_row: Some(at + values.len().max(1) - 1),
+            end_column: Some(self.start_column + values.iter().map(|r| r.len()).max().unwrap_or_default().max(1) - 1),
+        };
+        written.write(grid, values);
+        (table, written)
+    }
+
+    fn write(&self, grid: &mut Vec<Vec<String>>, values: &[Vec<String>]) {
+        for (i, row) in values.iter().enumerate() {
+            let r = self.start_row + i;
+            if grid.len() <= r {
+                grid.resize(r + 1, Vec::new());
+            }
+            for (j, value) in row.iter().enumerate() {
+                let c = self.start_column + j;
+                if grid[r].len() <= c {
+                    grid[r].resize(c + 1, String::new());
+                }
+                grid[r][c] = value.to_string();
+            }
+        }
+    }
+}
+
+impl fmt::Display for A1Range {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.sheet.chars().all(|c| c.is_ascii_alphanumeric()) {
+            write!(f, "{}!", self.sheet)?;
+        } else {
+            write!(f, "'{}'!", self.sheet)?;
+        }
+        write!(f, "{}{}", column_name(self.start_column), self.start_row + 1)?;
+
+        let end = format!(
+            "{}{}",
+            self.end_column.map(column_name).unwrap_or_default(),
+            self.end_row.map(|r| (r + 1).to_string()).unwrap_or_default()
+        );
+        if !end.is_empty() {
+            write!(f, ":{}", end)?;
+        }
+        Ok(())
+    }
+}
+
+/// The letters of a zero based column, like `AA` for 26.
+fn column_name(column: usize) -> String {
+    let mut name = String::new();
+    let mut n = column + 1;
+    while n > 0 {
+        name.insert(0, (b'A' + ((n - 1) % 26) as u8) as char);
+        n = (n - 1) / 26;
+    }
+    name
+}
+
+/// Whether a range without a sheet name is cells, like `B3` or `A:C`, rather
+/// than the name of a sheet.
+fn looks_like_cells(s: &str) -> bool {
+    let letters = s.chars().take_while(|c| c.is_ascii_alphabetic()).count();
+    s.contains(':') || (letters <= 3 && parse_cell(s).map(|(_, row)| row.is_some()).unwrap_or(false))
+}
+
+/// Parse a cell like `B3`, `B` or `3` into its zero based column and row.
+fn parse_cell(s: &str) -> Result<(Option<usize>, Option<usize>), String> {
+    let letters: String = s.chars().take_while(|c| c.is_ascii_alphabetic()).collect();
+    let digits = &s[letters.len()..];
+    if (letters.is_empty() && digits.is_empty()) || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!("Unable to parse range: {}", s));
+    }
+
+    let column = if letters.is_empty() {
+        None
+    } else {
+        Some(letters.to_uppercase().chars().fold(0, |acc, c| acc * 26 + (c as usize - 'A' as usize + 1)) - 1)
+    };
+    let row = match digits.parse::<usize>() {
+        Ok(0) => return Err(format!("Unable to parse range: {}", s)),
+        Ok(r) => Some(r - 1),
+        Err(_) => None,
+    };
+
+    Ok((column, row))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sheets::A1Range;
+
+    #[test]
+    fn test_a1_range() {
+        let range = A1Range::parse("'Swag Sheet'!B2:AA").unwrap();
+        assert_eq!(range.sheet, "Swag Sheet");
+        assert_eq!((range.start_column, range.start_row), (1, 1));
+        assert_eq!((range.end_column, range.end_row), (Some(26), None));
+
+        let range = A1Range::parse("C7").unwrap();
+        assert_eq!(range.sheet, "Sheet1");
+        assert_eq!((range.start_column, range.start_row, range.end_column, range.end_row), (2, 6, Some(2), Some(6)));
+
+        assert_eq!(A1Range::parse("Applicants").unwrap().end_row, None);
+
+        // A whole row, however wide the sheet is.
+        let range = A1Range::parse("Form Responses 1!1:1").unwrap();
+        assert_eq!(range.sheet, "Form Responses 1");
+        assert_eq!((range.start_column, range.start_row, range.end_column, range.end_row), (0, 0, None, Some(0)));
+        assert!(A1Range::parse("Sheet1!A0").is_err());
+    }
+
+    #[test]
+    fn test_read_and_write() {
+        let mut grid = Vec::new();
+        A1Range::parse("B2").unwrap().write(&mut grid, &[vec!["x".to_string(), "y".to_string()]]);
+        assert_eq!(grid, vec![vec![], vec!["".to_string(), "x".to_string(), "y".to_string()]]);
+
+        assert_eq!(A1Range::parse("A1:B2").unwrap().read(&grid), vec![vec![], vec!["".to_string(), "x".to_string()]]);
+        assert_eq!(A1Range::parse("C1:C").unwrap().read(&grid), vec![vec![], vec!["y".to_string()]]);
+        assert!(A1Range::parse("D5:E9").unwrap().read(&grid).is_empty());
+    }
+    #[test]
+    fn test_append_and_clear() {
+        let row = |cells: &[&str]| cells.iter().map(|c| c.to_string()).collect::<Vec<String>>();
+        let mut grid = vec![row(&["Name", "Email"]), row(&["Jane", "jane@example.com"]), vec![], row(&["notes"])];
+
+        let (table, written) = A1Range::parse("Sheet1!A:B").unwrap().append(&mut grid, &[row(&["John", "john@example.com"])]);
+        assert_eq!(table.unwrap().to_string(), "Sheet1!A1:B2");
+        assert_eq!(written.to_string(), "Sheet1!A3:B3");
+        assert_eq!(grid[2], row(&["John", "john@example.com"]));
+        // The rows below the table are moved down, not overwritten.
+        assert_eq!(grid[4], row(&["notes"]));
+
+        A1Range::parse("B2:B").unwrap().clear(&mut grid);
+        assert_eq!(grid[1], row(&["Jane", ""]));
+        assert_eq!(grid[4], row(&["notes"]));
+        assert_eq!(A1Range::parse("'Swag Sheet'!AA3:AB").unwrap().to_string(), "'Swag Sheet'!AA3:AB");
+    }
+}
@@ -0,0 +1,257 @@
+use std::sync::{Arc, Mutex};
+
+use chrono::Utc;
+use hyper::Method;
+use serde_json::{json, Value};
+
+use crate::server::{FakeServer, Request, Response};
+use crate::store::Store;
+
+/// A fake of the Shippo API.
+///
+/// Every shipment gets a cheap USPS rate marked `BESTVALUE` and `CHEAPEST`
+/// and a faster UPS rate, and every label bought for a rate succeeds. Tracking
+/// numbers start out `PRE_TRANSIT`, use `set_tracking_status` to move them
+/// along.
+pub struct FakeShippo {
+    server: FakeServer,
+    shipments: Store,
+    transactions: Store,
+    tracks: Store,
+}
+
+impl FakeShippo {
+    pub fn start() -> Self {
+        let server = FakeServer::start();
+        let shipments = Store::new("object_id", "shp_");
+        let rates = Store::new("object_id", "rate_");
+        let transactions = Store::new("object_id", "txn_");
+        let tracks = Store::new("tracking_number", "9400111");
+        let customs_items = Store::new("object_id", "ci_");
+        let pickups = Store::new("object_id", "pu_");
+        let tracking_numbers = Arc::new(Mutex::new(0usize));
+
+        let (s, r) = (shipments.clone(), rates.clone());
+        server.route(Method::POST, "/shipments", move |req| {
+            let now = Utc::now();
+            let mut shipment = req.json();
+            shipment["object_created"] = json!(now);
+            shipment["object_updated"] = json!(now);
+            shipment["shipment_date"] = json!(now);
+            shipment["status"] = json!("SUCCESS");
+            shipment["test"] = json!(true);
+            let shipment = s.insert(shipment);
+            let id = shipment["object_id"].as_str().unwrap_or_default().to_string();
+
+            let new_rates: Vec<Value> = vec![
+                json!({"provider": "USPS", "amount": "7.50", "amount_local": "7.50", "servicelevel": {"name": "Priority Mail", "token": "usps_priority"}, "estimated_days": 2, "attributes": ["BESTVALUE", "CHEAPEST"]}),
+                json!({"provider": "UPS", "amount": "24.10", "amount_local": "24.10", "servicelevel": {"name": "Next Day Air", "token": "ups_next_day_air"}, "estimated_days": 1, "attributes": ["FASTEST"]}),
+            ]
+            .into_iter()
+            .map(|mut rate| {
+                rate["object_created"] = json!(now);
+                rate["shipment"] = json!(id);
+                rate["currency"] = json!("USD");
+                rate["currency_local"] = json!("USD");
+                rate["test"] = json!(true);
+                r.insert(rate)
+            })
+            .collect();
+
+            Response::created(s.update(&id, &json!({ "rates": new_rates })).unwrap())
+        });
+        let s = shipments.clone();
+        server.route(Method::GET, "/shipments", move |_| Response::ok(page(s.all())));
+        let s = shipments.clone();
+        server.route(Method::GET, "/shipments/{id}", move |req| s.get(req.param("id")).map(Response::ok).unwrap_or_else(Response::not_found));
+
+        let (t, r, tr) = (transactions.clone(), rates, tracks.clone());
+        server.route(Method::POST, "/transactions", move |req| {
+            let body = req.json();
+            let rate = match r.get(body["rate"].as_str().unwrap_or_default()) {
+                Some(rate) => rate,
+                None => return Response::json(hyper::StatusCode::BAD_REQUEST, json!({ "rate": ["Rate not found."] })),
+            };
+
+            let mut n = tracking_numbers.lock().unwrap();
+            *n += 1;
+            let tracking_number = format!("9400111{:015}", *n);
+            let now = Utc::now();
+            tr.insert(tracking_status(&rate["provider"].as_str().unwrap_or_default().to_lowercase(), &tracking_number, "PRE_TRANSIT"));
+
+            Response::created(t.insert(json!({
+                "object_created": now,
+                "object_updated": now,
+                "object_state": "VALID",
+                "status": "SUCCESS",
+                "rate": rate["object_id"],
+                "metadata": body["metadata"].as_str().unwrap_or_default(),
+                "tracking_number": tracking_number,
+                "tracking_status": "UNKNOWN",
+                "tracking_url_provider": format!("https://tools.usps.com/go/TrackConfirmAction?tLabels={}", tracking_number),
+                "label_url": format!("https://shippo-delivery.s3.amazonaws.com/{}.pdf", tracking_number),
+                "eta": null,
+                "messages": [],
+                "test": true,
+            })))
+        });
+        let t = transactions.clone();
+        server.route(Method::GET, "/transactions", move |_| Response::ok(page(t.all())));
+        let t = transactions.clone();
+        server.route(Method::GET, "/transactions/{id}", move |req| {
+            t.get(req.param("id")).map(Response::ok).unwrap_or_else(Response::not_found)
+        });
+
+        let tr = tracks.clone();
+        server.route(Method::POST, "/tracks", move |req| {
+            let body = req.json();
+            let carrier = body["carrier"].as_str().unwrap_or_default();
+            let tracking_number = body["tracking_number"].as_str().unwrap_or_default();
+            let status = tr.get(tracking_number).unwrap_or_else(|| tr.insert(tracking_status(carrier, tracking_number, "UNKNOWN")));
+            Response::created(status)
+        });
+        let tr = tracks.clone();
+        server.route(Method::GET, "/tracks/{carrier}/{tracking_number}", move |req| {
+            tr.get(req.param("tracking_number")).map(Response::ok).unwrap_or_else(Response::not_found)
+        });
+
+        server.route(Method::POST, "/customs/items", move |req| {
+            let mut item = req.json();
+            item["object_state"] = json!("VALID");
+            item["object_created"] = json!(Utc::now());
+            Response::created(customs_items.insert(item))
+        });
+        server.route(Method::POST, "/pickups", move |req| {
+            let mut pickup = req.json();
+            pickup["object_created"] = json!(Utc::now());
+            pickup["status"] = json!("CONFIRMED");
+            pickup["confirmed_start_time"] = pickup["requested_start_time"].clone();
+            pickup["confirmed_end_time"] = pickup["requested_end_time"].clone();
+            Response::created(pickups.insert(pickup))
+        });
+        server.route(Method::GET, "/carrier_accounts", |_| {
+            Response::ok(page(vec![
+                json!({"object_id": "ca_usps", "carrier": "usps", "account_id": "shippo_usps_account", "active": true, "test": true}),
+                json!({"object_id": "ca_ups", "carrier": "ups", "account_id": "shippo_ups_account", "active": true, "test": true}),
+            ]))
+        });
+
+        FakeShippo {
+            server,
+            shipments,
+            transactions,
+            tracks,
+        }
+    }
+
+    /// The URL to pass to `Shippo::with_base_url`.
+    pub fn base_url(&self) -> String {
+        self.server.base_url()
+    }
+
+    /// Every request the fake has received, oldest first.
+    pub fn requests(&self) -> Vec<Request> {
+        self.server.requests()
+    }
+
+    /// The shipments that were created.
+    pub fn shipments(&self) -> Vec<Value> {
+        self.shipments.all()
+    }
+
+    /// The labels that were bought.
+    pub fn transactions(&self) -> Vec<Value> {
+        self.transactions.all()
+    }
+
+    /// Move a tracking number to a new status, like `TRANSIT` or `DELIVERED`,
+    /// and add the status to its history.
+    pub fn set_tracking_status(&self, tracking_number: &str, status: &str) {
+        if let Some(mut tracking) = self.tracks.get(tracking_number) {
+            let new_status = json!({ "status": status, "status_details": format!("Your shipment is {}.", status.to_lowercase()), "status_date": Utc::now() });
+            if let Some(history) = tracking["tracking_history"].as_array_mut() {
+                history.push(new_status.clone());
+            }
+            tracking["tracking_status"] = new_status;
+            self.tracks.update(tracking_number, &tracking);
+        }
+    }
+}
+
+fn page(results: Vec<Value>) -> Value {
+    json!({ "next": null, "previous": null, "results": results })
+}
+
+fn tracking_status(carrier: &str, tracking_number: &str, status: &str) -> Value {
+    let status = json!({ "status": status, "status_details": "", "status_date": Utc::now() });
+    json!({
+        "carrier": carrier,
+        "tracking_number": tracking_number,
+        "eta": null,
+        "original_eta": null,
+        "tracking_status": status,
+        "tracking_history": [status],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use shippo::{Address, NewShipment, NewTransaction, Parcel, Shippo};
+
+    use crate::shippo::FakeShippo;
+
+    #[tokio::test]
+    async fn test_fake_shippo() {
+        let fake = FakeShippo::start();
+        let shippo = Shippo::new("token").with_base_url(fake.base_url());
+
+        let address = Address {
+            name: "Jane Doe".to_string(),
+            street1: "1 Infinite Loop".to_string(),
+            city: "Cupertino".to_string(),
+            state: "CA".to_string(),
+            zip: "95014".to_string(),
+            country: "US".to_string(),
+            ..Default::default()
+        };
+        let shipment = shippo
+            .create_shipment(NewShipment {
+                address_from: address.clone(),
+                address_to: address,
+                parcels: vec![Parcel {
+                    length: "12".to_string(),
+                    width: "12".to_string(),
+                    height: "6".to_string(),
+                    distance_unit: "in".to_string(),
+                    weight: "1".to_string(),
+                    mass_unit: "lb".to_string(),
+                    ..Default::default()
+                }],
+                customs_declaration: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(shipment.rates.len(), 2);
+
+        let rate = shipment.rates.iter().find(|r| r.attributes.contains(&"CHEAPEST".to_string())).unwrap();
+        let label = shippo
+            .create_shipping_label_from_rate(NewTransaction {
+                rate: rate.object_id.to_string(),
+                r#async: false,
+                label_file_type: "".to_string(),
+                metadata: "".to_string(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(label.status, "SUCCESS");
+        assert_eq!(shippo.get_shipping_label(&label.object_id).await.unwrap().tracking_number, label.tracking_number);
+
+        let status = shippo.register_tracking_webhook("usps", &label.tracking_number).await.unwrap();
+        assert_eq!(status.tracking_status.unwrap().status, "PRE_TRANSIT");
+
+        fake.set_tracking_status(&label.tracking_number, "TRANSIT");
+        let status = shippo.get_tracking_status("usps", &label.tracking_number).await.unwrap();
+        assert_eq!(status.tracking_status.unwrap().status, "TRANSIT");
+        assert_eq!(status.tracking_history.len(), 2);
+    }
+}
@@ -0,0 +1,206 @@
+use hyper::Method;
+use serde_json::{json, Value};
+
+use crate::server::{FakeServer, Request, Response};
+use crate::store::Store;
+
+/// A fake of the Slack Web API, and of the incoming webhooks messages are
+/// posted to channels with.
+///
+/// Like the real API, errors reply `200 OK` with `ok: false`. Messages posted
+/// to `webhook_url` are kept by channel.
+pub struct FakeSlack {
+    server: FakeServer,
+    users: Store,
+    invites: Store,
+    messages: Store,
+}
+
+impl FakeSlack {
+    pub fn start() -> Self {
+        let server = FakeServer::start();
+        let users = Store::new("id", "U");
+        let invites = Store::new("email", "");
+        let messages = Store::new("id", "msg");
+
+        let u = users.clone();
+        server.route(Method::GET, "/api/users.list", move |req| {
+            // Like the real API, pages are `limit` members long and the cursor is
+            // opaque, here it is the offset of the next page.
+            let limit = req.query("limit").and_then(|l| l.parse::<usize>().ok()).unwrap_or(100);
+            let offset = req.query("cursor").and_then(|c| c.parse::<usize>().ok()).unwrap_or(0);
+            let users = u.all();
+            let members: Vec<Value> = users.iter().skip(offset).take(limit).cloned().collect();
+            let next_cursor = if offset + limit < users.len() { (offset + limit).to_string() } else { "".to_string() };
+            Response::ok(json!({ "ok": true, "members": members, "response_metadata": { "next_cursor": next_cursor } }))
+        });
+        let u = users.clone();
+        server.route(Method::GET, "/api/team.billableInfo", move |_| {
+            let billable_info: serde_json::Map<String, Value> = u
+                .all()
+                .iter()
+                .filter(|user| !user["is_bot"].as_bool().unwrap_or(false))
+                .map(|user| {
+                    (
+                        user["id"].as_str().unwrap_or_default().to_string(),
+                        json!({ "billing_active": !user["deleted"].as_bool().unwrap_or(false) }),
+                    )
+                })
+                .collect();
+            Response::ok(json!({ "ok": true, "billable_info": billable_info }))
+        });
+
+        let (u, i) = (users.clone(), invites.clone());
+        server.route(Method::POST, "/api/admin.users.invite", move |req| {
+            let invite = req.json();
+            let email = invite["email"].as_str().unwrap_or_default();
+            if u.find("email", email).is_some() {
+                return slack_error("already_in_team");
+            }
+            if i.get(email).is_some() && !invite["resend"].as_bool().unwrap_or(false) {
+                return slack_error("already_invited");
+            }
+            i.remove(email);
+            i.insert(invite);
+            Response::ok(json!({ "ok": true }))
+        });
+        let u = users.clone();
+        server.route(Method::POST, "/api/admin.users.remove", move |req| {
+            match u.update(req.json()["user_id"].as_str().unwrap_or_default(), &json!({ "deleted": true })) {
+                Some(_) => Response::ok(json!({ "ok": true })),
+                None => slack_error("user_not_found"),
+            }
+        });
+        let u = users.clone();
+        server.route(Method::POST, "/api/users.profile.set", move |req| {
+            let body = req.json();
+            let user = match u.get(body["user"].as_str().unwrap_or_default()) {
+                Some(user) => user,
+                None => return slack_error("user_not_found"),
+            };
+
+            let mut profile = user["profile"].clone();
+            if !profile.is_object() {
+                profile = json!({});
+            }
+            if let (Some(profile), Some(changes)) = (profile.as_object_mut(), body["profile"].as_object()) {
+                for (k, v) in changes {
+                    profile.insert(k.to_string(), v.clone());
+                }
+            }
+            let profile = u.update(user["id"].as_str().unwrap_or_default(), &json!({ "profile": profile })).unwrap()["profile"].clone();
+            Response::ok(json!({ "ok": true, "profile": profile }))
+        });
+
+        let m = messages.clone();
+        server.route(Method::POST, "/webhooks/{channel}", move |req| {
+            m.insert(json!({ "channel": req.param("channel"), "message": req.json() }));
+            Response::text(hyper::StatusCode::OK, "ok")
+        });
+
+        FakeSlack { server, users, invites, messages }
+    }
+
+    /// The URL to pass to `Slack::with_base_url`.
+    pub fn base_url(&self) -> String {
+        self.server.base_url()
+    }
+
+    /// Every request the fake has received, oldest first.
+    pub fn requests(&self) -> Vec<Request> {
+        self.server.requests()
+    }
+
+    /// The incoming webhook URL for a channel, like the ones in the
+    /// `SLACK_*_CHANNEL_POST_URL` environment variables.
+    pub fn webhook_url(&self, channel: &str) -> String {
+        format!("{}/webhooks/{}", self.server.base_url(), channel)
+    }
+
+    /// Add a member to the workspace and return their id. Like in the real
+    /// API, the email is only in their profile.
+    pub fn add_user(&self, name: &str, email: &str) -> String {
+        let user = self.users.insert(json!({
+            "team_id": "T000001",
+            "name": name,
+            "real_name": name,
+            "profile": { "email": email, "display_name": name },
+        }));
+        user["id"].as_str().unwrap_or_default().to_string()
+    }
+
+    /// The members of the workspace, including the ones that were removed.
+    pub fn users(&self) -> Vec<Value> {
+        self.users.all()
+    }
+
+    /// The invites that were sent.
+    pub fn invites(&self) -> Vec<Value> {
+        self.invites.all()
+    }
+
+    /// The messages that were posted to a channel, oldest first.
+    pub fn messages(&self, channel: &str) -> Vec<Value> {
+        self.messages
+            .all()
+            .into_iter()
+            .filter(|m| m["channel"].as_str() == Some(channel))
+            .map(|m| m["message"].clone())
+            .collect()
+    }
+}
+
+fn slack_error(error: &str) -> Response {
+    Response::ok(json!({ "ok": false, "error": error }))
+}
+
+#[cfg(test)]
+mod tests {
+    use slack_chat_api::{Slack, UserInvite, UserProfile};
+
+    use crate::slack::FakeSlack;
+
+    #[tokio::test]
+    async fn test_fake_slack() {
+        let fake = FakeSlack::start();
+        let slack = Slack::new("token", "T000001").with_base_url(fake.base_url());
+
+        let id = fake.add_user("jane", "jane@example.com");
+        fake.add_user("john", "john@example.com");
+        assert_eq!(slack.list_users().await.unwrap().len(), 2);
+
+        // More members than fit on a page are all listed.
+        for i in 0..250 {
+            fake.add_user(&format!("user{}", i), &format!("user{}@example.com", i));
+        }
+        let users = slack.list_users().await.unwrap();
+        assert_eq!(users.len(), 252);
+        assert_eq!(users[251].profile.email, "user249@example.com");
+        assert!(slack.billable_info().await.unwrap()[&id].billing_active);
+
+        slack
+            .invite_user(UserInvite {
+                email: "joe@example.com".to_string(),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(fake.invites().len(), 1);
+
+        slack
+            .update_user_profile(
+                &id,
+                UserProfile {
+                    display_name: "Jane D".to_string(),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(fake.users()[0]["profile"]["display_name"], "Jane D");
+        assert_eq!(fake.users()[0]["profile"]["email"], "jane@example.com");
+
+        slack.remove_user(&id).await.unwrap();
+        assert!(!slack.billable_info().await.unwrap()[&id].billing_active);
+    }
+}
@@ -0,0 +1,79 @@
+use std::sync::{Arc, Mutex};
+
+use serde_json::Value;
+
+/// An in-memory collection of JSON objects, used by the fakes to keep the
+/// things that were created through them.
+///
+/// Objects are identified by the string in their `id_field`. Objects inserted
+/// without one get an id made from the prefix and a counter.
+#[derive(Clone)]
+pub struct Store {
+    id_field: &'static str,
+    prefix: &'static str,
+    items: Arc<Mutex<Vec<Value>>>,
+    next_id: Arc<Mutex<usize>>,
+}
+
+impl Store {
+    pub fn new(id_field: &'static str, prefix: &'static str) -> Self {
+        Store {
+            id_field,
+            prefix,
+            items: Default::default(),
+            next_id: Arc::new(Mutex::new(1)),
+        }
+    }
+
+    /// Add an object and return it with its id.
+    pub fn insert(&self, mut item: Value) -> Value {
+        if id_of(&item, self.id_field).is_empty() {
+            let mut next_id = self.next_id.lock().unwrap();
+            item[self.id_field] = Value::String(format!("{}{:06}", self.prefix, next_id));
+            *next_id += 1;
+        }
+
+        self.items.lock().unwrap().push(item.clone());
+        item
+    }
+
+    /// Get an object by its id.
+    pub fn get(&self, id: &str) -> Option<Value> {
+        self.items.lock().unwrap().iter().find(|i| id_of(i, self.id_field) == id).cloned()
+    }
+
+    /// Get the first object with a string field that has the given value.
+    pub fn find(&self, field: &str, value: &str) -> Option<Value> {
+        self.items.lock().unwrap().iter().find(|i| i[field].as_str() == Some(value)).cloned()
+    }
+
+    /// Every object, in the order they were inserted.
+    pub fn all(&self) -> Vec<Value> {
+        self.items.lock().unwrap().clone()
+    }
+
+    /// Set the fields of an object that are in `changes`, and return the
+    /// updated object.
+    pub fn update(&self, id: &str, changes: &Value) -> Option<Value> {
+        let mut items = self.items.lock().unwrap();
+        let item = items.iter_mut().find(|i| id_of(i, self.id_field) == id)?;
+        if let (Some(item), Some(changes)) = (item.as_object_mut(), changes.as_object()) {
+            for (k, v) in changes {
+                item.insert(k.to_string(), v.clone());
+            }
+        }
+
+        Some(item.clone())
+    }
+
+    /// Remove an object and return it.
+    pub fn remove(&self, id: &str) -> Option<Value> {
+        let mut items = self.items.lock().unwrap();
+        let index = items.iter().position(|i| id_of(i, self.id_field) == id)?;
+        Some(items.remove(index))
+    }
+}
+
+fn id_of<'a>(item: &'a Value, id_field: &str) -> &'a str {
+    item[id_field].as_str().unwrap_or_default()
+}
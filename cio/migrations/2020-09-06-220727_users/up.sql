@@ -38,7 +38,7 @@ CREATE TABLE users (
     work_address_formatted VARCHAR NOT NULL,
     start_date DATE NOT NULL,
     birthday DATE NOT NULL,
-    public_ssh_keys [] TEXT NOT NULL,
+    public_ssh_keys TEXT [] NOT NULL,
     typev VARCHAR NOT NULL,
     google_anniversary_event_id VARCHAR NOT NULL,
     geocode_cache VARCHAR NOT NULL,
@@ -0,0 +1,12 @@
+CREATE TABLE cron_job_runs (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR NOT NULL,
+    status VARCHAR NOT NULL,
+    error TEXT NOT NULL DEFAULT '',
+    hostname VARCHAR NOT NULL DEFAULT '',
+    started_at TIMESTAMPTZ NOT NULL,
+    finished_at TIMESTAMPTZ DEFAULT NULL,
+    duration_seconds INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE INDEX cron_job_runs_name_started_at_idx ON cron_job_runs (name, started_at DESC)
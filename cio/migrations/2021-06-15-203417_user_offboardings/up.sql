@@ -0,0 +1,20 @@
+CREATE TABLE user_offboardings (
+    id SERIAL PRIMARY KEY,
+    username VARCHAR NOT NULL,
+    email VARCHAR NOT NULL,
+    github VARCHAR NOT NULL,
+    manager VARCHAR NOT NULL,
+    google_anniversary_event_id VARCHAR NOT NULL,
+    started_at TIMESTAMPTZ NOT NULL,
+    completed_at TIMESTAMPTZ DEFAULT NULL
+);
+
+CREATE TABLE user_offboarding_steps (
+    id SERIAL PRIMARY KEY,
+    offboarding_id INTEGER NOT NULL REFERENCES user_offboardings (id) ON DELETE CASCADE,
+    step VARCHAR NOT NULL,
+    status VARCHAR NOT NULL,
+    message TEXT NOT NULL DEFAULT '',
+    updated_at TIMESTAMPTZ NOT NULL,
+    UNIQUE (offboarding_id, step)
+)
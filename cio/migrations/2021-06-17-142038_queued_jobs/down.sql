@@ -0,0 +1 @@
+DROP TABLE queued_jobs
\ No newline at end of file
@@ -0,0 +1 @@
+DROP TABLE airtable_sync_states
\ No newline at end of file
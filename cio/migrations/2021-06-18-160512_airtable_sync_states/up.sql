@@ -0,0 +1,9 @@
+CREATE TABLE airtable_sync_states (
+    id SERIAL PRIMARY KEY,
+    base_id VARCHAR NOT NULL,
+    table_name VARCHAR NOT NULL,
+    last_synced_at TIMESTAMPTZ NOT NULL,
+    last_full_sync_at TIMESTAMPTZ NOT NULL,
+    record_hashes JSONB NOT NULL DEFAULT '{}',
+    UNIQUE (base_id, table_name)
+);
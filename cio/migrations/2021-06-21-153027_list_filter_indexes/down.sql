@@ -0,0 +1,4 @@
+DROP INDEX users_department_idx;
+DROP INDEX rfds_state_idx;
+DROP INDEX applicants_role_idx;
+DROP INDEX applicants_status_idx
\ No newline at end of file
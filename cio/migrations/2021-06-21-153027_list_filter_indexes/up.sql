@@ -0,0 +1,4 @@
+CREATE INDEX applicants_status_idx ON applicants (status);
+CREATE INDEX applicants_role_idx ON applicants (role);
+CREATE INDEX rfds_state_idx ON rfds (state);
+CREATE INDEX users_department_idx ON users (department)
\ No newline at end of file
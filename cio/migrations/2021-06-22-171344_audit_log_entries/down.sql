@@ -0,0 +1 @@
+DROP TABLE audit_log_entries
\ No newline at end of file
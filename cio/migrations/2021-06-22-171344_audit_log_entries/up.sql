@@ -0,0 +1,11 @@
+CREATE TABLE audit_log_entries (
+    id SERIAL PRIMARY KEY,
+    table_name VARCHAR NOT NULL,
+    record_id INTEGER NOT NULL,
+    action VARCHAR NOT NULL,
+    changes JSONB NOT NULL DEFAULT '{}',
+    source VARCHAR NOT NULL DEFAULT '',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX audit_log_entries_table_name_record_id_idx ON audit_log_entries (table_name, record_id, created_at DESC)
\ No newline at end of file
@@ -0,0 +1,30 @@
+ALTER TABLE users DROP COLUMN airtable_last_synced_at;
+ALTER TABLE swag_items DROP COLUMN airtable_last_synced_at;
+ALTER TABLE swag_inventory_items DROP COLUMN airtable_last_synced_at;
+ALTER TABLE software_vendors DROP COLUMN airtable_last_synced_at;
+ALTER TABLE rfds DROP COLUMN airtable_last_synced_at;
+ALTER TABLE recorded_meetings DROP COLUMN airtable_last_synced_at;
+ALTER TABLE rack_line_subscribers DROP COLUMN airtable_last_synced_at;
+ALTER TABLE page_views DROP COLUMN airtable_last_synced_at;
+ALTER TABLE package_pickups DROP COLUMN airtable_last_synced_at;
+ALTER TABLE outbound_shipments DROP COLUMN airtable_last_synced_at;
+ALTER TABLE mailing_list_subscribers DROP COLUMN airtable_last_synced_at;
+ALTER TABLE links DROP COLUMN airtable_last_synced_at;
+ALTER TABLE journal_club_papers DROP COLUMN airtable_last_synced_at;
+ALTER TABLE journal_club_meetings DROP COLUMN airtable_last_synced_at;
+ALTER TABLE inbound_shipments DROP COLUMN airtable_last_synced_at;
+ALTER TABLE groups DROP COLUMN airtable_last_synced_at;
+ALTER TABLE github_repos DROP COLUMN airtable_last_synced_at;
+ALTER TABLE expensed_items DROP COLUMN airtable_last_synced_at;
+ALTER TABLE credit_card_transactions DROP COLUMN airtable_last_synced_at;
+ALTER TABLE conference_rooms DROP COLUMN airtable_last_synced_at;
+ALTER TABLE certificates DROP COLUMN airtable_last_synced_at;
+ALTER TABLE buildings DROP COLUMN airtable_last_synced_at;
+ALTER TABLE barcode_scans DROP COLUMN airtable_last_synced_at;
+ALTER TABLE auth_users DROP COLUMN airtable_last_synced_at;
+ALTER TABLE auth_user_logins DROP COLUMN airtable_last_synced_at;
+ALTER TABLE applicants DROP COLUMN airtable_last_synced_at;
+ALTER TABLE applicant_reviewers DROP COLUMN airtable_last_synced_at;
+ALTER TABLE applicant_interviews DROP COLUMN airtable_last_synced_at;
+ALTER TABLE api_tokens DROP COLUMN airtable_last_synced_at;
+ALTER TABLE accounts_payables DROP COLUMN airtable_last_synced_at
\ No newline at end of file
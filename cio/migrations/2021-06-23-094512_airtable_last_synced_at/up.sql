@@ -0,0 +1,30 @@
+ALTER TABLE accounts_payables ADD COLUMN airtable_last_synced_at TIMESTAMPTZ;
+ALTER TABLE api_tokens ADD COLUMN airtable_last_synced_at TIMESTAMPTZ;
+ALTER TABLE applicant_interviews ADD COLUMN airtable_last_synced_at TIMESTAMPTZ;
+ALTER TABLE applicant_reviewers ADD COLUMN airtable_last_synced_at TIMESTAMPTZ;
+ALTER TABLE applicants ADD COLUMN airtable_last_synced_at TIMESTAMPTZ;
+ALTER TABLE auth_user_logins ADD COLUMN airtable_last_synced_at TIMESTAMPTZ;
+ALTER TABLE auth_users ADD COLUMN airtable_last_synced_at TIMESTAMPTZ;
+ALTER TABLE barcode_scans ADD COLUMN airtable_last_synced_at TIMESTAMPTZ;
+ALTER TABLE buildings ADD COLUMN airtable_last_synced_at TIMESTAMPTZ;
+ALTER TABLE certificates ADD COLUMN airtable_last_synced_at TIMESTAMPTZ;
+ALTER TABLE conference_rooms ADD COLUMN airtable_last_synced_at TIMESTAMPTZ;
+ALTER TABLE credit_card_transactions ADD COLUMN airtable_last_synced_at TIMESTAMPTZ;
+ALTER TABLE expensed_items ADD COLUMN airtable_last_synced_at TIMESTAMPTZ;
+ALTER TABLE github_repos ADD COLUMN airtable_last_synced_at TIMESTAMPTZ;
+ALTER TABLE groups ADD COLUMN airtable_last_synced_at TIMESTAMPTZ;
+ALTER TABLE inbound_shipments ADD COLUMN airtable_last_synced_at TIMESTAMPTZ;
+ALTER TABLE journal_club_meetings ADD COLUMN airtable_last_synced_at TIMESTAMPTZ;
+ALTER TABLE journal_club_papers ADD COLUMN airtable_last_synced_at TIMESTAMPTZ;
+ALTER TABLE links ADD COLUMN airtable_last_synced_at TIMESTAMPTZ;
+ALTER TABLE mailing_list_subscribers ADD COLUMN airtable_last_synced_at TIMESTAMPTZ;
+ALTER TABLE outbound_shipments ADD COLUMN airtable_last_synced_at TIMESTAMPTZ;
+ALTER TABLE package_pickups ADD COLUMN airtable_last_synced_at TIMESTAMPTZ;
+ALTER TABLE page_views ADD COLUMN airtable_last_synced_at TIMESTAMPTZ;
+ALTER TABLE rack_line_subscribers ADD COLUMN airtable_last_synced_at TIMESTAMPTZ;
+ALTER TABLE recorded_meetings ADD COLUMN airtable_last_synced_at TIMESTAMPTZ;
+ALTER TABLE rfds ADD COLUMN airtable_last_synced_at TIMESTAMPTZ;
+ALTER TABLE software_vendors ADD COLUMN airtable_last_synced_at TIMESTAMPTZ;
+ALTER TABLE swag_inventory_items ADD COLUMN airtable_last_synced_at TIMESTAMPTZ;
+ALTER TABLE swag_items ADD COLUMN airtable_last_synced_at TIMESTAMPTZ;
+ALTER TABLE users ADD COLUMN airtable_last_synced_at TIMESTAMPTZ
\ No newline at end of file
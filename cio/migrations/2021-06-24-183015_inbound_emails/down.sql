@@ -0,0 +1,2 @@
+DROP TABLE inbound_email_attachments;
+DROP TABLE inbound_emails
\ No newline at end of file
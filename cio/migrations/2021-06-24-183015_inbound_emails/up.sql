@@ -0,0 +1,36 @@
+CREATE TABLE inbound_emails (
+    id SERIAL PRIMARY KEY,
+    message_id VARCHAR NOT NULL UNIQUE,
+    in_reply_to VARCHAR NOT NULL DEFAULT '',
+    from_email VARCHAR NOT NULL,
+    from_name VARCHAR NOT NULL DEFAULT '',
+    to_addresses TEXT [] NOT NULL,
+    cc_addresses TEXT [] NOT NULL,
+    subject VARCHAR NOT NULL DEFAULT '',
+    text_body TEXT NOT NULL DEFAULT '',
+    html_body TEXT NOT NULL DEFAULT '',
+    raw TEXT NOT NULL,
+    spam_score REAL NOT NULL DEFAULT 0,
+    route VARCHAR NOT NULL DEFAULT '',
+    applicant_id INTEGER DEFAULT NULL,
+    accounts_payable_id INTEGER DEFAULT NULL,
+    inbound_shipment_id INTEGER DEFAULT NULL,
+    received_at TIMESTAMPTZ NOT NULL,
+    routed_at TIMESTAMPTZ DEFAULT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX inbound_emails_from_email_idx ON inbound_emails (from_email);
+CREATE INDEX inbound_emails_applicant_id_idx ON inbound_emails (applicant_id);
+
+CREATE TABLE inbound_email_attachments (
+    id SERIAL PRIMARY KEY,
+    inbound_email_id INTEGER NOT NULL REFERENCES inbound_emails (id) ON DELETE CASCADE,
+    filename VARCHAR NOT NULL,
+    content_type VARCHAR NOT NULL,
+    size_bytes INTEGER NOT NULL,
+    drive_file_id VARCHAR NOT NULL,
+    drive_url VARCHAR NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (inbound_email_id, filename)
+);
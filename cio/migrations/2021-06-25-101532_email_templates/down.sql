@@ -0,0 +1 @@
+DROP TABLE email_templates
\ No newline at end of file
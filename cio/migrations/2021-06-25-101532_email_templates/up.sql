@@ -0,0 +1,10 @@
+CREATE TABLE email_templates (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR NOT NULL,
+    version INTEGER NOT NULL,
+    subject VARCHAR NOT NULL,
+    text_body TEXT NOT NULL,
+    html_body TEXT NOT NULL DEFAULT '',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (name, version)
+);
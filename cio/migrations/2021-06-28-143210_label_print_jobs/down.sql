@@ -0,0 +1,4 @@
+ALTER TABLE swag_inventory_items DROP COLUMN label_print_status;
+ALTER TABLE swag_inventory_items DROP COLUMN label_print_job_id;
+ALTER TABLE outbound_shipments DROP COLUMN label_print_status;
+ALTER TABLE outbound_shipments DROP COLUMN label_print_job_id
\ No newline at end of file
@@ -0,0 +1,4 @@
+ALTER TABLE outbound_shipments ADD COLUMN label_print_job_id VARCHAR NOT NULL DEFAULT '';
+ALTER TABLE outbound_shipments ADD COLUMN label_print_status VARCHAR NOT NULL DEFAULT '';
+ALTER TABLE swag_inventory_items ADD COLUMN label_print_job_id VARCHAR NOT NULL DEFAULT '';
+ALTER TABLE swag_inventory_items ADD COLUMN label_print_status VARCHAR NOT NULL DEFAULT ''
\ No newline at end of file
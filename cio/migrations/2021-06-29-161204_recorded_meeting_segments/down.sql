@@ -0,0 +1 @@
+DROP TABLE recorded_meeting_segments
\ No newline at end of file
@@ -0,0 +1,14 @@
+CREATE TABLE recorded_meeting_segments (
+    id SERIAL PRIMARY KEY,
+    recorded_meeting_id INTEGER NOT NULL REFERENCES recorded_meetings (id) ON DELETE CASCADE,
+    kind VARCHAR NOT NULL,
+    position INTEGER NOT NULL,
+    speaker VARCHAR NOT NULL DEFAULT '',
+    start_seconds DOUBLE PRECISION NOT NULL DEFAULT 0,
+    end_seconds DOUBLE PRECISION NOT NULL DEFAULT 0,
+    text TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (recorded_meeting_id, kind, position)
+);
+
+CREATE INDEX recorded_meeting_segments_text_idx ON recorded_meeting_segments USING GIN (to_tsvector('english', text));
\ No newline at end of file
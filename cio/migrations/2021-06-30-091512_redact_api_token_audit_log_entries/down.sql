@@ -0,0 +1,2 @@
+-- The redacted values are gone for good.
+SELECT 1
\ No newline at end of file
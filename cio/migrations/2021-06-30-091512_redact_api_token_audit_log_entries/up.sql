@@ -0,0 +1,4 @@
+-- Token values were recorded in the audit log before it redacted them.
+UPDATE audit_log_entries
+SET changes = changes - 'access_token' - 'refresh_token'
+WHERE table_name = 'api_tokens'
\ No newline at end of file
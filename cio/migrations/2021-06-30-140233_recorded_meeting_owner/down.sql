@@ -0,0 +1 @@
+ALTER TABLE recorded_meetings DROP COLUMN owner
\ No newline at end of file
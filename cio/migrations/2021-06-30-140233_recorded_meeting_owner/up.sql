@@ -0,0 +1 @@
+ALTER TABLE recorded_meetings ADD COLUMN owner VARCHAR NOT NULL DEFAULT ''
\ No newline at end of file
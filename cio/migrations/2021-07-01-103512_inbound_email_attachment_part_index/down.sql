@@ -0,0 +1,3 @@
+ALTER TABLE inbound_email_attachments DROP CONSTRAINT inbound_email_attachments_inbound_email_id_part_index_key;
+ALTER TABLE inbound_email_attachments ADD CONSTRAINT inbound_email_attachments_inbound_email_id_filename_key UNIQUE (inbound_email_id, filename);
+ALTER TABLE inbound_email_attachments DROP COLUMN part_index;
@@ -0,0 +1,9 @@
+ALTER TABLE inbound_email_attachments ADD COLUMN part_index INTEGER NOT NULL DEFAULT 0;
+
+-- Attachments were uploaded in the order they appear in the message.
+UPDATE inbound_email_attachments a SET part_index = n.part_index
+FROM (SELECT id, ROW_NUMBER() OVER (PARTITION BY inbound_email_id ORDER BY id) - 1 AS part_index FROM inbound_email_attachments) n
+WHERE a.id = n.id;
+
+ALTER TABLE inbound_email_attachments DROP CONSTRAINT inbound_email_attachments_inbound_email_id_filename_key;
+ALTER TABLE inbound_email_attachments ADD CONSTRAINT inbound_email_attachments_inbound_email_id_part_index_key UNIQUE (inbound_email_id, part_index);
@@ -0,0 +1 @@
+ALTER TABLE recorded_meetings DROP COLUMN transcript_failure
\ No newline at end of file
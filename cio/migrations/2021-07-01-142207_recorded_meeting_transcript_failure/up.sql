@@ -0,0 +1 @@
+ALTER TABLE recorded_meetings ADD COLUMN transcript_failure VARCHAR NOT NULL DEFAULT ''
\ No newline at end of file
@@ -1,3 +1,13 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Duration, Utc};
+use diesel::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::db::Database;
+use crate::schema::airtable_sync_states;
+
 pub static AIRTABLE_BASE_ID_CUSTOMER_LEADS: &str = "appr7imQLcR3pWaNa";
 pub static AIRTABLE_MAILING_LIST_SIGNUPS_TABLE: &str = "Mailing List Signups";
 pub static AIRTABLE_RACK_LINE_SIGNUPS_TABLE: &str = "Rack Line Signups";
@@ -51,3 +61,93 @@ pub static AIRTABLE_BASE_ID_CIO: &str = "appCgxubYilrPRLLZ";
 pub static AIRTABLE_API_TOKENS_TABLE: &str = "API Tokens";
 
 pub static AIRTABLE_GRID_VIEW: &str = "Grid view";
+
+/// How far back to look for modified records on top of the last sync, so records
+/// modified while the last sync was running are not missed.
+const SYNC_OVERLAP_SECONDS: i64 = 5 * 60;
+
+/// How often an incremental sync falls back to a full sync, to pick up records
+/// that were added to or deleted from Airtable by hand.
+const FULL_SYNC_INTERVAL_HOURS: i64 = 24;
+
+#[derive(Debug, Insertable, AsChangeset, PartialEq, Clone)]
+#[table_name = "airtable_sync_states"]
+pub struct NewAirtableSyncState {
+    pub base_id: String,
+    pub table_name: String,
+    pub last_synced_at: DateTime<Utc>,
+    pub last_full_sync_at: DateTime<Utc>,
+    pub record_hashes: serde_json::Value,
+}
+
+/// The watermark of the last sync of a table to Airtable, used by the
+/// `update_airtable_incremental` functions generated by `#[db]`.
+#[derive(Debug, Queryable, Identifiable, PartialEq, Clone, JsonSchema, Deserialize, Serialize)]
+#[table_name = "airtable_sync_states"]
+pub struct AirtableSyncState {
+    pub id: i32,
+    pub base_id: String,
+    pub table_name: String,
+    /// When the last sync started. Records modified in Airtable after this are
+    /// fetched by the next sync.
+    pub last_synced_at: DateTime<Utc>,
+    pub last_full_sync_at: DateTime<Utc>,
+    /// A hash of every database record as of the last sync, keyed by id, so we
+    /// only send the records that changed.
+    pub record_hashes: serde_json::Value,
+}
+
+impl AirtableSyncState {
+    /// Get the state of the last sync of a table, if it was ever synced.
+    pub fn get_from_db(db: &Database, base_id: &str, table_name: &str) -> Result<Option<Self>, crate::Error> {
+        Ok(airtable_sync_states::table
+            .filter(airtable_sync_states::dsl::base_id.eq(base_id))
+            .filter(airtable_sync_states::dsl::table_name.eq(table_name))
+            .first::<AirtableSyncState>(&db.conn())
+            .optional()?)
+    }
+
+    /// Save the state of a sync that started at `synced_at`.
+    pub fn save(db: &Database, base_id: &str, table_name: &str, synced_at: DateTime<Utc>, full: bool, record_hashes: &BTreeMap<i32, String>) -> Result<Self, crate::Error> {
+        let last_full_sync_at = match AirtableSyncState::get_from_db(db, base_id, table_name)? {
+            Some(state) if !full => state.last_full_sync_at,
+            _ => synced_at,
+        };
+
+        let state = NewAirtableSyncState {
+            base_id: base_id.to_string(),
+            table_name: table_name.to_string(),
+            last_synced_at: synced_at,
+            last_full_sync_at,
+            record_hashes: serde_json::to_value(record_hashes)?,
+        };
+
+        Ok(diesel::insert_into(airtable_sync_states::table)
+            .values(&state)
+            .on_conflict((airtable_sync_states::dsl::base_id, airtable_sync_states::dsl::table_name))
+            .do_update()
+            .set(&state)
+            .get_result(&db.conn())?)
+    }
+
+    /// Returns true if the next sync should list every record in the table.
+    pub fn needs_full_sync(&self, now: DateTime<Utc>) -> bool {
+        now - self.last_full_sync_at > Duration::hours(FULL_SYNC_INTERVAL_HOURS)
+    }
+
+    /// The time to list modified records from.
+    pub fn modified_since(&self) -> DateTime<Utc> {
+        self.last_synced_at - Duration::seconds(SYNC_OVERLAP_SECONDS)
+    }
+
+    /// The hashes of the records as of the last sync, keyed by id.
+    pub fn record_hashes(&self) -> BTreeMap<i32, String> {
+        serde_json::from_value(self.record_hashes.clone()).unwrap_or_default()
+    }
+}
+
+/// Hash a record so we can tell if it changed since the last sync.
+pub fn record_hash<T: Serialize>(record: &T) -> Result<String, crate::Error> {
+    let json = serde_json::to_vec(record)?;
+    Ok(base64::encode(openssl::sha::sha256(&json)))
+}
@@ -39,7 +39,13 @@ impl UpdateAirtableRecord<PageView> for PageView {
     async fn update_airtable_record(&mut self, _record: PageView) {
         // Get the current auth users in Airtable so we can link to it.
         // TODO: make this more dry so we do not call it every single damn time.
-        let auth_users = AuthUsers::get_from_airtable().await;
+        let auth_users = match AuthUsers::get_from_airtable().await {
+            Ok(v) => v,
+            Err(e) => {
+                println!("[airtable] getting auth users to link page view for `{}` failed: {}", self.user_email, e);
+                return;
+            }
+        };
 
         // Iterate over the auth_users and see if we find a match.
         for (_id, auth_user_record) in auth_users {
@@ -59,18 +65,3 @@ impl NewPageView {
         self.page_link = format!("https://{}/{}", self.domain, self.path.trim_start_matches('/'));
     }
 }
-
-#[cfg(test)]
-mod tests {
-    use crate::analytics::PageViews;
-    use crate::db::Database;
-
-    #[ignore]
-    #[tokio::test(flavor = "multi_thread")]
-    async fn test_cron_page_views_airtable() {
-        // Initialize our database.
-        let db = Database::new();
-
-        PageViews::get_from_db(&db).update_airtable().await;
-    }
-}
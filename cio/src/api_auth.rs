@@ -0,0 +1,341 @@
+use std::collections::{BTreeSet, HashMap};
+use std::env;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use reqwest::StatusCode;
+use serde::Deserialize;
+
+use crate::auth_logins::AuthUser;
+use crate::configs::User;
+use crate::db::Database;
+use crate::utils::{default_date, GSUITE_DOMAIN};
+
+/// Members of this group can see applicants and the personal details of users.
+pub const HR_GROUP: &str = "hr";
+/// Members of this group can see everything, including auth users and mailing
+/// list subscribers.
+pub const ADMIN_GROUP: &str = "cio-admins";
+
+/// How long we trust a token after Auth0 verified it, so we don't call Auth0 on
+/// every request.
+const VERIFIED_TOKEN_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// What a caller of the cio API is allowed to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    /// Anyone with an account in our directory.
+    Employee,
+    /// Members of `HR_GROUP`.
+    HR,
+    /// Members of `ADMIN_GROUP`. Admins have every role.
+    Admin,
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Role::Employee => write!(f, "employee"),
+            Role::HR => write!(f, "hr"),
+            Role::Admin => write!(f, "admin"),
+        }
+    }
+}
+
+/// Get the roles of a user from the groups they are a member of.
+pub fn roles_for_groups(groups: &[String]) -> BTreeSet<Role> {
+    let mut roles: BTreeSet<Role> = Default::default();
+    roles.insert(Role::Employee);
+
+    for group in groups {
+        match group.as_str() {
+            HR_GROUP => {
+                roles.insert(Role::HR);
+            }
+            ADMIN_GROUP => {
+                roles.insert(Role::Admin);
+            }
+            _ => (),
+        }
+    }
+
+    roles
+}
+
+/// A verified caller of the cio API.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Caller {
+    pub email: String,
+    pub username: String,
+    pub roles: BTreeSet<Role>,
+}
+
+impl Caller {
+    /// Returns true if the caller has the role, admins have every role.
+    pub fn has_role(&self, role: Role) -> bool {
+        self.roles.contains(&role) || self.roles.contains(&Role::Admin)
+    }
+}
+
+/// Why a request could not be authenticated.
+pub enum AuthError {
+    /// The request did not have an `Authorization: Bearer` header.
+    MissingToken,
+    /// Auth0 did not accept the token, or the user it belongs to is not one of
+    /// ours.
+    InvalidToken(String),
+    /// Looking up the user failed.
+    Internal(crate::Error),
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AuthError::MissingToken => write!(f, "missing bearer token"),
+            AuthError::InvalidToken(reason) => write!(f, "invalid token: {}", reason),
+            AuthError::Internal(e) => write!(f, "authenticating failed: {}", e),
+        }
+    }
+}
+
+impl From<crate::Error> for AuthError {
+    fn from(e: crate::Error) -> Self {
+        AuthError::Internal(e)
+    }
+}
+
+/// The response from the Auth0 OIDC userinfo endpoint.
+#[derive(Debug, Clone, Deserialize)]
+struct UserInfo {
+    sub: String,
+    #[serde(default)]
+    email: String,
+    #[serde(default)]
+    email_verified: bool,
+}
+
+/// Verifies bearer tokens issued by Auth0 and works out the roles of the caller.
+///
+/// A token is accepted if Auth0's userinfo endpoint accepts it, the Auth0 user is
+/// in the `auth_users` we sync from Auth0, and their verified email belongs to a
+/// user in our directory.
+pub struct Authenticator {
+    domain: String,
+    client: cio_http::Client,
+    verified: Mutex<HashMap<String, (Instant, Caller)>>,
+}
+
+impl Default for Authenticator {
+    fn default() -> Self {
+        Authenticator::new(env::var("CIO_AUTH0_DOMAIN").unwrap_or_else(|_| "oxide".to_string()))
+    }
+}
+
+impl Authenticator {
+    /// Create an authenticator for the Auth0 tenant `https://{domain}.auth0.com`.
+    pub fn new(domain: String) -> Self {
+        Authenticator {
+            domain,
+            client: cio_http::Client::new(reqwest::Client::new()),
+            verified: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Authenticate a request from the value of its `Authorization` header.
+    pub async fn authenticate(&self, db: &Database, authorization: Option<&str>) -> Result<Caller, AuthError> {
+        let token = match authorization.and_then(|h| h.strip_prefix("Bearer ")) {
+            Some(t) if !t.trim().is_empty() => t.trim(),
+            _ => return Err(AuthError::MissingToken),
+        };
+
+        // Key the cache by a hash so we don't keep tokens in memory.
+        let key = base64::encode(openssl::sha::sha256(token.as_bytes()));
+        if let Some((verified_at, caller)) = self.verified.lock().unwrap().get(&key) {
+            if verified_at.elapsed() < VERIFIED_TOKEN_TTL {
+                return Ok(caller.clone());
+            }
+        }
+
+        let info = self.get_user_info(token).await?;
+        let caller = get_caller(db, &info)?;
+
+        let mut verified = self.verified.lock().unwrap();
+        verified.retain(|_, (verified_at, _)| verified_at.elapsed() < VERIFIED_TOKEN_TTL);
+        verified.insert(key, (Instant::now(), caller.clone()));
+
+        Ok(caller)
+    }
+
+    async fn get_user_info(&self, token: &str) -> Result<UserInfo, AuthError> {
+        let resp = self
+            .client
+            .send(self.client.get(&format!("https://{}.auth0.com/userinfo", self.domain)).bearer_auth(token))
+            .await
+            .map_err(|e| AuthError::InvalidToken(format!("calling auth0 failed: {}", e)))?;
+
+        match resp.status() {
+            StatusCode::OK => (),
+            s => return Err(AuthError::InvalidToken(format!("auth0 returned {}", s))),
+        };
+
+        resp.json::<UserInfo>().await.map_err(|e| AuthError::InvalidToken(format!("decoding auth0 userinfo failed: {}", e)))
+    }
+}
+
+/// Match the Auth0 user to one of our auth users and to a user in our directory.
+fn get_caller(db: &Database, info: &UserInfo) -> Result<Caller, AuthError> {
+    if AuthUser::get_from_db(db, info.sub.to_string()).is_none() {
+        return Err(AuthError::InvalidToken(format!("auth0 user `{}` is not one of our auth users", info.sub)));
+    }
+
+    if !info.email_verified {
+        return Err(AuthError::InvalidToken(format!("email `{}` is not verified", info.email)));
+    }
+
+    let username = match info.email.strip_suffix(&format!("@{}", GSUITE_DOMAIN)) {
+        Some(username) => username,
+        None => return Err(AuthError::InvalidToken(format!("email `{}` is not in our domain", info.email))),
+    };
+
+    let user = match User::get_from_db(db, username.to_string()) {
+        Some(user) => user,
+        None => return Err(AuthError::InvalidToken(format!("`{}` is not in our directory", info.email))),
+    };
+
+    Ok(Caller {
+        email: info.email.to_string(),
+        username: user.username.to_string(),
+        roles: roles_for_groups(&user.groups),
+    })
+}
+
+impl User {
+    /// Remove the details only HR should see, for callers without the HR role.
+    pub fn redact_for(mut self, caller: &Caller) -> Self {
+        if caller.has_role(Role::HR) {
+            return self;
+        }
+
+        self.home_address_street_1 = String::new();
+        self.home_address_street_2 = String::new();
+        self.home_address_city = String::new();
+        self.home_address_state = String::new();
+        self.home_address_zipcode = String::new();
+        self.home_address_country = String::new();
+        self.home_address_country_code = String::new();
+        self.home_address_formatted = String::new();
+        self.home_address_latitude = 0.0;
+        self.home_address_longitude = 0.0;
+        self.birthday = default_date();
+        self.geocode_cache = String::new();
+        self.recovery_email = String::new();
+        self.recovery_phone = String::new();
+
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        api_auth::{roles_for_groups, Caller, Role, ADMIN_GROUP, HR_GROUP},
+        configs::User,
+    };
+
+    #[test]
+    fn test_roles_for_groups() {
+        let roles = roles_for_groups(&["eng".to_string(), "all".to_string()]);
+        assert_eq!(roles.into_iter().collect::<Vec<_>>(), vec![Role::Employee]);
+
+        let roles = roles_for_groups(&[HR_GROUP.to_string()]);
+        assert_eq!(roles.into_iter().collect::<Vec<_>>(), vec![Role::Employee, Role::HR]);
+
+        // Admins have every role.
+        let caller = Caller {
+            email: "root@oxidecomputer.com".to_string(),
+            username: "root".to_string(),
+            roles: roles_for_groups(&[ADMIN_GROUP.to_string()]),
+        };
+        assert!(caller.has_role(Role::HR));
+        assert!(caller.has_role(Role::Employee));
+    }
+
+    #[test]
+    fn test_redact_user_for_non_hr_caller() {
+        let user: User = serde_json::from_value(serde_json::json!({
+            "id": 1,
+            "first_name": "Jane",
+            "last_name": "Doe",
+            "username": "jane",
+            "aliases": ["jd"],
+            "recovery_email": "jane@personal.example",
+            "recovery_phone": "+1-555-555-0100",
+            "gender": "Female",
+            "chat": "jane",
+            "github": "janedoe",
+            "twitter": "janedoe",
+            "department": "Engineering",
+            "manager": "bob",
+            "link_to_manager": ["rec123"],
+            "groups": ["eng"],
+            "is_group_admin": true,
+            "building": "Oakland Office",
+            "link_to_building": ["rec456"],
+            "aws_role": "engineer",
+            "home_address_street_1": "1 Private Lane",
+            "home_address_street_2": "Apt 2",
+            "home_address_city": "Hiddenville",
+            "home_address_state": "CA",
+            "home_address_zipcode": "94000",
+            "home_address_country": "United States",
+            "home_address_country_code": "US",
+            "home_address_formatted": "1 Private Lane Apt 2, Hiddenville, CA 94000",
+            "home_address_latitude": 37.5,
+            "home_address_longitude": -122.5,
+            "work_address_street_1": "1 Office Way",
+            "work_address_street_2": "",
+            "work_address_city": "Oakland",
+            "work_address_state": "CA",
+            "work_address_zipcode": "94607",
+            "work_address_country": "United States",
+            "work_address_country_code": "US",
+            "work_address_formatted": "1 Office Way, Oakland, CA 94607",
+            "start_date": "2020-02-03",
+            "birthday": "1990-04-05",
+            "public_ssh_keys": ["ssh-ed25519 AAAA"],
+            "type": "full-time",
+            "google_anniversary_event_id": "event123",
+            "geocode_cache": "{\"street\":\"1 Private Lane\"}",
+            "airtable_record_id": "rec789"
+        }))
+        .unwrap();
+
+        let hr = Caller {
+            email: "hr@oxidecomputer.com".to_string(),
+            username: "hr".to_string(),
+            roles: roles_for_groups(&[HR_GROUP.to_string()]),
+        };
+        let employee = Caller {
+            email: "bob@oxidecomputer.com".to_string(),
+            username: "bob".to_string(),
+            roles: roles_for_groups(&["eng".to_string()]),
+        };
+
+        // HR sees everything.
+        assert_eq!(user.clone().redact_for(&hr), user);
+
+        let redacted = user.clone().redact_for(&employee);
+        let json = serde_json::to_string(&redacted).unwrap();
+        for private in &["Private Lane", "Hiddenville", "94000", "personal.example", "555-0100", "1990-04-05", "37.5", "-122.5"] {
+            assert!(!json.contains(private), "{} was not redacted: {}", private, json);
+        }
+
+        // Everything else is left alone.
+        assert_eq!(redacted.first_name, user.first_name);
+        assert_eq!(redacted.work_address_formatted, user.work_address_formatted);
+        assert_eq!(redacted.start_date, user.start_date);
+        assert_eq!(redacted.public_ssh_keys, user.public_ssh_keys);
+        assert_eq!(redacted.airtable_record_id, user.airtable_record_id);
+    }
+}
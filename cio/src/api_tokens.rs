@@ -1,13 +1,22 @@
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
+use docusign::DocuSign;
+use gusto_api::Gusto;
 use macros::db;
+use quickbooks::QuickBooks;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::airtable::{AIRTABLE_API_TOKENS_TABLE, AIRTABLE_BASE_ID_CIO};
 use crate::core::UpdateAirtableRecord;
+use crate::db::Database;
+use crate::encryption::{decrypt, encrypt, is_encrypted};
 use crate::schema::{api_tokens, api_tokens as a_p_i_tokens};
 
+/// How long before an access token expires we refresh it, so it does not expire
+/// while we are still using it.
+const REFRESH_BEFORE_EXPIRY_SECONDS: i64 = 5 * 60;
+
 #[db {
     new_struct_name = "APIToken",
     airtable_base_id = "AIRTABLE_BASE_ID_CIO",
@@ -30,11 +39,13 @@ pub struct NewAPIToken {
     pub user_email: String,
     #[serde(default, skip_serializing_if = "String::is_empty")]
     pub token_type: String,
+    /// Encrypted, use `set_tokens` and `decrypt` rather than setting it directly.
     #[serde(default, skip_serializing_if = "String::is_empty")]
     pub access_token: String,
     /// Seconds until the token expires.
     #[serde(default)]
     pub expires_in: i32,
+    /// Encrypted, use `set_tokens` and `decrypt` rather than setting it directly.
     #[serde(default, skip_serializing_if = "String::is_empty")]
     pub refresh_token: String,
     /// Seconds until the refresh token expires.
@@ -49,15 +60,276 @@ impl UpdateAirtableRecord<APIToken> for APIToken {
     async fn update_airtable_record(&mut self, _record: APIToken) {}
 }
 
+impl NewAPIToken {
+    /// Encrypt and set the access and refresh tokens.
+    pub fn set_tokens(&mut self, access_token: &str, refresh_token: &str) -> Result<(), crate::Error> {
+        self.access_token = encrypt(access_token)?;
+        self.refresh_token = encrypt(refresh_token)?;
+        Ok(())
+    }
+}
+
+impl APIToken {
+    /// Encrypt and set the access and refresh tokens.
+    pub fn set_tokens(&mut self, access_token: &str, refresh_token: &str) -> Result<(), crate::Error> {
+        self.access_token = encrypt(access_token)?;
+        self.refresh_token = encrypt(refresh_token)?;
+        Ok(())
+    }
+
+    /// Get the token with the access and refresh tokens decrypted.
+    pub fn decrypt(&self) -> Result<OAuthToken, crate::Error> {
+        Ok(OAuthToken {
+            company_id: self.company_id.to_string(),
+            token_type: self.token_type.to_string(),
+            access_token: decrypt(&self.access_token)?,
+            expires_in: self.expires_in as i64,
+            refresh_token: decrypt(&self.refresh_token)?,
+            refresh_token_expires_in: self.refresh_token_expires_in as i64,
+        })
+    }
+
+    /// Returns true if the access token has expired or is about to.
+    pub fn expires_soon(&self) -> bool {
+        self.access_token.is_empty() || self.last_updated_at + Duration::seconds(self.expires_in as i64 - REFRESH_BEFORE_EXPIRY_SECONDS) <= Utc::now()
+    }
+
+    /// Returns true if the tokens were saved before we started encrypting them.
+    pub fn is_plaintext(&self) -> bool {
+        (!self.access_token.is_empty() && !is_encrypted(&self.access_token)) || (!self.refresh_token.is_empty() && !is_encrypted(&self.refresh_token))
+    }
+}
+
+/// Encrypt any tokens that were saved before we started encrypting them.
+pub async fn encrypt_plaintext_tokens(db: &Database) -> Result<(), crate::Error> {
+    for mut token in APITokens::get_from_db(db)? {
+        if !token.is_plaintext() {
+            continue;
+        }
+
+        let t = token.decrypt()?;
+        token.set_tokens(&t.access_token, &t.refresh_token)?;
+        token.update(db).await?;
+        println!("encrypted the {} token", token.product);
+    }
+
+    Ok(())
+}
+
+/// An OAuth token with the access and refresh tokens decrypted.
+#[derive(Debug, Default, Clone)]
+pub struct OAuthToken {
+    pub company_id: String,
+    pub token_type: String,
+    pub access_token: String,
+    /// Seconds until the token expires.
+    pub expires_in: i64,
+    pub refresh_token: String,
+    /// Seconds until the refresh token expires.
+    pub refresh_token_expires_in: i64,
+}
+
+/// An API client that authenticates with an OAuth token we keep in the
+/// `api_tokens` table.
+#[async_trait]
+pub trait OAuthClient: Sized {
+    /// The product the token is saved under.
+    const PRODUCT: &'static str;
+
+    /// Create the client with a valid token.
+    async fn with_token(token: &OAuthToken) -> Self;
+
+    /// Get a new token to replace the given one, which is empty if we do not
+    /// have a token for the product yet.
+    async fn refresh(token: &OAuthToken) -> Result<OAuthToken, String>;
+}
+
+#[async_trait]
+impl OAuthClient for Gusto {
+    const PRODUCT: &'static str = "gusto";
+
+    async fn with_token(token: &OAuthToken) -> Self {
+        Gusto::new_from_env(&token.access_token, &token.refresh_token)
+    }
+
+    async fn refresh(token: &OAuthToken) -> Result<OAuthToken, String> {
+        if token.refresh_token.is_empty() {
+            return Err("there is no refresh token, authorize the app through the gusto consent URL first".to_string());
+        }
+
+        let mut g = Gusto::new_from_env(&token.access_token, &token.refresh_token);
+        let t = g.refresh_access_token().await.map_err(|e| e.to_string())?;
+
+        Ok(OAuthToken {
+            company_id: token.company_id.to_string(),
+            token_type: t.token_type,
+            access_token: t.access_token,
+            expires_in: t.expires_in,
+            refresh_token: t.refresh_token,
+            refresh_token_expires_in: t.x_refresh_token_expires_in,
+        })
+    }
+}
+
+#[async_trait]
+impl OAuthClient for QuickBooks {
+    const PRODUCT: &'static str = "quickbooks";
+
+    async fn with_token(token: &OAuthToken) -> Self {
+        QuickBooks::new_from_env(&token.company_id, &token.access_token, &token.refresh_token)
+    }
+
+    async fn refresh(token: &OAuthToken) -> Result<OAuthToken, String> {
+        if token.refresh_token.is_empty() {
+            return Err("there is no refresh token, authorize the app through the quickbooks consent URL first".to_string());
+        }
+
+        let mut qb = QuickBooks::new_from_env(&token.company_id, &token.access_token, &token.refresh_token);
+        let t = qb.refresh_access_token().await.map_err(|e| e.to_string())?;
+
+        Ok(OAuthToken {
+            company_id: token.company_id.to_string(),
+            token_type: t.token_type,
+            access_token: t.access_token,
+            expires_in: t.expires_in,
+            refresh_token: t.refresh_token,
+            refresh_token_expires_in: t.x_refresh_token_expires_in,
+        })
+    }
+}
+
+#[async_trait]
+impl OAuthClient for DocuSign {
+    const PRODUCT: &'static str = "docusign";
+
+    async fn with_token(token: &OAuthToken) -> Self {
+        DocuSign::new_from_env_with_token(&token.access_token).await
+    }
+
+    async fn refresh(token: &OAuthToken) -> Result<OAuthToken, String> {
+        // DocuSign tokens cannot be refreshed, we get a new one with the JWT grant.
+        let t = DocuSign::get_access_token_from_env().await;
+
+        Ok(OAuthToken {
+            token_type: t.token_type,
+            access_token: t.access_token,
+            expires_in: t.expires_in,
+            ..token.clone()
+        })
+    }
+}
+
+/// Hands out API clients with a valid OAuth token.
+///
+/// The token is refreshed before it expires and the new one is saved, so all
+/// the clients for a product share a single token instead of each refreshing
+/// it on their own.
+pub struct TokenProvider<'a> {
+    db: &'a Database,
+}
+
+impl<'a> TokenProvider<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        TokenProvider { db }
+    }
+
+    /// Create a client with a valid token.
+    pub async fn client<C: OAuthClient>(&self) -> Result<C, crate::Error> {
+        let token = self.token::<C>().await?;
+        Ok(C::with_token(&token).await)
+    }
+
+    /// Get a valid token for the client's product, refreshing and saving it
+    /// first if it expires soon.
+    pub async fn token<C: OAuthClient>(&self) -> Result<OAuthToken, crate::Error> {
+        let existing = APIToken::get_from_db(self.db, C::PRODUCT.to_string());
+        let current = match &existing {
+            Some(t) if !t.expires_soon() => return t.decrypt(),
+            Some(t) => t.decrypt()?,
+            None => Default::default(),
+        };
+
+        println!("refreshing the {} token", C::PRODUCT);
+        let refreshed = C::refresh(&current).await.map_err(|message| crate::Error::TokenRefresh {
+            product: C::PRODUCT.to_string(),
+            message,
+        })?;
+
+        let mut token = match existing {
+            Some(t) => NewAPIToken::from(t),
+            None => NewAPIToken {
+                product: C::PRODUCT.to_string(),
+                company_id: String::new(),
+                item_id: String::new(),
+                user_email: String::new(),
+                token_type: String::new(),
+                access_token: String::new(),
+                expires_in: 0,
+                refresh_token: String::new(),
+                refresh_token_expires_in: 0,
+                last_updated_at: Utc::now(),
+            },
+        };
+        token.company_id = refreshed.company_id.to_string();
+        token.token_type = refreshed.token_type.to_string();
+        token.expires_in = refreshed.expires_in as i32;
+        token.refresh_token_expires_in = refreshed.refresh_token_expires_in as i32;
+        token.last_updated_at = Utc::now();
+        token.set_tokens(&refreshed.access_token, &refreshed.refresh_token)?;
+        token.upsert(self.db).await?;
+
+        Ok(refreshed)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::api_tokens::APITokens;
+    use std::env;
+
+    use chrono::Utc;
+    use cio_testkit::lock_env;
+
+    use crate::api_tokens::NewAPIToken;
+    use crate::audit::AuditLogEntry;
     use crate::db::Database;
 
-    #[ignore]
-    #[tokio::test(flavor = "multi_thread")]
-    async fn test_cron_api_tokens() {
-        let db = Database::new();
-        APITokens::get_from_db(&db).update_airtable().await;
+    /// This needs `CIO_DATABASE_URL` to point at a scratch database.
+    #[test]
+    fn test_token_changes_are_redacted_in_audit_log() {
+        let _env = lock_env();
+        env::set_var("CIO_TOKEN_ENCRYPTION_KEY", base64::encode([7u8; 32]));
+        let db = Database::new().with_audit_source("test");
+
+        // A token saved before we started encrypting them.
+        let product = format!("test-{}", Utc::now().timestamp_nanos());
+        let mut token = NewAPIToken {
+            product: product.to_string(),
+            company_id: Default::default(),
+            item_id: Default::default(),
+            user_email: Default::default(),
+            token_type: "bearer".to_string(),
+            access_token: "plaintext-access-token".to_string(),
+            expires_in: 3600,
+            refresh_token: "plaintext-refresh-token".to_string(),
+            refresh_token_expires_in: 0,
+            last_updated_at: Utc::now(),
+        }
+        .upsert_in_db(&db)
+        .unwrap();
+
+        // Encrypt it, then refresh it.
+        token.set_tokens("plaintext-access-token", "plaintext-refresh-token").unwrap();
+        let mut token = token.update_in_db(&db).unwrap();
+        token.set_tokens("new-access-token", "new-refresh-token").unwrap();
+        let token = token.update_in_db(&db).unwrap();
+
+        let entries = AuditLogEntry::get_for_record(&db, "api_tokens", token.id).unwrap();
+        assert_eq!(entries.len(), 3);
+        for entry in entries {
+            let changes = entry.changes.to_string();
+            for secret in &["plaintext-access-token", "plaintext-refresh-token", "new-access-token", "new-refresh-token", "enc:"] {
+                assert!(!changes.contains(secret), "{} entry stored `{}`: {}", entry.action, secret, changes);
+            }
+        }
     }
 }
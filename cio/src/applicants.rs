@@ -26,15 +26,17 @@ use schemars::JsonSchema;
 use sendgrid_api::SendGrid;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use sheets::Sheets;
+use sheets::{CellRef, HeaderMap, Sheets, ValueRange};
 use slack_chat_api::{FormattedMessage, MessageBlock, MessageBlockText, MessageBlockType, MessageType};
 use tar::Archive;
 use walkdir::WalkDir;
 
 use crate::airtable::{AIRTABLE_APPLICATIONS_TABLE, AIRTABLE_BASE_ID_RECURITING_APPLICATIONS, AIRTABLE_REVIEWER_LEADERBOARD_TABLE};
+use crate::api_tokens::TokenProvider;
 use crate::configs::{User, Users};
-use crate::core::UpdateAirtableRecord;
+use crate::core::{UpdateAirtableRecord, Validate};
 use crate::db::Database;
+use crate::email_templates::EmailTemplates;
 use crate::interviews::ApplicantInterview;
 use crate::models::{get_value, truncate};
 use crate::schema::{applicant_interviews, applicant_reviewers, applicants, users};
@@ -61,6 +63,11 @@ static QUESTION_WHY_OXIDE: &str = r"W(?s:.*)y do you want to work for Oxide\?";
         "email" = "String",
         "sheet_id" = "String",
     },
+    filter_on = {
+        "role" = "String",
+        "status" = "String",
+    },
+    airtable_fields = ["interviews", "geocode_cache"],
 }]
 #[derive(Debug, Insertable, AsChangeset, PartialEq, Clone, JsonSchema, Deserialize, Serialize)]
 #[table_name = "applicants"]
@@ -326,128 +333,36 @@ impl NewApplicant {
     }
 
     /// Send an email to the applicant that we recieved their application.
-    pub async fn send_email_recieved_application_to_applicant(&self) {
-        // Initialize the SendGrid client.
-        let sendgrid_client = SendGrid::new_from_env();
-
-        // Send the message.
-        sendgrid_client
-            .send_mail(
-                format!("Oxide Computer Company {} Application Received for {}", self.role, self.name),
-                format!(
-                    "Dear {},
-
-Thank you for submitting your application materials! We really appreciate all
-the time and thought everyone puts into their application. We will be in touch
-within the next few weeks with more information. Just a heads up this could take
-up to 4-6 weeks.
-
-Sincerely,
-  The Oxide Team",
-                    self.name
-                ),
-                vec![self.email.to_string()],
-                vec![format!("careers@{}", DOMAIN)],
-                vec![],
-                format!("careers@{}", DOMAIN),
-            )
-            .await;
+    pub async fn send_email_recieved_application_to_applicant(&self, db: &Database) {
+        self.send_email_template(db, "applicant_application_received").await;
     }
 
     /// Send an email to the applicant that we love them but they are too junior.
-    pub async fn send_email_rejection_junior_but_we_love_you(&self) {
-        // Initialize the SendGrid client.
-        let sendgrid_client = SendGrid::new_from_env();
-
-        // Send the message.
-        sendgrid_client
-            .send_mail(
-                format!("Thank you for your application, {}", self.name),
-                format!(
-                    "Dear {},
-
-Thank you for your application to join Oxide Computer Company. At this point
-in time, we are focusing on hiring engineers with professional experience,
-who have a track record of self-directed contributions to a team.
-
-We are grateful you took the time to apply and put so much thought into
-your candidate materials, we loved reading them. Although engineers at the
-early stages of their career are unlikely to be a fit for us right now, we
-are growing, and encourage you to consider re-applying in the future.
-
- We would absolutely love to work with you in the future and cannot wait for
-that stage of the company!
-
-All the best,
-The Oxide Team",
-                    self.name
-                ),
-                vec![self.email.to_string()],
-                vec![format!("careers@{}", DOMAIN)],
-                vec![],
-                format!("careers@{}", DOMAIN),
-            )
-            .await;
+    pub async fn send_email_rejection_junior_but_we_love_you(&self, db: &Database) {
+        self.send_email_template(db, "applicant_rejection_junior").await;
     }
 
     /// Send an email to the applicant that they did not provide materials.
-    pub async fn send_email_rejection_did_not_provide_materials(&self) {
-        // Initialize the SendGrid client.
-        let sendgrid_client = SendGrid::new_from_env();
-
-        // Send the message.
-        sendgrid_client
-            .send_mail(
-                format!("Thank you for your application, {}", self.name),
-                format!(
-                    "Dear {},
-
-Unfortunately, we cannot accept it at this time since you failed to provide the
-requested materials.
-
-All the best,
-The Oxide Team",
-                    self.name
-                ),
-                vec![self.email.to_string()],
-                vec![format!("careers@{}", DOMAIN)],
-                vec![],
-                format!("careers@{}", DOMAIN),
-            )
-            .await;
+    pub async fn send_email_rejection_did_not_provide_materials(&self, db: &Database) {
+        self.send_email_template(db, "applicant_rejection_no_materials").await;
     }
 
     /// Send an email to the applicant about timing.
-    pub async fn send_email_rejection_timing(&self) {
-        // Initialize the SendGrid client.
-        let sendgrid_client = SendGrid::new_from_env();
+    pub async fn send_email_rejection_timing(&self, db: &Database) {
+        self.send_email_template(db, "applicant_rejection_timing").await;
+    }
 
-        // Send the message.
-        sendgrid_client
-            .send_mail(
-                format!("Thank you for your application, {}", self.name),
-                format!(
-                    "Dear {},
-
-We are so humbled by your application to join Oxide Computer Company. At this
-stage of the company we are hyper-focused on certain areas of the stack and
-when we need specific domain space experience such as yours, please engage
-with us. Our roles will be updated as we need them.
-
-We are grateful you took the time to apply and put so much thought into the
-candidate materials, we loved reading them. We would absolutely love to work
-with you in the future and cannot wait for that stage of the company!
-
-All the best,
-The Oxide Team",
-                    self.name
-                ),
-                vec![self.email.to_string()],
-                vec![format!("careers@{}", DOMAIN)],
-                vec![],
-                format!("careers@{}", DOMAIN),
-            )
-            .await;
+    async fn send_email_template(&self, db: &Database, name: &str) {
+        let templates = EmailTemplates::load_or_defaults(db);
+
+        match templates.render(name, self) {
+            Ok(email) => {
+                email
+                    .send(vec![self.email.to_string()], vec![format!("careers@{}", DOMAIN)], vec![], format!("careers@{}", DOMAIN))
+                    .await
+            }
+            Err(e) => println!("rendering email template {} for applicant {} failed: {}", name, self.email, e),
+        }
     }
 
     /// Send an email internally that we have a new application.
@@ -468,120 +383,50 @@ The Oxide Team",
             .await;
     }
 
-    /// Parse the applicant from a Google Sheets row, where we also happen to know the columns.
+    /// Parse the applicant from a row of a Google Sheet of applications.
     /// This is how we get the spreadsheet back from the API.
-    pub async fn parse_from_row_with_columns(sheet_name: &str, sheet_id: &str, columns: &ApplicantSheetColumns, row: &[String]) -> Self {
-        // If the length of the row is greater than the status column
-        // then we have a status.
-        let raw_status = if row.len() > columns.status { row[columns.status].to_string() } else { "".to_string() };
+    pub async fn parse_from_sheet_row(sheet_name: &str, sheet_id: &str, row: &ApplicantSheetRow) -> Self {
+        let raw_status = row.status.to_string();
         let mut status = crate::applicant_status::Status::from_str(&raw_status).unwrap_or_default();
 
-        let (github, gitlab) = NewApplicant::parse_github_gitlab(&row[columns.github]);
+        let (github, gitlab) = NewApplicant::parse_github_gitlab(&row.github);
 
-        // If the length of the row is greater than the linkedin column
-        // then we have a linkedin.
-        let linkedin = if row.len() > columns.linkedin && columns.linkedin != 0 {
-            row[columns.linkedin].trim().to_lowercase()
-        } else {
-            "".to_string()
-        };
+        let linkedin = row.linkedin.trim().to_lowercase();
 
-        // If the length of the row is greater than the start date column
-        // then we have a start date.
-        let mut start_date = if row.len() > columns.start_date && columns.start_date != 0 {
-            if row[columns.start_date].trim().is_empty() {
-                None
-            } else {
-                Some(NaiveDate::parse_from_str(row[columns.start_date].trim(), "%m/%d/%Y").unwrap())
-            }
-        } else {
+        let mut start_date = if row.start_date.trim().is_empty() {
             None
-        };
-
-        // If the length of the row is greater than the interested in column
-        // then we have an interest.
-        let interested_in_str: Vec<&str> = if row.len() > columns.interested_in && columns.interested_in != 0 {
-            if row[columns.interested_in].trim().is_empty() {
-                vec![]
-            } else {
-                let split = row[columns.interested_in].trim().split(',');
-                split.collect()
-            }
-        } else {
-            vec![]
-        };
-        let mut interested_in: Vec<String> = Default::default();
-        for s in interested_in_str {
-            interested_in.push(s.trim().to_string());
-        }
-
-        // If the length of the row is greater than the portfolio column
-        // then we have a portfolio.
-        let portfolio = if row.len() > columns.portfolio && columns.portfolio != 0 {
-            row[columns.portfolio].trim().to_string()
         } else {
-            "".to_lowercase()
+            // People type all sorts of things in here, don't let one bad row stop the sync.
+            NaiveDate::parse_from_str(row.start_date.trim(), "%m/%d/%Y").ok()
         };
 
-        // If the length of the row is greater than the website column
-        // then we have a website.
-        let website = if row.len() > columns.website && columns.website != 0 {
-            row[columns.website].trim().to_lowercase()
-        } else {
-            "".to_lowercase()
-        };
+        let interested_in = row.interested_in.clone();
 
-        // If the length of the row is greater than the value_reflected column
-        // then we have a value_reflected.
-        let mut value_reflected = if row.len() > columns.value_reflected && columns.value_reflected != 0 {
-            row[columns.value_reflected].trim().to_lowercase()
-        } else {
-            "".to_lowercase()
-        };
+        let portfolio = row.portfolio.trim().to_string();
+        let website = row.website.trim().to_lowercase();
 
-        // If the length of the row is greater than the value_violated column
-        // then we have a value_violated.
-        let mut value_violated = if row.len() > columns.value_violated && columns.value_violated != 0 {
-            row[columns.value_violated].trim().to_lowercase()
-        } else {
-            "".to_lowercase()
-        };
+        let mut value_reflected = row.value_reflected.trim().to_lowercase();
+        let mut value_violated = row.value_violated.trim().to_lowercase();
 
-        let mut values_in_tension: Vec<String> = Default::default();
-        // If the length of the row is greater than the value_in_tension1 column
-        // then we have a value_in_tension1.
-        if row.len() > columns.value_in_tension_1 && columns.value_in_tension_1 != 0 {
-            values_in_tension.push(row[columns.value_in_tension_1].trim().to_lowercase());
-        }
-        // If the length of the row is greater than the value_in_tension2 column
-        // then we have a value_in_tension2.
-        if row.len() > columns.value_in_tension_2 && columns.value_in_tension_2 != 0 {
-            values_in_tension.push(row[columns.value_in_tension_2].trim().to_lowercase());
-        }
+        let mut values_in_tension: Vec<String> = vec![row.value_in_tension_1.trim().to_lowercase(), row.value_in_tension_2.trim().to_lowercase()]
+            .into_iter()
+            .filter(|v| !v.is_empty())
+            .collect();
         values_in_tension.sort();
 
-        // Check if we sent them an email that we received their application.
-        let mut sent_email_received = true;
-        if row[columns.sent_email_received].to_lowercase().contains("false") {
-            sent_email_received = false;
-        }
-
-        // Check if we sent them an email to either reject or follow up.
-        let mut sent_email_follow_up = true;
-        if row[columns.sent_email_follow_up].to_lowercase().contains("false") {
-            sent_email_follow_up = false;
-        }
+        let sent_email_received = row.sent_email_received.unwrap_or(true);
+        let sent_email_follow_up = row.sent_email_follow_up.unwrap_or(true);
 
         let mut rejection_sent_date_time = None;
 
-        let email = row[columns.email].trim().to_string();
-        let location = row[columns.location].trim().to_string();
+        let email = row.email.trim().to_string();
+        let location = row.location.trim().to_string();
         let mut latitude = 0.0;
         let mut longitude = 0.0;
-        let phone = row[columns.phone].trim().to_string();
+        let phone = row.phone.trim().to_string();
         let mut country_code = "".to_string();
-        let resume = row[columns.resume].to_string();
-        let materials = row[columns.materials].to_string();
+        let resume = row.resume.to_string();
+        let materials = row.materials.to_string();
 
         let mut resume_contents = String::new();
         let mut materials_contents = String::new();
@@ -814,8 +659,8 @@ The Oxide Team",
         }
 
         NewApplicant {
-            submitted_time: NewApplicant::parse_timestamp(&row[columns.timestamp]),
-            name: row[columns.name].to_string(),
+            submitted_time: NewApplicant::parse_timestamp(&row.timestamp),
+            name: row.name.to_string(),
             email,
             location,
             latitude,
@@ -926,50 +771,53 @@ The Oxide Team",
     }
 
     /// Expand the applicants materials and do any automation that needs to be done.
-    pub async fn expand(&mut self, drive_client: &GoogleDrive, sheets_client: &Sheets, sent_email_received_column_index: usize, sent_email_follow_up_index: usize, row_index: usize) {
+    /// The cells are where we mark in the sheet that we sent the applicant emails.
+    /// Each mark is written right after its email is sent, so if anything later fails
+    /// we do not send the same email again on the next run.
+    pub async fn expand(
+        &mut self,
+        db: &Database,
+        drive_client: &GoogleDrive,
+        sheets_client: &Sheets,
+        sent_email_received_cell: CellRef,
+        sent_email_follow_up_cell: CellRef,
+    ) -> Result<(), crate::Error> {
         // Check if we have sent them an email that we received their application.
         if !self.sent_email_received {
             // Send them an email.
-            self.send_email_recieved_application_to_applicant().await;
+            self.send_email_recieved_application_to_applicant(db).await;
 
             // Mark the column as true not false.
-            let mut colmn = "ABCDEFGHIJKLMNOPQRSTUVWXYZ".chars();
-            let rng = format!("{}{}", colmn.nth(sent_email_received_column_index).unwrap().to_string(), row_index);
-
-            sheets_client.update_values(&self.sheet_id, &rng, "TRUE".to_string()).await.unwrap();
+            sheets_client.batch_update_values(&self.sheet_id, vec![ValueRange::cell(sent_email_received_cell, "TRUE")]).await?;
 
             println!("[applicant] sent email to {} that we received their application", self.email);
         }
 
         // Send an email follow up if we should.
         if !self.sent_email_follow_up {
-            // Get the right cell to eventually change in the google sheet.
-            let mut colmn = "ABCDEFGHIJKLMNOPQRSTUVWXYZ".chars();
-            let rng = format!("{}{}", colmn.nth(sent_email_follow_up_index).unwrap().to_string(), row_index);
-
             let status = crate::applicant_status::Status::from_str(&self.status).unwrap_or_default();
             if status == crate::applicant_status::Status::Declined || status == crate::applicant_status::Status::Deferred {
                 // Check if we have sent the follow up email to them.unwrap_or_default().
                 if self.raw_status.contains("did not do materials") {
                     // Send the email.
-                    self.send_email_rejection_did_not_provide_materials().await;
+                    self.send_email_rejection_did_not_provide_materials(db).await;
 
                     println!("[applicant] sent email to {} tell them they did not do the materials", self.email);
                 } else if self.raw_status.contains("junior") {
                     // Send the email.
-                    self.send_email_rejection_junior_but_we_love_you().await;
+                    self.send_email_rejection_junior_but_we_love_you(db).await;
 
                     println!("[applicant] sent email to {} tell them we can't hire them at this stage", self.email);
                 } else {
                     // Send the email.
-                    self.send_email_rejection_timing().await;
+                    self.send_email_rejection_timing(db).await;
 
                     println!("[applicant] sent email to {} tell them about timing", self.email);
                 }
 
                 // Update the cell in the google sheet so we know we sent the email.
                 // Mark the column as true not false.
-                sheets_client.update_values(&self.sheet_id, &rng, "TRUE".to_string()).await.unwrap();
+                sheets_client.batch_update_values(&self.sheet_id, vec![ValueRange::cell(sent_email_follow_up_cell, "TRUE")]).await?;
 
                 // Mark the time we sent the email.
                 self.rejection_sent_date_time = Some(Utc::now());
@@ -981,7 +829,7 @@ The Oxide Team",
                 // Only when it's not in "NeedsToBeTriaged".
                 // Update the cell in the google sheet so we know we sent the email.
                 // Mark the column as true not false.
-                sheets_client.update_values(&self.sheet_id, &rng, "TRUE".to_string()).await.unwrap();
+                sheets_client.batch_update_values(&self.sheet_id, vec![ValueRange::cell(sent_email_follow_up_cell, "TRUE")]).await?;
 
                 self.sent_email_follow_up = true;
             }
@@ -1172,6 +1020,8 @@ The Oxide Team",
             self.question_values_in_tension = parse_question(QUESTION_VALUES_IN_TENSION, QUESTION_WHY_OXIDE, &materials_contents);
             self.question_why_oxide = parse_question(QUESTION_WHY_OXIDE, "", &materials_contents);
         }
+
+        Ok(())
     }
 
     /// Get the human duration of time since the application was submitted.
@@ -1355,7 +1205,7 @@ impl Applicant {
     }
 
     /// Send an invite to the applicant to do a background check.
-    pub async fn send_background_check_invitation(&mut self, db: &Database) {
+    pub async fn send_background_check_invitation(&mut self, db: &Database) -> Result<(), crate::Error> {
         // Initialize the Checker client.
         let checkr = Checkr::new_from_env();
 
@@ -1371,12 +1221,12 @@ impl Applicant {
                     // Update the database.
                     self.criminal_background_check_status = "requested".to_string();
 
-                    self.update(db).await;
+                    self.update(db).await?;
 
                     println!("[applicant] sent background check invitation to: {}", self.email);
                 }
                 // We can return early they already exist as a candidate.
-                return;
+                return Ok(());
             }
         }
 
@@ -1389,9 +1239,11 @@ impl Applicant {
         // Update the database.
         self.criminal_background_check_status = "requested".to_string();
 
-        self.update(db).await;
+        self.update(db).await?;
 
         println!("[applicant] sent background check invitation to: {}", self.email);
+
+        Ok(())
     }
 
     /// Convert the applicant into JSON for a Slack message.
@@ -1758,117 +1610,90 @@ fn parse_question(q1: &str, q2: &str, materials_contents: &str) -> String {
 /// Implement updating the Airtable record for an Applicant.
 #[async_trait]
 impl UpdateAirtableRecord<Applicant> for Applicant {
-    async fn update_airtable_record(&mut self, record: Applicant) {
-        self.interviews = record.interviews;
-        self.geocode_cache = record.geocode_cache;
+    async fn update_airtable_record(&mut self, _record: Applicant) {
         self.resume_contents = truncate(&self.resume_contents, 100000);
         self.materials_contents = truncate(&self.materials_contents, 100000);
         self.question_why_oxide = truncate(&self.question_why_oxide, 100000);
     }
 }
 
-/// The data type for a Google Sheet applicant columns, we use this when
-/// parsing the Google Sheets for applicants.
-#[derive(Debug, Default, Deserialize, Serialize)]
-pub struct ApplicantSheetColumns {
-    pub timestamp: usize,
-    pub name: usize,
-    pub email: usize,
-    pub location: usize,
-    pub phone: usize,
-    pub github: usize,
-    pub portfolio: usize,
-    pub website: usize,
-    pub linkedin: usize,
-    pub resume: usize,
-    pub materials: usize,
-    pub status: usize,
-    pub sent_email_received: usize,
-    pub sent_email_follow_up: usize,
-    pub value_reflected: usize,
-    pub value_violated: usize,
-    pub value_in_tension_1: usize,
-    pub value_in_tension_2: usize,
-    pub start_date: usize,
-    pub interested_in: usize,
-}
+/// Check an applicant before the API writes it.
+impl Validate for NewApplicant {
+    fn validation_errors(&self) -> Vec<String> {
+        let mut problems: Vec<String> = Default::default();
 
-impl ApplicantSheetColumns {
-    /// Parse the sheet columns from Google Sheets values.
-    pub fn parse(values: &[Vec<String>]) -> Self {
-        // Iterate over the columns.
-        // TODO: make this less horrible
-        let mut columns: ApplicantSheetColumns = Default::default();
+        if self.name.trim().is_empty() {
+            problems.push("name cannot be empty".to_string());
+        }
+        if !self.email.contains('@') || self.email.contains(char::is_whitespace) {
+            problems.push(format!("email `{}` is not an email address", self.email));
+        }
+        if self.role.trim().is_empty() {
+            problems.push("role cannot be empty".to_string());
+        }
+        // Anything we don't recognize parses as needs to be triaged, so make sure
+        // the status is one we know about.
+        let status = crate::applicant_status::Status::from_str(&self.status).unwrap_or_default();
+        if status.to_string() != self.status {
+            problems.push(format!("status `{}` is not a known status, did you mean `{}`?", self.status, status.to_string()));
+        }
 
-        // Get the first row.
-        let row = values.get(0).unwrap();
+        problems
+    }
+}
 
-        for (index, col) in row.iter().enumerate() {
-            let c = col.to_lowercase();
+/// A row of a Google Sheet of applications. The fields are read from the
+/// columns whose headers match their names, see `sheets::HeaderMap`.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct ApplicantSheetRow {
+    #[serde(default, rename = "timestamp")]
+    pub timestamp: String,
+    #[serde(default, rename = "name")]
+    pub name: String,
+    #[serde(default, rename = "email address")]
+    pub email: String,
+    #[serde(default, rename = "location")]
+    pub location: String,
+    #[serde(default, rename = "phone")]
+    pub phone: String,
+    #[serde(default, rename = "github")]
+    pub github: String,
+    #[serde(default, rename = "portfolio url")]
+    pub portfolio: String,
+    #[serde(default, rename = "website")]
+    pub website: String,
+    #[serde(default, rename = "linkedin profile url")]
+    pub linkedin: String,
+    #[serde(default, rename = "resume")]
+    pub resume: String,
+    #[serde(default, rename = "materials")]
+    pub materials: String,
+    #[serde(default, rename = "status")]
+    pub status: String,
+    /// This is `None` if the cell is empty, which we treat as sent so we never
+    /// send the email twice.
+    #[serde(rename = "sent email that we received their application")]
+    pub sent_email_received: Option<bool>,
+    #[serde(rename = "have sent follow up email")]
+    pub sent_email_follow_up: Option<bool>,
+    #[serde(default, rename = "value reflected")]
+    pub value_reflected: String,
+    #[serde(default, rename = "value violated")]
+    pub value_violated: String,
+    #[serde(default, rename = "value in tension [1")]
+    pub value_in_tension_1: String,
+    #[serde(default, rename = "value in tension [2")]
+    pub value_in_tension_2: String,
+    #[serde(default, rename = "start date")]
+    pub start_date: String,
+    #[serde(default, rename = "job descriptions are you interested in")]
+    pub interested_in: Vec<String>,
+}
 
-            if c.contains("timestamp") {
-                columns.timestamp = index;
-            }
-            if c.contains("name") {
-                columns.name = index;
-            }
-            if c.contains("email address") {
-                columns.email = index;
-            }
-            if c.contains("location") {
-                columns.location = index;
-            }
-            if c.contains("phone") {
-                columns.phone = index;
-            }
-            if c.contains("github") {
-                columns.github = index;
-            }
-            if c.contains("portfolio url") {
-                columns.portfolio = index;
-            }
-            if c.contains("website") {
-                columns.website = index;
-            }
-            if c.contains("linkedin") {
-                columns.linkedin = index;
-            }
-            if c.contains("resume") {
-                columns.resume = index;
-            }
-            if c.contains("materials") {
-                columns.materials = index;
-            }
-            if c.contains("status") {
-                columns.status = index;
-            }
-            if c.contains("value reflected") {
-                columns.value_reflected = index;
-            }
-            if c.contains("value violated") {
-                columns.value_violated = index;
-            }
-            if c.contains("value in tension [1") {
-                columns.value_in_tension_1 = index;
-            }
-            if c.contains("value in tension [2") {
-                columns.value_in_tension_2 = index;
-            }
-            if c.contains("sent email that we received their application") {
-                columns.sent_email_received = index;
-            }
-            if c.contains("have sent follow up email") {
-                columns.sent_email_follow_up = index;
-            }
-            if c.contains("start date") {
-                columns.start_date = index;
-            }
-            if c.contains("job descriptions are you interested in") {
-                columns.interested_in = index;
-            }
-        }
-        columns
-    }
+impl ApplicantSheetRow {
+    /// The headers of the columns where we mark that we sent the applicant emails.
+    pub const SENT_EMAIL_RECEIVED_HEADER: &'static str = "sent email that we received their application";
+    pub const SENT_EMAIL_FOLLOW_UP_HEADER: &'static str = "have sent follow up email";
 }
 
 /// Get the contexts of a file in Google Drive by it's URL as a text string.
@@ -2087,7 +1912,7 @@ pub fn get_role_from_sheet_id(sheet_id: &str) -> String {
 }
 
 // Sync the applicants with our database.
-pub async fn refresh_db_applicants(db: &Database) {
+pub async fn refresh_db_applicants(db: &Database) -> Result<(), crate::Error> {
     let github = authenticate_github_jwt();
 
     // Get all the hiring issues on the configs repository.
@@ -2111,33 +1936,57 @@ pub async fn refresh_db_applicants(db: &Database) {
     // depending on the application status.
     for (sheet_name, sheet_id) in get_sheets_map() {
         // Get the values in the sheet.
-        let sheet_values = sheets_client.get_values(&sheet_id, "Form Responses 1!A1:Z1000".to_string()).await.unwrap();
-        let values = sheet_values.values.unwrap();
+        let sheet_values = sheets_client.get_values(&sheet_id, "Form Responses 1".to_string()).await?;
+        let values = sheet_values.values.unwrap_or_default();
 
         if values.is_empty() {
             panic!("unable to retrieve any data values from Google sheet {} {}", sheet_id, sheet_name);
         }
 
         // Parse the sheet columns.
-        let columns = ApplicantSheetColumns::parse(&values);
+        let headers = HeaderMap::from_values(&values);
+        let (sent_email_received_column, sent_email_follow_up_column) = match (
+            headers.column(ApplicantSheetRow::SENT_EMAIL_RECEIVED_HEADER),
+            headers.column(ApplicantSheetRow::SENT_EMAIL_FOLLOW_UP_HEADER),
+        ) {
+            (Some(received), Some(follow_up)) => (received, follow_up),
+            _ => {
+                println!("[applicants] sheet {} {} is missing the columns for the emails we sent, skipping", sheet_id, sheet_name);
+                continue;
+            }
+        };
 
         // Iterate over the rows.
-        for (row_index, row) in values.iter().enumerate() {
+        for (row_index, values_row) in values.iter().enumerate() {
             if row_index == 0 {
                 // Continue the loop since we were on the header row.
                 continue;
             } // End get header information.
 
+            let row: ApplicantSheetRow = match headers.deserialize(values_row) {
+                Ok(row) => row,
+                Err(e) => {
+                    println!("[applicants] parsing row {} of sheet {} failed, skipping: {}", row_index + 1, sheet_name, e);
+                    continue;
+                }
+            };
+
             // Break the loop early if we reached an empty row.
-            if row[columns.email].is_empty() {
+            if row.email.is_empty() {
                 break;
             }
 
             // Parse the applicant out of the row information.
-            let mut applicant = NewApplicant::parse_from_row_with_columns(sheet_name, sheet_id, &columns, &row).await;
+            let mut applicant = NewApplicant::parse_from_sheet_row(sheet_name, sheet_id, &row).await;
             applicant
-                .expand(&drive_client, &sheets_client, columns.sent_email_received, columns.sent_email_follow_up, row_index + 1)
-                .await;
+                .expand(
+                    db,
+                    &drive_client,
+                    &sheets_client,
+                    CellRef::new(sent_email_received_column, row_index),
+                    CellRef::new(sent_email_follow_up_column, row_index),
+                )
+                .await?;
 
             if !applicant.sent_email_received {
                 // Post to Slack.
@@ -2147,11 +1996,19 @@ pub async fn refresh_db_applicants(db: &Database) {
                 applicant.send_email_internally().await;
             }
 
-            let new_applicant = applicant.upsert(db).await;
+            let new_applicant = match applicant.upsert(db).await {
+                Ok(a) => a,
+                Err(e) => {
+                    println!("upserting applicant {} failed, skipping: {}", applicant.email, e);
+                    continue;
+                }
+            };
 
             new_applicant.create_github_onboarding_issue(db, &github, &configs_issues).await;
         }
     }
+
+    Ok(())
 }
 
 /// The data type for a Google Sheet applicant form columns, we use this when
@@ -2179,8 +2036,8 @@ impl ApplicantFormSheetColumns {
     }
 }
 
-pub fn get_reviewer_pool(db: &Database) -> Vec<String> {
-    let users = Users::get_from_db(db);
+pub fn get_reviewer_pool(db: &Database) -> Result<Vec<String>, crate::Error> {
+    let users = Users::get_from_db(db)?;
 
     let mut reviewers: Vec<String> = Default::default();
     for user in users {
@@ -2188,7 +2045,7 @@ pub fn get_reviewer_pool(db: &Database) -> Vec<String> {
             reviewers.push(user.email());
         }
     }
-    reviewers
+    Ok(reviewers)
 }
 
 pub async fn update_applications_with_scoring_forms(db: &Database) {
@@ -2279,7 +2136,9 @@ pub async fn update_applications_with_scoring_forms(db: &Database) {
                     let status = crate::applicant_status::Status::from_str(&applicant.status);
                     if status != Ok(crate::applicant_status::Status::NeedsToBeTriaged) {
                         // Update the applicant in the database.
-                        applicant.update(db).await;
+                        if let Err(e) = applicant.update(db).await {
+                            println!("updating applicant {} failed: {}", applicant.email, e);
+                        }
 
                         // Continue we don't care.
                         continue;
@@ -2316,7 +2175,9 @@ pub async fn update_applications_with_scoring_forms(db: &Database) {
                      }*/
 
                     // Update the applicant in the database.
-                    applicant.update(db).await;
+                    if let Err(e) = applicant.update(db).await {
+                        println!("updating applicant {} failed: {}", applicant.email, e);
+                    }
                 }
             }
         }
@@ -2429,7 +2290,9 @@ pub async fn update_applications_with_scoring_results(db: &Database) {
                     applicant.values_in_tension = values_in_tension.clone();
 
                     // Update the applicant in the database.
-                    applicant.update(db).await;
+                    if let Err(e) = applicant.update(db).await {
+                        println!("updating applicant {} failed: {}", applicant.email, e);
+                    }
                 }
             }
         }
@@ -2458,7 +2321,9 @@ pub async fn update_applications_with_scoring_results(db: &Database) {
         applicant.scoring_underwhelming_materials_count = 0;
 
         // Update the applicant in the database.
-        applicant.update(db).await;
+        if let Err(e) = applicant.update(db).await {
+            println!("updating applicant {} failed: {}", applicant.email, e);
+        }
     }
 }
 
@@ -2512,7 +2377,9 @@ pub async fn refresh_background_checks(db: &Database) {
                     }
 
                     // Update the applicant.
-                    applicant.update(db).await;
+                    if let Err(e) = applicant.update(db).await {
+                        println!("updating applicant {} failed: {}", applicant.email, e);
+                    }
                 }
             } else {
                 println!("[checkr] could not find applicant with email {} in sheet_id {}", candidate.email, sheet_id);
@@ -2613,7 +2480,9 @@ pub async fn update_applicant_reviewers(db: &Database) {
                 };
 
                 // Upsert the applicant reviewer in the database.
-                reviewer.upsert(db).await;
+                if let Err(e) = reviewer.upsert(db).await {
+                    println!("upserting applicant reviewer {} failed: {}", reviewer.email, e);
+                }
             }
             None => {
                 println!("could not find user with email: {}", email);
@@ -2622,20 +2491,24 @@ pub async fn update_applicant_reviewers(db: &Database) {
     }
 }
 
-pub async fn refresh_docusign_for_applicants(db: &Database) {
+pub async fn refresh_docusign_for_applicants(db: &Database) -> Result<(), crate::Error> {
     // Authenticate DocuSign.
-    let ds = DocuSign::new_from_env().await;
+    let ds: DocuSign = TokenProvider::new(db).client().await?;
 
     // Get the template we need.
     let template_id = get_docusign_template_id(&ds).await;
 
     // TODO: we could actually query the DB by status, but whatever.
-    let applicants = Applicants::get_from_db(db);
+    let applicants = Applicants::get_from_db(db)?;
 
     // Iterate over the applicants and find any that have the status: giving offer.
     for mut applicant in applicants {
-        applicant.do_docusign(db, &ds, &template_id).await;
+        if let Err(e) = applicant.do_docusign(db, &ds, &template_id).await {
+            println!("updating docusign for applicant {} failed: {}", applicant.email, e);
+        }
     }
+
+    Ok(())
 }
 
 pub async fn get_docusign_template_id(ds: &DocuSign) -> String {
@@ -2650,7 +2523,7 @@ pub async fn get_docusign_template_id(ds: &DocuSign) -> String {
 }
 
 impl Applicant {
-    pub async fn do_docusign(&mut self, db: &Database, ds: &DocuSign, template_id: &str) {
+    pub async fn do_docusign(&mut self, db: &Database, ds: &DocuSign, template_id: &str) -> Result<(), crate::Error> {
         // We look for "Onboarding" here as well since we want to make sure we can actually update
         // the data for the user.
         if self.status != crate::applicant_status::Status::GivingOffer.to_string()
@@ -2658,7 +2531,7 @@ impl Applicant {
             && self.status != crate::applicant_status::Status::Hired.to_string()
         {
             // We can return early.
-            return;
+            return Ok(());
         }
 
         if self.docusign_envelope_id.is_empty() && self.status == crate::applicant_status::Status::GivingOffer.to_string() {
@@ -2730,17 +2603,19 @@ impl Applicant {
             self.docusign_envelope_status = envelope.status.to_string();
 
             // Update the applicant in the database.
-            self.update(db).await;
+            self.update(db).await?;
         } else if !self.docusign_envelope_id.is_empty() {
             // We have sent their offer.
             // Let's get the status of the envelope in Docusign.
             let envelope = ds.get_envelope(&self.docusign_envelope_id).await.unwrap();
 
-            self.update_applicant_from_docusign_envelope(db, &ds, envelope).await;
+            self.update_applicant_from_docusign_envelope(db, &ds, envelope).await?;
         }
+
+        Ok(())
     }
 
-    pub async fn update_applicant_from_docusign_envelope(&mut self, db: &Database, ds: &DocuSign, envelope: docusign::Envelope) {
+    pub async fn update_applicant_from_docusign_envelope(&mut self, db: &Database, ds: &DocuSign, envelope: docusign::Envelope) -> Result<(), crate::Error> {
         // Set the status in the database and airtable.
         self.docusign_envelope_status = envelope.status.to_string();
         self.offer_created = envelope.created_date_time;
@@ -2748,8 +2623,8 @@ impl Applicant {
         // If the document is completed, let's save it to Google Drive.
         if envelope.status != "completed" {
             // We will skip to the end and return early, only updating the status.
-            self.update(db).await;
-            return;
+            self.update(db).await?;
+            return Ok(());
         }
 
         // Set the completed time.
@@ -2762,7 +2637,7 @@ impl Applicant {
             // Request their background check, if we have not already.
             if self.criminal_background_check_status.is_empty() {
                 // Request the background check, since we previously have not requested one.
-                self.send_background_check_invitation(db).await;
+                self.send_background_check_invitation(db).await?;
             }
         }
 
@@ -2834,7 +2709,7 @@ impl Applicant {
             }
 
             // Update the employee.
-            employee.update(db).await;
+            employee.update(db).await?;
         }
 
         for fd in form_data {
@@ -2846,7 +2721,9 @@ impl Applicant {
             }
         }
 
-        self.update(db).await;
+        self.update(db).await?;
+
+        Ok(())
     }
 }
 
@@ -2898,13 +2775,13 @@ mod tests {
     #[tokio::test(flavor = "multi_thread")]
     async fn test_applicants() {
         let db = Database::new();
-        refresh_db_applicants(&db).await;
+        refresh_db_applicants(&db).await.unwrap();
 
         // Update Airtable.
-        Applicants::get_from_db(&db).update_airtable().await;
+        Applicants::get_from_db(&db).unwrap().update_airtable(&db).await.unwrap();
 
         // Refresh DocuSign for the applicants.
-        refresh_docusign_for_applicants(&db).await;
+        refresh_docusign_for_applicants(&db).await.unwrap();
     }
 
     #[ignore]
@@ -0,0 +1,250 @@
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use crate::db::Database;
+use crate::schema::audit_log_entries;
+
+/// Fields that are left out of the changes, since they are not something anyone
+/// edits. The Airtable record id changes whenever we sync a new record.
+const IGNORED_FIELDS: &[&str] = &["id", "airtable_record_id"];
+
+/// What a secret value is replaced with in the changes.
+pub const REDACTED: &str = "[redacted]";
+
+/// Get the fields of a table that hold secrets. The audit log records when
+/// they change, but not their values.
+fn redacted_fields(table: &str) -> &'static [&'static str] {
+    match table {
+        "api_tokens" => &["access_token", "refresh_token"],
+        _ => &[],
+    }
+}
+
+/// What happened to a record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    Create,
+    Update,
+    Delete,
+}
+
+impl fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AuditAction::Create => write!(f, "create"),
+            AuditAction::Update => write!(f, "update"),
+            AuditAction::Delete => write!(f, "delete"),
+        }
+    }
+}
+
+#[derive(Debug, Insertable, PartialEq, Clone)]
+#[table_name = "audit_log_entries"]
+pub struct NewAuditLogEntry {
+    pub table_name: String,
+    pub record_id: i32,
+    pub action: String,
+    pub changes: Value,
+    pub source: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A change to a record made by the functions generated by the `db` macro.
+#[derive(Debug, Queryable, Identifiable, PartialEq, Clone, JsonSchema, Deserialize, Serialize)]
+#[table_name = "audit_log_entries"]
+pub struct AuditLogEntry {
+    pub id: i32,
+    pub table_name: String,
+    pub record_id: i32,
+    pub action: String,
+    /// The fields that changed, each with its `old` and `new` value.
+    pub changes: Value,
+    /// What made the change, like `cron:applications`, `webhook:/shippo/tracking/update`
+    /// or `api:jess@oxidecomputer.com`.
+    pub source: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl AuditLogEntry {
+    /// Get the changes to a record, newest first.
+    pub fn get_for_record(db: &Database, table: &str, record_id: i32) -> Result<Vec<AuditLogEntry>, crate::Error> {
+        Ok(audit_log_entries::table
+            .filter(audit_log_entries::dsl::table_name.eq(table))
+            .filter(audit_log_entries::dsl::record_id.eq(record_id))
+            .order_by(audit_log_entries::dsl::created_at.desc())
+            .load::<AuditLogEntry>(&db.conn())?)
+    }
+
+    /// Get the fields of a record that changed after the given time, each with the
+    /// value it had at that time.
+    pub fn values_at(db: &Database, table: &str, record_id: i32, since: DateTime<Utc>) -> Result<Map<String, Value>, crate::Error> {
+        let entries = audit_log_entries::table
+            .filter(audit_log_entries::dsl::table_name.eq(table))
+            .filter(audit_log_entries::dsl::record_id.eq(record_id))
+            .filter(audit_log_entries::dsl::created_at.gt(since))
+            .order_by(audit_log_entries::dsl::created_at.asc())
+            .load::<AuditLogEntry>(&db.conn())?;
+
+        Ok(values_before(&entries))
+    }
+}
+
+/// Record a change to a record in the audit log. This is called by the functions
+/// generated by the `db` macro, on the same connection and in the same transaction
+/// as the change itself. Nothing is recorded if no fields changed.
+pub fn record<T: Serialize>(conn: &PgConnection, source: &str, table: &str, record_id: i32, action: AuditAction, before: Option<&T>, after: Option<&T>) -> Result<(), crate::Error> {
+    let before = match before {
+        Some(b) => serde_json::to_value(b)?,
+        None => Value::Null,
+    };
+    let after = match after {
+        Some(a) => serde_json::to_value(a)?,
+        None => Value::Null,
+    };
+
+    let mut changes = diff(&before, &after);
+    redact(table, &mut changes);
+    if changes.is_empty() {
+        return Ok(());
+    }
+
+    diesel::insert_into(audit_log_entries::table)
+        .values(&NewAuditLogEntry {
+            table_name: table.to_string(),
+            record_id,
+            action: action.to_string(),
+            changes: Value::Object(changes),
+            source: source.to_string(),
+            created_at: Utc::now(),
+        })
+        .execute(conn)?;
+
+    Ok(())
+}
+
+/// Get the fields that differ between two versions of a record, each with its
+/// `old` and `new` value. Either version can be `null`, for a record that was
+/// created or deleted, and a field that is missing counts as `null`.
+pub fn diff(before: &Value, after: &Value) -> Map<String, Value> {
+    let empty = Map::new();
+    let before = before.as_object().unwrap_or(&empty);
+    let after = after.as_object().unwrap_or(&empty);
+
+    let mut changes = Map::new();
+    for field in before.keys().chain(after.keys()) {
+        if IGNORED_FIELDS.contains(&field.as_str()) || changes.contains_key(field) {
+            continue;
+        }
+
+        let old = before.get(field).unwrap_or(&Value::Null);
+        let new = after.get(field).unwrap_or(&Value::Null);
+        if old != new {
+            changes.insert(field.to_string(), json!({ "old": old, "new": new }));
+        }
+    }
+
+    changes
+}
+
+/// Get the fields changed by the entries, each with its `old` value in the first
+/// entry that changed it. The entries must be oldest first.
+pub fn values_before(entries: &[AuditLogEntry]) -> Map<String, Value> {
+    let mut values = Map::new();
+    for entry in entries {
+        if let Some(changes) = entry.changes.as_object() {
+            for (field, change) in changes {
+                if !values.contains_key(field) {
+                    values.insert(field.to_string(), change.get("old").cloned().unwrap_or(Value::Null));
+                }
+            }
+        }
+    }
+
+    values
+}
+
+/// Replace the values of the secret fields of a table in the changes, leaving
+/// `null` as is so it still shows when a secret was set or cleared.
+pub fn redact(table: &str, changes: &mut Map<String, Value>) {
+    for field in redacted_fields(table) {
+        if let Some(Value::Object(change)) = changes.get_mut(*field) {
+            for value in change.values_mut() {
+                if !value.is_null() {
+                    *value = json!(REDACTED);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use chrono::Utc;
+
+    use crate::audit::{diff, redact, values_before, AuditLogEntry};
+
+    #[test]
+    fn test_diff() {
+        let before = json!({"id": 1, "name": "Jane", "status": "Interviewing", "airtable_record_id": ""});
+        let after = json!({"id": 1, "name": "Jane", "status": "Hired", "airtable_record_id": "rec123", "start_date": "2021-07-01"});
+
+        let changes = diff(&before, &after);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes["status"], json!({"old": "Interviewing", "new": "Hired"}));
+        assert_eq!(changes["start_date"], json!({"old": null, "new": "2021-07-01"}));
+
+        // A deleted record has every field go to null.
+        let changes = diff(&after, &json!(null));
+        assert_eq!(changes.len(), 3);
+        assert_eq!(changes["name"], json!({"old": "Jane", "new": null}));
+
+        assert!(diff(&before, &before).is_empty());
+    }
+
+    #[test]
+    fn test_redact() {
+        let before = json!({"product": "gusto", "access_token": "plaintext", "expires_in": 60});
+        let after = json!({"product": "gusto", "access_token": "enc:v1:abc", "refresh_token": "enc:v1:def", "expires_in": 120});
+
+        let mut changes = diff(&before, &after);
+        redact("api_tokens", &mut changes);
+        assert_eq!(changes["access_token"], json!({"old": "[redacted]", "new": "[redacted]"}));
+        assert_eq!(changes["refresh_token"], json!({"old": null, "new": "[redacted]"}));
+        assert_eq!(changes["expires_in"], json!({"old": 60, "new": 120}));
+
+        // Other tables are left alone.
+        let mut changes = diff(&before, &after);
+        redact("users", &mut changes);
+        assert_eq!(changes["access_token"], json!({"old": "plaintext", "new": "enc:v1:abc"}));
+    }
+
+    #[test]
+    fn test_values_before() {
+        let entry = |changes| AuditLogEntry {
+            id: 0,
+            table_name: "outbound_shipments".to_string(),
+            record_id: 1,
+            action: "update".to_string(),
+            changes,
+            source: "test".to_string(),
+            created_at: Utc::now(),
+        };
+        let entries = vec![
+            entry(json!({"status": {"old": "Label created", "new": "Shipped"}})),
+            entry(json!({"status": {"old": "Shipped", "new": "Delivered"}, "notes": {"old": null, "new": "Left at the door"}})),
+        ];
+
+        let values = values_before(&entries);
+        assert_eq!(values.len(), 2);
+        assert_eq!(values["status"], json!("Label created"));
+        assert_eq!(values["notes"], json!(null));
+    }
+}
@@ -1,7 +1,6 @@
 #![allow(clippy::from_over_into)]
 use std::collections::HashMap;
 use std::env;
-use std::{thread, time};
 
 use async_trait::async_trait;
 use chrono::naive::NaiveDateTime;
@@ -28,6 +27,7 @@ use crate::utils::{DOMAIN, GSUITE_DOMAIN};
     match_on = {
         "user_id" = "String",
     },
+    airtable_fields = ["link_to_people", "link_to_auth_user_logins", "link_to_page_views"],
 }]
 #[derive(Debug, Insertable, AsChangeset, PartialEq, Clone, JsonSchema, Deserialize, Serialize)]
 #[table_name = "auth_users"]
@@ -79,12 +79,7 @@ pub struct NewAuthUser {
 /// Implement updating the Airtable record for a AuthUser.
 #[async_trait]
 impl UpdateAirtableRecord<AuthUser> for AuthUser {
-    async fn update_airtable_record(&mut self, record: AuthUser) {
-        // Set the link_to_people and link_to_auth_user_logins from the original so it stays intact.
-        self.link_to_people = record.link_to_people.clone();
-        self.link_to_auth_user_logins = record.link_to_auth_user_logins;
-        self.link_to_page_views = record.link_to_page_views;
-    }
+    async fn update_airtable_record(&mut self, _record: AuthUser) {}
 }
 
 impl PartialEq for AuthUser {
@@ -159,7 +154,13 @@ impl UpdateAirtableRecord<AuthUserLogin> for AuthUserLogin {
     async fn update_airtable_record(&mut self, _record: AuthUserLogin) {
         // Get the current auth users in Airtable so we can link to it.
         // TODO: make this more dry so we do not call it every single damn time.
-        let auth_users = AuthUsers::get_from_airtable().await;
+        let auth_users = match AuthUsers::get_from_airtable().await {
+            Ok(v) => v,
+            Err(e) => {
+                println!("[airtable] getting auth users to link login for `{}` failed: {}", self.user_id, e);
+                return;
+            }
+        };
 
         // Iterate over the auth_users and see if we find a match.
         for (_id, auth_user_record) in auth_users {
@@ -273,7 +274,11 @@ pub struct Token {
 
 /// List users.
 pub async fn get_auth_users(domain: String, db: &Database) -> Vec<NewAuthUser> {
-    let client = Client::new();
+    // The client backs off when Auth0 rate limits us, and holds back the next
+    // request once we have used up our quota.
+    // https://auth0.com/docs/policies/rate-limit-policy
+    // https://auth0.com/docs/policies/rate-limit-policy/management-api-endpoint-rate-limits
+    let client = cio_http::Client::new(Client::new());
     // Get our token.
     let client_id = env::var("CIO_AUTH0_CLIENT_ID").unwrap();
     let client_secret = env::var("CIO_AUTH0_CLIENT_SECRET").unwrap();
@@ -284,22 +289,16 @@ pub async fn get_auth_users(domain: String, db: &Database) -> Vec<NewAuthUser> {
     map.insert("audience", format!("https://{}.auth0.com/api/v2/", domain));
     map.insert("grant_type", "client_credentials".to_string());
 
-    let resp = client.post(&format!("https://{}.auth0.com/oauth/token", domain)).json(&map).send().await.unwrap();
+    let resp = client.send(client.post(&format!("https://{}.auth0.com/oauth/token", domain)).json(&map)).await.unwrap();
 
     let token: Token = resp.json().await.unwrap();
 
     let mut users: Vec<User> = Default::default();
 
-    let rate_limit_sleep = time::Duration::from_millis(2000);
-
     let mut i: i32 = 0;
     let mut has_records = true;
     while has_records {
-        let mut u = get_auth_users_page(&token.access_token, &domain, &i.to_string()).await;
-        // We need to sleep here for a half second so we don't get rate limited.
-        // https://auth0.com/docs/policies/rate-limit-policy
-        // https://auth0.com/docs/policies/rate-limit-policy/management-api-endpoint-rate-limits
-        thread::sleep(rate_limit_sleep);
+        let mut u = get_auth_users_page(&client, &token.access_token, &domain, &i.to_string()).await;
 
         has_records = !u.is_empty();
         i += 1;
@@ -313,7 +312,7 @@ pub async fn get_auth_users(domain: String, db: &Database) -> Vec<NewAuthUser> {
         let mut auth_user = user.to_auth_user();
 
         // Get the application they last accessed.
-        let auth_user_logins = get_auth_logs_for_user(&token.access_token, &domain, &user.user_id).await;
+        let auth_user_logins = get_auth_logs_for_user(&client, &token.access_token, &domain, &user.user_id).await;
 
         // Get the first result.
         if !auth_user_logins.is_empty() {
@@ -323,15 +322,12 @@ pub async fn get_auth_users(domain: String, db: &Database) -> Vec<NewAuthUser> {
 
         auth_users.push(auth_user);
 
-        // We need to sleep here for a half second so we don't get rate limited.
-        // https://auth0.com/docs/policies/rate-limit-policy
-        // https://auth0.com/docs/policies/rate-limit-policy/management-api-endpoint-rate-limits
-        thread::sleep(rate_limit_sleep);
-
         // Update our database with all the auth_user_logins.
         for mut auth_user_login in auth_user_logins {
             auth_user_login.email = user.email.to_string();
-            auth_user_login.upsert(db).await;
+            if let Err(e) = auth_user_login.upsert(db).await {
+                println!("upserting auth user login for {} failed: {}", auth_user_login.email, e);
+            }
         }
     }
 
@@ -339,13 +335,14 @@ pub async fn get_auth_users(domain: String, db: &Database) -> Vec<NewAuthUser> {
 }
 
 // TODO: clean this all up to be an auth0 api library.
-async fn get_auth_logs_for_user(token: &str, domain: &str, user_id: &str) -> Vec<NewAuthUserLogin> {
-    let client = Client::new();
+async fn get_auth_logs_for_user(client: &cio_http::Client, token: &str, domain: &str, user_id: &str) -> Vec<NewAuthUserLogin> {
     let resp = client
-        .get(&format!("https://{}.auth0.com/api/v2/users/{}/logs", domain, user_id))
-        .bearer_auth(token)
-        .query(&[("sort", "date:-1"), ("per_page", "100")])
-        .send()
+        .send(
+            client
+                .get(&format!("https://{}.auth0.com/api/v2/users/{}/logs", domain, user_id))
+                .bearer_auth(token)
+                .query(&[("sort", "date:-1"), ("per_page", "100")]),
+        )
         .await
         .unwrap();
 
@@ -381,13 +378,14 @@ async fn get_auth_logs_for_user(token: &str, domain: &str, user_id: &str) -> Vec
     resp.json::<Vec<NewAuthUserLogin>>().await.unwrap()
 }
 
-async fn get_auth_users_page(token: &str, domain: &str, page: &str) -> Vec<User> {
-    let client = Client::new();
+async fn get_auth_users_page(client: &cio_http::Client, token: &str, domain: &str, page: &str) -> Vec<User> {
     let resp = client
-        .get(&format!("https://{}.auth0.com/api/v2/users", domain))
-        .bearer_auth(token)
-        .query(&[("per_page", "20"), ("page", page), ("sort", "last_login:-1")])
-        .send()
+        .send(
+            client
+                .get(&format!("https://{}.auth0.com/api/v2/users", domain))
+                .bearer_auth(token)
+                .query(&[("per_page", "20"), ("page", page), ("sort", "last_login:-1")]),
+        )
         .await
         .unwrap();
 
@@ -409,25 +407,8 @@ pub async fn refresh_auth_users_and_logins(db: &Database) {
 
     // Sync auth users.
     for auth_user in auth_users {
-        auth_user.upsert(db).await;
-    }
-}
-
-#[cfg(test)]
-mod tests {
-    use crate::auth_logins::{refresh_auth_users_and_logins, AuthUserLogins, AuthUsers};
-    use crate::db::Database;
-
-    #[ignore]
-    #[tokio::test(flavor = "multi_thread")]
-    async fn test_cron_auth_users_and_logins_refresh() {
-        // Initialize our database.
-        let db = Database::new();
-
-        refresh_auth_users_and_logins(&db).await;
-
-        // Update auth user and auth user logins in airtable.
-        AuthUserLogins::get_from_db(&db).update_airtable().await;
-        AuthUsers::get_from_db(&db).update_airtable().await;
+        if let Err(e) = auth_user.upsert(db).await {
+            println!("upserting auth user {} failed: {}", auth_user.email, e);
+        }
     }
 }
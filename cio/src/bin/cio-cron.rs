@@ -0,0 +1,57 @@
+use std::process;
+
+use chrono::Utc;
+use clap::{App, Arg, SubCommand};
+
+use cio_api::cron::{run_job, serve, CronJob, CronJobStatus, JOBS};
+use cio_api::db::Database;
+
+#[tokio::main]
+async fn main() {
+    let matches = App::new("cio-cron")
+        .about("Run the jobs that keep the database and Airtable in sync")
+        .subcommand(SubCommand::with_name("serve").about("Run the scheduler and start each job when it is due (default)"))
+        .subcommand(
+            SubCommand::with_name("run")
+                .about("Run a single job now")
+                .arg(Arg::with_name("job").required(true).help("The name of the job, see `cio-cron list`")),
+        )
+        .subcommand(SubCommand::with_name("list").about("List the jobs and when they next run"))
+        .get_matches();
+
+    match matches.subcommand() {
+        ("list", _) => {
+            for job in JOBS {
+                let next = job.schedule().upcoming(Utc).next().map(|n| n.to_rfc3339()).unwrap_or_default();
+                println!("{:<32} {:<18} timeout {:>5}s  next {}", job.name, job.schedule, job.timeout.as_secs(), next);
+            }
+        }
+        ("run", Some(m)) => {
+            let name = m.value_of("job").unwrap();
+            let job = match CronJob::get(name) {
+                Some(j) => j,
+                None => {
+                    eprintln!("unknown job `{}`, see `cio-cron list` for the available jobs", name);
+                    process::exit(1);
+                }
+            };
+
+            let db = Database::new();
+            match run_job(&db, job).await {
+                Ok(run) => {
+                    if run.status != CronJobStatus::Success.to_string() {
+                        process::exit(1);
+                    }
+                }
+                Err(e) => {
+                    eprintln!("recording run of {} failed: {}", job.name, e);
+                    process::exit(1);
+                }
+            }
+        }
+        _ => {
+            println!("[cron] starting scheduler with {} jobs", JOBS.len());
+            serve(Database::new()).await;
+        }
+    }
+}
@@ -1,5 +1,6 @@
 #![allow(clippy::from_over_into)]
 use std::collections::BTreeMap;
+use std::collections::BTreeSet;
 use std::collections::HashMap;
 use std::env;
 use std::fs;
@@ -17,7 +18,6 @@ use hubcaps::Github;
 use macros::db;
 use ramp_api::Ramp;
 use schemars::JsonSchema;
-use sendgrid_api::SendGrid;
 use serde::{Deserialize, Serialize};
 
 use crate::airtable::{AIRTABLE_BASE_ID_DIRECTORY, AIRTABLE_BUILDINGS_TABLE, AIRTABLE_CONFERENCE_ROOMS_TABLE, AIRTABLE_EMPLOYEES_TABLE, AIRTABLE_GROUPS_TABLE, AIRTABLE_LINKS_TABLE};
@@ -25,7 +25,9 @@ use crate::applicants::Applicant;
 use crate::certs::{Certificate, Certificates, NewCertificate};
 use crate::core::UpdateAirtableRecord;
 use crate::db::Database;
+use crate::email_templates::{sync_email_templates, EmailTemplateConfig, EmailTemplates};
 use crate::gsuite::{update_google_group_settings, update_group_aliases, update_gsuite_building, update_gsuite_calendar_resource};
+use crate::offboarding::{refresh_offboardings, UserOffboarding};
 use crate::schema::{applicants, buildings, conference_rooms, groups, links, users};
 use crate::shipments::NewOutboundShipment;
 use crate::templates::{generate_terraform_files_for_aws_and_github, generate_terraform_files_for_okta};
@@ -49,6 +51,9 @@ pub struct Config {
 
     #[serde(default)]
     pub certificates: BTreeMap<String, NewCertificate>,
+
+    #[serde(default)]
+    pub email_templates: BTreeMap<String, EmailTemplateConfig>,
 }
 
 impl Config {
@@ -88,6 +93,9 @@ impl Config {
     match_on = {
         "username" = "String",
     },
+    filter_on = {
+        "department" = "String",
+    },
 }]
 #[derive(Debug, Insertable, AsChangeset, PartialEq, Clone, JsonSchema, Deserialize, Serialize)]
 #[table_name = "users"]
@@ -455,172 +463,59 @@ impl User {
     /// - Create a record in outgoing shipments.
     /// - Generate the shippo label.
     /// - Print said shippo label.
-    pub async fn create_shipment_to_home_address(&self, db: &Database) {
+    pub async fn create_shipment_to_home_address(&self, db: &Database) -> Result<(), crate::Error> {
         // First let's check if the user even has an address.
         // If not we can return early.
         if self.home_address_formatted.is_empty() {
             println!("cannot create shipping label for user {} since we don't know their home address", self.username);
-            return;
+            return Ok(());
         }
 
         // Let's create the shipment.
         let new_shipment = NewOutboundShipment::from(self.clone());
         // Let's add it to our database.
-        let mut shipment = new_shipment.upsert(db).await;
+        let mut shipment = new_shipment.upsert(db).await?;
         // Create the shipment in shippo.
-        shipment.create_or_get_shippo_shipment(db).await;
+        shipment.create_or_get_shippo_shipment(db).await?;
         // Update airtable and the database again.
-        shipment.update(db).await;
+        shipment.update(db).await?;
+
+        Ok(())
+    }
+
+    /// Get the fields the user email templates are rendered against.
+    pub fn email_template_data(&self) -> serde_json::Value {
+        let mut data = serde_json::to_value(self).unwrap_or_default();
+        data["email"] = serde_json::json!(self.email());
+        data
     }
 
     /// Send an email to the new consultant about their account.
-    async fn send_email_new_consultant(&self) {
-        // Initialize the SendGrid client.
-        let sendgrid = SendGrid::new_from_env();
-
-        // Get the user's aliases if they have one.
-        let aliases = self.aliases.join(", ");
-
-        // Send the message.
-        sendgrid
-            .send_mail(
-                format!("Your New Email Account: {}", self.email()),
-                format!(
-                    "Yoyoyo {},
-
-You should have an email from Okta about setting up your account with them.
-We use Okta to authenticate to a number of different apps -- including
-Google Workspace. This includes email, calendar, drive, etc.
-
-After setting up your Okta account your email account with Google will be
-provisioned. You can then login to your email from: mail.corp.{}.
-Details for accessing are below.
-
-Website for Okta login: https://oxidecomputerlogin.okta.com
-Website for email login: https://mail.corp.{}
-Email: {}
-Aliases: {}
-
-Make sure you set up two-factor authentication for your account, or in one week
-you will be locked out.
-
-If you have any questions or your email does not work please email your
-administrator, who is cc-ed on this email. Spoiler alert it's Jess...
-jess@{}.
-
-xoxo,
-  The Onboarding Bot",
-                    self.first_name,
-                    DOMAIN,
-                    DOMAIN,
-                    self.email(),
-                    aliases,
-                    DOMAIN,
-                ),
-                vec![self.recovery_email.to_string()],
-                vec![self.email(), format!("jess@{}", DOMAIN)],
-                vec![],
-                format!("admin@{}", DOMAIN),
-            )
-            .await;
+    async fn send_email_new_consultant(&self, db: &Database) {
+        self.send_email_template(db, "user_new_consultant").await;
     }
 
     /// Send an email to the new user about their account.
-    async fn send_email_new_user(&self) {
-        // Initialize the SendGrid client.
-        let sendgrid = SendGrid::new_from_env();
-        let github_org = github_org();
-
-        // Get the user's aliases if they have one.
-        let aliases = self.aliases.join(", ");
-
-        let mut github_copy = format!(
-            "Your GitHub @{} has been added to our organization (https://github.com/{})
-and various teams within it. GitHub should have sent an email with instructions on
-accepting the invitation to our organization to the email you used
-when you signed up for GitHub. Or you can alternatively accept our invitation
-by going to https://github.com/{}.",
-            self.github, github_org, github_org
-        );
-        if self.github.is_empty() {
-            // Let the new hire know they need to create a GitHub account.
-            github_copy = format!(
-                "We do not have a github account for you. You will need to create one at https://github.com
-OR let jess@{} know your handle, if you already have one. Either way, be sure to
-let jess@{} know what your GitHub handle is.",
-                DOMAIN, DOMAIN
-            );
-        }
+    async fn send_email_new_user(&self, db: &Database) {
+        self.send_email_template(db, "user_new_account").await;
+    }
 
-        // Send the message.
-        sendgrid
-            .send_mail(
-                format!("Your New Email Account: {}", self.email()),
-                format!(
-                    "Yoyoyo {},
-
-You should have an email from Okta about setting up your account with them.
-We use Okta to authenticate to a number of different apps -- including
-Google Workspace and GitHub. This includes email, calendar, drive, etc.
-
-After setting up your Okta account your email account with Google will be
-provisioned. You can then login to your email from: mail.corp.{}.
-Details for accessing are below.
-
-Website for Okta login: https://oxidecomputerlogin.okta.com
-Website for email login: https://mail.corp.{}
-Email: {}
-Aliases: {}
-
-Make sure you set up two-factor authentication for your account, or in one week
-you will be locked out.
-
-{}
-
-If you have any questions or your email does not work please email your
-administrator, who is cc-ed on this email. Spoiler alert it's Jess...
-jess@{}. If you want other email aliases, let Jess know as well.
-
-You can find more onboarding information in GitHub:
-https://github.com/{}/meta/blob/master/general/onboarding.md
-You can find information about internal processes and applications at:
-https://github.com/{}/meta/blob/master/general/README.md
-
-As a first contribution to one of our repos, add a book
-to our internal library: https://github.com/{}/library
-
-We use Airtable for storing just about everything. You can login with single
-sign-on (SSO) after setting up your email at:
-https://airtable.com/sso/login.
-To join our Airtable workspace you need to click this link:
-https://airtable-join.corp.oxide.computer.
-Poke around once you've joined :)
-
-We have both a Riot server and a Slack for chat. Josh (josh@oxidecomputer.com) can get
-you set up with an account on the Riot server. You can use SSO to login to the Slack
-at https://oxidecomputer.slack.com.
-
-Lastly, be sure to order yourself some swag: https://swag.oxide.computer
-
-xoxo,
-  The Onboarding Bot",
-                    self.first_name,
-                    DOMAIN,
-                    DOMAIN,
-                    self.email(),
-                    aliases,
-                    github_copy,
-                    DOMAIN,
-                    github_org,
-                    github_org,
-                    github_org,
-                ),
-                vec![self.recovery_email.to_string()],
-                vec![self.email(), format!("jess@{}", DOMAIN)],
-                vec![],
-                format!("admin@{}", DOMAIN),
-            )
-            .await;
+    async fn send_email_template(&self, db: &Database, name: &str) {
+        let templates = EmailTemplates::load_or_defaults(db);
+
+        match templates.render(name, &self.email_template_data()) {
+            Ok(email) => {
+                email
+                    .send(
+                        vec![self.recovery_email.to_string()],
+                        vec![self.email(), format!("jess@{}", DOMAIN)],
+                        vec![],
+                        format!("admin@{}", DOMAIN),
+                    )
+                    .await
+            }
+            Err(e) => println!("rendering email template {} for user {} failed: {}", name, self.username, e),
+        }
     }
 }
 
@@ -630,25 +525,32 @@ impl UpdateAirtableRecord<User> for User {
     async fn update_airtable_record(&mut self, record: User) {
         // Get the current groups in Airtable so we can link to them.
         // TODO: make this more dry so we do not call it every single damn time.
-        let groups = Groups::get_from_airtable().await;
-
-        let mut links: Vec<String> = Default::default();
-        // Iterate over the group names in our record and match it against the
-        // group ids and see if we find a match.
-        for group in &self.groups {
-            // Iterate over the groups to get the ID.
-            for g in groups.values() {
-                if *group == g.fields.name {
-                    // Append the ID to our links.
-                    links.push(g.id.to_string());
-                    // Break the loop and return early.
-                    break;
+        match Groups::get_from_airtable().await {
+            Ok(groups) => {
+                let mut links: Vec<String> = Default::default();
+                // Iterate over the group names in our record and match it against the
+                // group ids and see if we find a match.
+                for group in &self.groups {
+                    // Iterate over the groups to get the ID.
+                    for g in groups.values() {
+                        if *group == g.fields.name {
+                            // Append the ID to our links.
+                            links.push(g.id.to_string());
+                            // Break the loop and return early.
+                            break;
+                        }
+                    }
                 }
+
+                self.groups = links;
+            }
+            Err(e) => {
+                // Keep the links we already have in Airtable rather than clobbering them.
+                println!("[airtable] getting groups to link user `{}` failed: {}", self.username, e);
+                self.groups = record.groups.clone();
             }
         }
 
-        self.groups = links;
-
         self.geocode_cache = record.geocode_cache.to_string();
 
         if self.start_date == crate::utils::default_date() && record.start_date != crate::utils::default_date() {
@@ -662,14 +564,21 @@ impl UpdateAirtableRecord<User> for User {
         // Set the building to right building link.
         // Get the current buildings in Airtable so we can link to it.
         // TODO: make this more dry so we do not call it every single damn time.
-        let buildings = Buildings::get_from_airtable().await;
-        // Iterate over the buildings to get the ID.
-        for building in buildings.values() {
-            if self.building == building.fields.name {
-                // Set the ID.
-                self.link_to_building = vec![building.id.to_string()];
-                // Break the loop and return early.
-                break;
+        match Buildings::get_from_airtable().await {
+            Ok(buildings) => {
+                // Iterate over the buildings to get the ID.
+                for building in buildings.values() {
+                    if self.building == building.fields.name {
+                        // Set the ID.
+                        self.link_to_building = vec![building.id.to_string()];
+                        // Break the loop and return early.
+                        break;
+                    }
+                }
+            }
+            Err(e) => {
+                println!("[airtable] getting buildings to link user `{}` failed: {}", self.username, e);
+                self.link_to_building = record.link_to_building.clone();
             }
         }
 
@@ -925,7 +834,13 @@ impl UpdateAirtableRecord<ConferenceRoom> for ConferenceRoom {
         // Set the building to right building link.
         // Get the current buildings in Airtable so we can link to it.
         // TODO: make this more dry so we do not call it every single damn time.
-        let buildings = Buildings::get_from_airtable().await;
+        let buildings = match Buildings::get_from_airtable().await {
+            Ok(v) => v,
+            Err(e) => {
+                println!("[airtable] getting buildings to link conference room `{}` failed: {}", self.name, e);
+                return;
+            }
+        };
         // Iterate over the buildings to get the ID.
         for building in buildings.values() {
             if self.building == building.fields.name {
@@ -962,6 +877,13 @@ pub struct LinkConfig {
     pub short_link: String,
 }
 
+impl LinkConfig {
+    /// Set the short link from the name of the link.
+    pub fn expand(&mut self) {
+        self.short_link = format!("https://{}.corp.{}", self.name, DOMAIN);
+    }
+}
+
 /// Implement updating the Airtable record for a Link.
 #[async_trait]
 impl UpdateAirtableRecord<Link> for Link {
@@ -996,29 +918,38 @@ pub struct HuddleConfig {
 }
 
 /// Get the configs from the GitHub repository and parse them.
-pub async fn get_configs_from_repo(github: &Github) -> Config {
+pub async fn get_configs_from_repo(github: &Github) -> Result<Config, crate::Error> {
+    let repo = github.repo(github_org(), "configs");
+    let r = repo.get().await?;
+
+    get_configs_from_repo_at_ref(github, &r.default_branch).await
+}
+
+/// Get the configs from a branch or commit of the GitHub repository and parse them.
+/// This is used to read the configs from a pull request before it is merged.
+pub async fn get_configs_from_repo_at_ref(github: &Github, git_ref: &str) -> Result<Config, crate::Error> {
     let repo = github.repo(github_org(), "configs");
-    let r = repo.get().await.unwrap();
     let repo_contents = repo.content();
 
-    let files = repo_contents.iter("/configs/", &r.default_branch).try_collect::<Vec<hubcaps::content::DirectoryItem>>().await.unwrap();
+    let files = repo_contents.iter("/configs/", git_ref).try_collect::<Vec<hubcaps::content::DirectoryItem>>().await?;
 
     let mut file_contents = String::new();
     for file in files {
         println!("decoding {}", file.name);
         // Get the contents of the file.
-        let contents = repo_contents.file(&format!("/{}", file.path), &r.default_branch).await.unwrap();
+        let contents = repo_contents.file(&format!("/{}", file.path), git_ref).await?;
 
-        let decoded = from_utf8(&contents.content).unwrap().trim().to_string();
+        let decoded = from_utf8(&contents.content)
+            .map_err(|e| crate::Error::Configs(format!("{} is not valid utf-8: {}", file.path, e)))?
+            .trim()
+            .to_string();
 
         // Append the body of the file to the rest of the contents.
         file_contents.push_str(&"\n");
         file_contents.push_str(&decoded);
     }
 
-    let config: Config = toml::from_str(&file_contents).unwrap();
-
-    config
+    Ok(toml::from_str(&file_contents)?)
 }
 
 /// Sync GitHub outside collaborators with our configs.
@@ -1058,13 +989,7 @@ pub async fn sync_github_outside_collaborators(github: &Github, outside_collabor
 }
 
 /// Sync our users with our database and then update Airtable from the database.
-pub async fn sync_users(db: &Database, github: &Github, users: BTreeMap<String, UserConfig>) {
-    // Get everything we need to authenticate with GSuite.
-    // Initialize the GSuite client.
-    let gsuite_customer = env::var("GADMIN_ACCOUNT_ID").unwrap();
-    let token = get_gsuite_token("").await;
-    let gsuite = GSuite::new(&gsuite_customer, GSUITE_DOMAIN, token);
-
+pub async fn sync_users(db: &Database, github: &Github, users: BTreeMap<String, UserConfig>) -> Result<(), crate::Error> {
     // Initialize the Ramp client.
     let ramp = Ramp::new_from_env().await;
     let ru = ramp.list_users().await.unwrap();
@@ -1079,23 +1004,12 @@ pub async fn sync_users(db: &Database, github: &Github, users: BTreeMap<String,
         ramp_departments.insert(r.name.to_string(), r);
     }
 
-    // Find the anniversary calendar.
-    // Get the list of our calendars.
-    let calendars = gsuite.list_calendars().await.unwrap();
-
-    let mut anniversary_cal_id = "".to_string();
-
-    // Iterate over the calendars.
-    for calendar in calendars {
-        if calendar.summary.contains("Anniversaries") {
-            // We are on the anniversaries calendar.
-            anniversary_cal_id = calendar.id;
-            break;
-        }
-    }
+    // Keep track of who is in the config so we do not offboard anyone who was
+    // added back.
+    let usernames: BTreeSet<String> = users.values().map(|u| u.username.to_string()).collect();
 
     // Get all the users.
-    let db_users = Users::get_from_db(db);
+    let db_users = Users::get_from_db(db)?;
     // Create a BTreeMap
     let mut user_map: BTreeMap<String, User> = Default::default();
     for u in db_users {
@@ -1103,6 +1017,11 @@ pub async fn sync_users(db: &Database, github: &Github, users: BTreeMap<String,
     }
     // Sync users.
     for (_, mut user) in users {
+        // Remove the user from the BTreeMap.
+        // We do this first so that a user who fails to sync below is not
+        // mistaken for one who was removed from the config.
+        user_map.remove(&user.username);
+
         // Check if we already have the new user in the database.
         let existing = User::get_from_db(db, user.username.to_string());
 
@@ -1111,7 +1030,13 @@ pub async fn sync_users(db: &Database, github: &Github, users: BTreeMap<String,
             user.google_anniversary_event_id = e.google_anniversary_event_id.to_string();
 
             // TODO: remove this when we populate from Gusto.
-            let airtable_record = e.get_existing_airtable_record().await.unwrap();
+            let airtable_record = match e.get_existing_airtable_record().await {
+                Some(r) => r,
+                None => {
+                    println!("could not find user {} in airtable, skipping", user.username);
+                    continue;
+                }
+            };
             user.home_address_street_1 = airtable_record.fields.home_address_street_1.to_string();
             user.home_address_street_2 = airtable_record.fields.home_address_street_2.to_string();
             user.home_address_city = airtable_record.fields.home_address_city.to_string();
@@ -1128,7 +1053,13 @@ pub async fn sync_users(db: &Database, github: &Github, users: BTreeMap<String,
 
         user.expand(db).await;
 
-        let new_user = user.upsert(db).await;
+        let new_user = match user.upsert(db).await {
+            Ok(u) => u,
+            Err(e) => {
+                println!("upserting user {} failed, skipping: {}", user.username, e);
+                continue;
+            }
+        };
 
         if existing.is_none() {
             // Now we need to update Okta to include the new user.
@@ -1136,7 +1067,9 @@ pub async fn sync_users(db: &Database, github: &Github, users: BTreeMap<String,
             // they should have a Google account by then.
             // Sync okta users and group from the database.
             // Do this after we update the users and groups in the database.
-            generate_terraform_files_for_okta(github, db).await;
+            if let Err(e) = generate_terraform_files_for_okta(github, db).await {
+                println!("generating okta terraform files for new user {} failed: {}", new_user.username, e);
+            }
             // TODO: this is horrible, but we will sleep here to allow the terraform
             // job to run.
             // We also need a better way to ensure the terraform job passed...
@@ -1146,9 +1079,9 @@ pub async fn sync_users(db: &Database, github: &Github, users: BTreeMap<String,
             // We should send them an email about setting up their account.
             println!("sending email to new user: {}", new_user.username);
             if new_user.is_consultant() {
-                new_user.send_email_new_consultant().await;
+                new_user.send_email_new_consultant(db).await;
             } else {
-                new_user.send_email_new_user().await;
+                new_user.send_email_new_user(db).await;
             }
         }
 
@@ -1176,33 +1109,36 @@ pub async fn sync_users(db: &Database, github: &Github, users: BTreeMap<String,
                 }
             }
         }
-
-        // Remove the user from the BTreeMap.
-        user_map.remove(&user.username);
     }
     // Remove any users that should no longer be in the database.
     // This is found by the remaining users that are in the map since we removed
     // the existing repos from the map above.
     for (username, user) in user_map {
-        println!("deleting user {} from the database", username);
-
-        if !user.google_anniversary_event_id.is_empty() {
-            // First delete the recurring event for their anniversary.
-            gsuite.delete_calendar_event(&anniversary_cal_id, &user.google_anniversary_event_id).await.unwrap();
-            println!("deleted user {} event {} from google", username, user.google_anniversary_event_id);
+        // Record what we need to offboard the user before we delete them, the
+        // offboarding itself runs below.
+        if let Err(e) = UserOffboarding::start(db, &user) {
+            println!("starting offboarding for user {} failed, not deleting them: {}", username, e);
+            continue;
         }
 
+        println!("deleting user {} from the database", username);
         // Delete the user from the database and Airtable.
-        user.delete(db).await;
+        if let Err(e) = user.delete(db).await {
+            println!("deleting user {} failed: {}", username, e);
+        }
     }
     println!("updated configs users in the database");
 
+    // Offboard the users who were removed, this also picks up any offboardings
+    // that did not finish on a previous run.
+    refresh_offboardings(db, github, &usernames).await?;
+
     // Update users in airtable.
-    Users::get_from_db(db).update_airtable().await;
+    Users::get_from_db(db)?.update_airtable(db).await
 }
 
 /// Sync our buildings with our database and then update Airtable from the database.
-pub async fn sync_buildings(db: &Database, buildings: BTreeMap<String, BuildingConfig>) {
+pub async fn sync_buildings(db: &Database, buildings: BTreeMap<String, BuildingConfig>) -> Result<(), crate::Error> {
     // Get everything we need to authenticate with GSuite.
     // Initialize the GSuite client.
     let gsuite_customer = env::var("GADMIN_ACCOUNT_ID").unwrap();
@@ -1213,7 +1149,7 @@ pub async fn sync_buildings(db: &Database, buildings: BTreeMap<String, BuildingC
     let gsuite_buildings = gsuite.list_buildings().await.unwrap();
 
     // Get all the buildings.
-    let db_buildings = Buildings::get_from_db(db);
+    let db_buildings = Buildings::get_from_db(db)?;
     // Create a BTreeMap
     let mut building_map: BTreeMap<String, Building> = Default::default();
     for u in db_buildings {
@@ -1223,7 +1159,9 @@ pub async fn sync_buildings(db: &Database, buildings: BTreeMap<String, BuildingC
     for (_, mut building) in buildings {
         building.expand();
 
-        building.upsert(db).await;
+        if let Err(e) = building.upsert(db).await {
+            println!("upserting building {} failed: {}", building.name, e);
+        }
 
         // Remove the building from the BTreeMap.
         building_map.remove(&building.name);
@@ -1234,7 +1172,10 @@ pub async fn sync_buildings(db: &Database, buildings: BTreeMap<String, BuildingC
     for (name, building) in building_map {
         println!("deleting building {} from the database, gsuite, etc", name);
 
-        building.delete(db).await;
+        if let Err(e) = building.delete(db).await {
+            println!("deleting building {} from the database failed: {}", name, e);
+            continue;
+        }
 
         // Delete the building from GSuite.
         gsuite.delete_building(&name).await.unwrap_or_else(|e| panic!("deleting building {} from gsuite failed: {}", name, e));
@@ -1244,7 +1185,7 @@ pub async fn sync_buildings(db: &Database, buildings: BTreeMap<String, BuildingC
 
     // Update the buildings in GSuite.
     // Get all the buildings.
-    let db_buildings = Buildings::get_from_db(db);
+    let db_buildings = Buildings::get_from_db(db)?;
     // Create a BTreeMap
     let mut building_map: BTreeMap<String, Building> = Default::default();
     for u in db_buildings {
@@ -1294,11 +1235,11 @@ pub async fn sync_buildings(db: &Database, buildings: BTreeMap<String, BuildingC
     }
 
     // Update buildings in airtable.
-    Buildings::get_from_db(db).update_airtable().await;
+    Buildings::get_from_db(db)?.update_airtable(db).await
 }
 
 /// Sync our conference_rooms with our database and then update Airtable from the database.
-pub async fn sync_conference_rooms(db: &Database, conference_rooms: BTreeMap<String, ResourceConfig>) {
+pub async fn sync_conference_rooms(db: &Database, conference_rooms: BTreeMap<String, ResourceConfig>) -> Result<(), crate::Error> {
     // Get everything we need to authenticate with GSuite.
     // Initialize the GSuite client.
     let gsuite_customer = env::var("GADMIN_ACCOUNT_ID").unwrap();
@@ -1309,7 +1250,7 @@ pub async fn sync_conference_rooms(db: &Database, conference_rooms: BTreeMap<Str
     let g_suite_calendar_resources = gsuite.list_calendar_resources().await.unwrap();
 
     // Get all the conference_rooms.
-    let db_conference_rooms = ConferenceRooms::get_from_db(db);
+    let db_conference_rooms = ConferenceRooms::get_from_db(db)?;
     // Create a BTreeMap
     let mut conference_room_map: BTreeMap<String, ConferenceRoom> = Default::default();
     for u in db_conference_rooms {
@@ -1317,7 +1258,9 @@ pub async fn sync_conference_rooms(db: &Database, conference_rooms: BTreeMap<Str
     }
     // Sync conference_rooms.
     for (_, conference_room) in conference_rooms {
-        conference_room.upsert(db).await;
+        if let Err(e) = conference_room.upsert(db).await {
+            println!("upserting conference room {} failed: {}", conference_room.name, e);
+        }
 
         // Remove the conference_room from the BTreeMap.
         conference_room_map.remove(&conference_room.name);
@@ -1327,13 +1270,15 @@ pub async fn sync_conference_rooms(db: &Database, conference_rooms: BTreeMap<Str
     // the existing repos from the map above.
     for (name, room) in conference_room_map {
         println!("deleting conference room {} from the database", name);
-        room.delete(db).await;
+        if let Err(e) = room.delete(db).await {
+            println!("deleting conference room {} from the database failed: {}", name, e);
+        }
     }
     println!("updated configs conference_rooms in the database");
 
     // Update the conference_rooms in GSuite.
     // Get all the conference_rooms.
-    let db_conference_rooms = ConferenceRooms::get_from_db(db);
+    let db_conference_rooms = ConferenceRooms::get_from_db(db)?;
     // Create a BTreeMap
     let mut conference_room_map: BTreeMap<String, ConferenceRoom> = Default::default();
     for u in db_conference_rooms {
@@ -1392,11 +1337,11 @@ pub async fn sync_conference_rooms(db: &Database, conference_rooms: BTreeMap<Str
     }
 
     // Update conference_rooms in airtable.
-    ConferenceRooms::get_from_db(db).update_airtable().await;
+    ConferenceRooms::get_from_db(db)?.update_airtable(db).await
 }
 
 /// Sync our groups with our database and then update Airtable from the database.
-pub async fn sync_groups(db: &Database, groups: BTreeMap<String, GroupConfig>) {
+pub async fn sync_groups(db: &Database, groups: BTreeMap<String, GroupConfig>) -> Result<(), crate::Error> {
     // Get everything we need to authenticate with GSuite.
     // Initialize the GSuite client.
     let gsuite_customer = env::var("GADMIN_ACCOUNT_ID").unwrap();
@@ -1407,7 +1352,7 @@ pub async fn sync_groups(db: &Database, groups: BTreeMap<String, GroupConfig>) {
     let gsuite_groups = gsuite.list_groups().await.unwrap();
 
     // Get all the groups.
-    let db_groups = Groups::get_from_db(db);
+    let db_groups = Groups::get_from_db(db)?;
     // Create a BTreeMap
     let mut group_map: BTreeMap<String, Group> = Default::default();
     for u in db_groups {
@@ -1417,7 +1362,9 @@ pub async fn sync_groups(db: &Database, groups: BTreeMap<String, GroupConfig>) {
     for (_, mut group) in groups {
         group.expand();
 
-        group.upsert(db).await;
+        if let Err(e) = group.upsert(db).await {
+            println!("upserting group {} failed: {}", group.name, e);
+        }
 
         // Remove the group from the BTreeMap.
         group_map.remove(&group.name);
@@ -1429,7 +1376,10 @@ pub async fn sync_groups(db: &Database, groups: BTreeMap<String, GroupConfig>) {
         println!("deleting group {} from the database, gsuite, etc", name);
 
         // Delete the group from the database and Airtable.
-        group.delete(db).await;
+        if let Err(e) = group.delete(db).await {
+            println!("deleting group {} from the database failed: {}", name, e);
+            continue;
+        }
 
         // Remove the group from GSuite.
         gsuite
@@ -1442,7 +1392,7 @@ pub async fn sync_groups(db: &Database, groups: BTreeMap<String, GroupConfig>) {
 
     // Update the groups in GSuite.
     // Get all the groups.
-    let db_groups = Groups::get_from_db(db);
+    let db_groups = Groups::get_from_db(db)?;
     // Create a BTreeMap
     let mut group_map: BTreeMap<String, Group> = Default::default();
     for u in db_groups {
@@ -1520,27 +1470,18 @@ pub async fn sync_groups(db: &Database, groups: BTreeMap<String, GroupConfig>) {
     }
 
     // Update groups in airtable.
-    Groups::get_from_db(db).update_airtable().await;
+    Groups::get_from_db(db)?.update_airtable(db).await
 }
 
-/// Sync our links with our database and then update Airtable from the database.
-pub async fn sync_links(db: &Database, links: BTreeMap<String, LinkConfig>, huddles: BTreeMap<String, HuddleConfig>) {
-    // Get all the links.
-    let db_links = Links::get_from_db(db);
-    // Create a BTreeMap
-    let mut link_map: BTreeMap<String, Link> = Default::default();
-    for u in db_links {
-        link_map.insert(u.name.to_string(), u);
-    }
-    // Sync links.
+/// Get all the links we should have in the database, including the links for the
+/// workspace and form of each huddle, keyed by name.
+pub fn expand_links(links: BTreeMap<String, LinkConfig>, huddles: BTreeMap<String, HuddleConfig>) -> BTreeMap<String, LinkConfig> {
+    let mut expanded: BTreeMap<String, LinkConfig> = Default::default();
     for (name, mut link) in links {
         link.name = name.to_string();
-        link.short_link = format!("https://{}.corp.{}", name, DOMAIN);
-
-        link.upsert(db).await;
+        link.expand();
 
-        // Remove the link from the BTreeMap.
-        link_map.remove(&link.name);
+        expanded.insert(name, link);
     }
     for (slug, huddle) in huddles {
         // Create the link for the workspace.
@@ -1551,20 +1492,34 @@ pub async fn sync_links(db: &Database, links: BTreeMap<String, LinkConfig>, hudd
             aliases: vec![format!("airtable-{}-huddle", slug)],
             short_link: format!("https://{}-huddle.corp.{}", slug, DOMAIN),
         };
+        expanded.insert(link.name.to_string(), link.clone());
 
-        link.upsert(db).await;
-
-        // Remove the link from the BTreeMap.
-        link_map.remove(&link.name);
-
-        // Update the link for the form.
+        // Create the link for the form.
         link.name = format!("{}-huddle-form", slug);
         link.link = huddle.link_to_airtable_form.to_string();
         link.aliases = vec![format!("airtable-{}-huddle-form", slug)];
         link.short_link = format!("https://{}-huddle-form.corp.{}", slug, DOMAIN);
         link.description = format!("Form for submitting topics to the {}", huddle.description.to_lowercase());
+        expanded.insert(link.name.to_string(), link);
+    }
 
-        link.upsert(db).await;
+    expanded
+}
+
+/// Sync our links with our database and then update Airtable from the database.
+pub async fn sync_links(db: &Database, links: BTreeMap<String, LinkConfig>, huddles: BTreeMap<String, HuddleConfig>) -> Result<(), crate::Error> {
+    // Get all the links.
+    let db_links = Links::get_from_db(db)?;
+    // Create a BTreeMap
+    let mut link_map: BTreeMap<String, Link> = Default::default();
+    for u in db_links {
+        link_map.insert(u.name.to_string(), u);
+    }
+    // Sync links.
+    for (_, link) in expand_links(links, huddles) {
+        if let Err(e) = link.upsert(db).await {
+            println!("upserting link {} failed: {}", link.name, e);
+        }
 
         // Remove the link from the BTreeMap.
         link_map.remove(&link.name);
@@ -1573,18 +1528,20 @@ pub async fn sync_links(db: &Database, links: BTreeMap<String, LinkConfig>, hudd
     // This is found by the remaining links that are in the map since we removed
     // the existing repos from the map above.
     for (_, link) in link_map {
-        link.delete(db).await;
+        if let Err(e) = link.delete(db).await {
+            println!("deleting link {} failed: {}", link.name, e);
+        }
     }
     println!("updated configs links in the database");
 
     // Update links in airtable.
-    Links::get_from_db(db).update_airtable().await;
+    Links::get_from_db(db)?.update_airtable(db).await
 }
 
 /// Sync our certificates with our database and then update Airtable from the database.
-pub async fn sync_certificates(db: &Database, github: &Github, certificates: BTreeMap<String, NewCertificate>) {
+pub async fn sync_certificates(db: &Database, github: &Github, certificates: BTreeMap<String, NewCertificate>) -> Result<(), crate::Error> {
     // Get all the certificates.
-    let db_certificates = Certificates::get_from_db(db);
+    let db_certificates = Certificates::get_from_db(db)?;
     // Create a BTreeMap
     let mut certificate_map: BTreeMap<String, Certificate> = Default::default();
     for u in db_certificates {
@@ -1607,7 +1564,9 @@ pub async fn sync_certificates(db: &Database, github: &Github, certificates: BTr
         }
 
         // Update the database and Airtable.
-        certificate.upsert(db).await;
+        if let Err(e) = certificate.upsert(db).await {
+            println!("upserting certificate {} failed: {}", certificate.domain, e);
+        }
 
         // Remove the certificate from the BTreeMap.
         certificate_map.remove(&certificate.domain);
@@ -1616,51 +1575,327 @@ pub async fn sync_certificates(db: &Database, github: &Github, certificates: BTr
     // This is found by the remaining certificates that are in the map since we removed
     // the existing repos from the map above.
     for (_, cert) in certificate_map {
-        cert.delete(db).await;
+        if let Err(e) = cert.delete(db).await {
+            println!("deleting certificate {} failed: {}", cert.domain, e);
+        }
     }
     println!("updated configs certificates in the database");
 
     // Update certificates in airtable.
-    Certificates::get_from_db(db).update_airtable().await;
+    Certificates::get_from_db(db)?.update_airtable(db).await
 }
 
-pub async fn refresh_db_configs_and_airtable(github: &Github) {
-    let configs = get_configs_from_repo(github).await;
+/// The fields of a user that are set in the config files. Everything else on a
+/// user is populated from Gusto, GitHub or Airtable when we sync.
+const USER_PLAN_FIELDS: &[&str] = &[
+    "first_name",
+    "last_name",
+    "aliases",
+    "recovery_email",
+    "recovery_phone",
+    "gender",
+    "chat",
+    "github",
+    "twitter",
+    "department",
+    "manager",
+    "groups",
+    "is_group_admin",
+    "building",
+    "aws_role",
+    "type",
+];
+
+/// The fields of a group that are set in the config files. The members are
+/// populated from the users.
+const GROUP_PLAN_FIELDS: &[&str] = &[
+    "description",
+    "link",
+    "aliases",
+    "allow_external_members",
+    "allow_web_posting",
+    "is_archived",
+    "who_can_discover_group",
+    "who_can_join",
+    "who_can_moderate_members",
+    "who_can_post_message",
+    "who_can_view_group",
+    "who_can_view_membership",
+    "enable_collaborative_inbox",
+];
+
+/// The fields of a building that are set in the config files. The employees and
+/// conference rooms are populated from their own tables.
+const BUILDING_PLAN_FIELDS: &[&str] = &["description", "street_address", "city", "state", "zipcode", "country", "address_formatted", "floors"];
+
+/// The fields of a conference room that are set in the config files.
+const CONFERENCE_ROOM_PLAN_FIELDS: &[&str] = &["description", "type", "building", "capacity", "floor", "section"];
+
+/// The fields of a link that are set in the config files.
+const LINK_PLAN_FIELDS: &[&str] = &["description", "link", "aliases", "short_link"];
+
+/// The action a sync would take on a record.
+#[derive(Debug, Clone, Copy, PartialEq, JsonSchema, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigChangeAction {
+    Create,
+    Update,
+    Delete,
+}
+
+/// A field that would change on a record.
+#[derive(Debug, Clone, PartialEq, JsonSchema, Deserialize, Serialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: serde_json::Value,
+    pub new: serde_json::Value,
+}
+
+/// A record that would be created, updated or deleted by a sync.
+#[derive(Debug, Clone, PartialEq, JsonSchema, Deserialize, Serialize)]
+pub struct ConfigChange {
+    pub name: String,
+    pub action: ConfigChangeAction,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldChange>,
+}
+
+/// The changes syncing a set of configs would make to the database, computed
+/// without changing anything.
+#[derive(Debug, Default, Clone, PartialEq, JsonSchema, Deserialize, Serialize)]
+pub struct ConfigPlan {
+    pub users: Vec<ConfigChange>,
+    pub groups: Vec<ConfigChange>,
+    pub buildings: Vec<ConfigChange>,
+    pub conference_rooms: Vec<ConfigChange>,
+    pub links: Vec<ConfigChange>,
+    pub certificates: Vec<ConfigChange>,
+}
+
+impl ConfigPlan {
+    /// Returns true if syncing would not change anything.
+    pub fn is_empty(&self) -> bool {
+        self.users.is_empty() && self.groups.is_empty() && self.buildings.is_empty() && self.conference_rooms.is_empty() && self.links.is_empty() && self.certificates.is_empty()
+    }
+
+    /// Render the plan as markdown, for commenting on pull requests to the
+    /// configs repo.
+    pub fn to_markdown(&self) -> String {
+        if self.is_empty() {
+            return "This will not change any users, groups, buildings, conference rooms, links or certificates.".to_string();
+        }
+
+        let mut markdown = String::new();
+        for (title, changes) in &[
+            ("Users", &self.users),
+            ("Groups", &self.groups),
+            ("Buildings", &self.buildings),
+            ("Conference rooms", &self.conference_rooms),
+            ("Links", &self.links),
+            ("Certificates", &self.certificates),
+        ] {
+            if changes.is_empty() {
+                continue;
+            }
+
+            markdown.push_str(&format!("#### {}\n\n", title));
+            for change in changes.iter() {
+                let action = match change.action {
+                    ConfigChangeAction::Create => "create",
+                    ConfigChangeAction::Update => "update",
+                    ConfigChangeAction::Delete => "delete",
+                };
+                markdown.push_str(&format!("- **{}** `{}`\n", action, change.name));
+                for field in &change.fields {
+                    if change.action == ConfigChangeAction::Create {
+                        markdown.push_str(&format!("  - `{}`: `{}`\n", field.field, field.new));
+                    } else {
+                        markdown.push_str(&format!("  - `{}`: `{}` → `{}`\n", field.field, field.old, field.new));
+                    }
+                }
+            }
+            markdown.push('\n');
+        }
+
+        if self.users.iter().any(|c| c.action == ConfigChangeAction::Create) {
+            markdown.push_str("New users will get a welcome email and a Ramp invite once this is merged.\n");
+        }
+        if self.users.iter().any(|c| c.action == ConfigChangeAction::Delete) {
+            markdown.push_str("Deleted users will be offboarded from every provider once this is merged.\n");
+        }
+
+        markdown.trim_end().to_string()
+    }
+}
+
+/// Compute the changes syncing the configs would make to the database, without
+/// changing anything.
+pub fn plan_configs(db: &Database, configs: &Config) -> Result<ConfigPlan, crate::Error> {
+    // Only apply the parts of `expand` that come from the config itself, the rest
+    // are fields we do not compare.
+    let mut users: BTreeMap<String, UserConfig> = Default::default();
+    for user in configs.users.values() {
+        let mut user = user.clone();
+        user.populate_type();
+        user.ensure_all_aliases();
+        user.ensure_all_groups();
+        users.insert(user.username.to_string(), user);
+    }
+    let db_users = Users::get_from_db(db)?.into_iter().map(|u| (u.username.to_string(), u)).collect();
+
+    let mut groups: BTreeMap<String, GroupConfig> = Default::default();
+    for group in configs.groups.values() {
+        let mut group = group.clone();
+        group.expand();
+        groups.insert(group.name.to_string(), group);
+    }
+    let db_groups = Groups::get_from_db(db)?.into_iter().map(|g| (g.name.to_string(), g)).collect();
+
+    let mut buildings: BTreeMap<String, BuildingConfig> = Default::default();
+    for building in configs.buildings.values() {
+        let mut building = building.clone();
+        building.expand();
+        buildings.insert(building.name.to_string(), building);
+    }
+    let db_buildings = Buildings::get_from_db(db)?.into_iter().map(|b| (b.name.to_string(), b)).collect();
+
+    let conference_rooms: BTreeMap<String, ResourceConfig> = configs.resources.values().map(|r| (r.name.to_string(), r.clone())).collect();
+    let db_conference_rooms = ConferenceRooms::get_from_db(db)?.into_iter().map(|r| (r.name.to_string(), r)).collect();
+
+    let links = expand_links(configs.links.clone(), configs.huddles.clone());
+    let db_links = Links::get_from_db(db)?.into_iter().map(|l| (l.name.to_string(), l)).collect();
+
+    // The rest of a certificate is populated from Let's Encrypt, so we only care
+    // which domains we have.
+    let certificates: BTreeMap<String, NewCertificate> = configs.certificates.values().map(|c| (c.domain.to_string(), c.clone())).collect();
+    let db_certificates = Certificates::get_from_db(db)?.into_iter().map(|c| (c.domain.to_string(), c)).collect();
+
+    Ok(ConfigPlan {
+        users: plan_changes(users, db_users, USER_PLAN_FIELDS)?,
+        groups: plan_changes(groups, db_groups, GROUP_PLAN_FIELDS)?,
+        buildings: plan_changes(buildings, db_buildings, BUILDING_PLAN_FIELDS)?,
+        conference_rooms: plan_changes(conference_rooms, db_conference_rooms, CONFERENCE_ROOM_PLAN_FIELDS)?,
+        links: plan_changes(links, db_links, LINK_PLAN_FIELDS)?,
+        certificates: plan_changes(certificates, db_certificates, &[])?,
+    })
+}
+
+/// Compare the records we want with the records we have, both keyed by the
+/// field they are matched on, and return what would change for the given fields.
+fn plan_changes<D: Serialize, E: Serialize>(desired: BTreeMap<String, D>, mut existing: BTreeMap<String, E>, fields: &[&str]) -> Result<Vec<ConfigChange>, crate::Error> {
+    let mut changes: Vec<ConfigChange> = Default::default();
+    for (name, record) in desired {
+        let new = serde_json::to_value(&record)?;
+
+        match existing.remove(&name) {
+            Some(e) => {
+                let old = serde_json::to_value(&e)?;
+                let fields = diff_fields(&old, &new, fields);
+                if !fields.is_empty() {
+                    changes.push(ConfigChange {
+                        name,
+                        action: ConfigChangeAction::Update,
+                        fields,
+                    });
+                }
+            }
+            None => changes.push(ConfigChange {
+                name,
+                action: ConfigChangeAction::Create,
+                fields: diff_fields(&serde_json::Value::Null, &new, fields),
+            }),
+        }
+    }
+
+    // Anything left over is no longer in the configs.
+    for (name, _) in existing {
+        changes.push(ConfigChange {
+            name,
+            action: ConfigChangeAction::Delete,
+            fields: Default::default(),
+        });
+    }
+
+    Ok(changes)
+}
+
+fn diff_fields(old: &serde_json::Value, new: &serde_json::Value, fields: &[&str]) -> Vec<FieldChange> {
+    let mut changes: Vec<FieldChange> = Default::default();
+    for field in fields {
+        let o = old.get(field).cloned().unwrap_or_default();
+        let n = new.get(field).cloned().unwrap_or_default();
+
+        // Empty fields are skipped when we serialize, so treat a missing field the
+        // same as an empty one.
+        if o == n || (is_empty_value(&o) && is_empty_value(&n)) {
+            continue;
+        }
+
+        changes.push(FieldChange {
+            field: field.to_string(),
+            old: o,
+            new: n,
+        });
+    }
+
+    changes
+}
+
+fn is_empty_value(value: &serde_json::Value) -> bool {
+    match value {
+        serde_json::Value::Null => true,
+        serde_json::Value::Bool(b) => !b,
+        serde_json::Value::Number(n) => n.as_f64() == Some(0.0),
+        serde_json::Value::String(s) => s.is_empty(),
+        serde_json::Value::Array(a) => a.is_empty(),
+        serde_json::Value::Object(o) => o.is_empty(),
+    }
+}
+
+pub async fn refresh_db_configs_and_airtable(github: &Github) -> Result<(), crate::Error> {
+    let configs = get_configs_from_repo(github).await?;
 
     // Initialize our database.
     let db = Database::new();
 
+    // Sync email templates.
+    // Syncing email templates must happen before we sync the users, so new
+    // users get the latest wording.
+    sync_email_templates(&db, configs.email_templates)?;
+
     // Sync buildings.
     // Syncing buildings must happen before we sync conference rooms.
-    sync_buildings(&db, configs.buildings).await;
+    sync_buildings(&db, configs.buildings).await?;
 
     // Sync conference rooms.
-    sync_conference_rooms(&db, configs.resources).await;
+    sync_conference_rooms(&db, configs.resources).await?;
 
     // Sync groups.
     // Syncing groups must happen before we sync the users.
-    sync_groups(&db, configs.groups).await;
+    sync_groups(&db, configs.groups).await?;
 
     // Sync users.
-    sync_users(&db, github, configs.users).await;
+    sync_users(&db, github, configs.users).await?;
 
     // Sync okta users and group from the database.
     // Do this after we update the users and groups in the database.
-    generate_terraform_files_for_okta(github, &db).await;
+    generate_terraform_files_for_okta(github, &db).await?;
     // Generate the terraform files for teams.
-    generate_terraform_files_for_aws_and_github(github, &db).await;
+    generate_terraform_files_for_aws_and_github(github, &db).await?;
 
     // Sync links.
-    sync_links(&db, configs.links, configs.huddles).await;
+    sync_links(&db, configs.links, configs.huddles).await?;
 
     // Sync certificates.
-    sync_certificates(&db, github, configs.certificates).await;
+    sync_certificates(&db, github, configs.certificates).await?;
 
     // Sync github outside collaborators.
     sync_github_outside_collaborators(github, configs.github_outside_collaborators).await;
+
+    Ok(())
 }
 
-pub async fn refresh_anniversary_events(db: &Database) {
+pub async fn refresh_anniversary_events(db: &Database) -> Result<(), crate::Error> {
     // Get everything we need to authenticate with GSuite.
     // Initialize the GSuite client.
     let gsuite_customer = env::var("GADMIN_ACCOUNT_ID").unwrap();
@@ -1684,11 +1919,11 @@ pub async fn refresh_anniversary_events(db: &Database) {
 
     if anniversary_cal_id.is_empty() {
         // Return early we couldn't find the calendar.
-        return;
+        return Ok(());
     }
 
     // Get our list of users from our database.
-    let users = Users::get_from_db(db);
+    let users = Users::get_from_db(db)?;
     // For each user, create an anniversary for their start date.
     for mut user in users {
         // We only care if the user has a start date.
@@ -1748,22 +1983,117 @@ pub async fn refresh_anniversary_events(db: &Database) {
         }
 
         // Update the user in the database.
-        user.update(db).await;
+        if let Err(e) = user.update(db).await {
+            println!("updating user {} anniversary event failed: {}", user.username, e);
+        }
     }
+
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::configs::{refresh_anniversary_events, refresh_db_configs_and_airtable};
+    use std::collections::BTreeMap;
+    use std::env;
+
+    use cio_testkit::FakeApis;
+
+    use crate::airtable::{AIRTABLE_BASE_ID_DIRECTORY, AIRTABLE_EMPLOYEES_TABLE};
+    use crate::configs::{plan_changes, sync_users, ConfigChangeAction, LinkConfig, User, UserConfig, LINK_PLAN_FIELDS};
     use crate::db::Database;
-    use crate::utils::authenticate_github_jwt;
+    use crate::utils::authenticate_github;
+
+    #[test]
+    fn test_plan_changes() {
+        let link = |name: &str, link: &str| LinkConfig {
+            name: name.to_string(),
+            description: format!("The {} link", name),
+            link: link.to_string(),
+            aliases: Default::default(),
+            short_link: Default::default(),
+        };
 
-    #[ignore]
+        let mut desired: BTreeMap<String, LinkConfig> = Default::default();
+        desired.insert("chat".to_string(), link("chat", "https://chat.example.com"));
+        desired.insert("docs".to_string(), link("docs", "https://docs.example.com"));
+        desired.insert("new".to_string(), link("new", "https://new.example.com"));
+
+        let mut existing: BTreeMap<String, LinkConfig> = Default::default();
+        existing.insert("chat".to_string(), link("chat", "https://chat.example.com"));
+        existing.insert("docs".to_string(), link("docs", "https://old-docs.example.com"));
+        existing.insert("old".to_string(), link("old", "https://old.example.com"));
+
+        let changes = plan_changes(desired, existing, LINK_PLAN_FIELDS).unwrap();
+        assert_eq!(changes.len(), 3);
+
+        assert_eq!(changes[0].name, "docs");
+        assert_eq!(changes[0].action, ConfigChangeAction::Update);
+        assert_eq!(changes[0].fields.len(), 1);
+        assert_eq!(changes[0].fields[0].field, "link");
+        assert_eq!(changes[0].fields[0].old, json!("https://old-docs.example.com"));
+        assert_eq!(changes[0].fields[0].new, json!("https://docs.example.com"));
+
+        assert_eq!(changes[1].name, "new");
+        assert_eq!(changes[1].action, ConfigChangeAction::Create);
+        // Empty fields are not part of the plan.
+        assert_eq!(changes[1].fields.iter().map(|f| f.field.as_str()).collect::<Vec<_>>(), vec!["description", "link"]);
+
+        assert_eq!(changes[2].name, "old");
+        assert_eq!(changes[2].action, ConfigChangeAction::Delete);
+    }
+
+    /// The APIs are all fakes, but the users are saved in the database, so
+    /// this needs `CIO_DATABASE_URL` to point at a scratch one. Any other
+    /// users in it would be offboarded.
     #[tokio::test(flavor = "multi_thread")]
-    async fn test_cron_configs() {
-        let github = authenticate_github_jwt();
-        refresh_db_configs_and_airtable(&github).await;
+    async fn test_sync_users() {
+        let fakes = FakeApis::start();
+        let _env = fakes.set_env();
+        // Nothing here talks to GitHub, but the client has to exist.
+        env::set_var("GITHUB_TOKEN", "ghp_fake");
+        let github = authenticate_github();
         let db = Database::new();
-        refresh_anniversary_events(&db).await;
+
+        let config = |extra: serde_json::Value| {
+            let mut user = json!({
+                "first_name": "Jane",
+                "last_name": "Doe",
+                "username": "jane",
+                "recovery_email": "jane@example.com",
+                "department": "hardware engineering",
+            });
+            user.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+            serde_json::from_value::<UserConfig>(user).unwrap()
+        };
+
+        // Jane already works here, and someone has added her home address in
+        // Airtable since she was last synced.
+        let jane = config(json!({"home_address_street_1": "1 Main St", "home_address_city": "Emeryville", "home_address_state": "CA"}))
+            .upsert(&db)
+            .await
+            .unwrap();
+        fakes
+            .airtable
+            .edit(AIRTABLE_BASE_ID_DIRECTORY, AIRTABLE_EMPLOYEES_TABLE, &jane.airtable_record_id, json!({"home_address_city": "Oakland"}));
+
+        // The config does not have her address, since that comes from Airtable.
+        let mut users: BTreeMap<String, UserConfig> = Default::default();
+        users.insert("jane".to_string(), config(json!({})));
+        sync_users(&db, &github, users).await.unwrap();
+
+        let jane = User::get_from_db(&db, "jane".to_string()).unwrap();
+        assert_eq!(jane.home_address_city, "Oakland");
+        assert_eq!(jane.department, "Hardware Engineering");
+        assert_eq!(jane.typev, "full-time");
+
+        // She did not have a Ramp account, so she was invited.
+        let ramp_users = fakes.ramp.users();
+        assert_eq!(ramp_users.len(), 1);
+        assert_eq!(ramp_users[0]["email"], json!(jane.email()));
+
+        // The Airtable record is up to date with the database.
+        let records = fakes.airtable.records(AIRTABLE_BASE_ID_DIRECTORY, AIRTABLE_EMPLOYEES_TABLE);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0]["fields"]["department"], json!("Hardware Engineering"));
     }
 }
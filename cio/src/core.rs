@@ -9,6 +9,23 @@ pub trait UpdateAirtableRecord<T> {
     async fn update_airtable_record(&mut self, _: T);
 }
 
+/// Define the trait for checking a record before the API writes it.
+pub trait Validate {
+    /// Return a description of each problem with the record, or nothing if it
+    /// is valid.
+    fn validation_errors(&self) -> Vec<String>;
+
+    /// Return an error describing the problems with the record, if there are any.
+    fn validate(&self) -> Result<(), crate::Error> {
+        let problems = self.validation_errors();
+        if problems.is_empty() {
+            return Ok(());
+        }
+
+        Err(crate::Error::Invalid(problems))
+    }
+}
+
 /// The data type for customer interactions.
 /// This is inline with our Airtable workspace.
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -0,0 +1,650 @@
+use std::env;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use cron::Schedule;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Bool};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::analytics::PageViews;
+use crate::api_tokens::{encrypt_plaintext_tokens, APITokens};
+use crate::applicants::{
+    refresh_background_checks, refresh_db_applicants, refresh_docusign_for_applicants, update_applicant_reviewers, update_applications_with_scoring_forms, update_applications_with_scoring_results,
+    Applicants,
+};
+use crate::auth_logins::{refresh_auth_users_and_logins, AuthUserLogins, AuthUsers};
+use crate::configs::{refresh_anniversary_events, refresh_db_configs_and_airtable};
+use crate::db::Database;
+use crate::finance::{refresh_accounts_payable, refresh_ramp_transactions, refresh_software_vendors, sync_quickbooks};
+use crate::huddles::{send_huddle_reminders, sync_changes_to_google_events, sync_huddle_meeting_notes, sync_huddles};
+use crate::influx;
+use crate::interviews::{compile_packets, refresh_interviews};
+use crate::journal_clubs::{refresh_db_journal_club_meetings, JournalClubMeetings, JournalClubPapers};
+use crate::mailing_list::{refresh_db_mailing_list_subscribers, MailingListSubscribers};
+use crate::models::{GithubRepos, RFDs};
+use crate::rack_line::{refresh_db_rack_line_subscribers, RackLineSubscribers};
+use crate::recorded_meetings::refresh_recorded_meetings;
+use crate::rfds::{refresh_db_rfds, send_rfd_changelog};
+use crate::schema::cron_job_runs;
+use crate::shipments::{refresh_inbound_shipments, refresh_outbound_shipments};
+use crate::shorturls::refresh_shorturls;
+use crate::swag_inventory::{refresh_swag_inventory_items, refresh_swag_items, BarcodeScans};
+use crate::tailscale::cleanup_old_tailscale_devices;
+use crate::utils::{authenticate_github_jwt, refresh_db_github_repos};
+
+/// A job that is run on a schedule.
+///
+/// The schedule is a cron expression with a leading seconds field, for example
+/// `0 0 */6 * * *` runs at the top of every sixth hour. All schedules are in UTC.
+#[derive(Debug, Clone, Copy)]
+pub struct CronJob {
+    pub name: &'static str,
+    pub schedule: &'static str,
+    /// How long the job can run before it is cancelled and marked as timed out.
+    pub timeout: Duration,
+    pub job: Job,
+}
+
+const MINUTE: u64 = 60;
+const HOUR: u64 = 60 * MINUTE;
+
+/// The jobs that keep the database and Airtable in sync.
+///
+/// The schedules match the `run-*` GitHub Actions workflows, which call
+/// `cio-cron run <job>` for each of these.
+pub const JOBS: &[CronJob] = &[
+    CronJob {
+        name: "configs",
+        schedule: "0 0 */6 * * *",
+        timeout: Duration::from_secs(2 * HOUR),
+        job: Job::Configs,
+    },
+    CronJob {
+        name: "shorturls",
+        schedule: "0 0 */6 * * *",
+        timeout: Duration::from_secs(HOUR),
+        job: Job::Shorturls,
+    },
+    CronJob {
+        name: "github-repos",
+        schedule: "0 0 */6 * * *",
+        timeout: Duration::from_secs(HOUR),
+        job: Job::GithubRepos,
+    },
+    CronJob {
+        name: "rfds",
+        schedule: "0 0 */6 * * *",
+        timeout: Duration::from_secs(2 * HOUR),
+        job: Job::RFDs,
+    },
+    CronJob {
+        name: "rfd-changelog",
+        schedule: "0 0 17 * * Mon",
+        timeout: Duration::from_secs(30 * MINUTE),
+        job: Job::RFDChangelog,
+    },
+    CronJob {
+        name: "journal-clubs",
+        schedule: "0 0 */6 * * *",
+        timeout: Duration::from_secs(30 * MINUTE),
+        job: Job::JournalClubs,
+    },
+    CronJob {
+        name: "mailing-list-subscribers",
+        schedule: "0 0 */6 * * *",
+        timeout: Duration::from_secs(30 * MINUTE),
+        job: Job::MailingListSubscribers,
+    },
+    CronJob {
+        name: "rack-line-subscribers",
+        schedule: "0 0 */6 * * *",
+        timeout: Duration::from_secs(30 * MINUTE),
+        job: Job::RackLineSubscribers,
+    },
+    CronJob {
+        name: "auth-users-and-logins",
+        schedule: "0 0 */6 * * *",
+        timeout: Duration::from_secs(2 * HOUR),
+        job: Job::AuthUsersAndLogins,
+    },
+    CronJob {
+        name: "page-views",
+        schedule: "0 0 */6 * * *",
+        timeout: Duration::from_secs(HOUR),
+        job: Job::PageViews,
+    },
+    CronJob {
+        name: "api-tokens",
+        schedule: "0 0 */6 * * *",
+        timeout: Duration::from_secs(10 * MINUTE),
+        job: Job::APITokens,
+    },
+    CronJob {
+        name: "recorded-meetings",
+        schedule: "0 0 */6 * * *",
+        timeout: Duration::from_secs(2 * HOUR),
+        job: Job::RecordedMeetings,
+    },
+    CronJob {
+        name: "swag-items",
+        schedule: "0 0 */6 * * *",
+        timeout: Duration::from_secs(30 * MINUTE),
+        job: Job::SwagItems,
+    },
+    CronJob {
+        name: "swag-inventory-items",
+        schedule: "0 0 */6 * * *",
+        timeout: Duration::from_secs(30 * MINUTE),
+        job: Job::SwagInventoryItems,
+    },
+    CronJob {
+        name: "barcode-scans",
+        schedule: "0 0 */6 * * *",
+        timeout: Duration::from_secs(30 * MINUTE),
+        job: Job::BarcodeScans,
+    },
+    CronJob {
+        name: "tailscale",
+        schedule: "0 0 */6 * * *",
+        timeout: Duration::from_secs(10 * MINUTE),
+        job: Job::Tailscale,
+    },
+    CronJob {
+        name: "influx-pull-requests",
+        schedule: "0 0 */6 * * *",
+        timeout: Duration::from_secs(60 * MINUTE),
+        job: Job::InfluxPullRequests,
+    },
+    CronJob {
+        name: "influx-issues",
+        schedule: "0 0 */6 * * *",
+        timeout: Duration::from_secs(60 * MINUTE),
+        job: Job::InfluxIssues,
+    },
+    CronJob {
+        name: "software-vendors",
+        schedule: "0 0 */2 * * *",
+        timeout: Duration::from_secs(30 * MINUTE),
+        job: Job::SoftwareVendors,
+    },
+    CronJob {
+        name: "accounts-payable",
+        schedule: "0 0 */2 * * *",
+        timeout: Duration::from_secs(30 * MINUTE),
+        job: Job::AccountsPayable,
+    },
+    CronJob {
+        name: "ramp-transactions",
+        schedule: "0 0 */2 * * *",
+        timeout: Duration::from_secs(HOUR),
+        job: Job::RampTransactions,
+    },
+    CronJob {
+        name: "quickbooks",
+        schedule: "0 0 */2 * * *",
+        timeout: Duration::from_secs(HOUR),
+        job: Job::Quickbooks,
+    },
+    CronJob {
+        name: "huddles",
+        schedule: "0 0 */2 * * *",
+        timeout: Duration::from_secs(HOUR),
+        job: Job::Huddles,
+    },
+    CronJob {
+        name: "applicants",
+        schedule: "0 0 */2 * * *",
+        timeout: Duration::from_secs(2 * HOUR),
+        job: Job::Applicants,
+    },
+    CronJob {
+        name: "applicant-reviewer-leaderboard",
+        schedule: "0 0 */2 * * *",
+        timeout: Duration::from_secs(30 * MINUTE),
+        job: Job::ApplicantReviewerLeaderboard,
+    },
+    CronJob {
+        name: "background-checks",
+        schedule: "0 0 */2 * * *",
+        timeout: Duration::from_secs(30 * MINUTE),
+        job: Job::BackgroundChecks,
+    },
+    CronJob {
+        name: "applicant-reviewers",
+        schedule: "0 0,30 * * * *",
+        timeout: Duration::from_secs(25 * MINUTE),
+        job: Job::ApplicantReviewers,
+    },
+    CronJob {
+        name: "interviews",
+        schedule: "0 0 * * * *",
+        timeout: Duration::from_secs(55 * MINUTE),
+        job: Job::Interviews,
+    },
+    CronJob {
+        name: "shipments",
+        schedule: "0 0 * * * *",
+        timeout: Duration::from_secs(55 * MINUTE),
+        job: Job::Shipments,
+    },
+];
+
+/// The work done by a cron job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Job {
+    Configs,
+    Shorturls,
+    GithubRepos,
+    RFDs,
+    RFDChangelog,
+    JournalClubs,
+    MailingListSubscribers,
+    RackLineSubscribers,
+    AuthUsersAndLogins,
+    PageViews,
+    APITokens,
+    RecordedMeetings,
+    SwagItems,
+    SwagInventoryItems,
+    BarcodeScans,
+    Tailscale,
+    InfluxPullRequests,
+    InfluxIssues,
+    SoftwareVendors,
+    AccountsPayable,
+    RampTransactions,
+    Quickbooks,
+    Huddles,
+    Applicants,
+    ApplicantReviewerLeaderboard,
+    BackgroundChecks,
+    ApplicantReviewers,
+    Interviews,
+    Shipments,
+}
+
+impl Job {
+    /// Run the job once.
+    pub async fn run(&self, db: &Database) -> Result<(), crate::Error> {
+        match self {
+            Job::Configs => {
+                let github = authenticate_github_jwt();
+                refresh_db_configs_and_airtable(&github).await?;
+                refresh_anniversary_events(db).await?;
+            }
+            Job::Shorturls => refresh_shorturls().await?,
+            Job::GithubRepos => {
+                let github = authenticate_github_jwt();
+                refresh_db_github_repos(db, &github).await?;
+                GithubRepos::get_from_db(db)?.update_airtable(db).await?;
+            }
+            Job::RFDs => {
+                let github = authenticate_github_jwt();
+                refresh_db_rfds(db, &github).await;
+                RFDs::get_from_db(db)?.update_airtable(db).await?;
+            }
+            Job::RFDChangelog => send_rfd_changelog().await?,
+            Job::JournalClubs => {
+                let github = authenticate_github_jwt();
+                refresh_db_journal_club_meetings(db, &github).await;
+                JournalClubPapers::get_from_db(db)?.update_airtable(db).await?;
+                JournalClubMeetings::get_from_db(db)?.update_airtable(db).await?;
+            }
+            Job::MailingListSubscribers => {
+                refresh_db_mailing_list_subscribers(db).await;
+                MailingListSubscribers::get_from_db(db)?.update_airtable(db).await?;
+            }
+            Job::RackLineSubscribers => {
+                refresh_db_rack_line_subscribers(db).await;
+                RackLineSubscribers::get_from_db(db)?.update_airtable(db).await?;
+            }
+            Job::AuthUsersAndLogins => {
+                refresh_auth_users_and_logins(db).await;
+                AuthUserLogins::get_from_db(db)?.update_airtable(db).await?;
+                AuthUsers::get_from_db(db)?.update_airtable(db).await?;
+            }
+            Job::PageViews => PageViews::get_from_db(db)?.update_airtable(db).await?,
+            Job::APITokens => {
+                encrypt_plaintext_tokens(db).await?;
+                APITokens::get_from_db(db)?.update_airtable(db).await?;
+            }
+            Job::RecordedMeetings => refresh_recorded_meetings().await?,
+            Job::SwagItems => refresh_swag_items().await,
+            Job::SwagInventoryItems => refresh_swag_inventory_items().await,
+            Job::BarcodeScans => BarcodeScans::get_from_db(db)?.update_airtable(db).await?,
+            Job::Tailscale => cleanup_old_tailscale_devices().await,
+            Job::InfluxPullRequests => influx::Client::new_from_env().update_pull_request_events().await,
+            Job::InfluxIssues => influx::Client::new_from_env().update_issues_events().await,
+            Job::SoftwareVendors => refresh_software_vendors().await?,
+            Job::AccountsPayable => refresh_accounts_payable().await,
+            Job::RampTransactions => refresh_ramp_transactions().await?,
+            Job::Quickbooks => sync_quickbooks().await?,
+            Job::Huddles => {
+                sync_changes_to_google_events().await?;
+                sync_huddles().await?;
+                send_huddle_reminders().await?;
+                sync_huddle_meeting_notes().await?;
+            }
+            Job::Applicants => {
+                refresh_db_applicants(db).await?;
+                Applicants::get_from_db(db)?.update_airtable_incremental(db).await?;
+                refresh_docusign_for_applicants(db).await?;
+            }
+            Job::ApplicantReviewerLeaderboard => update_applicant_reviewers(db).await,
+            Job::BackgroundChecks => refresh_background_checks(db).await,
+            Job::ApplicantReviewers => {
+                update_applications_with_scoring_forms(db).await;
+                // This must be after update_applications_with_scoring_forms, so that if someone
+                // has done the application then we remove them from the scorers.
+                update_applications_with_scoring_results(db).await;
+            }
+            Job::Interviews => {
+                refresh_interviews(db).await?;
+                compile_packets(db).await?;
+            }
+            Job::Shipments => {
+                refresh_outbound_shipments(db).await?;
+                refresh_inbound_shipments(db).await;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl CronJob {
+    /// Find a job by its name.
+    pub fn get(name: &str) -> Option<&'static CronJob> {
+        JOBS.iter().find(|j| j.name == name)
+    }
+
+    /// Parse the cron expression for the job.
+    pub fn schedule(&self) -> Schedule {
+        Schedule::from_str(self.schedule).unwrap_or_else(|e| panic!("parsing schedule `{}` for cron job `{}` failed: {}", self.schedule, self.name, e))
+    }
+
+    /// The key for the Postgres advisory lock that keeps two runs of the same
+    /// job from overlapping, even across processes.
+    ///
+    /// This is the 64-bit FNV-1a hash of the job name.
+    pub fn lock_key(&self) -> i64 {
+        let mut hash: u64 = 0xcbf29ce484222325;
+        for b in self.name.bytes() {
+            hash ^= b as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+        hash as i64
+    }
+}
+
+/// Return the jobs that were due to run after `since` and at or before `until`.
+pub fn due_jobs(schedules: &[(&'static CronJob, Schedule)], since: &DateTime<Utc>, until: &DateTime<Utc>) -> Vec<&'static CronJob> {
+    schedules
+        .iter()
+        .filter(|(_, schedule)| match schedule.after(since).next() {
+            Some(next) => next <= *until,
+            None => false,
+        })
+        .map(|(job, _)| *job)
+        .collect()
+}
+
+/// The status of a run of a cron job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CronJobStatus {
+    Running,
+    Success,
+    Failure,
+    TimedOut,
+    /// The job was still running from a previous run, so this run did nothing.
+    Skipped,
+}
+
+impl fmt::Display for CronJobStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CronJobStatus::Running => write!(f, "running"),
+            CronJobStatus::Success => write!(f, "success"),
+            CronJobStatus::Failure => write!(f, "failure"),
+            CronJobStatus::TimedOut => write!(f, "timed out"),
+            CronJobStatus::Skipped => write!(f, "skipped"),
+        }
+    }
+}
+
+#[derive(Debug, Insertable, PartialEq, Clone)]
+#[table_name = "cron_job_runs"]
+pub struct NewCronJobRun {
+    pub name: String,
+    pub status: String,
+    pub error: String,
+    pub hostname: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub duration_seconds: i32,
+}
+
+/// A single run of a cron job.
+#[derive(Debug, Queryable, Identifiable, PartialEq, Clone, JsonSchema, Deserialize, Serialize)]
+#[table_name = "cron_job_runs"]
+pub struct CronJobRun {
+    pub id: i32,
+    pub name: String,
+    pub status: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub error: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub hostname: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub duration_seconds: i32,
+}
+
+impl NewCronJobRun {
+    fn new(job: &CronJob, status: CronJobStatus) -> Self {
+        NewCronJobRun {
+            name: job.name.to_string(),
+            status: status.to_string(),
+            error: String::new(),
+            hostname: env::var("HOSTNAME").unwrap_or_default(),
+            started_at: Utc::now(),
+            finished_at: None,
+            duration_seconds: 0,
+        }
+    }
+
+    /// Mark the run as finished.
+    fn finish(&mut self, status: CronJobStatus, error: String) {
+        let finished_at = Utc::now();
+        self.status = status.to_string();
+        self.error = error;
+        self.duration_seconds = (finished_at - self.started_at).num_seconds() as i32;
+        self.finished_at = Some(finished_at);
+    }
+
+    /// Insert the run into the database.
+    fn create(&self, db: &Database) -> Result<CronJobRun, crate::Error> {
+        Ok(diesel::insert_into(cron_job_runs::table).values(self).get_result(&db.conn())?)
+    }
+}
+
+impl CronJobRun {
+    /// Get the most recent runs, newest first, optionally only for a single job.
+    pub fn get_from_db(db: &Database, name: Option<&str>, limit: i64) -> Result<Vec<CronJobRun>, crate::Error> {
+        let mut query = cron_job_runs::table.order_by(cron_job_runs::dsl::started_at.desc()).limit(limit).into_boxed();
+        if let Some(name) = name {
+            query = query.filter(cron_job_runs::dsl::name.eq(name.to_string()));
+        }
+
+        Ok(query.load::<CronJobRun>(&db.conn())?)
+    }
+}
+
+#[derive(QueryableByName)]
+struct AdvisoryLock {
+    #[sql_type = "Bool"]
+    locked: bool,
+}
+
+/// Run a job once, unless another run of it is still in progress, and record
+/// the run in the database.
+pub async fn run_job(db: &Database, job: &CronJob) -> Result<CronJobRun, crate::Error> {
+    let mut run = NewCronJobRun::new(job, CronJobStatus::Running);
+
+    // Hold the advisory lock on its own connection for the length of the run, so
+    // we do not starve the pool the job itself uses. Postgres releases the lock
+    // if we go away without unlocking it.
+    let database_url = env::var("CIO_DATABASE_URL").expect("CIO_DATABASE_URL must be set");
+    let lock_conn = match PgConnection::establish(&database_url) {
+        Ok(c) => c,
+        Err(e) => {
+            run.finish(CronJobStatus::Failure, format!("connecting to the database to lock the job failed: {}", e));
+            return run.create(db);
+        }
+    };
+    let lock: AdvisoryLock = diesel::sql_query("SELECT pg_try_advisory_lock($1) AS locked")
+        .bind::<BigInt, _>(job.lock_key())
+        .get_result(&lock_conn)?;
+    if !lock.locked {
+        println!("[cron] {} is still running from a previous run, skipping", job.name);
+        run.finish(CronJobStatus::Skipped, "a previous run is still in progress".to_string());
+        return run.create(db);
+    }
+
+    // Insert the run so we can see jobs that are currently in progress.
+    let id = run.create(db)?.id;
+
+    // The job functions are not all `Send` and some of them block, so each run
+    // gets its own thread and runtime. That way a job that blocks can't hold up
+    // the other jobs, or the timeout below. The thread owns the lock, so a run
+    // that timed out keeps the next runs from starting until it really stops.
+    println!("[cron] {} started", job.name);
+    let job_db = db.with_audit_source(format!("cron:{}", job.name));
+    let handle = tokio::task::spawn_blocking(move || {
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().expect("building a runtime for the job");
+        let result = runtime.block_on(job.job.run(&job_db));
+
+        if let Err(e) = diesel::sql_query("SELECT pg_advisory_unlock($1) AS locked")
+            .bind::<BigInt, _>(job.lock_key())
+            .get_result::<AdvisoryLock>(&lock_conn)
+        {
+            println!("[cron] unlocking {} failed: {}", job.name, e);
+        }
+
+        result
+    });
+    let (status, error) = match tokio::time::timeout(job.timeout, handle).await {
+        Ok(Ok(Ok(()))) => (CronJobStatus::Success, String::new()),
+        Ok(Ok(Err(e))) => (CronJobStatus::Failure, e.to_string()),
+        // The lock was released when the thread unwound and dropped the connection.
+        Ok(Err(e)) if e.is_panic() => (CronJobStatus::Failure, format!("job panicked: {}", panic_message(e.into_panic()))),
+        Ok(Err(e)) => (CronJobStatus::Failure, e.to_string()),
+        Err(_) => (CronJobStatus::TimedOut, format!("job did not finish within {} seconds", job.timeout.as_secs())),
+    };
+
+    run.finish(status, error);
+    let finished = diesel::update(cron_job_runs::table.find(id))
+        .set((
+            cron_job_runs::dsl::status.eq(&run.status),
+            cron_job_runs::dsl::error.eq(&run.error),
+            cron_job_runs::dsl::finished_at.eq(run.finished_at),
+            cron_job_runs::dsl::duration_seconds.eq(run.duration_seconds),
+        ))
+        .get_result::<CronJobRun>(&db.conn())?;
+    println!("[cron] {} finished with status `{}` in {}s", job.name, finished.status, finished.duration_seconds);
+    if !finished.error.is_empty() {
+        println!("[cron] {} error: {}", job.name, finished.error);
+    }
+
+    Ok(finished)
+}
+
+/// Get the message a job panicked with.
+fn panic_message(panic: Box<dyn std::any::Any + Send>) -> String {
+    if let Some(s) = panic.downcast_ref::<&str>() {
+        return s.to_string();
+    }
+    if let Some(s) = panic.downcast_ref::<String>() {
+        return s.to_string();
+    }
+
+    "unknown panic".to_string()
+}
+
+/// Run the scheduler forever, starting each job when it is due.
+pub async fn serve(db: Database) {
+    let schedules: Vec<(&'static CronJob, Schedule)> = JOBS.iter().map(|j| (j, j.schedule())).collect();
+
+    let mut last = Utc::now();
+    loop {
+        // Sleep until the next time any job is due.
+        let next = match schedules.iter().filter_map(|(_, s)| s.after(&last).next()).min() {
+            Some(n) => n,
+            None => return,
+        };
+        if let Ok(wait) = (next - Utc::now()).to_std() {
+            tokio::time::sleep(wait).await;
+        }
+
+        let now = Utc::now();
+        for job in due_jobs(&schedules, &last, &now) {
+            let db = db.clone();
+            tokio::spawn(async move {
+                if let Err(e) = run_job(&db, job).await {
+                    println!("[cron] recording run of {} failed: {}", job.name, e);
+                }
+            });
+        }
+        last = now;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use chrono::{TimeZone, Utc};
+
+    use crate::cron::{due_jobs, CronJob, JOBS};
+
+    #[test]
+    fn test_jobs_are_valid() {
+        let mut names = BTreeSet::new();
+        let mut keys = BTreeSet::new();
+        for job in JOBS {
+            job.schedule();
+            assert!(names.insert(job.name), "duplicate cron job name {}", job.name);
+            assert!(keys.insert(job.lock_key()), "duplicate lock key for cron job {}", job.name);
+            assert!(job.timeout.as_secs() > 0);
+        }
+    }
+
+    #[test]
+    fn test_due_jobs() {
+        let schedules: Vec<_> = ["configs", "rfd-changelog", "applicant-reviewers", "shipments"]
+            .iter()
+            .map(|name| {
+                let job = CronJob::get(name).unwrap();
+                (job, job.schedule())
+            })
+            .collect();
+        let names = |since, until| due_jobs(&schedules, &since, &until).iter().map(|j| j.name).collect::<Vec<_>>();
+
+        // Monday, June 14th 2021.
+        assert_eq!(
+            names(Utc.ymd(2021, 6, 14).and_hms(11, 59, 0), Utc.ymd(2021, 6, 14).and_hms(12, 0, 1)),
+            vec!["configs", "applicant-reviewers", "shipments"]
+        );
+        assert_eq!(names(Utc.ymd(2021, 6, 14).and_hms(12, 0, 1), Utc.ymd(2021, 6, 14).and_hms(12, 30, 0)), vec!["applicant-reviewers"]);
+        assert_eq!(
+            names(Utc.ymd(2021, 6, 14).and_hms(16, 59, 0), Utc.ymd(2021, 6, 14).and_hms(17, 0, 0)),
+            vec!["rfd-changelog", "applicant-reviewers", "shipments"]
+        );
+        assert!(names(Utc.ymd(2021, 6, 15).and_hms(17, 0, 1), Utc.ymd(2021, 6, 15).and_hms(17, 29, 59)).is_empty());
+    }
+}
@@ -1,11 +1,15 @@
 use std::env;
+use std::path::Path;
 use std::sync::Arc;
 
 use diesel::pg::PgConnection;
 use diesel::r2d2;
 
+#[derive(Clone)]
 pub struct Database {
     pool: Arc<r2d2::Pool<r2d2::ConnectionManager<PgConnection>>>,
+    /// What is making changes through this handle, recorded in the audit log.
+    audit_source: String,
 }
 
 impl Default for Database {
@@ -15,7 +19,10 @@ impl Default for Database {
         let manager = r2d2::ConnectionManager::new(&database_url);
         let pool = r2d2::Pool::builder().max_size(15).build(manager).unwrap();
 
-        Database { pool: Arc::new(pool) }
+        Database {
+            pool: Arc::new(pool),
+            audit_source: default_audit_source(),
+        }
     }
 }
 
@@ -29,4 +36,28 @@ impl Database {
     pub fn conn(&self) -> r2d2::PooledConnection<r2d2::ConnectionManager<PgConnection>> {
         self.pool.get().unwrap_or_else(|e| panic!("getting a connection from the pool failed: {}", e))
     }
+
+    /// Returns a handle to the same pool that records the given source, like
+    /// `cron:applications` or `api:jess@oxidecomputer.com`, in the audit log for
+    /// the changes made through it.
+    pub fn with_audit_source<S: ToString>(&self, source: S) -> Database {
+        Database {
+            pool: self.pool.clone(),
+            audit_source: source.to_string(),
+        }
+    }
+
+    /// Returns the source recorded in the audit log for changes made through this handle.
+    pub fn audit_source(&self) -> &str {
+        &self.audit_source
+    }
+}
+
+/// Changes we can't attribute to anything more specific are attributed to the
+/// program that made them.
+fn default_audit_source() -> String {
+    env::args()
+        .next()
+        .and_then(|a| Path::new(&a).file_name().map(|n| n.to_string_lossy().to_string()))
+        .unwrap_or_else(|| "unknown".to_string())
 }
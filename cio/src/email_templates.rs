@@ -0,0 +1,589 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use handlebars::Handlebars;
+use schemars::JsonSchema;
+use sendgrid_api::{Content, Email, Message, Personalization, SendGrid};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::db::Database;
+use crate::schema::email_templates;
+use crate::utils::{github_org, DOMAIN, GSUITE_DOMAIN};
+
+/// An email template in the configs repo, like:
+///
+/// ```toml
+/// [email_templates.applicant_rejection_timing]
+/// subject = "Thank you for your application, {{name}}"
+/// text = """
+/// Dear {{name}},
+/// ...
+/// """
+/// ```
+///
+/// Templates use handlebars and are rendered against the record the email is
+/// about, see `DEFAULT_EMAIL_TEMPLATES` for the names and the records.
+#[derive(Debug, Default, PartialEq, Clone, JsonSchema, Deserialize, Serialize)]
+pub struct EmailTemplateConfig {
+    pub subject: String,
+    /// The plaintext body.
+    pub text: String,
+    /// The HTML body, sent along with the plaintext body if it is set.
+    #[serde(default)]
+    pub html: String,
+}
+
+/// An email we send, with the wording we use until it is set in the configs repo.
+pub struct DefaultEmailTemplate {
+    pub name: &'static str,
+    /// The table of the records the template is rendered against.
+    pub table: &'static str,
+    pub subject: &'static str,
+    pub text: &'static str,
+}
+
+/// The emails we send with templates.
+pub const DEFAULT_EMAIL_TEMPLATES: &[DefaultEmailTemplate] = &[
+    DefaultEmailTemplate {
+        name: "applicant_application_received",
+        table: "applicants",
+        subject: "Oxide Computer Company {{role}} Application Received for {{name}}",
+        text: "Dear {{name}},
+
+Thank you for submitting your application materials! We really appreciate all
+the time and thought everyone puts into their application. We will be in touch
+within the next few weeks with more information. Just a heads up this could take
+up to 4-6 weeks.
+
+Sincerely,
+  The Oxide Team",
+    },
+    DefaultEmailTemplate {
+        name: "applicant_rejection_junior",
+        table: "applicants",
+        subject: "Thank you for your application, {{name}}",
+        text: "Dear {{name}},
+
+Thank you for your application to join Oxide Computer Company. At this point
+in time, we are focusing on hiring engineers with professional experience,
+who have a track record of self-directed contributions to a team.
+
+We are grateful you took the time to apply and put so much thought into
+your candidate materials, we loved reading them. Although engineers at the
+early stages of their career are unlikely to be a fit for us right now, we
+are growing, and encourage you to consider re-applying in the future.
+
+ We would absolutely love to work with you in the future and cannot wait for
+that stage of the company!
+
+All the best,
+The Oxide Team",
+    },
+    DefaultEmailTemplate {
+        name: "applicant_rejection_no_materials",
+        table: "applicants",
+        subject: "Thank you for your application, {{name}}",
+        text: "Dear {{name}},
+
+Unfortunately, we cannot accept it at this time since you failed to provide the
+requested materials.
+
+All the best,
+The Oxide Team",
+    },
+    DefaultEmailTemplate {
+        name: "applicant_rejection_timing",
+        table: "applicants",
+        subject: "Thank you for your application, {{name}}",
+        text: "Dear {{name}},
+
+We are so humbled by your application to join Oxide Computer Company. At this
+stage of the company we are hyper-focused on certain areas of the stack and
+when we need specific domain space experience such as yours, please engage
+with us. Our roles will be updated as we need them.
+
+We are grateful you took the time to apply and put so much thought into the
+candidate materials, we loved reading them. We would absolutely love to work
+with you in the future and cannot wait for that stage of the company!
+
+All the best,
+The Oxide Team",
+    },
+    DefaultEmailTemplate {
+        name: "shipment_order_received",
+        table: "outbound_shipments",
+        subject: "{{name}}, your order from the Oxide Computer Company has been received!",
+        text: "Below is the information for your order:
+
+**Contents:**
+{{contents}}
+
+**Address to:**
+{{name}}
+{{address}}
+
+You will receive another email once your order has been shipped with your tracking numbers.
+
+If you have any questions or concerns, please respond to this email!
+Have a splendid day!
+
+xoxo,
+  The Oxide Shipping Bot",
+    },
+    DefaultEmailTemplate {
+        name: "shipment_on_the_way",
+        table: "outbound_shipments",
+        subject: "{{name}}, your package from the Oxide Computer Company is on the way!",
+        text: "Below is the information for your package:
+
+**Contents:**
+{{contents}}
+
+**Address to:**
+{{name}}
+{{address}}
+
+**Tracking link:**
+{{oxide_tracking_link}}
+
+If you have any questions or concerns, please respond to this email!
+Have a splendid day!
+
+xoxo,
+  The Oxide Shipping Bot",
+    },
+    DefaultEmailTemplate {
+        name: "shipment_ready_to_package",
+        table: "outbound_shipments",
+        subject: "Shipment to {{name}} is ready to be packaged",
+        text: "Below is the information the package:
+
+**Contents:**
+{{contents}}
+
+**Address to:**
+{{name}}
+{{address}}
+
+**Tracking link:**
+{{oxide_tracking_link}}
+
+The label should already be printed on the cart with the label printers. Please
+take the label and affix it to the package with the specified contents. It can
+then be dropped off for {{carrier}}.
+
+You DO NOT need to scan the barcodes of the items since they have already been
+deducted from inventory. DO NOT SCAN THE BARCODES for the items since
+they have already been deducted from inventory.
+
+As always, the Airtable with all the shipments lives at:
+https://airtable-shipments.corp.oxide.computer.
+
+xoxo,
+  The Oxide Shipping Bot",
+    },
+    DefaultEmailTemplate {
+        name: "user_new_consultant",
+        table: "users",
+        subject: "Your New Email Account: {{email}}",
+        text: "Yoyoyo {{first_name}},
+
+You should have an email from Okta about setting up your account with them.
+We use Okta to authenticate to a number of different apps -- including
+Google Workspace. This includes email, calendar, drive, etc.
+
+After setting up your Okta account your email account with Google will be
+provisioned. You can then login to your email from: mail.corp.{{domain}}.
+Details for accessing are below.
+
+Website for Okta login: https://oxidecomputerlogin.okta.com
+Website for email login: https://mail.corp.{{domain}}
+Email: {{email}}
+Aliases: {{#each aliases}}{{this}}{{#unless @last}}, {{/unless}}{{/each}}
+
+Make sure you set up two-factor authentication for your account, or in one week
+you will be locked out.
+
+If you have any questions or your email does not work please email your
+administrator, who is cc-ed on this email. Spoiler alert it's Jess...
+jess@{{domain}}.
+
+xoxo,
+  The Onboarding Bot",
+    },
+    DefaultEmailTemplate {
+        name: "user_new_account",
+        table: "users",
+        subject: "Your New Email Account: {{email}}",
+        text: "Yoyoyo {{first_name}},
+
+You should have an email from Okta about setting up your account with them.
+We use Okta to authenticate to a number of different apps -- including
+Google Workspace and GitHub. This includes email, calendar, drive, etc.
+
+After setting up your Okta account your email account with Google will be
+provisioned. You can then login to your email from: mail.corp.{{domain}}.
+Details for accessing are below.
+
+Website for Okta login: https://oxidecomputerlogin.okta.com
+Website for email login: https://mail.corp.{{domain}}
+Email: {{email}}
+Aliases: {{#each aliases}}{{this}}{{#unless @last}}, {{/unless}}{{/each}}
+
+Make sure you set up two-factor authentication for your account, or in one week
+you will be locked out.
+
+{{#if github}}Your GitHub @{{github}} has been added to our organization (https://github.com/{{github_org}})
+and various teams within it. GitHub should have sent an email with instructions on
+accepting the invitation to our organization to the email you used
+when you signed up for GitHub. Or you can alternatively accept our invitation
+by going to https://github.com/{{github_org}}.{{else}}We do not have a github account for you. You will need to create one at https://github.com
+OR let jess@{{domain}} know your handle, if you already have one. Either way, be sure to
+let jess@{{domain}} know what your GitHub handle is.{{/if}}
+
+If you have any questions or your email does not work please email your
+administrator, who is cc-ed on this email. Spoiler alert it's Jess...
+jess@{{domain}}. If you want other email aliases, let Jess know as well.
+
+You can find more onboarding information in GitHub:
+https://github.com/{{github_org}}/meta/blob/master/general/onboarding.md
+You can find information about internal processes and applications at:
+https://github.com/{{github_org}}/meta/blob/master/general/README.md
+
+As a first contribution to one of our repos, add a book
+to our internal library: https://github.com/{{github_org}}/library
+
+We use Airtable for storing just about everything. You can login with single
+sign-on (SSO) after setting up your email at:
+https://airtable.com/sso/login.
+To join our Airtable workspace you need to click this link:
+https://airtable-join.corp.oxide.computer.
+Poke around once you've joined :)
+
+We have both a Riot server and a Slack for chat. Josh (josh@oxidecomputer.com) can get
+you set up with an account on the Riot server. You can use SSO to login to the Slack
+at https://oxidecomputer.slack.com.
+
+Lastly, be sure to order yourself some swag: https://swag.oxide.computer
+
+xoxo,
+  The Onboarding Bot",
+    },
+];
+
+/// Get the default for a template by its name.
+pub fn default_email_template(name: &str) -> Option<&'static DefaultEmailTemplate> {
+    DEFAULT_EMAIL_TEMPLATES.iter().find(|t| t.name == name)
+}
+
+#[derive(Debug, Insertable, PartialEq, Clone)]
+#[table_name = "email_templates"]
+pub struct NewEmailTemplate {
+    pub name: String,
+    pub version: i32,
+    pub subject: String,
+    pub text_body: String,
+    pub html_body: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A version of an email template from the configs repo. A new version is
+/// saved every time the template changes, so we know what wording was sent.
+#[derive(Debug, Queryable, Identifiable, PartialEq, Clone, JsonSchema, Deserialize, Serialize)]
+#[table_name = "email_templates"]
+pub struct EmailTemplate {
+    pub id: i32,
+    pub name: String,
+    pub version: i32,
+    pub subject: String,
+    pub text_body: String,
+    pub html_body: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl EmailTemplate {
+    /// Get the latest version of every template.
+    pub fn get_latest(db: &Database) -> Result<Vec<EmailTemplate>, crate::Error> {
+        Ok(email_templates::table
+            .distinct_on(email_templates::dsl::name)
+            .order_by((email_templates::dsl::name, email_templates::dsl::version.desc()))
+            .load::<EmailTemplate>(&db.conn())?)
+    }
+
+    /// Get a version of a template.
+    pub fn get_version(db: &Database, name: &str, version: i32) -> Result<EmailTemplate, crate::Error> {
+        match email_templates::table
+            .filter(email_templates::dsl::name.eq(name))
+            .filter(email_templates::dsl::version.eq(version))
+            .first::<EmailTemplate>(&db.conn())
+        {
+            Ok(t) => Ok(t),
+            Err(diesel::result::Error::NotFound) => Err(crate::Error::not_found("email_templates", format!("{} v{}", name, version))),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// An email rendered from a template.
+#[derive(Debug, Default, PartialEq, Clone, JsonSchema, Deserialize, Serialize)]
+pub struct RenderedEmail {
+    pub template: String,
+    /// The version of the template, 0 for the default in the code.
+    pub version: i32,
+    pub subject: String,
+    pub text: String,
+    /// The HTML body, empty if the template does not have one.
+    pub html: String,
+}
+
+impl RenderedEmail {
+    /// Send the email with SendGrid.
+    pub async fn send(&self, to: Vec<String>, cc: Vec<String>, bcc: Vec<String>, from: String) {
+        let mut p = Personalization::new();
+        for t in to {
+            p = p.add_to(Email::new().set_email(&t).set_name(&t));
+        }
+        for c in cc {
+            p = p.add_cc(Email::new().set_email(&c).set_name(&c));
+        }
+        for b in bcc {
+            p = p.add_bcc(Email::new().set_email(&b).set_name(&b));
+        }
+
+        // The plaintext body has to come before the HTML body.
+        let mut message = Message::new()
+            .set_from(Email::new().set_email(&from).set_name(&from))
+            .set_subject(&self.subject)
+            .add_content(Content::new().set_content_type("text/plain").set_value(&self.text));
+        if !self.html.is_empty() {
+            message = message.add_content(Content::new().set_content_type("text/html").set_value(&self.html));
+        }
+
+        SendGrid::new_from_env().send_raw_mail(message.add_personalization(p)).await;
+        println!("[email_templates] sent `{}` v{}", self.template, self.version);
+    }
+}
+
+/// The email templates, ready to render.
+///
+/// Each template is the latest version from the configs repo, or the default
+/// if it is not in the configs repo. If a version from the configs repo does
+/// not render, for example because it uses a field the record does not have,
+/// the default is rendered instead so the email still goes out.
+pub struct EmailTemplates {
+    /// Renders subjects and plaintext bodies, without escaping HTML.
+    text: Handlebars<'static>,
+    /// Renders HTML bodies.
+    html: Handlebars<'static>,
+    versions: BTreeMap<String, i32>,
+}
+
+impl EmailTemplates {
+    /// Get the default templates.
+    pub fn defaults() -> Self {
+        let mut text = Handlebars::new();
+        text.register_escape_fn(handlebars::no_escape);
+        text.set_strict_mode(true);
+        let mut html = Handlebars::new();
+        html.set_strict_mode(true);
+
+        let mut templates = EmailTemplates {
+            text,
+            html,
+            versions: Default::default(),
+        };
+        for t in DEFAULT_EMAIL_TEMPLATES {
+            templates.text.register_template_string(&key(t.name, "subject", 0), t.subject).unwrap();
+            templates.text.register_template_string(&key(t.name, "text", 0), t.text).unwrap();
+        }
+        templates
+    }
+
+    /// Get the latest version of each template from the database.
+    pub fn load(db: &Database) -> Result<Self, crate::Error> {
+        let mut templates = EmailTemplates::defaults();
+        for t in EmailTemplate::get_latest(db)? {
+            if let Err(e) = templates.add(&t) {
+                println!("[email_templates] `{}` v{} does not parse, using the default: {}", t.name, t.version, e);
+            }
+        }
+        Ok(templates)
+    }
+
+    /// Get the latest version of each template from the database, or the
+    /// defaults if the database can not be read, so emails still go out.
+    pub fn load_or_defaults(db: &Database) -> Self {
+        EmailTemplates::load(db).unwrap_or_else(|e| {
+            println!("[email_templates] loading the templates failed, using the defaults: {}", e);
+            EmailTemplates::defaults()
+        })
+    }
+
+    /// Use a version of a template instead of the one we have.
+    pub fn add(&mut self, template: &EmailTemplate) -> Result<(), crate::Error> {
+        self.register(&template.name, template.version, &template.subject, &template.text_body, &template.html_body)
+    }
+
+    fn register(&mut self, name: &str, version: i32, subject: &str, text: &str, html: &str) -> Result<(), crate::Error> {
+        self.text.register_template_string(&key(name, "subject", version), subject)?;
+        self.text.register_template_string(&key(name, "text", version), text)?;
+        if !html.is_empty() {
+            self.html.register_template_string(&key(name, "html", version), html)?;
+        }
+        self.versions.insert(name.to_string(), version);
+        Ok(())
+    }
+
+    /// Render a template against a record. Besides the fields of the record,
+    /// templates can use `domain`, `gsuite_domain` and `github_org`.
+    pub fn render<T: Serialize>(&self, name: &str, record: &T) -> Result<RenderedEmail, crate::Error> {
+        let data = template_data(record)?;
+        let version = self.versions.get(name).cloned().unwrap_or_default();
+        if version > 0 {
+            match self.render_data(name, version, &data) {
+                Ok(email) => return Ok(email),
+                Err(e) => println!("[email_templates] rendering `{}` v{} failed, using the default: {}", name, version, e),
+            }
+        }
+
+        if default_email_template(name).is_none() {
+            return Err(crate::Error::not_found("email_templates", name));
+        }
+        self.render_data(name, 0, &data)
+    }
+
+    /// Render a version of a template against a record, without falling back
+    /// to the default. The version has to be added first.
+    pub fn render_version<T: Serialize>(&self, name: &str, version: i32, record: &T) -> Result<RenderedEmail, crate::Error> {
+        if !self.text.has_template(&key(name, "text", version)) {
+            return Err(crate::Error::not_found("email_templates", format!("{} v{}", name, version)));
+        }
+        self.render_data(name, version, &template_data(record)?)
+    }
+
+    fn render_data(&self, name: &str, version: i32, data: &Value) -> Result<RenderedEmail, crate::Error> {
+        let html_key = key(name, "html", version);
+        Ok(RenderedEmail {
+            template: name.to_string(),
+            version,
+            subject: self.text.render(&key(name, "subject", version), data)?.trim().to_string(),
+            text: self.text.render(&key(name, "text", version), data)?,
+            html: if self.html.has_template(&html_key) { self.html.render(&html_key, data)? } else { String::new() },
+        })
+    }
+}
+
+/// Get the fields of a record, along with the fields every template can use.
+fn template_data<T: Serialize>(record: &T) -> Result<Value, crate::Error> {
+    let mut data = serde_json::to_value(record)?;
+    if let Value::Object(fields) = &mut data {
+        for (k, v) in &[("domain", DOMAIN.to_string()), ("gsuite_domain", GSUITE_DOMAIN.to_string()), ("github_org", github_org())] {
+            fields.entry(k.to_string()).or_insert_with(|| json!(v));
+        }
+    }
+    Ok(data)
+}
+
+/// The name of a part of a version of a template in the handlebars registry.
+fn key(name: &str, part: &str, version: i32) -> String {
+    format!("{}/v{}/{}", name, version, part)
+}
+
+/// Save the templates in our configs that changed as new versions.
+///
+/// A template that does not parse is skipped, so the last version that did
+/// keeps being sent.
+pub fn sync_email_templates(db: &Database, templates: BTreeMap<String, EmailTemplateConfig>) -> Result<(), crate::Error> {
+    let latest: BTreeMap<String, EmailTemplate> = EmailTemplate::get_latest(db)?.into_iter().map(|t| (t.name.to_string(), t)).collect();
+
+    for (name, config) in templates {
+        if default_email_template(&name).is_none() {
+            println!("[email_templates] `{}` is not an email we send, skipping", name);
+            continue;
+        }
+
+        let version = match latest.get(&name) {
+            Some(t) if t.subject == config.subject && t.text_body == config.text && t.html_body == config.html => continue,
+            Some(t) => t.version + 1,
+            None => 1,
+        };
+
+        // Make sure the template parses before anything is sent with it.
+        if let Err(e) = EmailTemplates::defaults().register(&name, version, &config.subject, &config.text, &config.html) {
+            println!("[email_templates] `{}` does not parse, skipping: {}", name, e);
+            continue;
+        }
+
+        let new_template = NewEmailTemplate {
+            name: name.to_string(),
+            version,
+            subject: config.subject,
+            text_body: config.text,
+            html_body: config.html,
+            created_at: Utc::now(),
+        };
+
+        diesel::insert_into(email_templates::table).values(&new_template).execute(&db.conn())?;
+        println!("[email_templates] saved `{}` v{}", name, version);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use serde_json::json;
+
+    use crate::email_templates::{EmailTemplate, EmailTemplates};
+
+    #[test]
+    fn test_render_email_templates() {
+        std::env::set_var("GITHUB_ORG", "oxidecomputer");
+        let applicant = json!({ "name": "Jane Doe", "role": "Software Engineer" });
+
+        let mut templates = EmailTemplates::defaults();
+        let email = templates.render("applicant_rejection_timing", &applicant).unwrap();
+        assert_eq!(email.version, 0);
+        assert_eq!(email.subject, "Thank you for your application, Jane Doe");
+        assert!(email.text.starts_with("Dear Jane Doe,\n\nWe are so humbled"));
+
+        let mut template = EmailTemplate {
+            id: 1,
+            name: "applicant_rejection_timing".to_string(),
+            version: 2,
+            subject: "Thanks, {{name}}".to_string(),
+            text_body: "Hi {{name}} & co".to_string(),
+            html_body: "<p>Hi {{name}} & co</p>".to_string(),
+            created_at: Utc::now(),
+        };
+        templates.add(&template).unwrap();
+        let email = templates.render("applicant_rejection_timing", &json!({ "name": "<Jane>" })).unwrap();
+        assert_eq!(email.version, 2);
+        assert_eq!(email.text, "Hi <Jane> & co");
+        assert_eq!(email.html, "<p>Hi &lt;Jane&gt; & co</p>");
+
+        // A field the record does not have falls back to the default.
+        template.version = 3;
+        template.text_body = "Hi {{nickname}}".to_string();
+        templates.add(&template).unwrap();
+        assert_eq!(templates.render("applicant_rejection_timing", &applicant).unwrap().version, 0);
+        assert!(templates.render_version("applicant_rejection_timing", 3, &applicant).unwrap_err().is_invalid());
+        assert!(templates.render_version("applicant_rejection_timing", 4, &applicant).unwrap_err().is_not_found());
+
+        assert!(templates.render("not_a_template", &applicant).is_err());
+    }
+
+    #[test]
+    fn test_render_user_templates() {
+        std::env::set_var("GITHUB_ORG", "oxidecomputer");
+        let templates = EmailTemplates::defaults();
+        let user = json!({ "first_name": "Jane", "email": "jane@example.com", "aliases": ["j", "jd"], "github": "" });
+
+        let email = templates.render("user_new_account", &user).unwrap();
+        assert!(email.text.contains("Aliases: j, jd\n"));
+        assert!(email.text.contains("We do not have a github account for you."));
+    }
+}
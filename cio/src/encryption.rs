@@ -0,0 +1,136 @@
+use std::env;
+
+use aes_gcm::aead::{Aead, NewAead};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use rand::RngCore;
+
+/// The prefix of a value encrypted by `encrypt`, so we can tell it apart from a
+/// value that was saved before we started encrypting.
+const ENVELOPE_PREFIX: &str = "enc:v1:";
+
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+
+/// Encrypt a value with envelope encryption.
+///
+/// The value is encrypted with a new random data key, and the data key is
+/// encrypted with the key in the `CIO_TOKEN_ENCRYPTION_KEY` environment variable,
+/// which is 32 bytes encoded as base64. Both end up in the returned string, so
+/// rotating the key only means re-encrypting the data keys.
+///
+/// Empty values are left empty.
+pub fn encrypt(plaintext: &str) -> Result<String, crate::Error> {
+    encrypt_with_key(&key_from_env()?, plaintext)
+}
+
+/// Decrypt a value encrypted by `encrypt`. Values that were saved before we
+/// started encrypting are returned as is.
+pub fn decrypt(value: &str) -> Result<String, crate::Error> {
+    if !is_encrypted(value) {
+        return Ok(value.to_string());
+    }
+
+    decrypt_with_key(&key_from_env()?, value)
+}
+
+/// Returns true if the value was encrypted by `encrypt`.
+pub fn is_encrypted(value: &str) -> bool {
+    value.starts_with(ENVELOPE_PREFIX)
+}
+
+fn key_from_env() -> Result<Vec<u8>, crate::Error> {
+    let encoded = env::var("CIO_TOKEN_ENCRYPTION_KEY").map_err(|_| crate::Error::Encryption("CIO_TOKEN_ENCRYPTION_KEY must be set".to_string()))?;
+    let key = base64::decode(encoded.trim()).map_err(|e| crate::Error::Encryption(format!("decoding CIO_TOKEN_ENCRYPTION_KEY failed: {}", e)))?;
+    if key.len() != KEY_LEN {
+        return Err(crate::Error::Encryption(format!("CIO_TOKEN_ENCRYPTION_KEY must be {} bytes, got {}", KEY_LEN, key.len())));
+    }
+
+    Ok(key)
+}
+
+fn encrypt_with_key(key_encryption_key: &[u8], plaintext: &str) -> Result<String, crate::Error> {
+    if plaintext.is_empty() {
+        return Ok(String::new());
+    }
+
+    let mut data_key = [0u8; KEY_LEN];
+    rand::thread_rng().fill_bytes(&mut data_key);
+
+    let wrapped_key = seal(key_encryption_key, &data_key)?;
+    let ciphertext = seal(&data_key, plaintext.as_bytes())?;
+
+    Ok(format!("{}{}:{}", ENVELOPE_PREFIX, base64::encode(wrapped_key), base64::encode(ciphertext)))
+}
+
+fn decrypt_with_key(key_encryption_key: &[u8], value: &str) -> Result<String, crate::Error> {
+    let mut parts = value.trim_start_matches(ENVELOPE_PREFIX).splitn(2, ':');
+    let (wrapped_key, ciphertext) = match (parts.next(), parts.next()) {
+        (Some(k), Some(c)) => (k, c),
+        _ => return Err(crate::Error::Encryption("encrypted value is malformed".to_string())),
+    };
+
+    let wrapped_key = base64::decode(wrapped_key).map_err(|e| crate::Error::Encryption(format!("decoding data key failed: {}", e)))?;
+    let ciphertext = base64::decode(ciphertext).map_err(|e| crate::Error::Encryption(format!("decoding ciphertext failed: {}", e)))?;
+
+    let data_key = open(key_encryption_key, &wrapped_key)?;
+    if data_key.len() != KEY_LEN {
+        return Err(crate::Error::Encryption("data key is the wrong length".to_string()));
+    }
+    let plaintext = open(&data_key, &ciphertext)?;
+
+    String::from_utf8(plaintext).map_err(|e| crate::Error::Encryption(format!("decrypted value is not utf-8: {}", e)))
+}
+
+/// Encrypt with AES-256-GCM, returning the nonce followed by the ciphertext.
+fn seal(key: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, crate::Error> {
+    let cipher = Aes256Gcm::new(Key::from_slice(key));
+
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut nonce);
+
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), plaintext)
+        .map_err(|_| crate::Error::Encryption("encrypting failed".to_string()))?;
+
+    let mut sealed = nonce.to_vec();
+    sealed.extend(ciphertext);
+    Ok(sealed)
+}
+
+/// Decrypt the output of `seal`.
+fn open(key: &[u8], sealed: &[u8]) -> Result<Vec<u8>, crate::Error> {
+    if sealed.len() < NONCE_LEN {
+        return Err(crate::Error::Encryption("encrypted value is too short".to_string()));
+    }
+
+    let cipher = Aes256Gcm::new(Key::from_slice(key));
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+
+    cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| crate::Error::Encryption("decrypting failed, the key is wrong or the value was tampered with".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::encryption::{decrypt_with_key, encrypt_with_key, is_encrypted};
+
+    #[test]
+    fn test_encryption_round_trip() {
+        let key = [7u8; 32];
+
+        let encrypted = encrypt_with_key(&key, "some-refresh-token").unwrap();
+        assert!(is_encrypted(&encrypted));
+        assert!(!encrypted.contains("some-refresh-token"));
+        assert_eq!(decrypt_with_key(&key, &encrypted).unwrap(), "some-refresh-token");
+
+        // Every value gets its own data key and nonce.
+        assert_ne!(encrypt_with_key(&key, "some-refresh-token").unwrap(), encrypted);
+
+        // The wrong key does not decrypt the value.
+        assert!(decrypt_with_key(&[8u8; 32], &encrypted).is_err());
+
+        // Empty values stay empty.
+        assert_eq!(encrypt_with_key(&key, "").unwrap(), "");
+    }
+}
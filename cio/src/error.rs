@@ -0,0 +1,164 @@
+use std::error;
+use std::fmt;
+
+/// Error type returned by the database and Airtable functions generated by the
+/// `db` macro.
+pub enum Error {
+    /// A database query failed.
+    Diesel(diesel::result::Error),
+    /// A request to Airtable failed.
+    Airtable(airtable_api::APIError),
+    /// A request to Google Drive failed.
+    Drive(google_drive::APIError),
+    /// A request to rev.ai failed.
+    RevAI(revai::APIError),
+    /// A request to Google Sheets failed.
+    Sheets(sheets::APIError),
+    /// A request to GitHub failed.
+    GitHub(hubcaps::errors::Error),
+    /// The configs files could not be parsed.
+    Configs(String),
+    /// The record does not exist.
+    NotFound { table: String, id: String },
+    /// Serializing or deserializing a record failed.
+    Serialization(serde_json::Error),
+    /// Encrypting or decrypting a value failed.
+    Encryption(String),
+    /// Getting a new OAuth token for a product failed.
+    TokenRefresh { product: String, message: String },
+    /// The record is not valid, with a description of each problem.
+    Invalid(Vec<String>),
+    /// Parsing or rendering an email template failed.
+    Template(String),
+    /// A request to printy failed.
+    Print(String),
+}
+
+impl Error {
+    /// Return a not found error for a record in a table.
+    pub fn not_found<T: ToString, I: ToString>(table: T, id: I) -> Self {
+        Error::NotFound {
+            table: table.to_string(),
+            id: id.to_string(),
+        }
+    }
+
+    /// Returns true if the error is because the record does not exist.
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, Error::NotFound { .. } | Error::Diesel(diesel::result::Error::NotFound))
+    }
+
+    /// Returns true if the error is because the record, or a template rendered
+    /// against it, is not valid.
+    pub fn is_invalid(&self) -> bool {
+        matches!(self, Error::Invalid(_) | Error::Template(_))
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Diesel(e) => write!(f, "[db] {}", e),
+            Error::Airtable(e) => write!(f, "[airtable] {}", e),
+            Error::Drive(e) => write!(f, "[drive] {}", e),
+            Error::RevAI(e) => write!(f, "[revai] {}", e),
+            Error::Sheets(e) => write!(f, "[sheets] {}", e),
+            Error::GitHub(e) => write!(f, "[github] {}", e),
+            Error::Configs(e) => write!(f, "[configs] {}", e),
+            Error::NotFound { table, id } => write!(f, "record `{}` not found in `{}`", id, table),
+            Error::Serialization(e) => write!(f, "serialization failed: {}", e),
+            Error::Encryption(e) => write!(f, "[encryption] {}", e),
+            Error::TokenRefresh { product, message } => write!(f, "refreshing the {} token failed: {}", product, message),
+            Error::Invalid(problems) => write!(f, "invalid record: {}", problems.join("; ")),
+            Error::Template(e) => write!(f, "[template] {}", e),
+            Error::Print(e) => write!(f, "[print] {}", e),
+        }
+    }
+}
+
+impl fmt::Debug for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Diesel(e) => Some(e),
+            Error::Airtable(e) => Some(e),
+            Error::Drive(e) => Some(e),
+            Error::RevAI(e) => Some(e),
+            Error::Sheets(e) => Some(e),
+            Error::GitHub(e) => Some(e),
+            Error::Configs(_) => None,
+            Error::NotFound { .. } => None,
+            Error::Serialization(e) => Some(e),
+            Error::Encryption(_) => None,
+            Error::TokenRefresh { .. } => None,
+            Error::Invalid(_) => None,
+            Error::Template(_) => None,
+            Error::Print(_) => None,
+        }
+    }
+}
+
+impl From<diesel::result::Error> for Error {
+    fn from(e: diesel::result::Error) -> Self {
+        Error::Diesel(e)
+    }
+}
+
+impl From<airtable_api::APIError> for Error {
+    fn from(e: airtable_api::APIError) -> Self {
+        Error::Airtable(e)
+    }
+}
+
+impl From<google_drive::APIError> for Error {
+    fn from(e: google_drive::APIError) -> Self {
+        Error::Drive(e)
+    }
+}
+
+impl From<revai::APIError> for Error {
+    fn from(e: revai::APIError) -> Self {
+        Error::RevAI(e)
+    }
+}
+
+impl From<sheets::APIError> for Error {
+    fn from(e: sheets::APIError) -> Self {
+        Error::Sheets(e)
+    }
+}
+
+impl From<hubcaps::errors::Error> for Error {
+    fn from(e: hubcaps::errors::Error) -> Self {
+        Error::GitHub(e)
+    }
+}
+
+impl From<toml::de::Error> for Error {
+    fn from(e: toml::de::Error) -> Self {
+        Error::Configs(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Serialization(e)
+    }
+}
+
+impl From<handlebars::TemplateError> for Error {
+    fn from(e: handlebars::TemplateError) -> Self {
+        Error::Template(e.to_string())
+    }
+}
+
+impl From<handlebars::RenderError> for Error {
+    fn from(e: handlebars::RenderError) -> Self {
+        Error::Template(e.to_string())
+    }
+}
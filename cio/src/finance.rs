@@ -13,7 +13,7 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::airtable::{AIRTABLE_ACCOUNTS_PAYABLE_TABLE, AIRTABLE_BASE_ID_FINANCE, AIRTABLE_CREDIT_CARD_TRANSACTIONS_TABLE, AIRTABLE_EXPENSED_ITEMS_TABLE, AIRTABLE_SOFTWARE_VENDORS_TABLE};
-use crate::api_tokens::APIToken;
+use crate::api_tokens::TokenProvider;
 use crate::configs::{Group, User};
 use crate::core::UpdateAirtableRecord;
 use crate::db::Database;
@@ -27,6 +27,7 @@ use crate::utils::{authenticate_github_jwt, get_gsuite_token, github_org, GSUITE
     match_on = {
         "name" = "String",
     },
+    airtable_fields = ["link_to_transactions", "link_to_accounts_payable", "link_to_expensed_items"],
 }]
 #[derive(Debug, Insertable, AsChangeset, PartialEq, Clone, JsonSchema, Deserialize, Serialize)]
 #[table_name = "software_vendors"]
@@ -78,20 +79,14 @@ fn is_zero(num: &f32) -> bool {
 /// Implement updating the Airtable record for a SoftwareVendor.
 #[async_trait]
 impl UpdateAirtableRecord<SoftwareVendor> for SoftwareVendor {
-    async fn update_airtable_record(&mut self, record: SoftwareVendor) {
+    async fn update_airtable_record(&mut self, _record: SoftwareVendor) {
         // This is a function so we can't change it through the API.
         self.total_cost_per_month = 0.0;
-        // Keep this the same, we update it from the transactions.
-        self.link_to_transactions = record.link_to_transactions;
-        // Keep this the same, we update it from the accounts payable.
-        self.link_to_accounts_payable = record.link_to_accounts_payable;
-        // Keep this the same, we update it from the expensed items.
-        self.link_to_expensed_items = record.link_to_expensed_items;
     }
 }
 
 /// Sync software vendors from Airtable.
-pub async fn refresh_software_vendors() {
+pub async fn refresh_software_vendors() -> Result<(), crate::Error> {
     let gsuite_customer = env::var("GADMIN_ACCOUNT_ID").unwrap();
     let token = get_gsuite_token("").await;
     let gsuite = GSuite::new(&gsuite_customer, GSUITE_DOMAIN, token.clone());
@@ -146,7 +141,13 @@ pub async fn refresh_software_vendors() {
         }
 
         // Upsert the record in our database.
-        let mut db_vendor = vendor.upsert_in_db(&db);
+        let mut db_vendor = match vendor.upsert_in_db(&db) {
+            Ok(v) => v,
+            Err(e) => {
+                println!("upserting software vendor {} failed, skipping: {}", vendor.name, e);
+                continue;
+            }
+        };
 
         if db_vendor.airtable_record_id.is_empty() {
             db_vendor.airtable_record_id = vendor_record.id;
@@ -155,10 +156,12 @@ pub async fn refresh_software_vendors() {
         // Update the cost per month.
         db_vendor.total_cost_per_month = (db_vendor.cost_per_user_per_month * db_vendor.users as f32) + db_vendor.flat_cost_per_month;
 
-        db_vendor.update(&db).await;
+        if let Err(e) = db_vendor.update(&db).await {
+            println!("updating software vendor {} failed: {}", db_vendor.name, e);
+        }
     }
 
-    SoftwareVendors::get_from_db(&db).update_airtable().await
+    SoftwareVendors::get_from_db(&db)?.update_airtable(&db).await
 }
 
 #[db {
@@ -219,7 +222,7 @@ impl UpdateAirtableRecord<CreditCardTransaction> for CreditCardTransaction {
     async fn update_airtable_record(&mut self, _record: CreditCardTransaction) {}
 }
 
-pub async fn refresh_ramp_transactions() {
+pub async fn refresh_ramp_transactions() -> Result<(), crate::Error> {
     // Create the Ramp client.
     let ramp = Ramp::new_from_env().await;
 
@@ -274,10 +277,12 @@ pub async fn refresh_ramp_transactions() {
             link_to_vendor,
         };
 
-        nt.upsert(&db).await;
+        if let Err(e) = nt.upsert(&db).await {
+            println!("upserting ramp transaction {} failed: {}", nt.transaction_id, e);
+        }
     }
 
-    CreditCardTransactions::get_from_db(&db).update_airtable().await;
+    CreditCardTransactions::get_from_db(&db)?.update_airtable_incremental(&db).await
 }
 
 // Changes the vendor name to one that matches our existing list.
@@ -584,7 +589,9 @@ pub async fn refresh_brex_transactions() {
         }
 
         // Let's add the record to our database.
-        record.upsert(&db).await;
+        if let Err(e) = record.upsert(&db).await {
+            println!("upserting transaction {} failed: {}", record.transaction_id, e);
+        }
     }
 }
 
@@ -678,13 +685,21 @@ pub async fn refresh_accounts_payable() {
         }
 
         // Upsert the record in our database.
-        let mut db_bill = bill.upsert_in_db(&db);
+        let mut db_bill = match bill.upsert_in_db(&db) {
+            Ok(b) => b,
+            Err(e) => {
+                println!("upserting bill {} failed, skipping: {}", bill.confirmation_number, e);
+                continue;
+            }
+        };
 
         if db_bill.airtable_record_id.is_empty() {
             db_bill.airtable_record_id = bill_record.id;
         }
 
-        db_bill.update(&db).await;
+        if let Err(e) = db_bill.update(&db).await {
+            println!("updating bill {} failed: {}", db_bill.confirmation_number, e);
+        }
     }
 }
 
@@ -842,7 +857,9 @@ pub async fn refresh_expensify_transactions() {
         }
 
         // Let's add the record to our database.
-        record.upsert(&db).await;
+        if let Err(e) = record.upsert(&db).await {
+            println!("upserting transaction {} failed: {}", record.transaction_id, e);
+        }
     }
 }
 
@@ -892,26 +909,19 @@ pub async fn refresh_bill_com_transactions() {
         }
 
         // Let's add the record to our database.
-        record.upsert(&db).await;
+        if let Err(e) = record.upsert(&db).await {
+            println!("upserting bill {} failed: {}", record.confirmation_number, e);
+        }
     }
 }
 
-pub async fn sync_quickbooks() {
+pub async fn sync_quickbooks() -> Result<(), crate::Error> {
     // Initialize the database.
     let db = Database::new();
 
-    // Get the APIToken from the database.
-    let mut t = APIToken::get_from_db(&db, "quickbooks".to_string()).unwrap();
     // Initialize the QuickBooks client.
-    let mut qb = QuickBooks::new_from_env(t.company_id.to_string(), t.access_token, t.refresh_token);
-    let nt = qb.refresh_access_token().await.unwrap();
-    t.access_token = nt.access_token.to_string();
-    t.expires_in = nt.expires_in as i32;
-    t.refresh_token = nt.refresh_token.to_string();
-    t.refresh_token_expires_in = nt.x_refresh_token_expires_in as i32;
-    t.last_updated_at = Utc::now();
-    // Update the token in the database.
-    t.update(&db).await;
+    // This refreshes the token and saves the new one if it is about to expire.
+    let qb: QuickBooks = TokenProvider::new(&db).client().await?;
 
     let bill_payments = qb.list_bill_payments().await.unwrap();
     for bill_payment in bill_payments {
@@ -952,7 +962,9 @@ pub async fn sync_quickbooks() {
                     }
                 }
 
-                transaction.update(&db).await;
+                if let Err(e) = transaction.update(&db).await {
+                    println!("updating accounts payable {} failed: {}", transaction.confirmation_number, e);
+                }
                 continue;
             }
             Err(e) => {
@@ -1001,7 +1013,9 @@ pub async fn sync_quickbooks() {
                     for attachment in attachments {
                         transaction.receipts.push(attachment.temp_download_uri.to_string());
                     }
-                    transaction.update(&db).await;
+                    if let Err(e) = transaction.update(&db).await {
+                        println!("updating transaction {} failed: {}", transaction.transaction_id, e);
+                    }
                     continue;
                 }
                 Err(e) => {
@@ -1016,6 +1030,8 @@ pub async fn sync_quickbooks() {
         }
     }
     println!("len: {}", purchases.len());
+
+    Ok(())
 }
 
 fn clean_merchant_name(s: &str) -> String {
@@ -1067,7 +1083,7 @@ mod tests {
     #[ignore]
     #[tokio::test(flavor = "multi_thread")]
     async fn test_finance_quickbooks() {
-        sync_quickbooks().await;
+        sync_quickbooks().await.unwrap();
     }
 
     #[ignore]
@@ -1091,10 +1107,10 @@ mod tests {
     #[ignore]
     #[tokio::test(flavor = "multi_thread")]
     async fn test_finance() {
-        refresh_software_vendors().await;
+        refresh_software_vendors().await.unwrap();
 
         refresh_accounts_payable().await;
 
-        refresh_ramp_transactions().await;
+        refresh_ramp_transactions().await.unwrap();
     }
 }
@@ -14,9 +14,9 @@ use crate::db::Database;
 use crate::utils::{authenticate_github_jwt, create_or_update_file_in_github_repo, get_gsuite_token, github_org, GSUITE_DOMAIN};
 
 /// Make sure if an event is moved in Google Calendar that Airtable is updated.
-pub async fn sync_changes_to_google_events() {
+pub async fn sync_changes_to_google_events() -> Result<(), crate::Error> {
     let github = authenticate_github_jwt();
-    let configs = get_configs_from_repo(&github).await;
+    let configs = get_configs_from_repo(&github).await?;
 
     let gsuite_customer = env::var("GADMIN_ACCOUNT_ID").unwrap();
     let token = get_gsuite_token("").await;
@@ -109,11 +109,13 @@ The Airtable workspace lives at: https://{}-huddle-corp.oxide.computer
             }
         }
     }
+
+    Ok(())
 }
 
-pub async fn send_huddle_reminders() {
+pub async fn send_huddle_reminders() -> Result<(), crate::Error> {
     let github = authenticate_github_jwt();
-    let configs = get_configs_from_repo(&github).await;
+    let configs = get_configs_from_repo(&github).await?;
 
     let gsuite_customer = env::var("GADMIN_ACCOUNT_ID").unwrap();
     let token = get_gsuite_token("").await;
@@ -267,6 +269,8 @@ pub async fn send_huddle_reminders() {
             }
         }
     }
+
+    Ok(())
 }
 
 /// Email template for the meeting huddle reminders.
@@ -300,9 +304,9 @@ See you soon,
 The Oxide Airtable Huddle Bot"#;
 
 /// Sync the huddle meeting notes with the GitHub reports repository.
-pub async fn sync_huddle_meeting_notes() {
+pub async fn sync_huddle_meeting_notes() -> Result<(), crate::Error> {
     let github = authenticate_github_jwt();
-    let configs = get_configs_from_repo(&github).await;
+    let configs = get_configs_from_repo(&github).await?;
 
     // Define the date format.
     let date_format = "%A, %-d %B, %C%y";
@@ -344,11 +348,13 @@ pub async fn sync_huddle_meeting_notes() {
             create_or_update_file_in_github_repo(&reports_repo, "master", &notes_path, notes.as_bytes().to_vec()).await;
         }
     }
+
+    Ok(())
 }
 
-pub async fn sync_huddles() {
+pub async fn sync_huddles() -> Result<(), crate::Error> {
     let github = authenticate_github_jwt();
-    let configs = get_configs_from_repo(&github).await;
+    let configs = get_configs_from_repo(&github).await?;
 
     let gsuite_customer = env::var("GADMIN_ACCOUNT_ID").unwrap();
     let token = get_gsuite_token("").await;
@@ -516,6 +522,8 @@ pub async fn sync_huddles() {
             }
         }
     }
+
+    Ok(())
 }
 
 #[cfg(test)]
@@ -525,12 +533,12 @@ mod tests {
     #[ignore]
     #[tokio::test(flavor = "multi_thread")]
     async fn test_huddles() {
-        sync_changes_to_google_events().await;
+        sync_changes_to_google_events().await.unwrap();
 
-        sync_huddles().await;
+        sync_huddles().await.unwrap();
 
-        send_huddle_reminders().await;
+        send_huddle_reminders().await.unwrap();
 
-        sync_huddle_meeting_notes().await;
+        sync_huddle_meeting_notes().await.unwrap();
     }
 }
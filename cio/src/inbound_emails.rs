@@ -0,0 +1,727 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+
+use chrono::{DateTime, TimeZone, Utc};
+use diesel::prelude::*;
+use google_drive::GoogleDrive;
+use mailparse::{DispositionType, MailHeaderMap, ParsedMail};
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::applicants::Applicant;
+use crate::db::Database;
+use crate::finance::{NewAccountsPayable, SoftwareVendors};
+use crate::schema::{applicants, inbound_email_attachments, inbound_emails};
+use crate::shipments::NewInboundShipment;
+use crate::utils::{get_gsuite_token, DOMAIN, GSUITE_DOMAIN};
+
+/// The local parts of the addresses vendors send their invoices to.
+const ACCOUNTS_PAYABLE_ADDRESSES: &[&str] = &["bills", "invoices", "accounts-payable"];
+
+/// The local parts of the addresses shipping notices are forwarded to.
+const SHIPPING_ADDRESSES: &[&str] = &["packages", "shipping"];
+
+/// The domains carriers send shipping notices from, so we can route the ones
+/// that are sent to us directly and not forwarded.
+const CARRIER_DOMAINS: &[&str] = &["ups.com", "fedex.com", "usps.com", "dhl.com"];
+
+/// The shared drive and folder in it that attachments are uploaded to.
+const ATTACHMENTS_DRIVE: &str = "Automated Documents";
+const ATTACHMENTS_FOLDER: &str = "inbound_email_attachments";
+
+/// What we did with an inbound email.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InboundEmailRoute {
+    /// The email is from an applicant and was attached to their application.
+    ApplicantReply,
+    /// The email was sent to one of our accounts payable addresses and was
+    /// turned into an accounts payable record for finance to review.
+    VendorInvoice,
+    /// The email has a tracking number and was turned into an inbound shipment.
+    ShippingNotice,
+    /// None of the rules matched. The email is still stored.
+    Unrouted,
+}
+
+impl fmt::Display for InboundEmailRoute {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InboundEmailRoute::ApplicantReply => write!(f, "applicant-reply"),
+            InboundEmailRoute::VendorInvoice => write!(f, "vendor-invoice"),
+            InboundEmailRoute::ShippingNotice => write!(f, "shipping-notice"),
+            InboundEmailRoute::Unrouted => write!(f, "unrouted"),
+        }
+    }
+}
+
+#[derive(Debug, Insertable, PartialEq, Clone)]
+#[table_name = "inbound_emails"]
+pub struct NewInboundEmail {
+    pub message_id: String,
+    pub in_reply_to: String,
+    pub from_email: String,
+    pub from_name: String,
+    pub to_addresses: Vec<String>,
+    pub cc_addresses: Vec<String>,
+    pub subject: String,
+    pub text_body: String,
+    pub html_body: String,
+    pub raw: String,
+    pub spam_score: f32,
+    pub route: String,
+    pub applicant_id: Option<i32>,
+    pub accounts_payable_id: Option<i32>,
+    pub inbound_shipment_id: Option<i32>,
+    pub received_at: DateTime<Utc>,
+    pub routed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// An email sent to one of our addresses, received from the SendGrid parse
+/// webhook.
+#[derive(Debug, Queryable, Identifiable, PartialEq, Clone, JsonSchema, Deserialize, Serialize)]
+#[table_name = "inbound_emails"]
+pub struct InboundEmail {
+    pub id: i32,
+    pub message_id: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub in_reply_to: String,
+    pub from_email: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub from_name: String,
+    pub to_addresses: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cc_addresses: Vec<String>,
+    pub subject: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub text_body: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub html_body: String,
+    /// The full MIME message, so the attachments can be read again. The bodies
+    /// of the attachments are removed once they are uploaded to Drive.
+    #[serde(skip)]
+    pub raw: String,
+    #[serde(default)]
+    pub spam_score: f32,
+    /// The `InboundEmailRoute` the email took. This is empty until it was routed.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub route: String,
+    pub applicant_id: Option<i32>,
+    pub accounts_payable_id: Option<i32>,
+    pub inbound_shipment_id: Option<i32>,
+    pub received_at: DateTime<Utc>,
+    pub routed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Insertable, PartialEq, Clone)]
+#[table_name = "inbound_email_attachments"]
+pub struct NewInboundEmailAttachment {
+    pub inbound_email_id: i32,
+    pub part_index: i32,
+    pub filename: String,
+    pub content_type: String,
+    pub size_bytes: i32,
+    pub drive_file_id: String,
+    pub drive_url: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A file attached to an inbound email, uploaded to Google Drive.
+#[derive(Debug, Queryable, Identifiable, Associations, PartialEq, Clone, JsonSchema, Deserialize, Serialize)]
+#[belongs_to(InboundEmail)]
+#[table_name = "inbound_email_attachments"]
+pub struct InboundEmailAttachment {
+    pub id: i32,
+    pub inbound_email_id: i32,
+    /// The position of the attachment in the message.
+    pub part_index: i32,
+    pub filename: String,
+    pub content_type: String,
+    pub size_bytes: i32,
+    pub drive_file_id: String,
+    pub drive_url: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A file attached to a MIME message.
+#[derive(Debug, PartialEq, Clone)]
+pub struct EmailAttachment {
+    /// The position of the attachment in the message. Unlike the filename, this
+    /// is unique, and it does not change when the bodies are removed.
+    pub part_index: i32,
+    pub filename: String,
+    pub content_type: String,
+    pub contents: Vec<u8>,
+}
+
+impl NewInboundEmail {
+    /// Parse a MIME message, like the `email` field SendGrid sends when the
+    /// parse webhook is set to post the raw message.
+    pub fn parse(raw: &str) -> Result<Self, crate::Error> {
+        let parsed = parse_mail(raw)?;
+        let headers = &parsed.headers;
+        let header = |name: &str| headers.get_first_value(name).unwrap_or_default().trim().to_string();
+
+        let (from_name, from_email) = addresses(&header("From")).into_iter().next().unwrap_or_default();
+        let received_at = mailparse::dateparse(&header("Date")).map(|t| Utc.timestamp(t, 0)).unwrap_or_else(|_| Utc::now());
+
+        let mut message_id = header("Message-ID");
+        if message_id.is_empty() {
+            // Some senders leave out the message id. Use a hash of the message
+            // so the same email delivered twice is still only stored once.
+            let mut hasher = DefaultHasher::new();
+            raw.hash(&mut hasher);
+            message_id = format!("<{:x}@inbound.{}>", hasher.finish(), DOMAIN);
+        }
+
+        let mut text_body = String::new();
+        let mut html_body = String::new();
+        walk_parts(&parsed, &mut text_body, &mut html_body, &mut vec![])?;
+
+        let now = Utc::now();
+        Ok(NewInboundEmail {
+            message_id,
+            in_reply_to: header("In-Reply-To"),
+            from_email,
+            from_name,
+            to_addresses: addresses(&header("To")).into_iter().map(|(_, email)| email).collect(),
+            cc_addresses: addresses(&header("Cc")).into_iter().map(|(_, email)| email).collect(),
+            subject: header("Subject"),
+            text_body,
+            html_body,
+            raw: raw.to_string(),
+            spam_score: 0.0,
+            route: String::new(),
+            applicant_id: None,
+            accounts_payable_id: None,
+            inbound_shipment_id: None,
+            received_at,
+            routed_at: None,
+            created_at: now,
+        })
+    }
+
+    /// Store the email. If an email with the same message id was already stored,
+    /// nothing is added and that email is returned instead.
+    pub fn create(&self, db: &Database) -> Result<InboundEmail, crate::Error> {
+        let conn = db.conn();
+        let inserted = diesel::insert_into(inbound_emails::table)
+            .values(self)
+            .on_conflict(inbound_emails::dsl::message_id)
+            .do_nothing()
+            .get_result::<InboundEmail>(&conn)
+            .optional()?;
+
+        match inserted {
+            Some(email) => Ok(email),
+            None => Ok(inbound_emails::table.filter(inbound_emails::dsl::message_id.eq(&self.message_id)).first::<InboundEmail>(&conn)?),
+        }
+    }
+}
+
+impl InboundEmail {
+    pub fn get_by_id(db: &Database, id: i32) -> Result<Self, crate::Error> {
+        inbound_emails::table
+            .find(id)
+            .first::<InboundEmail>(&db.conn())
+            .optional()?
+            .ok_or_else(|| crate::Error::not_found("inbound_emails", id))
+    }
+
+    /// Get the emails an applicant sent us, newest first.
+    pub fn get_for_applicant(db: &Database, applicant_id: i32) -> Result<Vec<Self>, crate::Error> {
+        Ok(inbound_emails::table
+            .filter(inbound_emails::dsl::applicant_id.eq(applicant_id))
+            .order_by(inbound_emails::dsl::received_at.desc())
+            .load::<InboundEmail>(&db.conn())?)
+    }
+
+    /// Get the attachments that were uploaded to Drive.
+    pub fn get_attachments(&self, db: &Database) -> Result<Vec<InboundEmailAttachment>, crate::Error> {
+        Ok(InboundEmailAttachment::belonging_to(self)
+            .order_by(inbound_email_attachments::dsl::id.asc())
+            .load::<InboundEmailAttachment>(&db.conn())?)
+    }
+
+    /// Read the attachments from the MIME message.
+    pub fn attachments(&self) -> Result<Vec<EmailAttachment>, crate::Error> {
+        let parsed = parse_mail(&self.raw)?;
+        let mut attachments = vec![];
+        walk_parts(&parsed, &mut String::new(), &mut String::new(), &mut attachments)?;
+        Ok(attachments)
+    }
+
+    /// Upload the attachments to Drive, and route the email.
+    ///
+    /// This is safe to run again if it fails part of the way through:
+    /// attachments that were uploaded are not uploaded again, and an email
+    /// that was routed is left alone.
+    pub async fn process(&mut self, db: &Database) -> Result<InboundEmailRoute, crate::Error> {
+        let attachments = self.upload_attachments(db).await?;
+
+        if self.routed_at.is_some() {
+            println!("[inbound-email] {} was already routed to {}", self.message_id, self.route);
+            return Ok(self.stored_route());
+        }
+
+        let route = self.find_route(db)?;
+        match route {
+            InboundEmailRoute::ApplicantReply => {
+                // `find_route` already linked the applicant.
+            }
+            InboundEmailRoute::VendorInvoice => {
+                let bill = self.create_invoice_candidate(db, &attachments).await?;
+                self.accounts_payable_id = Some(bill.id);
+            }
+            InboundEmailRoute::ShippingNotice => {
+                let shipment = self.create_inbound_shipment(db).await?;
+                self.inbound_shipment_id = Some(shipment.id);
+            }
+            InboundEmailRoute::Unrouted => (),
+        }
+
+        self.route = route.to_string();
+        self.routed_at = Some(Utc::now());
+        diesel::update(inbound_emails::table.find(self.id))
+            .set((
+                inbound_emails::dsl::route.eq(&self.route),
+                inbound_emails::dsl::applicant_id.eq(self.applicant_id),
+                inbound_emails::dsl::accounts_payable_id.eq(self.accounts_payable_id),
+                inbound_emails::dsl::inbound_shipment_id.eq(self.inbound_shipment_id),
+                inbound_emails::dsl::routed_at.eq(self.routed_at),
+            ))
+            .execute(&db.conn())?;
+
+        println!("[inbound-email] routed {} from {} to {}", self.message_id, self.from_email, route);
+        Ok(route)
+    }
+
+    /// Decide which rule applies to the email. The first rule that matches wins:
+    /// replies from applicants, then invoices, then shipping notices.
+    fn find_route(&mut self, db: &Database) -> Result<InboundEmailRoute, crate::Error> {
+        if let Some(applicant) = applicants::table
+            .filter(applicants::dsl::email.eq(&self.from_email))
+            .order_by(applicants::dsl::submitted_time.desc())
+            .first::<Applicant>(&db.conn())
+            .optional()?
+        {
+            self.applicant_id = Some(applicant.id);
+            return Ok(InboundEmailRoute::ApplicantReply);
+        }
+
+        if is_addressed_to(&self.to_addresses, ACCOUNTS_PAYABLE_ADDRESSES) || is_addressed_to(&self.cc_addresses, ACCOUNTS_PAYABLE_ADDRESSES) {
+            return Ok(InboundEmailRoute::VendorInvoice);
+        }
+
+        let from_carrier = CARRIER_DOMAINS.iter().any(|d| email_domain(&self.from_email).ends_with(d));
+        if (from_carrier || is_addressed_to(&self.to_addresses, SHIPPING_ADDRESSES)) && find_tracking_number(&self.searchable_text()).is_some() {
+            return Ok(InboundEmailRoute::ShippingNotice);
+        }
+
+        Ok(InboundEmailRoute::Unrouted)
+    }
+
+    /// The route that was stored for the email.
+    fn stored_route(&self) -> InboundEmailRoute {
+        match self.route.as_str() {
+            "applicant-reply" => InboundEmailRoute::ApplicantReply,
+            "vendor-invoice" => InboundEmailRoute::VendorInvoice,
+            "shipping-notice" => InboundEmailRoute::ShippingNotice,
+            _ => InboundEmailRoute::Unrouted,
+        }
+    }
+
+    /// The subject and the body, which the routing rules search.
+    fn searchable_text(&self) -> String {
+        format!("{}\n{}", self.subject, self.text_body)
+    }
+
+    async fn upload_attachments(&mut self, db: &Database) -> Result<Vec<InboundEmailAttachment>, crate::Error> {
+        let mut uploaded = self.get_attachments(db)?;
+        let attachments: Vec<EmailAttachment> = self.attachments()?.into_iter().filter(|a| !uploaded.iter().any(|u| u.part_index == a.part_index)).collect();
+        if attachments.is_empty() {
+            self.strip_attachment_bodies(db)?;
+            return Ok(uploaded);
+        }
+
+        // Get gsuite token.
+        let token = get_gsuite_token("").await;
+
+        // Initialize the Google Drive client.
+        let drive_client = GoogleDrive::new(token);
+        // Figure out where our directory is.
+        // It should be in the shared drive : "Automated Documents"/"inbound_email_attachments"
+        let shared_drive = drive_client.get_drive_by_name(ATTACHMENTS_DRIVE).await?;
+        let drive_id = shared_drive.id.to_string();
+        let parent_id = match drive_client.get_file_by_name(&drive_id, ATTACHMENTS_FOLDER).await?.first() {
+            Some(folder) => folder.id.to_string(),
+            None => drive_client.create_folder(&drive_id, "", ATTACHMENTS_FOLDER).await?,
+        };
+
+        for attachment in attachments {
+            // Prefix the name with the email id and the position of the attachment,
+            // since files with the same name are updated in place.
+            let filename = format!("{}-{} - {}", self.id, attachment.part_index, attachment.filename);
+            let drive_file = drive_client
+                .create_or_update_file(&drive_id, &parent_id, &filename, &attachment.content_type, &attachment.contents)
+                .await?;
+
+            let new_attachment = NewInboundEmailAttachment {
+                inbound_email_id: self.id,
+                part_index: attachment.part_index,
+                filename: attachment.filename.to_string(),
+                content_type: attachment.content_type.to_string(),
+                size_bytes: attachment.contents.len() as i32,
+                drive_url: format!("https://drive.google.com/open?id={}", drive_file.id),
+                drive_file_id: drive_file.id,
+                created_at: Utc::now(),
+            };
+            uploaded.push(diesel::insert_into(inbound_email_attachments::table).values(&new_attachment).get_result(&db.conn())?);
+            println!("[inbound-email] uploaded attachment {} of {} to drive", attachment.filename, self.message_id);
+        }
+
+        self.strip_attachment_bodies(db)?;
+        Ok(uploaded)
+    }
+
+    /// Remove the bodies of the attachments from the stored message, now that
+    /// they are in Drive.
+    fn strip_attachment_bodies(&mut self, db: &Database) -> Result<(), crate::Error> {
+        let raw = strip_attachment_bodies(&self.raw)?;
+        if raw == self.raw {
+            return Ok(());
+        }
+
+        diesel::update(inbound_emails::table.find(self.id)).set(inbound_emails::dsl::raw.eq(&raw)).execute(&db.conn())?;
+        self.raw = raw;
+        Ok(())
+    }
+
+    /// Turn the email into an accounts payable record for finance to review.
+    async fn create_invoice_candidate(&self, db: &Database, attachments: &[InboundEmailAttachment]) -> Result<crate::finance::AccountsPayable, crate::Error> {
+        let text = self.searchable_text();
+
+        // Try to find the vendor by the domain they sent the email from.
+        let domain = email_domain(&self.from_email);
+        let vendor = SoftwareVendors::get_from_db(db)?.into_iter().find(|v| {
+            let website = v.website.trim_start_matches("https://").trim_start_matches("http://").trim_start_matches("www.");
+            let website = website.split('/').next().unwrap_or_default();
+            !website.is_empty() && domain.ends_with(website)
+        });
+
+        let bill = NewAccountsPayable {
+            confirmation_number: format!("EMAIL-{}", self.id),
+            amount: find_amount(&text).unwrap_or_default(),
+            invoice_number: find_invoice_number(&text).unwrap_or_default(),
+            vendor: match &vendor {
+                Some(v) => v.name.to_string(),
+                None if !self.from_name.is_empty() => self.from_name.to_string(),
+                None => domain.to_string(),
+            },
+            currency: "USD".to_string(),
+            date: self.received_at.naive_utc().date(),
+            payment_type: "".to_string(),
+            status: "Needs Review".to_string(),
+            notes: format!("Created from an email from {} with the subject \"{}\".", self.from_email, self.subject),
+            invoices: attachments.iter().map(|a| a.drive_url.to_string()).collect(),
+            link_to_vendor: vendor.map(|v| vec![v.airtable_record_id]).unwrap_or_default(),
+        };
+
+        bill.upsert(db).await
+    }
+
+    /// Create an inbound shipment for the tracking number in the email.
+    async fn create_inbound_shipment(&self, db: &Database) -> Result<crate::shipments::InboundShipment, crate::Error> {
+        // `find_route` only picks this route if there is a tracking number.
+        let (carrier, tracking_number) = find_tracking_number(&self.searchable_text()).unwrap_or_default();
+
+        let mut shipment = NewInboundShipment {
+            carrier: carrier.to_string(),
+            tracking_number: tracking_number.to_string(),
+            name: self.subject.to_string(),
+            notes: format!("Created from an email from {}.", self.from_email),
+            ..Default::default()
+        };
+        shipment.expand().await;
+        if shipment.tracking_number.is_empty() {
+            // The tracking API did not know about the shipment yet.
+            shipment.tracking_number = tracking_number;
+        }
+
+        shipment.upsert(db).await
+    }
+}
+
+fn parse_mail(raw: &str) -> Result<ParsedMail, crate::Error> {
+    mailparse::parse_mail(raw.as_bytes()).map_err(|e| crate::Error::Invalid(vec![format!("parsing the email failed: {}", e)]))
+}
+
+/// Collect the bodies and attachments of a message and all of its parts. The
+/// first `text/plain` and `text/html` parts that are not attachments are the
+/// bodies.
+fn walk_parts(part: &ParsedMail, text_body: &mut String, html_body: &mut String, attachments: &mut Vec<EmailAttachment>) -> Result<(), crate::Error> {
+    let invalid = |e: mailparse::MailParseError| crate::Error::Invalid(vec![format!("parsing the email failed: {}", e)]);
+
+    if !part.subparts.is_empty() {
+        for subpart in &part.subparts {
+            walk_parts(subpart, text_body, html_body, attachments)?;
+        }
+        return Ok(());
+    }
+
+    if let Some(filename) = attachment_filename(part) {
+        attachments.push(EmailAttachment {
+            part_index: attachments.len() as i32,
+            filename: if filename.is_empty() { format!("attachment-{}", attachments.len() + 1) } else { filename },
+            content_type: part.ctype.mimetype.to_string(),
+            contents: part.get_body_raw().map_err(invalid)?,
+        });
+    } else if part.ctype.mimetype == "text/plain" && text_body.is_empty() {
+        *text_body = part.get_body().map_err(invalid)?;
+    } else if part.ctype.mimetype == "text/html" && html_body.is_empty() {
+        *html_body = part.get_body().map_err(invalid)?;
+    }
+
+    Ok(())
+}
+
+/// The filename of a part if it is an attachment, which is empty if the sender
+/// did not name it.
+fn attachment_filename(part: &ParsedMail) -> Option<String> {
+    let disposition = part.get_content_disposition();
+    let filename = disposition.params.get("filename").or_else(|| part.ctype.params.get("name")).cloned().unwrap_or_default();
+    if matches!(disposition.disposition, DispositionType::Attachment) || !filename.is_empty() {
+        Some(filename)
+    } else {
+        None
+    }
+}
+
+/// Remove the bodies of the attachments from a MIME message. Their headers are
+/// kept, so the attachments are still found in the same places.
+fn strip_attachment_bodies(raw: &str) -> Result<String, crate::Error> {
+    let parsed = parse_mail(raw)?;
+    let mut bodies = vec![];
+    find_attachment_bodies(raw.as_bytes(), &parsed, &mut bodies);
+
+    let mut stripped = raw.to_string();
+    for body in bodies.into_iter().rev() {
+        stripped.replace_range(body, "");
+    }
+    Ok(stripped)
+}
+
+/// Collect where the body of each attachment of a message is in the raw message.
+fn find_attachment_bodies(raw: &[u8], part: &ParsedMail, bodies: &mut Vec<Range<usize>>) {
+    if !part.subparts.is_empty() {
+        for subpart in &part.subparts {
+            find_attachment_bodies(raw, subpart, bodies);
+        }
+        return;
+    }
+
+    if attachment_filename(part).is_none() {
+        return;
+    }
+
+    // The part borrows from the raw message, and its body starts after the blank
+    // line that ends its headers.
+    let start = part.raw_bytes.as_ptr() as usize - raw.as_ptr() as usize;
+    let headers_end = match (find_bytes(part.raw_bytes, b"\r\n\r\n"), find_bytes(part.raw_bytes, b"\n\n")) {
+        (Some(crlf), Some(lf)) if lf < crlf => lf + 2,
+        (Some(crlf), _) => crlf + 4,
+        (None, Some(lf)) => lf + 2,
+        (None, None) => return,
+    };
+    bodies.push(start + headers_end..start + part.raw_bytes.len());
+}
+
+fn find_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+/// Parse a list of addresses from a header into names and lowercase emails.
+fn addresses(value: &str) -> Vec<(String, String)> {
+    let list = match mailparse::addrparse(value) {
+        Ok(list) => list,
+        Err(_) => return vec![],
+    };
+
+    let mut addresses = vec![];
+    for addr in list.iter() {
+        let singles = match addr {
+            mailparse::MailAddr::Single(info) => vec![info.clone()],
+            mailparse::MailAddr::Group(group) => group.addrs.clone(),
+        };
+        for info in singles {
+            addresses.push((info.display_name.unwrap_or_default(), info.addr.to_lowercase()));
+        }
+    }
+    addresses
+}
+
+fn email_domain(email: &str) -> &str {
+    email.rsplit('@').next().unwrap_or_default()
+}
+
+/// Returns true if one of the addresses is one of the given local parts at our
+/// domains.
+fn is_addressed_to(addresses: &[String], local_parts: &[&str]) -> bool {
+    addresses.iter().any(|address| {
+        let mut split = address.splitn(2, '@');
+        let local = split.next().unwrap_or_default();
+        let domain = split.next().unwrap_or_default();
+        (domain == DOMAIN || domain == GSUITE_DOMAIN) && local_parts.contains(&local)
+    })
+}
+
+/// Find a tracking number in the text of a shipping notice, and the carrier
+/// it belongs to. FedEx and DHL numbers are plain digits, so they only count
+/// when the carrier is named.
+pub fn find_tracking_number(text: &str) -> Option<(String, String)> {
+    let lower = text.to_lowercase();
+    let rules = [
+        ("UPS", r"\b(1Z[0-9A-Z]{16})\b", true),
+        ("USPS", r"\b(9[2-5][0-9]{20}|9[2-5][0-9]{18})\b", true),
+        ("FedEx", r"\b([0-9]{15}|[0-9]{12})\b", lower.contains("fedex")),
+        ("DHL", r"\b([0-9]{10})\b", lower.contains("dhl")),
+    ];
+
+    for (carrier, pattern, applies) in rules.iter() {
+        if !applies {
+            continue;
+        }
+        let re = Regex::new(pattern).unwrap();
+        if let Some(c) = re.captures(text) {
+            return Some((carrier.to_string(), c[1].to_string()));
+        }
+    }
+
+    None
+}
+
+/// Find an invoice number, like `Invoice #INV-1234` or `Invoice number: 1234`.
+fn find_invoice_number(text: &str) -> Option<String> {
+    let re = Regex::new(r"(?i)invoice\s*(?:#|no\.?|number)?\s*:?\s*#?([A-Z0-9-]*[0-9][A-Z0-9-]*)\b").unwrap();
+    re.captures(text).map(|c| c[1].to_string())
+}
+
+/// Find the first dollar amount, like `$1,234.56`.
+fn find_amount(text: &str) -> Option<f32> {
+    let re = Regex::new(r"\$\s?([0-9][0-9,]*(?:\.[0-9]{2})?)").unwrap();
+    re.captures(text).and_then(|c| c[1].replace(',', "").parse::<f32>().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::inbound_emails::{find_amount, find_invoice_number, find_tracking_number, parse_mail, strip_attachment_bodies, walk_parts, NewInboundEmail};
+
+    #[test]
+    fn test_parse_inbound_email() {
+        let raw = "From: Jane Doe <Jane@Example.com>\r\n\
+To: bills@oxidecomputer.com\r\n\
+Subject: Invoice #INV-2041\r\n\
+Message-ID: <abc123@example.com>\r\n\
+Date: Thu, 24 Jun 2021 10:00:00 -0700\r\n\
+MIME-Version: 1.0\r\n\
+Content-Type: multipart/mixed; boundary=\"b1\"\r\n\
+\r\n\
+--b1\r\n\
+Content-Type: text/plain; charset=utf-8\r\n\
+\r\n\
+Your invoice for $1,250.00 is attached.\r\n\
+--b1\r\n\
+Content-Type: application/pdf; name=\"invoice.pdf\"\r\n\
+Content-Disposition: attachment; filename=\"invoice.pdf\"\r\n\
+Content-Transfer-Encoding: base64\r\n\
+\r\n\
+JVBERi0xLjQK\r\n\
+--b1--\r\n";
+
+        let email = NewInboundEmail::parse(raw).unwrap();
+        assert_eq!(email.message_id, "<abc123@example.com>");
+        assert_eq!(email.from_name, "Jane Doe");
+        assert_eq!(email.from_email, "jane@example.com");
+        assert_eq!(email.to_addresses, vec!["bills@oxidecomputer.com".to_string()]);
+        assert_eq!(email.received_at.to_rfc3339(), "2021-06-24T17:00:00+00:00");
+        assert!(email.text_body.starts_with("Your invoice"));
+
+        let text = format!("{}\n{}", email.subject, email.text_body);
+        assert_eq!(find_invoice_number(&text), Some("INV-2041".to_string()));
+        assert_eq!(find_amount(&text), Some(1250.0));
+    }
+
+    #[test]
+    fn test_strip_attachment_bodies() {
+        let raw = "From: vendor@example.com\r\n\
+Subject: Invoices\r\n\
+Content-Type: multipart/mixed; boundary=\"b1\"\r\n\
+\r\n\
+--b1\r\n\
+Content-Type: text/plain; charset=utf-8\r\n\
+\r\n\
+Both invoices are attached.\r\n\
+--b1\r\n\
+Content-Type: application/pdf; name=\"invoice.pdf\"\r\n\
+Content-Disposition: attachment; filename=\"invoice.pdf\"\r\n\
+Content-Transfer-Encoding: base64\r\n\
+\r\n\
+JVBERi0xLjQK\r\n\
+--b1\r\n\
+Content-Type: application/pdf; name=\"invoice.pdf\"\r\n\
+Content-Disposition: attachment; filename=\"invoice.pdf\"\r\n\
+Content-Transfer-Encoding: base64\r\n\
+\r\n\
+JVBERi0xLjUK\r\n\
+--b1--\r\n";
+
+        let attachments = |raw: &str| {
+            let mut text_body = String::new();
+            let mut attachments = vec![];
+            walk_parts(&parse_mail(raw).unwrap(), &mut text_body, &mut String::new(), &mut attachments).unwrap();
+            (text_body, attachments)
+        };
+
+        // Attachments with the same name are told apart by their position.
+        let (_, before) = attachments(raw);
+        assert_eq!(
+            before.iter().map(|a| (a.part_index, a.filename.as_str())).collect::<Vec<_>>(),
+            vec![(0, "invoice.pdf"), (1, "invoice.pdf")]
+        );
+        assert_eq!(before[1].contents, b"%PDF-1.5\n".to_vec());
+
+        let stripped = strip_attachment_bodies(raw).unwrap();
+        assert!(!stripped.contains("JVBERi0xLjQK"));
+        assert!(!stripped.contains("JVBERi0xLjUK"));
+
+        let (text_body, after) = attachments(&stripped);
+        assert!(text_body.starts_with("Both invoices"));
+        assert_eq!(
+            after.iter().map(|a| (a.part_index, a.filename.as_str())).collect::<Vec<_>>(),
+            vec![(0, "invoice.pdf"), (1, "invoice.pdf")]
+        );
+        assert!(after.iter().all(|a| a.contents.is_empty()));
+    }
+
+    #[test]
+    fn test_find_tracking_number() {
+        assert_eq!(
+            find_tracking_number("UPS Update: Package 1Z999AA10123456784 is on its way"),
+            Some(("UPS".to_string(), "1Z999AA10123456784".to_string()))
+        );
+        assert_eq!(
+            find_tracking_number("USPS tracking 9400111899223100012345"),
+            Some(("USPS".to_string(), "9400111899223100012345".to_string()))
+        );
+        assert_eq!(
+            find_tracking_number("Your FedEx shipment 123456789012 has shipped"),
+            Some(("FedEx".to_string(), "123456789012".to_string()))
+        );
+        // A bare number is not a tracking number unless the carrier is named.
+        assert_eq!(find_tracking_number("Order 123456789012 confirmed"), None);
+    }
+}
@@ -5,12 +5,12 @@ use std::{thread, time};
 
 use chrono::offset::Utc;
 use chrono::{DateTime, Duration};
-use cio_api::utils::{authenticate_github_jwt, list_all_github_repos};
 use futures_util::stream::TryStreamExt;
 use influxdb::InfluxDbWriteable;
 use influxdb::{Client as InfluxClient, Query as InfluxQuery};
 
 use crate::event_types::EventType;
+use crate::utils::{authenticate_github_jwt, list_all_github_repos};
 
 #[derive(Clone, Debug)]
 pub struct Client(pub InfluxClient);
@@ -801,18 +801,4 @@ mod tests {
         let influx = Client::new_from_env();
         influx.update_push_events().await;
     }
-
-    #[ignore]
-    #[tokio::test(flavor = "multi_thread")]
-    async fn test_cron_influx_pulls() {
-        let influx = Client::new_from_env();
-        influx.update_pull_request_events().await;
-    }
-
-    #[ignore]
-    #[tokio::test(flavor = "multi_thread")]
-    async fn test_cron_influx_issues() {
-        let influx = Client::new_from_env();
-        influx.update_issues_events().await;
-    }
 }
@@ -63,7 +63,7 @@ impl UpdateAirtableRecord<ApplicantInterview> for ApplicantInterview {
 }
 
 /// Sync interviews.
-pub async fn refresh_interviews(db: &Database) {
+pub async fn refresh_interviews(db: &Database) -> Result<(), crate::Error> {
     let gsuite_customer = env::var("GADMIN_ACCOUNT_ID").unwrap();
     let token = get_gsuite_token("").await;
     let gsuite = GSuite::new(&gsuite_customer, GSUITE_DOMAIN, token.clone());
@@ -88,7 +88,9 @@ pub async fn refresh_interviews(db: &Database) {
             if event.status == "cancelled" {
                 // See if we have the event.
                 if let Some(db_event) = ApplicantInterview::get_from_db(db, event.id.to_string()) {
-                    db_event.delete(db).await;
+                    if let Err(e) = db_event.delete(db).await {
+                        println!("deleting cancelled interview {} failed: {}", event.id, e);
+                    }
                 }
 
                 // Continue since we don't want to save this event again.
@@ -200,15 +202,17 @@ pub async fn refresh_interviews(db: &Database) {
                 // We only care about interviews where the candidate has interviewers.
                 continue;
             }
-            interview.upsert(db).await;
+            if let Err(e) = interview.upsert(db).await {
+                println!("upserting interview {} failed: {}", interview.name, e);
+            }
         }
     }
 
-    ApplicantInterviews::get_from_db(db).update_airtable().await;
+    ApplicantInterviews::get_from_db(db)?.update_airtable(db).await
 }
 
 /// Compile interview packets for each interviewee.
-pub async fn compile_packets(db: &Database) {
+pub async fn compile_packets(db: &Database) -> Result<(), crate::Error> {
     // Get gsuite token.
     let token = get_gsuite_token("").await;
 
@@ -225,7 +229,7 @@ pub async fn compile_packets(db: &Database) {
 
     // Iterate over each user we have in gsuite and download their materials
     // locally.
-    let employees = Users::get_from_db(db);
+    let employees = Users::get_from_db(db)?;
     for employee in employees {
         if employee.is_system_account() {
             continue;
@@ -249,7 +253,7 @@ pub async fn compile_packets(db: &Database) {
         download_materials(&drive_client, &materials_url, &employee.username).await;
     }
 
-    let interviews = ApplicantInterviews::get_from_db(db);
+    let interviews = ApplicantInterviews::get_from_db(db)?;
 
     // Let's group the interviewers into each interview.
     let mut interviewers: HashMap<String, Vec<(User, DateTime<Tz>, DateTime<Tz>)>> = HashMap::new();
@@ -403,8 +407,12 @@ The Oxide Team
         // Create or update the file in the google_drive.
         let drive_file = drive_client.create_or_update_file(&drive_id, &parent_id, &filename, "application/pdf", &buffer).await.unwrap();
         applicant.interview_packet = format!("https://drive.google.com/open?id={}", drive_file.id);
-        applicant.update(db).await;
+        if let Err(e) = applicant.update(db).await {
+            println!("updating interview packet for applicant {} failed: {}", applicant.name, e);
+        }
     }
+
+    Ok(())
 }
 
 /// Download materials file from Google drive and save it as a pdf under the persons username.
@@ -653,7 +661,7 @@ mod tests {
     #[tokio::test(flavor = "multi_thread")]
     async fn test_interviews() {
         let db = Database::new();
-        refresh_interviews(&db).await;
-        compile_packets(&db).await;
+        refresh_interviews(&db).await.unwrap();
+        compile_packets(&db).await.unwrap();
     }
 }
@@ -24,6 +24,7 @@ use crate::utils::github_org;
     match_on = {
         "issue" = "String",
     },
+    airtable_fields = ["papers"],
 }]
 #[derive(Debug, Insertable, AsChangeset, PartialEq, Clone, Deserialize, Serialize)]
 #[table_name = "journal_club_meetings"]
@@ -135,10 +136,7 @@ impl JournalClubMeeting {
 /// Implement updating the Airtable record for a JournalClubMeeting.
 #[async_trait]
 impl UpdateAirtableRecord<JournalClubMeeting> for JournalClubMeeting {
-    async fn update_airtable_record(&mut self, record: JournalClubMeeting) {
-        // Set the papers field, since it is pre-populated as table links.
-        self.papers = record.papers;
-    }
+    async fn update_airtable_record(&mut self, _record: JournalClubMeeting) {}
 }
 
 /// The data type for a NewJournalClubPaper.
@@ -169,7 +167,13 @@ impl UpdateAirtableRecord<JournalClubPaper> for JournalClubPaper {
     async fn update_airtable_record(&mut self, _record: JournalClubPaper) {
         // Get the current journal club meetings in Airtable so we can link to it.
         // TODO: make this more dry so we do not call it every single damn time.
-        let journal_club_meetings = JournalClubMeetings::get_from_airtable().await;
+        let journal_club_meetings = match JournalClubMeetings::get_from_airtable().await {
+            Ok(v) => v,
+            Err(e) => {
+                println!("[airtable] getting journal club meetings to link paper `{}` failed: {}", self.title, e);
+                return;
+            }
+        };
 
         // Iterate over the journal_club_meetings and see if we find a match.
         for (_id, meeting_record) in journal_club_meetings {
@@ -297,31 +301,17 @@ pub async fn refresh_db_journal_club_meetings(db: &Database, github: &Github) {
 
     // Sync journal_club_meetings.
     for journal_club_meeting in journal_club_meetings {
-        journal_club_meeting.to_model().upsert(db).await;
+        if let Err(e) = journal_club_meeting.to_model().upsert(db).await {
+            println!("upserting journal club meeting {} failed, skipping: {}", journal_club_meeting.issue, e);
+            continue;
+        }
 
         // Upsert the papers.
         for mut journal_club_paper in journal_club_meeting.papers {
             journal_club_paper.meeting = journal_club_meeting.issue.to_string();
-            journal_club_paper.upsert(db).await;
+            if let Err(e) = journal_club_paper.upsert(db).await {
+                println!("upserting journal club paper {} failed: {}", journal_club_paper.title, e);
+            }
         }
     }
 }
-
-#[cfg(test)]
-mod tests {
-    use crate::db::Database;
-    use crate::journal_clubs::{refresh_db_journal_club_meetings, JournalClubMeetings, JournalClubPapers};
-    use crate::utils::authenticate_github_jwt;
-
-    #[ignore]
-    #[tokio::test(flavor = "multi_thread")]
-    async fn test_cron_journal_club_meetings_and_papers() {
-        let github = authenticate_github_jwt();
-        let db = Database::new();
-
-        refresh_db_journal_club_meetings(&db, &github).await;
-
-        JournalClubPapers::get_from_db(&db).update_airtable().await;
-        JournalClubMeetings::get_from_db(&db).update_airtable().await;
-    }
-}
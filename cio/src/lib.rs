@@ -3,23 +3,35 @@
 
 pub mod airtable;
 pub mod analytics;
+pub mod api_auth;
 pub mod api_tokens;
 pub mod applicant_status;
 pub mod applicants;
+pub mod audit;
 pub mod auth_logins;
 pub mod certs;
 pub mod configs;
 pub mod core;
+pub mod cron;
 pub mod db;
+pub mod email_templates;
+pub mod encryption;
+pub mod error;
+pub mod event_types;
 pub mod finance;
 pub mod gsuite;
 pub mod huddles;
+pub mod inbound_emails;
+pub mod influx;
 pub mod interviews;
 pub mod journal_clubs;
 pub mod mailchimp;
 pub mod mailing_list;
 pub mod models;
+pub mod offboarding;
 pub mod png;
+pub mod print_jobs;
+pub mod queue;
 pub mod rack_line;
 pub mod recorded_meetings;
 pub mod rfds;
@@ -42,3 +54,5 @@ extern crate serde_json;
 
 #[macro_use]
 extern crate lopdf;
+
+pub use crate::error::Error;
@@ -183,7 +183,9 @@ pub async fn refresh_db_mailing_list_subscribers(db: &Database) {
     // Sync subscribers.
     for member in members {
         let ns: NewMailingListSubscriber = member.into();
-        ns.upsert(db).await;
+        if let Err(e) = ns.upsert(db).await {
+            println!("upserting mailing list subscriber {} failed: {}", ns.email, e);
+        }
     }
 }
 
@@ -217,19 +219,3 @@ impl Into<NewMailingListSubscriber> for MailchimpMember {
         }
     }
 }
-
-#[cfg(test)]
-mod tests {
-    use crate::db::Database;
-    use crate::mailing_list::{refresh_db_mailing_list_subscribers, MailingListSubscribers};
-
-    #[ignore]
-    #[tokio::test(flavor = "multi_thread")]
-    async fn test_cron_mailing_list_subscribers() {
-        // Initialize our database.
-        let db = Database::new();
-
-        refresh_db_mailing_list_subscribers(&db).await;
-        MailingListSubscribers::get_from_db(&db).update_airtable().await;
-    }
-}
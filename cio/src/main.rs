@@ -1,15 +1,31 @@
 use std::fs::File;
 use std::sync::Arc;
 
-use dropshot::{endpoint, ApiDescription, ConfigDropshot, ConfigLogging, ConfigLoggingLevel, HttpError, HttpResponseOk, HttpServerStarter, RequestContext};
+use dropshot::{
+    endpoint, ApiDescription, ConfigDropshot, ConfigLogging, ConfigLoggingLevel, HttpError, HttpResponseCreated, HttpResponseDeleted, HttpResponseOk, HttpServerStarter, PaginationParams, Path, Query,
+    RequestContext, ResultsPage, TypedBody, WhichPage,
+};
+use hyper::{Body, Response};
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
 
-use cio_api::applicants::{Applicant, Applicants};
-use cio_api::auth_logins::{AuthUser, AuthUsers};
-use cio_api::configs::{Building, Buildings, ConferenceRoom, ConferenceRooms, Group, Groups, Link, Links, User, Users};
+use cio_api::api_auth::{AuthError, Authenticator, Caller, Role};
+use cio_api::applicants::{Applicant, ApplicantFilter, ApplicantPatch, Applicants, NewApplicant};
+use cio_api::audit::AuditLogEntry;
+use cio_api::auth_logins::{AuthUser, AuthUserFilter, AuthUsers};
+use cio_api::configs::{Building, BuildingFilter, Buildings, ConferenceRoom, ConferenceRoomFilter, ConferenceRooms, Group, GroupFilter, Groups, Link, LinkFilter, Links, User, UserFilter, Users};
+use cio_api::core::Validate;
+use cio_api::cron::CronJobRun;
 use cio_api::db::Database;
-use cio_api::journal_clubs::{JournalClubMeeting, JournalClubMeetings};
-use cio_api::mailing_list::{MailingListSubscriber, MailingListSubscribers};
-use cio_api::models::{GithubRepo, GithubRepos, RFDs, RFD};
+use cio_api::email_templates::{default_email_template, EmailTemplate, EmailTemplates, RenderedEmail};
+use cio_api::journal_clubs::{JournalClubMeeting, JournalClubMeetingFilter, JournalClubMeetings};
+use cio_api::mailing_list::{MailingListSubscriber, MailingListSubscriberFilter, MailingListSubscribers};
+use cio_api::models::{GithubRepo, GithubRepoFilter, GithubRepos, RFDFilter, RFDs, RFD};
+use cio_api::queue::QueuedJob;
+use cio_api::recorded_meetings::{RecordedMeetingSearchResult, RecordedMeetingSegment};
+use cio_api::shipments::{NewOutboundShipment, OutboundShipment, OutboundShipmentFilter, OutboundShipmentPatch, OutboundShipments};
+use cio_api::swag_inventory::{NewSwagInventoryItem, SwagInventoryItem, SwagInventoryItemFilter, SwagInventoryItemPatch, SwagInventoryItems};
 
 #[tokio::main]
 async fn main() -> Result<(), String> {
@@ -36,18 +52,48 @@ async fn main() -> Result<(), String> {
      * Build a description of the API.
      */
     let mut api = ApiDescription::new();
+    api.register(api_create_applicant).unwrap();
+    api.register(api_create_outbound_shipment).unwrap();
+    api.register(api_create_swag_inventory_item).unwrap();
+    api.register(api_delete_applicant).unwrap();
+    api.register(api_delete_outbound_shipment).unwrap();
+    api.register(api_delete_swag_inventory_item).unwrap();
+    api.register(api_get_applicant).unwrap();
     api.register(api_get_applicants).unwrap();
+    api.register(api_get_auth_user).unwrap();
     api.register(api_get_auth_users).unwrap();
+    api.register(api_get_building).unwrap();
     api.register(api_get_buildings).unwrap();
+    api.register(api_get_conference_room).unwrap();
     api.register(api_get_conference_rooms).unwrap();
+    api.register(api_get_cron_job_runs).unwrap();
+    api.register(api_get_dead_queued_jobs).unwrap();
+    api.register(api_get_email_template_preview).unwrap();
+    api.register(api_get_github_repo).unwrap();
     api.register(api_get_github_repos).unwrap();
+    api.register(api_get_group).unwrap();
     api.register(api_get_groups).unwrap();
+    api.register(api_get_history).unwrap();
+    api.register(api_get_journal_club_meeting).unwrap();
     api.register(api_get_journal_club_meetings).unwrap();
+    api.register(api_get_link).unwrap();
     api.register(api_get_links).unwrap();
+    api.register(api_get_mailing_list_subscriber).unwrap();
     api.register(api_get_mailing_list_subscribers).unwrap();
+    api.register(api_get_outbound_shipment).unwrap();
+    api.register(api_get_outbound_shipments).unwrap();
+    api.register(api_get_rfd).unwrap();
     api.register(api_get_rfds).unwrap();
     api.register(api_get_schema).unwrap();
+    api.register(api_get_swag_inventory_item).unwrap();
+    api.register(api_get_swag_inventory_items).unwrap();
+    api.register(api_get_user).unwrap();
     api.register(api_get_users).unwrap();
+    api.register(api_requeue_queued_job).unwrap();
+    api.register(api_search_recorded_meetings).unwrap();
+    api.register(api_update_applicant).unwrap();
+    api.register(api_update_outbound_shipment).unwrap();
+    api.register(api_update_swag_inventory_item).unwrap();
 
     // Print the OpenAPI Spec to stdout.
     let mut api_definition = &mut api.openapi(&"CIO API", &"0.0.1");
@@ -80,6 +126,7 @@ async fn main() -> Result<(), String> {
  * Application-specific context (state shared by handler functions)
  */
 struct Context {
+    auth: Authenticator,
     db: Database,
     schema: String,
 }
@@ -89,7 +136,11 @@ impl Context {
      * Return a new Context.
      */
     pub async fn new(schema: String) -> Context {
-        Context { schema, db: Database::new() }
+        Context {
+            auth: Authenticator::default(),
+            schema,
+            db: Database::new(),
+        }
     }
 }
 
@@ -111,156 +162,1043 @@ async fn api_get_schema(rqctx: Arc<RequestContext<Context>>) -> Result<HttpRespo
 }
 
 /**
- * Fetch all auth users.
+ * Fetch auth users.
+ *
+ * Records are returned newest first, a page at a time.
+ *
+ * Requires the admin role.
  */
 #[endpoint {
     method = GET,
     path = "/auth/users",
 }]
-async fn api_get_auth_users(rqctx: Arc<RequestContext<Context>>) -> Result<HttpResponseOk<Vec<AuthUser>>, HttpError> {
+async fn api_get_auth_users(
+    rqctx: Arc<RequestContext<Context>>,
+    query: Query<PaginationParams<AuthUserFilter, PageSelector<AuthUserFilter>>>,
+) -> Result<HttpResponseOk<ResultsPage<AuthUser>>, HttpError> {
+    authorize(&rqctx, Role::Admin).await?;
     let api_context = rqctx.context();
     let db = &api_context.db;
+    let (filter, before_id, limit) = page_params(&rqctx, &query.into_inner())?;
 
-    Ok(HttpResponseOk(AuthUsers::get_from_db(db).0))
+    let records = AuthUsers::get_page_from_db(db, &filter, before_id, limit).map_err(handle_db_error)?.0;
+
+    results_page(records, &filter, |r| r.id)
+}
+
+/**
+ * Fetch an auth user.
+ *
+ * Requires the admin role.
+ */
+#[endpoint {
+    method = GET,
+    path = "/auth/users/{id}",
+}]
+async fn api_get_auth_user(rqctx: Arc<RequestContext<Context>>, path_params: Path<IdPathParams>) -> Result<HttpResponseOk<AuthUser>, HttpError> {
+    authorize(&rqctx, Role::Admin).await?;
+    let api_context = rqctx.context();
+    let db = &api_context.db;
+
+    Ok(HttpResponseOk(AuthUser::get_by_id(db, path_params.into_inner().id).map_err(handle_db_error)?))
 }
 
 /**
- * Fetch all applicants.
+ * Fetch applicants, optionally filtered by status and role.
+ *
+ * Records are returned newest first, a page at a time.
+ *
+ * Requires the HR role.
  */
 #[endpoint {
     method = GET,
     path = "/applicants",
 }]
-async fn api_get_applicants(rqctx: Arc<RequestContext<Context>>) -> Result<HttpResponseOk<Vec<Applicant>>, HttpError> {
+async fn api_get_applicants(
+    rqctx: Arc<RequestContext<Context>>,
+    query: Query<PaginationParams<ApplicantFilter, PageSelector<ApplicantFilter>>>,
+) -> Result<HttpResponseOk<ResultsPage<Applicant>>, HttpError> {
+    authorize(&rqctx, Role::HR).await?;
     let api_context = rqctx.context();
     let db = &api_context.db;
+    let (filter, before_id, limit) = page_params(&rqctx, &query.into_inner())?;
+
+    let records = Applicants::get_page_from_db(db, &filter, before_id, limit).map_err(handle_db_error)?.0;
 
-    Ok(HttpResponseOk(Applicants::get_from_db(db).0))
+    results_page(records, &filter, |r| r.id)
+}
+
+/**
+ * Fetch an applicant.
+ *
+ * Requires the HR role.
+ */
+#[endpoint {
+    method = GET,
+    path = "/applicants/{id}",
+}]
+async fn api_get_applicant(rqctx: Arc<RequestContext<Context>>, path_params: Path<IdPathParams>) -> Result<HttpResponseOk<Applicant>, HttpError> {
+    authorize(&rqctx, Role::HR).await?;
+    let api_context = rqctx.context();
+    let db = &api_context.db;
+
+    Ok(HttpResponseOk(Applicant::get_by_id(db, path_params.into_inner().id).map_err(handle_db_error)?))
+}
+
+/**
+ * Create an applicant, or update it if it already exists.
+ *
+ * Returns 201 if the applicant was created and 200 if it was updated.
+ *
+ * Requires the HR role.
+ */
+#[endpoint {
+    method = POST,
+    path = "/applicants",
+}]
+async fn api_create_applicant(rqctx: Arc<RequestContext<Context>>, body_param: TypedBody<NewApplicant>) -> Result<Response<Body>, HttpError> {
+    let caller = authorize(&rqctx, Role::HR).await?;
+    let api_context = rqctx.context();
+    let db = &api_context.db.with_audit_source(format!("api:{}", caller.email));
+    let applicant = body_param.into_inner();
+
+    applicant.validate().map_err(handle_db_error)?;
+
+    let status = upsert_status(Applicant::get_from_db(db, applicant.email.to_string(), applicant.sheet_id.to_string()).is_some());
+    json_response(status, &applicant.upsert(db).await.map_err(handle_db_error)?)
+}
+
+/**
+ * Change the fields of an applicant, fields that are left out are not changed.
+ *
+ * Requires the HR role.
+ */
+#[endpoint {
+    method = PATCH,
+    path = "/applicants/{id}",
+}]
+async fn api_update_applicant(rqctx: Arc<RequestContext<Context>>, path_params: Path<IdPathParams>, body_param: TypedBody<ApplicantPatch>) -> Result<HttpResponseOk<Applicant>, HttpError> {
+    let caller = authorize(&rqctx, Role::HR).await?;
+    let api_context = rqctx.context();
+    let db = &api_context.db.with_audit_source(format!("api:{}", caller.email));
+
+    let mut applicant = Applicant::get_by_id(db, path_params.into_inner().id).map_err(handle_db_error)?;
+    applicant.apply_patch(body_param.into_inner());
+    NewApplicant::from(applicant.clone()).validate().map_err(handle_db_error)?;
+
+    Ok(HttpResponseOk(applicant.update(db).await.map_err(handle_db_error)?))
+}
+
+/**
+ * Delete an applicant.
+ *
+ * Requires the HR role.
+ */
+#[endpoint {
+    method = DELETE,
+    path = "/applicants/{id}",
+}]
+async fn api_delete_applicant(rqctx: Arc<RequestContext<Context>>, path_params: Path<IdPathParams>) -> Result<HttpResponseDeleted, HttpError> {
+    let caller = authorize(&rqctx, Role::HR).await?;
+    let api_context = rqctx.context();
+    let db = &api_context.db.with_audit_source(format!("api:{}", caller.email));
+
+    let applicant = Applicant::get_by_id(db, path_params.into_inner().id).map_err(handle_db_error)?;
+    applicant.delete(db).await.map_err(handle_db_error)?;
+
+    Ok(HttpResponseDeleted())
 }
 
 /**
  * Fetch a list of office buildings.
+ *
+ * Records are returned newest first, a page at a time.
+ *
+ * Requires the employee role.
  */
 #[endpoint {
     method = GET,
     path = "/buildings",
 }]
-async fn api_get_buildings(rqctx: Arc<RequestContext<Context>>) -> Result<HttpResponseOk<Vec<Building>>, HttpError> {
+async fn api_get_buildings(
+    rqctx: Arc<RequestContext<Context>>,
+    query: Query<PaginationParams<BuildingFilter, PageSelector<BuildingFilter>>>,
+) -> Result<HttpResponseOk<ResultsPage<Building>>, HttpError> {
+    authorize(&rqctx, Role::Employee).await?;
+    let api_context = rqctx.context();
+    let db = &api_context.db;
+    let (filter, before_id, limit) = page_params(&rqctx, &query.into_inner())?;
+
+    let records = Buildings::get_page_from_db(db, &filter, before_id, limit).map_err(handle_db_error)?.0;
+
+    results_page(records, &filter, |r| r.id)
+}
+
+/**
+ * Fetch an office building.
+ *
+ * Requires the employee role.
+ */
+#[endpoint {
+    method = GET,
+    path = "/buildings/{id}",
+}]
+async fn api_get_building(rqctx: Arc<RequestContext<Context>>, path_params: Path<IdPathParams>) -> Result<HttpResponseOk<Building>, HttpError> {
+    authorize(&rqctx, Role::Employee).await?;
     let api_context = rqctx.context();
     let db = &api_context.db;
 
-    Ok(HttpResponseOk(Buildings::get_from_db(db).0))
+    Ok(HttpResponseOk(Building::get_by_id(db, path_params.into_inner().id).map_err(handle_db_error)?))
 }
 
 /**
  * Fetch a list of conference rooms.
+ *
+ * Records are returned newest first, a page at a time.
+ *
+ * Requires the employee role.
  */
 #[endpoint {
     method = GET,
     path = "/conference_rooms",
 }]
-#[inline]
-async fn api_get_conference_rooms(rqctx: Arc<RequestContext<Context>>) -> Result<HttpResponseOk<Vec<ConferenceRoom>>, HttpError> {
+async fn api_get_conference_rooms(
+    rqctx: Arc<RequestContext<Context>>,
+    query: Query<PaginationParams<ConferenceRoomFilter, PageSelector<ConferenceRoomFilter>>>,
+) -> Result<HttpResponseOk<ResultsPage<ConferenceRoom>>, HttpError> {
+    authorize(&rqctx, Role::Employee).await?;
+    let api_context = rqctx.context();
+    let db = &api_context.db;
+    let (filter, before_id, limit) = page_params(&rqctx, &query.into_inner())?;
+
+    let records = ConferenceRooms::get_page_from_db(db, &filter, before_id, limit).map_err(handle_db_error)?.0;
+
+    results_page(records, &filter, |r| r.id)
+}
+
+/**
+ * Fetch a conference room.
+ *
+ * Requires the employee role.
+ */
+#[endpoint {
+    method = GET,
+    path = "/conference_rooms/{id}",
+}]
+async fn api_get_conference_room(rqctx: Arc<RequestContext<Context>>, path_params: Path<IdPathParams>) -> Result<HttpResponseOk<ConferenceRoom>, HttpError> {
+    authorize(&rqctx, Role::Employee).await?;
+    let api_context = rqctx.context();
+    let db = &api_context.db;
+
+    Ok(HttpResponseOk(ConferenceRoom::get_by_id(db, path_params.into_inner().id).map_err(handle_db_error)?))
+}
+
+/// Query parameters for filtering cron job runs.
+#[derive(Debug, Clone, Default, JsonSchema, Deserialize)]
+struct CronJobRunsQuery {
+    /// Only return runs of the job with this name.
+    #[serde(default)]
+    name: String,
+    /// The maximum number of runs to return, defaults to 100 and is capped at 500.
+    #[serde(default)]
+    limit: i64,
+}
+
+/**
+ * Fetch the most recent runs of the cron jobs, newest first.
+ *
+ * Requires the admin role.
+ */
+#[endpoint {
+    method = GET,
+    path = "/cron/runs",
+}]
+async fn api_get_cron_job_runs(rqctx: Arc<RequestContext<Context>>, query_args: Query<CronJobRunsQuery>) -> Result<HttpResponseOk<Vec<CronJobRun>>, HttpError> {
+    authorize(&rqctx, Role::Admin).await?;
     let api_context = rqctx.context();
     let db = &api_context.db;
+    let query = query_args.into_inner();
+
+    let name = if query.name.is_empty() { None } else { Some(query.name.as_str()) };
+    let limit = if query.limit > 0 { query.limit.min(500) } else { 100 };
+
+    Ok(HttpResponseOk(CronJobRun::get_from_db(db, name, limit).map_err(handle_db_error)?))
+}
+
+/// Path parameters for previewing an email template.
+#[derive(Debug, Clone, JsonSchema, Deserialize)]
+struct EmailTemplatePreviewPathParams {
+    /// The name of the template, like `applicant_rejection_timing`.
+    name: String,
+    /// The id of the record to render the template against.
+    id: i32,
+}
 
-    Ok(HttpResponseOk(ConferenceRooms::get_from_db(db).0))
+/// Query parameters for previewing an email template.
+#[derive(Debug, Clone, JsonSchema, Deserialize)]
+struct EmailTemplatePreviewQuery {
+    /// The version of the template to render, the one we send if it is not set.
+    version: Option<i32>,
+}
+
+/**
+ * Render an email template against a record, without sending it.
+ *
+ * Templates about applicants and users require the HR role, since the email
+ * includes their personal details. Everything else requires the admin role.
+ */
+#[endpoint {
+    method = GET,
+    path = "/email-templates/{name}/preview/{id}",
+}]
+async fn api_get_email_template_preview(
+    rqctx: Arc<RequestContext<Context>>,
+    path_params: Path<EmailTemplatePreviewPathParams>,
+    query_args: Query<EmailTemplatePreviewQuery>,
+) -> Result<HttpResponseOk<RenderedEmail>, HttpError> {
+    let params = path_params.into_inner();
+    let query = query_args.into_inner();
+    let template = default_email_template(&params.name).ok_or_else(|| handle_db_error(cio_api::Error::not_found("email_templates", &params.name)))?;
+    let role = match template.table {
+        "applicants" | "users" => Role::HR,
+        _ => Role::Admin,
+    };
+    authorize(&rqctx, role).await?;
+    let api_context = rqctx.context();
+    let db = &api_context.db;
+
+    // Render against the same fields as the email we send.
+    let record = match template.table {
+        "applicants" => serde_json::to_value(Applicant::get_by_id(db, params.id).map_err(handle_db_error)?).map_err(|e| handle_db_error(e.into()))?,
+        "outbound_shipments" => OutboundShipment::get_by_id(db, params.id).map_err(handle_db_error)?.email_template_data(),
+        _ => User::get_by_id(db, params.id).map_err(handle_db_error)?.email_template_data(),
+    };
+
+    let email = match query.version {
+        Some(version) => {
+            let mut templates = EmailTemplates::defaults();
+            if version > 0 {
+                templates
+                    .add(&EmailTemplate::get_version(db, &params.name, version).map_err(handle_db_error)?)
+                    .map_err(handle_db_error)?;
+            }
+            templates.render_version(&params.name, version, &record)
+        }
+        None => EmailTemplates::load(db).map_err(handle_db_error)?.render(&params.name, &record),
+    };
+
+    Ok(HttpResponseOk(email.map_err(handle_db_error)?))
+}
+
+/// Path parameters for fetching the history of a record.
+#[derive(Debug, Clone, JsonSchema, Deserialize)]
+struct HistoryPathParams {
+    /// The database table of the record, like `applicants` or `outbound_shipments`.
+    table: String,
+    /// The id of the record.
+    id: i32,
+}
+
+/**
+ * Fetch the changes made to a record, newest first.
+ *
+ * The history of applicants and users requires the HR role, since it includes
+ * their personal details. The history of everything else requires the admin role.
+ */
+#[endpoint {
+    method = GET,
+    path = "/history/{table}/{id}",
+}]
+async fn api_get_history(rqctx: Arc<RequestContext<Context>>, path_params: Path<HistoryPathParams>) -> Result<HttpResponseOk<Vec<AuditLogEntry>>, HttpError> {
+    let params = path_params.into_inner();
+    let role = match params.table.as_str() {
+        "applicants" | "users" => Role::HR,
+        _ => Role::Admin,
+    };
+    authorize(&rqctx, role).await?;
+    let api_context = rqctx.context();
+    let db = &api_context.db;
+
+    Ok(HttpResponseOk(AuditLogEntry::get_for_record(db, &params.table, params.id).map_err(handle_db_error)?))
 }
 
 /**
  * Fetch a list of our GitHub repositories.
+ *
+ * Records are returned newest first, a page at a time.
+ *
+ * Requires the employee role.
  */
 #[endpoint {
     method = GET,
     path = "/github/repos",
 }]
-async fn api_get_github_repos(rqctx: Arc<RequestContext<Context>>) -> Result<HttpResponseOk<Vec<GithubRepo>>, HttpError> {
+async fn api_get_github_repos(
+    rqctx: Arc<RequestContext<Context>>,
+    query: Query<PaginationParams<GithubRepoFilter, PageSelector<GithubRepoFilter>>>,
+) -> Result<HttpResponseOk<ResultsPage<GithubRepo>>, HttpError> {
+    authorize(&rqctx, Role::Employee).await?;
+    let api_context = rqctx.context();
+    let db = &api_context.db;
+    let (filter, before_id, limit) = page_params(&rqctx, &query.into_inner())?;
+
+    let records = GithubRepos::get_page_from_db(db, &filter, before_id, limit).map_err(handle_db_error)?.0;
+
+    results_page(records, &filter, |r| r.id)
+}
+
+/**
+ * Fetch one of our GitHub repositories.
+ *
+ * Requires the employee role.
+ */
+#[endpoint {
+    method = GET,
+    path = "/github/repos/{id}",
+}]
+async fn api_get_github_repo(rqctx: Arc<RequestContext<Context>>, path_params: Path<IdPathParams>) -> Result<HttpResponseOk<GithubRepo>, HttpError> {
+    authorize(&rqctx, Role::Employee).await?;
     let api_context = rqctx.context();
     let db = &api_context.db;
 
-    Ok(HttpResponseOk(GithubRepos::get_from_db(db).0))
+    Ok(HttpResponseOk(GithubRepo::get_by_id(db, path_params.into_inner().id).map_err(handle_db_error)?))
 }
 
 /**
  * Fetch a list of Google groups.
+ *
+ * Records are returned newest first, a page at a time.
+ *
+ * Requires the employee role.
  */
 #[endpoint {
     method = GET,
     path = "/groups",
 }]
-async fn api_get_groups(rqctx: Arc<RequestContext<Context>>) -> Result<HttpResponseOk<Vec<Group>>, HttpError> {
+async fn api_get_groups(rqctx: Arc<RequestContext<Context>>, query: Query<PaginationParams<GroupFilter, PageSelector<GroupFilter>>>) -> Result<HttpResponseOk<ResultsPage<Group>>, HttpError> {
+    authorize(&rqctx, Role::Employee).await?;
+    let api_context = rqctx.context();
+    let db = &api_context.db;
+    let (filter, before_id, limit) = page_params(&rqctx, &query.into_inner())?;
+
+    let records = Groups::get_page_from_db(db, &filter, before_id, limit).map_err(handle_db_error)?.0;
+
+    results_page(records, &filter, |r| r.id)
+}
+
+/**
+ * Fetch a Google group.
+ *
+ * Requires the employee role.
+ */
+#[endpoint {
+    method = GET,
+    path = "/groups/{id}",
+}]
+async fn api_get_group(rqctx: Arc<RequestContext<Context>>, path_params: Path<IdPathParams>) -> Result<HttpResponseOk<Group>, HttpError> {
+    authorize(&rqctx, Role::Employee).await?;
     let api_context = rqctx.context();
     let db = &api_context.db;
 
-    Ok(HttpResponseOk(Groups::get_from_db(db).0))
+    Ok(HttpResponseOk(Group::get_by_id(db, path_params.into_inner().id).map_err(handle_db_error)?))
 }
 
 /**
  * Fetch a list of journal club meetings.
+ *
+ * Records are returned newest first, a page at a time.
+ *
+ * Requires the employee role.
  */
 #[endpoint {
     method = GET,
     path = "/journal_club_meetings",
 }]
-async fn api_get_journal_club_meetings(rqctx: Arc<RequestContext<Context>>) -> Result<HttpResponseOk<Vec<JournalClubMeeting>>, HttpError> {
+async fn api_get_journal_club_meetings(
+    rqctx: Arc<RequestContext<Context>>,
+    query: Query<PaginationParams<JournalClubMeetingFilter, PageSelector<JournalClubMeetingFilter>>>,
+) -> Result<HttpResponseOk<ResultsPage<JournalClubMeeting>>, HttpError> {
+    authorize(&rqctx, Role::Employee).await?;
+    let api_context = rqctx.context();
+    let db = &api_context.db;
+    let (filter, before_id, limit) = page_params(&rqctx, &query.into_inner())?;
+
+    let records = JournalClubMeetings::get_page_from_db(db, &filter, before_id, limit).map_err(handle_db_error)?.0;
+
+    results_page(records, &filter, |r| r.id)
+}
+
+/**
+ * Fetch a journal club meeting.
+ *
+ * Requires the employee role.
+ */
+#[endpoint {
+    method = GET,
+    path = "/journal_club_meetings/{id}",
+}]
+async fn api_get_journal_club_meeting(rqctx: Arc<RequestContext<Context>>, path_params: Path<IdPathParams>) -> Result<HttpResponseOk<JournalClubMeeting>, HttpError> {
+    authorize(&rqctx, Role::Employee).await?;
     let api_context = rqctx.context();
     let db = &api_context.db;
 
-    Ok(HttpResponseOk(JournalClubMeetings::get_from_db(db).0))
+    Ok(HttpResponseOk(JournalClubMeeting::get_by_id(db, path_params.into_inner().id).map_err(handle_db_error)?))
 }
 
 /**
  * Fetch a list of internal links.
+ *
+ * Records are returned newest first, a page at a time.
+ *
+ * Requires the employee role.
  */
 #[endpoint {
     method = GET,
     path = "/links",
 }]
-async fn api_get_links(rqctx: Arc<RequestContext<Context>>) -> Result<HttpResponseOk<Vec<Link>>, HttpError> {
+async fn api_get_links(rqctx: Arc<RequestContext<Context>>, query: Query<PaginationParams<LinkFilter, PageSelector<LinkFilter>>>) -> Result<HttpResponseOk<ResultsPage<Link>>, HttpError> {
+    authorize(&rqctx, Role::Employee).await?;
+    let api_context = rqctx.context();
+    let db = &api_context.db;
+    let (filter, before_id, limit) = page_params(&rqctx, &query.into_inner())?;
+
+    let records = Links::get_page_from_db(db, &filter, before_id, limit).map_err(handle_db_error)?.0;
+
+    results_page(records, &filter, |r| r.id)
+}
+
+/**
+ * Fetch an internal link.
+ *
+ * Requires the employee role.
+ */
+#[endpoint {
+    method = GET,
+    path = "/links/{id}",
+}]
+async fn api_get_link(rqctx: Arc<RequestContext<Context>>, path_params: Path<IdPathParams>) -> Result<HttpResponseOk<Link>, HttpError> {
+    authorize(&rqctx, Role::Employee).await?;
     let api_context = rqctx.context();
     let db = &api_context.db;
 
-    Ok(HttpResponseOk(Links::get_from_db(db).0))
+    Ok(HttpResponseOk(Link::get_by_id(db, path_params.into_inner().id).map_err(handle_db_error)?))
 }
 
 /**
  * Fetch a list of mailing list subscribers.
+ *
+ * Records are returned newest first, a page at a time.
+ *
+ * Requires the admin role.
  */
 #[endpoint {
     method = GET,
     path = "/mailing_list_subscribers",
 }]
-async fn api_get_mailing_list_subscribers(rqctx: Arc<RequestContext<Context>>) -> Result<HttpResponseOk<Vec<MailingListSubscriber>>, HttpError> {
+async fn api_get_mailing_list_subscribers(
+    rqctx: Arc<RequestContext<Context>>,
+    query: Query<PaginationParams<MailingListSubscriberFilter, PageSelector<MailingListSubscriberFilter>>>,
+) -> Result<HttpResponseOk<ResultsPage<MailingListSubscriber>>, HttpError> {
+    authorize(&rqctx, Role::Admin).await?;
+    let api_context = rqctx.context();
+    let db = &api_context.db;
+    let (filter, before_id, limit) = page_params(&rqctx, &query.into_inner())?;
+
+    let records = MailingListSubscribers::get_page_from_db(db, &filter, before_id, limit).map_err(handle_db_error)?.0;
+
+    results_page(records, &filter, |r| r.id)
+}
+
+/**
+ * Fetch a mailing list subscriber.
+ *
+ * Requires the admin role.
+ */
+#[endpoint {
+    method = GET,
+    path = "/mailing_list_subscribers/{id}",
+}]
+async fn api_get_mailing_list_subscriber(rqctx: Arc<RequestContext<Context>>, path_params: Path<IdPathParams>) -> Result<HttpResponseOk<MailingListSubscriber>, HttpError> {
+    authorize(&rqctx, Role::Admin).await?;
+    let api_context = rqctx.context();
+    let db = &api_context.db;
+
+    Ok(HttpResponseOk(MailingListSubscriber::get_by_id(db, path_params.into_inner().id).map_err(handle_db_error)?))
+}
+
+/**
+ * Fetch outbound shipments.
+ *
+ * Records are returned newest first, a page at a time.
+ *
+ * Requires the admin role.
+ */
+#[endpoint {
+    method = GET,
+    path = "/outbound_shipments",
+}]
+async fn api_get_outbound_shipments(
+    rqctx: Arc<RequestContext<Context>>,
+    query: Query<PaginationParams<OutboundShipmentFilter, PageSelector<OutboundShipmentFilter>>>,
+) -> Result<HttpResponseOk<ResultsPage<OutboundShipment>>, HttpError> {
+    authorize(&rqctx, Role::Admin).await?;
     let api_context = rqctx.context();
     let db = &api_context.db;
+    let (filter, before_id, limit) = page_params(&rqctx, &query.into_inner())?;
+
+    let records = OutboundShipments::get_page_from_db(db, &filter, before_id, limit).map_err(handle_db_error)?.0;
 
-    Ok(HttpResponseOk(MailingListSubscribers::get_from_db(db).0))
+    results_page(records, &filter, |r| r.id)
 }
 
 /**
- * Fetch all RFDs.
+ * Fetch an outbound shipment.
+ *
+ * Requires the admin role.
+ */
+#[endpoint {
+    method = GET,
+    path = "/outbound_shipments/{id}",
+}]
+async fn api_get_outbound_shipment(rqctx: Arc<RequestContext<Context>>, path_params: Path<IdPathParams>) -> Result<HttpResponseOk<OutboundShipment>, HttpError> {
+    authorize(&rqctx, Role::Admin).await?;
+    let api_context = rqctx.context();
+    let db = &api_context.db;
+
+    Ok(HttpResponseOk(OutboundShipment::get_by_id(db, path_params.into_inner().id).map_err(handle_db_error)?))
+}
+
+/**
+ * Create an outbound shipment.
+ *
+ * New shipments do not have a carrier or tracking number yet, so this always
+ * creates a shipment rather than updating one that matches.
+ *
+ * Requires the admin role.
+ */
+#[endpoint {
+    method = POST,
+    path = "/outbound_shipments",
+}]
+async fn api_create_outbound_shipment(rqctx: Arc<RequestContext<Context>>, body_param: TypedBody<NewOutboundShipment>) -> Result<HttpResponseCreated<OutboundShipment>, HttpError> {
+    let caller = authorize(&rqctx, Role::Admin).await?;
+    let api_context = rqctx.context();
+    let db = &api_context.db.with_audit_source(format!("api:{}", caller.email));
+    let outbound_shipment = body_param.into_inner();
+
+    outbound_shipment.validate().map_err(handle_db_error)?;
+
+    Ok(HttpResponseCreated(outbound_shipment.create(db).await.map_err(handle_db_error)?))
+}
+
+/**
+ * Change the fields of an outbound shipment, fields that are left out are not changed.
+ *
+ * Requires the admin role.
+ */
+#[endpoint {
+    method = PATCH,
+    path = "/outbound_shipments/{id}",
+}]
+async fn api_update_outbound_shipment(
+    rqctx: Arc<RequestContext<Context>>,
+    path_params: Path<IdPathParams>,
+    body_param: TypedBody<OutboundShipmentPatch>,
+) -> Result<HttpResponseOk<OutboundShipment>, HttpError> {
+    let caller = authorize(&rqctx, Role::Admin).await?;
+    let api_context = rqctx.context();
+    let db = &api_context.db.with_audit_source(format!("api:{}", caller.email));
+
+    let mut outbound_shipment = OutboundShipment::get_by_id(db, path_params.into_inner().id).map_err(handle_db_error)?;
+    outbound_shipment.apply_patch(body_param.into_inner());
+    NewOutboundShipment::from(outbound_shipment.clone()).validate().map_err(handle_db_error)?;
+
+    Ok(HttpResponseOk(outbound_shipment.update(db).await.map_err(handle_db_error)?))
+}
+
+/**
+ * Delete an outbound shipment.
+ *
+ * Requires the admin role.
+ */
+#[endpoint {
+    method = DELETE,
+    path = "/outbound_shipments/{id}",
+}]
+async fn api_delete_outbound_shipment(rqctx: Arc<RequestContext<Context>>, path_params: Path<IdPathParams>) -> Result<HttpResponseDeleted, HttpError> {
+    let caller = authorize(&rqctx, Role::Admin).await?;
+    let api_context = rqctx.context();
+    let db = &api_context.db.with_audit_source(format!("api:{}", caller.email));
+
+    let outbound_shipment = OutboundShipment::get_by_id(db, path_params.into_inner().id).map_err(handle_db_error)?;
+    outbound_shipment.delete(db).await.map_err(handle_db_error)?;
+
+    Ok(HttpResponseDeleted())
+}
+
+/**
+ * Fetch the webhook jobs that failed too many times to be retried, newest first.
+ *
+ * Requires the admin role.
+ */
+#[endpoint {
+    method = GET,
+    path = "/queued_jobs/dead",
+}]
+async fn api_get_dead_queued_jobs(rqctx: Arc<RequestContext<Context>>) -> Result<HttpResponseOk<Vec<QueuedJob>>, HttpError> {
+    authorize(&rqctx, Role::Admin).await?;
+    let api_context = rqctx.context();
+    let db = &api_context.db;
+
+    Ok(HttpResponseOk(QueuedJob::get_dead_from_db(db).map_err(handle_db_error)?))
+}
+
+/**
+ * Put a dead webhook job back in the queue, to be retried with a fresh set of attempts.
+ *
+ * Requires the admin role.
+ */
+#[endpoint {
+    method = POST,
+    path = "/queued_jobs/{id}/requeue",
+}]
+async fn api_requeue_queued_job(rqctx: Arc<RequestContext<Context>>, path_params: Path<IdPathParams>) -> Result<HttpResponseOk<QueuedJob>, HttpError> {
+    authorize(&rqctx, Role::Admin).await?;
+    let api_context = rqctx.context();
+    let db = &api_context.db;
+
+    let job = QueuedJob::get_by_id(db, path_params.into_inner().id).map_err(handle_db_error)?;
+
+    Ok(HttpResponseOk(job.requeue(db).map_err(handle_db_error)?))
+}
+
+/**
+ * Fetch RFDs, optionally filtered by state.
+ *
+ * Records are returned newest first, a page at a time.
+ *
+ * Requires the employee role.
  */
 #[endpoint {
     method = GET,
     path = "/rfds",
 }]
-async fn api_get_rfds(rqctx: Arc<RequestContext<Context>>) -> Result<HttpResponseOk<Vec<RFD>>, HttpError> {
+async fn api_get_rfds(rqctx: Arc<RequestContext<Context>>, query: Query<PaginationParams<RFDFilter, PageSelector<RFDFilter>>>) -> Result<HttpResponseOk<ResultsPage<RFD>>, HttpError> {
+    authorize(&rqctx, Role::Employee).await?;
+    let api_context = rqctx.context();
+    let db = &api_context.db;
+    let (filter, before_id, limit) = page_params(&rqctx, &query.into_inner())?;
+
+    let records = RFDs::get_page_from_db(db, &filter, before_id, limit).map_err(handle_db_error)?.0;
+
+    results_page(records, &filter, |r| r.id)
+}
+
+/**
+ * Fetch an RFD.
+ *
+ * Requires the employee role.
+ */
+#[endpoint {
+    method = GET,
+    path = "/rfds/{id}",
+}]
+async fn api_get_rfd(rqctx: Arc<RequestContext<Context>>, path_params: Path<IdPathParams>) -> Result<HttpResponseOk<RFD>, HttpError> {
+    authorize(&rqctx, Role::Employee).await?;
+    let api_context = rqctx.context();
+    let db = &api_context.db;
+
+    Ok(HttpResponseOk(RFD::get_by_id(db, path_params.into_inner().id).map_err(handle_db_error)?))
+}
+
+/// Query parameters for searching recorded meetings.
+#[derive(Debug, Clone, Default, JsonSchema, Deserialize)]
+struct RecordedMeetingSearchQuery {
+    /// The words to search for.
+    q: String,
+    /// The maximum number of results to return, defaults to 50.
+    #[serde(default)]
+    limit: i64,
+}
+
+/**
+ * Search the transcripts and chat logs of recorded meetings.
+ *
+ * Results are returned best match first, each with a link to the moment in the
+ * meeting video where it was said. Only the meetings the caller was invited to
+ * or organized are searched, admins search every meeting.
+ *
+ * Requires the employee role.
+ */
+#[endpoint {
+    method = GET,
+    path = "/recorded_meetings/search",
+}]
+async fn api_search_recorded_meetings(rqctx: Arc<RequestContext<Context>>, query_args: Query<RecordedMeetingSearchQuery>) -> Result<HttpResponseOk<Vec<RecordedMeetingSearchResult>>, HttpError> {
+    let caller = authorize(&rqctx, Role::Employee).await?;
     let api_context = rqctx.context();
     let db = &api_context.db;
+    let query = query_args.into_inner();
+
+    let limit = if query.limit > 0 { query.limit.min(500) } else { 50 };
+    let attendee = if caller.roles.contains(&Role::Admin) { None } else { Some(caller.email.as_str()) };
 
-    Ok(HttpResponseOk(RFDs::get_from_db(db).0))
+    Ok(HttpResponseOk(RecordedMeetingSegment::search(db, &query.q, attendee, limit).map_err(handle_db_error)?))
 }
 
 /**
- * Fetch a list of employees.
+ * Fetch swag inventory items.
+ *
+ * Records are returned newest first, a page at a time.
+ *
+ * Requires the admin role.
+ */
+#[endpoint {
+    method = GET,
+    path = "/swag_inventory_items",
+}]
+async fn api_get_swag_inventory_items(
+    rqctx: Arc<RequestContext<Context>>,
+    query: Query<PaginationParams<SwagInventoryItemFilter, PageSelector<SwagInventoryItemFilter>>>,
+) -> Result<HttpResponseOk<ResultsPage<SwagInventoryItem>>, HttpError> {
+    authorize(&rqctx, Role::Admin).await?;
+    let api_context = rqctx.context();
+    let db = &api_context.db;
+    let (filter, before_id, limit) = page_params(&rqctx, &query.into_inner())?;
+
+    let records = SwagInventoryItems::get_page_from_db(db, &filter, before_id, limit).map_err(handle_db_error)?.0;
+
+    results_page(records, &filter, |r| r.id)
+}
+
+/**
+ * Fetch a swag inventory item.
+ *
+ * Requires the admin role.
+ */
+#[endpoint {
+    method = GET,
+    path = "/swag_inventory_items/{id}",
+}]
+async fn api_get_swag_inventory_item(rqctx: Arc<RequestContext<Context>>, path_params: Path<IdPathParams>) -> Result<HttpResponseOk<SwagInventoryItem>, HttpError> {
+    authorize(&rqctx, Role::Admin).await?;
+    let api_context = rqctx.context();
+    let db = &api_context.db;
+
+    Ok(HttpResponseOk(SwagInventoryItem::get_by_id(db, path_params.into_inner().id).map_err(handle_db_error)?))
+}
+
+/**
+ * Create a swag inventory item, or update it if it already exists.
+ *
+ * Returns 201 if the item was created and 200 if it was updated.
+ *
+ * Requires the admin role.
+ */
+#[endpoint {
+    method = POST,
+    path = "/swag_inventory_items",
+}]
+async fn api_create_swag_inventory_item(rqctx: Arc<RequestContext<Context>>, body_param: TypedBody<NewSwagInventoryItem>) -> Result<Response<Body>, HttpError> {
+    let caller = authorize(&rqctx, Role::Admin).await?;
+    let api_context = rqctx.context();
+    let db = &api_context.db.with_audit_source(format!("api:{}", caller.email));
+    let swag_inventory_item = body_param.into_inner();
+
+    swag_inventory_item.validate().map_err(handle_db_error)?;
+
+    let status = upsert_status(SwagInventoryItem::get_from_db(db, swag_inventory_item.item.to_string(), swag_inventory_item.size.to_string()).is_some());
+    json_response(status, &swag_inventory_item.upsert(db).await.map_err(handle_db_error)?)
+}
+
+/**
+ * Change the fields of a swag inventory item, fields that are left out are not changed.
+ *
+ * Requires the admin role.
+ */
+#[endpoint {
+    method = PATCH,
+    path = "/swag_inventory_items/{id}",
+}]
+async fn api_update_swag_inventory_item(
+    rqctx: Arc<RequestContext<Context>>,
+    path_params: Path<IdPathParams>,
+    body_param: TypedBody<SwagInventoryItemPatch>,
+) -> Result<HttpResponseOk<SwagInventoryItem>, HttpError> {
+    let caller = authorize(&rqctx, Role::Admin).await?;
+    let api_context = rqctx.context();
+    let db = &api_context.db.with_audit_source(format!("api:{}", caller.email));
+
+    let mut swag_inventory_item = SwagInventoryItem::get_by_id(db, path_params.into_inner().id).map_err(handle_db_error)?;
+    swag_inventory_item.apply_patch(body_param.into_inner());
+    NewSwagInventoryItem::from(swag_inventory_item.clone()).validate().map_err(handle_db_error)?;
+
+    Ok(HttpResponseOk(swag_inventory_item.update(db).await.map_err(handle_db_error)?))
+}
+
+/**
+ * Delete a swag inventory item.
+ *
+ * Requires the admin role.
+ */
+#[endpoint {
+    method = DELETE,
+    path = "/swag_inventory_items/{id}",
+}]
+async fn api_delete_swag_inventory_item(rqctx: Arc<RequestContext<Context>>, path_params: Path<IdPathParams>) -> Result<HttpResponseDeleted, HttpError> {
+    let caller = authorize(&rqctx, Role::Admin).await?;
+    let api_context = rqctx.context();
+    let db = &api_context.db.with_audit_source(format!("api:{}", caller.email));
+
+    let swag_inventory_item = SwagInventoryItem::get_by_id(db, path_params.into_inner().id).map_err(handle_db_error)?;
+    swag_inventory_item.delete(db).await.map_err(handle_db_error)?;
+
+    Ok(HttpResponseDeleted())
+}
+
+/**
+ * Fetch a list of employees, optionally filtered by department.
+ *
+ * Records are returned newest first, a page at a time.
+ *
+ * Requires the employee role.
  */
 #[endpoint {
     method = GET,
     path = "/users",
 }]
-async fn api_get_users(rqctx: Arc<RequestContext<Context>>) -> Result<HttpResponseOk<Vec<User>>, HttpError> {
+async fn api_get_users(rqctx: Arc<RequestContext<Context>>, query: Query<PaginationParams<UserFilter, PageSelector<UserFilter>>>) -> Result<HttpResponseOk<ResultsPage<User>>, HttpError> {
+    let caller = authorize(&rqctx, Role::Employee).await?;
+    let api_context = rqctx.context();
+    let db = &api_context.db;
+    let (filter, before_id, limit) = page_params(&rqctx, &query.into_inner())?;
+
+    let users = Users::get_page_from_db(db, &filter, before_id, limit).map_err(handle_db_error)?.0;
+
+    // Only HR gets to see home addresses and birthdays.
+    let users = users.into_iter().map(|u| u.redact_for(&caller)).collect();
+
+    results_page(users, &filter, |r| r.id)
+}
+
+/**
+ * Fetch an employee.
+ *
+ * Requires the employee role.
+ */
+#[endpoint {
+    method = GET,
+    path = "/users/{id}",
+}]
+async fn api_get_user(rqctx: Arc<RequestContext<Context>>, path_params: Path<IdPathParams>) -> Result<HttpResponseOk<User>, HttpError> {
+    let caller = authorize(&rqctx, Role::Employee).await?;
     let api_context = rqctx.context();
     let db = &api_context.db;
 
-    Ok(HttpResponseOk(Users::get_from_db(db).0))
+    let user = User::get_by_id(db, path_params.into_inner().id).map_err(handle_db_error)?;
+
+    Ok(HttpResponseOk(user.redact_for(&caller)))
+}
+
+/// Path parameters for fetching a single record.
+#[derive(Debug, Clone, JsonSchema, Deserialize)]
+struct IdPathParams {
+    /// The id of the record.
+    id: i32,
+}
+
+/// The page token for listing records: the filter the first page was requested
+/// with and the id of the last record on the previous page.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct PageSelector<F> {
+    filter: F,
+    last_id: i32,
+}
+
+/// Get the filter, the id to list records before, and the number of records to
+/// return from the pagination parameters of a request.
+fn page_params<F>(rqctx: &Arc<RequestContext<Context>>, params: &PaginationParams<F, PageSelector<F>>) -> Result<(F, Option<i32>, i64), HttpError>
+where
+    F: Clone + DeserializeOwned + Serialize,
+{
+    let limit = rqctx.page_limit(params)?.get() as i64;
+
+    Ok(match &params.page {
+        WhichPage::First(filter) => (filter.clone(), None, limit),
+        WhichPage::Next(selector) => (selector.filter.clone(), Some(selector.last_id), limit),
+    })
+}
+
+/// Return a page of records, with a token for the next page that keeps the filter.
+fn results_page<T, F>(records: Vec<T>, filter: &F, id: fn(&T) -> i32) -> Result<HttpResponseOk<ResultsPage<T>>, HttpError>
+where
+    T: Serialize,
+    F: Clone + Serialize,
+{
+    Ok(HttpResponseOk(ResultsPage::new(records, filter, |record, filter| PageSelector {
+        filter: filter.clone(),
+        last_id: id(record),
+    })?))
+}
+
+/// Authenticate the caller from their bearer token and check they have the role.
+/// Callers we can't authenticate get a 401 and callers without the role get a 403.
+async fn authorize(rqctx: &Arc<RequestContext<Context>>, role: Role) -> Result<Caller, HttpError> {
+    let api_context = rqctx.context();
+
+    let authorization = {
+        let req = rqctx.request.lock().await;
+        req.headers().get(http::header::AUTHORIZATION).and_then(|v| v.to_str().ok()).map(|v| v.to_string())
+    };
+
+    let caller = api_context.auth.authenticate(&api_context.db, authorization.as_deref()).await.map_err(|e| match e {
+        AuthError::Internal(e) => handle_db_error(e),
+        e => HttpError::for_client_error(None, http::StatusCode::UNAUTHORIZED, e.to_string()),
+    })?;
+
+    if !caller.has_role(role) {
+        return Err(HttpError::for_client_error(
+            None,
+            http::StatusCode::FORBIDDEN,
+            format!("`{}` does not have the {} role", caller.email, role),
+        ));
+    }
+
+    Ok(caller)
+}
+
+/// The status for a create endpoint that upserts: 200 if the record already
+/// existed and was updated, 201 if it was created.
+fn upsert_status(exists: bool) -> http::StatusCode {
+    if exists {
+        http::StatusCode::OK
+    } else {
+        http::StatusCode::CREATED
+    }
+}
+
+/// Build a JSON response with a status that is only known once the request has
+/// been handled, which the typed dropshot responses can't express.
+fn json_response<T: Serialize>(status: http::StatusCode, body: &T) -> Result<Response<Body>, HttpError> {
+    let body = serde_json::to_string(body).map_err(|e| HttpError::for_internal_error(e.to_string()))?;
+
+    Response::builder()
+        .status(status)
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .body(body.into())
+        .map_err(|e| HttpError::for_internal_error(e.to_string()))
+}
+
+/// Convert an error from the database into a 404 if the record does not exist,
+/// a 400 if the record is not valid, or a 500 for the client.
+fn handle_db_error(e: cio_api::Error) -> HttpError {
+    if e.is_not_found() {
+        return HttpError::for_client_error(None, http::StatusCode::NOT_FOUND, e.to_string());
+    }
+    if e.is_invalid() {
+        return HttpError::for_client_error(None, http::StatusCode::BAD_REQUEST, e.to_string());
+    }
+
+    println!("database request failed: {}", e);
+    HttpError::for_internal_error(e.to_string())
 }
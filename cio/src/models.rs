@@ -356,7 +356,11 @@ impl NewRepo {
     airtable_table = "AIRTABLE_RFD_TABLE",
     match_on = {
         "number" = "i32",
-    }
+    },
+    filter_on = {
+        "state" = "String",
+    },
+    airtable_fields = ["milestones", "relevant_components"],
 }]
 #[derive(Debug, Insertable, AsChangeset, PartialEq, Clone, JsonSchema, Deserialize, Serialize)]
 #[table_name = "rfds"]
@@ -824,10 +828,7 @@ impl RFD {
 /// Implement updating the Airtable record for an RFD.
 #[async_trait]
 impl UpdateAirtableRecord<RFD> for RFD {
-    async fn update_airtable_record(&mut self, record: RFD) {
-        // Set the Link to People from the original so it stays intact.
-        self.milestones = record.milestones.clone();
-        self.relevant_components = record.relevant_components;
+    async fn update_airtable_record(&mut self, _record: RFD) {
         // Airtable can only hold 100,000 chars. IDK which one is that long but LOL
         // https://community.airtable.com/t/what-is-the-long-text-character-limit/1780
         self.content = truncate(&self.content, 100000);
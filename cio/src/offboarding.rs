@@ -0,0 +1,426 @@
+use std::collections::BTreeSet;
+use std::env;
+use std::error;
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use gsuite_api::{ApplicationDataTransfer, ApplicationTransferParam, DataTransfer, GSuite};
+use hubcaps::Github;
+use okta::Okta;
+use ramp_api::Ramp;
+use reqwest::StatusCode;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use slack_chat_api::Slack;
+use tailscale_api::Tailscale;
+use zoom_api::Zoom;
+
+use crate::configs::User;
+use crate::db::Database;
+use crate::schema::{user_offboarding_steps, user_offboardings};
+use crate::templates::generate_terraform_files_for_aws_and_github;
+use crate::utils::{get_gsuite_token, GSUITE_DOMAIN};
+
+/// The name of the Google application we transfer files for.
+const GOOGLE_DRIVE_APPLICATION: &str = "Drive and Docs";
+
+type StepResult = Result<String, Box<dyn error::Error + Send + Sync>>;
+
+/// A step in offboarding a user.
+///
+/// The steps are run in order and each one is recorded, so if a step fails the
+/// next run of the offboarding picks up where the last one left off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OffboardingStep {
+    DeleteAnniversaryEvent,
+    SuspendGSuiteUser,
+    TransferDriveFiles,
+    DeactivateOktaUser,
+    LockRampCards,
+    DeactivateRampUser,
+    RemoveSlackUser,
+    DeactivateZoomUser,
+    RemoveGitHubMembership,
+    DeleteTailscaleDevices,
+}
+
+/// All the steps, in the order they are run.
+pub const OFFBOARDING_STEPS: &[OffboardingStep] = &[
+    OffboardingStep::DeleteAnniversaryEvent,
+    OffboardingStep::SuspendGSuiteUser,
+    OffboardingStep::TransferDriveFiles,
+    OffboardingStep::DeactivateOktaUser,
+    OffboardingStep::LockRampCards,
+    OffboardingStep::DeactivateRampUser,
+    OffboardingStep::RemoveSlackUser,
+    OffboardingStep::DeactivateZoomUser,
+    OffboardingStep::RemoveGitHubMembership,
+    OffboardingStep::DeleteTailscaleDevices,
+];
+
+impl fmt::Display for OffboardingStep {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            OffboardingStep::DeleteAnniversaryEvent => "delete anniversary event",
+            OffboardingStep::SuspendGSuiteUser => "suspend gsuite user",
+            OffboardingStep::TransferDriveFiles => "transfer drive files",
+            OffboardingStep::DeactivateOktaUser => "deactivate okta user",
+            OffboardingStep::LockRampCards => "lock ramp cards",
+            OffboardingStep::DeactivateRampUser => "deactivate ramp user",
+            OffboardingStep::RemoveSlackUser => "remove slack user",
+            OffboardingStep::DeactivateZoomUser => "deactivate zoom user",
+            OffboardingStep::RemoveGitHubMembership => "remove github membership",
+            OffboardingStep::DeleteTailscaleDevices => "delete tailscale devices",
+        };
+
+        write!(f, "{}", s)
+    }
+}
+
+/// The status of a step that finished, it will not be run again.
+pub static STEP_STATUS_COMPLETED: &str = "completed";
+/// The status of a step that failed, it will be retried on the next run.
+pub static STEP_STATUS_FAILED: &str = "failed";
+
+#[derive(Debug, Insertable, PartialEq, Clone)]
+#[table_name = "user_offboardings"]
+pub struct NewUserOffboarding {
+    pub username: String,
+    pub email: String,
+    pub github: String,
+    pub manager: String,
+    pub google_anniversary_event_id: String,
+    pub started_at: DateTime<Utc>,
+}
+
+/// A user who is being, or has been, offboarded.
+///
+/// This keeps what we need to know about the user once they are deleted from the
+/// users table.
+#[derive(Debug, Queryable, Identifiable, PartialEq, Clone, JsonSchema, Deserialize, Serialize)]
+#[table_name = "user_offboardings"]
+pub struct UserOffboarding {
+    pub id: i32,
+    pub username: String,
+    pub email: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub github: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub manager: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub google_anniversary_event_id: String,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Insertable, AsChangeset, PartialEq, Clone)]
+#[table_name = "user_offboarding_steps"]
+pub struct NewUserOffboardingStep {
+    pub offboarding_id: i32,
+    pub step: String,
+    pub status: String,
+    pub message: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// The outcome of the last run of a step for a user's offboarding.
+#[derive(Debug, Queryable, Identifiable, Associations, PartialEq, Clone, JsonSchema, Deserialize, Serialize)]
+#[belongs_to(UserOffboarding, foreign_key = "offboarding_id")]
+#[table_name = "user_offboarding_steps"]
+pub struct UserOffboardingStep {
+    pub id: i32,
+    pub offboarding_id: i32,
+    pub step: String,
+    pub status: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub message: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl UserOffboarding {
+    /// Start offboarding a user.
+    /// If we are already offboarding the user, that offboarding is returned.
+    pub fn start(db: &Database, user: &User) -> Result<UserOffboarding, crate::Error> {
+        if let Some(existing) = user_offboardings::table
+            .filter(user_offboardings::dsl::username.eq(&user.username))
+            .filter(user_offboardings::dsl::completed_at.is_null())
+            .first::<UserOffboarding>(&db.conn())
+            .optional()?
+        {
+            return Ok(existing);
+        }
+
+        let new = NewUserOffboarding {
+            username: user.username.to_string(),
+            email: user.email(),
+            github: user.github.to_string(),
+            manager: user.manager.to_string(),
+            google_anniversary_event_id: user.google_anniversary_event_id.to_string(),
+            started_at: Utc::now(),
+        };
+
+        Ok(diesel::insert_into(user_offboardings::table).values(&new).get_result(&db.conn())?)
+    }
+
+    /// Get all the offboardings that still have steps left to finish.
+    pub fn get_incomplete(db: &Database) -> Result<Vec<UserOffboarding>, crate::Error> {
+        Ok(user_offboardings::table
+            .filter(user_offboardings::dsl::completed_at.is_null())
+            .order_by(user_offboardings::dsl::started_at.asc())
+            .load::<UserOffboarding>(&db.conn())?)
+    }
+
+    /// Get the recorded steps for the offboarding.
+    pub fn steps(&self, db: &Database) -> Result<Vec<UserOffboardingStep>, crate::Error> {
+        Ok(UserOffboardingStep::belonging_to(self).load::<UserOffboardingStep>(&db.conn())?)
+    }
+
+    fn record_step(&self, db: &Database, step: OffboardingStep, status: &str, message: String) -> Result<(), crate::Error> {
+        let s = NewUserOffboardingStep {
+            offboarding_id: self.id,
+            step: step.to_string(),
+            status: status.to_string(),
+            message,
+            updated_at: Utc::now(),
+        };
+
+        diesel::insert_into(user_offboarding_steps::table)
+            .values(&s)
+            .on_conflict((user_offboarding_steps::dsl::offboarding_id, user_offboarding_steps::dsl::step))
+            .do_update()
+            .set(&s)
+            .execute(&db.conn())?;
+
+        Ok(())
+    }
+
+    /// Run every step that has not yet completed.
+    /// A failed step does not stop the steps after it, since they do not depend
+    /// on each other, but the offboarding is only marked as completed once every
+    /// step has completed.
+    pub async fn run(&self, db: &Database, github: &Github) -> Result<(), crate::Error> {
+        let completed: BTreeSet<String> = self.steps(db)?.into_iter().filter(|s| s.status == STEP_STATUS_COMPLETED).map(|s| s.step).collect();
+
+        let mut failed = false;
+        for step in OFFBOARDING_STEPS {
+            if completed.contains(&step.to_string()) {
+                continue;
+            }
+
+            match self.run_step(db, github, *step).await {
+                Ok(message) => {
+                    println!("[offboarding] {}: {}: {}", self.username, step, message);
+                    self.record_step(db, *step, STEP_STATUS_COMPLETED, message)?;
+                }
+                Err(e) => {
+                    println!("[offboarding] {}: {} failed: {}", self.username, step, e);
+                    self.record_step(db, *step, STEP_STATUS_FAILED, e.to_string())?;
+                    failed = true;
+                }
+            }
+        }
+
+        if !failed {
+            diesel::update(self).set(user_offboardings::dsl::completed_at.eq(Some(Utc::now()))).execute(&db.conn())?;
+            println!("[offboarding] {}: completed", self.username);
+        }
+
+        Ok(())
+    }
+
+    async fn run_step(&self, db: &Database, github: &Github, step: OffboardingStep) -> StepResult {
+        match step {
+            OffboardingStep::DeleteAnniversaryEvent => self.delete_anniversary_event().await,
+            OffboardingStep::SuspendGSuiteUser => self.suspend_gsuite_user().await,
+            OffboardingStep::TransferDriveFiles => self.transfer_drive_files().await,
+            OffboardingStep::DeactivateOktaUser => self.deactivate_okta_user().await,
+            OffboardingStep::LockRampCards => self.lock_ramp_cards().await,
+            OffboardingStep::DeactivateRampUser => self.deactivate_ramp_user().await,
+            OffboardingStep::RemoveSlackUser => self.remove_slack_user().await,
+            OffboardingStep::DeactivateZoomUser => self.deactivate_zoom_user().await,
+            OffboardingStep::RemoveGitHubMembership => {
+                if self.github.is_empty() {
+                    return Ok("no github handle".to_string());
+                }
+
+                // Our GitHub org and team membership is managed by terraform from
+                // the users in the database, which the user is no longer in.
+                generate_terraform_files_for_aws_and_github(github, db).await?;
+                Ok(format!("regenerated github terraform without @{}", self.github))
+            }
+            OffboardingStep::DeleteTailscaleDevices => self.delete_tailscale_devices().await,
+        }
+    }
+
+    async fn gsuite(&self) -> GSuite {
+        let gsuite_customer = env::var("GADMIN_ACCOUNT_ID").unwrap();
+        let token = get_gsuite_token("").await;
+        GSuite::new(&gsuite_customer, GSUITE_DOMAIN, token)
+    }
+
+    async fn delete_anniversary_event(&self) -> StepResult {
+        if self.google_anniversary_event_id.is_empty() {
+            return Ok("no anniversary event".to_string());
+        }
+
+        let gsuite = self.gsuite().await;
+        let calendars = gsuite.list_calendars().await?;
+        let calendar = match calendars.into_iter().find(|c| c.summary.contains("Anniversaries")) {
+            Some(c) => c,
+            None => return Err("could not find the anniversaries calendar".into()),
+        };
+
+        gsuite.delete_calendar_event(&calendar.id, &self.google_anniversary_event_id).await?;
+        Ok(format!("deleted event {}", self.google_anniversary_event_id))
+    }
+
+    async fn suspend_gsuite_user(&self) -> StepResult {
+        let gsuite = self.gsuite().await;
+        let mut user = match gsuite.get_user(&self.email).await {
+            Ok(u) => u,
+            Err(e) if e.status_code == StatusCode::NOT_FOUND => return Ok("no gsuite user".to_string()),
+            Err(e) => return Err(e.into()),
+        };
+
+        if user.suspended {
+            return Ok("already suspended".to_string());
+        }
+
+        user.suspended = true;
+        gsuite.update_user(&user).await?;
+        Ok("suspended".to_string())
+    }
+
+    async fn transfer_drive_files(&self) -> StepResult {
+        let gsuite = self.gsuite().await;
+        let user = match gsuite.get_user(&self.email).await {
+            Ok(u) => u,
+            Err(e) if e.status_code == StatusCode::NOT_FOUND => return Ok("no gsuite user".to_string()),
+            Err(e) => return Err(e.into()),
+        };
+
+        // Give the files to the user's manager, or to our admin if they did not
+        // have one.
+        let new_owner_email = if self.manager.is_empty() {
+            env::var("GADMIN_SUBJECT").unwrap()
+        } else {
+            format!("{}@{}", self.manager, GSUITE_DOMAIN)
+        };
+        let new_owner = gsuite.get_user(&new_owner_email).await?;
+
+        let applications = gsuite.list_data_transfer_applications().await?;
+        let drive = match applications.into_iter().find(|a| a.name == GOOGLE_DRIVE_APPLICATION) {
+            Some(a) => a,
+            None => return Err(format!("could not find the `{}` data transfer application", GOOGLE_DRIVE_APPLICATION).into()),
+        };
+
+        let transfer = gsuite
+            .create_data_transfer(&DataTransfer {
+                old_owner_user_id: user.id.to_string(),
+                new_owner_user_id: new_owner.id.to_string(),
+                application_data_transfers: vec![ApplicationDataTransfer {
+                    application_id: drive.id,
+                    application_transfer_params: vec![ApplicationTransferParam {
+                        key: "PRIVACY_LEVEL".to_string(),
+                        value: vec!["PRIVATE".to_string(), "SHARED".to_string()],
+                    }],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            })
+            .await?;
+        Ok(format!("started transfer {} to {}", transfer.id, new_owner_email))
+    }
+
+    async fn deactivate_okta_user(&self) -> StepResult {
+        let okta = Okta::new_from_env();
+        let user = match okta.get_user(&self.email).await {
+            Ok(u) => u,
+            Err(e) if e.status_code == StatusCode::NOT_FOUND => return Ok("no okta user".to_string()),
+            Err(e) => return Err(e.into()),
+        };
+
+        if user.status == "DEPROVISIONED" {
+            return Ok("already deactivated".to_string());
+        }
+
+        okta.deactivate_user(&user.id).await?;
+        Ok("deactivated".to_string())
+    }
+
+    async fn ramp_user(&self, ramp: &Ramp) -> Result<Option<ramp_api::User>, Box<dyn error::Error + Send + Sync>> {
+        Ok(ramp.list_users().await?.into_iter().find(|u| u.email == self.email))
+    }
+
+    async fn lock_ramp_cards(&self) -> StepResult {
+        let ramp = Ramp::new_from_env().await;
+        let user = match self.ramp_user(&ramp).await? {
+            Some(u) => u,
+            None => return Ok("no ramp user".to_string()),
+        };
+
+        let cards = ramp.list_cards_for_user(&user.id).await?;
+        for card in &cards {
+            ramp.suspend_card(&card.id).await?;
+        }
+
+        Ok(format!("suspended {} cards", cards.len()))
+    }
+
+    async fn deactivate_ramp_user(&self) -> StepResult {
+        let ramp = Ramp::new_from_env().await;
+        let user = match self.ramp_user(&ramp).await? {
+            Some(u) => u,
+            None => return Ok("no ramp user".to_string()),
+        };
+
+        ramp.deactivate_user(&user.id).await?;
+        Ok("deactivated".to_string())
+    }
+
+    async fn remove_slack_user(&self) -> StepResult {
+        let slack = Slack::new_from_env();
+        let user = match slack.list_users().await?.into_iter().find(|u| u.profile.email.eq_ignore_ascii_case(&self.email)) {
+            Some(u) => u,
+            None => return Ok("no slack user".to_string()),
+        };
+
+        slack.remove_user(&user.id).await?;
+        Ok(format!("removed {}", user.id))
+    }
+
+    async fn deactivate_zoom_user(&self) -> StepResult {
+        let zoom = Zoom::new_from_env();
+        match zoom.deactivate_user(self.email.to_string()).await {
+            Ok(()) => Ok("deactivated".to_string()),
+            Err(e) if e.status_code == StatusCode::NOT_FOUND => Ok("no zoom user".to_string()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn delete_tailscale_devices(&self) -> StepResult {
+        let tailscale = Tailscale::new_from_env();
+        let devices: Vec<_> = tailscale.list_devices().await?.into_iter().filter(|d| d.user == self.email).collect();
+        for device in &devices {
+            tailscale.delete_device(&device.id).await?;
+        }
+
+        Ok(format!("deleted {} devices", devices.len()))
+    }
+}
+
+/// Run the offboarding for every user that still has steps left to finish.
+/// Users who were added back to the configs since they were removed are skipped.
+pub async fn refresh_offboardings(db: &Database, github: &Github, current_usernames: &BTreeSet<String>) -> Result<(), crate::Error> {
+    for offboarding in UserOffboarding::get_incomplete(db)? {
+        if current_usernames.contains(&offboarding.username) {
+            println!("[offboarding] {}: user is back in the configs, skipping", offboarding.username);
+            continue;
+        }
+
+        offboarding.run(db, github).await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,184 @@
+use std::env;
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+use reqwest::StatusCode;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::db::Database;
+use crate::shipments::OutboundShipment;
+use crate::swag_inventory::{PrintLabelsRequest, PrintZplRequest, SwagInventoryItem};
+
+/// The state of a job in printy's queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, JsonSchema, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PrintJobStatus {
+    /// The label has not been sent to CUPS yet.
+    Queued,
+    /// CUPS has the job and has not finished it.
+    Printing,
+    Completed,
+    /// The label could not be downloaded, or CUPS would not take the job.
+    Failed,
+    Cancelled,
+}
+
+impl Default for PrintJobStatus {
+    fn default() -> Self {
+        PrintJobStatus::Queued
+    }
+}
+
+impl PrintJobStatus {
+    /// Returns true if the job will not change anymore.
+    pub fn is_done(self) -> bool {
+        matches!(self, PrintJobStatus::Completed | PrintJobStatus::Failed | PrintJobStatus::Cancelled)
+    }
+}
+
+impl fmt::Display for PrintJobStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            PrintJobStatus::Queued => "queued",
+            PrintJobStatus::Printing => "printing",
+            PrintJobStatus::Completed => "completed",
+            PrintJobStatus::Failed => "failed",
+            PrintJobStatus::Cancelled => "cancelled",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// The record a label is printed for, so printy can report back whether it
+/// printed.
+#[derive(Debug, Clone, PartialEq, Eq, JsonSchema, Deserialize, Serialize)]
+pub struct PrintJobSource {
+    /// The table of the record, `outbound_shipments` or `swag_inventory_items`.
+    pub table: String,
+    pub id: i32,
+}
+
+impl PrintJobSource {
+    /// Get a new id for a print job for the record. We give printy the id of
+    /// the job, so the record can save it before printy reports on the job.
+    pub fn new_job_id(&self) -> String {
+        format!("{}-{}-{}", self.table, self.id, Utc::now().timestamp_nanos())
+    }
+}
+
+/// A job in printy's queue.
+#[derive(Debug, Clone, PartialEq, JsonSchema, Deserialize, Serialize)]
+pub struct PrintJob {
+    pub id: String,
+    /// The printer profile the job was sent to, like `rollo` or `zebra`.
+    pub printer: String,
+    /// The kind of label, `shipping` or `barcode`. printy picks the printer
+    /// for the job by it.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub label_type: String,
+    /// The URL of the label to print.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub url: String,
+    /// The ZPL of the label to print, sent to the printer as is instead of
+    /// downloading `url`.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub zpl: String,
+    pub copies: i32,
+    #[serde(default)]
+    pub status: PrintJobStatus,
+    /// The id CUPS gave the job, like `Zebra_ZD420-12`.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub cups_job_id: String,
+    /// Why the job failed.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub error: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<PrintJobSource>,
+    /// The id of the job this one prints again.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub reprint_of: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl PrintJob {
+    /// The status to show on the record the label is for, with the reason if
+    /// the job failed.
+    pub fn status_message(&self) -> String {
+        if self.error.is_empty() {
+            self.status.to_string()
+        } else {
+            format!("{}: {}", self.status, self.error)
+        }
+    }
+}
+
+/// Send a label to the `rollo` or `zebra` printer, and return the job printy
+/// queued for it.
+pub async fn send_print_request(printer: &str, request: &PrintLabelsRequest) -> Result<PrintJob, crate::Error> {
+    post_print_request(printer, request).await
+}
+
+/// Send a ZPL label to the `zebra` printer, and return the job printy queued
+/// for it.
+///
+/// This returns an error rather than panicking, so the caller can fall back to
+/// printing the PDF label.
+pub async fn send_zpl_print_request(printer: &str, request: &PrintZplRequest) -> Result<PrintJob, crate::Error> {
+    post_print_request(&format!("{}/zpl", printer), request).await
+}
+
+async fn post_print_request<T: Serialize>(path: &str, request: &T) -> Result<PrintJob, crate::Error> {
+    let printer_url = env::var("PRINTER_URL").map_err(|e| crate::Error::Print(format!("PRINTER_URL: {}", e)))?;
+    let printer_url = format!("{}/{}", printer_url.trim_end_matches('/'), path);
+    let client = reqwest::Client::new();
+    let resp = client
+        .post(&printer_url)
+        .body(json!(request).to_string())
+        .send()
+        .await
+        .map_err(|e| crate::Error::Print(e.to_string()))?;
+    match resp.status() {
+        StatusCode::ACCEPTED => resp.json().await.map_err(|e| crate::Error::Print(e.to_string())),
+        s => Err(crate::Error::Print(format!("status_code: {}, body: {}", s, resp.text().await.unwrap_or_default()))),
+    }
+}
+
+/// Save the status of a print job on the record the label was printed for.
+///
+/// Only the latest job for a record, or a reprint of it, is saved, so a job
+/// that finishes late does not overwrite the status of the one that replaced it.
+pub async fn update_print_job_source(db: &Database, job: &PrintJob) -> Result<(), crate::Error> {
+    let source = match &job.source {
+        Some(source) => source,
+        None => return Ok(()),
+    };
+
+    match source.table.as_str() {
+        "outbound_shipments" => {
+            let mut shipment = OutboundShipment::get_by_id(db, source.id)?;
+            if job.id != shipment.label_print_job_id && job.reprint_of != shipment.label_print_job_id {
+                return Ok(());
+            }
+
+            shipment.label_print_job_id = job.id.to_string();
+            shipment.label_print_status = job.status_message();
+            shipment.update(db).await?;
+        }
+        "swag_inventory_items" => {
+            let mut item = SwagInventoryItem::get_by_id(db, source.id)?;
+            if job.id != item.label_print_job_id && job.reprint_of != item.label_print_job_id {
+                return Ok(());
+            }
+
+            item.label_print_job_id = job.id.to_string();
+            item.label_print_status = job.status_message();
+            item.update(db).await?;
+        }
+        table => return Err(crate::Error::not_found(table, source.id)),
+    }
+
+    println!("[print] {} {} label is {}", source.table, source.id, job.status_message());
+    Ok(())
+}
@@ -0,0 +1,283 @@
+use std::fmt;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::db::Database;
+use crate::schema::queued_jobs;
+
+/// How long a job can be running before we assume the worker running it went
+/// away and hand it to another worker. Workers must give up on a job before this.
+pub const JOB_LEASE: Duration = Duration::from_secs(60 * 60);
+
+/// The number of times a job is tried before it is moved to the dead letter state.
+pub const DEFAULT_MAX_ATTEMPTS: i32 = 8;
+
+/// The delay before the first retry of a failed job. This doubles with every
+/// attempt up to `MAX_RETRY_DELAY`.
+const BASE_RETRY_DELAY: Duration = Duration::from_secs(30);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60 * 60);
+
+/// The status of a job in the queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueuedJobStatus {
+    /// The job is waiting to run, either for the first time or to be retried.
+    Queued,
+    Running,
+    Succeeded,
+    /// The job failed `max_attempts` times and will not be retried.
+    Dead,
+}
+
+impl fmt::Display for QueuedJobStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            QueuedJobStatus::Queued => write!(f, "queued"),
+            QueuedJobStatus::Running => write!(f, "running"),
+            QueuedJobStatus::Succeeded => write!(f, "succeeded"),
+            QueuedJobStatus::Dead => write!(f, "dead"),
+        }
+    }
+}
+
+#[derive(Debug, Insertable, PartialEq, Clone)]
+#[table_name = "queued_jobs"]
+pub struct NewQueuedJob {
+    pub kind: String,
+    pub payload: serde_json::Value,
+    pub idempotency_key: String,
+    pub status: String,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    pub last_error: String,
+    pub run_at: DateTime<Utc>,
+    pub locked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A job in the queue.
+#[derive(Debug, Queryable, Identifiable, PartialEq, Clone, JsonSchema, Deserialize, Serialize)]
+#[table_name = "queued_jobs"]
+pub struct QueuedJob {
+    pub id: i32,
+    pub kind: String,
+    pub payload: serde_json::Value,
+    /// Enqueueing a job with a key that is already in the queue does nothing.
+    pub idempotency_key: String,
+    pub status: String,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub last_error: String,
+    /// The job will not be claimed before this time.
+    pub run_at: DateTime<Utc>,
+    pub locked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl NewQueuedJob {
+    pub fn new<P: Serialize>(kind: &str, payload: &P, idempotency_key: &str) -> Result<Self, crate::Error> {
+        let now = Utc::now();
+        Ok(NewQueuedJob {
+            kind: kind.to_string(),
+            payload: serde_json::to_value(payload)?,
+            idempotency_key: idempotency_key.to_string(),
+            status: QueuedJobStatus::Queued.to_string(),
+            attempts: 0,
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            last_error: String::new(),
+            run_at: now,
+            locked_at: None,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    /// Add the job to the queue. If a job with the same idempotency key was
+    /// already enqueued, nothing is added and that job is returned instead.
+    pub fn enqueue(&self, db: &Database) -> Result<QueuedJob, crate::Error> {
+        let conn = db.conn();
+        let inserted = diesel::insert_into(queued_jobs::table)
+            .values(self)
+            .on_conflict(queued_jobs::dsl::idempotency_key)
+            .do_nothing()
+            .get_result::<QueuedJob>(&conn)
+            .optional()?;
+
+        match inserted {
+            Some(job) => Ok(job),
+            None => Ok(queued_jobs::table.filter(queued_jobs::dsl::idempotency_key.eq(&self.idempotency_key)).first::<QueuedJob>(&conn)?),
+        }
+    }
+}
+
+impl QueuedJob {
+    /// Claim the next job of one of the given kinds that is ready to run, if any.
+    ///
+    /// Jobs that have been running for longer than `JOB_LEASE` are claimed again,
+    /// since the worker running them must have gone away. Rows are locked with
+    /// `SKIP LOCKED` so two workers never claim the same job.
+    pub fn claim_next(db: &Database, kinds: &[&str]) -> Result<Option<QueuedJob>, crate::Error> {
+        let conn = db.conn();
+        let now = Utc::now();
+        let expired = now - chrono::Duration::from_std(JOB_LEASE).unwrap();
+
+        Ok(conn.transaction::<_, diesel::result::Error, _>(|| {
+            let job = queued_jobs::table
+                .filter(queued_jobs::dsl::kind.eq_any(kinds.to_vec()))
+                .filter(
+                    queued_jobs::dsl::status
+                        .eq(QueuedJobStatus::Queued.to_string())
+                        .and(queued_jobs::dsl::run_at.le(now))
+                        .or(queued_jobs::dsl::status.eq(QueuedJobStatus::Running.to_string()).and(queued_jobs::dsl::locked_at.lt(expired))),
+                )
+                .order_by(queued_jobs::dsl::run_at.asc())
+                .limit(1)
+                .for_update()
+                .skip_locked()
+                .get_result::<QueuedJob>(&conn)
+                .optional()?;
+
+            match job {
+                Some(job) => diesel::update(&job)
+                    .set((
+                        queued_jobs::dsl::status.eq(QueuedJobStatus::Running.to_string()),
+                        queued_jobs::dsl::attempts.eq(job.attempts + 1),
+                        queued_jobs::dsl::locked_at.eq(Some(now)),
+                        queued_jobs::dsl::updated_at.eq(now),
+                    ))
+                    .get_result::<QueuedJob>(&conn)
+                    .map(Some),
+                None => Ok(None),
+            }
+        })?)
+    }
+
+    /// Mark the job as done.
+    pub fn succeed(&self, db: &Database) -> Result<QueuedJob, crate::Error> {
+        Ok(diesel::update(self)
+            .set((
+                queued_jobs::dsl::status.eq(QueuedJobStatus::Succeeded.to_string()),
+                queued_jobs::dsl::last_error.eq(""),
+                queued_jobs::dsl::locked_at.eq(None::<DateTime<Utc>>),
+                queued_jobs::dsl::updated_at.eq(Utc::now()),
+            ))
+            .get_result(&db.conn())?)
+    }
+
+    /// Record a failed attempt at the job. The job is retried with exponential
+    /// backoff until it has been tried `max_attempts` times, then it is dead.
+    pub fn fail(&self, db: &Database, error: &str) -> Result<QueuedJob, crate::Error> {
+        let now = Utc::now();
+        let (status, run_at) = if self.attempts >= self.max_attempts {
+            (QueuedJobStatus::Dead, self.run_at)
+        } else {
+            (QueuedJobStatus::Queued, now + chrono::Duration::from_std(retry_delay(self.attempts)).unwrap())
+        };
+
+        Ok(diesel::update(self)
+            .set((
+                queued_jobs::dsl::status.eq(status.to_string()),
+                queued_jobs::dsl::last_error.eq(error),
+                queued_jobs::dsl::run_at.eq(run_at),
+                queued_jobs::dsl::locked_at.eq(None::<DateTime<Utc>>),
+                queued_jobs::dsl::updated_at.eq(now),
+            ))
+            .get_result(&db.conn())?)
+    }
+
+    /// Get the jobs that failed too many times to be retried, newest first.
+    pub fn get_dead_from_db(db: &Database) -> Result<Vec<QueuedJob>, crate::Error> {
+        Ok(queued_jobs::table
+            .filter(queued_jobs::dsl::status.eq(QueuedJobStatus::Dead.to_string()))
+            .order_by(queued_jobs::dsl::updated_at.desc())
+            .load::<QueuedJob>(&db.conn())?)
+    }
+
+    /// Get a job by its id.
+    pub fn get_by_id(db: &Database, id: i32) -> Result<QueuedJob, crate::Error> {
+        match queued_jobs::table.find(id).first::<QueuedJob>(&db.conn()) {
+            Ok(job) => Ok(job),
+            Err(diesel::result::Error::NotFound) => Err(crate::Error::not_found("queued_jobs", id)),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Put a dead job back in the queue with a fresh set of attempts.
+    pub fn requeue(&self, db: &Database) -> Result<QueuedJob, crate::Error> {
+        if self.status != QueuedJobStatus::Dead.to_string() {
+            return Err(crate::Error::Invalid(vec![format!("job {} is {}, only dead jobs can be requeued", self.id, self.status)]));
+        }
+
+        let now = Utc::now();
+        Ok(diesel::update(self)
+            .set((
+                queued_jobs::dsl::status.eq(QueuedJobStatus::Queued.to_string()),
+                queued_jobs::dsl::attempts.eq(0),
+                queued_jobs::dsl::last_error.eq(""),
+                queued_jobs::dsl::run_at.eq(now),
+                queued_jobs::dsl::locked_at.eq(None::<DateTime<Utc>>),
+                queued_jobs::dsl::updated_at.eq(now),
+            ))
+            .get_result(&db.conn())?)
+    }
+}
+
+/// How long to wait before retrying a job that has failed `attempts` times.
+pub fn retry_delay(attempts: i32) -> Duration {
+    let exponent = attempts.max(1) as u32 - 1;
+    match 2u32.checked_pow(exponent).and_then(|m| BASE_RETRY_DELAY.checked_mul(m)) {
+        Some(delay) if delay < MAX_RETRY_DELAY => delay,
+        _ => MAX_RETRY_DELAY,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chrono::Utc;
+
+    use crate::db::Database;
+    use crate::queue::{retry_delay, NewQueuedJob, QueuedJob, QueuedJobStatus};
+
+    #[test]
+    fn test_retry_delay() {
+        assert_eq!(retry_delay(1), Duration::from_secs(30));
+        assert_eq!(retry_delay(2), Duration::from_secs(60));
+        assert_eq!(retry_delay(3), Duration::from_secs(120));
+        assert_eq!(retry_delay(7), Duration::from_secs(1920));
+        assert_eq!(retry_delay(8), Duration::from_secs(60 * 60));
+        assert_eq!(retry_delay(100), Duration::from_secs(60 * 60));
+    }
+
+    /// This needs `CIO_DATABASE_URL` to point at a scratch database.
+    #[test]
+    fn test_requeue_dead_job() {
+        let db = Database::new();
+
+        let kind = format!("test-{}", Utc::now().timestamp_nanos());
+        let mut new_job = NewQueuedJob::new(&kind, &"payload", &kind).unwrap();
+        new_job.max_attempts = 1;
+        let job = new_job.enqueue(&db).unwrap();
+        assert!(job.requeue(&db).is_err());
+
+        // The only attempt fails, so the job is dead.
+        let job = QueuedJob::claim_next(&db, &[kind.as_str()]).unwrap().unwrap();
+        let job = job.fail(&db, "boom").unwrap();
+        assert_eq!(job.status, QueuedJobStatus::Dead.to_string());
+        assert!(QueuedJob::get_dead_from_db(&db).unwrap().iter().any(|j| j.id == job.id));
+
+        let job = QueuedJob::get_by_id(&db, job.id).unwrap().requeue(&db).unwrap();
+        assert_eq!(job.status, QueuedJobStatus::Queued.to_string());
+        assert_eq!(job.attempts, 0);
+        assert_eq!(job.last_error, "");
+        assert_eq!(job.locked_at, None);
+        assert_eq!(QueuedJob::claim_next(&db, &[kind.as_str()]).unwrap().map(|j| j.id), Some(job.id));
+    }
+}
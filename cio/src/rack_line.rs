@@ -25,6 +25,7 @@ use crate::schema::rack_line_subscribers;
     match_on = {
         "email" = "String",
     },
+    airtable_fields = ["link_to_people"],
 }]
 #[derive(Debug, Insertable, AsChangeset, PartialEq, Clone, JsonSchema, Deserialize, Serialize)]
 #[table_name = "rack_line_subscribers"]
@@ -144,10 +145,7 @@ impl Default for NewRackLineSubscriber {
 /// Implement updating the Airtable record for a RackLineSubscriber.
 #[async_trait]
 impl UpdateAirtableRecord<RackLineSubscriber> for RackLineSubscriber {
-    async fn update_airtable_record(&mut self, record: RackLineSubscriber) {
-        // Set the link_to_people from the original so it stays intact.
-        self.link_to_people = record.link_to_people;
-    }
+    async fn update_airtable_record(&mut self, _record: RackLineSubscriber) {}
 }
 
 /// Sync the rack_line_subscribers from Mailchimp with our database.
@@ -157,7 +155,9 @@ pub async fn refresh_db_rack_line_subscribers(db: &Database) {
     // Sync subscribers.
     for member in members {
         let ns: NewRackLineSubscriber = member.into();
-        ns.upsert(db).await;
+        if let Err(e) = ns.upsert(db).await {
+            println!("upserting rack line subscriber {} failed: {}", ns.email, e);
+        }
     }
 }
 
@@ -183,19 +183,3 @@ impl Into<NewRackLineSubscriber> for MailchimpMember {
         }
     }
 }
-
-#[cfg(test)]
-mod tests {
-    use crate::db::Database;
-    use crate::rack_line::{refresh_db_rack_line_subscribers, RackLineSubscribers};
-
-    #[ignore]
-    #[tokio::test(flavor = "multi_thread")]
-    async fn test_cron_rack_line_subscribers() {
-        // Initialize our database.
-        let db = Database::new();
-
-        refresh_db_rack_line_subscribers(&db).await;
-        RackLineSubscribers::get_from_db(&db).update_airtable().await;
-    }
-}
@@ -5,10 +5,12 @@ use std::str::from_utf8;
 use async_trait::async_trait;
 use chrono::offset::Utc;
 use chrono::DateTime;
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Double, Float, Integer, Nullable, Text, Timestamptz};
 use google_drive::GoogleDrive;
 use gsuite_api::GSuite;
 use macros::db;
-use revai::RevAI;
+use revai::{Job, JobOptions, RevAI, Transcript};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
@@ -17,7 +19,7 @@ use crate::configs::User;
 use crate::core::UpdateAirtableRecord;
 use crate::db::Database;
 use crate::models::truncate;
-use crate::schema::recorded_meetings;
+use crate::schema::{recorded_meeting_segments, recorded_meetings};
 use crate::utils::{get_gsuite_token, GSUITE_DOMAIN};
 
 /// The data type for a recorded meeting.
@@ -52,12 +54,20 @@ pub struct NewRecordedMeeting {
     pub transcript: String,
     #[serde(default, skip_serializing_if = "String::is_empty")]
     pub transcript_id: String,
+    /// Why rev.ai could not transcribe the video. The transcript is not asked
+    /// for again once this is set.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub transcript_failure: String,
     #[serde(default, skip_serializing_if = "String::is_empty")]
     pub google_event_id: String,
     #[serde(default, skip_serializing_if = "String::is_empty")]
     pub event_link: String,
     #[serde(default, skip_serializing_if = "String::is_empty")]
     pub location: String,
+    /// The email of the person who organized the meeting, the recording is in
+    /// their Google Drive.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub owner: String,
 }
 
 /// Implement updating the Airtable record for a RecordedMeeting.
@@ -75,10 +85,398 @@ impl UpdateAirtableRecord<RecordedMeeting> for RecordedMeeting {
     }
 }
 
+impl RecordedMeeting {
+    /// Get the meeting a rev.ai job is transcribing.
+    pub fn get_by_transcript_id(db: &Database, transcript_id: &str) -> Result<Self, crate::Error> {
+        recorded_meetings::table
+            .filter(recorded_meetings::dsl::transcript_id.eq(transcript_id))
+            .first::<RecordedMeeting>(&db.conn())
+            .optional()?
+            .ok_or_else(|| crate::Error::not_found("recorded_meetings", transcript_id))
+    }
+
+    /// Send the video of the meeting to rev.ai to be transcribed. The video is
+    /// streamed from Google Drive to rev.ai as it downloads, since recordings
+    /// can be too big to hold in memory.
+    ///
+    /// rev.ai posts to `REVAI_CALLBACK_URL`, if it is set, when the transcript
+    /// is ready, ie. `https://webhooky/revai/jobs/complete?token=<secret>`.
+    /// Otherwise the next refresh picks it up.
+    pub async fn submit_for_transcription(&mut self, db: &Database, drive_client: &GoogleDrive, revai: &RevAI) -> Result<(), crate::Error> {
+        let download = drive_client.download_file_stream_by_id(drive_file_id(&self.video)).await?;
+        let content_length = download.content_length();
+        let options = JobOptions {
+            metadata: format!("recorded_meetings:{}", self.id),
+            callback_url: env::var("REVAI_CALLBACK_URL").unwrap_or_default(),
+            ..Default::default()
+        };
+
+        let job = revai.create_job_from_stream(download.bytes_stream(), content_length, &options).await?;
+        println!("started rev.ai job {} for recorded meeting {}", job.id, self.name);
+
+        self.transcript_id = job.id;
+        self.transcript_failure = "".to_string();
+        self.update(db).await?;
+        Ok(())
+    }
+
+    /// Get the transcript of the meeting from rev.ai, and save it as text and
+    /// split into segments. This fails until rev.ai is done with it.
+    pub async fn save_transcript(&mut self, db: &Database, revai: &RevAI) -> Result<(), crate::Error> {
+        let transcript = revai.get_transcript_json(&self.transcript_id).await?;
+
+        self.transcript = transcript.to_text();
+        self.update(db).await?;
+        RecordedMeetingSegment::replace(db, self.id, TRANSCRIPT_SEGMENT, &transcript_segments(self.id, &transcript))
+    }
+}
+
+/// Save the transcript of the meeting a rev.ai job was for, once rev.ai tells
+/// us the job is done.
+pub async fn update_recorded_meeting_from_revai_job(db: &Database, job: &Job) -> Result<(), crate::Error> {
+    let mut linked = false;
+    let mut meeting = match RecordedMeeting::get_by_transcript_id(db, &job.id) {
+        Ok(meeting) => meeting,
+        // rev.ai can tell us about the job before its id was saved on the meeting,
+        // so find the meeting from the metadata the job was created with.
+        Err(e) if e.is_not_found() => {
+            let id = job.metadata.strip_prefix("recorded_meetings:").and_then(|id| id.parse::<i32>().ok()).ok_or(e)?;
+            let mut meeting = RecordedMeeting::get_by_id(db, id)?;
+            if !meeting.transcript_id.is_empty() && meeting.transcript_id != job.id {
+                println!(
+                    "rev.ai job {} is for recorded meeting {}, which is on job {} now, skipping",
+                    job.id, meeting.name, meeting.transcript_id
+                );
+                return Ok(());
+            }
+            meeting.transcript_id = job.id.to_string();
+            linked = true;
+            meeting
+        }
+        Err(e) => return Err(e),
+    };
+
+    if job.is_failed() {
+        // Trying again would not help, so save why it failed for someone to
+        // look at, which also stops the refresh from asking for the transcript.
+        println!("rev.ai job {} for recorded meeting {} failed: {}: {}", job.id, meeting.name, job.failure, job.failure_detail);
+        meeting.transcript_failure = format!("{}: {}", job.failure, job.failure_detail);
+        meeting.update(db).await?;
+        return Ok(());
+    }
+    if !job.is_transcribed() {
+        if linked {
+            meeting.update(db).await?;
+        }
+        return Ok(());
+    }
+
+    meeting.save_transcript(db, &RevAI::new_from_env()).await?;
+    println!("saved transcript for recorded meeting {} from rev.ai job {}", meeting.name, job.id);
+    Ok(())
+}
+
+/// Get the id of a Google Drive file from its link.
+fn drive_file_id(link: &str) -> &str {
+    link.trim_start_matches("https://drive.google.com/open?id=")
+}
+
+/// The kind of segment for a sentence of the transcript of a meeting.
+pub const TRANSCRIPT_SEGMENT: &str = "transcript";
+/// The kind of segment for a message in the chat log of a meeting.
+pub const CHAT_SEGMENT: &str = "chat";
+
+#[derive(Debug, Insertable, PartialEq, Clone)]
+#[table_name = "recorded_meeting_segments"]
+pub struct NewRecordedMeetingSegment {
+    pub recorded_meeting_id: i32,
+    pub kind: String,
+    pub position: i32,
+    pub speaker: String,
+    pub start_seconds: f64,
+    pub end_seconds: f64,
+    pub text: String,
+}
+
+/// A sentence of the transcript of a recorded meeting, or a message in its
+/// chat log, with who said it and when.
+#[derive(Debug, Queryable, Identifiable, Associations, PartialEq, Clone, JsonSchema, Deserialize, Serialize)]
+#[belongs_to(RecordedMeeting)]
+#[table_name = "recorded_meeting_segments"]
+pub struct RecordedMeetingSegment {
+    pub id: i32,
+    pub recorded_meeting_id: i32,
+    /// `transcript` or `chat`.
+    pub kind: String,
+    /// The order of the segment in the transcript or the chat log.
+    pub position: i32,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub speaker: String,
+    /// When the segment starts, in seconds from the start of the video.
+    pub start_seconds: f64,
+    pub end_seconds: f64,
+    pub text: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl RecordedMeetingSegment {
+    /// Count the segments of a kind for a meeting.
+    pub fn count(db: &Database, recorded_meeting_id: i32, kind: &str) -> Result<i64, crate::Error> {
+        Ok(recorded_meeting_segments::table
+            .filter(recorded_meeting_segments::dsl::recorded_meeting_id.eq(recorded_meeting_id))
+            .filter(recorded_meeting_segments::dsl::kind.eq(kind))
+            .count()
+            .get_result(&db.conn())?)
+    }
+
+    /// Replace the segments of a kind for a meeting.
+    pub fn replace(db: &Database, recorded_meeting_id: i32, kind: &str, segments: &[NewRecordedMeetingSegment]) -> Result<(), crate::Error> {
+        let conn = db.conn();
+        conn.transaction::<_, diesel::result::Error, _>(|| {
+            diesel::delete(
+                recorded_meeting_segments::table
+                    .filter(recorded_meeting_segments::dsl::recorded_meeting_id.eq(recorded_meeting_id))
+                    .filter(recorded_meeting_segments::dsl::kind.eq(kind)),
+            )
+            .execute(&conn)?;
+            diesel::insert_into(recorded_meeting_segments::table).values(segments).execute(&conn)?;
+            Ok(())
+        })?;
+
+        Ok(())
+    }
+
+    /// Search the transcripts and chat logs of meetings, best match first. If
+    /// `attendee` is set, only the meetings they were invited to or organized
+    /// are searched.
+    pub fn search(db: &Database, query: &str, attendee: Option<&str>, limit: i64) -> Result<Vec<RecordedMeetingSearchResult>, crate::Error> {
+        if query.trim().is_empty() {
+            return Err(crate::Error::Invalid(vec!["the search cannot be empty".to_string()]));
+        }
+
+        let rows: Vec<SearchRow> = diesel::sql_query(
+            "SELECT s.recorded_meeting_id, m.name AS meeting_name, m.start_time AS meeting_start_time, m.video, s.kind, s.speaker, s.start_seconds, s.text, \
+                ts_rank(to_tsvector('english', s.text), q) AS rank \
+             FROM recorded_meeting_segments s \
+             JOIN recorded_meetings m ON m.id = s.recorded_meeting_id, \
+                plainto_tsquery('english', $1) q \
+             WHERE to_tsvector('english', s.text) @@ q \
+                AND ($3 IS NULL OR lower(m.owner) = lower($3) OR EXISTS (SELECT 1 FROM unnest(m.attendees) a WHERE lower(a) = lower($3))) \
+             ORDER BY rank DESC, m.start_time DESC, s.position \
+             LIMIT $2",
+        )
+        .bind::<Text, _>(query.trim())
+        .bind::<BigInt, _>(limit)
+        .bind::<Nullable<Text>, _>(attendee)
+        .load(&db.conn())?;
+
+        Ok(rows
+            .into_iter()
+            .map(|r| RecordedMeetingSearchResult {
+                link: video_link_at(&r.video, r.start_seconds),
+                recorded_meeting_id: r.recorded_meeting_id,
+                meeting_name: r.meeting_name,
+                meeting_start_time: r.meeting_start_time,
+                kind: r.kind,
+                speaker: r.speaker,
+                start_seconds: r.start_seconds,
+                text: r.text,
+                rank: r.rank,
+            })
+            .collect())
+    }
+}
+
+#[derive(QueryableByName)]
+struct SearchRow {
+    #[sql_type = "Integer"]
+    recorded_meeting_id: i32,
+    #[sql_type = "Text"]
+    meeting_name: String,
+    #[sql_type = "Timestamptz"]
+    meeting_start_time: DateTime<Utc>,
+    #[sql_type = "Text"]
+    video: String,
+    #[sql_type = "Text"]
+    kind: String,
+    #[sql_type = "Text"]
+    speaker: String,
+    #[sql_type = "Double"]
+    start_seconds: f64,
+    #[sql_type = "Text"]
+    text: String,
+    #[sql_type = "Float"]
+    rank: f32,
+}
+
+/// A segment of a meeting that matched a search.
+#[derive(Debug, PartialEq, Clone, JsonSchema, Deserialize, Serialize)]
+pub struct RecordedMeetingSearchResult {
+    pub recorded_meeting_id: i32,
+    pub meeting_name: String,
+    pub meeting_start_time: DateTime<Utc>,
+    /// `transcript` or `chat`.
+    pub kind: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub speaker: String,
+    pub start_seconds: f64,
+    pub text: String,
+    /// A link to the video in Google Drive, starting where the segment starts.
+    pub link: String,
+    pub rank: f32,
+}
+
+/// Return a link to a meeting video in Google Drive that starts playing at a
+/// time. Videos are saved as `https://drive.google.com/open?id=ID`, links we
+/// can't get the id out of are returned as is.
+pub fn video_link_at(video: &str, seconds: f64) -> String {
+    match video.strip_prefix("https://drive.google.com/open?id=") {
+        Some(id) if !id.is_empty() => format!("https://drive.google.com/file/d/{}/view?t={}", id, seconds.max(0.0) as i64),
+        _ => video.to_string(),
+    }
+}
+
+/// Split a transcript into a segment for every sentence, so search results
+/// link to the moment the sentence was said rather than the start of a long
+/// monologue.
+pub fn transcript_segments(recorded_meeting_id: i32, transcript: &Transcript) -> Vec<NewRecordedMeetingSegment> {
+    let mut segments: Vec<NewRecordedMeetingSegment> = Default::default();
+
+    for monologue in &transcript.monologues {
+        let speaker = monologue.speaker_label();
+        let mut text = String::new();
+        let mut start: Option<f64> = None;
+        let mut end = 0.0;
+
+        for element in &monologue.elements {
+            text.push_str(&element.value);
+            if element.is_text() {
+                start.get_or_insert(element.ts);
+                end = element.end_ts;
+            }
+
+            let ends_sentence = !element.is_text() && element.value.trim_end().ends_with(&['.', '?', '!'][..]);
+            if ends_sentence && !text.trim().is_empty() {
+                segments.push(NewRecordedMeetingSegment {
+                    recorded_meeting_id,
+                    kind: TRANSCRIPT_SEGMENT.to_string(),
+                    position: segments.len() as i32,
+                    speaker: speaker.to_string(),
+                    start_seconds: start.unwrap_or(end),
+                    end_seconds: end,
+                    text: text.trim().to_string(),
+                });
+                text = String::new();
+                start = None;
+            }
+        }
+
+        if !text.trim().is_empty() {
+            segments.push(NewRecordedMeetingSegment {
+                recorded_meeting_id,
+                kind: TRANSCRIPT_SEGMENT.to_string(),
+                position: segments.len() as i32,
+                speaker: speaker.to_string(),
+                start_seconds: start.unwrap_or(end),
+                end_seconds: end,
+                text: text.trim().to_string(),
+            });
+        }
+    }
+
+    segments
+}
+
+/// Split a chat log into a segment for every message.
+///
+/// Messages start with the time they were sent, either alone on a line with
+/// the message on the next, like Google Meet:
+/// ```text
+/// 00:02:13.482,00:02:16.482
+/// Jess Frazelle : hello
+/// ```
+/// or on the same line, like Zoom: `00:02:13 From Jess Frazelle : hello`.
+pub fn chat_log_segments(recorded_meeting_id: i32, chat_log: &str) -> Vec<NewRecordedMeetingSegment> {
+    // The start, end and text of every message.
+    let mut messages: Vec<(f64, f64, String)> = Default::default();
+    for line in chat_log.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let (start, rest) = split_timestamp(line);
+        match start {
+            Some(start) => {
+                let (end, rest) = split_timestamp(rest);
+                messages.push((start, end.unwrap_or(start), rest.to_string()));
+            }
+            None => match messages.last_mut() {
+                // The message, or the next line of it.
+                Some((_, _, text)) => {
+                    if !text.is_empty() {
+                        text.push('\n');
+                    }
+                    text.push_str(line);
+                }
+                None => messages.push((0.0, 0.0, line.to_string())),
+            },
+        }
+    }
+
+    messages
+        .into_iter()
+        .filter(|(_, _, text)| !text.is_empty())
+        .enumerate()
+        .map(|(i, (start, end, text))| {
+            let (speaker, text) = match text.split_once(" : ") {
+                Some((speaker, text)) => {
+                    // Zoom starts the speaker with `From`.
+                    let speaker = speaker.trim();
+                    (speaker.strip_prefix("From ").unwrap_or(speaker).trim().to_string(), text.trim().to_string())
+                }
+                None => (String::new(), text),
+            };
+            NewRecordedMeetingSegment {
+                recorded_meeting_id,
+                kind: CHAT_SEGMENT.to_string(),
+                position: i as i32,
+                speaker,
+                start_seconds: start,
+                end_seconds: end,
+                text,
+            }
+        })
+        .collect()
+}
+
+/// Split a timestamp like `01:02:03.456` or `02:03` off the start of a line,
+/// and return it in seconds with the rest of the line.
+fn split_timestamp(line: &str) -> (Option<f64>, &str) {
+    let token = line.split(|c: char| c.is_whitespace() || c == ',').next().unwrap_or_default();
+    let parts: Vec<&str> = token.split(':').collect();
+    if parts.len() < 2 || parts.len() > 3 {
+        return (None, line);
+    }
+
+    let mut seconds = 0.0;
+    for part in parts {
+        if part.is_empty() || !part.chars().all(|c| c.is_ascii_digit() || c == '.') {
+            return (None, line);
+        }
+        match part.parse::<f64>() {
+            Ok(n) => seconds = seconds * 60.0 + n,
+            Err(_) => return (None, line),
+        }
+    }
+
+    (Some(seconds), line[token.len()..].trim_start_matches(|c: char| c.is_whitespace() || c == ','))
+}
+
 /// Sync the recorded meetings.
-pub async fn refresh_recorded_meetings() {
+pub async fn refresh_recorded_meetings() -> Result<(), crate::Error> {
     let db = Database::new();
-    RecordedMeetings::get_from_db(&db).update_airtable().await;
+    RecordedMeetings::get_from_db(&db)?.update_airtable(&db).await?;
 
     let gsuite_customer = env::var("GADMIN_ACCOUNT_ID").unwrap();
     let token = get_gsuite_token("").await;
@@ -144,25 +542,10 @@ pub async fn refresh_recorded_meetings() {
                 let mut chat_log = "".to_string();
                 if !chat_log_link.is_empty() {
                     // Download the file.
-                    let contents = drive_client
-                        .download_file_by_id(&chat_log_link.trim_start_matches("https://drive.google.com/open?id="))
-                        .await
-                        .unwrap_or_default();
+                    let contents = drive_client.download_file_by_id(drive_file_id(&chat_log_link)).await.unwrap_or_default();
                     chat_log = from_utf8(&contents).unwrap_or_default().trim().to_string();
                 }
 
-                // Try to download the video.
-                let video_contents = drive_client
-                    .download_file_by_id(&video.trim_start_matches("https://drive.google.com/open?id="))
-                    .await
-                    .unwrap_or_default();
-
-                // Make sure the contents aren't empty.
-                if video_contents.is_empty() {
-                    // Continue early.
-                    continue;
-                }
-
                 let mut meeting = NewRecordedMeeting {
                     name: event.summary.trim().to_string(),
                     description: event.description.trim().to_string(),
@@ -175,17 +558,21 @@ pub async fn refresh_recorded_meetings() {
                     attendees,
                     transcript: "".to_string(),
                     transcript_id: "".to_string(),
+                    transcript_failure: "".to_string(),
                     location: event.location.to_string(),
                     google_event_id: event.id.to_string(),
                     event_link: event.html_link.to_string(),
+                    owner: owner.to_string(),
                 };
 
                 // Let's try to get the meeting.
                 let existing = RecordedMeeting::get_from_db(&db, event.id.to_string());
+                let previous_chat_log = existing.as_ref().map(|m| m.chat_log.to_string()).unwrap_or_default();
                 if let Some(m) = existing {
                     // Update the meeting.
                     meeting.transcript = m.transcript.to_string();
                     meeting.transcript_id = m.transcript_id.to_string();
+                    meeting.transcript_failure = m.transcript_failure.to_string();
 
                     // Get it from Airtable.
                     if let Some(existing_airtable) = m.get_existing_airtable_record().await {
@@ -199,38 +586,207 @@ pub async fn refresh_recorded_meetings() {
                 }
 
                 // Upsert the meeting in the database.
-                let mut db_meeting = meeting.upsert(&db).await;
+                let mut db_meeting = match meeting.upsert(&db).await {
+                    Ok(m) => m,
+                    Err(e) => {
+                        println!("upserting recorded meeting {} failed, skipping: {}", meeting.name, e);
+                        continue;
+                    }
+                };
+
+                // Split the chat log into messages, so it can be searched. The chat
+                // log can change after the meeting, so the messages are replaced
+                // whenever it does.
+                let chat_log_changed = db_meeting.chat_log != previous_chat_log;
+                match RecordedMeetingSegment::count(&db, db_meeting.id, CHAT_SEGMENT) {
+                    Ok(count) if chat_log_changed || (count == 0 && !db_meeting.chat_log.is_empty()) => {
+                        if let Err(e) = RecordedMeetingSegment::replace(&db, db_meeting.id, CHAT_SEGMENT, &chat_log_segments(db_meeting.id, &db_meeting.chat_log)) {
+                            println!("saving chat log segments for recorded meeting {} failed: {}", db_meeting.name, e);
+                        }
+                    }
+                    Ok(_) => (),
+                    Err(e) => println!("counting chat log segments for recorded meeting {} failed: {}", db_meeting.name, e),
+                }
+
                 // Check if we have a transcript id.
                 if db_meeting.transcript_id.is_empty() && db_meeting.transcript.is_empty() {
-                    // If we don't have a transcript ID, let's post the video to be
-                    // transcribed.
-                    // Now let's upload it to rev.ai so it can start a job.
-                    let job = revai.create_job(video_contents).await.unwrap();
-                    // Set the transcript id.
-                    db_meeting.transcript_id = job.id.to_string();
-                    db_meeting.update(&db).await;
-                } else {
-                    // We have a transcript id, let's try and get the transcript if we don't have
-                    // it already.
-                    if db_meeting.transcript.is_empty() {
-                        // Now let's try to get the transcript.
-                        let transcript = revai.get_transcript(&db_meeting.transcript_id).await.unwrap_or_default();
-                        db_meeting.transcript = transcript.trim().to_string();
-                        db_meeting.update(&db).await;
+                    // If we don't have a transcript ID, let's send the video to
+                    // rev.ai to be transcribed.
+                    if let Err(e) = db_meeting.submit_for_transcription(&db, &drive_client, &revai).await {
+                        println!("sending recorded meeting {} to rev.ai failed: {}", db_meeting.name, e);
+                    }
+                } else if !db_meeting.transcript_id.is_empty() && db_meeting.transcript_failure.is_empty() {
+                    // We have a transcript id, let's check on the job if we don't have the
+                    // transcript already, or have not split it into segments yet. This
+                    // is usually done by the rev.ai callback already.
+                    let segments = RecordedMeetingSegment::count(&db, db_meeting.id, TRANSCRIPT_SEGMENT).unwrap_or_default();
+                    if db_meeting.transcript.is_empty() || segments == 0 {
+                        let result = match revai.get_job(&db_meeting.transcript_id).await {
+                            Ok(job) => update_recorded_meeting_from_revai_job(&db, &job).await,
+                            Err(e) => Err(e.into()),
+                        };
+                        if let Err(e) = result {
+                            println!("saving transcript for recorded meeting {} failed: {}", db_meeting.name, e);
+                        }
                     }
                 }
             }
         }
     }
+
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::recorded_meetings::refresh_recorded_meetings;
+    use chrono::Utc;
+    use cio_testkit::FakeApis;
+    use revai::{Job, Transcript};
+    use serde_json::json;
+
+    use crate::db::Database;
+    use crate::recorded_meetings::{
+        chat_log_segments, transcript_segments, update_recorded_meeting_from_revai_job, video_link_at, NewRecordedMeeting, RecordedMeeting, RecordedMeetingSegment, CHAT_SEGMENT,
+    };
+
+    #[test]
+    fn test_transcript_segments() {
+        let transcript: Transcript = serde_json::from_str(
+            r#"{"monologues": [
+                {"speaker": 0, "elements": [
+                    {"type": "text", "value": "Hello", "ts": 1.5, "end_ts": 2.0, "confidence": 1},
+                    {"type": "punct", "value": "."},
+                    {"type": "punct", "value": " "},
+                    {"type": "text", "value": "Ready", "ts": 3.0, "end_ts": 3.5, "confidence": 1},
+                    {"type": "punct", "value": "?"}
+                ]},
+                {"speaker": 1, "speaker_name": "Jess", "elements": [
+                    {"type": "text", "value": "Yes", "ts": 4.0, "end_ts": 4.25, "confidence": 1}
+                ]}
+            ]}"#,
+        )
+        .unwrap();
+
+        let segments = transcript_segments(7, &transcript);
+        let got: Vec<(i32, &str, f64, f64, &str)> = segments.iter().map(|s| (s.position, s.speaker.as_str(), s.start_seconds, s.end_seconds, s.text.as_str())).collect();
+        assert_eq!(got, vec![(0, "Speaker 0", 1.5, 2.0, "Hello."), (1, "Speaker 0", 3.0, 3.5, "Ready?"), (2, "Jess", 4.0, 4.25, "Yes")]);
+        assert!(segments.iter().all(|s| s.recorded_meeting_id == 7 && s.kind == "transcript"));
+
+        assert_eq!(transcript.to_text(), "Speaker 0    00:00:01    Hello. Ready?\n\nJess    00:00:04    Yes");
+    }
+
+    #[test]
+    fn test_chat_log_segments() {
+        let meet = chat_log_segments(
+            7,
+            "00:02:13.482,00:02:16.482
+Jess Frazelle : hello
 
-    #[ignore]
+01:00:01.000,01:00:04.000
+Steve Tuck : see https://oxide.computer
+and the RFD
+",
+        );
+        let got: Vec<(&str, f64, f64, &str)> = meet.iter().map(|s| (s.speaker.as_str(), s.start_seconds, s.end_seconds, s.text.as_str())).collect();
+        assert_eq!(
+            got,
+            vec![("Jess Frazelle", 133.482, 136.482, "hello"), ("Steve Tuck", 3601.0, 3604.0, "see https://oxide.computer\nand the RFD")]
+        );
+
+        let zoom = chat_log_segments(7, "00:02:13\t From  Jess Frazelle : hello\nno timestamp");
+        assert_eq!(zoom.len(), 1);
+        assert_eq!(zoom[0].speaker, "Jess Frazelle");
+        assert_eq!(zoom[0].start_seconds, 133.0);
+        assert_eq!(zoom[0].text, "hello\nno timestamp");
+    }
+
+    #[test]
+    fn test_video_link_at() {
+        assert_eq!(video_link_at("https://drive.google.com/open?id=abc123", 65.7), "https://drive.google.com/file/d/abc123/view?t=65");
+        assert_eq!(video_link_at("https://example.com/video.mp4", 65.7), "https://example.com/video.mp4");
+    }
+
+    /// This needs `CIO_DATABASE_URL` to point at a scratch database.
+    #[test]
+    fn test_search_only_meetings_the_caller_attended() {
+        let db = Database::new();
+
+        // A word no other meeting in the database has.
+        let word = format!("frobnicate{}", Utc::now().timestamp_nanos());
+        let meeting = NewRecordedMeeting {
+            name: "Hiring sync".to_string(),
+            description: "".to_string(),
+            start_time: Utc::now(),
+            end_time: Utc::now(),
+            video: "https://drive.google.com/open?id=abc123".to_string(),
+            chat_log_link: "".to_string(),
+            chat_log: "".to_string(),
+            is_recurring: false,
+            attendees: vec!["jane@example.com".to_string(), "Steve@example.com".to_string()],
+            transcript: "".to_string(),
+            transcript_id: "".to_string(),
+            transcript_failure: "".to_string(),
+            google_event_id: word.to_string(),
+            event_link: "".to_string(),
+            location: "".to_string(),
+            owner: "jess@example.com".to_string(),
+        }
+        .create_in_db(&db)
+        .unwrap();
+        let segments = chat_log_segments(meeting.id, &format!("00:00:05 From Jane : we should {} the offer", word));
+        RecordedMeetingSegment::replace(&db, meeting.id, CHAT_SEGMENT, &segments).unwrap();
+
+        let search = |attendee: Option<&str>| RecordedMeetingSegment::search(&db, &word, attendee, 10).unwrap();
+        assert_eq!(search(None).len(), 1);
+        assert_eq!(search(Some("jane@example.com")).len(), 1);
+        assert_eq!(search(Some("steve@example.com")).len(), 1);
+        assert_eq!(search(Some("jess@example.com")).len(), 1);
+        assert!(search(Some("eve@example.com")).is_empty());
+    }
+
+    /// The APIs are all fakes, but the meeting is saved in the database, so
+    /// this needs `CIO_DATABASE_URL` to point at a scratch one.
     #[tokio::test(flavor = "multi_thread")]
-    async fn test_cron_recorded_meetings() {
-        refresh_recorded_meetings().await;
+    async fn test_failed_revai_job_before_its_id_was_saved() {
+        let fakes = FakeApis::start();
+        let _env = fakes.set_env();
+        let db = Database::new();
+
+        let meeting = NewRecordedMeeting {
+            name: "Hiring sync".to_string(),
+            description: "".to_string(),
+            start_time: Utc::now(),
+            end_time: Utc::now(),
+            video: "https://drive.google.com/open?id=abc123".to_string(),
+            chat_log_link: "".to_string(),
+            chat_log: "".to_string(),
+            is_recurring: false,
+            attendees: vec![],
+            transcript: "".to_string(),
+            transcript_id: "".to_string(),
+            transcript_failure: "".to_string(),
+            google_event_id: format!("event{}", Utc::now().timestamp_nanos()),
+            event_link: "".to_string(),
+            location: "".to_string(),
+            owner: "jess@example.com".to_string(),
+        }
+        .create_in_db(&db)
+        .unwrap();
+
+        // rev.ai can tell us the job failed before `submit_for_transcription` saved its id.
+        let job: Job = serde_json::from_value(json!({
+            "id": format!("job{}", meeting.id),
+            "status": "failed",
+            "created_on": Utc::now(),
+            "metadata": format!("recorded_meetings:{}", meeting.id),
+            "failure": "download_failure",
+            "failure_detail": "The media could not be downloaded.",
+        }))
+        .unwrap();
+        update_recorded_meeting_from_revai_job(&db, &job).await.unwrap();
+
+        let meeting = RecordedMeeting::get_by_id(&db, meeting.id).unwrap();
+        assert_eq!(meeting.transcript_id, job.id);
+        assert_eq!(meeting.transcript_failure, "download_failure: The media could not be downloaded.");
     }
 }
@@ -203,7 +203,13 @@ pub async fn refresh_db_rfds(db: &Database, github: &Github) {
 
     // Sync rfds.
     for (_, rfd) in rfds {
-        let mut new_rfd = rfd.upsert(db).await;
+        let mut new_rfd = match rfd.upsert(db).await {
+            Ok(r) => r,
+            Err(e) => {
+                println!("upserting RFD {} failed, skipping: {}", rfd.number, e);
+                continue;
+            }
+        };
 
         // Expand the fields in the RFD.
         new_rfd.expand(github).await;
@@ -213,12 +219,14 @@ pub async fn refresh_db_rfds(db: &Database, github: &Github) {
 
         // Update the RFD again.
         // We do this so the expand functions are only one place.
-        new_rfd.update(db).await;
+        if let Err(e) = new_rfd.update(db).await {
+            println!("updating RFD {} failed: {}", new_rfd.number, e);
+        }
     }
 }
 
 /// Create a changelog email for the RFDs.
-pub async fn send_rfd_changelog() {
+pub async fn send_rfd_changelog() -> Result<(), crate::Error> {
     // Initialize our database.
     let db = Database::new();
     let github = authenticate_github_jwt();
@@ -228,7 +236,7 @@ pub async fn send_rfd_changelog() {
     let mut changelog = format!("Changes to RFDs for the week {}:\n", week_format);
 
     // Iterate over the RFDs.
-    let rfds = RFDs::get_from_db(&db);
+    let rfds = RFDs::get_from_db(&db)?;
     for rfd in rfds {
         let changes = rfd.get_weekly_changelog(&github, seven_days_ago).await;
         if !changes.is_empty() {
@@ -250,6 +258,8 @@ pub async fn send_rfd_changelog() {
             format!("rfds@{}", DOMAIN),
         )
         .await;
+
+    Ok(())
 }
 
 #[cfg(test)]
@@ -269,13 +279,13 @@ mod tests {
         refresh_db_rfds(&db, &github).await;
 
         // Update rfds in airtable.
-        RFDs::get_from_db(&db).update_airtable().await;
+        RFDs::get_from_db(&db).unwrap().update_airtable(&db).await.unwrap();
     }
 
     #[ignore]
     #[tokio::test(flavor = "multi_thread")]
     async fn test_monday_cron_rfds_changelog() {
-        send_rfd_changelog().await;
+        send_rfd_changelog().await.unwrap();
     }
 
     #[test]
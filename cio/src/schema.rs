@@ -13,6 +13,18 @@ table! {
         invoices -> Array<Text>,
         link_to_vendor -> Array<Text>,
         airtable_record_id -> Varchar,
+        airtable_last_synced_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    airtable_sync_states (id) {
+        id -> Int4,
+        base_id -> Varchar,
+        table_name -> Varchar,
+        last_synced_at -> Timestamptz,
+        last_full_sync_at -> Timestamptz,
+        record_hashes -> Jsonb,
     }
 }
 
@@ -30,6 +42,7 @@ table! {
         refresh_token_expires_in -> Int4,
         last_updated_at -> Timestamptz,
         airtable_record_id -> Varchar,
+        airtable_last_synced_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -45,6 +58,7 @@ table! {
         event_link -> Varchar,
         applicant -> Array<Text>,
         airtable_record_id -> Varchar,
+        airtable_last_synced_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -60,6 +74,7 @@ table! {
         no -> Int4,
         not_applicable -> Int4,
         airtable_record_id -> Varchar,
+        airtable_last_synced_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -135,6 +150,19 @@ table! {
         offer_created -> Nullable<Timestamptz>,
         offer_completed -> Nullable<Timestamptz>,
         airtable_record_id -> Varchar,
+        airtable_last_synced_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    audit_log_entries (id) {
+        id -> Int4,
+        table_name -> Varchar,
+        record_id -> Int4,
+        action -> Varchar,
+        changes -> Jsonb,
+        source -> Varchar,
+        created_at -> Timestamptz,
     }
 }
 
@@ -162,6 +190,7 @@ table! {
         user_agent -> Varchar,
         link_to_auth_user -> Array<Text>,
         airtable_record_id -> Varchar,
+        airtable_last_synced_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -191,6 +220,7 @@ table! {
         link_to_auth_user_logins -> Array<Text>,
         link_to_page_views -> Array<Text>,
         airtable_record_id -> Varchar,
+        airtable_last_synced_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -204,6 +234,7 @@ table! {
         barcode -> Varchar,
         link_to_item -> Array<Text>,
         airtable_record_id -> Varchar,
+        airtable_last_synced_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -223,6 +254,7 @@ table! {
         conference_rooms -> Array<Text>,
         geocode_cache -> Varchar,
         airtable_record_id -> Varchar,
+        airtable_last_synced_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -235,6 +267,7 @@ table! {
         valid_days_left -> Int4,
         expiration_date -> Date,
         airtable_record_id -> Varchar,
+        airtable_last_synced_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -250,6 +283,7 @@ table! {
         floor -> Varchar,
         section -> Varchar,
         airtable_record_id -> Varchar,
+        airtable_last_synced_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -271,6 +305,32 @@ table! {
         receipts -> Array<Text>,
         link_to_vendor -> Array<Text>,
         airtable_record_id -> Varchar,
+        airtable_last_synced_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    cron_job_runs (id) {
+        id -> Int4,
+        name -> Varchar,
+        status -> Varchar,
+        error -> Text,
+        hostname -> Varchar,
+        started_at -> Timestamptz,
+        finished_at -> Nullable<Timestamptz>,
+        duration_seconds -> Int4,
+    }
+}
+
+table! {
+    email_templates (id) {
+        id -> Int4,
+        name -> Varchar,
+        version -> Int4,
+        subject -> Varchar,
+        text_body -> Text,
+        html_body -> Text,
+        created_at -> Timestamptz,
     }
 }
 
@@ -292,6 +352,7 @@ table! {
         receipts -> Array<Text>,
         link_to_vendor -> Array<Text>,
         airtable_record_id -> Varchar,
+        airtable_last_synced_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -365,6 +426,7 @@ table! {
         created_at -> Timestamptz,
         updated_at -> Timestamptz,
         airtable_record_id -> Varchar,
+        airtable_last_synced_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -387,6 +449,45 @@ table! {
         who_can_view_membership -> Varchar,
         enable_collaborative_inbox -> Bool,
         airtable_record_id -> Varchar,
+        airtable_last_synced_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    inbound_email_attachments (id) {
+        id -> Int4,
+        inbound_email_id -> Int4,
+        part_index -> Int4,
+        filename -> Varchar,
+        content_type -> Varchar,
+        size_bytes -> Int4,
+        drive_file_id -> Varchar,
+        drive_url -> Varchar,
+        created_at -> Timestamptz,
+    }
+}
+
+table! {
+    inbound_emails (id) {
+        id -> Int4,
+        message_id -> Varchar,
+        in_reply_to -> Varchar,
+        from_email -> Varchar,
+        from_name -> Varchar,
+        to_addresses -> Array<Text>,
+        cc_addresses -> Array<Text>,
+        subject -> Varchar,
+        text_body -> Text,
+        html_body -> Text,
+        raw -> Text,
+        spam_score -> Float4,
+        route -> Varchar,
+        applicant_id -> Nullable<Int4>,
+        accounts_payable_id -> Nullable<Int4>,
+        inbound_shipment_id -> Nullable<Int4>,
+        received_at -> Timestamptz,
+        routed_at -> Nullable<Timestamptz>,
+        created_at -> Timestamptz,
     }
 }
 
@@ -405,6 +506,7 @@ table! {
         name -> Varchar,
         notes -> Varchar,
         airtable_record_id -> Varchar,
+        airtable_last_synced_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -420,6 +522,7 @@ table! {
         state -> Varchar,
         recording -> Varchar,
         airtable_record_id -> Varchar,
+        airtable_last_synced_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -431,6 +534,7 @@ table! {
         meeting -> Varchar,
         link_to_meeting -> Array<Text>,
         airtable_record_id -> Varchar,
+        airtable_last_synced_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -443,6 +547,7 @@ table! {
         aliases -> Array<Text>,
         short_link -> Varchar,
         airtable_record_id -> Varchar,
+        airtable_last_synced_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -465,6 +570,7 @@ table! {
         tags -> Array<Text>,
         link_to_people -> Array<Text>,
         airtable_record_id -> Varchar,
+        airtable_last_synced_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -503,7 +609,10 @@ table! {
         geocode_cache -> Varchar,
         local_pickup -> Bool,
         link_to_package_pickup -> Array<Text>,
+        label_print_job_id -> Varchar,
+        label_print_status -> Varchar,
         airtable_record_id -> Varchar,
+        airtable_last_synced_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -524,6 +633,7 @@ table! {
         cancel_by_time -> Nullable<Timestamptz>,
         messages -> Varchar,
         airtable_record_id -> Varchar,
+        airtable_last_synced_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -537,6 +647,24 @@ table! {
         page_link -> Varchar,
         link_to_auth_user -> Array<Text>,
         airtable_record_id -> Varchar,
+        airtable_last_synced_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    queued_jobs (id) {
+        id -> Int4,
+        kind -> Varchar,
+        payload -> Jsonb,
+        idempotency_key -> Varchar,
+        status -> Varchar,
+        attempts -> Int4,
+        max_attempts -> Int4,
+        last_error -> Text,
+        run_at -> Timestamptz,
+        locked_at -> Nullable<Timestamptz>,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
     }
 }
 
@@ -555,6 +683,21 @@ table! {
         tags -> Array<Text>,
         link_to_people -> Array<Text>,
         airtable_record_id -> Varchar,
+        airtable_last_synced_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    recorded_meeting_segments (id) {
+        id -> Int4,
+        recorded_meeting_id -> Int4,
+        kind -> Varchar,
+        position -> Int4,
+        speaker -> Varchar,
+        start_seconds -> Float8,
+        end_seconds -> Float8,
+        text -> Text,
+        created_at -> Timestamptz,
     }
 }
 
@@ -572,10 +715,13 @@ table! {
         attendees -> Array<Text>,
         transcript -> Text,
         transcript_id -> Varchar,
+        transcript_failure -> Varchar,
         google_event_id -> Varchar,
         event_link -> Varchar,
         location -> Varchar,
+        owner -> Varchar,
         airtable_record_id -> Varchar,
+        airtable_last_synced_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -601,6 +747,7 @@ table! {
         pdf_link_github -> Varchar,
         pdf_link_google_drive -> Varchar,
         airtable_record_id -> Varchar,
+        airtable_last_synced_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -626,6 +773,7 @@ table! {
         link_to_accounts_payable -> Array<Text>,
         link_to_expensed_items -> Array<Text>,
         airtable_record_id -> Varchar,
+        airtable_last_synced_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -642,7 +790,10 @@ table! {
         barcode_pdf_label -> Varchar,
         print_barcode_label_quantity -> Int4,
         link_to_item -> Array<Text>,
+        label_print_job_id -> Varchar,
+        label_print_status -> Varchar,
         airtable_record_id -> Varchar,
+        airtable_last_synced_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -659,6 +810,31 @@ table! {
         link_to_order_october_2020 -> Array<Text>,
         link_to_order_may_2021 -> Array<Text>,
         airtable_record_id -> Varchar,
+        airtable_last_synced_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    user_offboarding_steps (id) {
+        id -> Int4,
+        offboarding_id -> Int4,
+        step -> Varchar,
+        status -> Varchar,
+        message -> Text,
+        updated_at -> Timestamptz,
+    }
+}
+
+table! {
+    user_offboardings (id) {
+        id -> Int4,
+        username -> Varchar,
+        email -> Varchar,
+        github -> Varchar,
+        manager -> Varchar,
+        google_anniversary_event_id -> Varchar,
+        started_at -> Timestamptz,
+        completed_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -708,15 +884,22 @@ table! {
         google_anniversary_event_id -> Varchar,
         geocode_cache -> Varchar,
         airtable_record_id -> Varchar,
+        airtable_last_synced_at -> Nullable<Timestamptz>,
     }
 }
 
+joinable!(inbound_email_attachments -> inbound_emails (inbound_email_id));
+joinable!(recorded_meeting_segments -> recorded_meetings (recorded_meeting_id));
+joinable!(user_offboarding_steps -> user_offboardings (offboarding_id));
+
 allow_tables_to_appear_in_same_query!(
     accounts_payables,
+    airtable_sync_states,
     api_tokens,
     applicant_interviews,
     applicant_reviewers,
     applicants,
+    audit_log_entries,
     auth_user_logins,
     auth_users,
     barcode_scans,
@@ -724,9 +907,13 @@ allow_tables_to_appear_in_same_query!(
     certificates,
     conference_rooms,
     credit_card_transactions,
+    cron_job_runs,
+    email_templates,
     expensed_items,
     github_repos,
     groups,
+    inbound_email_attachments,
+    inbound_emails,
     inbound_shipments,
     journal_club_meetings,
     journal_club_papers,
@@ -735,11 +922,15 @@ allow_tables_to_appear_in_same_query!(
     outbound_shipments,
     package_pickups,
     page_views,
+    queued_jobs,
     rack_line_subscribers,
+    recorded_meeting_segments,
     recorded_meetings,
     rfds,
     software_vendors,
     swag_inventory_items,
     swag_items,
+    user_offboarding_steps,
+    user_offboardings,
     users,
 );
@@ -1,7 +1,6 @@
 #![allow(clippy::from_over_into)]
 use std::collections::HashMap;
 use std::convert::From;
-use std::env;
 
 use async_trait::async_trait;
 use chrono::naive::NaiveDate;
@@ -9,18 +8,18 @@ use chrono::offset::Utc;
 use chrono::{DateTime, Duration, NaiveTime};
 use google_geocode::Geocode;
 use macros::db;
-use reqwest::StatusCode;
 use schemars::JsonSchema;
-use sendgrid_api::SendGrid;
 use serde::{Deserialize, Serialize};
-use sheets::Sheets;
+use sheets::{HeaderMap, Sheets};
 use shippo::{Address, CustomsDeclaration, CustomsItem, NewShipment, NewTransaction, Parcel, Shippo};
 
 use crate::airtable::{AIRTABLE_BASE_ID_SHIPMENTS, AIRTABLE_INBOUND_TABLE, AIRTABLE_OUTBOUND_TABLE, AIRTABLE_PACKAGE_PICKUPS_TABLE};
 use crate::configs::User;
-use crate::core::UpdateAirtableRecord;
+use crate::core::{UpdateAirtableRecord, Validate};
 use crate::db::Database;
+use crate::email_templates::EmailTemplates;
 use crate::models::get_value;
+use crate::print_jobs::{send_print_request, PrintJobSource, PrintJobStatus};
 use crate::schema::{inbound_shipments, outbound_shipments, package_pickups};
 use crate::utils::{get_gsuite_token, DOMAIN};
 
@@ -33,6 +32,7 @@ use crate::utils::{get_gsuite_token, DOMAIN};
         "tracking_number" = "String",
         "carrier" = "String",
     },
+    merged_fields = ["notes"],
 }]
 #[derive(Debug, Insertable, AsChangeset, Default, PartialEq, Clone, JsonSchema, Deserialize, Serialize)]
 #[table_name = "inbound_shipments"]
@@ -89,9 +89,6 @@ impl UpdateAirtableRecord<InboundShipment> for InboundShipment {
         if self.eta.is_none() {
             self.eta = record.eta;
         }
-        if self.notes.is_empty() {
-            self.notes = record.notes;
-        }
     }
 }
 
@@ -190,6 +187,8 @@ impl InboundShipment {
         "tracking_number" = "String",
         "carrier" = "String",
     },
+    airtable_fields = ["link_to_package_pickup", "geocode_cache"],
+    merged_fields = ["status", "notes"],
 }]
 #[derive(Debug, Insertable, AsChangeset, PartialEq, Clone, JsonSchema, Deserialize, Serialize)]
 #[table_name = "outbound_shipments"]
@@ -261,6 +260,12 @@ pub struct NewOutboundShipment {
     /// This is automatically filled in by Airtbale.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub link_to_package_pickup: Vec<String>,
+    /// The id of the latest printy job for the label.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub label_print_job_id: String,
+    /// Whether the label printed, as reported by printy.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub label_print_status: String,
 }
 
 impl From<User> for NewOutboundShipment {
@@ -298,6 +303,8 @@ impl From<User> for NewOutboundShipment {
             geocode_cache: Default::default(),
             local_pickup: Default::default(),
             link_to_package_pickup: Default::default(),
+            label_print_job_id: Default::default(),
+            label_print_status: Default::default(),
         }
     }
 }
@@ -442,12 +449,16 @@ impl OutboundShipments {
         };
 
         // Insert the new pickup into the database.
-        np.upsert(&db).await;
+        if let Err(e) = np.upsert(&db).await {
+            println!("saving pickup {} failed: {}", np.confirmation_code, e);
+        }
 
         // For each of the shipments, let's set the pickup date.
         for mut shipment in shipments {
             shipment.pickup_date = Some(pickup_date);
-            shipment.update(&db).await;
+            if let Err(e) = shipment.update(&db).await {
+                println!("setting pickup date for shipment {} failed: {}", shipment.tracking_number, e);
+            }
         }
     }
 }
@@ -568,125 +579,36 @@ impl NewOutboundShipment {
             geocode_cache: Default::default(),
             local_pickup: false,
             link_to_package_pickup: Default::default(),
+            label_print_job_id: Default::default(),
+            label_print_status: Default::default(),
         }
     }
 
-    /// Parse the shipment from a Google Sheets row, where we also happen to know the columns.
+    /// Parse the shipment from a Google Sheets row, read by header with `sheets::HeaderMap`.
     /// This is how we get the spreadsheet back from the API.
-    pub async fn parse_from_row_with_columns(db: &Database, columns: &SwagSheetColumns, row: &[String]) -> (Self, bool) {
-        // If the length of the row is greater than the sent column
-        // then we have a sent status.
-        let sent = if row.len() > columns.sent { row[columns.sent].to_lowercase().contains("true") } else { false };
-
-        // If the length of the row is greater than the country column
-        // then we have a country.
-        let mut country = if row.len() > columns.country && columns.country != 0 {
-            row[columns.country].trim().to_uppercase()
-        } else {
-            "US".to_string()
-        };
+    pub async fn parse_from_sheet_row(db: &Database, row: &SwagSheetRow) -> (Self, bool) {
+        let sent = row.sent;
+
+        let mut country = row.country.trim().to_uppercase();
         if country.is_empty() {
             country = "US".to_string();
         }
 
-        // If the length of the row is greater than the name column
-        // then we have a name.
-        let name = if row.len() > columns.name && columns.name != 0 {
-            row[columns.name].trim().to_string()
-        } else {
-            "".to_lowercase()
-        };
-
-        // If the length of the row is greater than the phone column
-        // then we have a phone.
-        let phone = if row.len() > columns.phone && columns.phone != 0 {
-            row[columns.phone].trim().to_lowercase()
-        } else {
-            "".to_lowercase()
-        };
-
-        // If the length of the row is greater than the zipcode column
-        // then we have a zipcode.
-        let zipcode = if row.len() > columns.zipcode && columns.zipcode != 0 {
-            row[columns.zipcode].trim().to_uppercase()
-        } else {
-            "".to_lowercase()
-        };
-
-        // If the length of the row is greater than the state column
-        // then we have a state.
-        let state = if row.len() > columns.state && columns.state != 0 {
-            row[columns.state].trim().to_uppercase()
-        } else {
-            "".to_lowercase()
-        };
-
-        // If the length of the row is greater than the city column
-        // then we have a city.
-        let city = if row.len() > columns.city && columns.city != 0 {
-            row[columns.city].trim().to_uppercase()
-        } else {
-            "".to_lowercase()
-        };
-
-        // If the length of the row is greater than the street_1 column
-        // then we have a street_1.
-        let street_1 = if row.len() > columns.street_1 && columns.street_1 != 0 {
-            row[columns.street_1].trim().to_uppercase()
-        } else {
-            "".to_lowercase()
-        };
-
-        // If the length of the row is greater than the street_2 column
-        // then we have a street_2.
-        let street_2 = if row.len() > columns.street_2 && columns.street_2 != 0 {
-            row[columns.street_2].trim().to_uppercase()
-        } else {
-            "".to_lowercase()
-        };
-
-        // If the length of the row is greater than the hoodie_size column
-        // then we have a hoodie_size.
-        let hoodie_size = if row.len() > columns.hoodie_size && columns.hoodie_size != 0 {
-            row[columns.hoodie_size].trim().to_uppercase()
-        } else {
-            "".to_lowercase()
-        };
-
-        // If the length of the row is greater than the fleece_size column
-        // then we have a fleece_size.
-        let fleece_size = if row.len() > columns.fleece_size && columns.fleece_size != 0 {
-            row[columns.fleece_size].trim().to_uppercase()
-        } else {
-            "".to_lowercase()
-        };
-
-        // If the length of the row is greater than the womens_shirt_size column
-        // then we have a womens_shirt_size.
-        let womens_shirt_size = if row.len() > columns.womens_shirt_size && columns.womens_shirt_size != 0 {
-            row[columns.womens_shirt_size].trim().to_uppercase()
-        } else {
-            "".to_lowercase()
-        };
-
-        // If the length of the row is greater than the unisex_shirt_size column
-        // then we have a unisex_shirt_size.
-        let unisex_shirt_size = if row.len() > columns.unisex_shirt_size && columns.unisex_shirt_size != 0 {
-            row[columns.unisex_shirt_size].trim().to_uppercase()
-        } else {
-            "".to_lowercase()
-        };
-
-        // If the length of the row is greater than the kids_shirt_size column
-        // then we have a kids_shirt_size.
-        let kids_shirt_size = if row.len() > columns.kids_shirt_size && columns.kids_shirt_size != 0 {
-            row[columns.kids_shirt_size].trim().to_uppercase()
-        } else {
-            "".to_lowercase()
-        };
+        let name = row.name.trim().to_string();
+        let phone = row.phone.trim().to_lowercase();
+        let zipcode = row.zipcode.trim().to_uppercase();
+        let state = row.state.trim().to_uppercase();
+        let city = row.city.trim().to_uppercase();
+        let street_1 = row.street_1.trim().to_uppercase();
+        let street_2 = row.street_2.trim().to_uppercase();
+        let hoodie_size = row.hoodie_size.trim().to_uppercase();
+        let fleece_size = row.fleece_size.trim().to_uppercase();
+        let womens_shirt_size = row.womens_shirt_size.trim().to_uppercase();
+        let unisex_shirt_size = row.unisex_shirt_size.trim().to_uppercase();
+        let kids_shirt_size = row.kids_shirt_size.trim().to_uppercase();
 
         // TODO: make all these more DRY.
-        let email = row[columns.email].trim().to_lowercase();
+        let email = row.email.trim().to_lowercase();
         let mut contents = String::new();
         if !hoodie_size.is_empty() && !hoodie_size.contains("N/A") {
             contents += &format!("1 x Oxide Hoodie, Size: {}\n", hoodie_size);
@@ -704,7 +626,7 @@ impl NewOutboundShipment {
             contents += &format!("1 x Oxide Kids Shirt, Size: {}\n", kids_shirt_size);
         }
 
-        let created_time = NewOutboundShipment::parse_timestamp(&row[columns.timestamp]);
+        let created_time = NewOutboundShipment::parse_timestamp(&row.timestamp);
 
         let mut carrier = Default::default();
         let mut address_formatted = Default::default();
@@ -727,6 +649,8 @@ impl NewOutboundShipment {
         let mut longitude = Default::default();
         let mut local_pickup = Default::default();
         let mut link_to_package_pickup = Default::default();
+        let mut label_print_job_id = Default::default();
+        let mut label_print_status = Default::default();
 
         // Let's try to get the record from the database.
         if let Ok(shipment) = outbound_shipments::dsl::outbound_shipments
@@ -759,6 +683,8 @@ impl NewOutboundShipment {
             tracking_number = shipment.tracking_number;
             latitude = shipment.latitude;
             longitude = shipment.longitude;
+            label_print_job_id = shipment.label_print_job_id.to_string();
+            label_print_status = shipment.label_print_status.to_string();
         }
 
         (
@@ -795,23 +721,48 @@ impl NewOutboundShipment {
                 geocode_cache,
                 local_pickup,
                 link_to_package_pickup,
+                label_print_job_id,
+                label_print_status,
             },
             sent,
         )
     }
 }
 
+/// Check an outbound shipment before the API writes it.
+impl Validate for NewOutboundShipment {
+    fn validation_errors(&self) -> Vec<String> {
+        let mut problems: Vec<String> = Default::default();
+
+        if self.name.trim().is_empty() {
+            problems.push("name cannot be empty".to_string());
+        }
+        if self.contents.trim().is_empty() {
+            problems.push("contents cannot be empty".to_string());
+        }
+        if !self.email.is_empty() && (!self.email.contains('@') || self.email.contains(char::is_whitespace)) {
+            problems.push(format!("email `{}` is not an email address", self.email));
+        }
+        // We only need an address if we are going to ship the package.
+        if !self.local_pickup {
+            for (field, value) in &[("street_1", &self.street_1), ("city", &self.city), ("zipcode", &self.zipcode), ("country", &self.country)] {
+                if value.trim().is_empty() {
+                    problems.push(format!("{} cannot be empty unless the package is picked up locally", field));
+                }
+            }
+        }
+        if self.cost < 0.0 {
+            problems.push("cost cannot be negative".to_string());
+        }
+
+        problems
+    }
+}
+
 /// Implement updating the Airtable record for an OutboundShipment.
 #[async_trait]
 impl UpdateAirtableRecord<OutboundShipment> for OutboundShipment {
     async fn update_airtable_record(&mut self, record: OutboundShipment) {
-        self.link_to_package_pickup = record.link_to_package_pickup;
-
-        self.geocode_cache = record.geocode_cache;
-
-        if self.status.is_empty() {
-            self.status = record.status;
-        }
         if self.carrier.is_empty() {
             self.carrier = record.carrier;
         }
@@ -845,9 +796,6 @@ impl UpdateAirtableRecord<OutboundShipment> for OutboundShipment {
         if self.cost == 0.0 {
             self.cost = record.cost;
         }
-        if self.notes.is_empty() {
-            self.notes = record.notes;
-        }
     }
 }
 
@@ -868,22 +816,39 @@ impl OutboundShipment {
         format!("https://track.oxide.computer/{}/{}", self.carrier, self.tracking_number)
     }
 
-    /// Send the label to our printer.
-    pub async fn print_label(&self) {
+    /// Send the label to our printer, and keep track of the print job.
+    ///
+    /// The job is saved to the database before it is sent, so printy can't
+    /// report on it before we know about it.
+    pub async fn print_label(&mut self, db: &Database) -> Result<(), crate::Error> {
         if self.label_link.trim().is_empty() {
             // Return early.
-            return;
+            return Ok(());
         }
-        let mut printer_url = env::var("PRINTER_URL").unwrap().trim_end_matches('/').to_string();
-        printer_url = format!("{}/rollo", printer_url);
-        let client = reqwest::Client::new();
-        let resp = client.post(&printer_url).body(json!(self.label_link).to_string()).send().await.unwrap();
-        match resp.status() {
-            StatusCode::ACCEPTED => (),
-            s => {
-                panic!("[print]: status_code: {}, body: {}", s, resp.text().await.unwrap());
-            }
+
+        let source = PrintJobSource {
+            table: "outbound_shipments".to_string(),
+            id: self.id,
+        };
+        self.label_print_job_id = source.new_job_id();
+        self.label_print_status = PrintJobStatus::Queued.to_string();
+        *self = self.update_in_db(db)?;
+
+        let request = PrintLabelsRequest {
+            url: self.label_link.to_string(),
+            quantity: 1,
+            source: Some(source),
+            job_id: self.label_print_job_id.to_string(),
         };
+        if let Err(e) = send_print_request("rollo", &request).await {
+            self.label_print_status = format!("{}: {}", PrintJobStatus::Failed, e);
+            *self = self.update_in_db(db)?;
+            return Err(e);
+        }
+
+        // printy could have reported on the job already.
+        self.label_print_status = OutboundShipment::get_by_id(db, self.id)?.label_print_status;
+        Ok(())
     }
 
     /// Format address.
@@ -896,133 +861,42 @@ impl OutboundShipment {
         format!("{}\n{}, {} {} {}", street, self.city, self.state, self.zipcode, self.country)
     }
 
+    /// Get the fields the shipment email templates are rendered against.
+    pub fn email_template_data(&self) -> serde_json::Value {
+        let mut data = serde_json::to_value(self).unwrap_or_default();
+        data["address"] = json!(self.format_address());
+        data
+    }
+
     /// Send an email to the recipient with their order information.
     /// This should happen before they get the email that it has been shipped.
-    pub async fn send_email_to_recipient_pre_shipping(&self) {
-        // Initialize the SendGrid client.
-        let sendgrid_client = SendGrid::new_from_env();
-        // Send the message.
-        sendgrid_client
-            .send_mail(
-                format!("{}, your order from the Oxide Computer Company has been received!", self.name),
-                format!(
-                    "Below is the information for your order:
-
-**Contents:**
-{}
-
-**Address to:**
-{}
-{}
-
-You will receive another email once your order has been shipped with your tracking numbers.
-
-If you have any questions or concerns, please respond to this email!
-Have a splendid day!
-
-xoxo,
-  The Oxide Shipping Bot",
-                    self.contents,
-                    self.name,
-                    self.format_address(),
-                ),
-                vec![self.email.to_string()],
-                vec![format!("packages@{}", DOMAIN)],
-                vec![],
-                format!("packages@{}", DOMAIN),
-            )
+    pub async fn send_email_to_recipient_pre_shipping(&self, db: &Database) {
+        self.send_email_template(db, "shipment_order_received", vec![self.email.to_string()], vec![format!("packages@{}", DOMAIN)])
             .await;
     }
 
     /// Send an email to the recipient with their tracking code and information.
-    pub async fn send_email_to_recipient(&self) {
-        // Initialize the SendGrid client.
-        let sendgrid_client = SendGrid::new_from_env();
-        // Send the message.
-        sendgrid_client
-            .send_mail(
-                format!("{}, your package from the Oxide Computer Company is on the way!", self.name),
-                format!(
-                    "Below is the information for your package:
-
-**Contents:**
-{}
-
-**Address to:**
-{}
-{}
-
-**Tracking link:**
-{}
-
-If you have any questions or concerns, please respond to this email!
-Have a splendid day!
-
-xoxo,
-  The Oxide Shipping Bot",
-                    self.contents,
-                    self.name,
-                    self.format_address(),
-                    self.oxide_tracking_link
-                ),
-                vec![self.email.to_string()],
-                vec![format!("packages@{}", DOMAIN)],
-                vec![],
-                format!("packages@{}", DOMAIN),
-            )
+    pub async fn send_email_to_recipient(&self, db: &Database) {
+        self.send_email_template(db, "shipment_on_the_way", vec![self.email.to_string()], vec![format!("packages@{}", DOMAIN)])
             .await;
     }
 
     /// Send an email internally that we need to package the shipment.
-    pub async fn send_email_internally(&self) {
-        // Initialize the SendGrid client.
-        let sendgrid_client = SendGrid::new_from_env();
-        // Send the message.
-        sendgrid_client
-            .send_mail(
-                format!("Shipment to {} is ready to be packaged", self.name),
-                format!(
-                    "Below is the information the package:
-
-**Contents:**
-{}
-
-**Address to:**
-{}
-{}
-
-**Tracking link:**
-{}
-
-The label should already be printed on the cart with the label printers. Please
-take the label and affix it to the package with the specified contents. It can
-then be dropped off for {}.
-
-You DO NOT need to scan the barcodes of the items since they have already been
-deducted from inventory. DO NOT SCAN THE BARCODES for the items since
-they have already been deducted from inventory.
-
-As always, the Airtable with all the shipments lives at:
-https://airtable-shipments.corp.oxide.computer.
-
-xoxo,
-  The Oxide Shipping Bot",
-                    self.contents,
-                    self.name,
-                    self.format_address(),
-                    self.oxide_tracking_link,
-                    self.carrier,
-                ),
-                vec![format!("packages@{}", DOMAIN)],
-                vec![],
-                vec![],
-                format!("packages@{}", DOMAIN),
-            )
-            .await;
+    pub async fn send_email_internally(&self, db: &Database) {
+        self.send_email_template(db, "shipment_ready_to_package", vec![format!("packages@{}", DOMAIN)], vec![]).await;
+    }
+
+    async fn send_email_template(&self, db: &Database, name: &str, to: Vec<String>, cc: Vec<String>) {
+        let templates = EmailTemplates::load_or_defaults(db);
+
+        match templates.render(name, &self.email_template_data()) {
+            Ok(email) => email.send(to, cc, vec![], format!("packages@{}", DOMAIN)).await,
+            Err(e) => println!("rendering email template {} for shipment {} failed: {}", name, self.id, e),
+        }
     }
 
     /// Create or get a shipment in shippo that matches this shipment.
-    pub async fn create_or_get_shippo_shipment(&mut self, db: &Database) {
+    pub async fn create_or_get_shippo_shipment(&mut self, db: &Database) -> Result<(), crate::Error> {
         // Update the formatted address.
         self.populate_formatted_address();
 
@@ -1039,15 +913,15 @@ xoxo,
             self.latitude = location.lat as f32;
             self.longitude = location.lng as f32;
             // Update here just in case something goes wrong later.
-            self.update(db).await;
+            self.update(db).await?;
         }
 
         // If we did local_pickup, we can return early here.
         if self.local_pickup {
             self.status = "Picked up".to_string();
-            self.update(db).await;
+            self.update(db).await?;
             // Return early.
-            return;
+            return Ok(());
         }
 
         // If we already have a shippo id, get the information for the label.
@@ -1084,7 +958,7 @@ xoxo,
                 if self.status != *"Shipped" {
                     // Send an email to the recipient with their tracking link.
                     // Wait until it is in transit to do this.
-                    self.send_email_to_recipient().await;
+                    self.send_email_to_recipient(db).await;
                     // We make sure it only does this one time.
                     // Set the shipped date as this first date.
                     self.shipped_time = tracking_status.status_date;
@@ -1119,7 +993,7 @@ xoxo,
             }
 
             // Return early.
-            return;
+            return Ok(());
         }
 
         // We need to create the label since we don't have one already.
@@ -1249,7 +1123,7 @@ xoxo,
                 self.oxide_tracking_link = self.oxide_tracking_link();
 
                 // Save it in Airtable here, in case one of the below steps fails.
-                self.update(db).await;
+                self.update(db).await?;
 
                 // Register a tracking webhook for this shipment.
                 shippo_client.register_tracking_webhook(&self.carrier, &self.tracking_number).await.unwrap_or_else(|e| {
@@ -1257,12 +1131,13 @@ xoxo,
                     Default::default()
                 });
 
-                // Print the label.
-                self.print_label().await;
+                // Print the label, and save that we did before anything else can fail.
+                self.print_label(db).await?;
                 self.status = "Label printed".to_string();
+                self.update(db).await?;
 
                 // Send an email to us that we need to package the shipment.
-                self.send_email_internally().await;
+                self.send_email_internally(db).await;
 
                 break;
             }
@@ -1270,99 +1145,51 @@ xoxo,
 
         // TODO: do something if we don't find a rate.
         // However we should always find a rate.
+
+        Ok(())
     }
 }
 
-/// The data type for a Google Sheet swag columns, we use this when
-/// parsing the Google Sheets for shipments.
+/// A row of a Google Sheet for swag, read by header with `sheets::HeaderMap`.
+/// We use this when parsing the Google Sheets for shipments.
 #[derive(Debug, Default, Deserialize, Serialize)]
-pub struct SwagSheetColumns {
-    pub timestamp: usize,
-    pub name: usize,
-    pub email: usize,
-    pub street_1: usize,
-    pub street_2: usize,
-    pub city: usize,
-    pub state: usize,
-    pub zipcode: usize,
-    pub country: usize,
-    pub phone: usize,
-    pub sent: usize,
-    pub fleece_size: usize,
-    pub hoodie_size: usize,
-    pub womens_shirt_size: usize,
-    pub unisex_shirt_size: usize,
-    pub kids_shirt_size: usize,
-}
-
-impl SwagSheetColumns {
-    /// Parse the sheet columns from Google Sheets values.
-    pub fn parse(values: &[Vec<String>]) -> Self {
-        // Iterate over the columns.
-        // TODO: make this less horrible
-        let mut columns: SwagSheetColumns = Default::default();
-
-        // Get the first row.
-        let row = values.get(0).unwrap();
-
-        for (index, col) in row.iter().enumerate() {
-            let c = col.to_lowercase();
-
-            if c.contains("timestamp") {
-                columns.timestamp = index;
-            }
-            if c.contains("name") {
-                columns.name = index;
-            }
-            if c.contains("email address") {
-                columns.email = index;
-            }
-            if c.contains("fleece") {
-                columns.fleece_size = index;
-            }
-            if c.contains("hoodie") {
-                columns.hoodie_size = index;
-            }
-            if c.contains("women's tee") {
-                columns.womens_shirt_size = index;
-            }
-            if c.contains("unisex tee") {
-                columns.unisex_shirt_size = index;
-            }
-            if c.contains("onesie") {
-                columns.kids_shirt_size = index;
-            }
-            if c.contains("street address line 1") {
-                columns.street_1 = index;
-            }
-            if c.contains("street address line 2") {
-                columns.street_2 = index;
-            }
-            if c.contains("city") {
-                columns.city = index;
-            }
-            if c.contains("state") {
-                columns.state = index;
-            }
-            if c.contains("zipcode") {
-                columns.zipcode = index;
-            }
-            if c.contains("country") {
-                columns.country = index;
-            }
-            if c.contains("phone") {
-                columns.phone = index;
-            }
-            if c.contains("sent") {
-                columns.sent = index;
-            }
-        }
-        columns
-    }
+pub struct SwagSheetRow {
+    #[serde(default, rename = "timestamp")]
+    pub timestamp: String,
+    #[serde(default, rename = "name")]
+    pub name: String,
+    #[serde(default, rename = "email address")]
+    pub email: String,
+    #[serde(default, rename = "street address line 1")]
+    pub street_1: String,
+    #[serde(default, rename = "street address line 2")]
+    pub street_2: String,
+    #[serde(default, rename = "city")]
+    pub city: String,
+    #[serde(default, rename = "state")]
+    pub state: String,
+    #[serde(default, rename = "zipcode")]
+    pub zipcode: String,
+    #[serde(default, rename = "country")]
+    pub country: String,
+    #[serde(default, rename = "phone")]
+    pub phone: String,
+    #[serde(default, rename = "sent")]
+    pub sent: bool,
+    #[serde(default, rename = "fleece")]
+    pub fleece_size: String,
+    #[serde(default, rename = "hoodie")]
+    pub hoodie_size: String,
+    #[serde(default, rename = "women's tee")]
+    pub womens_shirt_size: String,
+    #[serde(default, rename = "unisex tee")]
+    pub unisex_shirt_size: String,
+    #[serde(default, rename = "onesie")]
+    pub kids_shirt_size: String,
 }
 
 // Sync the outbound shipments.
-pub async fn refresh_outbound_shipments(db: &Database) {
+pub async fn refresh_outbound_shipments(db: &Database) -> Result<(), crate::Error> {
     // Get the GSuite token.
     let token = get_gsuite_token("").await;
 
@@ -1380,30 +1207,49 @@ pub async fn refresh_outbound_shipments(db: &Database) {
         }
 
         // Parse the sheet columns.
-        let columns = SwagSheetColumns::parse(&values);
+        let headers = HeaderMap::from_values(&values);
 
         // Iterate over the rows.
-        for (row_index, row) in values.iter().enumerate() {
+        for (row_index, values_row) in values.iter().enumerate() {
             if row_index == 0 {
                 // Continue the loop since we were on the header row.
                 continue;
             } // End get header information.
 
+            let row: SwagSheetRow = match headers.deserialize(values_row) {
+                Ok(row) => row,
+                Err(e) => {
+                    println!("parsing row {} of swag sheet {} failed, skipping: {}", row_index + 1, sheet_id, e);
+                    continue;
+                }
+            };
+
             // Break the loop early if we reached an empty row.
-            if row[columns.email].is_empty() {
+            if row.email.is_empty() {
                 break;
             }
 
             // Parse the shipment out of the row information.
-            let (mut shipment, sent) = NewOutboundShipment::parse_from_row_with_columns(db, &columns, &row).await;
+            let (mut shipment, sent) = NewOutboundShipment::parse_from_sheet_row(db, &row).await;
 
             if !sent {
                 shipment.notes = format!("Automatically generated from the Google sheet {}", sheet_id);
-                let mut new_shipment = shipment.upsert(db).await;
+                let mut new_shipment = match shipment.upsert(db).await {
+                    Ok(s) => s,
+                    Err(e) => {
+                        println!("upserting shipment for {} failed, skipping: {}", shipment.email, e);
+                        continue;
+                    }
+                };
                 // Create or update the shipment from shippo.
-                new_shipment.create_or_get_shippo_shipment(db).await;
+                if let Err(e) = new_shipment.create_or_get_shippo_shipment(db).await {
+                    println!("creating shippo shipment for {} failed: {}", new_shipment.email, e);
+                    continue;
+                }
                 // Update airtable and the database again.
-                new_shipment.update(db).await;
+                if let Err(e) = new_shipment.update(db).await {
+                    println!("updating shipment for {} failed: {}", new_shipment.email, e);
+                }
             }
         }
     }
@@ -1412,7 +1258,7 @@ pub async fn refresh_outbound_shipments(db: &Database) {
     // This ensures that any one offs (that don't come from spreadsheets) are also updated.
     // TODO: if we decide to accept one-offs straight in airtable support that, but for now
     // we do not.
-    let shipments = OutboundShipments::get_from_db(&db);
+    let shipments = OutboundShipments::get_from_db(&db)?;
     for mut s in shipments {
         if let Some(existing) = s.get_existing_airtable_record().await {
             // Take the field from Airtable.
@@ -1420,10 +1266,17 @@ pub async fn refresh_outbound_shipments(db: &Database) {
         }
 
         // Update the shipment from shippo.
-        s.create_or_get_shippo_shipment(db).await;
+        if let Err(e) = s.create_or_get_shippo_shipment(db).await {
+            println!("updating shippo shipment for {} failed: {}", s.email, e);
+            continue;
+        }
         // Update airtable and the database again.
-        s.update(db).await;
+        if let Err(e) = s.update(db).await {
+            println!("updating shipment for {} failed: {}", s.email, e);
+        }
     }
+
+    Ok(())
 }
 
 // Get the sheadsheets that contain shipments.
@@ -1455,11 +1308,19 @@ pub async fn refresh_inbound_shipments(db: &Database) {
             tracking_link: record.fields.tracking_link,
         };
         new_shipment.expand().await;
-        let mut shipment = new_shipment.upsert_in_db(&db);
+        let mut shipment = match new_shipment.upsert_in_db(&db) {
+            Ok(s) => s,
+            Err(e) => {
+                println!("upserting inbound shipment {} failed, skipping: {}", new_shipment.tracking_number, e);
+                continue;
+            }
+        };
         if shipment.airtable_record_id.is_empty() {
             shipment.airtable_record_id = record.id;
         }
-        shipment.update(&db).await;
+        if let Err(e) = shipment.update(&db).await {
+            println!("updating inbound shipment {} failed: {}", shipment.tracking_number, e);
+        }
     }
 }
 
@@ -1475,8 +1336,96 @@ pub fn clean_address_string(s: &str) -> String {
 
 #[cfg(test)]
 mod tests {
+    use chrono::Utc;
+    use cio_testkit::FakeApis;
+    use serde_json::json;
+
+    use crate::airtable::{AIRTABLE_BASE_ID_SHIPMENTS, AIRTABLE_OUTBOUND_TABLE};
     use crate::db::Database;
-    use crate::shipments::{refresh_inbound_shipments, refresh_outbound_shipments, OutboundShipments};
+    use crate::shipments::{refresh_inbound_shipments, refresh_outbound_shipments, NewOutboundShipment, OutboundShipment, OutboundShipments};
+
+    /// The APIs are all fakes, but the shipment is saved in the database, so
+    /// this needs `CIO_DATABASE_URL` to point at a scratch one.
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_merged_fields_resolve_per_field() {
+        let fakes = FakeApis::start();
+        let _env = fakes.set_env();
+        let db = Database::new();
+
+        let shipment: NewOutboundShipment = serde_json::from_value(json!({
+            "name": "Jane Doe",
+            "carrier": "USPS",
+            "tracking_number": format!("9400{}", Utc::now().timestamp_nanos()),
+            "status": "Label created",
+            "label_print_status": "",
+        }))
+        .unwrap();
+        let mut record = shipment.upsert(&db).await.unwrap();
+
+        // Someone adds a note in Airtable, then Shippo tells us the package shipped.
+        fakes.airtable.edit(
+            AIRTABLE_BASE_ID_SHIPMENTS,
+            AIRTABLE_OUTBOUND_TABLE,
+            &record.airtable_record_id,
+            json!({"notes": "Leave it at the front desk"}),
+        );
+        record.status = "Shipped".to_string();
+        let record = record.update_in_db(&db).unwrap();
+
+        // Each side keeps the field it changed.
+        let existing_record = record.get_existing_airtable_record().await.unwrap();
+        let (resolved, from_airtable) = record.resolve_with_airtable(&db, &existing_record).await.unwrap();
+        assert!(from_airtable);
+        assert_eq!(resolved.status, "Shipped");
+        assert_eq!(resolved.notes, "Leave it at the front desk");
+
+        // Without the time of the last sync, the database wins.
+        let mut never_synced = record.clone();
+        never_synced.airtable_last_synced_at = None;
+        let (resolved, from_airtable) = never_synced.resolve_with_airtable(&db, &existing_record).await.unwrap();
+        assert!(!from_airtable);
+        assert_eq!(resolved.status, "Shipped");
+        assert_eq!(resolved.notes, "");
+    }
+
+    /// The APIs are all fakes, but the shipment is saved in the database, so
+    /// this needs `CIO_DATABASE_URL` to point at a scratch one.
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_database_edit_after_airtable_edit_is_synced() {
+        let fakes = FakeApis::start();
+        let _env = fakes.set_env();
+        let db = Database::new();
+
+        let shipment: NewOutboundShipment = serde_json::from_value(json!({
+            "name": "Jane Doe",
+            "carrier": "USPS",
+            "tracking_number": format!("9400{}", Utc::now().timestamp_nanos()),
+            "status": "Label created",
+            "label_print_status": "",
+        }))
+        .unwrap();
+        let record = shipment.upsert(&db).await.unwrap();
+
+        // Someone adds a note in Airtable, and the sync saves it to the database.
+        fakes.airtable.edit(
+            AIRTABLE_BASE_ID_SHIPMENTS,
+            AIRTABLE_OUTBOUND_TABLE,
+            &record.airtable_record_id,
+            json!({"notes": "Leave it at the front desk"}),
+        );
+        OutboundShipments(vec![record.clone()]).update_airtable(&db).await.unwrap();
+        let mut record = OutboundShipment::get_by_id(&db, record.id).unwrap();
+        assert_eq!(record.notes, "Leave it at the front desk");
+
+        // Then the note is changed in the database only, which is not a conflict,
+        // so the next sync sends it to Airtable.
+        record.notes = "Ring the bell".to_string();
+        let record = record.update_in_db(&db).unwrap();
+        OutboundShipments(vec![record.clone()]).update_airtable(&db).await.unwrap();
+
+        assert_eq!(OutboundShipment::get_by_id(&db, record.id).unwrap().notes, "Ring the bell");
+        assert_eq!(record.get_existing_airtable_record().await.unwrap().fields.notes, "Ring the bell");
+    }
 
     #[ignore]
     #[tokio::test(flavor = "multi_thread")]
@@ -1491,7 +1440,7 @@ mod tests {
     async fn test_shipments() {
         let db = Database::new();
 
-        refresh_outbound_shipments(&db).await;
+        refresh_outbound_shipments(&db).await.unwrap();
         refresh_inbound_shipments(&db).await;
     }
 }
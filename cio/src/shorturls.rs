@@ -9,13 +9,13 @@ use crate::templates::{generate_nginx_and_terraform_files_for_shorturls, generat
 use crate::utils::{authenticate_github_jwt, github_org, DOMAIN, GSUITE_DOMAIN};
 
 /// Generate the files for the GitHub repository short URLs.
-pub async fn generate_shorturls_for_repos(db: &Database, repo: &Repository) {
+pub async fn generate_shorturls_for_repos(db: &Database, repo: &Repository) -> Result<(), crate::Error> {
     let subdomain = "git";
     // Initialize the array of links.
     let mut links: Vec<ShortUrl> = Default::default();
 
     // Get the github repos from the database.
-    let repos = GithubRepos::get_from_db(db);
+    let repos = GithubRepos::get_from_db(db)?;
 
     // Create the array of links.
     for repo in repos {
@@ -35,16 +35,18 @@ pub async fn generate_shorturls_for_repos(db: &Database, repo: &Repository) {
 
     // Generate the files for the links.
     generate_nginx_and_terraform_files_for_shorturls(repo, links.clone()).await;
+
+    Ok(())
 }
 
 /// Generate the files for the RFD short URLs.
-pub async fn generate_shorturls_for_rfds(db: &Database, repo: &Repository) {
+pub async fn generate_shorturls_for_rfds(db: &Database, repo: &Repository) -> Result<(), crate::Error> {
     let subdomain = "rfd";
     // Initialize the array of links.
     let mut links: Vec<ShortUrl> = Default::default();
 
     // Get the rfds from the database.
-    let rfds = RFDs::get_from_db(db);
+    let rfds = RFDs::get_from_db(db)?;
     for rfd in rfds {
         let mut link = ShortUrl {
             name: rfd.number.to_string(),
@@ -66,16 +68,18 @@ pub async fn generate_shorturls_for_rfds(db: &Database, repo: &Repository) {
 
     // Generate the files for the links.
     generate_nginx_and_terraform_files_for_shorturls(repo, links.clone()).await;
+
+    Ok(())
 }
 
 /// Generate the files for the configs links.
-pub async fn generate_shorturls_for_configs_links(db: &Database, repo: &Repository) {
+pub async fn generate_shorturls_for_configs_links(db: &Database, repo: &Repository) -> Result<(), crate::Error> {
     let subdomain = "corp";
     // Initialize the array of links.
     let mut links: Vec<ShortUrl> = Default::default();
 
     // Get the config.
-    let configs_links = Links::get_from_db(db);
+    let configs_links = Links::get_from_db(db)?;
 
     // Create the array of links.
     for link in configs_links {
@@ -104,6 +108,8 @@ pub async fn generate_shorturls_for_configs_links(db: &Database, repo: &Reposito
 
     // Generate the files for the links.
     generate_nginx_and_terraform_files_for_shorturls(repo, links).await;
+
+    Ok(())
 }
 
 /// Generate the cloudflare terraform files for the tailscale devices.
@@ -169,16 +175,18 @@ pub async fn generate_dns_for_tailscale_devices(repo: &Repository) {
 }
 
 /// Update all the short URLs and DNS.
-pub async fn refresh_shorturls() {
+pub async fn refresh_shorturls() -> Result<(), crate::Error> {
     let github = authenticate_github_jwt();
     let repo = github.repo(github_org(), "configs");
 
     let db = Database::new();
 
-    generate_shorturls_for_repos(&db, &repo).await;
-    generate_shorturls_for_rfds(&db, &repo).await;
-    generate_shorturls_for_configs_links(&db, &repo).await;
+    generate_shorturls_for_repos(&db, &repo).await?;
+    generate_shorturls_for_rfds(&db, &repo).await?;
+    generate_shorturls_for_configs_links(&db, &repo).await?;
     generate_dns_for_tailscale_devices(&repo).await;
+
+    Ok(())
 }
 
 /// The data type for a short URL that will be used in a template.
@@ -196,14 +204,3 @@ pub struct ShortUrl {
     #[serde(default, skip_serializing_if = "String::is_empty")]
     pub discussion: String,
 }
-
-#[cfg(test)]
-mod tests {
-    use crate::shorturls::refresh_shorturls;
-
-    #[ignore]
-    #[tokio::test(flavor = "multi_thread")]
-    async fn test_cron_shorturls() {
-        refresh_shorturls().await;
-    }
-}
@@ -1,5 +1,3 @@
-use std::env;
-
 use async_trait::async_trait;
 use barcoders::generators::image::*;
 use barcoders::generators::svg::*;
@@ -10,13 +8,13 @@ use image::{DynamicImage, ImageFormat};
 use lopdf::content::{Content, Operation};
 use lopdf::{Document, Object, Stream, StringFormat};
 use macros::db;
-use reqwest::StatusCode;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::airtable::{AIRTABLE_BARCODE_SCANS_TABLE, AIRTABLE_BASE_ID_SWAG, AIRTABLE_SWAG_INVENTORY_ITEMS_TABLE, AIRTABLE_SWAG_ITEMS_TABLE};
-use crate::core::UpdateAirtableRecord;
+use crate::core::{UpdateAirtableRecord, Validate};
 use crate::db::Database;
+use crate::print_jobs::{send_print_request, send_zpl_print_request, PrintJobSource, PrintJobStatus};
 use crate::schema::{barcode_scans, swag_inventory_items, swag_items};
 use crate::utils::get_gsuite_token;
 
@@ -75,9 +73,17 @@ pub async fn refresh_swag_items() {
     for item_record in results {
         let item: NewSwagItem = item_record.fields.into();
 
-        let mut db_item = item.upsert_in_db(&db);
+        let mut db_item = match item.upsert_in_db(&db) {
+            Ok(i) => i,
+            Err(e) => {
+                println!("upserting swag item {} failed, skipping: {}", item.name, e);
+                continue;
+            }
+        };
         db_item.airtable_record_id = item_record.id.to_string();
-        db_item.update(&db).await;
+        if let Err(e) = db_item.update(&db).await {
+            println!("updating swag item {} failed: {}", db_item.name, e);
+        }
     }
 }
 
@@ -89,6 +95,7 @@ pub async fn refresh_swag_items() {
         "item" = "String",
         "size" = "String",
     },
+    airtable_fields = ["print_barcode_label_quantity"],
 }]
 #[derive(Debug, Insertable, AsChangeset, PartialEq, Clone, JsonSchema, Deserialize, Serialize)]
 #[table_name = "swag_inventory_items"]
@@ -124,6 +131,13 @@ pub struct NewSwagInventoryItem {
     /// This is populated by Airtable.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub link_to_item: Vec<String>,
+
+    /// The id of the latest printy job for the label.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub label_print_job_id: String,
+    /// Whether the label printed, as reported by printy.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub label_print_status: String,
 }
 
 /// Implement updating the Airtable record for a SwagInventoryItem.
@@ -136,9 +150,6 @@ impl UpdateAirtableRecord<SwagInventoryItem> for SwagInventoryItem {
 
         // This is a funtion in Airtable so we can't update it.
         self.name = "".to_string();
-
-        // This is set in airtable so we need to keep it.
-        self.print_barcode_label_quantity = record.print_barcode_label_quantity;
     }
 }
 
@@ -313,6 +324,28 @@ impl NewSwagInventoryItem {
     }
 }
 
+/// Check a swag inventory item before the API writes it.
+impl Validate for NewSwagInventoryItem {
+    fn validation_errors(&self) -> Vec<String> {
+        let mut problems: Vec<String> = Default::default();
+
+        if self.item.trim().is_empty() {
+            problems.push("item cannot be empty".to_string());
+        }
+        if self.size.trim().is_empty() {
+            problems.push("size cannot be empty".to_string());
+        }
+        if self.current_stock < 0 {
+            problems.push("current_stock cannot be negative".to_string());
+        }
+        if self.print_barcode_label_quantity < 0 {
+            problems.push("print_barcode_label_quantity cannot be negative".to_string());
+        }
+
+        problems
+    }
+}
+
 /// A request to print labels.
 #[derive(Debug, Clone, Default, JsonSchema, Deserialize, Serialize)]
 pub struct PrintLabelsRequest {
@@ -320,39 +353,136 @@ pub struct PrintLabelsRequest {
     pub url: String,
     #[serde(default)]
     pub quantity: i32,
+    /// The record the label is for, so printy can report back whether it printed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<PrintJobSource>,
+    /// The id to give the job, see `PrintJobSource::new_job_id`. printy picks
+    /// one if it is empty.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub job_id: String,
+}
+
+/// A request to print a label written in ZPL, the language of our Zebra
+/// printer.
+#[derive(Debug, Clone, Default, JsonSchema, Deserialize, Serialize)]
+pub struct PrintZplRequest {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub zpl: String,
+    #[serde(default)]
+    pub quantity: i32,
+    /// The record the label is for, so printy can report back whether it printed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<PrintJobSource>,
+    /// The id to give the job, see `PrintJobSource::new_job_id`. printy picks
+    /// one if it is empty.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub job_id: String,
 }
 
 impl SwagInventoryItem {
-    /// Send the label to our printer.
-    pub async fn print_label(&self) {
-        if self.barcode_pdf_label.trim().is_empty() {
+    /// Send the label to our printer, and keep track of the print job.
+    ///
+    /// The label is sent as ZPL so the printer draws the barcode itself, which
+    /// keeps it sharp. If printy can't take the ZPL, we print the PDF label
+    /// instead.
+    ///
+    /// The job is saved to the database before it is sent, so printy can't
+    /// report on it before we know about it.
+    pub async fn print_label(&mut self, db: &Database) -> Result<(), crate::Error> {
+        if self.barcode.trim().is_empty() && self.barcode_pdf_label.trim().is_empty() {
             // Return early.
-            return;
+            return Ok(());
         }
-        let mut printer_url = env::var("PRINTER_URL").unwrap().trim_end_matches('/').to_string();
-        printer_url = format!("{}/zebra", printer_url);
-        let client = reqwest::Client::new();
-        let resp = client
-            .post(&printer_url)
-            .body(
-                json!(PrintLabelsRequest {
+
+        let source = PrintJobSource {
+            table: "swag_inventory_items".to_string(),
+            id: self.id,
+        };
+        self.label_print_job_id = source.new_job_id();
+        self.label_print_status = PrintJobStatus::Queued.to_string();
+        *self = self.update_in_db(db)?;
+
+        let mut result = Err(crate::Error::Print(format!("{} does not have a PDF label", self.name)));
+        if !self.barcode.trim().is_empty() {
+            let request = PrintZplRequest {
+                zpl: generate_zpl_barcode_label(&self.barcode, &self.item, &self.size),
+                quantity: self.print_barcode_label_quantity,
+                source: Some(source.clone()),
+                job_id: self.label_print_job_id.to_string(),
+            };
+            result = send_zpl_print_request("zebra", &request).await;
+        }
+
+        if let Err(e) = &result {
+            if !self.barcode_pdf_label.trim().is_empty() {
+                if !self.barcode.trim().is_empty() {
+                    println!("printing the ZPL label for {} failed, printing the PDF label instead: {}", self.name, e);
+                }
+
+                let request = PrintLabelsRequest {
                     url: self.barcode_pdf_label.to_string(),
-                    quantity: self.print_barcode_label_quantity
-                })
-                .to_string(),
-            )
-            .send()
-            .await
-            .unwrap();
-        match resp.status() {
-            StatusCode::ACCEPTED => (),
-            s => {
-                panic!("[print]: status_code: {}, body: {}", s, resp.text().await.unwrap());
+                    quantity: self.print_barcode_label_quantity,
+                    source: Some(source),
+                    job_id: self.label_print_job_id.to_string(),
+                };
+                result = send_print_request("zebra", &request).await;
             }
-        };
+        }
+
+        if let Err(e) = result {
+            self.label_print_status = format!("{}: {}", PrintJobStatus::Failed, e);
+            *self = self.update_in_db(db)?;
+            return Err(e);
+        }
+
+        // printy could have reported on the job already.
+        self.label_print_status = SwagInventoryItem::get_by_id(db, self.id)?.label_print_status;
+        Ok(())
     }
 }
 
+/// The width of our barcode labels in printer dots, 3in at 203dpi.
+const ZPL_LABEL_WIDTH: usize = 609;
+/// The height of our barcode labels in printer dots, 2in at 203dpi.
+const ZPL_LABEL_HEIGHT: usize = 406;
+/// The width of the narrowest bar of the barcode in printer dots.
+const ZPL_MODULE_WIDTH: usize = 2;
+
+/// Return the ZPL for the barcode label of an item: the item name and size,
+/// and the barcode as a Code 128 the printer draws at its own resolution,
+/// with the barcode text under it.
+///
+/// This is the same label as `generate_pdf_barcode_label`, without the logo.
+pub fn generate_zpl_barcode_label(barcode: &str, item: &str, size: &str) -> String {
+    let margin = 20;
+    let text_width = ZPL_LABEL_WIDTH - (margin * 2);
+
+    // Center the barcode. Code 128 takes 11 modules per character, plus the
+    // start and check characters, and 13 modules for the stop pattern.
+    let barcode_width = (11 * (barcode.chars().count() + 2) + 13) * ZPL_MODULE_WIDTH;
+    let barcode_x = ZPL_LABEL_WIDTH.saturating_sub(barcode_width) / 2;
+
+    [
+        "^XA".to_string(),
+        // Read the fields as UTF-8.
+        "^CI28".to_string(),
+        format!("^PW{}", ZPL_LABEL_WIDTH),
+        format!("^LL{}", ZPL_LABEL_HEIGHT),
+        // The item name, wrapped onto two lines if it is long.
+        format!("^FO{},{}^A0N,36,36^FB{},2,0,C^FH^FD{}^FS", margin, margin, text_width, zpl_field_data(item)),
+        format!("^FO{},{}^A0N,30,30^FB{},1,0,C^FH^FDSize: {}^FS", margin, 110, text_width, zpl_field_data(size)),
+        format!("^FO{},{}^BY{}^BCN,150,Y,N,N^FH^FD{}^FS", barcode_x, 170, ZPL_MODULE_WIDTH, zpl_field_data(barcode)),
+        "^XZ".to_string(),
+    ]
+    .join("\n")
+}
+
+/// Escape the characters ZPL would read as commands in field data, using the
+/// hex escapes `^FH` turns on.
+fn zpl_field_data(s: &str) -> String {
+    s.replace('_', "_5F").replace('^', "_5E").replace('~', "_7E")
+}
+
 pub fn image_to_pdf_object(mut doc: Document, png_bytes: &[u8]) -> (Document, Stream, crate::png::PngInfo) {
     // Insert our barcode image.
     let info = crate::png::get_info(png_bytes);
@@ -450,9 +580,17 @@ pub async fn refresh_swag_inventory_items() {
         let mut inventory_item: NewSwagInventoryItem = inventory_item_record.fields.into();
         inventory_item.expand(&drive_client).await;
 
-        let mut db_inventory_item = inventory_item.upsert_in_db(&db);
+        let mut db_inventory_item = match inventory_item.upsert_in_db(&db) {
+            Ok(i) => i,
+            Err(e) => {
+                println!("upserting swag inventory item {} failed, skipping: {}", inventory_item.name, e);
+                continue;
+            }
+        };
         db_inventory_item.airtable_record_id = inventory_item_record.id.to_string();
-        db_inventory_item.update(&db).await;
+        if let Err(e) = db_inventory_item.update(&db).await {
+            println!("updating swag inventory item {} failed: {}", db_inventory_item.name, e);
+        }
     }
 }
 
@@ -517,7 +655,10 @@ impl BarcodeScan {
                 // in the database.
                 swag_inventory_item.current_stock -= 1;
                 // Update the database.
-                swag_inventory_item.update(&db).await;
+                if let Err(e) = swag_inventory_item.update(&db).await {
+                    println!("updating stock for {} failed: {}", swag_inventory_item.name, e);
+                    return;
+                }
                 println!("Subtracted one from {} stock, we now have {}", swag_inventory_item.name, swag_inventory_item.current_stock);
 
                 // Now add our barcode scan to the barcode scans database.
@@ -531,7 +672,9 @@ impl BarcodeScan {
                 };
 
                 // Add our barcode scan to the database.
-                new_barcode_scan.upsert(&db).await;
+                if let Err(e) = new_barcode_scan.upsert(&db).await {
+                    println!("saving barcode scan {} failed: {}", barcode, e);
+                }
             }
             Err(e) => println!("could not find inventory item with barcode {}: {}", barcode, e),
         }
@@ -540,25 +683,18 @@ impl BarcodeScan {
 
 #[cfg(test)]
 mod tests {
-    use crate::db::Database;
-    use crate::swag_inventory::{refresh_swag_inventory_items, refresh_swag_items, BarcodeScans};
-
-    #[ignore]
-    #[tokio::test(flavor = "multi_thread")]
-    async fn test_cron_swag_items() {
-        refresh_swag_items().await;
-    }
-
-    #[ignore]
-    #[tokio::test(flavor = "multi_thread")]
-    async fn test_cron_swag_inventory_items() {
-        refresh_swag_inventory_items().await;
-    }
-
-    #[ignore]
-    #[tokio::test(flavor = "multi_thread")]
-    async fn test_cron_refresh_barcode_scans() {
-        let db = Database::new();
-        BarcodeScans::get_from_db(&db).update_airtable().await;
+    use crate::swag_inventory::generate_zpl_barcode_label;
+
+    #[test]
+    fn test_generate_zpl_barcode_label() {
+        let zpl = generate_zpl_barcode_label("00000UHOODBLK", "Unisex Hoodie ^ Black", "L");
+        let lines: Vec<&str> = zpl.lines().collect();
+
+        assert_eq!(lines.first(), Some(&"^XA"));
+        assert_eq!(lines.last(), Some(&"^XZ"));
+        assert!(lines.contains(&"^FO20,20^A0N,36,36^FB569,2,0,C^FH^FDUnisex Hoodie _5E Black^FS"));
+        assert!(lines.contains(&"^FO20,110^A0N,30,30^FB569,1,0,C^FH^FDSize: L^FS"));
+        // 13 characters of Code 128 at 2 dots a module are 356 dots wide.
+        assert!(lines.contains(&"^FO126,170^BY2^BCN,150,Y,N,N^FH^FD00000UHOODBLK^FS"));
     }
 }
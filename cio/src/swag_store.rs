@@ -48,31 +48,45 @@ impl Order {
         let mut contents = String::new();
         for item in &self.items {
             // Get the swag item from the database.
-            let swag_inventory_item = SwagInventoryItem::get_by_id(&db, item.id);
+            let swag_inventory_item = match SwagInventoryItem::get_by_id(&db, item.id) {
+                Ok(s) => s,
+                Err(e) => {
+                    println!("getting swag inventory item {} for order contents failed: {}", item.id, e);
+                    continue;
+                }
+            };
             contents = format!("{} x {}, Size: {}\n{}", item.quantity, swag_inventory_item.item, swag_inventory_item.size, contents);
         }
 
         contents.trim().to_string()
     }
 
-    pub async fn create_shipment_for_order(&self, db: &Database) {
+    pub async fn create_shipment_for_order(&self, db: &Database) -> Result<(), crate::Error> {
         // Convert the shipment to an order.
         let shipment: NewOutboundShipment = self.clone().into();
         // Add the shipment to the database.
-        let mut new_shipment = shipment.upsert(db).await;
+        let mut new_shipment = shipment.upsert(db).await?;
         // Create or update the shipment from shippo.
-        new_shipment.create_or_get_shippo_shipment(db).await;
+        new_shipment.create_or_get_shippo_shipment(db).await?;
         // Update airtable and the database again.
-        new_shipment.update(db).await;
+        new_shipment.update(db).await?;
         // Send an email to the person that we recieved their order and what they are
         // getting.
-        new_shipment.send_email_to_recipient_pre_shipping().await;
+        new_shipment.send_email_to_recipient_pre_shipping(db).await;
+
+        Ok(())
     }
 
     pub async fn subtract_order_from_inventory(&self, db: &Database) {
         for item in &self.items {
             // Get the swag item from the database.
-            let mut swag_inventory_item = SwagInventoryItem::get_by_id(&db, item.id);
+            let mut swag_inventory_item = match SwagInventoryItem::get_by_id(&db, item.id) {
+                Ok(s) => s,
+                Err(e) => {
+                    println!("getting swag inventory item {} to subtract order failed: {}", item.id, e);
+                    continue;
+                }
+            };
             swag_inventory_item.current_stock -= item.quantity;
             if swag_inventory_item.current_stock < 0 {
                 // TODO: Hopefully this never happens. The store code _should_ only allow people
@@ -84,11 +98,13 @@ impl Order {
                 "subtracted {} from current stock of {} making the total now {}",
                 item.quantity, swag_inventory_item.name, swag_inventory_item.current_stock
             );
-            swag_inventory_item.update(db).await;
+            if let Err(e) = swag_inventory_item.update(db).await {
+                println!("updating swag inventory item {} failed: {}", swag_inventory_item.name, e);
+            }
         }
     }
 
-    pub async fn do_order(&self, db: &Database) {
+    pub async fn do_order(&self, db: &Database) -> Result<(), crate::Error> {
         // If their email is empty return early.
         if self.email.is_empty()
             || self.street_1.is_empty()
@@ -101,11 +117,13 @@ impl Order {
         {
             // This should not happen since we verify on the client side we have these
             // things.
-            return;
+            return Ok(());
         }
 
-        self.create_shipment_for_order(db).await;
+        self.create_shipment_for_order(db).await?;
         self.subtract_order_from_inventory(db).await;
+
+        Ok(())
     }
 }
 
@@ -146,6 +164,62 @@ impl From<Order> for NewOutboundShipment {
             geocode_cache: Default::default(),
             local_pickup: false,
             link_to_package_pickup: Default::default(),
+            label_print_job_id: Default::default(),
+            label_print_status: Default::default(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use cio_testkit::FakeApis;
+    use diesel::prelude::*;
+
+    use crate::db::Database;
+    use crate::schema::outbound_shipments;
+    use crate::shipments::OutboundShipment;
+    use crate::swag_store::{Order, OrderItem};
+
+    /// The APIs are all fakes, but the shipment is still saved in the
+    /// database, so this needs `CIO_DATABASE_URL` to point at a scratch one.
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_do_order() {
+        let fakes = FakeApis::start();
+        let _env = fakes.set_env();
+        let db = Database::new();
+
+        let order = Order {
+            name: "Jane Doe".to_string(),
+            email: "jane@example.com".to_string(),
+            phone: "+14155550100".to_string(),
+            street_1: "1 Infinite Loop".to_string(),
+            street_2: "".to_string(),
+            city: "Cupertino".to_string(),
+            state: "CA".to_string(),
+            zipcode: "95014".to_string(),
+            country: "US".to_string(),
+            notes: "".to_string(),
+            items: vec![OrderItem { id: 1, quantity: 1 }],
+        };
+        order.do_order(&db).await.unwrap();
+
+        assert_eq!(fakes.shippo.shipments().len(), 1);
+        let labels = fakes.shippo.transactions();
+        assert_eq!(labels.len(), 1);
+
+        let jobs = fakes.printer.jobs();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].printer, "rollo");
+        assert_eq!(jobs[0].url, labels[0]["label_url"].as_str().unwrap());
+
+        // The shipment has the job, and saved it before sending it to the printer.
+        let shipment = outbound_shipments::table
+            .filter(outbound_shipments::dsl::label_print_job_id.eq(&jobs[0].id))
+            .first::<OutboundShipment>(&db.conn())
+            .unwrap();
+        assert_eq!(shipment.email, order.email);
+        assert!(jobs[0].id.starts_with(&format!("outbound_shipments-{}-", shipment.id)));
+
+        assert!(fakes.sendgrid.sent().iter().any(|m| m.to.contains(&order.email)));
+    }
+}
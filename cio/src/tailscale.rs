@@ -26,14 +26,3 @@ pub async fn cleanup_old_tailscale_devices() {
         }
     }
 }
-
-#[cfg(test)]
-mod tests {
-    use crate::tailscale::cleanup_old_tailscale_devices;
-
-    #[ignore]
-    #[tokio::test(flavor = "multi_thread")]
-    async fn test_cron_tailscale() {
-        cleanup_old_tailscale_devices().await;
-    }
-}
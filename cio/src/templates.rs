@@ -49,9 +49,9 @@ struct GitHubTeamMembers {
  *
  * This function uses the users.toml and the groups.toml file in the configs repo for information.
  */
-pub async fn generate_terraform_files_for_okta(github: &Github, db: &Database) {
-    let users = Users::get_from_db(db);
-    let groups = Groups::get_from_db(db);
+pub async fn generate_terraform_files_for_okta(github: &Github, db: &Database) -> Result<(), crate::Error> {
+    let users = Users::get_from_db(db)?;
+    let groups = Groups::get_from_db(db)?;
 
     let repo = github.repo(github_org(), "configs");
     let r = repo.get().await.unwrap();
@@ -78,6 +78,8 @@ pub async fn generate_terraform_files_for_okta(github: &Github, db: &Database) {
     let groups_file = format!("{}/generated.groups.tf", okta_path);
 
     create_or_update_file_in_github_repo(&repo, &r.default_branch, &groups_file, groups_rendered.as_bytes().to_vec()).await;
+
+    Ok(())
 }
 
 /**
@@ -88,8 +90,8 @@ pub async fn generate_terraform_files_for_okta(github: &Github, db: &Database) {
  *
  * This function uses the users.toml file in the configs repo for information.
  */
-pub async fn generate_terraform_files_for_aws_and_github(github: &Github, db: &Database) {
-    let users = Users::get_from_db(db);
+pub async fn generate_terraform_files_for_aws_and_github(github: &Github, db: &Database) -> Result<(), crate::Error> {
+    let users = Users::get_from_db(db)?;
 
     let repo = github.repo(github_org(), "configs");
     let r = repo.get().await.unwrap();
@@ -140,6 +142,8 @@ pub async fn generate_terraform_files_for_aws_and_github(github: &Github, db: &D
 
         create_or_update_file_in_github_repo(&repo, &r.default_branch, &file, rendered.as_bytes().to_vec()).await;
     }
+
+    Ok(())
 }
 
 /// Generate nginx and terraform files for shorturls.
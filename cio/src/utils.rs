@@ -73,6 +73,7 @@ pub async fn get_gsuite_token(subject: &str) -> AccessToken {
             "https://www.googleapis.com/auth/admin.directory.group",
             "https://www.googleapis.com/auth/admin.directory.resource.calendar",
             "https://www.googleapis.com/auth/admin.directory.user",
+            "https://www.googleapis.com/auth/admin.datatransfer",
             "https://www.googleapis.com/auth/calendar",
             "https://www.googleapis.com/auth/apps.groups.settings",
             "https://www.googleapis.com/auth/spreadsheets",
@@ -183,11 +184,11 @@ pub async fn list_all_github_repos(github: &Github) -> Vec<NewRepo> {
 }
 
 /// Sync the repos with our database.
-pub async fn refresh_db_github_repos(db: &Database, github: &Github) {
+pub async fn refresh_db_github_repos(db: &Database, github: &Github) -> Result<(), crate::Error> {
     let github_repos = list_all_github_repos(github).await;
 
     // Get all the repos.
-    let db_repos = GithubRepos::get_from_db(db);
+    let db_repos = GithubRepos::get_from_db(db)?;
 
     // Create a BTreeMap
     let mut repo_map: BTreeMap<String, GithubRepo> = Default::default();
@@ -197,18 +198,25 @@ pub async fn refresh_db_github_repos(db: &Database, github: &Github) {
 
     // Sync github_repos.
     for github_repo in github_repos {
-        github_repo.upsert(db).await;
-
         // Remove the repo from the map.
+        // We do this first so that a repo that fails to sync is not deleted below.
         repo_map.remove(&github_repo.name);
+
+        if let Err(e) = github_repo.upsert(db).await {
+            println!("upserting github repo {} failed: {}", github_repo.name, e);
+        }
     }
 
     // Remove any repos that should no longer be in the database.
     // This is found by the remaining repos that are in the map since we removed
     // the existing repos from the map above.
     for (_, repo) in repo_map {
-        repo.delete(db).await;
+        if let Err(e) = repo.delete(db).await {
+            println!("deleting github repo {} failed: {}", repo.name, e);
+        }
     }
+
+    Ok(())
 }
 
 /// Get a files content from a repo.
@@ -387,23 +395,3 @@ impl SliceExt for Vec<u8> {
 pub fn default_date() -> chrono::naive::NaiveDate {
     chrono::naive::NaiveDate::parse_from_str("1970-01-01", "%Y-%m-%d").unwrap()
 }
-
-#[cfg(test)]
-mod tests {
-    use crate::db::Database;
-    use crate::models::GithubRepos;
-    use crate::utils::{authenticate_github_jwt, refresh_db_github_repos};
-
-    #[ignore]
-    #[tokio::test(flavor = "multi_thread")]
-    async fn test_cron_github_repos() {
-        let github = authenticate_github_jwt();
-
-        // Initialize our database.
-        let db = Database::new();
-
-        refresh_db_github_repos(&db, &github).await;
-
-        GithubRepos::get_from_db(&db).update_airtable().await;
-    }
-}
@@ -48,14 +48,18 @@ const ENDPOINT: &str = "https://na4.docusign.net/restapi/v2.1/";
 pub struct DocuSign {
     token: String,
     jwt_config: JWTConfig,
+    base_url: String,
 
-    client: Arc<Client>,
+    client: Arc<cio_http::Client>,
 }
 
 impl DocuSign {
     /// Create a new DocuSign client struct. It takes a type that can convert into
     /// an &str (`String` or `Vec<u8>` for example). As long as the function is
     /// given a valid API key your requests will work.
+    /// The client, including the requests for its token and webhook, talks to
+    /// the base URL in the DOCUSIGN_BASE_URL env variable if it is set, like a
+    /// fake server in tests.
     pub async fn new<I, K, B, P, A>(account_id: I, rsa_key: K, integration_key: B, key_pair_id: P, api_username: A) -> Self
     where
         I: ToString,
@@ -64,28 +68,33 @@ impl DocuSign {
         P: ToString,
         A: ToString,
     {
+        let jwt_config = JWTConfig {
+            account_id: account_id.to_string(),
+            private_key: rsa_key.to_string(),
+            integrator_key: integration_key.to_string(),
+            key_pair_id: key_pair_id.to_string(),
+            api_username: api_username.to_string(),
+            is_demo: false,
+        };
+
+        // This is super hacky and a work arouind since there is no way to
+        // auth without using the browser.
+        println!("docusign consent URL: {}", jwt_config.user_consent_url());
+        let token = jwt_config.get_access_token().await.access_token;
+
+        DocuSign::new_with_jwt_config(jwt_config, token).await
+    }
+
+    async fn new_with_jwt_config(jwt_config: JWTConfig, token: String) -> Self {
         let client = Client::builder().build();
         match client {
             Ok(c) => {
-                let jwt_config = JWTConfig {
-                    account_id: account_id.to_string(),
-                    private_key: rsa_key.to_string(),
-                    integrator_key: integration_key.to_string(),
-                    key_pair_id: key_pair_id.to_string(),
-                    api_username: api_username.to_string(),
-                    is_demo: false,
-                };
-
-                // This is super hacky and a work arouind since there is no way to
-                // auth without using the browser.
-                println!("docusign consent URL: {}", jwt_config.user_consent_url());
-                let token = jwt_config.get_access_token().await;
-
                 let ds = DocuSign {
                     jwt_config,
                     token,
+                    base_url: env::var("DOCUSIGN_BASE_URL").unwrap_or_default(),
 
-                    client: Arc::new(c),
+                    client: Arc::new(cio_http::Client::new(c)),
                 };
 
                 // Create our webhook (this will make sure one doesn't already exist as well).
@@ -112,11 +121,28 @@ impl DocuSign {
         DocuSign::new(account_id, rsa_key, integration_key, key_pair_id, api_username).await
     }
 
+    /// Create a new DocuSign client struct from environment variables with an
+    /// access token we already have, so if you are storing it in a database you
+    /// do not need to get a new one every time. The token must still be valid.
+    pub async fn new_from_env_with_token<T>(token: T) -> Self
+    where
+        T: ToString,
+    {
+        DocuSign::new_with_jwt_config(JWTConfig::from_env(), token.to_string()).await
+    }
+
+    /// Get a new access token with the JWT grant, using the credentials from
+    /// environment variables. DocuSign access tokens are valid for an hour and
+    /// cannot be refreshed, so use this to get a new one instead.
+    pub async fn get_access_token_from_env() -> AccessToken {
+        JWTConfig::from_env().get_access_token().await
+    }
+
     fn request<B>(&self, method: Method, path: &str, body: B, query: Option<&[(&str, &str)]>) -> Request
     where
         B: Serialize,
     {
-        let base = Url::parse(ENDPOINT).unwrap();
+        let base = Url::parse(&cio_http::rebase(ENDPOINT, &self.base_url)).unwrap();
         let url = base.join(path).unwrap();
 
         let bt = format!("Bearer {}", self.token);
@@ -675,6 +701,17 @@ pub struct JWTConfig {
 }
 
 impl JWTConfig {
+    fn from_env() -> Self {
+        JWTConfig {
+            account_id: env::var("DOCUSIGN_ACCOUNT_ID").unwrap(),
+            private_key: env::var("DOCUSIGN_RSA_KEY").unwrap(),
+            integrator_key: env::var("DOCUSIGN_INTEGRATION_KEY").unwrap(),
+            key_pair_id: env::var("DOCUSIGN_KEY_PAIR_ID").unwrap(),
+            api_username: env::var("DOCUSIGN_API_USERNAME").unwrap(),
+            is_demo: false,
+        }
+    }
+
     /// UserConsentURL creates a url allowing a user to consent to impersonation
     /// https://developers.docusign.com/esign-rest-api/guides/authentication/obtaining-consent#individual-consent
     fn user_consent_url(&self) -> String {
@@ -722,19 +759,18 @@ impl JWTConfig {
         t.as_str().to_string()
     }
 
-    async fn get_access_token(&self) -> String {
+    async fn get_access_token(&self) -> AccessToken {
         let jwt_token = self.get_jwt_token();
 
         let mut endpoint = "https://account.docusign.com/oauth/token";
         if self.is_demo {
             endpoint = "https://account-d.docusign.com/oauth/token";
         }
+        let endpoint = cio_http::rebase(endpoint, &env::var("DOCUSIGN_BASE_URL").unwrap_or_default());
 
-        let client = reqwest::Client::new();
+        let client = cio_http::Client::new(reqwest::Client::new());
         let resp = client
-            .post(endpoint)
-            .form(&[("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"), ("assertion", &jwt_token)])
-            .send()
+            .send(client.post(&endpoint).form(&[("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"), ("assertion", &jwt_token)]))
             .await
             .unwrap();
 
@@ -746,8 +782,7 @@ impl JWTConfig {
             }
         };
 
-        let t: AccessToken = resp.json().await.unwrap();
-        t.access_token
+        resp.json().await.unwrap()
     }
 }
 
@@ -44,12 +44,14 @@
  */
 #![allow(clippy::field_reassign_with_default)]
 use std::collections::HashMap;
+use std::env;
 use std::error;
 use std::fmt;
 use std::sync::Arc;
 use std::time::Duration;
 
 use bytes::Bytes;
+use futures_core::Stream;
 use reqwest::{header, Client, Method, Request, StatusCode, Url};
 use serde::{Deserialize, Serialize};
 use yup_oauth2::AccessToken;
@@ -57,25 +59,46 @@ use yup_oauth2::AccessToken;
 /// The endpoint for the Google Drive API.
 const ENDPOINT: &str = "https://www.googleapis.com/drive/v3/";
 
+/// How long streaming a file can take, from sending the request to reading
+/// the last byte.
+const STREAM_TIMEOUT: Duration = Duration::from_secs(4 * 60 * 60);
+
 /// Entrypoint for interacting with the Google Drive API.
 pub struct GoogleDrive {
     token: AccessToken,
+    base_url: String,
 
-    client: Arc<Client>,
+    client: Arc<cio_http::Client>,
 }
 
 impl GoogleDrive {
     /// Create a new Drive client struct. It takes a type that can convert into
     /// an &str (`String` or `Vec<u8>` for example). As long as the function is
     /// given a valid API Key and Secret your requests will work.
+    /// The client talks to the base URL in the DRIVE_BASE_URL env variable
+    /// if it is set, see `with_base_url`.
     pub fn new(token: AccessToken) -> Self {
         let client = Client::builder().timeout(Duration::from_secs(360)).build();
         match client {
-            Ok(c) => Self { token, client: Arc::new(c) },
+            Ok(c) => Self {
+                token,
+                base_url: env::var("DRIVE_BASE_URL").unwrap_or_default(),
+                client: Arc::new(cio_http::Client::new(c)),
+            },
             Err(e) => panic!("creating client failed: {:?}", e),
         }
     }
 
+    /// Send requests to another base URL, like a fake server in tests, instead
+    /// of the Google Drive API.
+    pub fn with_base_url<U>(mut self, base_url: U) -> Self
+    where
+        U: ToString,
+    {
+        self.base_url = base_url.to_string();
+        self
+    }
+
     /// Get the currently set authorization token.
     pub fn get_token(&self) -> &AccessToken {
         &self.token
@@ -89,7 +112,7 @@ impl GoogleDrive {
         let url = if !path.starts_with("http") {
             // Build the URL from our endpoint instead since a full URL was not
             // passed.
-            let base = Url::parse(ENDPOINT).unwrap();
+            let base = Url::parse(&cio_http::rebase(ENDPOINT, &self.base_url)).unwrap();
             base.join(&path).unwrap()
         } else {
             // Parse the full URL.
@@ -174,6 +197,37 @@ impl GoogleDrive {
         Ok(resp.bytes().await.unwrap())
     }
 
+    /// Start downloading a file stored on Google Drive by it's ID, without
+    /// reading it into memory. Use this for files too big to buffer, like
+    /// meeting recordings.
+    pub async fn download_file_stream_by_id(&self, id: &str) -> Result<FileDownload, APIError> {
+        // Build the request.
+        let mut request = self.request(
+            Method::GET,
+            format!("files/{}", id),
+            (),
+            Some(vec![("supportsAllDrives", "true".to_string()), ("alt", "media".to_string())]),
+            &[],
+            "",
+        );
+        // The client timeout covers reading the whole body, which takes a
+        // while for big files.
+        *request.timeout_mut() = Some(STREAM_TIMEOUT);
+
+        let resp = self.client.execute(request).await.unwrap();
+        match resp.status() {
+            StatusCode::OK => (),
+            s => {
+                return Err(APIError {
+                    status_code: s,
+                    body: resp.text().await.unwrap(),
+                });
+            }
+        };
+
+        Ok(FileDownload { resp })
+    }
+
     /// Get a file's contents by it's ID. Only works for Google Docs.
     pub async fn get_file_contents_by_id(&self, id: &str) -> Result<String, APIError> {
         // Build the request.
@@ -335,7 +389,7 @@ impl GoogleDrive {
         // Create the file.
         let mut f: File = Default::default();
         let mut method = Method::POST;
-        let mut uri = "https://www.googleapis.com/upload/drive/v3/files".to_string();
+        let mut uri = cio_http::rebase("https://www.googleapis.com/upload/drive/v3/files", &self.base_url);
 
         // Check if the file exists.
         let files = self.get_file_by_name(drive_id, name).await.unwrap_or_default();
@@ -442,7 +496,14 @@ impl GoogleDrive {
         }
 
         // Build the request.
-        let request = self.request(Method::POST, format!("https://storage.googleapis.com/upload/storage/v1/b/{}/o", bucket), (), Some(q), body, mime_type);
+        let request = self.request(
+            Method::POST,
+            cio_http::rebase(&format!("https://storage.googleapis.com/upload/storage/v1/b/{}/o", bucket), &self.base_url),
+            (),
+            Some(q),
+            body,
+            mime_type,
+        );
 
         let resp = self.client.execute(request).await.unwrap();
         match resp.status() {
@@ -460,6 +521,23 @@ impl GoogleDrive {
     }
 }
 
+/// A file being downloaded from Google Drive.
+pub struct FileDownload {
+    resp: reqwest::Response,
+}
+
+impl FileDownload {
+    /// The size of the file in bytes, if Google Drive sent it.
+    pub fn content_length(&self) -> Option<u64> {
+        self.resp.content_length()
+    }
+
+    /// The contents of the file, as they are downloaded.
+    pub fn bytes_stream(self) -> impl Stream<Item = reqwest::Result<Bytes>> {
+        self.resp.bytes_stream()
+    }
+}
+
 /// Error type returned by our library.
 pub struct APIError {
     pub status_code: StatusCode,
@@ -37,7 +37,7 @@ const ENDPOINT: &str = "https://api.giphy.com/v1/";
 pub struct Giphy {
     key: String,
 
-    client: Arc<Client>,
+    client: Arc<cio_http::Client>,
 }
 
 impl Giphy {
@@ -53,7 +53,7 @@ impl Giphy {
             Ok(c) => Self {
                 key: key.to_string(),
 
-                client: Arc::new(c),
+                client: Arc::new(cio_http::Client::new(c)),
             },
             Err(e) => panic!("creating client failed: {:?}", e),
         }
@@ -37,14 +37,17 @@ const ENDPOINT: &str = "https://maps.google.com/maps/api/geocode/json";
 /// Entrypoint for interacting with the Geocode API.
 pub struct Geocode {
     key: String,
+    base_url: String,
 
-    client: Arc<Client>,
+    client: Arc<cio_http::Client>,
 }
 
 impl Geocode {
     /// Create a new Geocode client struct. It takes a type that can convert into
     /// an &str (`String` or `Vec<u8>` for example). As long as the function is
     /// given a valid API key your requests will work.
+    /// The client talks to the base URL in the GOOGLE_GEOCODE_BASE_URL env variable
+    /// if it is set, see `with_base_url`.
     pub fn new<K>(key: K) -> Self
     where
         K: ToString,
@@ -53,8 +56,9 @@ impl Geocode {
         match client {
             Ok(c) => Self {
                 key: key.to_string(),
+                base_url: env::var("GOOGLE_GEOCODE_BASE_URL").unwrap_or_default(),
 
-                client: Arc::new(c),
+                client: Arc::new(cio_http::Client::new(c)),
             },
             Err(e) => panic!("creating client failed: {:?}", e),
         }
@@ -70,11 +74,21 @@ impl Geocode {
         Geocode::new(key)
     }
 
+    /// Send requests to another base URL, like a fake server in tests, instead
+    /// of the Google Geocoding API.
+    pub fn with_base_url<U>(mut self, base_url: U) -> Self
+    where
+        U: ToString,
+    {
+        self.base_url = base_url.to_string();
+        self
+    }
+
     fn request<B>(&self, method: Method, path: &str, body: B, query: Option<Vec<(&str, String)>>) -> Request
     where
         B: Serialize,
     {
-        let base = Url::parse(ENDPOINT).unwrap();
+        let base = Url::parse(&cio_http::rebase(ENDPOINT, &self.base_url)).unwrap();
         let url = base.join(path).unwrap();
 
         // Set the default headers.
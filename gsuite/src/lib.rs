@@ -54,6 +54,7 @@
  * ```
  */
 use std::collections::HashMap;
+use std::env;
 use std::error;
 use std::fmt;
 use std::sync::Arc;
@@ -78,30 +79,47 @@ const GROUPS_SETTINGS_ENDPOINT: &str = "https://www.googleapis.com/groups/v1/gro
 /// Endpoint for the Google Calendar API.
 const CALENDAR_ENDPOINT: &str = "https://www.googleapis.com/calendar/v3/";
 
+/// Endpoint for the Google Data Transfer API.
+const DATA_TRANSFER_ENDPOINT: &str = "https://admin.googleapis.com/admin/datatransfer/v1/";
+
 /// Entrypoint for interacting with the GSuite APIs.
 pub struct GSuite {
     customer: String,
     domain: String,
 
     token: AccessToken,
+    base_url: String,
 
-    client: Arc<Client>,
+    client: Arc<cio_http::Client>,
 }
 
 impl GSuite {
     /// Create a new GSuite client struct. It takes a type that can convert into
     /// an &str (`String` or `Vec<u8>` for example). As long as the function is
     /// given a valid API Key and Secret your requests will work.
+    /// The client talks to the base URL in the GSUITE_BASE_URL env variable
+    /// if it is set, see `with_base_url`.
     pub fn new(customer: &str, domain: &str, token: AccessToken) -> Self {
         let client = Client::builder().build().expect("creating client failed");
         Self {
             customer: customer.to_string(),
             domain: domain.to_string(),
             token,
-            client: Arc::new(client),
+            base_url: env::var("GSUITE_BASE_URL").unwrap_or_default(),
+            client: Arc::new(cio_http::Client::new(client)),
         }
     }
 
+    /// Send requests to another base URL, like a fake server in tests, instead
+    /// of the Google Workspace API.
+    pub fn with_base_url<U>(mut self, base_url: U) -> Self
+    where
+        U: ToString,
+    {
+        self.base_url = base_url.to_string();
+        self
+    }
+
     /// Get the currently set authorization token.
     pub fn get_token(&self) -> &AccessToken {
         &self.token
@@ -111,7 +129,7 @@ impl GSuite {
     where
         B: Serialize,
     {
-        let base = Url::parse(endpoint).unwrap();
+        let base = Url::parse(&cio_http::rebase(endpoint, &self.base_url)).unwrap();
         let url = base.join(path).unwrap();
 
         // Check if the token is expired and panic.
@@ -448,6 +466,28 @@ impl GSuite {
         Ok(())
     }
 
+    /// Get a user.
+    /// The `user_key` can be the user's primary email address, alias email address, or unique user ID.
+    /// FROM: https://developers.google.com/admin-sdk/directory/reference/rest/v1/users/get
+    pub async fn get_user(&self, user_key: &str) -> Result<User, APIError> {
+        // Build the request.
+        let request = self.request(DIRECTORY_ENDPOINT, Method::GET, &format!("users/{}", user_key), (), Some(&[("projection", "full")]));
+
+        let resp = self.client.execute(request).await.unwrap();
+        match resp.status() {
+            StatusCode::OK => (),
+            s => {
+                return Err(APIError {
+                    status_code: s,
+                    body: resp.text().await.unwrap(),
+                });
+            }
+        };
+
+        // Try to deserialize the response.
+        Ok(resp.json().await.unwrap())
+    }
+
     /// Create a user.
     pub async fn create_user(&self, user: &User) -> Result<User, APIError> {
         // Build the request.
@@ -929,6 +969,50 @@ impl GSuite {
 
         Ok(())
     }
+
+    /// List the applications that support transferring data between users.
+    /// FROM: https://developers.google.com/admin-sdk/data-transfer/reference/rest/v1/applications/list
+    pub async fn list_data_transfer_applications(&self) -> Result<Vec<DataTransferApplication>, APIError> {
+        // Build the request.
+        let request = self.request(DATA_TRANSFER_ENDPOINT, Method::GET, "applications", (), Some(&[("customerId", &self.customer)]));
+
+        let resp = self.client.execute(request).await.unwrap();
+        match resp.status() {
+            StatusCode::OK => (),
+            s => {
+                return Err(APIError {
+                    status_code: s,
+                    body: resp.text().await.unwrap(),
+                });
+            }
+        };
+
+        // Try to deserialize the response.
+        let value: DataTransferApplications = resp.json().await.unwrap();
+
+        Ok(value.applications)
+    }
+
+    /// Start transferring a user's data to another user.
+    /// FROM: https://developers.google.com/admin-sdk/data-transfer/reference/rest/v1/transfers/insert
+    pub async fn create_data_transfer(&self, transfer: &DataTransfer) -> Result<DataTransfer, APIError> {
+        // Build the request.
+        let request = self.request(DATA_TRANSFER_ENDPOINT, Method::POST, "transfers", transfer, None);
+
+        let resp = self.client.execute(request).await.unwrap();
+        match resp.status() {
+            StatusCode::OK => (),
+            s => {
+                return Err(APIError {
+                    status_code: s,
+                    body: resp.text().await.unwrap(),
+                });
+            }
+        };
+
+        // Try to deserialize the response.
+        Ok(resp.json().await.unwrap())
+    }
 }
 
 /// Error type returned by our library.
@@ -2110,6 +2194,82 @@ pub struct BuildingAddress {
     pub address_lines: Vec<String>,
 }
 
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+struct DataTransferApplications {
+    /// Token used to access next page of this result.
+    #[serde(default, skip_serializing_if = "String::is_empty", rename = "nextPageToken")]
+    pub next_page_token: String,
+    /// Kind of resource this is.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub kind: String,
+    /// ETag of the resource.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub etag: String,
+    /// List of applications that support data transfer.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub applications: Vec<DataTransferApplication>,
+}
+
+/// An application that supports transferring data between users.
+/// FROM: https://developers.google.com/admin-sdk/data-transfer/reference/rest/v1/applications#Application
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct DataTransferApplication {
+    /// The application's ID.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub id: String,
+    /// The application's name, for example `Drive and Docs`.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+    /// The parameters that can be set when transferring data for this application.
+    #[serde(default, skip_serializing_if = "Vec::is_empty", rename = "transferParams")]
+    pub transfer_params: Vec<ApplicationTransferParam>,
+}
+
+/// A transfer of data from one user to another.
+/// FROM: https://developers.google.com/admin-sdk/data-transfer/reference/rest/v1/transfers#DataTransfer
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct DataTransfer {
+    /// The transfer's ID (Read-only).
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub id: String,
+    /// ID of the user whose data is being transferred.
+    #[serde(default, skip_serializing_if = "String::is_empty", rename = "oldOwnerUserId")]
+    pub old_owner_user_id: String,
+    /// ID of the user to whom the data is being transferred.
+    #[serde(default, skip_serializing_if = "String::is_empty", rename = "newOwnerUserId")]
+    pub new_owner_user_id: String,
+    /// List of per application data transfer resources.
+    #[serde(default, skip_serializing_if = "Vec::is_empty", rename = "applicationDataTransfers")]
+    pub application_data_transfers: Vec<ApplicationDataTransfer>,
+    /// Overall transfer status (Read-only).
+    #[serde(default, skip_serializing_if = "String::is_empty", rename = "overallTransferStatusCode")]
+    pub overall_transfer_status_code: String,
+}
+
+/// The data to transfer for a single application.
+/// FROM: https://developers.google.com/admin-sdk/data-transfer/reference/rest/v1/transfers#ApplicationDataTransfer
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct ApplicationDataTransfer {
+    /// The application's ID.
+    #[serde(default, skip_serializing_if = "String::is_empty", rename = "applicationId")]
+    pub application_id: String,
+    /// The transfer parameters for the application.
+    #[serde(default, skip_serializing_if = "Vec::is_empty", rename = "applicationTransferParams")]
+    pub application_transfer_params: Vec<ApplicationTransferParam>,
+    /// Current status of transfer for this application (Read-only).
+    #[serde(default, skip_serializing_if = "String::is_empty", rename = "applicationTransferStatus")]
+    pub application_transfer_status: String,
+}
+
+/// A parameter for a data transfer, for example `PRIVACY_LEVEL`.
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct ApplicationTransferParam {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub key: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub value: Vec<String>,
+}
+
 mod calendar_date_format {
     use chrono::{DateTime, Utc};
     use serde::{self, Serializer};
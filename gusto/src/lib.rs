@@ -45,7 +45,7 @@ pub struct Gusto {
     client_secret: String,
     redirect_uri: String,
 
-    client: Arc<Client>,
+    client: Arc<cio_http::Client>,
 }
 
 impl Gusto {
@@ -70,7 +70,7 @@ impl Gusto {
                     token: token.to_string(),
                     refresh_token: refresh_token.to_string(),
 
-                    client: Arc::new(c),
+                    client: Arc::new(cio_http::Client::new(c)),
                 };
 
                 if g.token.is_empty() || g.refresh_token.is_empty() {
@@ -144,8 +144,8 @@ impl Gusto {
             ("client_id", &self.client_id),
             ("client_secret", &self.client_secret),
         ];
-        let client = reqwest::Client::new();
-        let resp = client.post(&format!("{}oauth/token", ENDPOINT)).headers(headers).form(&params).send().await.unwrap();
+        let client = cio_http::Client::new(reqwest::Client::new());
+        let resp = client.send(client.post(&format!("{}oauth/token", ENDPOINT)).headers(headers).form(&params)).await.unwrap();
 
         // Unwrap the response.
         let t: AccessToken = resp.json().await.unwrap();
@@ -167,8 +167,8 @@ impl Gusto {
             ("client_id", &self.client_id),
             ("client_secret", &self.client_secret),
         ];
-        let client = reqwest::Client::new();
-        let resp = client.post(&format!("{}oauth/token", ENDPOINT)).headers(headers).form(&params).send().await.unwrap();
+        let client = cio_http::Client::new(reqwest::Client::new());
+        let resp = client.send(client.post(&format!("{}oauth/token", ENDPOINT)).headers(headers).form(&params)).await.unwrap();
 
         // Unwrap the response.
         let t: AccessToken = resp.json().await.unwrap();
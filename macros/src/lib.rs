@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 extern crate proc_macro;
 
@@ -28,6 +28,22 @@ struct Params {
     custom_partial_eq: bool,
     /// The struct item and type that we will filter on to find unique database entries.
     match_on: HashMap<String, String>,
+    /// The struct items and types the API can filter the records on when listing
+    /// them. These should be indexed columns.
+    #[serde(default)]
+    filter_on: BTreeMap<String, String>,
+    /// The struct items that people edit in Airtable. The value in Airtable always
+    /// wins for these, and it is copied back into the database when we sync.
+    #[serde(default)]
+    airtable_fields: Vec<String>,
+    /// The struct items that can be edited in the database or in Airtable. The side
+    /// that changed the value since the last sync wins, field by field. If both did,
+    /// the value in Airtable wins and the conflict is logged. If the record was never
+    /// synced, the value in the database wins.
+    /// Every struct item that is not in `airtable_fields` or `merged_fields` is owned
+    /// by the database, and the value in the database always wins.
+    #[serde(default)]
+    merged_fields: Vec<String>,
 }
 
 #[proc_macro_attribute]
@@ -66,6 +82,28 @@ fn do_db(attr: TokenStream, item: TokenStream) -> TokenStream {
         function_args = quote!(#function_args self.#f.clone(),);
     }
 
+    // Let's create the filter for listing records.
+    let filter_struct_name = format_ident!("{}Filter", params.new_struct_name);
+    let mut filter_fields = quote!();
+    let mut filter_idents = quote!();
+    let mut filter_query = quote!();
+    for (field, type_) in params.filter_on {
+        let f = format_ident!("{}", field);
+        let t: Type = syn::parse_str(&type_).unwrap();
+        let doc = format!("Only return records where `{}` is this value.", field);
+        filter_fields = quote!(#filter_fields
+            #[doc = #doc]
+            #[serde(default, skip_serializing_if = "Option::is_none")]
+            pub #f: Option<#t>,
+        );
+        filter_idents = quote!(#filter_idents #f,);
+        filter_query = quote!(#filter_query
+            if let Some(v) = #f {
+                query = query.filter(crate::schema::#db_schema::dsl::#f.eq(v.clone()));
+            }
+        );
+    }
+
     // Get the original struct information.
     let og_struct: ItemStruct = syn::parse2(item.clone()).unwrap();
     let mut fields: Vec<&Field> = Default::default();
@@ -77,6 +115,166 @@ fn do_db(attr: TokenStream, item: TokenStream) -> TokenStream {
     }
     let og_struct_name = og_struct.ident;
 
+    // Let's create the patch for changing some of the fields of a record. Fields
+    // that are already optional stay optional, so leaving them out of a patch
+    // cannot clear them.
+    let patch_struct_name = format_ident!("{}Patch", params.new_struct_name);
+    let mut patch_fields = quote!();
+    let mut patch_idents = quote!();
+    let mut patch_apply = quote!();
+    for field in &fields {
+        let ident = field.ident.clone();
+        let ty = &field.ty;
+        let docs = field.attrs.iter().filter(|a| a.path.is_ident("doc"));
+        let names = serde_names(field);
+        if is_option(ty) {
+            patch_fields = quote!(#patch_fields
+                #(#docs)*
+                #[serde(default, skip_serializing_if = "Option::is_none" #(, #names)*)]
+                pub #ident: #ty,
+            );
+            patch_apply = quote!(#patch_apply
+                if #ident.is_some() {
+                    self.#ident = #ident;
+                }
+            );
+        } else {
+            patch_fields = quote!(#patch_fields
+                #(#docs)*
+                #[serde(default, skip_serializing_if = "Option::is_none" #(, #names)*)]
+                pub #ident: Option<#ty>,
+            );
+            patch_apply = quote!(#patch_apply
+                if let Some(v) = #ident {
+                    self.#ident = v;
+                }
+            );
+        }
+        patch_idents = quote!(#patch_idents #ident,);
+    }
+
+    // Let's resolve the fields that are not owned by the database against the record
+    // in Airtable.
+    let mut resolve_airtable_fields = quote!();
+    for name in &params.airtable_fields {
+        let f = format_ident!("{}", name);
+        resolve_airtable_fields = quote!(#resolve_airtable_fields
+            if self.#f != existing_record.fields.#f {
+                resolved.#f = existing_record.fields.#f.clone();
+                from_airtable = true;
+            }
+        );
+    }
+    // Airtable owns these fields, so creating or updating the record from its source
+    // keeps the values the database already has from Airtable.
+    let mut keep_airtable_fields = quote!();
+    for name in &params.airtable_fields {
+        let f = format_ident!("{}", name);
+        keep_airtable_fields = quote!(#keep_airtable_fields
+            values.#f = r.#f.clone();
+        );
+    }
+    let upsert_values = if params.airtable_fields.is_empty() {
+        quote!(let values = self.clone();)
+    } else {
+        quote! {
+            let mut values = self.clone();
+            #keep_airtable_fields
+        }
+    };
+
+    let mut merged_differ = quote!();
+    let mut merged_resolve = quote!();
+    for name in &params.merged_fields {
+        let f = format_ident!("{}", name);
+        // The audit log has the fields by the name they are serialized with.
+        let serialized = fields
+            .iter()
+            .find(|field| field.ident.as_ref().map(|i| i == name).unwrap_or(false))
+            .map(|field| serialized_name(field))
+            .unwrap_or_else(|| name.to_string());
+        merged_differ = quote!(#merged_differ
+            if self.#f != existing_record.fields.#f {
+                differ.push(#serialized);
+            }
+        );
+        merged_resolve = quote!(#merged_resolve
+            #serialized => {
+                let in_airtable = in_airtable.get(#serialized).unwrap_or(&serde_json::Value::Null);
+                let take = match synced_values.get(#serialized) {
+                    // Only Airtable changed the value.
+                    None => true,
+                    // Both sides changed the value.
+                    Some(synced) if synced != in_airtable => {
+                        println!("[airtable] id={} `{}` in {} changed in the database and in Airtable since the last sync, keeping the value in Airtable", self.id, #serialized, stringify!(#db_schema));
+                        true
+                    }
+                    // Only the database changed the value.
+                    Some(_) => false,
+                };
+                if take {
+                    resolved.#f = existing_record.fields.#f.clone();
+                    from_airtable = true;
+                }
+            }
+        );
+    }
+    let resolve_with_airtable = if params.airtable_fields.is_empty() && params.merged_fields.is_empty() {
+        quote! {
+            pub async fn resolve_with_airtable(&self, _db: &crate::db::Database, _existing_record: &airtable_api::Record<#new_struct_name>) -> Result<(Self, bool), crate::Error> {
+                // Every field is owned by the database.
+                Ok((self.clone(), false))
+            }
+        }
+    } else {
+        let mut resolve_merged_fields = quote!();
+        let mut db_arg = quote!(_db);
+        if !params.merged_fields.is_empty() {
+            db_arg = quote!(db);
+            resolve_merged_fields = quote! {
+                // Merged fields take the value from whichever side changed it since
+                // the last sync. The audit log has the values the database had at the
+                // last sync, so we can tell which side changed each field.
+                let mut differ: Vec<&str> = Default::default();
+                #merged_differ
+                match self.airtable_last_synced_at {
+                    Some(since) if !differ.is_empty() => {
+                        let synced_values = crate::audit::AuditLogEntry::values_at(#db_arg, stringify!(#db_schema), self.id, since)?;
+                        // Serialize the whole record, like the audit log does, so empty
+                        // values that are skipped compare the same.
+                        let in_airtable = serde_json::to_value(&existing_record.fields)?;
+                        for field in differ {
+                            match field {
+                                #merged_resolve
+                                _ => (),
+                            }
+                        }
+                    }
+                    // We don't know what either side had at the last sync, so the values
+                    // in the database win until they are synced.
+                    None if !differ.is_empty() => {
+                        println!("[airtable] id={} in {} was never synced, keeping the values in the database for {:?}", self.id, stringify!(#db_schema), differ);
+                    }
+                    _ => (),
+                }
+            };
+        }
+
+        quote! {
+            pub async fn resolve_with_airtable(&self, #db_arg: &crate::db::Database, existing_record: &airtable_api::Record<#new_struct_name>) -> Result<(Self, bool), crate::Error> {
+                let mut resolved = self.clone();
+                let mut from_airtable = false;
+
+                // Fields owned by Airtable always take the value in Airtable.
+                #resolve_airtable_fields
+
+                #resolve_merged_fields
+
+                Ok((resolved, from_airtable))
+            }
+        }
+    };
+
     // Get the Airtable information.
     let airtable_base_id = format_ident!("{}", params.airtable_base_id);
     let airtable_table = format_ident!("{}", params.airtable_table);
@@ -87,50 +285,60 @@ fn do_db(attr: TokenStream, item: TokenStream) -> TokenStream {
 
     impl #og_struct_name {
         /// Create a new record in the database and Airtable.
-        pub async fn create(&self, db: &crate::db::Database) -> #new_struct_name {
-            let mut new_record = self.create_in_db(db);
+        pub async fn create(&self, db: &crate::db::Database) -> Result<#new_struct_name, crate::Error> {
+            let mut new_record = self.create_in_db(db)?;
 
             // Let's also create this record in Airtable.
-            let new_airtable_record = new_record.create_in_airtable().await;
+            let new_airtable_record = new_record.create_in_airtable().await?;
 
             // Now we have the id we need to update the database.
             new_record.airtable_record_id = new_airtable_record.id.to_string();
-            new_record.update_in_db(db)
+            new_record.update_in_db_synced(db)
         }
 
         /// Create a new record in the database.
-        pub fn create_in_db(&self, db: &crate::db::Database) -> #new_struct_name {
-            diesel::insert_into(crate::schema::#db_schema::table)
-                .values(self)
-                .get_result(&db.conn())
-                .unwrap_or_else(|e| panic!("creating record {:?} failed: {}", self, e))
+        pub fn create_in_db(&self, db: &crate::db::Database) -> Result<#new_struct_name, crate::Error> {
+            let conn = db.conn();
+            conn.transaction::<_, crate::Error, _>(|| {
+                let r: #new_struct_name = diesel::insert_into(crate::schema::#db_schema::table)
+                    .values(self)
+                    .get_result(&conn)?;
+
+                crate::audit::record(&conn, db.audit_source(), stringify!(#db_schema), r.id, crate::audit::AuditAction::Create, None, Some(&r))?;
+
+                Ok(r)
+            })
         }
 
         /// Create or update the record in the database and Airtable.
-        pub async fn upsert(&self, db: &crate::db::Database) -> #new_struct_name {
-            let mut record = self.upsert_in_db(db);
+        pub async fn upsert(&self, db: &crate::db::Database) -> Result<#new_struct_name, crate::Error> {
+            let mut record = self.upsert_in_db(db)?;
 
             // Let's also update this record in Airtable.
-            let new_airtable_record = record.upsert_in_airtable().await;
-
-            if record.airtable_record_id.is_empty(){
-                // Now we have the id we need to update the database.
-                record.airtable_record_id = new_airtable_record.id.to_string();
-                return record.update_in_db(db);
-            }
+            let new_airtable_record = record.upsert_in_airtable(db).await?;
 
-            record
+            // Now we have the id we need to update the database, along with the values
+            // Airtable won and the time we synced.
+            record.airtable_record_id = new_airtable_record.id.to_string();
+            record.update_in_db_synced(db)
         }
 
         /// Create or update the record in the database.
-        pub fn upsert_in_db(&self, db: &crate::db::Database) -> #new_struct_name {
+        pub fn upsert_in_db(&self, db: &crate::db::Database) -> Result<#new_struct_name, crate::Error> {
             // See if we already have the record in the database.
             if let Some(r) = #new_struct_name::get_from_db(db, #function_args) {
                 // Update the record.
-                return diesel::update(&r)
-                    .set(self)
-                    .get_result::<#new_struct_name>(&db.conn())
-                    .unwrap_or_else(|e| panic!("unable to update record {}: {}", r.id, e));
+                #upsert_values
+                let conn = db.conn();
+                return conn.transaction::<_, crate::Error, _>(|| {
+                    let record = diesel::update(&r)
+                        .set(&values)
+                        .get_result::<#new_struct_name>(&conn)?;
+
+                    crate::audit::record(&conn, db.audit_source(), stringify!(#db_schema), record.id, crate::audit::AuditAction::Update, Some(&r), Some(&record))?;
+
+                    Ok(record)
+                });
             }
 
             self.create_in_db(db)
@@ -147,25 +355,49 @@ fn do_db(attr: TokenStream, item: TokenStream) -> TokenStream {
 
     impl #new_struct_name {
         /// Update the record in the database and Airtable.
-        pub async fn update(&self, db: &crate::db::Database) -> Self {
+        pub async fn update(&self, db: &crate::db::Database) -> Result<Self, crate::Error> {
             // Update the record.
-            let mut record = self.update_in_db(db);
+            let mut record = self.update_in_db(db)?;
 
             // Let's also update this record in Airtable.
-            let new_airtable_record = record.upsert_in_airtable().await;
+            let new_airtable_record = record.upsert_in_airtable(db).await?;
 
-            // Now we have the id we need to update the database.
+            // Now we have the id we need to update the database, along with the values
+            // Airtable won and the time we synced.
             record.airtable_record_id = new_airtable_record.id.to_string();
-            record.update_in_db(db)
+            record.update_in_db_synced(db)
         }
 
         /// Update the record in the database.
-        pub fn update_in_db(&self, db: &crate::db::Database) -> Self {
-            // Update the record.
-            diesel::update(self)
-                .set(self.clone())
-                .get_result::<#new_struct_name>(&db.conn())
-                .unwrap_or_else(|e| panic!("[db] unable to update record {}: {}", self.id, e))
+        pub fn update_in_db(&self, db: &crate::db::Database) -> Result<Self, crate::Error> {
+            let conn = db.conn();
+            conn.transaction::<_, crate::Error, _>(|| {
+                // Get the record as it is now for the audit log.
+                let before = crate::schema::#db_schema::dsl::#db_schema.find(self.id).first::<#new_struct_name>(&conn)?;
+
+                // Update the record.
+                let record = diesel::update(self)
+                    .set(self.clone())
+                    .get_result::<#new_struct_name>(&conn)?;
+
+                crate::audit::record(&conn, db.audit_source(), stringify!(#db_schema), record.id, crate::audit::AuditAction::Update, Some(&before), Some(&record))?;
+
+                Ok(record)
+            })
+        }
+
+        /// Update the record in the database after syncing it with Airtable, and
+        /// save the time we synced after the update. The update saves the values
+        /// Airtable won, so its audit log entry has to be before the time we synced,
+        /// or the next sync would take those values for changes made in the database.
+        pub fn update_in_db_synced(&self, db: &crate::db::Database) -> Result<Self, crate::Error> {
+            let mut record = self.update_in_db(db)?;
+
+            let synced_at = chrono::Utc::now();
+            #new_struct_name_plural::set_airtable_last_synced_at(db, &[record.id], synced_at)?;
+            record.airtable_last_synced_at = Some(synced_at);
+
+            Ok(record)
         }
 
         /// Get a record from the database.
@@ -181,35 +413,54 @@ fn do_db(attr: TokenStream, item: TokenStream) -> TokenStream {
             }
         }
 
+        /// Change the fields of the record that are set in the patch.
+        pub fn apply_patch(&mut self, patch: #patch_struct_name) {
+            let #patch_struct_name { #patch_idents } = patch;
+            #patch_apply
+        }
+
         /// Get a record by its id.
-        pub fn get_by_id(db: &crate::db::Database, id: i32) -> Self {
-            #db_schema::dsl::#db_schema.find(id)
-                .first::<#new_struct_name>(&db.conn()).unwrap()
+        pub fn get_by_id(db: &crate::db::Database, id: i32) -> Result<Self, crate::Error> {
+            match #db_schema::dsl::#db_schema.find(id).first::<#new_struct_name>(&db.conn()) {
+                Ok(r) => Ok(r),
+                Err(diesel::result::Error::NotFound) => Err(crate::Error::not_found(stringify!(#db_schema), id)),
+                Err(e) => Err(e.into()),
+            }
         }
 
         /// Get the row in our airtable workspace.
-        pub async fn get_from_airtable(id: &str) -> Self {
+        pub async fn get_from_airtable(id: &str) -> Result<Self, crate::Error> {
             let record = #new_struct_name::airtable()
                 .get_record(&#new_struct_name::airtable_table(), id)
-                .await.unwrap();
+                .await?;
 
-            record.fields
+            Ok(record.fields)
         }
 
         /// Delete a record from the database and Airtable.
-        pub async fn delete(&self, db: &crate::db::Database) {
-            self.delete_from_db(db);
+        pub async fn delete(&self, db: &crate::db::Database) -> Result<(), crate::Error> {
+            self.delete_from_db(db)?;
 
             // Let's also delete the record from Airtable.
-            self.delete_from_airtable().await;
+            self.delete_from_airtable().await
         }
 
         /// Delete a record from the database.
-        pub fn delete_from_db(&self, db: &crate::db::Database) {
-            diesel::delete(
-                crate::schema::#db_schema::dsl::#db_schema.filter(
-                    crate::schema::#db_schema::dsl::id.eq(self.id)))
-                    .execute(&db.conn()).unwrap();
+        pub fn delete_from_db(&self, db: &crate::db::Database) -> Result<(), crate::Error> {
+            let conn = db.conn();
+            conn.transaction::<_, crate::Error, _>(|| {
+                let deleted = diesel::delete(
+                    crate::schema::#db_schema::dsl::#db_schema.filter(
+                        crate::schema::#db_schema::dsl::id.eq(self.id)))
+                        .get_result::<#new_struct_name>(&conn)
+                        .optional()?;
+
+                if let Some(r) = deleted {
+                    crate::audit::record(&conn, db.audit_source(), stringify!(#db_schema), r.id, crate::audit::AuditAction::Delete, Some(&r), None)?;
+                }
+
+                Ok(())
+            })
         }
 
         /// Create the Airtable client.
@@ -225,7 +476,7 @@ fn do_db(attr: TokenStream, item: TokenStream) -> TokenStream {
         }
 
         /// Create the row in the Airtable base.
-        pub async fn create_in_airtable(&mut self) -> airtable_api::Record<#new_struct_name> {
+        pub async fn create_in_airtable(&mut self) -> Result<airtable_api::Record<#new_struct_name>, crate::Error> {
             // Create the record.
             let record = airtable_api::Record {
                 id: "".to_string(),
@@ -236,47 +487,87 @@ fn do_db(attr: TokenStream, item: TokenStream) -> TokenStream {
             // Send the new record to the Airtable client.
             let records : Vec<airtable_api::Record<#new_struct_name>> = #new_struct_name::airtable()
                 .create_records(&#new_struct_name::airtable_table(), vec![record])
-                .await
-                .unwrap();
+                .await?;
 
             println!("[airtable] created new row: {:?}", self);
+            self.airtable_last_synced_at = Some(chrono::Utc::now());
 
             // Return the first record back.
-            records.get(0).unwrap().clone()
+            match records.get(0) {
+                Some(r) => Ok(r.clone()),
+                None => Err(crate::Error::not_found(#new_struct_name::airtable_table(), self.id)),
+            }
         }
 
-        /// Update the record in Airtable.
-        pub async fn update_in_airtable(&self, existing_record: &mut airtable_api::Record<#new_struct_name>) -> airtable_api::Record<#new_struct_name> {
+        /// Resolve the differences between this record and its record in Airtable,
+        /// following who owns each field: see `airtable_fields` and `merged_fields`
+        /// in the `db` macro. Returns the record with the values Airtable won, and
+        /// whether there were any.
+        #resolve_with_airtable
+
+        /// Resolve the differences with the record in Airtable, and save the values
+        /// Airtable won to the database. Returns the record, and whether it was saved.
+        async fn merge_from_airtable(&self, db: &crate::db::Database, existing_record: &airtable_api::Record<#new_struct_name>) -> Result<(Self, bool), crate::Error> {
+            let (record, from_airtable) = self.resolve_with_airtable(db, existing_record).await?;
+            if from_airtable {
+                return Ok((record.update_in_db(db)?, true));
+            }
+
+            Ok((record, false))
+        }
+
+        /// Get the Airtable record updated with this record, or `None` if the
+        /// Airtable record is already up to date.
+        pub async fn airtable_record_changes(&self, existing_record: &airtable_api::Record<#new_struct_name>) -> Option<airtable_api::Record<#new_struct_name>> {
             let mut mut_self = self.clone();
+            // Airtable doesn't have the time we last synced.
+            mut_self.airtable_last_synced_at = existing_record.fields.airtable_last_synced_at;
             // Run the custom trait to update the new record from the old record.
             // We do this because where we join Airtable tables, things tend to get a little
             // weird if we aren't nit picky about this.
             mut_self.update_airtable_record(existing_record.fields.clone()).await;
 
-            // If the Airtable record and the record that was passed in are the same, then we can return early since
-            // we do not need to update it in Airtable.
+            // If the Airtable record and the record that was passed in are the same, then we
+            // do not need to update it in Airtable.
             // We do this after we update the record so that any fields that are links to other
             // tables match as well and this can return true even if we have linked records.
             if mut_self == existing_record.fields {
-                println!("[airtable] id={} in given object equals Airtable record, skipping update", self.id);
-                return existing_record.clone();
+                return None;
             }
 
-            existing_record.fields = mut_self;
+            let mut record = existing_record.clone();
+            record.fields = mut_self;
+            Some(record)
+        }
+
+        /// Update the record in Airtable. This takes the values of the fields Airtable
+        /// won and the time we synced, so they can be saved to the database.
+        pub async fn update_in_airtable(&mut self, db: &crate::db::Database, existing_record: &mut airtable_api::Record<#new_struct_name>) -> Result<airtable_api::Record<#new_struct_name>, crate::Error> {
+            let (resolved, _) = self.resolve_with_airtable(db, existing_record).await?;
+            *self = resolved;
+
+            match self.airtable_record_changes(existing_record).await {
+                Some(record) => *existing_record = record,
+                None => {
+                    println!("[airtable] id={} in given object equals Airtable record, skipping update", self.id);
+                    self.airtable_last_synced_at = Some(chrono::Utc::now());
+                    return Ok(existing_record.clone());
+                }
+            }
 
             // Send the updated record to Airtable.
             let records : Vec<airtable_api::Record<#new_struct_name>> = #new_struct_name::airtable().update_records(
                 &#new_struct_name::airtable_table(),
                 vec![existing_record.clone()],
-            ).await.unwrap();
+            ).await?;
 
             println!("[airtable] id={} updated", self.id);
+            self.airtable_last_synced_at = Some(chrono::Utc::now());
 
-            if records.is_empty() {
-                return existing_record.clone();
+            match records.get(0) {
+                Some(r) => Ok(r.clone()),
+                None => Ok(existing_record.clone()),
             }
-
-            records.get(0).unwrap().clone()
         }
 
         /// Get the existing record in Airtable that matches this id.
@@ -298,7 +589,7 @@ fn do_db(attr: TokenStream, item: TokenStream) -> TokenStream {
 
 
         /// Create or update a row in the Airtable base.
-        pub async fn upsert_in_airtable(&mut self) -> airtable_api::Record<#new_struct_name> {
+        pub async fn upsert_in_airtable(&mut self, db: &crate::db::Database) -> Result<airtable_api::Record<#new_struct_name>, crate::Error> {
             // First check if we have an `airtable_record_id` for this record.
             // If we do we can move ahead faster.
             if !self.airtable_record_id.is_empty() {
@@ -306,21 +597,16 @@ fn do_db(attr: TokenStream, item: TokenStream) -> TokenStream {
 
                 if let Some(mut existing_record) = er {
                     // Return the result from the update.
-                    return self.update_in_airtable(&mut existing_record).await;
+                    return self.update_in_airtable(db, &mut existing_record).await;
                 }
                 // Otherwise we need to continue through the other loop.
             }
 
-            // Since we don't know the airtable record id, we need to find it by looking
-            // through all the existing records in Airtable and matching on our database id.
-            // This is slow so we should always try to make sure we have the airtable_record_id
-            // set. This function is mostly here until we migrate away from the old way of doing
-            // things.
-            let records = #new_struct_name_plural::get_from_airtable().await;
-            for (id, record) in records {
-                if self.id == id {
-                    return self.update_in_airtable(&mut record.clone()).await;
-                }
+            // Since we don't know the airtable record id, we need to find it by
+            // our database id. This costs an extra request so we should always try
+            // to make sure we have the airtable_record_id set.
+            if let Some(mut existing_record) = #new_struct_name::find_in_airtable(self.id).await? {
+                return self.update_in_airtable(db, &mut existing_record).await;
             }
 
             // We've tried everything to find the record in our existing Airtable but it is not
@@ -328,18 +614,47 @@ fn do_db(attr: TokenStream, item: TokenStream) -> TokenStream {
             self.create_in_airtable().await
         }
 
+        /// Find the record in Airtable by its database id.
+        pub async fn find_in_airtable(id: i32) -> Result<Option<airtable_api::Record<#new_struct_name>>, crate::Error> {
+            let options = airtable_api::ListOptions {
+                filter_by_formula: format!("{{id}} = {}", id),
+                max_records: Some(1),
+                ..Default::default()
+            };
+
+            let mut records: Vec<airtable_api::Record<#new_struct_name>> = #new_struct_name::airtable()
+                .list_records_with_options(&#new_struct_name::airtable_table(), &options)
+                .await?;
+
+            Ok(records.pop())
+        }
+
         /// Delete a record from Airtable.
-        pub async fn delete_from_airtable(&self) {
+        pub async fn delete_from_airtable(&self) -> Result<(), crate::Error> {
             if !self.airtable_record_id.is_empty() {
                 // Delete the record from airtable.
-                #new_struct_name::airtable().delete_record(&#new_struct_name::airtable_table(), &self.airtable_record_id).await.unwrap();
+                #new_struct_name::airtable().delete_record(&#new_struct_name::airtable_table(), &self.airtable_record_id).await?;
             }
+
+            Ok(())
         }
     }
 
     #[derive(Debug, Clone, Deserialize, Serialize)]
     pub struct #new_struct_name_plural(pub Vec<#new_struct_name>);
 
+    /// The fields to change in a record, fields that are left out are not changed.
+    #[derive(Debug, Default, Clone, JsonSchema, Deserialize, Serialize)]
+    pub struct #patch_struct_name {
+        #patch_fields
+    }
+
+    /// The fields the records can be filtered on when listing them.
+    #[derive(Debug, Default, Clone, PartialEq, JsonSchema, Deserialize, Serialize)]
+    pub struct #filter_struct_name {
+        #filter_fields
+    }
+
     impl IntoIterator for #new_struct_name_plural {
         type Item = #new_struct_name;
         type IntoIter = std::vec::IntoIter<Self::Item>;
@@ -357,21 +672,38 @@ fn do_db(attr: TokenStream, item: TokenStream) -> TokenStream {
 
     impl #new_struct_name_plural {
         /// Get the current records for this type from the database.
-        pub fn get_from_db(db: &crate::db::Database) -> Self {
-            #new_struct_name_plural(
-                crate::schema::#db_schema::dsl::#db_schema
-                    .order_by(crate::schema::#db_schema::dsl::id.desc())
-                    .load::<#new_struct_name>(&db.conn())
-                    .unwrap()
-            )
+        pub fn get_from_db(db: &crate::db::Database) -> Result<Self, crate::Error> {
+            let records = crate::schema::#db_schema::dsl::#db_schema
+                .order_by(crate::schema::#db_schema::dsl::id.desc())
+                .load::<#new_struct_name>(&db.conn())?;
+
+            Ok(#new_struct_name_plural(records))
+        }
+
+        /// Get a page of the records matching the filter from the database, newest first.
+        /// To get the next page, pass the id of the last record in this page as `before_id`.
+        pub fn get_page_from_db(db: &crate::db::Database, filter: &#filter_struct_name, before_id: Option<i32>, limit: i64) -> Result<Self, crate::Error> {
+            let #filter_struct_name { #filter_idents } = filter;
+
+            let mut query = crate::schema::#db_schema::dsl::#db_schema.into_boxed();
+            if let Some(id) = before_id {
+                query = query.filter(crate::schema::#db_schema::dsl::id.lt(id));
+            }
+            #filter_query
+
+            let records = query
+                .order_by(crate::schema::#db_schema::dsl::id.desc())
+                .limit(limit)
+                .load::<#new_struct_name>(&db.conn())?;
+
+            Ok(#new_struct_name_plural(records))
         }
 
         /// Get the current records for this type from Airtable.
-        pub async fn get_from_airtable() -> std::collections::BTreeMap<i32, airtable_api::Record<#new_struct_name>> {
+        pub async fn get_from_airtable() -> Result<std::collections::BTreeMap<i32, airtable_api::Record<#new_struct_name>>, crate::Error> {
             let result: Vec<airtable_api::Record<#new_struct_name>> = #new_struct_name::airtable()
                 .list_records(&#new_struct_name::airtable_table(), "Grid view", vec![])
-                .await
-                .unwrap();
+                .await?;
 
             let mut records: std::collections::BTreeMap<i32, airtable_api::Record<#new_struct_name>> =
                 Default::default();
@@ -379,42 +711,263 @@ fn do_db(attr: TokenStream, item: TokenStream) -> TokenStream {
                 records.insert(record.fields.id, record);
             }
 
-            records
+            Ok(records)
         }
 
-        /// Update Airtable records in a table from a vector.
-        pub async fn update_airtable(&self) {
-            let mut records = #new_struct_name_plural::get_from_airtable().await;
+        /// Get the records for this type that were modified in Airtable after the given time.
+        pub async fn get_from_airtable_modified_since(since: chrono::DateTime<chrono::Utc>) -> Result<std::collections::BTreeMap<i32, airtable_api::Record<#new_struct_name>>, crate::Error> {
+            let result: Vec<airtable_api::Record<#new_struct_name>> = #new_struct_name::airtable()
+                .list_records_with_options(&#new_struct_name::airtable_table(), &airtable_api::ListOptions::modified_since(since))
+                .await?;
 
-            for mut vec_record in self.0.clone() {
-                // See if we have it in our Airtable records.
-                match records.get(&vec_record.id) {
-                    Some(r) => {
-                        let mut record = r.clone();
+            let mut records: std::collections::BTreeMap<i32, airtable_api::Record<#new_struct_name>> =
+                Default::default();
+            for record in result {
+                records.insert(record.fields.id, record);
+            }
 
-                        // Update the record in Airtable.
-                        vec_record.update_in_airtable(&mut record).await;
+            Ok(records)
+        }
 
-                        // Remove it from the map.
-                        records.remove(&vec_record.id);
+        /// Update Airtable records in a table from a vector, only sending the records
+        /// that changed in the database or in Airtable since the last sync.
+        ///
+        /// The first sync of a table, and the first sync each day after that, is a
+        /// full `update_airtable`, since records added to or deleted from Airtable by
+        /// hand only show up when we list the whole table.
+        pub async fn update_airtable_incremental(&self, db: &crate::db::Database) -> Result<(), crate::Error> {
+            let started_at = chrono::Utc::now();
+            let table = #new_struct_name::airtable_table();
+
+            let mut hashes: std::collections::BTreeMap<i32, String> = Default::default();
+            let state = match crate::airtable::AirtableSyncState::get_from_db(db, #airtable_base_id, &table)? {
+                Some(state) if !state.needs_full_sync(started_at) => state,
+                _ => {
+                    let failed = self.sync_all_to_airtable(db).await?;
+
+                    // Leave the hashes of the records that failed out so we try
+                    // again on the next sync.
+                    for record in self.0.iter().filter(|r| !failed.contains(&r.id)) {
+                        hashes.insert(record.id, crate::airtable::record_hash(record)?);
                     }
+                    crate::airtable::AirtableSyncState::save(db, #airtable_base_id, &table, started_at, true, &hashes)?;
+
+                    return Ok(());
+                }
+            };
+
+            let previous_hashes = state.record_hashes();
+            let modified = #new_struct_name_plural::get_from_airtable_modified_since(state.modified_since()).await?;
+
+            let mut sent = 0;
+            let mut unchanged: Vec<i32> = Default::default();
+            let mut merged: Vec<i32> = Default::default();
+            let mut to_update: Vec<airtable_api::Record<#new_struct_name>> = Default::default();
+            for mut vec_record in self.0.clone() {
+                let hash = crate::airtable::record_hash(&vec_record)?;
+
+                match modified.get(&vec_record.id) {
+                    // The record changed in Airtable, merge the values Airtable won and
+                    // batch the update.
+                    Some(r) => match vec_record.merge_from_airtable(db, r).await {
+                        Ok((record, saved)) => {
+                            match record.airtable_record_changes(r).await {
+                                Some(changes) => to_update.push(changes),
+                                None if saved => merged.push(record.id),
+                                None => unchanged.push(record.id),
+                            }
+                            hashes.insert(record.id, crate::airtable::record_hash(&record)?);
+                        }
+                        // Leave the hash out so we try again on the next sync.
+                        Err(e) => println!("[airtable] merging id={} from {} failed: {}", vec_record.id, table, e),
+                    },
+                    // Nothing changed on either side since the last sync.
+                    None if previous_hashes.get(&vec_record.id) == Some(&hash) => {
+                        hashes.insert(vec_record.id, hash);
+                    }
+                    // The record is new or it changed in the database. We need the
+                    // Airtable record to merge the changes into, so these are sent one
+                    // at a time.
                     None => {
-                        // We do not have the record in Airtable, Let's create it.
-                        // Create the record in Airtable.
-                        vec_record.create_in_airtable().await;
+                        sent += 1;
+                        let result = match vec_record.upsert_in_airtable(db).await {
+                            Ok(r) => {
+                                vec_record.airtable_record_id = r.id.to_string();
+                                vec_record.update_in_db_synced(db)
+                            }
+                            Err(e) => Err(e),
+                        };
 
-                        // Remove it from the map.
-                        records.remove(&vec_record.id);
+                        match result {
+                            Ok(record) => {
+                                hashes.insert(record.id, crate::airtable::record_hash(&record)?);
+                            }
+                            // Leave the hash out so we try again on the next sync.
+                            Err(e) => println!("[airtable] syncing id={} to {} failed: {}", vec_record.id, table, e),
+                        }
                     }
                 }
             }
+            sent += to_update.len();
+
+            // Delete the records we synced last time that are no longer in our vector.
+            let ids: std::collections::BTreeSet<i32> = self.0.iter().map(|r| r.id).collect();
+            let mut to_delete: Vec<airtable_api::Record<#new_struct_name>> = Default::default();
+            for id in previous_hashes.keys().filter(|id| !ids.contains(id)) {
+                match #new_struct_name::find_in_airtable(*id).await {
+                    Ok(Some(record)) => to_delete.push(record),
+                    Ok(None) => (),
+                    Err(e) => println!("[airtable] finding id={} in {} failed: {}", id, table, e),
+                }
+            }
 
-            // Iterate over the records remaining and remove them from airtable
-            // since they don't exist in our vector.
-            for (_, record) in records {
-                // Delete the record from airtable.
-                #new_struct_name::airtable().delete_record(&#new_struct_name::airtable_table(), &record.id).await.unwrap();
+            let updated: Vec<i32> = to_update.iter().map(|r| r.fields.id).collect();
+            let failed = #new_struct_name_plural::send_to_airtable(vec![], to_update, to_delete).await;
+            hashes.retain(|id, _| !failed.contains(id));
+
+            // Nothing was modified in Airtable after we started, except by the updates
+            // we just sent. The values Airtable won were saved to the database after we
+            // started, so those records are synced as of now, or the next sync would
+            // take the saves for changes made in the database.
+            #new_struct_name_plural::set_airtable_last_synced_at(db, &unchanged, started_at)?;
+            let updated: Vec<i32> = updated.into_iter().filter(|id| !failed.contains(id)).chain(merged).collect();
+            #new_struct_name_plural::set_airtable_last_synced_at(db, &updated, chrono::Utc::now())?;
+
+            crate::airtable::AirtableSyncState::save(db, #airtable_base_id, &table, started_at, false, &hashes)?;
+            println!("[airtable] sent {} of {} records to {}, {} were modified in Airtable since {}", sent, self.0.len(), table, modified.len(), state.modified_since());
+
+            Ok(())
+        }
+
+        /// Update Airtable records in a table from a vector.
+        ///
+        /// This compares the vector to the records in Airtable and sends the records to
+        /// create, update and delete in batches of `airtable_api::MAX_RECORDS_PER_REQUEST`.
+        /// The values of the fields Airtable won are saved to the database first.
+        /// A batch that fails to sync is logged and skipped so the rest of the table
+        /// still gets updated.
+        pub async fn update_airtable(&self, db: &crate::db::Database) -> Result<(), crate::Error> {
+            self.sync_all_to_airtable(db).await?;
+
+            Ok(())
+        }
+
+        /// Compare the vector to every record in Airtable and send the differences.
+        /// Returns the database ids of the records that failed to sync.
+        async fn sync_all_to_airtable(&self, db: &crate::db::Database) -> Result<std::collections::BTreeSet<i32>, crate::Error> {
+            let table = #new_struct_name::airtable_table();
+            let listed_at = chrono::Utc::now();
+            let mut records = #new_struct_name_plural::get_from_airtable().await?;
+
+            let mut failed: std::collections::BTreeSet<i32> = Default::default();
+            let mut unchanged: Vec<i32> = Default::default();
+            let mut merged: Vec<i32> = Default::default();
+            let mut to_create: Vec<airtable_api::Record<#new_struct_name>> = Default::default();
+            let mut to_update: Vec<airtable_api::Record<#new_struct_name>> = Default::default();
+            for vec_record in self.0.clone() {
+                // See if we have it in our Airtable records, and remove it from the map so
+                // we are left with the records to delete.
+                match records.remove(&vec_record.id) {
+                    Some(existing_record) => {
+                        let (record, saved) = match vec_record.merge_from_airtable(db, &existing_record).await {
+                            Ok(r) => r,
+                            Err(e) => {
+                                println!("[airtable] merging id={} from {} failed: {}", vec_record.id, table, e);
+                                failed.insert(vec_record.id);
+                                continue;
+                            }
+                        };
+
+                        match record.airtable_record_changes(&existing_record).await {
+                            Some(changes) => to_update.push(changes),
+                            None if saved => merged.push(record.id),
+                            None => unchanged.push(record.id),
+                        }
+                    }
+                    None => to_create.push(airtable_api::Record {
+                        id: "".to_string(),
+                        created_time: None,
+                        fields: vec_record,
+                    }),
+                }
+            }
+
+            // The records remaining don't exist in our vector, so we remove them from Airtable.
+            let to_delete: Vec<airtable_api::Record<#new_struct_name>> = records.into_iter().map(|(_, r)| r).collect();
+
+            let sent: Vec<i32> = to_create.iter().chain(to_update.iter()).map(|r| r.fields.id).collect();
+            failed.extend(#new_struct_name_plural::send_to_airtable(to_create, to_update, to_delete).await);
+
+            // Nothing was modified in Airtable after we listed the records, except by the
+            // records we just sent. The records Airtable won values for were saved after
+            // we listed them, so they are synced as of now too.
+            #new_struct_name_plural::set_airtable_last_synced_at(db, &unchanged, listed_at)?;
+            let sent: Vec<i32> = sent.into_iter().filter(|id| !failed.contains(id)).chain(merged).collect();
+            #new_struct_name_plural::set_airtable_last_synced_at(db, &sent, chrono::Utc::now())?;
+
+            Ok(failed)
+        }
+
+        /// Save the time the records were last synced with Airtable.
+        fn set_airtable_last_synced_at(db: &crate::db::Database, ids: &[i32], synced_at: chrono::DateTime<chrono::Utc>) -> Result<(), crate::Error> {
+            if ids.is_empty() {
+                return Ok(());
+            }
+
+            diesel::update(crate::schema::#db_schema::dsl::#db_schema.filter(crate::schema::#db_schema::dsl::id.eq_any(ids)))
+                .set(crate::schema::#db_schema::dsl::airtable_last_synced_at.eq(Some(synced_at)))
+                .execute(&db.conn())?;
+
+            Ok(())
+        }
+
+        /// Create, update and delete records in Airtable in batches of
+        /// `airtable_api::MAX_RECORDS_PER_REQUEST`. Returns the database ids of the
+        /// records in batches that failed, after logging the error.
+        async fn send_to_airtable(
+            to_create: Vec<airtable_api::Record<#new_struct_name>>,
+            to_update: Vec<airtable_api::Record<#new_struct_name>>,
+            to_delete: Vec<airtable_api::Record<#new_struct_name>>,
+        ) -> std::collections::BTreeSet<i32> {
+            let airtable = #new_struct_name::airtable();
+            let table = #new_struct_name::airtable_table();
+            let mut failed: std::collections::BTreeSet<i32> = Default::default();
+
+            for batch in to_create.chunks(airtable_api::MAX_RECORDS_PER_REQUEST) {
+                let ids: Vec<i32> = batch.iter().map(|r| r.fields.id).collect();
+                match airtable.create_records(&table, batch.to_vec()).await {
+                    Ok(_) => println!("[airtable] created ids={:?} in {}", ids, table),
+                    Err(e) => {
+                        println!("[airtable] creating ids={:?} in {} failed: {}", ids, table, e);
+                        failed.extend(ids);
+                    }
+                }
+            }
+
+            for batch in to_update.chunks(airtable_api::MAX_RECORDS_PER_REQUEST) {
+                let ids: Vec<i32> = batch.iter().map(|r| r.fields.id).collect();
+                match airtable.update_records(&table, batch.to_vec()).await {
+                    Ok(_) => println!("[airtable] updated ids={:?} in {}", ids, table),
+                    Err(e) => {
+                        println!("[airtable] updating ids={:?} in {} failed: {}", ids, table, e);
+                        failed.extend(ids);
+                    }
+                }
+            }
+
+            for batch in to_delete.chunks(airtable_api::MAX_RECORDS_PER_REQUEST) {
+                let ids: Vec<i32> = batch.iter().map(|r| r.fields.id).collect();
+                let record_ids: Vec<String> = batch.iter().map(|r| r.id.to_string()).collect();
+                match airtable.delete_records(&table, &record_ids).await {
+                    Ok(_) => println!("[airtable] deleted ids={:?} from {}", ids, table),
+                    Err(e) => {
+                        println!("[airtable] deleting records {:?} from {} failed: {}", record_ids, table, e);
+                        failed.extend(ids);
+                    }
+                }
             }
+
+            failed
         }
     }
     };
@@ -445,12 +998,56 @@ fn do_db(attr: TokenStream, item: TokenStream) -> TokenStream {
             #[serde(default)]
             pub id: i32,
             #(#fields),*,
-            // This has to be the last field, due to the schemas.
+            // These have to be the last fields, due to the schemas.
             #[serde(default, skip_serializing_if = "String::is_empty")]
             pub airtable_record_id: String,
+            /// When the record was last synced with Airtable, to tell if it was
+            /// edited in Airtable since. This is never sent to Airtable.
+            #[serde(skip)]
+            pub airtable_last_synced_at: Option<chrono::DateTime<chrono::Utc>>,
         }
 
         #airtable
     );
     new_struct
 }
+
+/// Get the `rename` and `alias` serde attributes of a field, so the field has the
+/// same name in a patch as it does in the record.
+fn serde_names(field: &Field) -> Vec<syn::MetaNameValue> {
+    let mut names: Vec<syn::MetaNameValue> = Default::default();
+    for attr in field.attrs.iter().filter(|a| a.path.is_ident("serde")) {
+        if let Ok(syn::Meta::List(list)) = attr.parse_meta() {
+            for nested in list.nested {
+                if let syn::NestedMeta::Meta(syn::Meta::NameValue(nv)) = nested {
+                    if nv.path.is_ident("rename") || nv.path.is_ident("alias") {
+                        names.push(nv);
+                    }
+                }
+            }
+        }
+    }
+
+    names
+}
+
+/// Get the name a field is serialized with.
+fn serialized_name(field: &Field) -> String {
+    for nv in serde_names(field) {
+        if nv.path.is_ident("rename") {
+            if let syn::Lit::Str(s) = nv.lit {
+                return s.value();
+            }
+        }
+    }
+
+    field.ident.as_ref().map(|i| i.to_string()).unwrap_or_default()
+}
+
+/// Returns true if the type is an `Option`.
+fn is_option(ty: &Type) -> bool {
+    match ty {
+        Type::Path(p) => p.qself.is_none() && p.path.segments.last().map(|s| s.ident == "Option").unwrap_or(false),
+        _ => false,
+    }
+}
@@ -35,14 +35,17 @@ use serde::{Deserialize, Serialize};
 pub struct Okta {
     key: String,
     domain: String,
+    base_url: String,
 
-    client: Arc<Client>,
+    client: Arc<cio_http::Client>,
 }
 
 impl Okta {
     /// Create a new Okta client struct. It takes a type that can convert into
     /// an &str (`String` or `Vec<u8>` for example). As long as the function is
     /// given a valid API Key your requests will work.
+    /// The client talks to the base URL in the OKTA_BASE_URL env variable
+    /// if it is set, see `with_base_url`.
     pub fn new<K, D>(key: K, domain: D) -> Self
     where
         K: ToString,
@@ -53,8 +56,9 @@ impl Okta {
             Ok(c) => Self {
                 key: key.to_string(),
                 domain: domain.to_string(),
+                base_url: env::var("OKTA_BASE_URL").unwrap_or_default(),
 
-                client: Arc::new(c),
+                client: Arc::new(cio_http::Client::new(c)),
             },
             Err(e) => panic!("creating client failed: {:?}", e),
         }
@@ -71,6 +75,16 @@ impl Okta {
         Okta::new(key, domain)
     }
 
+    /// Send requests to another base URL, like a fake server in tests, instead
+    /// of the Okta API.
+    pub fn with_base_url<U>(mut self, base_url: U) -> Self
+    where
+        U: ToString,
+    {
+        self.base_url = base_url.to_string();
+        self
+    }
+
     /// Get the currently set API key.
     pub fn get_key(&self) -> &str {
         &self.key
@@ -92,7 +106,7 @@ impl Okta {
         );
 
         // Build the url.
-        let base = Url::parse(&endpoint).unwrap();
+        let base = Url::parse(&cio_http::rebase(&endpoint, &self.base_url)).unwrap();
         let mut p = path.to_string();
         // Make sure we have the leading "/".
         if !p.starts_with('/') {
@@ -187,6 +201,29 @@ impl Okta {
         Ok(result)
     }
 
+    /// Deactivate a user.
+    /// This is the first step in deleting a user, the user can no longer sign in
+    /// and all of their sessions are removed.
+    /// FROM: https://developer.okta.com/docs/reference/api/users/#deactivate-user
+    pub async fn deactivate_user(&self, user_id: &str) -> Result<(), APIError> {
+        // Build the request.
+        let rb = self.request(Method::POST, format!("/api/v1/users/{}/lifecycle/deactivate", user_id), ());
+        let request = rb.build().unwrap();
+
+        let resp = self.client.execute(request).await.unwrap();
+        match resp.status() {
+            StatusCode::OK => (),
+            s => {
+                return Err(APIError {
+                    status_code: s,
+                    body: resp.text().await.unwrap(),
+                })
+            }
+        };
+
+        Ok(())
+    }
+
     /// Update a user.
     pub async fn update_user(&self, profile: Profile) -> Result<User, APIError> {
         // First we need to get the user to get their user_id.
@@ -0,0 +1,303 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::process::Command;
+use std::str::from_utf8;
+
+use cio_api::print_jobs::PrintJobStatus;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// A printer, as CUPS reports it.
+#[derive(Debug, Clone, Default, PartialEq, JsonSchema, Serialize)]
+pub struct Printer {
+    /// The name of the CUPS queue, like `Zebra_ZD420`.
+    pub name: String,
+    /// `idle`, `printing` or `disabled`.
+    pub status: String,
+    pub accepting_jobs: bool,
+    /// The reasons CUPS gives for the state of the printer, like
+    /// `offline-report` or `media-empty-error`.
+    pub alerts: Vec<String>,
+    /// The media the printer prints on when a job does not say, like
+    /// `w288h432`.
+    pub media: String,
+    /// Whether jobs sent to the printer will print.
+    pub online: bool,
+}
+
+/// Get every printer CUPS knows about.
+pub fn get_printers() -> Result<Vec<Printer>, String> {
+    let mut printers = parse_printers(&run("lpstat", &["-l", "-p"])?);
+    let accepting = parse_accepting(&run("lpstat", &["-a"])?);
+
+    for printer in printers.iter_mut() {
+        printer.accepting_jobs = accepting.contains(&printer.name);
+        printer.online = printer.status != "disabled" && printer.accepting_jobs && !printer.alerts.iter().any(|a| a.starts_with("offline"));
+        // Not every driver has options, so this is not worth failing over.
+        printer.media = run("lpoptions", &["-p", &printer.name, "-l"]).map(|o| parse_default_media(&o)).unwrap_or_default();
+    }
+
+    Ok(printers)
+}
+
+/// Send a file to a printer with the `lp` options for it, and return the id
+/// CUPS gave the job.
+pub fn print_file(printer: &str, file: &str, options: &[String], copies: i32) -> Result<String, String> {
+    println!("Sending file `{}` to printer `{}`", file, printer);
+    let copies = format!("{}", copies);
+    let mut args = vec!["-d", printer, "-n", &copies];
+    for option in options {
+        args.push("-o");
+        args.push(option);
+    }
+    args.push(file);
+    let output = run("lp", &args)?;
+
+    println!("Printing: {}", output);
+    parse_request_id(&output).ok_or_else(|| format!("[lp] no request id in output: {}", output))
+}
+
+/// Send a file the printer reads as is, like ZPL for a Zebra, to a printer,
+/// and return the id CUPS gave the job.
+pub fn print_raw_file(printer: &str, file: &str, copies: i32) -> Result<String, String> {
+    println!("Sending raw file `{}` to printer `{}`", file, printer);
+    let output = run("lp", &["-d", printer, "-n", &format!("{}", copies), "-o", "raw", file])?;
+
+    println!("Printing: {}", output);
+    parse_request_id(&output).ok_or_else(|| format!("[lp] no request id in output: {}", output))
+}
+
+/// Get the ids of the jobs CUPS has not finished, on every printer.
+pub fn get_active_jobs() -> Result<BTreeSet<String>, String> {
+    Ok(parse_job_ids(&run("lpstat", &["-o"])?))
+}
+
+/// Get how the jobs CUPS has finished ended, by their id, with the reasons
+/// CUPS gives if they did not print.
+pub fn get_finished_jobs() -> Result<BTreeMap<String, (PrintJobStatus, String)>, String> {
+    Ok(parse_finished_jobs(&run("lpstat", &["-l", "-W", "completed", "-o"])?))
+}
+
+/// Cancel a job.
+pub fn cancel_job(cups_job_id: &str) -> Result<(), String> {
+    run("cancel", &[cups_job_id]).map(|_| ())
+}
+
+/// Run a CUPS command and return its output.
+fn run(cmd: &str, args: &[&str]) -> Result<String, String> {
+    let output = Command::new(cmd).args(args).output().map_err(|e| format!("[{}] failed to execute process: {}", cmd, e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "[{}] stderr: {}\nstdout: {}",
+            cmd,
+            from_utf8(&output.stderr).unwrap_or_default(),
+            from_utf8(&output.stdout).unwrap_or_default()
+        ));
+    }
+
+    Ok(from_utf8(&output.stdout).unwrap_or_default().to_string())
+}
+
+/// Parse the job id out of the output of `lp`, like
+/// `request id is Zebra-12 (1 file(s))`.
+fn parse_request_id(output: &str) -> Option<String> {
+    let (_, rest) = output.split_once("request id is ")?;
+    rest.split_whitespace().next().map(|id| id.to_string())
+}
+
+/// Parse the printers out of the output of `lpstat -l -p`, like
+/// ```text
+/// printer Zebra_ZD420 is idle.  enabled since Mon 28 Jun 2021 10:00:00 AM PDT
+///         Alerts: offline-report
+/// ```
+fn parse_printers(output: &str) -> Vec<Printer> {
+    let mut printers: Vec<Printer> = Default::default();
+    for line in output.lines() {
+        if let Some(rest) = line.strip_prefix("printer ") {
+            let name = rest.split_whitespace().next().unwrap_or_default();
+            let status = if rest.contains(" disabled ") {
+                "disabled"
+            } else if rest.contains(" now printing ") {
+                "printing"
+            } else {
+                "idle"
+            };
+            printers.push(Printer {
+                name: name.to_string(),
+                status: status.to_string(),
+                ..Default::default()
+            });
+        } else if let (Some(printer), Some(alerts)) = (printers.last_mut(), line.trim().strip_prefix("Alerts:")) {
+            printer.alerts = alerts.split_whitespace().filter(|a| *a != "none").map(|a| a.to_string()).collect();
+        }
+    }
+
+    printers
+}
+
+/// Parse the names of the printers taking jobs out of the output of
+/// `lpstat -a`, like `Zebra_ZD420 accepting requests since Mon 28 Jun 2021`.
+fn parse_accepting(output: &str) -> BTreeSet<String> {
+    output
+        .lines()
+        .filter(|line| line.contains(" accepting requests") && !line.contains(" not accepting requests"))
+        .filter_map(|line| line.split_whitespace().next())
+        .map(|name| name.to_string())
+        .collect()
+}
+
+/// Parse the default media out of the output of `lpoptions -l`, where the
+/// default is marked with a `*`, like `PageSize/Media Size: w162h90 *w288h432`.
+fn parse_default_media(output: &str) -> String {
+    for line in output.lines() {
+        let (option, values) = match line.split_once(':') {
+            Some(o) => o,
+            None => continue,
+        };
+        let option = option.split('/').next().unwrap_or_default();
+        if option != "PageSize" && option != "media" {
+            continue;
+        }
+        if let Some(media) = values.split_whitespace().find_map(|v| v.strip_prefix('*')) {
+            return media.to_string();
+        }
+    }
+
+    Default::default()
+}
+
+/// Parse the job ids out of the output of `lpstat -o`, where each line starts
+/// with a job id, like `Zebra-12  jess  1024  Mon 28 Jun 2021 10:00:00 AM PDT`.
+fn parse_job_ids(output: &str) -> BTreeSet<String> {
+    output
+        .lines()
+        .filter(|line| !line.starts_with(char::is_whitespace))
+        .filter_map(|line| line.split_whitespace().next())
+        .map(|id| id.to_string())
+        .collect()
+}
+
+/// Parse the finished jobs out of the output of `lpstat -l -W completed -o`,
+/// where the job state reasons are on the `Alerts:` line under each job, like
+/// ```text
+/// Zebra-12  jess  1024  Mon 28 Jun 2021 10:00:00 AM PDT
+///         Alerts: job-completed-successfully
+///         queued for Zebra
+/// ```
+fn parse_finished_jobs(output: &str) -> BTreeMap<String, (PrintJobStatus, String)> {
+    let mut jobs: BTreeMap<String, (PrintJobStatus, String)> = Default::default();
+    let mut id = "";
+    for line in output.lines() {
+        if !line.starts_with(char::is_whitespace) {
+            id = line.split_whitespace().next().unwrap_or_default();
+            if !id.is_empty() {
+                jobs.insert(id.to_string(), (PrintJobStatus::Completed, String::new()));
+            }
+        } else if let Some(alerts) = line.trim().strip_prefix("Alerts:") {
+            let reasons: Vec<&str> = alerts.split_whitespace().filter(|a| *a != "none").collect();
+            let status = if reasons.iter().any(|r| r.contains("canceled")) {
+                PrintJobStatus::Cancelled
+            } else if reasons.iter().any(|r| r.contains("aborted") || r.contains("error")) {
+                PrintJobStatus::Failed
+            } else {
+                PrintJobStatus::Completed
+            };
+            let error = if status == PrintJobStatus::Completed { String::new() } else { reasons.join(" ") };
+            if let Some(job) = jobs.get_mut(id) {
+                *job = (status, error);
+            }
+        }
+    }
+
+    jobs
+}
+
+#[cfg(test)]
+mod tests {
+    use cio_api::print_jobs::PrintJobStatus;
+
+    use crate::cups::{parse_accepting, parse_default_media, parse_finished_jobs, parse_job_ids, parse_printers, parse_request_id, Printer};
+
+    #[test]
+    fn test_parse_cups_output() {
+        assert_eq!(parse_request_id("request id is Zebra_ZD420-12 (1 file(s))\n"), Some("Zebra_ZD420-12".to_string()));
+        assert_eq!(parse_request_id(""), None);
+
+        let ids = parse_job_ids("Rollo-3                 jess           1024   Mon 28 Jun 2021 10:00:00 AM PDT\nRollo-4   jess   2048   Mon 28 Jun 2021 10:01:00 AM PDT\n");
+        assert_eq!(ids.into_iter().collect::<Vec<_>>(), vec!["Rollo-3", "Rollo-4"]);
+        assert!(parse_job_ids("").is_empty());
+
+        let finished = parse_finished_jobs(
+            "Rollo-3                 jess           1024   Mon 28 Jun 2021 10:00:00 AM PDT
+	Alerts: job-completed-successfully
+	queued for Rollo
+Rollo-4                 jess           2048   Mon 28 Jun 2021 10:01:00 AM PDT
+	Alerts: job-canceled-by-user
+	queued for Rollo
+Zebra-5                 jess           2048   Mon 28 Jun 2021 10:02:00 AM PDT
+	Status: The printer is not responding.
+	Alerts: aborted-by-system
+	queued for Zebra
+Zebra-6                 jess           2048   Mon 28 Jun 2021 10:03:00 AM PDT
+",
+        );
+        assert_eq!(
+            finished.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("Rollo-3".to_string(), (PrintJobStatus::Completed, "".to_string())),
+                ("Rollo-4".to_string(), (PrintJobStatus::Cancelled, "job-canceled-by-user".to_string())),
+                ("Zebra-5".to_string(), (PrintJobStatus::Failed, "aborted-by-system".to_string())),
+                ("Zebra-6".to_string(), (PrintJobStatus::Completed, "".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_cups_printers() {
+        let printers = parse_printers(
+            "printer Rollo is idle.  enabled since Mon 28 Jun 2021 10:00:00 AM PDT
+\tForm mounted:
+\tAlerts: none
+\tDescription: Rollo Printer
+printer Zebra_ZD420 disabled since Mon 28 Jun 2021 10:00:00 AM PDT -
+\tPaused
+\tAlerts: offline-report paused
+printer Zebra_ZD421 now printing Zebra_ZD421-12.  enabled since Mon 28 Jun 2021 10:00:00 AM PDT
+",
+        );
+        assert_eq!(
+            printers,
+            vec![
+                Printer {
+                    name: "Rollo".to_string(),
+                    status: "idle".to_string(),
+                    ..Default::default()
+                },
+                Printer {
+                    name: "Zebra_ZD420".to_string(),
+                    status: "disabled".to_string(),
+                    alerts: vec!["offline-report".to_string(), "paused".to_string()],
+                    ..Default::default()
+                },
+                Printer {
+                    name: "Zebra_ZD421".to_string(),
+                    status: "printing".to_string(),
+                    ..Default::default()
+                },
+            ]
+        );
+
+        let accepting = parse_accepting(
+            "Rollo accepting requests since Mon 28 Jun 2021 10:00:00 AM PDT
+Zebra_ZD420 not accepting requests since Mon 28 Jun 2021 10:00:00 AM PDT -
+\tPaused
+",
+        );
+        assert_eq!(accepting.into_iter().collect::<Vec<_>>(), vec!["Rollo"]);
+
+        assert_eq!(
+            parse_default_media("PageSize/Media Size: w162h90 *w288h432 Custom.WIDTHxHEIGHT\nResolution/Resolution: *203dpi\n"),
+            "w288h432"
+        );
+        assert_eq!(parse_default_media("Resolution/Resolution: *203dpi\n"), "");
+    }
+}
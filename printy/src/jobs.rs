@@ -0,0 +1,226 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use chrono::{Duration, Utc};
+use cio_api::print_jobs::{PrintJob, PrintJobSource, PrintJobStatus};
+use uuid::Uuid;
+
+use crate::printers::LabelType;
+
+/// How long we keep jobs that are done.
+const KEEP_DONE_JOBS: i64 = 30;
+
+/// The label a job prints.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Label<'a> {
+    /// The URL of a PDF.
+    Url(&'a str),
+    /// ZPL, sent to the printer as is.
+    Zpl(&'a str),
+}
+
+impl<'a> Label<'a> {
+    /// Get the label of a job.
+    pub fn of(job: &'a PrintJob) -> Self {
+        if job.zpl.is_empty() {
+            Label::Url(&job.url)
+        } else {
+            Label::Zpl(&job.zpl)
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Label::Url(s) | Label::Zpl(s) => s.trim().is_empty(),
+        }
+    }
+
+    pub fn is_zpl(&self) -> bool {
+        matches!(self, Label::Zpl(_))
+    }
+}
+
+/// The print jobs we have accepted, saved to a file after every change so they
+/// survive printy restarting.
+pub struct JobQueue {
+    path: PathBuf,
+    jobs: Mutex<BTreeMap<String, PrintJob>>,
+    /// The ids of the jobs whose latest state webhooky has not heard about. These
+    /// are saved next to the jobs, so we still report them after a restart.
+    unreported: Mutex<BTreeSet<String>>,
+}
+
+impl JobQueue {
+    /// Open the queue saved at the path, or start an empty one if there is none.
+    pub fn open(path: PathBuf) -> Self {
+        let jobs = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str::<Vec<PrintJob>>(&contents).unwrap_or_else(|e| panic!("decoding the jobs in `{}` failed: {}", path.display(), e)),
+            Err(_) => Default::default(),
+        };
+        println!("Loaded {} print jobs from `{}`", jobs.len(), path.display());
+
+        let unreported_path = unreported_path(&path);
+        let unreported = match fs::read_to_string(&unreported_path) {
+            Ok(contents) => serde_json::from_str::<BTreeSet<String>>(&contents).unwrap_or_else(|e| panic!("decoding the unreported jobs in `{}` failed: {}", unreported_path.display(), e)),
+            Err(_) => Default::default(),
+        };
+
+        JobQueue {
+            path,
+            jobs: Mutex::new(jobs.into_iter().map(|j| (j.id.to_string(), j)).collect()),
+            unreported: Mutex::new(unreported),
+        }
+    }
+
+    /// Add a job for a printer. The job gets the id the client picked, if any, so
+    /// the client can save it before we report on the job. Adding a job with an
+    /// id we already have returns that job, so the client can retry.
+    pub fn add(&self, id: &str, printer: &str, label_type: LabelType, label: Label, copies: i32, source: Option<PrintJobSource>) -> PrintJob {
+        if let Some(job) = self.get(id) {
+            return job;
+        }
+
+        let (url, zpl) = match label {
+            Label::Url(url) => (url.trim(), ""),
+            Label::Zpl(zpl) => ("", zpl.trim()),
+        };
+        let now = Utc::now();
+        let job = PrintJob {
+            id: if id.is_empty() { Uuid::new_v4().to_string() } else { id.to_string() },
+            printer: printer.to_string(),
+            label_type: label_type.to_string(),
+            url: url.to_string(),
+            zpl: zpl.to_string(),
+            copies,
+            status: PrintJobStatus::Queued,
+            cups_job_id: Default::default(),
+            error: Default::default(),
+            source,
+            reprint_of: Default::default(),
+            created_at: now,
+            updated_at: now,
+        };
+
+        self.insert(job)
+    }
+
+    /// Add a job that prints the label of a job again, on a printer.
+    pub fn reprint(&self, job: &PrintJob, printer: &str, label_type: LabelType) -> PrintJob {
+        let now = Utc::now();
+        self.insert(PrintJob {
+            id: Uuid::new_v4().to_string(),
+            printer: printer.to_string(),
+            label_type: label_type.to_string(),
+            status: PrintJobStatus::Queued,
+            cups_job_id: Default::default(),
+            error: Default::default(),
+            reprint_of: job.id.to_string(),
+            created_at: now,
+            updated_at: now,
+            ..job.clone()
+        })
+    }
+
+    fn insert(&self, job: PrintJob) -> PrintJob {
+        let mut jobs = self.jobs.lock().unwrap();
+        // Two requests with the same id can race, the first one wins.
+        let job = jobs.entry(job.id.to_string()).or_insert(job).clone();
+        self.save(&mut jobs);
+        job
+    }
+
+    /// Get a job.
+    pub fn get(&self, id: &str) -> Option<PrintJob> {
+        self.jobs.lock().unwrap().get(id).cloned()
+    }
+
+    /// Get every job, newest first.
+    pub fn list(&self) -> Vec<PrintJob> {
+        let mut jobs: Vec<PrintJob> = self.jobs.lock().unwrap().values().cloned().collect();
+        jobs.sort_by_key(|j| Reverse(j.created_at));
+        jobs
+    }
+
+    /// Get the jobs with a status, oldest first.
+    pub fn with_status(&self, status: PrintJobStatus) -> Vec<PrintJob> {
+        let mut jobs: Vec<PrintJob> = self.jobs.lock().unwrap().values().filter(|j| j.status == status).cloned().collect();
+        jobs.sort_by_key(|j| j.created_at);
+        jobs
+    }
+
+    /// Change the status of a job, and return it if it changed.
+    pub fn set_status(&self, id: &str, status: PrintJobStatus, cups_job_id: &str, error: &str) -> Option<PrintJob> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let job = jobs.get_mut(id)?;
+        if job.status == status && job.error == error && (cups_job_id.is_empty() || job.cups_job_id == cups_job_id) {
+            return None;
+        }
+
+        job.status = status;
+        job.error = error.to_string();
+        if !cups_job_id.is_empty() {
+            job.cups_job_id = cups_job_id.to_string();
+        }
+        job.updated_at = Utc::now();
+        let job = job.clone();
+
+        self.save(&mut jobs);
+        Some(job)
+    }
+
+    /// Save whether webhooky has heard about the latest state of a job.
+    pub fn set_reported(&self, id: &str, reported: bool) {
+        let mut unreported = self.unreported.lock().unwrap();
+        let changed = if reported { unreported.remove(id) } else { unreported.insert(id.to_string()) };
+        if changed {
+            self.save_unreported(&unreported);
+        }
+    }
+
+    /// Get the jobs whose latest state webhooky has not heard about.
+    pub fn unreported(&self) -> Vec<PrintJob> {
+        let mut unreported = self.unreported.lock().unwrap();
+        let jobs = self.jobs.lock().unwrap();
+        // Forget the jobs we have dropped.
+        let count = unreported.len();
+        unreported.retain(|id| jobs.contains_key(id));
+        if unreported.len() != count {
+            self.save_unreported(&unreported);
+        }
+        unreported.iter().map(|id| jobs[id].clone()).collect()
+    }
+
+    /// Write the jobs to the file, dropping the ones that have been done for a while.
+    fn save(&self, jobs: &mut BTreeMap<String, PrintJob>) {
+        let cutoff = Utc::now() - Duration::days(KEEP_DONE_JOBS);
+        jobs.retain(|_, j| !j.status.is_done() || j.updated_at > cutoff);
+
+        let contents = serde_json::to_string_pretty(&jobs.values().collect::<Vec<_>>()).unwrap();
+        write_file(&self.path, &contents, "print jobs");
+    }
+
+    /// Write the ids of the jobs webhooky has not heard about to their file.
+    fn save_unreported(&self, unreported: &BTreeSet<String>) {
+        let contents = serde_json::to_string_pretty(unreported).unwrap();
+        write_file(&unreported_path(&self.path), &contents, "unreported print jobs");
+    }
+}
+
+/// The file the unreported jobs are saved to, next to the jobs file.
+fn unreported_path(path: &Path) -> PathBuf {
+    path.with_extension("unreported.json")
+}
+
+/// Write to a temporary file and move it into place, so we never leave a half
+/// written file behind.
+fn write_file(path: &Path, contents: &str, what: &str) {
+    let tmp = path.with_extension("tmp");
+    if let Err(e) = fs::write(&tmp, contents).and_then(|_| fs::rename(&tmp, path)) {
+        let e = format!("saving the {} to `{}` failed: {}", what, path.display(), e);
+        println!("{}", e);
+        sentry::capture_message(&e, sentry::Level::Fatal);
+    }
+}
@@ -1,15 +1,30 @@
+mod cups;
+mod jobs;
+mod printers;
+
 use std::env;
 use std::fs::File;
 use std::io::Write;
+use std::path::PathBuf;
 use std::process::Command;
-use std::str::from_utf8;
 use std::sync::Arc;
+use std::time::Duration;
 
-use cio_api::swag_inventory::PrintLabelsRequest;
-use dropshot::{endpoint, ApiDescription, ConfigDropshot, ConfigLogging, ConfigLoggingLevel, HttpError, HttpResponseAccepted, HttpResponseOk, HttpServerStarter, RequestContext, TypedBody};
+use cio_api::print_jobs::{PrintJob, PrintJobSource, PrintJobStatus};
+use cio_api::swag_inventory::{PrintLabelsRequest, PrintZplRequest};
+use dropshot::{endpoint, ApiDescription, ConfigDropshot, ConfigLogging, ConfigLoggingLevel, HttpError, HttpResponseAccepted, HttpResponseOk, HttpServerStarter, Path, RequestContext, TypedBody};
+use schemars::JsonSchema;
 use sentry::IntoDsn;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Notify;
 use uuid::Uuid;
 
+use crate::jobs::{JobQueue, Label};
+use crate::printers::{LabelType, PrinterProfile, PrinterProfiles};
+
+/// How long to wait before checking on the jobs CUPS is printing again.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
 #[tokio::main]
 async fn main() -> Result<(), String> {
     // Try to get the current git hash.
@@ -60,12 +75,18 @@ async fn main() -> Result<(), String> {
      * allowing this metadata to live right alongside the handler function.
      */
     api.register(ping).unwrap();
+    api.register(api_cancel_job).unwrap();
+    api.register(api_get_job).unwrap();
+    api.register(api_get_jobs).unwrap();
+    api.register(api_get_printers).unwrap();
+    api.register(api_reprint_job).unwrap();
     api.register(listen_print_rollo_requests).unwrap();
     api.register(listen_print_zebra_requests).unwrap();
+    api.register(listen_print_zebra_zpl_requests).unwrap();
 
     let mut api_definition = &mut api.openapi(&"Print API", &"0.0.1");
     api_definition = api_definition
-        .description("Internal API server for printing shipping labels on a Rollo printer")
+        .description("Internal API server for printing shipping labels and barcode labels on our label printers")
         .contact_url("https://oxide.computer")
         .contact_email("printy@oxide.computer");
     let api_file = "openapi-printy.json";
@@ -79,6 +100,9 @@ async fn main() -> Result<(), String> {
      */
     let api_context = Context::new(schema).await;
 
+    // Send the queued jobs to CUPS and keep track of them until they are done.
+    tokio::spawn(run_jobs(api_context.jobs.clone(), api_context.jobs_added.clone(), api_context.profiles.clone()));
+
     /*
      * Set up the server.
      */
@@ -94,6 +118,11 @@ async fn main() -> Result<(), String> {
  */
 struct Context {
     schema: String,
+    jobs: Arc<JobQueue>,
+    /// Wakes up the worker when a job is added, so it does not wait for the
+    /// next poll to send it to CUPS.
+    jobs_added: Arc<Notify>,
+    profiles: Arc<PrinterProfiles>,
 }
 
 impl Context {
@@ -101,7 +130,15 @@ impl Context {
      * Return a new Context.
      */
     pub async fn new(schema: String) -> Context {
-        Context { schema }
+        let jobs_file = env::var("PRINTY_JOBS_FILE").unwrap_or_else(|_| "printy-jobs.json".to_string());
+        let config_file = env::var("PRINTY_CONFIG_FILE").unwrap_or_else(|_| "printy.toml".to_string());
+
+        Context {
+            schema,
+            jobs: Arc::new(JobQueue::open(PathBuf::from(jobs_file))),
+            jobs_added: Arc::new(Notify::new()),
+            profiles: Arc::new(PrinterProfiles::load(&PathBuf::from(config_file))),
+        }
     }
 }
 
@@ -131,127 +168,324 @@ async fn ping(_rqctx: Arc<RequestContext<Context>>) -> Result<HttpResponseOk<Str
     Ok(HttpResponseOk("pong".to_string()))
 }
 
-/** Listen for print requests for the Rollo label printer */
+/** Listen for requests to print shipping labels, which go to the Rollo unless the printer profiles say otherwise */
 #[endpoint {
     method = POST,
     path = "/print/rollo",
 }]
-async fn listen_print_rollo_requests(_rqctx: Arc<RequestContext<Context>>, body_param: TypedBody<String>) -> Result<HttpResponseAccepted<String>, HttpError> {
-    sentry::start_session();
-    let url = body_param.into_inner();
-    let printer = get_printer("rollo");
-    println!("{:?}", printer);
-
-    if !url.trim().is_empty() {
-        // Save the contents of our URL to a file.
-        let file = save_url_to_file(url).await;
-
-        // Print the file.
-        print_file(&printer, &file, "4.00x6.00", 1);
-    }
+async fn listen_print_rollo_requests(rqctx: Arc<RequestContext<Context>>, body_param: TypedBody<PrintLabelsRequest>) -> Result<HttpResponseAccepted<PrintJob>, HttpError> {
+    let r = body_param.into_inner();
 
-    // Print the body to the rollo printer.
-    sentry::end_session();
-    Ok(HttpResponseAccepted("ok".to_string()))
+    // Shipping labels are printed one at a time.
+    queue_job(rqctx.context(), &r.job_id, LabelType::Shipping, Label::Url(&r.url), 1, r.source)
 }
 
-/** Listen for print requests for the Zebra label printer */
+/** Listen for requests to print barcode labels, which go to the Zebra unless the printer profiles say otherwise */
 #[endpoint {
     method = POST,
     path = "/print/zebra",
 }]
-async fn listen_print_zebra_requests(_rqctx: Arc<RequestContext<Context>>, body_param: TypedBody<PrintLabelsRequest>) -> Result<HttpResponseAccepted<String>, HttpError> {
-    sentry::start_session();
+async fn listen_print_zebra_requests(rqctx: Arc<RequestContext<Context>>, body_param: TypedBody<PrintLabelsRequest>) -> Result<HttpResponseAccepted<PrintJob>, HttpError> {
+    let r = body_param.into_inner();
+
+    queue_job(rqctx.context(), &r.job_id, LabelType::Barcode, Label::Url(&r.url), r.quantity, r.source)
+}
+
+/** Listen for requests to print barcode labels written in ZPL, on a printer profile that takes ZPL */
+#[endpoint {
+    method = POST,
+    path = "/print/zebra/zpl",
+}]
+async fn listen_print_zebra_zpl_requests(rqctx: Arc<RequestContext<Context>>, body_param: TypedBody<PrintZplRequest>) -> Result<HttpResponseAccepted<PrintJob>, HttpError> {
     let r = body_param.into_inner();
-    let printer = get_printer("zebra");
-    println!("{:?}", printer);
 
-    if !r.url.trim().is_empty() && r.quantity > 0 {
-        // Save the contents of our URL to a file.
-        let file = save_url_to_file(r.url).await;
+    queue_job(rqctx.context(), &r.job_id, LabelType::Barcode, Label::Zpl(&r.zpl), r.quantity, r.source)
+}
+
+/// The printers CUPS knows about, and where the jobs for each profile go.
+#[derive(Debug, Clone, JsonSchema, Serialize)]
+struct PrintersResponse {
+    printers: Vec<cups::Printer>,
+    profiles: Vec<ProfileStatus>,
+}
+
+/// A printer profile, and whether it has a printer to print to.
+#[derive(Debug, Clone, JsonSchema, Serialize)]
+struct ProfileStatus {
+    #[serde(flatten)]
+    profile: PrinterProfile,
+    /// The CUPS printer the jobs for the profile are sent to.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    printer: String,
+    /// Why jobs can't be sent to the profile right now.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    error: String,
+}
+
+/** List the printers, with the status, media and online state CUPS reports for each. */
+#[endpoint {
+    method = GET,
+    path = "/printers",
+}]
+async fn api_get_printers(rqctx: Arc<RequestContext<Context>>) -> Result<HttpResponseOk<PrintersResponse>, HttpError> {
+    let printers = cups::get_printers().map_err(HttpError::for_internal_error)?;
+
+    let profiles = rqctx
+        .context()
+        .profiles
+        .printers
+        .iter()
+        .map(|profile| {
+            let (printer, error) = match profile.available_printer(&printers) {
+                Ok(p) => (p.name.to_string(), String::new()),
+                Err(e) => (String::new(), e),
+            };
+            ProfileStatus {
+                profile: profile.clone(),
+                printer,
+                error,
+            }
+        })
+        .collect();
+
+    Ok(HttpResponseOk(PrintersResponse { printers, profiles }))
+}
+
+/// Path parameters for a single job.
+#[derive(Debug, Clone, JsonSchema, Deserialize)]
+struct JobPathParams {
+    /// The id of the job.
+    id: String,
+}
+
+/** List the print jobs, newest first. */
+#[endpoint {
+    method = GET,
+    path = "/jobs",
+}]
+async fn api_get_jobs(rqctx: Arc<RequestContext<Context>>) -> Result<HttpResponseOk<Vec<PrintJob>>, HttpError> {
+    Ok(HttpResponseOk(rqctx.context().jobs.list()))
+}
+
+/** Get a print job. */
+#[endpoint {
+    method = GET,
+    path = "/jobs/{id}",
+}]
+async fn api_get_job(rqctx: Arc<RequestContext<Context>>, path_params: Path<JobPathParams>) -> Result<HttpResponseOk<PrintJob>, HttpError> {
+    Ok(HttpResponseOk(get_job(rqctx.context(), &path_params.into_inner().id)?))
+}
+
+/** Cancel a print job that has not finished printing. */
+#[endpoint {
+    method = POST,
+    path = "/jobs/{id}/cancel",
+}]
+async fn api_cancel_job(rqctx: Arc<RequestContext<Context>>, path_params: Path<JobPathParams>) -> Result<HttpResponseOk<PrintJob>, HttpError> {
+    let api_context = rqctx.context();
+    let job = get_job(api_context, &path_params.into_inner().id)?;
 
-        // Print the file.
-        print_file(&printer, &file, "3.00x2.00", r.quantity);
+    match job.status {
+        PrintJobStatus::Queued => (),
+        PrintJobStatus::Printing => cups::cancel_job(&job.cups_job_id).map_err(HttpError::for_internal_error)?,
+        status => return Err(HttpError::for_bad_request(None, format!("job `{}` is already {}", job.id, status))),
     }
 
-    // Print the body to the rollo printer.
-    sentry::end_session();
-    Ok(HttpResponseAccepted("ok".to_string()))
+    let job = api_context.jobs.set_status(&job.id, PrintJobStatus::Cancelled, "", "").unwrap_or(job);
+    report_job(&api_context.jobs, &job).await;
+    Ok(HttpResponseOk(job))
+}
+
+/** Print the label of a print job again, as a new job. */
+#[endpoint {
+    method = POST,
+    path = "/jobs/{id}/reprint",
+}]
+async fn api_reprint_job(rqctx: Arc<RequestContext<Context>>, path_params: Path<JobPathParams>) -> Result<HttpResponseAccepted<PrintJob>, HttpError> {
+    let api_context = rqctx.context();
+    let job = get_job(api_context, &path_params.into_inner().id)?;
+
+    let label_type = LabelType::from_job(&job.label_type, &job.printer);
+    let printer = route_job(api_context, label_type, Label::of(&job).is_zpl())?;
+
+    let reprint = api_context.jobs.reprint(&job, &printer, label_type);
+    api_context.jobs_added.notify_one();
+    Ok(HttpResponseAccepted(reprint))
 }
 
-// Return the printer we are looking for.
-fn get_printer(name: &str) -> String {
-    let output = Command::new("lpstat").args(&["-a"]).output().expect("failed to execute process");
-    if !output.status.success() {
-        let e = format!("[lpstat] stderr: {}\nstdout: {}", from_utf8(&output.stderr).unwrap(), from_utf8(&output.stdout).unwrap());
+fn get_job(api_context: &Context, id: &str) -> Result<PrintJob, HttpError> {
+    api_context
+        .jobs
+        .get(id)
+        .ok_or_else(|| HttpError::for_client_error(None, http::StatusCode::NOT_FOUND, format!("job `{}` not found", id)))
+}
+
+// Pick the printer profile for a kind of label. Fails if no printer can
+// print it right now, rather than queueing a job that can't print.
+fn route_job(api_context: &Context, label_type: LabelType, zpl: bool) -> Result<String, HttpError> {
+    let printers = cups::get_printers().map_err(HttpError::for_internal_error)?;
+    let profile = api_context.profiles.route(label_type, zpl, &printers).map_err(|e| {
         println!("{}", e);
         sentry::capture_message(&e, sentry::Level::Fatal);
-        return "".to_string();
+        HttpError::for_unavail(None, e)
+    })?;
+
+    Ok(profile.name.to_string())
+}
+
+// Add a job to the queue, the worker picks it up from there.
+fn queue_job(api_context: &Context, id: &str, label_type: LabelType, label: Label, copies: i32, source: Option<PrintJobSource>) -> Result<HttpResponseAccepted<PrintJob>, HttpError> {
+    if label.is_empty() || copies <= 0 {
+        return Err(HttpError::for_bad_request(None, "a print request needs a label and a quantity of at least 1".to_string()));
+    }
+    let printer = route_job(api_context, label_type, label.is_zpl())?;
+
+    let job = api_context.jobs.add(id, &printer, label_type, label, copies, source);
+    api_context.jobs_added.notify_one();
+    if job.zpl.is_empty() {
+        println!("Queued {} job {} on {} for `{}`", label_type, job.id, printer, job.url);
+    } else {
+        println!("Queued {} job {} on {} for a ZPL label", label_type, job.id, printer);
+    }
+    Ok(HttpResponseAccepted(job))
+}
+
+// Send the queued jobs to CUPS, then check on the ones it is printing until
+// they are done.
+async fn run_jobs(jobs: Arc<JobQueue>, jobs_added: Arc<Notify>, profiles: Arc<PrinterProfiles>) {
+    loop {
+        for job in jobs.with_status(PrintJobStatus::Queued) {
+            let (status, cups_job_id, error) = match send_job_to_cups(&job, &profiles).await {
+                Ok(cups_job_id) => (PrintJobStatus::Printing, cups_job_id, String::new()),
+                Err(e) => {
+                    println!("{}", e);
+                    sentry::capture_message(&e, sentry::Level::Fatal);
+                    (PrintJobStatus::Failed, String::new(), e)
+                }
+            };
+
+            // The job could have been cancelled while we were sending it.
+            if jobs.get(&job.id).map(|j| j.status) != Some(PrintJobStatus::Queued) {
+                if !cups_job_id.is_empty() {
+                    cups::cancel_job(&cups_job_id).unwrap_or_else(|e| println!("{}", e));
+                }
+                continue;
+            }
+            if let Some(job) = jobs.set_status(&job.id, status, &cups_job_id, &error) {
+                report_job(&jobs, &job).await;
+            }
+        }
+
+        let printing = jobs.with_status(PrintJobStatus::Printing);
+        if !printing.is_empty() {
+            match cups::get_active_jobs().and_then(|active| Ok((active, cups::get_finished_jobs()?))) {
+                Ok((active, finished)) => {
+                    for job in printing {
+                        if active.contains(&job.cups_job_id) {
+                            continue;
+                        }
+                        // CUPS only keeps the history of finished jobs for a while, so a
+                        // job it no longer knows about at all is done too.
+                        let (status, error) = finished.get(&job.cups_job_id).cloned().unwrap_or((PrintJobStatus::Completed, String::new()));
+                        if let Some(job) = jobs.set_status(&job.id, status, "", &error) {
+                            println!("{} job {} is {}", job.printer, job.id, job.status_message());
+                            report_job(&jobs, &job).await;
+                        }
+                    }
+                }
+                Err(e) => println!("getting the jobs from CUPS failed: {}", e),
+            }
+        }
+
+        // Send the latest state of the jobs webhooky did not hear about.
+        for job in jobs.unreported() {
+            report_job(&jobs, &job).await;
+        }
+
+        let _ = tokio::time::timeout(POLL_INTERVAL, jobs_added.notified()).await;
     }
+}
+
+// Download the label for a job and send it to the printer of its profile.
+// Returns the id CUPS gave the job.
+async fn send_job_to_cups(job: &PrintJob, profiles: &PrinterProfiles) -> Result<String, String> {
+    let profile = profiles.get(&job.printer).ok_or_else(|| format!("there is no printer profile named `{}`", job.printer))?;
+    let printers = cups::get_printers()?;
+    let printer = profile.available_printer(&printers).map_err(|e| format!("printer `{}` is not available: {}", profile.name, e))?;
+    let copies = job.copies * profile.copies;
+
+    // ZPL goes to the printer as is, so it draws the label at its own
+    // resolution.
+    if !job.zpl.is_empty() {
+        let file = save_to_file(job.zpl.as_bytes(), "zpl")?;
+        return cups::print_raw_file(&printer.name, &file, copies);
+    }
+
+    // Save the contents of our URL to a file.
+    let file = save_url_to_file(&job.url).await?;
+
+    // Print the file.
+    cups::print_file(&printer.name, &file, &profile.lp_options(), copies)
+}
 
-    let os = from_utf8(&output.stdout).unwrap();
-    let printers = os.trim().split('\n');
-    for printer in printers {
-        if printer.to_lowercase().contains(name) {
-            let (p, _r) = printer.split_once(' ').unwrap();
-            return p.to_string();
+// Tell webhooky a job changed, so the record the label is for shows whether
+// it printed. If webhooky does not get it, the worker tries again.
+async fn report_job(jobs: &JobQueue, job: &PrintJob) {
+    match post_job(job).await {
+        Ok(()) => jobs.set_reported(&job.id, true),
+        Err(e) => {
+            println!("reporting job {} failed, trying again later: {}", job.id, e);
+            jobs.set_reported(&job.id, false);
         }
     }
+}
+
+async fn post_job(job: &PrintJob) -> Result<(), String> {
+    let callback_url = env::var("PRINTY_CALLBACK_URL").unwrap_or_default();
+    if job.source.is_none() || callback_url.is_empty() {
+        return Ok(());
+    }
 
-    "".to_string()
+    let client = reqwest::Client::new();
+    let resp = client
+        .post(&callback_url)
+        .header("Content-Type", "application/json")
+        .header("X-Webhook-Token", env::var("PRINTY_WEBHOOK_SECRET").unwrap_or_default())
+        .body(serde_json::to_string(job).unwrap())
+        .send()
+        .await;
+    match resp {
+        Ok(r) if r.status().is_success() => Ok(()),
+        Ok(r) => Err(format!("status_code: {}, body: {}", r.status(), r.text().await.unwrap_or_default())),
+        Err(e) => Err(e.to_string()),
+    }
 }
 
 // Save URL contents to a temporary file.
 // Returns the filepath.
-async fn save_url_to_file(url: String) -> String {
+async fn save_url_to_file(url: &str) -> Result<String, String> {
     println!("Getting contents of URL `{}` to print", url);
-    let body = reqwest::get(&url).await.unwrap().bytes().await.unwrap();
-
-    let mut dir = env::temp_dir();
-    let file_name = format!("{}.pdf", Uuid::new_v4());
-    dir.push(file_name);
-
-    let mut file = File::create(&dir).unwrap();
-    file.write_all(&body).unwrap();
+    let resp = reqwest::get(url).await.map_err(|e| format!("getting `{}` failed: {}", url, e))?;
+    if !resp.status().is_success() {
+        return Err(format!("getting `{}` failed: status_code: {}", url, resp.status()));
+    }
+    let body = resp.bytes().await.map_err(|e| format!("reading `{}` failed: {}", url, e))?;
 
-    let path = dir.to_str().unwrap().to_string();
+    let path = save_to_file(&body, "pdf")?;
     println!("Saved contents of URL to `{}`", path);
 
-    path
+    Ok(path)
 }
 
-// Save URL contents to a temporary file.
+// Save a label to a temporary file.
 // Returns the filepath.
-fn print_file(printer: &str, file: &str, media: &str, copies: i32) {
-    println!("Sending file `{}` to printer `{}`", file, printer);
-    let output = Command::new("lp")
-        .args(&[
-            "-d",
-            printer,
-            "-n",
-            &format!("{}", copies),
-            "-o",
-            "fit-to-page",
-            "-o",
-            &format!("media={}\"", media),
-            "-o",
-            "page-left=0",
-            "-o",
-            "page-right=0",
-            "-o",
-            "page-top=0",
-            "-o",
-            "page-bottom=0",
-            file,
-        ])
-        .output()
-        .expect("failed to execute process");
-    if !output.status.success() {
-        let e = format!("[lpstat] stderr: {}\nstdout: {}", from_utf8(&output.stderr).unwrap(), from_utf8(&output.stdout).unwrap());
-        println!("{}", e);
-        sentry::capture_message(&e, sentry::Level::Fatal);
-        return;
-    }
+fn save_to_file(body: &[u8], extension: &str) -> Result<String, String> {
+    let mut dir = env::temp_dir();
+    let file_name = format!("{}.{}", Uuid::new_v4(), extension);
+    dir.push(file_name);
+
+    let mut file = File::create(&dir).map_err(|e| format!("creating `{}` failed: {}", dir.display(), e))?;
+    file.write_all(body).map_err(|e| format!("writing `{}` failed: {}", dir.display(), e))?;
 
-    println!("Printing: {}", from_utf8(&output.stdout).unwrap());
+    Ok(dir.to_str().unwrap().to_string())
 }
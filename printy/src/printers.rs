@@ -0,0 +1,259 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::cups::Printer;
+
+/// The kinds of labels we print.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, JsonSchema, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LabelType {
+    /// 4x6 shipping labels from Shippo.
+    Shipping,
+    /// 3x2 swag barcode labels.
+    Barcode,
+}
+
+impl LabelType {
+    /// Parse the label type saved on a job. Jobs queued before printy knew
+    /// about label types only have the printer, and the Rollo only printed
+    /// shipping labels.
+    pub fn from_job(label_type: &str, printer: &str) -> Self {
+        match label_type {
+            "shipping" => LabelType::Shipping,
+            "barcode" => LabelType::Barcode,
+            _ if printer == "rollo" => LabelType::Shipping,
+            _ => LabelType::Barcode,
+        }
+    }
+}
+
+impl fmt::Display for LabelType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            LabelType::Shipping => "shipping",
+            LabelType::Barcode => "barcode",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// The margins of a label, in points.
+#[derive(Debug, Clone, Default, PartialEq, JsonSchema, Deserialize, Serialize)]
+pub struct Margins {
+    #[serde(default)]
+    pub top: i32,
+    #[serde(default)]
+    pub right: i32,
+    #[serde(default)]
+    pub bottom: i32,
+    #[serde(default)]
+    pub left: i32,
+}
+
+/// How to print on one of our printers.
+#[derive(Debug, Clone, PartialEq, JsonSchema, Deserialize, Serialize)]
+pub struct PrinterProfile {
+    /// The name jobs are sent to, like `rollo`.
+    pub name: String,
+    /// Part of the name of the CUPS queue, matched without case. This
+    /// defaults to the name of the profile.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub cups_name: String,
+    /// The labels the printer prints.
+    pub label_types: Vec<LabelType>,
+    /// The size of the labels in inches, like `4.00x6.00`.
+    pub media: String,
+    /// The number of copies of every label.
+    #[serde(default = "default_copies")]
+    pub copies: i32,
+    #[serde(default)]
+    pub margins: Margins,
+    /// Whether the printer takes labels written in ZPL.
+    #[serde(default)]
+    pub zpl: bool,
+}
+
+fn default_copies() -> i32 {
+    1
+}
+
+impl PrinterProfile {
+    /// Returns true if the profile prints to the CUPS printer.
+    pub fn matches(&self, printer: &Printer) -> bool {
+        let cups_name = if self.cups_name.is_empty() { &self.name } else { &self.cups_name };
+        printer.name.to_lowercase().contains(&cups_name.to_lowercase())
+    }
+
+    /// Find the CUPS printer for the profile, if it is online.
+    pub fn available_printer<'a>(&self, printers: &'a [Printer]) -> Result<&'a Printer, String> {
+        let printer = printers.iter().find(|p| self.matches(p)).ok_or_else(|| format!("no CUPS printer matches `{}`", self.name))?;
+        if !printer.online {
+            return Err(format!("`{}` is {} ({})", printer.name, printer.status, printer.alerts.join(", ")));
+        }
+
+        Ok(printer)
+    }
+
+    /// The `lp` options for printing a PDF label.
+    pub fn lp_options(&self) -> Vec<String> {
+        vec![
+            "fit-to-page".to_string(),
+            format!("media={}\"", self.media),
+            format!("page-top={}", self.margins.top),
+            format!("page-right={}", self.margins.right),
+            format!("page-bottom={}", self.margins.bottom),
+            format!("page-left={}", self.margins.left),
+        ]
+    }
+}
+
+/// The printer profiles, read from the file at `PRINTY_CONFIG_FILE`, like
+/// ```toml
+/// [[printers]]
+/// name = "rollo"
+/// label_types = ["shipping"]
+/// media = "4.00x6.00"
+/// ```
+///
+/// Jobs go to the first profile in the file that prints their kind of label
+/// and has a printer online.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct PrinterProfiles {
+    pub printers: Vec<PrinterProfile>,
+}
+
+impl Default for PrinterProfiles {
+    /// The Rollo for shipping labels and the Zebra for barcode labels.
+    fn default() -> Self {
+        PrinterProfiles {
+            printers: vec![
+                PrinterProfile {
+                    name: "rollo".to_string(),
+                    cups_name: Default::default(),
+                    label_types: vec![LabelType::Shipping],
+                    media: "4.00x6.00".to_string(),
+                    copies: 1,
+                    margins: Default::default(),
+                    zpl: false,
+                },
+                PrinterProfile {
+                    name: "zebra".to_string(),
+                    cups_name: Default::default(),
+                    label_types: vec![LabelType::Barcode],
+                    media: "3.00x2.00".to_string(),
+                    copies: 1,
+                    margins: Default::default(),
+                    zpl: true,
+                },
+            ],
+        }
+    }
+}
+
+impl PrinterProfiles {
+    /// Read the profiles from a file, or use the defaults if there is none.
+    pub fn load(path: &Path) -> Self {
+        let profiles = match fs::read_to_string(path) {
+            Ok(contents) => toml::from_str(&contents).unwrap_or_else(|e| panic!("decoding the printer profiles in `{}` failed: {}", path.display(), e)),
+            Err(_) => Default::default(),
+        };
+        println!("Using printer profiles: {:?}", profiles);
+        profiles
+    }
+
+    /// Get a profile.
+    pub fn get(&self, name: &str) -> Option<&PrinterProfile> {
+        self.printers.iter().find(|p| p.name == name)
+    }
+
+    /// Pick the profile to print a kind of label with, given the printers
+    /// CUPS has. Returns why not if no printer can print it.
+    pub fn route(&self, label_type: LabelType, zpl: bool, printers: &[Printer]) -> Result<&PrinterProfile, String> {
+        let profiles: Vec<&PrinterProfile> = self.printers.iter().filter(|p| p.label_types.contains(&label_type) && (p.zpl || !zpl)).collect();
+        if profiles.is_empty() {
+            let kind = if zpl { "ZPL " } else { "" };
+            return Err(format!("no printer profile prints {}{} labels", kind, label_type));
+        }
+
+        let mut problems: Vec<String> = Default::default();
+        for profile in profiles {
+            match profile.available_printer(printers) {
+                Ok(_) => return Ok(profile),
+                Err(e) => problems.push(format!("{}: {}", profile.name, e)),
+            }
+        }
+
+        Err(format!("no printer is available for {} labels: {}", label_type, problems.join("; ")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cups::Printer;
+    use crate::printers::{LabelType, PrinterProfiles};
+
+    #[test]
+    fn test_route_printer_profiles() {
+        let profiles: PrinterProfiles = toml::from_str(
+            r#"
+[[printers]]
+name = "zebra"
+label_types = ["barcode"]
+media = "3.00x2.00"
+zpl = true
+
+[[printers]]
+name = "rollo"
+label_types = ["shipping", "barcode"]
+media = "4.00x6.00"
+copies = 2
+margins = { top = 10 }
+"#,
+        )
+        .unwrap();
+        assert_eq!(profiles.get("rollo").unwrap().copies, 2);
+        assert_eq!(profiles.get("rollo").unwrap().margins.top, 10);
+        assert_eq!(profiles.get("zebra").unwrap().copies, 1);
+
+        let mut printers = vec![
+            Printer {
+                name: "Rollo_X1040".to_string(),
+                status: "idle".to_string(),
+                accepting_jobs: true,
+                online: true,
+                ..Default::default()
+            },
+            Printer {
+                name: "Zebra_ZD420".to_string(),
+                status: "idle".to_string(),
+                accepting_jobs: true,
+                online: true,
+                ..Default::default()
+            },
+        ];
+        assert_eq!(profiles.route(LabelType::Barcode, false, &printers).unwrap().name, "zebra");
+        assert_eq!(profiles.route(LabelType::Shipping, false, &printers).unwrap().name, "rollo");
+        assert_eq!(profiles.route(LabelType::Shipping, true, &printers).unwrap_err(), "no printer profile prints ZPL shipping labels");
+
+        // Barcode labels go to the Rollo when the Zebra is offline, but ZPL
+        // labels have nowhere to go.
+        printers[1].status = "disabled".to_string();
+        printers[1].alerts = vec!["offline-report".to_string()];
+        printers[1].online = false;
+        assert_eq!(profiles.route(LabelType::Barcode, false, &printers).unwrap().name, "rollo");
+        assert_eq!(
+            profiles.route(LabelType::Barcode, true, &printers).unwrap_err(),
+            "no printer is available for barcode labels: zebra: `Zebra_ZD420` is disabled (offline-report)"
+        );
+
+        printers.remove(0);
+        assert_eq!(
+            profiles.route(LabelType::Shipping, false, &printers).unwrap_err(),
+            "no printer is available for shipping labels: rollo: no CUPS printer matches `rollo`"
+        );
+    }
+}
@@ -47,7 +47,7 @@ pub struct QuickBooks {
     redirect_uri: String,
     company_id: String,
 
-    client: Arc<Client>,
+    client: Arc<cio_http::Client>,
 }
 
 impl QuickBooks {
@@ -74,7 +74,7 @@ impl QuickBooks {
                     token: token.to_string(),
                     refresh_token: refresh_token.to_string(),
 
-                    client: Arc::new(c),
+                    client: Arc::new(cio_http::Client::new(c)),
                 };
 
                 if qb.token.is_empty() || qb.refresh_token.is_empty() {
@@ -153,13 +153,15 @@ impl QuickBooks {
         headers.append(header::ACCEPT, header::HeaderValue::from_static("application/json"));
 
         let params = [("grant_type", "refresh_token"), ("refresh_token", &self.refresh_token)];
-        let client = reqwest::Client::new();
+        let client = cio_http::Client::new(reqwest::Client::new());
         let resp = client
-            .post("https://oauth.platform.intuit.com/oauth2/v1/tokens/bearer")
-            .headers(headers)
-            .basic_auth(&self.client_id, Some(&self.client_secret))
-            .form(&params)
-            .send()
+            .send(
+                client
+                    .post("https://oauth.platform.intuit.com/oauth2/v1/tokens/bearer")
+                    .headers(headers)
+                    .basic_auth(&self.client_id, Some(&self.client_secret))
+                    .form(&params),
+            )
             .await
             .unwrap();
 
@@ -177,13 +179,15 @@ impl QuickBooks {
         headers.append(header::ACCEPT, header::HeaderValue::from_static("application/json"));
 
         let params = [("grant_type", "authorization_code"), ("code", code), ("redirect_uri", &self.redirect_uri)];
-        let client = reqwest::Client::new();
+        let client = cio_http::Client::new(reqwest::Client::new());
         let resp = client
-            .post("https://oauth.platform.intuit.com/oauth2/v1/tokens/bearer")
-            .headers(headers)
-            .basic_auth(&self.client_id, Some(&self.client_secret))
-            .form(&params)
-            .send()
+            .send(
+                client
+                    .post("https://oauth.platform.intuit.com/oauth2/v1/tokens/bearer")
+                    .headers(headers)
+                    .basic_auth(&self.client_id, Some(&self.client_secret))
+                    .form(&params),
+            )
             .await
             .unwrap();
 
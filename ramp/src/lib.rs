@@ -39,18 +39,33 @@ pub struct Ramp {
     client_id: String,
     client_secret: String,
     token: String,
+    base_url: String,
 
-    client: Arc<Client>,
+    client: Arc<cio_http::Client>,
 }
 
 impl Ramp {
     /// Create a new Ramp client struct. It takes a type that can convert into
     /// an &str (`String` or `Vec<u8>` for example). As long as the function is
     /// given a valid API client ID and secret your requests will work.
+    /// The client talks to the base URL in the RAMP_BASE_URL env variable
+    /// if it is set, see `new_with_base_url`.
     pub async fn new<K, S>(client_id: K, client_secret: S) -> Self
     where
         K: ToString,
         S: ToString,
+    {
+        Ramp::new_with_base_url(client_id, client_secret, env::var("RAMP_BASE_URL").unwrap_or_default()).await
+    }
+
+    /// Create a new Ramp client struct that sends its requests, including the
+    /// one for its token, to another base URL, like a fake server in tests.
+    /// An empty base URL means the Ramp API.
+    pub async fn new_with_base_url<K, S, U>(client_id: K, client_secret: S, base_url: U) -> Self
+    where
+        K: ToString,
+        S: ToString,
+        U: ToString,
     {
         let client = Client::builder().build();
         match client {
@@ -59,8 +74,9 @@ impl Ramp {
                     client_id: client_id.to_string(),
                     client_secret: client_secret.to_string(),
                     token: "".to_string(),
+                    base_url: base_url.to_string(),
 
-                    client: Arc::new(c),
+                    client: Arc::new(cio_http::Client::new(c)),
                 };
 
                 // Let's get the token.
@@ -85,13 +101,21 @@ impl Ramp {
 
     // Sets the token for requests.
     async fn get_token(&mut self) -> Result<(), APIError> {
-        let client = reqwest::Client::new();
+        let client = cio_http::Client::new(reqwest::Client::new());
 
         let params = [
             ("grant_type", "client_credentials"),
-            ("scope", "transactions:read users:read users:write receipts:read cards:read departments:read"),
+            ("scope", "transactions:read users:read users:write receipts:read cards:read cards:write departments:read"),
         ];
-        let resp = client.post(TOKEN_ENDPOINT).form(&params).basic_auth(&self.client_id, Some(&self.client_secret)).send().await.unwrap();
+        let resp = client
+            .send(
+                client
+                    .post(&cio_http::rebase(TOKEN_ENDPOINT, &self.base_url))
+                    .form(&params)
+                    .basic_auth(&self.client_id, Some(&self.client_secret)),
+            )
+            .await
+            .unwrap();
         match resp.status() {
             StatusCode::OK => (),
             s => {
@@ -112,7 +136,7 @@ impl Ramp {
     where
         B: Serialize,
     {
-        let base = Url::parse(ENDPOINT).unwrap();
+        let base = Url::parse(&cio_http::rebase(ENDPOINT, &self.base_url)).unwrap();
         let url = base.join(&path).unwrap();
 
         let bt = format!("Bearer {}", self.token);
@@ -371,6 +395,27 @@ impl Ramp {
         Ok(resp.json().await.unwrap())
     }
 
+    /// Deactivate a user.
+    /// The user can no longer log in or use any of their cards.
+    pub async fn deactivate_user(&self, id: &str) -> Result<(), APIError> {
+        // Build the request.
+        let request = self.request(Method::PATCH, &format!("users/{}/deactivate", id), (), None);
+
+        let resp = self.client.execute(request).await.unwrap();
+        match resp.status() {
+            StatusCode::OK => (),
+            StatusCode::NO_CONTENT => (),
+            s => {
+                return Err(APIError {
+                    status_code: s,
+                    body: resp.text().await.unwrap(),
+                })
+            }
+        };
+
+        Ok(())
+    }
+
     /// Create a physical card.
     pub async fn create_physical_card(&self, card: &Card) -> Result<Card, APIError> {
         // Build the request.
@@ -433,6 +478,27 @@ impl Ramp {
         Ok(r.data)
     }
 
+    /// Suspend a card so it can no longer be used.
+    /// The suspension is done asynchronously by Ramp.
+    pub async fn suspend_card(&self, id: &str) -> Result<(), APIError> {
+        // Build the request.
+        let request = self.request(Method::POST, &format!("cards/{}/deferred/suspension", id), (), None);
+
+        let resp = self.client.execute(request).await.unwrap();
+        match resp.status() {
+            StatusCode::OK => (),
+            StatusCode::CREATED => (),
+            s => {
+                return Err(APIError {
+                    status_code: s,
+                    body: resp.text().await.unwrap(),
+                })
+            }
+        };
+
+        Ok(())
+    }
+
     /// Get a receipt.
     pub async fn get_receipt(&self, id: &str) -> Result<Receipt, APIError> {
         // Build the request.
@@ -7,7 +7,7 @@
  * Example:
  *
  * ```
- * use revai::RevAI;
+ * use revai::{JobOptions, RevAI};
  * use serde::{Deserialize, Serialize};
  *
  * async fn geocode() {
@@ -17,6 +17,26 @@
  *     let transcript = revai.get_transcript("some_id").await.unwrap();
  *
  *     println!("{}", transcript);
+ *
+ *     // Get the transcript with who said what, and when.
+ *     let transcript = revai.get_transcript_json("some_id").await.unwrap();
+ *
+ *     for monologue in transcript.monologues {
+ *         println!("{}: {}", monologue.speaker_label(), monologue.text());
+ *     }
+ * }
+ *
+ * async fn transcribe() {
+ *     let revai = RevAI::new_from_env();
+ *
+ *     // Have rev.ai download the video and tell us when it is done.
+ *     let options = JobOptions {
+ *         callback_url: "https://example.com/revai/jobs/complete".to_string(),
+ *         ..Default::default()
+ *     };
+ *     let job = revai.create_job_from_url("https://example.com/video.mp4", &options).await.unwrap();
+ *
+ *     println!("{} is {}", job.id, job.status);
  * }
  * ```
  */
@@ -29,8 +49,9 @@ use std::sync::Arc;
 use bytes::Bytes;
 use chrono::offset::Utc;
 use chrono::DateTime;
-use reqwest::multipart::Form;
-use reqwest::{header, Client, Method, Request, StatusCode, Url};
+use futures_core::TryStream;
+use reqwest::multipart::{Form, Part};
+use reqwest::{header, Body, Client, Method, Request, StatusCode, Url};
 use serde::{Deserialize, Serialize};
 
 /// Endpoint for the RevAI API.
@@ -40,7 +61,7 @@ const ENDPOINT: &str = "https://api.rev.ai/speechtotext/v1/";
 pub struct RevAI {
     key: String,
 
-    client: Arc<Client>,
+    client: Arc<cio_http::Client>,
 }
 
 impl RevAI {
@@ -56,7 +77,7 @@ impl RevAI {
             Ok(c) => Self {
                 key: key.to_string(),
 
-                client: Arc::new(c),
+                client: Arc::new(cio_http::Client::new(c)),
             },
             Err(e) => panic!("creating client failed: {:?}", e),
         }
@@ -108,12 +129,71 @@ impl RevAI {
 
     /// Create a job.
     pub async fn create_job(&self, bytes: Bytes) -> Result<Job, APIError> {
+        self.upload_job(Part::bytes(bytes.to_vec()), &Default::default()).await
+    }
+
+    /// Create a job for a video that is uploaded as it is read from the
+    /// stream, so it is never held in memory. Pass the size of the video if
+    /// it is known, otherwise it is sent in chunks.
+    pub async fn create_job_from_stream<S>(&self, stream: S, content_length: Option<u64>, options: &JobOptions) -> Result<Job, APIError>
+    where
+        S: TryStream + Send + Sync + 'static,
+        S::Error: Into<Box<dyn error::Error + Send + Sync>>,
+        Bytes: From<S::Ok>,
+    {
+        let body = Body::wrap_stream(stream);
+        let part = match content_length {
+            Some(length) => Part::stream_with_length(body, length),
+            None => Part::stream(body),
+        };
+
+        self.upload_job(part, options).await
+    }
+
+    /// Create a job for media rev.ai downloads itself from a URL.
+    pub async fn create_job_from_url(&self, media_url: &str, options: &JobOptions) -> Result<Job, APIError> {
+        let mut options = options.clone();
+        options.media_url = media_url.to_string();
+
+        // Build the request.
+        let mut request = self.request(Method::POST, "jobs", None, None);
+        request.headers_mut().insert(header::CONTENT_TYPE, header::HeaderValue::from_static("application/json"));
+        *request.body_mut() = Some(serde_json::to_vec(&options).unwrap().into());
+
+        self.send_job(request).await
+    }
+
+    /// Upload the media for a job as a multipart form.
+    async fn upload_job(&self, media: Part, options: &JobOptions) -> Result<Job, APIError> {
         let form = Form::new()
-            .part("media", reqwest::multipart::Part::bytes(bytes.to_vec()).mime_str("video/mp4").unwrap().file_name("testing.mp4"))
-            .text("options", "{}");
+            .part("media", media.mime_str("video/mp4").unwrap().file_name("recording.mp4"))
+            .text("options", serde_json::to_string(options).unwrap());
         // Build the request.
         let request = self.request(Method::POST, "jobs", Some(form), None);
 
+        self.send_job(request).await
+    }
+
+    async fn send_job(&self, request: Request) -> Result<Job, APIError> {
+        let resp = self.client.execute(request).await.unwrap();
+        match resp.status() {
+            StatusCode::OK => (),
+            s => {
+                return Err(APIError {
+                    status_code: s,
+                    body: resp.text().await.unwrap(),
+                })
+            }
+        };
+
+        Ok(resp.json().await.unwrap())
+    }
+
+    /// Get a job by its ID.
+    pub async fn get_job(&self, id: &str) -> Result<Job, APIError> {
+        // Build the request.
+        let request = self.request(Method::GET, &format!("jobs/{}", id), None, None);
+
         let resp = self.client.execute(request).await.unwrap();
         match resp.status() {
             StatusCode::OK => (),
@@ -146,6 +226,29 @@ impl RevAI {
 
         Ok(resp.text().await.unwrap())
     }
+
+    /// Get a transcript from a job ID, split into monologues by speaker with
+    /// the time of every word.
+    pub async fn get_transcript_json(&self, id: &str) -> Result<Transcript, APIError> {
+        // Build the request.
+        let mut request = self.request(Method::GET, &format!("jobs/{}/transcript", id), None, None);
+        request
+            .headers_mut()
+            .insert(header::ACCEPT, header::HeaderValue::from_static("application/vnd.rev.transcript.v1.0+json"));
+
+        let resp = self.client.execute(request).await.unwrap();
+        match resp.status() {
+            StatusCode::OK => (),
+            s => {
+                return Err(APIError {
+                    status_code: s,
+                    body: resp.text().await.unwrap(),
+                })
+            }
+        };
+
+        Ok(resp.json().await.unwrap())
+    }
 }
 
 /// Error type returned by our library.
@@ -178,25 +281,172 @@ impl error::Error for APIError {
 pub struct Job {
     #[serde(default, skip_serializing_if = "String::is_empty")]
     pub id: String,
+    /// `in_progress`, `transcribed` or `failed`.
     #[serde(default, skip_serializing_if = "String::is_empty")]
     pub status: String,
     pub created_on: DateTime<Utc>,
-    #[serde(default, skip_serializing_if = "String::is_empty")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed_on: Option<DateTime<Utc>>,
+    #[serde(default, rename = "type", skip_serializing_if = "String::is_empty")]
     pub type_: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub media_url: String,
+    /// The metadata the job was created with.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub metadata: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub callback_url: String,
+    #[serde(default)]
+    pub duration_seconds: f64,
+    /// Why the job failed, like `download_failure`.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub failure: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub failure_detail: String,
     #[serde(default)]
     pub delete_after_seconds: i64,
 }
 
+impl Job {
+    /// Returns true if the transcript is ready.
+    pub fn is_transcribed(&self) -> bool {
+        self.status == "transcribed"
+    }
+
+    /// Returns true if rev.ai could not transcribe the media.
+    pub fn is_failed(&self) -> bool {
+        self.status == "failed"
+    }
+}
+
+/// The body rev.ai posts to the callback URL of a job when it is done.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobCallback {
+    pub job: Job,
+}
+
+/// The options for a job. Only the options that are set are sent.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct JobOptions {
+    /// The URL rev.ai downloads the media from. This is set by
+    /// `create_job_from_url`.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub media_url: String,
+    #[serde(default, skip_serializing_if = "is_false")]
     pub skip_diarization: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
     pub skip_punctuation: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
     pub remove_disfluencies: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
     pub filter_profanity: bool,
+    #[serde(default, skip_serializing_if = "is_zero")]
     pub speaker_channels_count: i64,
+    /// Returned with the job, to tell which of our records it is for.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub metadata: String,
+    /// The URL rev.ai posts a `JobCallback` to when the job is done.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub callback_url: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub custom_vocabulary_id: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub language: String,
+    #[serde(default, skip_serializing_if = "is_zero")]
     pub delete_after_seconds: i64,
 }
+
+fn is_false(b: &bool) -> bool {
+    !b
+}
+
+fn is_zero(i: &i64) -> bool {
+    *i == 0
+}
+
+/// A transcript, split into monologues by speaker.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Transcript {
+    #[serde(default)]
+    pub monologues: Vec<Monologue>,
+}
+
+impl Transcript {
+    /// Return the transcript as text, with the speaker and the time each
+    /// monologue starts, like the plain text transcript.
+    pub fn to_text(&self) -> String {
+        self.monologues
+            .iter()
+            .map(|m| {
+                let start = m.start() as i64;
+                format!("{}    {:02}:{:02}:{:02}    {}", m.speaker_label(), start / 3600, (start / 60) % 60, start % 60, m.text())
+            })
+            .collect::<Vec<String>>()
+            .join("\n\n")
+    }
+}
+
+/// What one speaker said until another one started.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Monologue {
+    #[serde(default)]
+    pub speaker: i64,
+    /// The name of the speaker, if it was given for the job.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub speaker_name: String,
+    #[serde(default)]
+    pub elements: Vec<Element>,
+}
+
+impl Monologue {
+    /// Return the name of the speaker, or `Speaker N` if the speaker has no name.
+    pub fn speaker_label(&self) -> String {
+        if self.speaker_name.is_empty() {
+            format!("Speaker {}", self.speaker)
+        } else {
+            self.speaker_name.to_string()
+        }
+    }
+
+    /// Return the text of the monologue.
+    pub fn text(&self) -> String {
+        self.elements.iter().map(|e| e.value.as_str()).collect::<String>().trim().to_string()
+    }
+
+    /// Return when the first word starts, in seconds.
+    pub fn start(&self) -> f64 {
+        self.elements.iter().find(|e| e.is_text()).map(|e| e.ts).unwrap_or_default()
+    }
+
+    /// Return when the last word ends, in seconds.
+    pub fn end(&self) -> f64 {
+        self.elements.iter().rev().find(|e| e.is_text()).map(|e| e.end_ts).unwrap_or_default()
+    }
+}
+
+/// A word, punctuation or the space between words.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Element {
+    /// `text`, `punct` or `unknown`.
+    #[serde(default, rename = "type", skip_serializing_if = "String::is_empty")]
+    pub type_: String,
+    #[serde(default)]
+    pub value: String,
+    /// When the word starts, in seconds. Only set for `text` elements.
+    #[serde(default)]
+    pub ts: f64,
+    /// When the word ends, in seconds. Only set for `text` elements.
+    #[serde(default)]
+    pub end_ts: f64,
+    #[serde(default)]
+    pub confidence: f64,
+}
+
+impl Element {
+    /// Returns true if the element is a word.
+    pub fn is_text(&self) -> bool {
+        self.type_ == "text"
+    }
+}
@@ -8,12 +8,13 @@
 # <bitbar.image></bitbar.image> <!-- fix me -->
 
 jq=/usr/local/bin/jq
-output=$(curl -s api.internal.oxide.computer/rfds)
-RFD_COUNT=$(echo "$output" | $jq length)
+# CIO_API_TOKEN is an Auth0 access token for your account.
+output=$(curl -s -H "Authorization: Bearer ${CIO_API_TOKEN}" "api.internal.oxide.computer/rfds?limit=1000")
+RFD_COUNT=$(echo "$output" | $jq '.items | length')
 RFDC_FORMATTED=`printf "%'.f\n" $RFD_COUNT`
 echo "$RFDC_FORMATTED RFDs"
 echo ---
-for row in $(echo "${output}" | $jq -r 'reverse | .[] | @base64'); do
+for row in $(echo "${output}" | $jq -r '.items | reverse | .[] | @base64'); do
     _jq() {
 		echo ${row} | base64 --decode | $jq -r ${1}
     }
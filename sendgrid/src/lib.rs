@@ -42,14 +42,17 @@ const ENDPOINT: &str = "https://api.sendgrid.com/v3/";
 /// Entrypoint for interacting with the SendGrid API.
 pub struct SendGrid {
     key: String,
+    base_url: String,
 
-    client: Arc<Client>,
+    client: Arc<cio_http::Client>,
 }
 
 impl SendGrid {
     /// Create a new SendGrid client struct. It takes a type that can convert into
     /// an &str (`String` or `Vec<u8>` for example). As long as the function is
     /// given a valid API Key your requests will work.
+    /// The client talks to the base URL in the SENDGRID_BASE_URL env variable
+    /// if it is set, see `with_base_url`.
     pub fn new<K>(key: K) -> Self
     where
         K: ToString,
@@ -58,8 +61,9 @@ impl SendGrid {
         match client {
             Ok(c) => Self {
                 key: key.to_string(),
+                base_url: env::var("SENDGRID_BASE_URL").unwrap_or_default(),
 
-                client: Arc::new(c),
+                client: Arc::new(cio_http::Client::new(c)),
             },
             Err(e) => panic!("creating client failed: {:?}", e),
         }
@@ -75,6 +79,16 @@ impl SendGrid {
         SendGrid::new(key)
     }
 
+    /// Send requests to another base URL, like a fake server in tests, instead
+    /// of the SendGrid API.
+    pub fn with_base_url<U>(mut self, base_url: U) -> Self
+    where
+        U: ToString,
+    {
+        self.base_url = base_url.to_string();
+        self
+    }
+
     /// Get the currently set API key.
     pub fn get_key(&self) -> &str {
         &self.key
@@ -84,7 +98,7 @@ impl SendGrid {
     where
         B: Serialize,
     {
-        let base = Url::parse(ENDPOINT).unwrap();
+        let base = Url::parse(&cio_http::rebase(ENDPOINT, &self.base_url)).unwrap();
         let url = base.join(&path).unwrap();
 
         let bt = format!("Bearer {}", self.key);
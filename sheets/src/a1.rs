@@ -0,0 +1,189 @@
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+
+/// Get the letters for a zero-based column index: 0 is `A`, 25 is `Z` and 26 is `AA`.
+pub fn column_name(index: usize) -> String {
+    let mut name = Vec::new();
+    let mut n = index + 1;
+    while n > 0 {
+        let rem = (n - 1) % 26;
+        name.push(b'A' + rem as u8);
+        n = (n - 1) / 26;
+    }
+    name.reverse();
+    String::from_utf8(name).unwrap()
+}
+
+/// Get the zero-based column index for column letters, like `AB`. Lowercase
+/// letters are accepted.
+pub fn column_index(name: &str) -> Option<usize> {
+    if name.is_empty() {
+        return None;
+    }
+
+    let mut index: usize = 0;
+    for c in name.chars() {
+        if !c.is_ascii_alphabetic() {
+            return None;
+        }
+        index = index.checked_mul(26)?.checked_add((c.to_ascii_uppercase() as u8 - b'A') as usize + 1)?;
+    }
+    Some(index - 1)
+}
+
+/// Error returned when a cell reference or range is not valid A1 notation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseA1Error {
+    input: String,
+}
+
+impl fmt::Display for ParseA1Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "`{}` is not valid A1 notation", self.input)
+    }
+}
+
+impl error::Error for ParseA1Error {}
+
+/// A single cell. Both the column and the row are zero-based, so `A1` is
+/// `CellRef::new(0, 0)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CellRef {
+    pub column: usize,
+    pub row: usize,
+}
+
+impl CellRef {
+    pub fn new(column: usize, row: usize) -> Self {
+        CellRef { column, row }
+    }
+}
+
+impl fmt::Display for CellRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", column_name(self.column), self.row + 1)
+    }
+}
+
+impl FromStr for CellRef {
+    type Err = ParseA1Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseA1Error { input: s.to_string() };
+
+        // Absolute references like `$A$1` point at the same cell.
+        let cell = s.trim().replace('$', "");
+        let split = cell.find(|c: char| c.is_ascii_digit()).ok_or_else(err)?;
+        let column = column_index(&cell[..split]).ok_or_else(err)?;
+        let row: usize = cell[split..].parse().map_err(|_| err())?;
+        if row == 0 {
+            return Err(err());
+        }
+
+        Ok(CellRef::new(column, row - 1))
+    }
+}
+
+/// A rectangle of cells, optionally on a named sheet. A range without an
+/// `end` is a single cell.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Range {
+    pub sheet: Option<String>,
+    pub start: CellRef,
+    pub end: Option<CellRef>,
+}
+
+impl Range {
+    pub fn new(start: CellRef, end: CellRef) -> Self {
+        Range { sheet: None, start, end: Some(end) }
+    }
+
+    pub fn cell(cell: CellRef) -> Self {
+        Range { sheet: None, start: cell, end: None }
+    }
+
+    /// Put the range on a sheet, like `Form Responses 1`.
+    pub fn on_sheet<S: ToString>(mut self, sheet: S) -> Self {
+        self.sheet = Some(sheet.to_string());
+        self
+    }
+}
+
+impl fmt::Display for Range {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(sheet) = &self.sheet {
+            if sheet.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                write!(f, "{}!", sheet)?;
+            } else {
+                write!(f, "'{}'!", sheet.replace('\'', "''"))?;
+            }
+        }
+
+        write!(f, "{}", self.start)?;
+        if let Some(end) = &self.end {
+            write!(f, ":{}", end)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Range {
+    type Err = ParseA1Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseA1Error { input: s.to_string() };
+
+        let (sheet, cells) = match s.rfind('!') {
+            Some(i) => {
+                let sheet = &s[..i];
+                let sheet = if sheet.len() >= 2 && sheet.starts_with('\'') && sheet.ends_with('\'') {
+                    sheet[1..sheet.len() - 1].replace("''", "'")
+                } else {
+                    sheet.to_string()
+                };
+                (Some(sheet), &s[i + 1..])
+            }
+            None => (None, s),
+        };
+
+        let mut split = cells.splitn(2, ':');
+        let start = split.next().ok_or_else(err)?.parse().map_err(|_| err())?;
+        let end = match split.next() {
+            Some(end) => Some(end.parse().map_err(|_| err())?),
+            None => None,
+        };
+
+        Ok(Range { sheet, start, end })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::a1::{column_index, column_name, CellRef, Range};
+
+    #[test]
+    fn test_columns() {
+        for (index, name) in &[(0, "A"), (25, "Z"), (26, "AA"), (27, "AB"), (51, "AZ"), (52, "BA"), (701, "ZZ"), (702, "AAA")] {
+            assert_eq!(column_name(*index), *name);
+            assert_eq!(column_index(name), Some(*index));
+        }
+        assert_eq!(column_index("ab"), Some(27));
+        assert_eq!(column_index(""), None);
+        assert_eq!(column_index("A1"), None);
+    }
+
+    #[test]
+    fn test_ranges() {
+        assert_eq!("AB12".parse::<CellRef>().unwrap(), CellRef::new(27, 11));
+        assert_eq!("$C$3".parse::<CellRef>().unwrap(), CellRef::new(2, 2));
+        assert!("A0".parse::<CellRef>().is_err());
+        assert!("12".parse::<CellRef>().is_err());
+
+        let range = Range::new(CellRef::new(0, 0), CellRef::new(27, 999)).on_sheet("Form Responses 1");
+        assert_eq!(range.to_string(), "'Form Responses 1'!A1:AB1000");
+        assert_eq!(range.to_string().parse::<Range>().unwrap(), range);
+        assert_eq!("Sheet1!B2".parse::<Range>().unwrap(), Range::cell(CellRef::new(1, 1)).on_sheet("Sheet1"));
+        assert_eq!("'Bob''s Sheet'!A1:B2".parse::<Range>().unwrap().sheet.unwrap(), "Bob's Sheet");
+    }
+}
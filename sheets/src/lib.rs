@@ -47,6 +47,10 @@
  * }
  * ```
  */
+pub mod a1;
+pub mod rows;
+
+use std::env;
 use std::error;
 use std::fmt;
 use std::sync::Arc;
@@ -55,28 +59,48 @@ use reqwest::{header, Client, Method, Request, StatusCode, Url};
 use serde::{Deserialize, Serialize};
 use yup_oauth2::AccessToken;
 
+pub use crate::a1::{CellRef, Range};
+pub use crate::rows::HeaderMap;
+
 /// Endpoint for the Google Sheets API.
 const ENDPOINT: &str = "https://sheets.googleapis.com/v4/";
 
 /// Entrypoint for interacting with the Google Sheets API.
 pub struct Sheets {
     token: AccessToken,
+    base_url: String,
 
-    client: Arc<Client>,
+    client: Arc<cio_http::Client>,
 }
 
 impl Sheets {
     /// Create a new Sheets client struct. It takes a type that can convert into
     /// an &str (`String` or `Vec<u8>` for example). As long as the function is
     /// given a valid API Key and Secret your requests will work.
+    /// The client talks to the base URL in the SHEETS_BASE_URL env variable
+    /// if it is set, see `with_base_url`.
     pub fn new(token: AccessToken) -> Self {
         let client = Client::builder().build();
         match client {
-            Ok(c) => Self { token, client: Arc::new(c) },
+            Ok(c) => Self {
+                token,
+                base_url: env::var("SHEETS_BASE_URL").unwrap_or_default(),
+                client: Arc::new(cio_http::Client::new(c)),
+            },
             Err(e) => panic!("creating client failed: {:?}", e),
         }
     }
 
+    /// Send requests to another base URL, like a fake server in tests, instead
+    /// of the Google Sheets API.
+    pub fn with_base_url<U>(mut self, base_url: U) -> Self
+    where
+        U: ToString,
+    {
+        self.base_url = base_url.to_string();
+        self
+    }
+
     /// Get the currently set authorization token.
     pub fn get_token(&self) -> &AccessToken {
         &self.token
@@ -86,7 +110,7 @@ impl Sheets {
     where
         B: Serialize,
     {
-        let base = Url::parse(ENDPOINT).unwrap();
+        let base = Url::parse(&cio_http::rebase(ENDPOINT, &self.base_url)).unwrap();
         let url = base.join(&path).unwrap();
 
         // Check if the token is expired and panic.
@@ -232,6 +256,143 @@ impl Sheets {
         // Try to deserialize the response.
         Ok(resp.json().await.unwrap())
     }
+
+    /// Get the values in several ranges of a spreadsheet in one request.
+    /// The value ranges are returned in the order of the ranges.
+    pub async fn batch_get_values(&self, sheet_id: &str, ranges: &[String]) -> Result<Vec<ValueRange>, APIError> {
+        let mut query = vec![
+            ("valueRenderOption", "FORMATTED_VALUE".to_string()),
+            ("dateTimeRenderOption", "FORMATTED_STRING".to_string()),
+            ("majorDimension", "ROWS".to_string()),
+        ];
+        for range in ranges {
+            query.push(("ranges", range.to_string()));
+        }
+
+        // Build the request.
+        let request = self.request(Method::GET, format!("spreadsheets/{}/values:batchGet", sheet_id), (), Some(query));
+
+        let resp = self.client.execute(request).await.unwrap();
+        match resp.status() {
+            StatusCode::OK => (),
+            s => {
+                return Err(APIError {
+                    status_code: s,
+                    body: resp.text().await.unwrap(),
+                })
+            }
+        };
+
+        // Try to deserialize the response.
+        let r: BatchGetValuesResponse = resp.json().await.unwrap();
+        Ok(r.value_ranges.unwrap_or_default())
+    }
+
+    /// Update the values in several ranges of a spreadsheet in one request.
+    pub async fn batch_update_values(&self, sheet_id: &str, data: Vec<ValueRange>) -> Result<BatchUpdateValuesResponse, APIError> {
+        // Build the request.
+        let request = self.request(
+            Method::POST,
+            format!("spreadsheets/{}/values:batchUpdate", sheet_id),
+            BatchUpdateValuesRequest {
+                value_input_option: "USER_ENTERED".to_string(),
+                data,
+                response_value_render_option: "FORMATTED_VALUE".to_string(),
+                response_date_time_render_option: "FORMATTED_STRING".to_string(),
+            },
+            None,
+        );
+
+        let resp = self.client.execute(request).await.unwrap();
+        match resp.status() {
+            StatusCode::OK => (),
+            s => {
+                return Err(APIError {
+                    status_code: s,
+                    body: resp.text().await.unwrap(),
+                })
+            }
+        };
+
+        // Try to deserialize the response.
+        Ok(resp.json().await.unwrap())
+    }
+
+    /// Append rows after the table in a range, like `Form Responses 1!A:Z`.
+    /// The rows are inserted, so nothing below the table is overwritten.
+    pub async fn append_values(&self, sheet_id: &str, range: &str, values: Vec<Vec<String>>) -> Result<AppendValuesResponse, APIError> {
+        // Build the request.
+        let request = self.request(
+            Method::POST,
+            format!("spreadsheets/{}/values/{}:append", sheet_id, range),
+            ValueRange {
+                range: Some(range.to_string()),
+                values: Some(values),
+                major_dimension: None,
+            },
+            Some(vec![
+                ("valueInputOption", "USER_ENTERED".to_string()),
+                ("insertDataOption", "INSERT_ROWS".to_string()),
+                ("responseValueRenderOption", "FORMATTED_VALUE".to_string()),
+                ("responseDateTimeRenderOption", "FORMATTED_STRING".to_string()),
+            ]),
+        );
+
+        let resp = self.client.execute(request).await.unwrap();
+        match resp.status() {
+            StatusCode::OK => (),
+            s => {
+                return Err(APIError {
+                    status_code: s,
+                    body: resp.text().await.unwrap(),
+                })
+            }
+        };
+
+        // Try to deserialize the response.
+        Ok(resp.json().await.unwrap())
+    }
+
+    /// Clear the values in a range. Formatting and validation are kept.
+    pub async fn clear_values(&self, sheet_id: &str, range: &str) -> Result<ClearValuesResponse, APIError> {
+        // Build the request.
+        let request = self.request(Method::POST, format!("spreadsheets/{}/values/{}:clear", sheet_id, range), ClearValuesRequest {}, None);
+
+        let resp = self.client.execute(request).await.unwrap();
+        match resp.status() {
+            StatusCode::OK => (),
+            s => {
+                return Err(APIError {
+                    status_code: s,
+                    body: resp.text().await.unwrap(),
+                })
+            }
+        };
+
+        // Try to deserialize the response.
+        Ok(resp.json().await.unwrap())
+    }
+
+    /// Get the metadata for a spreadsheet: its title and the sheets in it, with
+    /// their grid sizes. The values are not included.
+    pub async fn get_spreadsheet(&self, sheet_id: &str) -> Result<Spreadsheet, APIError> {
+        // Build the request.
+        let request = self.request(Method::GET, format!("spreadsheets/{}", sheet_id), (), Some(vec![("includeGridData", "false".to_string())]));
+
+        let resp = self.client.execute(request).await.unwrap();
+        match resp.status() {
+            StatusCode::OK => (),
+            s => {
+                return Err(APIError {
+                    status_code: s,
+                    body: resp.text().await.unwrap(),
+                })
+            }
+        };
+
+        // Try to deserialize the response.
+        Ok(resp.json().await.unwrap())
+    }
 }
 
 /// Error type returned by our library.
@@ -297,6 +458,20 @@ pub struct ValueRange {
     pub major_dimension: Option<String>,
 }
 
+impl ValueRange {
+    /// The value for a single cell, like an update for `Sheets::batch_update_values`.
+    pub fn cell<S>(cell: CellRef, value: S) -> Self
+    where
+        S: ToString,
+    {
+        ValueRange {
+            range: Some(cell.to_string()),
+            values: Some(vec![vec![value.to_string()]]),
+            major_dimension: None,
+        }
+    }
+}
+
 /// The response returned from updating values.
 #[derive(Default, Clone, Debug, Serialize, Deserialize)]
 pub struct UpdateValuesResponse {
@@ -321,3 +496,170 @@ pub struct UpdateValuesResponse {
     #[serde(rename = "updatedCells")]
     pub updated_cells: Option<i32>,
 }
+
+/// The response returned from getting the values in several ranges.
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct BatchGetValuesResponse {
+    /// The ID of the spreadsheet the data was retrieved from.
+    #[serde(rename = "spreadsheetId")]
+    pub spreadsheet_id: Option<String>,
+    /// The requested values. The order of the ValueRanges is the same as the
+    /// order of the requested ranges.
+    #[serde(rename = "valueRanges")]
+    pub value_ranges: Option<Vec<ValueRange>>,
+}
+
+/// The request for updating the values in several ranges.
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct BatchUpdateValuesRequest {
+    /// How the input data should be interpreted.
+    #[serde(rename = "valueInputOption")]
+    pub value_input_option: String,
+    /// The new values to apply to the spreadsheet.
+    pub data: Vec<ValueRange>,
+    /// Determines how values in the response should be rendered.
+    #[serde(rename = "responseValueRenderOption")]
+    pub response_value_render_option: String,
+    /// Determines how dates, times, and durations in the response should be
+    /// rendered.
+    #[serde(rename = "responseDateTimeRenderOption")]
+    pub response_date_time_render_option: String,
+}
+
+/// The response returned from updating the values in several ranges.
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct BatchUpdateValuesResponse {
+    /// The spreadsheet the updates were applied to.
+    #[serde(rename = "spreadsheetId")]
+    pub spreadsheet_id: Option<String>,
+    /// The total number of rows where at least one cell in the row was updated.
+    #[serde(rename = "totalUpdatedRows")]
+    pub total_updated_rows: Option<i32>,
+    /// The total number of columns where at least one cell in the column was
+    /// updated.
+    #[serde(rename = "totalUpdatedColumns")]
+    pub total_updated_columns: Option<i32>,
+    /// The total number of cells updated.
+    #[serde(rename = "totalUpdatedCells")]
+    pub total_updated_cells: Option<i32>,
+    /// The total number of sheets where at least one cell in the sheet was
+    /// updated.
+    #[serde(rename = "totalUpdatedSheets")]
+    pub total_updated_sheets: Option<i32>,
+    /// One UpdateValuesResponse per requested range, in the same order as the
+    /// requests appeared.
+    pub responses: Option<Vec<UpdateValuesResponse>>,
+}
+
+/// The response returned from appending values.
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct AppendValuesResponse {
+    /// The spreadsheet the updates were applied to.
+    #[serde(rename = "spreadsheetId")]
+    pub spreadsheet_id: Option<String>,
+    /// The range (in A1 notation) of the table that values are being appended
+    /// to (before the values were appended).
+    /// Empty if no table was found.
+    #[serde(rename = "tableRange")]
+    pub table_range: Option<String>,
+    /// Information about the updates that were applied.
+    pub updates: Option<UpdateValuesResponse>,
+}
+
+/// The request for clearing a range of values.
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct ClearValuesRequest {}
+
+/// The response returned from clearing a range of values.
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct ClearValuesResponse {
+    /// The spreadsheet the updates were applied to.
+    #[serde(rename = "spreadsheetId")]
+    pub spreadsheet_id: Option<String>,
+    /// The range (in A1 notation) that was cleared.
+    /// (If the request was for an unbounded range or a range larger
+    /// than the bounds of the sheet, this will be the actual range
+    /// that was cleared, bounded to the sheet's limits.)
+    #[serde(rename = "clearedRange")]
+    pub cleared_range: Option<String>,
+}
+
+/// A spreadsheet, without its values.
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct Spreadsheet {
+    /// The ID of the spreadsheet.
+    #[serde(rename = "spreadsheetId")]
+    pub spreadsheet_id: Option<String>,
+    /// Overall properties of a spreadsheet.
+    pub properties: Option<SpreadsheetProperties>,
+    /// The sheets that are part of a spreadsheet.
+    #[serde(default)]
+    pub sheets: Vec<Sheet>,
+    /// The url of the spreadsheet.
+    #[serde(rename = "spreadsheetUrl")]
+    pub spreadsheet_url: Option<String>,
+}
+
+impl Spreadsheet {
+    /// Get the properties of a sheet by its title, like `Form Responses 1`.
+    pub fn sheet(&self, title: &str) -> Option<&SheetProperties> {
+        self.sheets.iter().filter_map(|s| s.properties.as_ref()).find(|p| p.title.as_deref() == Some(title))
+    }
+}
+
+/// Properties of a spreadsheet.
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct SpreadsheetProperties {
+    /// The title of the spreadsheet.
+    pub title: Option<String>,
+    /// The locale of the spreadsheet, like `en_US`.
+    pub locale: Option<String>,
+    /// The time zone of the spreadsheet, in CLDR format such as
+    /// `America/New_York`.
+    #[serde(rename = "timeZone")]
+    pub time_zone: Option<String>,
+}
+
+/// A sheet in a spreadsheet.
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct Sheet {
+    /// The properties of the sheet.
+    pub properties: Option<SheetProperties>,
+}
+
+/// Properties of a sheet.
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct SheetProperties {
+    /// The ID of the sheet. Must be non-negative.
+    #[serde(rename = "sheetId")]
+    pub sheet_id: Option<i64>,
+    /// The name of the sheet.
+    pub title: Option<String>,
+    /// The index of the sheet within the spreadsheet.
+    pub index: Option<i32>,
+    /// The type of sheet, like `GRID`.
+    #[serde(rename = "sheetType")]
+    pub sheet_type: Option<String>,
+    /// Additional properties of the sheet if this sheet is a grid.
+    #[serde(rename = "gridProperties")]
+    pub grid_properties: Option<GridProperties>,
+    /// True if the sheet is hidden in the UI, false if it's visible.
+    pub hidden: Option<bool>,
+}
+
+/// Properties of a grid.
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct GridProperties {
+    /// The number of rows in the grid.
+    #[serde(rename = "rowCount")]
+    pub row_count: Option<i32>,
+    /// The number of columns in the grid.
+    #[serde(rename = "columnCount")]
+    pub column_count: Option<i32>,
+    /// The number of rows that are frozen in the grid.
+    #[serde(rename = "frozenRowCount")]
+    pub frozen_row_count: Option<i32>,
+    /// The number of columns that are frozen in the grid.
+    #[serde(rename = "frozenColumnCount")]
+    pub frozen_column_count: Option<i32>,
+}
@@ -0,0 +1,238 @@
+use serde::de::value::{Error, MapDeserializer, SeqDeserializer};
+use serde::de::{self, DeserializeOwned, Deserializer, IntoDeserializer, Unexpected, Visitor};
+use serde::forward_to_deserialize_any;
+
+use crate::a1::CellRef;
+
+/// Maps the names in the header row of a sheet to their columns, so rows can
+/// be read by header instead of by position.
+///
+/// A name matches the header that is equal to it, ignoring case and
+/// surrounding whitespace. If there is none, it matches the first header that
+/// contains it, so `email address` finds `Email Address (work)`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HeaderMap {
+    headers: Vec<String>,
+}
+
+impl HeaderMap {
+    /// Create a header map from the header row.
+    pub fn new(header_row: &[String]) -> Self {
+        HeaderMap {
+            headers: header_row.iter().map(|h| h.trim().to_lowercase()).collect(),
+        }
+    }
+
+    /// Create a header map from the values of a sheet, where the first row is
+    /// the header row.
+    pub fn from_values(values: &[Vec<String>]) -> Self {
+        HeaderMap::new(values.first().map(|row| row.as_slice()).unwrap_or_default())
+    }
+
+    /// Get the zero-based column for a header.
+    pub fn column(&self, name: &str) -> Option<usize> {
+        let name = name.trim().to_lowercase();
+        if name.is_empty() {
+            return None;
+        }
+
+        self.headers.iter().position(|h| *h == name).or_else(|| self.headers.iter().position(|h| h.contains(&name)))
+    }
+
+    /// Get the cell for a header in a zero-based row.
+    pub fn cell(&self, name: &str, row: usize) -> Option<CellRef> {
+        self.column(name).map(|column| CellRef::new(column, row))
+    }
+
+    /// Get the value for a header in a row. Rows returned by the API leave out
+    /// empty cells at the end, so a missing cell is an empty string.
+    pub fn get<'a>(&self, row: &'a [String], name: &str) -> &'a str {
+        self.column(name).and_then(|column| row.get(column)).map(|v| v.as_str()).unwrap_or_default()
+    }
+
+    /// Deserialize a row into a struct. Each field is read from the column of
+    /// the header that matches its name, so use `#[serde(rename = "...")]` to
+    /// match a header.
+    ///
+    /// Empty cells are left out, so fields for them must be an `Option` or have
+    /// a `#[serde(default)]`. Cells are parsed into the type of their field:
+    /// booleans from `TRUE` and `FALSE`, numbers with or without thousands
+    /// separators, and a `Vec` from a comma separated list.
+    pub fn deserialize<T: DeserializeOwned>(&self, row: &[String]) -> Result<T, Error> {
+        T::deserialize(RowDeserializer { headers: self, row })
+    }
+}
+
+struct RowDeserializer<'a> {
+    headers: &'a HeaderMap,
+    row: &'a [String],
+}
+
+impl<'de, 'a> Deserializer<'de> for RowDeserializer<'a> {
+    type Error = Error;
+
+    /// Without a list of fields we can only give back every header with its value.
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let row = self.row;
+        let cells = self
+            .headers
+            .headers
+            .iter()
+            .enumerate()
+            .filter_map(|(column, header)| row.get(column).map(|v| (header.as_str(), v.trim())))
+            .filter(|(_, v)| !v.is_empty())
+            .map(|(header, v)| (header.to_string(), CellDeserializer(v)));
+        visitor.visit_map(MapDeserializer::new(cells))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(self, _name: &'static str, fields: &'static [&'static str], visitor: V) -> Result<V::Value, Error> {
+        let row = self.row;
+        let headers = self.headers;
+        let cells = fields
+            .iter()
+            .filter_map(|field| headers.column(field).and_then(|column| row.get(column)).map(|v| (*field, v.trim())))
+            .filter(|(_, v)| !v.is_empty())
+            .map(|(field, v)| (field, CellDeserializer(v)));
+        visitor.visit_map(MapDeserializer::new(cells))
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map enum identifier ignored_any
+    }
+}
+
+/// The value of a single cell.
+struct CellDeserializer<'a>(&'a str);
+
+impl<'de, 'a> IntoDeserializer<'de, Error> for CellDeserializer<'a> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+macro_rules! deserialize_number {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                match self.0.trim().replace(',', "").parse() {
+                    Ok(n) => visitor.$visit(n),
+                    Err(_) => Err(de::Error::invalid_value(Unexpected::Str(self.0), &"a number")),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de, 'a> Deserializer<'de> for CellDeserializer<'a> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_str(self.0)
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.0.trim().to_lowercase().as_str() {
+            "true" | "yes" => visitor.visit_bool(true),
+            "false" | "no" => visitor.visit_bool(false),
+            _ => Err(de::Error::invalid_value(Unexpected::Str(self.0), &"TRUE or FALSE")),
+        }
+    }
+
+    deserialize_number! {
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        // Empty cells are never deserialized, so there is always a value.
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let items = self.0.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()).map(CellDeserializer);
+        visitor.visit_seq(SeqDeserializer::new(items))
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_enum(self.0.trim().into_deserializer())
+    }
+
+    forward_to_deserialize_any! {
+        i128 u128 char str string bytes byte_buf unit unit_struct tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use crate::a1::CellRef;
+    use crate::rows::HeaderMap;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Row {
+        #[serde(rename = "name")]
+        name: String,
+        #[serde(default, rename = "email address")]
+        email: String,
+        #[serde(default, rename = "have sent follow up email")]
+        sent: bool,
+        #[serde(rename = "quantity")]
+        quantity: Option<i32>,
+        #[serde(default, rename = "interested in")]
+        interested_in: Vec<String>,
+    }
+
+    #[test]
+    fn test_header_map() {
+        let values: Vec<Vec<String>> = vec![
+            vec!["Timestamp", "Name", "Email Address", "Have sent follow up email?", "Quantity", "Which roles are you interested in?"],
+            vec!["1/1/2021 10:00:00", "Jane", "jane@example.com", "TRUE", "1,200", "Hardware, Software"],
+            vec!["1/2/2021 10:00:00", "John", "", "FALSE"],
+        ]
+        .into_iter()
+        .map(|row| row.into_iter().map(|v| v.to_string()).collect())
+        .collect();
+
+        let headers = HeaderMap::from_values(&values);
+        assert_eq!(headers.column("name"), Some(1));
+        assert_eq!(headers.column("have sent follow up email"), Some(3));
+        assert_eq!(headers.column("phone"), None);
+        assert_eq!(headers.cell("email address", 2), Some(CellRef::new(2, 2)));
+        assert_eq!(headers.get(&values[2], "quantity"), "");
+
+        let row: Row = headers.deserialize(&values[1]).unwrap();
+        assert_eq!(
+            row,
+            Row {
+                name: "Jane".to_string(),
+                email: "jane@example.com".to_string(),
+                sent: true,
+                quantity: Some(1200),
+                interested_in: vec!["Hardware".to_string(), "Software".to_string()],
+            }
+        );
+
+        let row: Row = headers.deserialize(&values[2]).unwrap();
+        assert_eq!(row.email, "");
+        assert_eq!(row.quantity, None);
+        assert!(!row.sent);
+    }
+}
@@ -44,14 +44,17 @@ const ENDPOINT: &str = "https://api.goshippo.com/";
 /// Entrypoint for interacting with the Shippo API.
 pub struct Shippo {
     token: String,
+    base_url: String,
 
-    client: Arc<Client>,
+    client: Arc<cio_http::Client>,
 }
 
 impl Shippo {
     /// Create a new Shippo client struct. It takes a type that can convert into
     /// an &str (`String` or `Vec<u8>` for example). As long as the function is
     /// given a valid API Token your requests will work.
+    /// The client talks to the base URL in the SHIPPO_BASE_URL env variable
+    /// if it is set, see `with_base_url`.
     pub fn new<K>(token: K) -> Self
     where
         K: ToString,
@@ -60,8 +63,9 @@ impl Shippo {
         match client {
             Ok(c) => Self {
                 token: token.to_string(),
+                base_url: env::var("SHIPPO_BASE_URL").unwrap_or_default(),
 
-                client: Arc::new(c),
+                client: Arc::new(cio_http::Client::new(c)),
             },
             Err(e) => panic!("creating client failed: {:?}", e),
         }
@@ -77,11 +81,21 @@ impl Shippo {
         Shippo::new(token)
     }
 
+    /// Send requests to another base URL, like a fake server in tests, instead
+    /// of the Shippo API.
+    pub fn with_base_url<U>(mut self, base_url: U) -> Self
+    where
+        U: ToString,
+    {
+        self.base_url = base_url.to_string();
+        self
+    }
+
     fn request<B>(&self, method: Method, path: &str, body: B, query: Option<Vec<(String, String)>>) -> Request
     where
         B: Serialize,
     {
-        let base = Url::parse(ENDPOINT).unwrap();
+        let base = Url::parse(&cio_http::rebase(ENDPOINT, &self.base_url)).unwrap();
         let url = base.join(path).unwrap();
 
         let bt = format!("ShippoToken {}", self.token);
@@ -44,14 +44,17 @@ const ENDPOINT: &str = "https://slack.com/api/";
 pub struct Slack {
     token: String,
     workspace_id: String,
+    base_url: String,
 
-    client: Arc<Client>,
+    client: Arc<cio_http::Client>,
 }
 
 impl Slack {
     /// Create a new Slack client struct. It takes a type that can convert into
     /// an &str (`String` or `Vec<u8>` for example). As long as the function is
     /// given a valid API Token and Workspace ID your requests will work.
+    /// The client talks to the base URL in the SLACK_BASE_URL env variable
+    /// if it is set, see `with_base_url`.
     pub fn new<K, B>(token: K, workspace_id: B) -> Self
     where
         K: ToString,
@@ -62,8 +65,9 @@ impl Slack {
             Ok(c) => Self {
                 token: token.to_string(),
                 workspace_id: workspace_id.to_string(),
+                base_url: env::var("SLACK_BASE_URL").unwrap_or_default(),
 
-                client: Arc::new(c),
+                client: Arc::new(cio_http::Client::new(c)),
             },
             Err(e) => panic!("creating client failed: {:?}", e),
         }
@@ -80,11 +84,21 @@ impl Slack {
         Slack::new(token, workspace_id)
     }
 
+    /// Send requests to another base URL, like a fake server in tests, instead
+    /// of the Slack API.
+    pub fn with_base_url<U>(mut self, base_url: U) -> Self
+    where
+        U: ToString,
+    {
+        self.base_url = base_url.to_string();
+        self
+    }
+
     fn request<B>(&self, method: Method, path: &str, body: B, query: Option<Vec<(&str, String)>>) -> Request
     where
         B: Serialize,
     {
-        let base = Url::parse(ENDPOINT).unwrap();
+        let base = Url::parse(&cio_http::rebase(ENDPOINT, &self.base_url)).unwrap();
         let url = base.join(path).unwrap();
 
         let bt = format!("Bearer {}", self.token);
@@ -113,27 +127,46 @@ impl Slack {
         rb.build().unwrap()
     }
 
-    /// List users on a workspace.
-    /// FROM: https://api.slack.com/methods/admin.users.list
+    /// List users on a workspace. Their email addresses are in their
+    /// `profile`.
+    /// FROM: https://api.slack.com/methods/users.list
     pub async fn list_users(&self) -> Result<Vec<User>, APIError> {
-        // Build the request.
-        // TODO: paginate.
-        let request = self.request(Method::GET, "users.list", (), Some(vec![("limit", "100".to_string())]));
-
-        let resp = self.client.execute(request).await.unwrap();
-        match resp.status() {
-            StatusCode::OK => (),
-            s => {
+        let mut users = vec![];
+        let mut cursor = String::new();
+        loop {
+            // Build the request.
+            let mut query = vec![("limit", "200".to_string())];
+            if !cursor.is_empty() {
+                query.push(("cursor", cursor.to_string()));
+            }
+            let request = self.request(Method::GET, "users.list", (), Some(query));
+
+            let resp = self.client.execute(request).await.map_err(APIError::from)?;
+            match resp.status() {
+                StatusCode::OK => (),
+                s => {
+                    return Err(APIError {
+                        status_code: s,
+                        body: resp.text().await.unwrap_or_default(),
+                    })
+                }
+            };
+
+            let r: APIResponse = resp.json().await.map_err(APIError::from)?;
+            if !r.ok {
                 return Err(APIError {
-                    status_code: s,
-                    body: resp.text().await.unwrap(),
-                })
+                    status_code: StatusCode::OK,
+                    body: r.error,
+                });
             }
-        };
-
-        let r: APIResponse = resp.json().await.unwrap();
+            users.extend(r.users);
 
-        Ok(r.users)
+            // The last page has an empty cursor.
+            cursor = r.response_metadata.next_cursor;
+            if cursor.is_empty() {
+                return Ok(users);
+            }
+        }
     }
 
     /// Get billable info.
@@ -240,6 +273,15 @@ impl fmt::Debug for APIError {
     }
 }
 
+impl From<reqwest::Error> for APIError {
+    fn from(e: reqwest::Error) -> Self {
+        APIError {
+            status_code: e.status().unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+            body: e.to_string(),
+        }
+    }
+}
+
 // This is important for other errors to wrap this one.
 impl error::Error for APIError {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
@@ -509,9 +551,23 @@ pub struct UserInvite {
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct APIResponse {
     pub ok: bool,
+    /// Why the request failed, when `ok` is false.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub error: String,
 
     #[serde(default, skip_serializing_if = "Vec::is_empty", alias = "members")]
     pub users: Vec<User>,
+    #[serde(default)]
+    pub response_metadata: ResponseMetadata,
+}
+
+/// The data type for the paging information of a list.
+/// FROM: https://api.slack.com/docs/pagination
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ResponseMetadata {
+    /// The cursor for the next page, which is empty on the last page.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub next_cursor: String,
 }
 
 /// The data type for a User.
@@ -40,7 +40,7 @@ pub struct Tailscale {
     key: String,
     domain: String,
 
-    client: Arc<Client>,
+    client: Arc<cio_http::Client>,
 }
 
 impl Tailscale {
@@ -58,7 +58,7 @@ impl Tailscale {
                 key: key.to_string(),
                 domain: domain.to_string(),
 
-                client: Arc::new(c),
+                client: Arc::new(cio_http::Client::new(c)),
             },
             Err(e) => panic!("creating client failed: {:?}", e),
         }
@@ -1,9 +1,10 @@
 #![allow(clippy::field_reassign_with_default)]
-pub mod event_types;
-use crate::event_types::EventType;
 pub mod repos;
 use crate::repos::Repo;
-pub mod influx;
+pub mod signatures;
+use crate::signatures::WebhookProvider;
+mod worker;
+use crate::worker::WebhookJob;
 #[macro_use]
 extern crate serde_json;
 
@@ -25,24 +26,34 @@ use dropshot::{
 };
 use google_drive::GoogleDrive;
 use gusto_api::Gusto;
+use hubcaps::comments::{CommentListOptions, CommentOptions};
 use hubcaps::issues::{IssueListOptions, State};
 use hubcaps::Github;
 use quickbooks::QuickBooks;
+use revai::{Job, JobCallback};
 use schemars::JsonSchema;
 use sentry::IntoDsn;
 use serde::{Deserialize, Serialize};
 use serde_qs::Config as QSConfig;
-use sheets::Sheets;
+use sheets::{CellRef, HeaderMap, Sheets};
 
 use cio_api::analytics::NewPageView;
-use cio_api::api_tokens::NewAPIToken;
-use cio_api::applicants::{get_docusign_template_id, get_role_from_sheet_id, Applicant, NewApplicant};
-use cio_api::configs::{get_configs_from_repo, sync_buildings, sync_certificates, sync_conference_rooms, sync_github_outside_collaborators, sync_groups, sync_links, sync_users, User};
+use cio_api::api_tokens::{NewAPIToken, TokenProvider};
+use cio_api::applicants::{get_docusign_template_id, get_role_from_sheet_id, Applicant, ApplicantSheetRow, NewApplicant};
+use cio_api::configs::{
+    get_configs_from_repo, get_configs_from_repo_at_ref, plan_configs, sync_buildings, sync_certificates, sync_conference_rooms, sync_github_outside_collaborators, sync_groups, sync_links,
+    sync_users, User,
+};
 use cio_api::db::Database;
+use cio_api::event_types::EventType;
+use cio_api::inbound_emails::{InboundEmail, NewInboundEmail};
+use cio_api::influx;
 use cio_api::mailchimp::MailchimpWebhook;
 use cio_api::mailing_list::MailingListSubscriber;
 use cio_api::models::{GitHubUser, NewRFD, NewRepo, RFD};
+use cio_api::print_jobs::{update_print_job_source, PrintJob};
 use cio_api::rack_line::RackLineSubscriber;
+use cio_api::recorded_meetings::update_recorded_meeting_from_revai_job;
 use cio_api::rfds::is_image;
 use cio_api::schema::applicants;
 use cio_api::shipments::{get_shipments_spreadsheets, InboundShipment, NewInboundShipment, NewOutboundShipment, OutboundShipment, OutboundShipments};
@@ -114,6 +125,8 @@ async fn main() -> Result<(), String> {
     api.register(listen_github_webhooks).unwrap();
     api.register(listen_mailchimp_mailing_list_webhooks).unwrap();
     api.register(listen_mailchimp_rack_line_webhooks).unwrap();
+    api.register(listen_printy_jobs_update_webhooks).unwrap();
+    api.register(listen_revai_jobs_complete_webhooks).unwrap();
     api.register(listen_shippo_tracking_update_webhooks).unwrap();
     api.register(listen_store_order_create).unwrap();
     api.register(ping_mailchimp_mailing_list_webhooks).unwrap();
@@ -136,7 +149,16 @@ async fn main() -> Result<(), String> {
     /*
      * The functions that implement our API endpoints will share this context.
      */
-    let api_context = Context::new(schema).await;
+    let api_context = Context::new(schema.to_string()).await;
+
+    /*
+     * Start the workers that run the jobs our handlers enqueue. They get their
+     * own context since the server takes ownership of this one.
+     */
+    let worker_context = Arc::new(Context::new(schema).await);
+    for _ in 0..worker::WORKERS {
+        tokio::spawn(worker::run(worker_context.clone()));
+    }
 
     /*
      * Set up the server.
@@ -222,11 +244,14 @@ async fn ping(_rqctx: Arc<RequestContext<Context>>) -> Result<HttpResponseOk<Str
     method = POST,
     path = "/github",
 }]
-async fn listen_github_webhooks(rqctx: Arc<RequestContext<Context>>, body_param: TypedBody<GitHubWebhook>) -> Result<HttpResponseAccepted<String>, HttpError> {
+async fn listen_github_webhooks(rqctx: Arc<RequestContext<Context>>, body_param: UntypedBody) -> Result<HttpResponseAccepted<String>, HttpError> {
     sentry::start_session();
     let api_context = rqctx.context();
+    let db = &api_context.db.with_audit_source("webhook:/github");
 
-    let event = body_param.into_inner();
+    // Make sure the event was actually sent by GitHub.
+    verify_webhook(&rqctx, WebhookProvider::GitHub, body_param.as_bytes()).await?;
+    let event: GitHubWebhook = parse_webhook_body(WebhookProvider::GitHub, body_param.as_bytes())?;
 
     // Parse the `X-GitHub-Event` header.
     // TODO: make this nicer when supported as a first class method in dropshot.
@@ -314,7 +339,7 @@ async fn listen_github_webhooks(rqctx: Arc<RequestContext<Context>>, body_param:
             api_context.influx.query(influx_event, event_type.name()).await;
 
             // Now let's handle the event.
-            let resp = handle_repository_event(api_context, event).await;
+            let resp = handle_repository_event(api_context, db, event).await;
             sentry::end_session();
             return resp;
         }
@@ -328,24 +353,31 @@ async fn listen_github_webhooks(rqctx: Arc<RequestContext<Context>>, body_param:
         match repo_name {
             Repo::RFD => match event_type {
                 EventType::Push => {
-                    let resp = handle_rfd_push(api_context, event).await;
+                    // Rendering and uploading the RFDs is slow, so do it from the job queue.
+                    WebhookJob::RFDPush(event).enqueue(db).map_err(handle_db_error)?;
                     sentry::end_session();
-                    return resp;
+                    return Ok(HttpResponseAccepted("ok".to_string()));
                 }
                 EventType::PullRequest => {
-                    let resp = handle_rfd_pull_request(api_context, event).await;
+                    let resp = handle_rfd_pull_request(api_context, db, event).await;
                     sentry::end_session();
                     return resp;
                 }
                 _ => (),
             },
-            Repo::Configs => {
-                if let EventType::Push = event_type {
-                    let resp = handle_configs_push(api_context, event).await;
+            Repo::Configs => match event_type {
+                EventType::Push => {
+                    let resp = handle_configs_push(api_context, db, event).await;
                     sentry::end_session();
                     return resp;
                 }
-            }
+                EventType::PullRequest => {
+                    let resp = handle_configs_pull_request(api_context, db, event).await;
+                    sentry::end_session();
+                    return resp;
+                }
+                _ => (),
+            },
             _ => {
                 // We can throw this out, log it and return early.
                 println!("`{}` event was to the {} repo, no automations are set up for this repo yet", event_type, repo_name);
@@ -374,7 +406,7 @@ async fn trigger_rfd_update_by_number(rqctx: Arc<RequestContext<Context>>, path_
 
     let api_context = rqctx.context();
     let github = &api_context.github;
-    let db = &api_context.db;
+    let db = &api_context.db.with_audit_source("webhook:/rfd/{num}");
 
     let result = RFD::get_from_db(db, num);
     if result.is_none() {
@@ -392,7 +424,7 @@ async fn trigger_rfd_update_by_number(rqctx: Arc<RequestContext<Context>>, path_
     println!("updated pdf `{}` for RFD {}", rfd.get_pdf_filename(), rfd.number_string);
 
     // Save the rfd back to our database.
-    rfd.update(db).await;
+    rfd.update(db).await.map_err(handle_db_error)?;
 
     sentry::end_session();
     Ok(HttpResponseAccepted("ok".to_string()))
@@ -446,7 +478,7 @@ async fn listen_google_sheets_edit_webhooks(rqctx: Arc<RequestContext<Context>>,
     let sheets = Sheets::new(token.clone());
 
     let api_context = rqctx.context();
-    let db = &api_context.db;
+    let db = &api_context.db.with_audit_source("webhook:/google/sheets/edit");
     let github = &api_context.github;
 
     let event = body_param.into_inner();
@@ -464,9 +496,25 @@ async fn listen_google_sheets_edit_webhooks(rqctx: Arc<RequestContext<Context>>,
     // and the database:
     //  - The applicant's email
     //  - The name of the column that was updated.
-    // Let's first get the email for this applicant. This is always in column B.
-    let mut cell_name = format!("B{}", event.event.range.row_start);
-    let email = sheets.get_value(&event.spreadsheet.id, cell_name).await.unwrap();
+    // The email for this applicant is always in column B, and the header for
+    // the column of the cell that changed is always in row 1, so we get both at once.
+    // The range in the event is one indexed.
+    let row: usize = (event.event.range.row_start - 1).try_into().unwrap();
+    let column: usize = (event.event.range.column_start - 1).try_into().unwrap();
+    let cells = sheets
+        .batch_get_values(&event.spreadsheet.id, &[CellRef::new(1, row).to_string(), CellRef::new(column, 0).to_string()])
+        .await
+        .unwrap();
+    let cell_value = |index: usize| -> String {
+        cells
+            .get(index)
+            .and_then(|c| c.values.as_ref())
+            .and_then(|v| v.first())
+            .and_then(|r| r.first())
+            .cloned()
+            .unwrap_or_default()
+    };
+    let email = cell_value(0);
 
     if email.is_empty() {
         // We can return early, the row does not have an email.
@@ -475,12 +523,7 @@ async fn listen_google_sheets_edit_webhooks(rqctx: Arc<RequestContext<Context>>,
         return Ok(HttpResponseAccepted("ok".to_string()));
     }
 
-    // Now let's get the header for the column of the cell that changed.
-    // This is always in row 1.
-    // These should be zero indexed.
-    let column_letters = "0ABCDEFGHIJKLMNOPQRSTUVWXYZ";
-    cell_name = format!("{}1", column_letters.chars().nth(event.event.range.column_start.try_into().unwrap()).unwrap().to_string());
-    let column_header = sheets.get_value(&event.spreadsheet.id, cell_name).await.unwrap().to_lowercase();
+    let column_header = cell_value(1).to_lowercase();
 
     // Now let's get the applicant from the database so we can update it.
     let result = applicants::dsl::applicants
@@ -519,15 +562,15 @@ async fn listen_google_sheets_edit_webhooks(rqctx: Arc<RequestContext<Context>>,
             // If they changed their status to OnBoarding let's do the docusign updates.
             if a.status == cio_api::applicant_status::Status::Onboarding.to_string() {
                 // First let's update the applicant.
-                a.update(db).await;
+                a.update(db).await.map_err(handle_db_error)?;
 
                 // Authenticate DocuSign.
-                let ds = docusign::DocuSign::new_from_env().await;
+                let ds: docusign::DocuSign = TokenProvider::new(db).client().await.map_err(handle_db_error)?;
 
                 // Get the template we need.
                 let template_id = get_docusign_template_id(&ds).await;
 
-                a.do_docusign(db, &ds, &template_id).await;
+                a.do_docusign(db, &ds, &template_id).await.map_err(handle_db_error)?;
             }
         }
     } else if column_header.contains("start date") {
@@ -551,16 +594,12 @@ async fn listen_google_sheets_edit_webhooks(rqctx: Arc<RequestContext<Context>>,
     } else if column_header.contains("value in tension [1]") {
         // The person updated the values in tension.
         // We need to get the other value in tension in the next column to the right.
-        let value_column = event.event.range.column_start + 1;
-        cell_name = format!("{}{}", column_letters.chars().nth(value_column.try_into().unwrap()).unwrap().to_string(), event.event.range.row_start);
-        let value_in_tension_2 = sheets.get_value(&event.spreadsheet.id, cell_name).await.unwrap().to_lowercase();
+        let value_in_tension_2 = sheets.get_value(&event.spreadsheet.id, CellRef::new(column + 1, row).to_string()).await.unwrap().to_lowercase();
         a.values_in_tension = vec![value_in_tension_2, event.event.value.to_lowercase()];
     } else if column_header.contains("value in tension [2]") {
         // The person updated the values in tension.
         // We need to get the other value in tension in the next column to the left.
-        let value_column = event.event.range.column_start - 1;
-        cell_name = format!("{}{}", column_letters.chars().nth(value_column.try_into().unwrap()).unwrap().to_string(), event.event.range.row_start);
-        let value_in_tension_1 = sheets.get_value(&event.spreadsheet.id, cell_name).await.unwrap().to_lowercase();
+        let value_in_tension_1 = sheets.get_value(&event.spreadsheet.id, CellRef::new(column - 1, row).to_string()).await.unwrap().to_lowercase();
         a.values_in_tension = vec![value_in_tension_1, event.event.value.to_lowercase()];
     } else {
         // If this is a field wehipmentdon't care about, return early.
@@ -570,7 +609,7 @@ async fn listen_google_sheets_edit_webhooks(rqctx: Arc<RequestContext<Context>>,
     }
 
     // Update the applicant in the database and Airtable.
-    let new_applicant = a.update(db).await;
+    let new_applicant = a.update(db).await.map_err(handle_db_error)?;
 
     // Get all the hiring issues on the configs repository.
     let configs_issues = github
@@ -667,7 +706,7 @@ async fn listen_google_sheets_row_create_webhooks(rqctx: Arc<RequestContext<Cont
     let drive = GoogleDrive::new(token);
 
     let api_context = rqctx.context();
-    let db = &api_context.db;
+    let db = &api_context.db.with_audit_source("webhook:/google/sheets/row/create");
 
     let event = body_param.into_inner();
     println!("{:?}", event);
@@ -688,7 +727,7 @@ async fn listen_google_sheets_row_create_webhooks(rqctx: Arc<RequestContext<Cont
         let mut shipment = NewOutboundShipment::parse_from_row(&event.event.named_values);
         // Create or update the shipment in airtable.
         shipment.notes = format!("Automatically generated from the Google sheet {}", event.spreadsheet.id);
-        shipment.upsert(db).await;
+        shipment.upsert(db).await.map_err(handle_db_error)?;
 
         // Handle if the event is for a swag spreadsheet.
         sentry::end_session();
@@ -704,19 +743,30 @@ async fn listen_google_sheets_row_create_webhooks(rqctx: Arc<RequestContext<Cont
         return Ok(HttpResponseAccepted("ok".to_string()));
     }
 
-    // We do not need to add one to the end of the columns to get the column where the email sent verification is
-    // because google sheets index's at 0, so adding one would put us over, we are just right here.
-    let sent_email_received_column_index = event.event.range.column_end;
-    let sent_email_follow_up_index = event.event.range.column_end + 6;
+    // Find the columns where we mark the emails we sent by their headers, since
+    // they are not always in the same place in every sheet.
+    let header_row = sheets
+        .get_values(&event.spreadsheet.id, "Form Responses 1!1:1".to_string())
+        .await
+        .map_err(|e| handle_db_error(e.into()))?;
+    let headers = HeaderMap::from_values(&header_row.values.unwrap_or_default());
+    let row: usize = (event.event.range.row_start - 1).try_into().unwrap();
+    let (sent_email_received_cell, sent_email_follow_up_cell) = match (
+        headers.cell(ApplicantSheetRow::SENT_EMAIL_RECEIVED_HEADER, row),
+        headers.cell(ApplicantSheetRow::SENT_EMAIL_FOLLOW_UP_HEADER, row),
+    ) {
+        (Some(received), Some(follow_up)) => (received, follow_up),
+        _ => {
+            let message = format!("sheet {} is missing the columns for the emails we sent", event.spreadsheet.id);
+            sentry::capture_message(&message, sentry::Level::Fatal);
+            sentry::end_session();
+            return Err(HttpError::for_internal_error(message));
+        }
+    };
     applicant
-        .expand(
-            &drive,
-            &sheets,
-            sent_email_received_column_index.try_into().unwrap(),
-            sent_email_follow_up_index.try_into().unwrap(),
-            event.event.range.row_start.try_into().unwrap(),
-        )
-        .await;
+        .expand(db, &drive, &sheets, sent_email_received_cell, sent_email_follow_up_cell)
+        .await
+        .map_err(handle_db_error)?;
 
     if !applicant.sent_email_received {
         println!("applicant is new, sending internal notifications: {:?}", applicant);
@@ -731,7 +781,7 @@ async fn listen_google_sheets_row_create_webhooks(rqctx: Arc<RequestContext<Cont
     }
 
     // Send the applicant to the database and Airtable.
-    let a = applicant.upsert(db).await;
+    let a = applicant.upsert(db).await.map_err(handle_db_error)?;
 
     println!("applicant {} created successfully", a.email);
     sentry::end_session();
@@ -757,6 +807,7 @@ pub struct GoogleSpreadsheetRowCreateEvent {
 async fn listen_airtable_employees_print_home_address_label_webhooks(rqctx: Arc<RequestContext<Context>>, body_param: TypedBody<AirtableRowEvent>) -> Result<HttpResponseAccepted<String>, HttpError> {
     sentry::start_session();
     let api_context = rqctx.context();
+    let db = &api_context.db.with_audit_source("webhook:/airtable/employees/print_home_address_label");
 
     let event = body_param.into_inner();
     println!("{:?}", event);
@@ -768,10 +819,10 @@ async fn listen_airtable_employees_print_home_address_label_webhooks(rqctx: Arc<
     }
 
     // Get the row from airtable.
-    let user = User::get_from_airtable(&event.record_id).await;
+    let user = User::get_from_airtable(&event.record_id).await.map_err(handle_db_error)?;
 
     // Create a new shipment for the employee and print the label.
-    user.create_shipment_to_home_address(&api_context.db).await;
+    user.create_shipment_to_home_address(db).await.map_err(handle_db_error)?;
 
     sentry::end_session();
     Ok(HttpResponseAccepted("ok".to_string()))
@@ -785,10 +836,12 @@ async fn listen_airtable_employees_print_home_address_label_webhooks(rqctx: Arc<
     path = "/airtable/swag/inventory/items/print_barcode_labels",
 }]
 async fn listen_airtable_swag_inventory_items_print_barcode_labels_webhooks(
-    _rqctx: Arc<RequestContext<Context>>,
+    rqctx: Arc<RequestContext<Context>>,
     body_param: TypedBody<AirtableRowEvent>,
 ) -> Result<HttpResponseAccepted<String>, HttpError> {
     sentry::start_session();
+    let api_context = rqctx.context();
+    let db = &api_context.db.with_audit_source("webhook:/airtable/swag/inventory/items/print_barcode_labels");
 
     let event = body_param.into_inner();
     println!("{:?}", event);
@@ -800,11 +853,14 @@ async fn listen_airtable_swag_inventory_items_print_barcode_labels_webhooks(
     }
 
     // Get the row from airtable.
-    let swag_inventory_item = SwagInventoryItem::get_from_airtable(&event.record_id).await;
+    let mut swag_inventory_item = SwagInventoryItem::get_from_airtable(&event.record_id).await.map_err(handle_db_error)?;
 
     // Print the barcode label(s).
-    swag_inventory_item.print_label().await;
-    println!("swag inventory item {} printed label", swag_inventory_item.name);
+    swag_inventory_item.print_label(db).await.map_err(handle_db_error)?;
+    println!("swag inventory item {} sent label to the printer", swag_inventory_item.name);
+
+    // Update Airtable with the print job.
+    swag_inventory_item.update(db).await.map_err(handle_db_error)?;
 
     sentry::end_session();
     Ok(HttpResponseAccepted("ok".to_string()))
@@ -820,6 +876,7 @@ async fn listen_airtable_swag_inventory_items_print_barcode_labels_webhooks(
 async fn listen_airtable_applicants_request_background_check_webhooks(rqctx: Arc<RequestContext<Context>>, body_param: TypedBody<AirtableRowEvent>) -> Result<HttpResponseAccepted<String>, HttpError> {
     sentry::start_session();
     let api_context = rqctx.context();
+    let db = &api_context.db.with_audit_source("webhook:/airtable/applicants/request_background_check");
 
     let event = body_param.into_inner();
     println!("{:?}", event);
@@ -831,10 +888,10 @@ async fn listen_airtable_applicants_request_background_check_webhooks(rqctx: Arc
     }
 
     // Get the row from airtable.
-    let mut applicant = Applicant::get_from_airtable(&event.record_id).await;
+    let mut applicant = Applicant::get_from_airtable(&event.record_id).await.map_err(handle_db_error)?;
     if applicant.criminal_background_check_status.is_empty() {
         // Request the background check, since we previously have not requested one.
-        applicant.send_background_check_invitation(&api_context.db).await;
+        applicant.send_background_check_invitation(db).await.map_err(handle_db_error)?;
         println!("sent background check invitation to applicant: {}", applicant.email);
     }
 
@@ -856,6 +913,7 @@ async fn listen_airtable_shipments_outbound_create_webhooks(rqctx: Arc<RequestCo
     println!("{:?}", event);
 
     let api_context = rqctx.context();
+    let db = &api_context.db.with_audit_source("webhook:/airtable/shipments/outbound/create");
 
     if event.record_id.is_empty() {
         sentry::capture_message("Record id is empty", sentry::Level::Fatal);
@@ -863,8 +921,18 @@ async fn listen_airtable_shipments_outbound_create_webhooks(rqctx: Arc<RequestCo
         return Ok(HttpResponseAccepted("ok".to_string()));
     }
 
+    // Creating the label in Shippo is slow, so do it from the job queue.
+    WebhookJob::ShipmentsOutboundCreate(event).enqueue(db).map_err(handle_db_error)?;
+
+    sentry::end_session();
+    Ok(HttpResponseAccepted("ok".to_string()))
+}
+
+/// Handle a row created in the outbound shipments table in Airtable.
+/// This is run by the job queue.
+async fn handle_airtable_shipments_outbound_create(api_context: &Context, db: &Database, event: AirtableRowEvent) -> Result<HttpResponseAccepted<String>, HttpError> {
     // Get the row from airtable.
-    let shipment = OutboundShipment::get_from_airtable(&event.record_id).await;
+    let shipment = OutboundShipment::get_from_airtable(&event.record_id).await.map_err(handle_db_error)?;
 
     // If it is a row we created from our internal store do nothing.
     if shipment.notes.contains("Oxide store") || shipment.notes.contains("Google sheet") || shipment.notes.contains("Internal") {
@@ -873,19 +941,17 @@ async fn listen_airtable_shipments_outbound_create_webhooks(rqctx: Arc<RequestCo
 
     if shipment.email.is_empty() {
         sentry::capture_message("Got an empty email for row", sentry::Level::Fatal);
-        sentry::end_session();
         return Ok(HttpResponseAccepted("ok".to_string()));
     }
 
     // Update the row in our database.
-    let mut new_shipment = shipment.update(&api_context.db).await;
+    let mut new_shipment = shipment.update(db).await.map_err(handle_db_error)?;
     // Create the shipment in shippo.
-    new_shipment.create_or_get_shippo_shipment(&api_context.db).await;
+    new_shipment.create_or_get_shippo_shipment(db).await.map_err(handle_db_error)?;
     // Update airtable again.
-    new_shipment.update(&api_context.db).await;
+    new_shipment.update(db).await.map_err(handle_db_error)?;
 
     println!("shipment {} created successfully", shipment.email);
-    sentry::end_session();
     Ok(HttpResponseAccepted("ok".to_string()))
 }
 
@@ -915,19 +981,20 @@ async fn listen_airtable_shipments_outbound_reprint_label_webhooks(rqctx: Arc<Re
     }
 
     let api_context = rqctx.context();
+    let db = &api_context.db.with_audit_source("webhook:/airtable/shipments/outbound/reprint_label");
 
     // Get the row from airtable.
-    let mut shipment = OutboundShipment::get_from_airtable(&event.record_id).await;
+    let mut shipment = OutboundShipment::get_from_airtable(&event.record_id).await.map_err(handle_db_error)?;
 
     // Reprint the label.
-    shipment.print_label().await;
+    shipment.print_label(db).await.map_err(handle_db_error)?;
     println!("shipment {} reprinted label", shipment.email);
 
     // Update the field.
     shipment.status = "Label printed".to_string();
 
     // Update Airtable.
-    shipment.update(&api_context.db).await;
+    shipment.update(db).await.map_err(handle_db_error)?;
 
     sentry::end_session();
     Ok(HttpResponseAccepted("ok".to_string()))
@@ -941,10 +1008,12 @@ async fn listen_airtable_shipments_outbound_reprint_label_webhooks(rqctx: Arc<Re
     path = "/airtable/shipments/outbound/resend_shipment_status_email_to_recipient",
 }]
 async fn listen_airtable_shipments_outbound_resend_shipment_status_email_to_recipient_webhooks(
-    _rqctx: Arc<RequestContext<Context>>,
+    rqctx: Arc<RequestContext<Context>>,
     body_param: TypedBody<AirtableRowEvent>,
 ) -> Result<HttpResponseAccepted<String>, HttpError> {
     sentry::start_session();
+    let api_context = rqctx.context();
+    let db = &api_context.db.with_audit_source("webhook:/airtable/shipments/outbound/resend_shipment_status_email_to_recipient");
     let event = body_param.into_inner();
     println!("{:?}", event);
 
@@ -955,10 +1024,10 @@ async fn listen_airtable_shipments_outbound_resend_shipment_status_email_to_reci
     }
 
     // Get the row from airtable.
-    let shipment = OutboundShipment::get_from_airtable(&event.record_id).await;
+    let shipment = OutboundShipment::get_from_airtable(&event.record_id).await.map_err(handle_db_error)?;
 
     // Resend the email to the recipient.
-    shipment.send_email_to_recipient().await;
+    shipment.send_email_to_recipient(db).await;
     println!("resent the shipment email to the recipient {}", shipment.email);
 
     sentry::end_session();
@@ -985,7 +1054,8 @@ async fn listen_airtable_shipments_outbound_schedule_pickup_webhooks(rqctx: Arc<
 
     // Schedule the pickup.
     let api_context = rqctx.context();
-    OutboundShipments::create_pickup(&api_context.db).await;
+    let db = &api_context.db.with_audit_source("webhook:/airtable/shipments/outbound/schedule_pickup");
+    OutboundShipments::create_pickup(db).await;
 
     sentry::end_session();
     Ok(HttpResponseAccepted("ok".to_string()))
@@ -1034,31 +1104,61 @@ pub struct IncomingEmail {
 
 /**
  * Listen for emails coming inbound from SendGrid's parse API.
- * We use this for scanning for packages in emails.
+ * We store them and route them to applicants, accounts payable and inbound
+ * shipments.
  */
 #[endpoint {
     method = POST,
     path = "/emails/incoming/sendgrid/parse",
 }]
-async fn listen_emails_incoming_sendgrid_parse_webhooks(_rqctx: Arc<RequestContext<Context>>, body_param: UntypedBody) -> Result<HttpResponseAccepted<String>, HttpError> {
+async fn listen_emails_incoming_sendgrid_parse_webhooks(rqctx: Arc<RequestContext<Context>>, body_param: UntypedBody) -> Result<HttpResponseAccepted<String>, HttpError> {
     sentry::start_session();
     let event_string = body_param.as_str().unwrap().to_string();
-    sentry::capture_message(&format!("sendgrid parse event string: {}", event_string), sentry::Level::Info);
 
     let qs_non_strict = QSConfig::new(10, false);
 
-    let event: IncomingEmail = qs_non_strict.deserialize_str(&event_string).unwrap();
+    let event: IncomingEmail = qs_non_strict.deserialize_str(&event_string).map_err(|e| {
+        sentry::capture_message(&format!("decoding sendgrid parse body failed: {}", e), sentry::Level::Info);
+        sentry::end_session();
+        HttpError::for_bad_request(None, format!("decoding body failed: {}", e))
+    })?;
 
-    sentry::capture_message(&format!("sendgrid parse: {:?}", event), sentry::Level::Info);
+    let api_context = rqctx.context();
+    let db = &api_context.db.with_audit_source("webhook:/emails/incoming/sendgrid/parse");
 
     // Parse the email MIME message.
-    let parsed = mailparse::parse_mail(event.email.as_bytes()).unwrap();
-    sentry::capture_message(&format!("sendgrid parsed: {:?}", parsed), sentry::Level::Info);
+    let mut new_email = match NewInboundEmail::parse(&event.email) {
+        Ok(e) => e,
+        Err(e) => {
+            // SendGrid would keep sending it, so just let someone know.
+            sentry::capture_message(&format!("sendgrid parse: {}", e), sentry::Level::Fatal);
+            sentry::end_session();
+            return Ok(HttpResponseAccepted("ok".to_string()));
+        }
+    };
+    new_email.spam_score = event.spam_score.trim().parse().unwrap_or_default();
+
+    // Store the email first, so we still have it if routing it fails.
+    let email = new_email.create(db).map_err(handle_db_error)?;
+    println!("stored inbound email {} from {}", email.message_id, email.from_email);
+
+    // Uploading the attachments to Drive and routing the email is slow, so do it
+    // from the job queue.
+    WebhookJob::InboundEmailRoute(email.id).enqueue(db).map_err(handle_db_error)?;
 
     sentry::end_session();
     Ok(HttpResponseAccepted("ok".to_string()))
 }
 
+/// Upload the attachments of an inbound email and route it.
+/// This is run by the job queue.
+async fn handle_inbound_email_route(db: &Database, id: i32) -> Result<HttpResponseAccepted<String>, HttpError> {
+    let mut email = InboundEmail::get_by_id(db, id).map_err(handle_db_error)?;
+    email.process(db).await.map_err(handle_db_error)?;
+
+    Ok(HttpResponseAccepted("ok".to_string()))
+}
+
 /**
  * Listen for rows created in our Airtable workspace.
  * These are set up with an Airtable script on the workspaces themselves.
@@ -1079,10 +1179,10 @@ async fn listen_airtable_shipments_inbound_create_webhooks(rqctx: Arc<RequestCon
     }
 
     let api_context = rqctx.context();
-    let db = &api_context.db;
+    let db = &api_context.db.with_audit_source("webhook:/airtable/shipments/inbound/create");
 
     // Get the row from airtable.
-    let record = InboundShipment::get_from_airtable(&event.record_id).await;
+    let record = InboundShipment::get_from_airtable(&event.record_id).await.map_err(handle_db_error)?;
 
     if record.tracking_number.is_empty() || record.carrier.is_empty() {
         // Return early, we don't care.
@@ -1106,11 +1206,11 @@ async fn listen_airtable_shipments_inbound_create_webhooks(rqctx: Arc<RequestCon
     };
 
     new_shipment.expand().await;
-    let mut shipment = new_shipment.upsert_in_db(&db);
+    let mut shipment = new_shipment.upsert_in_db(&db).map_err(handle_db_error)?;
     if shipment.airtable_record_id.is_empty() {
         shipment.airtable_record_id = event.record_id;
     }
-    shipment.update(&db).await;
+    shipment.update(&db).await.map_err(handle_db_error)?;
 
     println!("inbound shipment {} updated successfully", shipment.tracking_number);
     sentry::end_session();
@@ -1124,19 +1224,79 @@ async fn listen_airtable_shipments_inbound_create_webhooks(rqctx: Arc<RequestCon
     method = POST,
     path = "/store/order",
 }]
-async fn listen_store_order_create(rqctx: Arc<RequestContext<Context>>, body_param: TypedBody<Order>) -> Result<HttpResponseAccepted<String>, HttpError> {
+async fn listen_store_order_create(rqctx: Arc<RequestContext<Context>>, body_param: UntypedBody) -> Result<HttpResponseAccepted<String>, HttpError> {
     sentry::start_session();
     let api_context = rqctx.context();
+    let db = &api_context.db.with_audit_source("webhook:/store/order");
 
-    let event = body_param.into_inner();
+    // Make sure the order was actually sent by the store.
+    verify_webhook(&rqctx, WebhookProvider::Store, body_param.as_bytes()).await?;
+    let event: Order = parse_webhook_body(WebhookProvider::Store, body_param.as_bytes())?;
     println!("order {:?}", event);
-    event.do_order(&api_context.db).await;
+    event.do_order(db).await.map_err(handle_db_error)?;
 
     println!("order for {} created successfully", event.email);
     sentry::end_session();
     Ok(HttpResponseAccepted("ok".to_string()))
 }
 
+/**
+ * Listen for print jobs changing status in printy, so the shipment or swag
+ * inventory item the label was printed for shows whether it printed.
+ */
+#[endpoint {
+    method = POST,
+    path = "/printy/jobs/update",
+}]
+async fn listen_printy_jobs_update_webhooks(rqctx: Arc<RequestContext<Context>>, body_param: UntypedBody) -> Result<HttpResponseAccepted<String>, HttpError> {
+    sentry::start_session();
+    let api_context = rqctx.context();
+    let db = &api_context.db.with_audit_source("webhook:/printy/jobs/update");
+
+    // Make sure the event was actually sent by printy.
+    verify_webhook(&rqctx, WebhookProvider::Printy, body_param.as_bytes()).await?;
+    let job: PrintJob = parse_webhook_body(WebhookProvider::Printy, body_param.as_bytes())?;
+    println!("print job {} is {}", job.id, job.status_message());
+
+    update_print_job_source(db, &job).await.map_err(handle_db_error)?;
+
+    sentry::end_session();
+    Ok(HttpResponseAccepted("ok".to_string()))
+}
+
+/**
+ * Listen for rev.ai jobs finishing, so we save the transcripts of recorded
+ * meetings as soon as they are ready.
+ */
+#[endpoint {
+    method = POST,
+    path = "/revai/jobs/complete",
+}]
+async fn listen_revai_jobs_complete_webhooks(rqctx: Arc<RequestContext<Context>>, body_param: UntypedBody) -> Result<HttpResponseAccepted<String>, HttpError> {
+    sentry::start_session();
+    let api_context = rqctx.context();
+    let db = &api_context.db.with_audit_source("webhook:/revai/jobs/complete");
+
+    // Make sure the event was actually sent by rev.ai.
+    verify_webhook(&rqctx, WebhookProvider::RevAI, body_param.as_bytes()).await?;
+    let event: JobCallback = parse_webhook_body(WebhookProvider::RevAI, body_param.as_bytes())?;
+    println!("rev.ai job {} is {}", event.job.id, event.job.status);
+
+    // Downloading the transcript is slow, so do it from the job queue.
+    WebhookJob::RevAIJobComplete(event.job).enqueue(db).map_err(handle_db_error)?;
+
+    sentry::end_session();
+    Ok(HttpResponseAccepted("ok".to_string()))
+}
+
+/// Save the transcript of the recorded meeting a rev.ai job was for.
+/// This is run by the job queue.
+async fn handle_revai_job_complete(db: &Database, job: Job) -> Result<HttpResponseAccepted<String>, HttpError> {
+    update_recorded_meeting_from_revai_job(db, &job).await.map_err(handle_db_error)?;
+
+    Ok(HttpResponseAccepted("ok".to_string()))
+}
+
 /**
  * Listen for shipment tracking updated from Shippo.
  */
@@ -1144,11 +1304,14 @@ async fn listen_store_order_create(rqctx: Arc<RequestContext<Context>>, body_par
     method = POST,
     path = "/shippo/tracking/update",
 }]
-async fn listen_shippo_tracking_update_webhooks(rqctx: Arc<RequestContext<Context>>, body_param: TypedBody<serde_json::Value>) -> Result<HttpResponseAccepted<String>, HttpError> {
+async fn listen_shippo_tracking_update_webhooks(rqctx: Arc<RequestContext<Context>>, body_param: UntypedBody) -> Result<HttpResponseAccepted<String>, HttpError> {
     sentry::start_session();
     let api_context = rqctx.context();
+    let db = &api_context.db.with_audit_source("webhook:/shippo/tracking/update");
 
-    let event = body_param.into_inner();
+    // Make sure the event was actually sent by Shippo.
+    verify_webhook(&rqctx, WebhookProvider::Shippo, body_param.as_bytes()).await?;
+    let event: serde_json::Value = parse_webhook_body(WebhookProvider::Shippo, body_param.as_bytes())?;
     let body: ShippoTrackingUpdateEvent = serde_json::from_str(&event.to_string()).unwrap_or_else(|e| {
         sentry::capture_message(&format!("decoding event body for shippo `{}` failed: {}", event.to_string(), e), sentry::Level::Info);
 
@@ -1164,7 +1327,7 @@ async fn listen_shippo_tracking_update_webhooks(rqctx: Arc<RequestContext<Contex
     }
 
     // Update the inbound shipment, if it exists.
-    if let Some(mut shipment) = InboundShipment::get_from_db(&api_context.db, ts.tracking_number.to_string(), ts.carrier.to_string()) {
+    if let Some(mut shipment) = InboundShipment::get_from_db(db, ts.tracking_number.to_string(), ts.carrier.to_string()) {
         // Get the tracking status for the shipment and fill in the details.
         shipment.tracking_number = ts.tracking_number.to_string();
         let tracking_status = ts.tracking_status.unwrap_or_default();
@@ -1195,16 +1358,16 @@ async fn listen_shippo_tracking_update_webhooks(rqctx: Arc<RequestContext<Contex
             shipment.delivered_time = tracking_status.status_date;
         }
 
-        shipment.update(&api_context.db).await;
+        shipment.update(db).await.map_err(handle_db_error)?;
     }
 
     // Update the outbound shipment if it exists.
-    if let Some(mut shipment) = OutboundShipment::get_from_db(&api_context.db, ts.tracking_number.to_string(), ts.carrier.to_string()) {
+    if let Some(mut shipment) = OutboundShipment::get_from_db(db, ts.tracking_number.to_string(), ts.carrier.to_string()) {
         // Update the shipment in shippo.
         // TODO: we likely don't need the extra request here, but it makes the code more DRY.
         // Clean this up eventually.
-        shipment.create_or_get_shippo_shipment(&api_context.db).await;
-        shipment.update(&api_context.db).await;
+        shipment.create_or_get_shippo_shipment(db).await.map_err(handle_db_error)?;
+        shipment.update(db).await.map_err(handle_db_error)?;
     }
 
     println!("shipment {} tracking status updated successfully", ts.tracking_number);
@@ -1228,10 +1391,14 @@ pub struct ShippoTrackingUpdateEvent {
     method = POST,
     path = "/checkr/background/update",
 }]
-async fn listen_checkr_background_update_webhooks(rqctx: Arc<RequestContext<Context>>, body_param: TypedBody<checkr::WebhookEvent>) -> Result<HttpResponseAccepted<String>, HttpError> {
+async fn listen_checkr_background_update_webhooks(rqctx: Arc<RequestContext<Context>>, body_param: UntypedBody) -> Result<HttpResponseAccepted<String>, HttpError> {
     sentry::start_session();
     let api_context = rqctx.context();
-    let event = body_param.into_inner();
+    let db = &api_context.db.with_audit_source("webhook:/checkr/background/update");
+
+    // Make sure the event was actually sent by Checkr.
+    verify_webhook(&rqctx, WebhookProvider::Checkr, body_param.as_bytes()).await?;
+    let event: checkr::WebhookEvent = parse_webhook_body(WebhookProvider::Checkr, body_param.as_bytes())?;
 
     // Run the update of the background checks.
     // If we have a candidate ID let's get them from checkr.
@@ -1250,7 +1417,7 @@ async fn listen_checkr_background_update_webhooks(rqctx: Arc<RequestContext<Cont
                 .or(applicants::dsl::name.eq(format!("{} {}", candidate.first_name, candidate.last_name))),
         )
         .filter(applicants::dsl::status.eq(cio_api::applicant_status::Status::Onboarding.to_string()))
-        .first::<Applicant>(&api_context.db.conn());
+        .first::<Applicant>(&db.conn());
     if result.is_ok() {
         let mut applicant = result.unwrap();
         // Set the status for the report.
@@ -1262,7 +1429,7 @@ async fn listen_checkr_background_update_webhooks(rqctx: Arc<RequestContext<Cont
         }
 
         // Update the applicant.
-        applicant.update(&api_context.db).await;
+        applicant.update(db).await.map_err(handle_db_error)?;
     }
 
     sentry::end_session();
@@ -1288,6 +1455,7 @@ pub struct AuthCallback {
 async fn listen_auth_gusto_callback(rqctx: Arc<RequestContext<Context>>, query_args: Query<AuthCallback>) -> Result<HttpResponseAccepted<String>, HttpError> {
     sentry::start_session();
     let api_context = rqctx.context();
+    let db = &api_context.db.with_audit_source("webhook:/auth/gusto/callback");
     let event = query_args.into_inner();
 
     sentry::capture_message(&format!("auth gusto callback: {:?}", event), sentry::Level::Info);
@@ -1298,20 +1466,21 @@ async fn listen_auth_gusto_callback(rqctx: Arc<RequestContext<Context>>, query_a
     // Let's get the token from the code.
     let t = g.get_access_token(&event.code).await.unwrap();
     // Save the token to the database.
-    let token = NewAPIToken {
+    let mut token = NewAPIToken {
         product: "gusto".to_string(),
         token_type: t.token_type.to_string(),
-        access_token: t.access_token.to_string(),
+        access_token: "".to_string(),
         expires_in: t.expires_in as i32,
-        refresh_token: t.refresh_token.to_string(),
+        refresh_token: "".to_string(),
         refresh_token_expires_in: t.x_refresh_token_expires_in as i32,
         company_id: "".to_string(),
         item_id: "".to_string(),
         user_email: "".to_string(),
         last_updated_at: Utc::now(),
     };
+    token.set_tokens(&t.access_token, &t.refresh_token).map_err(handle_db_error)?;
     // Update it in the database.
-    token.upsert(&api_context.db).await;
+    token.upsert(db).await.map_err(handle_db_error)?;
 
     sentry::end_session();
     Ok(HttpResponseAccepted("ok".to_string()))
@@ -1325,6 +1494,7 @@ async fn listen_auth_gusto_callback(rqctx: Arc<RequestContext<Context>>, query_a
 async fn listen_auth_quickbooks_callback(rqctx: Arc<RequestContext<Context>>, query_args: Query<AuthCallback>) -> Result<HttpResponseAccepted<String>, HttpError> {
     sentry::start_session();
     let api_context = rqctx.context();
+    let db = &api_context.db.with_audit_source("webhook:/auth/quickbooks/callback");
     let event = query_args.into_inner();
 
     // Initialize the QuickBooks client.
@@ -1332,20 +1502,21 @@ async fn listen_auth_quickbooks_callback(rqctx: Arc<RequestContext<Context>>, qu
     // Let's get the token from the code.
     let t = qb.get_access_token(&event.code).await.unwrap();
     // Save the token to the database.
-    let token = NewAPIToken {
+    let mut token = NewAPIToken {
         product: "quickbooks".to_string(),
         token_type: t.token_type.to_string(),
-        access_token: t.access_token.to_string(),
+        access_token: "".to_string(),
         expires_in: t.expires_in as i32,
-        refresh_token: t.refresh_token.to_string(),
+        refresh_token: "".to_string(),
         refresh_token_expires_in: t.x_refresh_token_expires_in as i32,
         company_id: event.realm_id.to_string(),
         item_id: "".to_string(),
         user_email: "".to_string(),
         last_updated_at: Utc::now(),
     };
+    token.set_tokens(&t.access_token, &t.refresh_token).map_err(handle_db_error)?;
     // Update it in the database.
-    token.upsert(&api_context.db).await;
+    token.upsert(db).await.map_err(handle_db_error)?;
 
     sentry::end_session();
     Ok(HttpResponseAccepted("ok".to_string()))
@@ -1386,12 +1557,14 @@ async fn listen_auth_docusign_callback(_rqctx: Arc<RequestContext<Context>>, que
     method = POST,
     path = "/docusign/envelope/update",
 }]
-async fn listen_docusign_envelope_update_webhooks(rqctx: Arc<RequestContext<Context>>, body_param: TypedBody<docusign::Envelope>) -> Result<HttpResponseAccepted<String>, HttpError> {
+async fn listen_docusign_envelope_update_webhooks(rqctx: Arc<RequestContext<Context>>, body_param: UntypedBody) -> Result<HttpResponseAccepted<String>, HttpError> {
     sentry::start_session();
     let api_context = rqctx.context();
-    let db = &api_context.db;
+    let db = &api_context.db.with_audit_source("webhook:/docusign/envelope/update");
 
-    let event = body_param.into_inner();
+    // Make sure the event was actually sent by DocuSign.
+    verify_webhook(&rqctx, WebhookProvider::DocuSign, body_param.as_bytes()).await?;
+    let event: docusign::Envelope = parse_webhook_body(WebhookProvider::DocuSign, body_param.as_bytes())?;
 
     // We need to get the applicant for the envelope.
     let result = applicants::dsl::applicants
@@ -1400,8 +1573,8 @@ async fn listen_docusign_envelope_update_webhooks(rqctx: Arc<RequestContext<Cont
     match result {
         Ok(mut applicant) => {
             // Create our docusign client.
-            let ds = docusign::DocuSign::new_from_env().await;
-            applicant.update_applicant_from_docusign_envelope(db, &ds, event).await;
+            let ds: docusign::DocuSign = TokenProvider::new(db).client().await.map_err(handle_db_error)?;
+            applicant.update_applicant_from_docusign_envelope(db, &ds, event).await.map_err(handle_db_error)?;
         }
         Err(e) => {
             sentry::capture_message(
@@ -1423,7 +1596,7 @@ async fn listen_docusign_envelope_update_webhooks(rqctx: Arc<RequestContext<Cont
 async fn listen_analytics_page_view_webhooks(rqctx: Arc<RequestContext<Context>>, body_param: TypedBody<NewPageView>) -> Result<HttpResponseAccepted<String>, HttpError> {
     sentry::start_session();
     let api_context = rqctx.context();
-    let db = &api_context.db;
+    let db = &api_context.db.with_audit_source("webhook:/analytics/page_view");
 
     let mut event = body_param.into_inner();
     println!("{:?}", event);
@@ -1432,7 +1605,7 @@ async fn listen_analytics_page_view_webhooks(rqctx: Arc<RequestContext<Context>>
     event.set_page_link();
 
     // Add the page_view to the database and Airttable.
-    let pv = event.create(db).await;
+    let pv = event.create(db).await.map_err(handle_db_error)?;
 
     println!("page_view `{} | {}` created successfully", pv.page_link, pv.user_email);
     sentry::end_session();
@@ -1456,7 +1629,10 @@ async fn ping_mailchimp_mailing_list_webhooks(_rqctx: Arc<RequestContext<Context
 async fn listen_mailchimp_mailing_list_webhooks(rqctx: Arc<RequestContext<Context>>, body_param: UntypedBody) -> Result<HttpResponseAccepted<String>, HttpError> {
     sentry::start_session();
     let api_context = rqctx.context();
-    let db = &api_context.db;
+    let db = &api_context.db.with_audit_source("webhook:/mailchimp/mailing_list");
+
+    // Make sure the event was actually sent by MailChimp.
+    verify_webhook(&rqctx, WebhookProvider::Mailchimp, body_param.as_bytes()).await?;
 
     // We should have a string, which we will then parse into our args.
     let event_string = body_param.as_str().unwrap().to_string();
@@ -1477,7 +1653,7 @@ async fn listen_mailchimp_mailing_list_webhooks(rqctx: Arc<RequestContext<Contex
     let existing = MailingListSubscriber::get_from_db(db, new_subscriber.email.to_string());
     if existing.is_none() {
         // Update the subscriber in the database.
-        let subscriber = new_subscriber.upsert(db).await;
+        let subscriber = new_subscriber.upsert(db).await.map_err(handle_db_error)?;
 
         // Parse the signup into a slack message.
         // Send the message to the slack channel.
@@ -1510,7 +1686,10 @@ async fn ping_mailchimp_rack_line_webhooks(_rqctx: Arc<RequestContext<Context>>)
 async fn listen_mailchimp_rack_line_webhooks(rqctx: Arc<RequestContext<Context>>, body_param: UntypedBody) -> Result<HttpResponseAccepted<String>, HttpError> {
     sentry::start_session();
     let api_context = rqctx.context();
-    let db = &api_context.db;
+    let db = &api_context.db.with_audit_source("webhook:/mailchimp/rack_line");
+
+    // Make sure the event was actually sent by MailChimp.
+    verify_webhook(&rqctx, WebhookProvider::Mailchimp, body_param.as_bytes()).await?;
 
     // We should have a string, which we will then parse into our args.
     let event_string = body_param.as_str().unwrap().to_string();
@@ -1531,7 +1710,7 @@ async fn listen_mailchimp_rack_line_webhooks(rqctx: Arc<RequestContext<Context>>
     let existing = RackLineSubscriber::get_from_db(db, new_subscriber.email.to_string());
     if existing.is_none() {
         // Update the subscriber in the database.
-        let subscriber = new_subscriber.upsert(db).await;
+        let subscriber = new_subscriber.upsert(db).await.map_err(handle_db_error)?;
 
         // Parse the signup into a slack message.
         // Send the message to the slack channel.
@@ -2081,6 +2260,39 @@ pub mod deserialize_null_string {
     }
 }
 
+/// Verify a webhook was sent by the provider we expect.
+/// Requests that fail the check are logged and get a 401.
+async fn verify_webhook(rqctx: &Arc<RequestContext<Context>>, provider: WebhookProvider, body: &[u8]) -> Result<(), HttpError> {
+    let req = rqctx.request.lock().await;
+    let query = req.uri().query().unwrap_or_default();
+
+    if let Err(e) = provider.verify(req.headers(), query, body) {
+        println!("`{}` webhook to {} failed verification: {}", provider, req.uri().path(), e);
+        sentry::capture_message(&format!("`{}` webhook to {} failed verification: {}", provider, req.uri().path(), e), sentry::Level::Warning);
+        sentry::end_session();
+        return Err(HttpError::for_client_error(None, http::StatusCode::UNAUTHORIZED, "webhook failed verification".to_string()));
+    }
+
+    Ok(())
+}
+
+/// Parse the JSON body of a webhook we have already verified.
+fn parse_webhook_body<T: serde::de::DeserializeOwned>(provider: WebhookProvider, body: &[u8]) -> Result<T, HttpError> {
+    serde_json::from_slice(body).map_err(|e| {
+        sentry::capture_message(&format!("decoding `{}` webhook body failed: {}", provider, e), sentry::Level::Info);
+        sentry::end_session();
+        HttpError::for_bad_request(None, format!("decoding body failed: {}", e))
+    })
+}
+
+/// Report a failed database or Airtable call and turn it into a 500.
+fn handle_db_error(e: cio_api::Error) -> HttpError {
+    println!("database request failed: {}", e);
+    sentry::capture_message(&format!("database request failed: {}", e), sentry::Level::Fatal);
+    sentry::end_session();
+    HttpError::for_internal_error(e.to_string())
+}
+
 fn filter(files: &[String], dir: &str) -> Vec<String> {
     let mut in_dir: Vec<String> = Default::default();
     for file in files {
@@ -2093,9 +2305,7 @@ fn filter(files: &[String], dir: &str) -> Vec<String> {
 }
 
 /// Handle a `pull_request` event for the rfd repo.
-async fn handle_rfd_pull_request(api_context: &Context, event: GitHubWebhook) -> Result<HttpResponseAccepted<String>, HttpError> {
-    let db = &api_context.db;
-
+async fn handle_rfd_pull_request(api_context: &Context, db: &Database, event: GitHubWebhook) -> Result<HttpResponseAccepted<String>, HttpError> {
     // Get the repo.
     let github_repo = api_context.github.repo(api_context.github_org.to_string(), "rfd".to_string());
 
@@ -2137,12 +2347,13 @@ async fn handle_rfd_pull_request(api_context: &Context, event: GitHubWebhook) ->
             .get(event.pull_request.number.try_into().unwrap())
             .edit(&hubcaps::pulls::PullEditOptions::builder().title(rfd.name.to_string()).build())
             .await
-            .unwrap_or_else(|e| {
-                panic!(
+            .map_err(|e| {
+                println!(
                     "unable to update title of pull request from `{}` to `{}` for pr#{}: {}, {:?} {}",
                     event.pull_request.title, rfd.name, event.pull_request.number, e, rfd, number
-                )
-            });
+                );
+                handle_db_error(e.into())
+            })?;
     }
 
     // Update the labels for the pull request.
@@ -2186,11 +2397,10 @@ async fn handle_rfd_pull_request(api_context: &Context, event: GitHubWebhook) ->
 
             // Try to get the markdown instead.
             path = format!("{}/README.md", dir);
-            let contents = github_repo
-                .content()
-                .file(&path, &branch)
-                .await
-                .unwrap_or_else(|e| panic!("getting file contents for {} on branch {} failed: {}", path, branch, e));
+            let contents = github_repo.content().file(&path, &branch).await.map_err(|e| {
+                println!("getting file contents for {} on branch {} failed: {}", path, branch, e);
+                handle_db_error(e.into())
+            })?;
 
             rfd.content = from_utf8(&contents.content).unwrap().trim().to_string();
             rfd.sha = contents.sha;
@@ -2212,7 +2422,7 @@ async fn handle_rfd_pull_request(api_context: &Context, event: GitHubWebhook) ->
     }
 
     // Update the RFD to show the new state and link in the database.
-    rfd.update(db).await;
+    rfd.update(db).await.map_err(handle_db_error)?;
 
     // Update the file in GitHub.
     // Keep in mind: this push will kick off another webhook.
@@ -2223,15 +2433,13 @@ async fn handle_rfd_pull_request(api_context: &Context, event: GitHubWebhook) ->
 }
 
 /// Handle a `push` event for the rfd repo.
-async fn handle_rfd_push(api_context: &Context, event: GitHubWebhook) -> Result<HttpResponseAccepted<String>, HttpError> {
+async fn handle_rfd_push(api_context: &Context, db: &Database, event: GitHubWebhook) -> Result<HttpResponseAccepted<String>, HttpError> {
     // Get gsuite token.
     // We re-get the token here because otherwise it will expire.
     let token = get_gsuite_token("").await;
     // Initialize the Google Drive client.
     let drive = GoogleDrive::new(token);
 
-    let db = &api_context.db;
-
     // Get the repo.
     let github_repo = api_context.github.repo(api_context.github_org.to_string(), event.repository.name.to_string());
 
@@ -2339,21 +2547,23 @@ async fn handle_rfd_push(api_context: &Context, event: GitHubWebhook) -> Result<
             }
 
             // Update the RFD in the database.
-            let mut rfd = new_rfd.upsert(db).await;
+            let mut rfd = new_rfd.upsert(db).await.map_err(handle_db_error)?;
             // Update all the fields for the RFD.
             rfd.expand(&api_context.github).await;
-            rfd.update(db).await;
+            rfd.update(db).await.map_err(handle_db_error)?;
             println!("updated RFD {} in the database", new_rfd.number_string);
             println!("updated airtable for RFD {}", new_rfd.number_string);
 
             // Create all the shorturls for the RFD if we need to,
             // this would be on added files, only.
-            generate_shorturls_for_rfds(&db, &api_context.github.repo(&api_context.github_org, "configs")).await;
+            generate_shorturls_for_rfds(&db, &api_context.github.repo(&api_context.github_org, "configs"))
+                .await
+                .map_err(handle_db_error)?;
             println!("generated shorturls for the rfds");
 
             // Update the PDFs for the RFD.
             rfd.convert_and_upload_pdf(&api_context.github).await;
-            rfd.update(db).await;
+            rfd.update(db).await.map_err(handle_db_error)?;
             println!("updated pdf `{}` for RFD {}", new_rfd.number_string, rfd.get_pdf_filename());
 
             // Check if the RFD state changed from what is currently in the
@@ -2416,7 +2626,7 @@ async fn handle_rfd_push(api_context: &Context, event: GitHubWebhook) -> Result<
                 rfd_mut.update_state("published", file.ends_with(".md"));
 
                 // Update the RFD to show the new state in the database.
-                rfd_mut.update(db).await;
+                rfd_mut.update(db).await.map_err(handle_db_error)?;
 
                 // Update the file in GitHub.
                 // Keep in mind: this push will kick off another webhook.
@@ -2463,12 +2673,19 @@ async fn handle_rfd_push(api_context: &Context, event: GitHubWebhook) -> Result<
 }
 
 /// Handle a `push` event for the configs repo.
-async fn handle_configs_push(api_context: &Context, event: GitHubWebhook) -> Result<HttpResponseAccepted<String>, HttpError> {
+async fn handle_configs_push(api_context: &Context, db: &Database, event: GitHubWebhook) -> Result<HttpResponseAccepted<String>, HttpError> {
     // Get the repo.
     let github_repo = api_context.github.repo(api_context.github_org.to_string(), event.repository.name.to_string());
 
     // Get the commit.
-    let mut commit = event.commits.get(0).unwrap().clone();
+    let mut commit = match event.commits.get(0) {
+        Some(commit) => commit.clone(),
+        None => {
+            // We can throw this out, log it and return early.
+            println!("`push` event has no commits: {:?}", event);
+            return Ok(HttpResponseAccepted("ok".to_string()));
+        }
+    };
 
     // Ignore any changes that are not to the `configs/` directory.
     let dir = "configs/";
@@ -2489,15 +2706,15 @@ async fn handle_configs_push(api_context: &Context, event: GitHubWebhook) -> Res
     }
 
     // Get the configs from our repo.
-    let configs = get_configs_from_repo(&api_context.github).await;
+    let configs = get_configs_from_repo(&api_context.github).await.map_err(handle_db_error)?;
 
     // Check if the links.toml file changed.
     if commit.file_changed("configs/links.toml") || commit.file_changed("configs/huddles.toml") {
         // Update our links in the database.
-        sync_links(&api_context.db, configs.links, configs.huddles).await;
+        sync_links(db, configs.links, configs.huddles).await.map_err(handle_db_error)?;
 
         // We need to update the short URLs for the links.
-        generate_shorturls_for_configs_links(&api_context.db, &github_repo).await;
+        generate_shorturls_for_configs_links(db, &github_repo).await.map_err(handle_db_error)?;
         println!("generated shorturls for the configs links");
     }
 
@@ -2505,34 +2722,34 @@ async fn handle_configs_push(api_context: &Context, event: GitHubWebhook) -> Res
     // IMPORTANT: we need to sync the groups _before_ we sync the users in case we
     // added a new group to GSuite.
     if commit.file_changed("configs/groups.toml") {
-        sync_groups(&api_context.db, configs.groups).await;
+        sync_groups(db, configs.groups).await.map_err(handle_db_error)?;
     }
 
     // Check if the users.toml file changed.
     if commit.file_changed("configs/users.toml") {
-        sync_users(&api_context.db, &api_context.github, configs.users).await;
+        sync_users(db, &api_context.github, configs.users).await.map_err(handle_db_error)?;
     }
 
     if commit.file_changed("configs/users.toml") || commit.file_changed("configs/groups.toml") {
         // Sync okta users and group from the database.
         // Do this after we update the users and groups in the database.
-        generate_terraform_files_for_okta(&api_context.github, &api_context.db).await;
+        generate_terraform_files_for_okta(&api_context.github, db).await.map_err(handle_db_error)?;
     }
 
     // Check if the buildings.toml file changed.
     // Buildings needs to be synchronized _before_ we move on to conference rooms.
     if commit.file_changed("configs/buildings.toml") {
-        sync_buildings(&api_context.db, configs.buildings).await;
+        sync_buildings(db, configs.buildings).await.map_err(handle_db_error)?;
     }
 
     // Check if the resources.toml file changed.
     if commit.file_changed("configs/resources.toml") {
-        sync_conference_rooms(&api_context.db, configs.resources).await;
+        sync_conference_rooms(db, configs.resources).await.map_err(handle_db_error)?;
     }
 
     // Check if the certificates.toml file changed.
     if commit.file_changed("configs/certificates.toml") {
-        sync_certificates(&api_context.db, &api_context.github, configs.certificates).await;
+        sync_certificates(db, &api_context.github, configs.certificates).await.map_err(handle_db_error)?;
     }
 
     // Check if the github-outside-collaborators.toml file changed.
@@ -2546,16 +2763,72 @@ async fn handle_configs_push(api_context: &Context, event: GitHubWebhook) -> Res
     Ok(HttpResponseAccepted("ok".to_string()))
 }
 
+/// The first line of the comment with the configs plan, used to find it again.
+const CONFIGS_PLAN_HEADER: &str = "### Configs plan";
+
+/// Handle a `pull_request` event for the configs repo.
+/// We comment on the pull request with the changes that syncing the configs would
+/// make once it is merged, so they can be reviewed first.
+async fn handle_configs_pull_request(api_context: &Context, db: &Database, event: GitHubWebhook) -> Result<HttpResponseAccepted<String>, HttpError> {
+    // We only care about pull requests that were opened or had new commits pushed.
+    if event.action != "opened" && event.action != "reopened" && event.action != "synchronize" {
+        // We can throw this out, log it and return early.
+        println!("no automations are set up for action `{}` yet", event.action);
+        return Ok(HttpResponseAccepted("ok".to_string()));
+    }
+
+    // Get the repo.
+    let github_repo = api_context.github.repo(api_context.github_org.to_string(), event.repository.name.to_string());
+
+    // Get the configs from the head of the pull request.
+    let branch = event.pull_request.head.commit_ref.to_string();
+    let body = match get_configs_from_repo_at_ref(&api_context.github, &branch).await {
+        Ok(configs) => {
+            let plan = plan_configs(db, &configs).map_err(handle_db_error)?;
+            format!("{}\n\nThis is what will change once this is merged:\n\n{}", CONFIGS_PLAN_HEADER, plan.to_markdown())
+        }
+        Err(cio_api::Error::Configs(e)) => format!(
+            "{}\n\nParsing the configs on `{}` failed, nothing will be synced until this is fixed:\n\n```\n{}\n```",
+            CONFIGS_PLAN_HEADER, branch, e
+        ),
+        Err(e) => return Err(handle_db_error(e)),
+    };
+
+    // Update the plan we already commented on the pull request, if there is one,
+    // rather than adding a new comment every time commits are pushed.
+    let comments = github_repo.issue(event.pull_request.number.try_into().unwrap()).comments();
+    let existing = comments
+        .list(&CommentListOptions::builder().per_page(100).build())
+        .await
+        .map_err(|e| handle_db_error(e.into()))?
+        .into_iter()
+        .find(|c| c.user.login.ends_with("[bot]") && c.body.starts_with(CONFIGS_PLAN_HEADER));
+    match existing {
+        Some(comment) => {
+            comments.edit(comment.id, &CommentOptions { body }).await.map_err(|e| handle_db_error(e.into()))?;
+            println!("updated the configs plan on pull request {}", event.pull_request.number);
+        }
+        None => {
+            comments.create(&CommentOptions { body }).await.map_err(|e| handle_db_error(e.into()))?;
+            println!("commented the configs plan on pull request {}", event.pull_request.number);
+        }
+    }
+
+    Ok(HttpResponseAccepted("ok".to_string()))
+}
+
 /// Handle the `repository` event for all repos.
-async fn handle_repository_event(api_context: &Context, event: GitHubWebhook) -> Result<HttpResponseAccepted<String>, HttpError> {
+async fn handle_repository_event(api_context: &Context, db: &Database, event: GitHubWebhook) -> Result<HttpResponseAccepted<String>, HttpError> {
     let repo = &api_context.github.repo(event.repository.owner.login, event.repository.name).get().await.unwrap();
     let nr = NewRepo::new(repo.clone());
-    nr.upsert(&api_context.db).await;
+    nr.upsert(db).await.map_err(handle_db_error)?;
 
     // TODO: since we know only one repo changed we don't need to refresh them all,
     // make this a bit better.
     // Update the short urls for all the repos.
-    generate_shorturls_for_repos(&api_context.db, &api_context.github.repo(&api_context.github_org, "configs")).await;
+    generate_shorturls_for_repos(db, &api_context.github.repo(&api_context.github_org, "configs"))
+        .await
+        .map_err(handle_db_error)?;
     println!("generated shorturls for all the GitHub repos");
 
     Ok(HttpResponseAccepted("ok".to_string()))
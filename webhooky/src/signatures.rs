@@ -0,0 +1,233 @@
+use std::env;
+use std::fmt;
+
+use hmac::{Hmac, Mac, NewMac};
+use http::HeaderMap;
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// The third parties we receive webhooks from and verify before doing any work.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum WebhookProvider {
+    Checkr,
+    DocuSign,
+    GitHub,
+    Mailchimp,
+    Printy,
+    RevAI,
+    Shippo,
+    Store,
+}
+
+impl WebhookProvider {
+    /// Returns a static string for the provider name.
+    pub fn name(self) -> &'static str {
+        match self {
+            WebhookProvider::Checkr => "checkr",
+            WebhookProvider::DocuSign => "docusign",
+            WebhookProvider::GitHub => "github",
+            WebhookProvider::Mailchimp => "mailchimp",
+            WebhookProvider::Printy => "printy",
+            WebhookProvider::RevAI => "revai",
+            WebhookProvider::Shippo => "shippo",
+            WebhookProvider::Store => "store",
+        }
+    }
+
+    /// Returns the environment variable that holds the secret for the provider.
+    pub fn secret_env_var(self) -> &'static str {
+        match self {
+            WebhookProvider::Checkr => "CHECKR_WEBHOOK_SECRET",
+            WebhookProvider::DocuSign => "DOCUSIGN_WEBHOOK_SECRET",
+            WebhookProvider::GitHub => "GH_WEBHOOK_SECRET",
+            WebhookProvider::Mailchimp => "MAILCHIMP_WEBHOOK_SECRET",
+            WebhookProvider::Printy => "PRINTY_WEBHOOK_SECRET",
+            WebhookProvider::RevAI => "REVAI_WEBHOOK_SECRET",
+            WebhookProvider::Shippo => "SHIPPO_WEBHOOK_SECRET",
+            WebhookProvider::Store => "STORE_WEBHOOK_SECRET",
+        }
+    }
+
+    /// Verify a webhook request with the secret from the environment.
+    /// If the secret is not set, every request is rejected.
+    pub fn verify(self, headers: &HeaderMap, query: &str, body: &[u8]) -> Result<(), String> {
+        let secret = env::var(self.secret_env_var()).unwrap_or_default();
+        if secret.is_empty() {
+            return Err(format!("`{}` is not set, refusing all {} webhooks", self.secret_env_var(), self));
+        }
+
+        self.verify_with_secret(&secret, headers, query, body)
+    }
+
+    /// Verify a webhook request against the given secret.
+    pub fn verify_with_secret(self, secret: &str, headers: &HeaderMap, query: &str, body: &[u8]) -> Result<(), String> {
+        match self {
+            WebhookProvider::GitHub => {
+                // GitHub sends `sha256=<hex digest>`.
+                // FROM: https://docs.github.com/en/developers/webhooks-and-events/webhooks/securing-your-webhooks
+                let header = get_header(headers, "X-Hub-Signature-256")?;
+                let signature = hex::decode(header.trim_start_matches("sha256=")).map_err(|e| format!("decoding `X-Hub-Signature-256` failed: {}", e))?;
+                verify_hmac_sha256(secret, body, &signature)
+            }
+            WebhookProvider::Checkr => {
+                // Checkr sends the hex digest of the body signed with our API key.
+                // FROM: https://docs.checkr.com/#section/Webhooks/Securing-webhooks
+                let header = get_header(headers, "X-Checkr-Signature")?;
+                let signature = hex::decode(header).map_err(|e| format!("decoding `X-Checkr-Signature` failed: {}", e))?;
+                verify_hmac_sha256(secret, body, &signature)
+            }
+            WebhookProvider::DocuSign => {
+                // DocuSign Connect sends the base64 digest of the body for every key
+                // configured, so we accept a match on any of them to allow rotating keys.
+                // FROM: https://developers.docusign.com/platform/webhooks/connect/hmac/
+                let mut last_err = "missing `X-DocuSign-Signature-1` header".to_string();
+                for i in 1.. {
+                    let name = format!("X-DocuSign-Signature-{}", i);
+                    let header = match get_header(headers, &name) {
+                        Ok(h) => h,
+                        Err(_) => break,
+                    };
+                    // A header we can't decode doesn't rule out a match on the next one.
+                    let signature = match base64::decode(header) {
+                        Ok(s) => s,
+                        Err(e) => {
+                            last_err = format!("decoding `{}` failed: {}", name, e);
+                            continue;
+                        }
+                    };
+                    match verify_hmac_sha256(secret, body, &signature) {
+                        Ok(_) => return Ok(()),
+                        Err(e) => last_err = e,
+                    }
+                }
+                Err(last_err)
+            }
+            WebhookProvider::Mailchimp | WebhookProvider::Printy | WebhookProvider::RevAI | WebhookProvider::Shippo | WebhookProvider::Store => {
+                // These do not sign their payloads, so we give them a URL with a shared
+                // token in the query string, ie. `/shippo/tracking/update?token=<secret>`.
+                // The store and printy can also send it in the `X-Webhook-Token` header.
+                let token = match get_header(headers, "X-Webhook-Token") {
+                    Ok(t) => t,
+                    Err(_) => get_query_param(query, "token").ok_or_else(|| "missing `token` query parameter".to_string())?,
+                };
+                if constant_time_eq(token.as_bytes(), secret.as_bytes()) {
+                    Ok(())
+                } else {
+                    Err("token does not match".to_string())
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for WebhookProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Return the value for a header, or an error if it is not present.
+fn get_header(headers: &HeaderMap, name: &str) -> Result<String, String> {
+    match headers.get(name) {
+        Some(v) => Ok(v.to_str().map_err(|e| format!("header `{}` is not valid: {}", name, e))?.trim().to_string()),
+        None => Err(format!("missing `{}` header", name)),
+    }
+}
+
+/// Return the decoded value for a parameter in a query string.
+fn get_query_param(query: &str, name: &str) -> Option<String> {
+    for pair in query.split('&') {
+        let mut split = pair.splitn(2, '=');
+        if split.next() == Some(name) {
+            return urlencoding::decode(split.next().unwrap_or_default()).ok();
+        }
+    }
+
+    None
+}
+
+/// Check the HMAC-SHA256 of the body matches the signature we were given.
+fn verify_hmac_sha256(secret: &str, body: &[u8], signature: &[u8]) -> Result<(), String> {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).map_err(|e| format!("creating hmac failed: {}", e))?;
+    mac.update(body);
+    // This comparison is done in constant time.
+    mac.verify(signature).map_err(|_| "signature does not match".to_string())
+}
+
+/// Compare two byte slices without short-circuiting on the first difference.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    a.iter().zip(b.iter()).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use hmac::{Mac, NewMac};
+    use http::header::HeaderValue;
+    use http::HeaderMap;
+
+    use crate::signatures::{HmacSha256, WebhookProvider};
+
+    fn sign(secret: &str, body: &[u8]) -> Vec<u8> {
+        let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(body);
+        mac.finalize().into_bytes().to_vec()
+    }
+
+    #[test]
+    fn test_verify_github() {
+        let body = br#"{"action":"opened"}"#;
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Hub-Signature-256", HeaderValue::from_str(&format!("sha256={}", hex::encode(sign("secret", body)))).unwrap());
+
+        assert!(WebhookProvider::GitHub.verify_with_secret("secret", &headers, "", body).is_ok());
+        assert!(WebhookProvider::GitHub.verify_with_secret("not-the-secret", &headers, "", body).is_err());
+        assert!(WebhookProvider::GitHub.verify_with_secret("secret", &headers, "", br#"{"action":"closed"}"#).is_err());
+        assert!(WebhookProvider::GitHub.verify_with_secret("secret", &HeaderMap::new(), "", body).is_err());
+    }
+
+    #[test]
+    fn test_verify_checkr() {
+        let body = br#"{"type":"report.completed"}"#;
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Checkr-Signature", HeaderValue::from_str(&hex::encode(sign("secret", body))).unwrap());
+
+        assert!(WebhookProvider::Checkr.verify_with_secret("secret", &headers, "", body).is_ok());
+        assert!(WebhookProvider::Checkr.verify_with_secret("other", &headers, "", body).is_err());
+    }
+
+    #[test]
+    fn test_verify_docusign() {
+        let body = br#"{"envelopeId":"1234"}"#;
+        let mut headers = HeaderMap::new();
+        headers.insert("X-DocuSign-Signature-1", HeaderValue::from_str(&base64::encode(sign("old", body))).unwrap());
+        headers.insert("X-DocuSign-Signature-2", HeaderValue::from_str(&base64::encode(sign("new", body))).unwrap());
+
+        assert!(WebhookProvider::DocuSign.verify_with_secret("old", &headers, "", body).is_ok());
+        assert!(WebhookProvider::DocuSign.verify_with_secret("new", &headers, "", body).is_ok());
+        assert!(WebhookProvider::DocuSign.verify_with_secret("other", &headers, "", body).is_err());
+
+        // A malformed header doesn't stop us from checking the ones after it.
+        headers.insert("X-DocuSign-Signature-1", HeaderValue::from_static("not base64!"));
+        assert!(WebhookProvider::DocuSign.verify_with_secret("new", &headers, "", body).is_ok());
+        assert!(WebhookProvider::DocuSign.verify_with_secret("old", &headers, "", body).is_err());
+    }
+
+    #[test]
+    fn test_verify_token() {
+        let headers = HeaderMap::new();
+        assert!(WebhookProvider::Shippo.verify_with_secret("s3cr3t", &headers, "foo=bar&token=s3cr3t", b"").is_ok());
+        assert!(WebhookProvider::RevAI.verify_with_secret("s3cr3t", &headers, "token=s3cr3t", b"").is_ok());
+        assert!(WebhookProvider::Mailchimp.verify_with_secret("s3cr3t", &headers, "token=nope", b"").is_err());
+        assert!(WebhookProvider::Mailchimp.verify_with_secret("s3cr3t", &headers, "", b"").is_err());
+
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Webhook-Token", HeaderValue::from_static("s3cr3t"));
+        assert!(WebhookProvider::Store.verify_with_secret("s3cr3t", &headers, "", b"").is_ok());
+        assert!(WebhookProvider::Printy.verify_with_secret("s3cr3t", &headers, "", b"").is_ok());
+    }
+}
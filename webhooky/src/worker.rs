@@ -0,0 +1,152 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use cio_api::queue::{NewQueuedJob, QueuedJob, QueuedJobStatus};
+
+use revai::Job;
+
+use crate::{handle_airtable_shipments_outbound_create, handle_inbound_email_route, handle_revai_job_complete, handle_rfd_push, AirtableRowEvent, Context, GitHubWebhook};
+
+/// The number of jobs we run at the same time.
+pub(crate) const WORKERS: usize = 4;
+
+/// How long to wait before checking the queue again when it is empty.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How long a job can run before we give up on it. This must be shorter than
+/// `cio_api::queue::JOB_LEASE`, otherwise another worker could claim the job
+/// while it is still running.
+const JOB_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// The kinds of jobs webhooky enqueues, so we only claim jobs we know how to run.
+const KINDS: &[&str] = &["rfd-push", "shipments-outbound-create", "inbound-email-route", "revai-job-complete"];
+
+/// Work that a webhook handler hands off to the job queue so it survives the
+/// process restarting.
+pub(crate) enum WebhookJob {
+    /// Render and upload the RFDs changed by a push to the rfd repo.
+    RFDPush(GitHubWebhook),
+    /// Create the Shippo shipment and label for a new outbound shipment.
+    ShipmentsOutboundCreate(AirtableRowEvent),
+    /// Upload the attachments of a stored inbound email and route it.
+    InboundEmailRoute(i32),
+    /// Save the transcript of the recorded meeting a finished rev.ai job was for.
+    RevAIJobComplete(Job),
+}
+
+impl WebhookJob {
+    fn kind(&self) -> &'static str {
+        match self {
+            WebhookJob::RFDPush(_) => "rfd-push",
+            WebhookJob::ShipmentsOutboundCreate(_) => "shipments-outbound-create",
+            WebhookJob::InboundEmailRoute(_) => "inbound-email-route",
+            WebhookJob::RevAIJobComplete(_) => "revai-job-complete",
+        }
+    }
+
+    /// The key that identifies the work, so a webhook that is delivered twice
+    /// only does the work once.
+    fn idempotency_key(&self) -> String {
+        match self {
+            // The first commit does not identify a push: a force push can start with a
+            // commit an earlier push had, so key on the SHA the push left the ref at.
+            WebhookJob::RFDPush(event) => format!("{}-{}-{}", self.kind(), event.refv, event.after),
+            WebhookJob::ShipmentsOutboundCreate(event) => format!("{}-{}", self.kind(), event.record_id),
+            WebhookJob::InboundEmailRoute(id) => format!("{}-{}", self.kind(), id),
+            WebhookJob::RevAIJobComplete(job) => format!("{}-{}-{}", self.kind(), job.id, job.status),
+        }
+    }
+
+    /// Add the job to the queue.
+    pub(crate) fn enqueue(&self, db: &cio_api::db::Database) -> Result<QueuedJob, cio_api::Error> {
+        let new_job = match self {
+            WebhookJob::RFDPush(event) => NewQueuedJob::new(self.kind(), event, &self.idempotency_key())?,
+            WebhookJob::ShipmentsOutboundCreate(event) => NewQueuedJob::new(self.kind(), event, &self.idempotency_key())?,
+            WebhookJob::InboundEmailRoute(id) => NewQueuedJob::new(self.kind(), id, &self.idempotency_key())?,
+            WebhookJob::RevAIJobComplete(job) => NewQueuedJob::new(self.kind(), job, &self.idempotency_key())?,
+        };
+
+        let job = new_job.enqueue(db)?;
+        println!("[queue] enqueued {} job {} with key `{}`", job.kind, job.id, job.idempotency_key);
+        Ok(job)
+    }
+
+    fn from_queued(job: &QueuedJob) -> Result<Self, String> {
+        match job.kind.as_str() {
+            "rfd-push" => Ok(WebhookJob::RFDPush(serde_json::from_value(job.payload.clone()).map_err(|e| e.to_string())?)),
+            "shipments-outbound-create" => Ok(WebhookJob::ShipmentsOutboundCreate(serde_json::from_value(job.payload.clone()).map_err(|e| e.to_string())?)),
+            "inbound-email-route" => Ok(WebhookJob::InboundEmailRoute(serde_json::from_value(job.payload.clone()).map_err(|e| e.to_string())?)),
+            "revai-job-complete" => Ok(WebhookJob::RevAIJobComplete(serde_json::from_value(job.payload.clone()).map_err(|e| e.to_string())?)),
+            kind => Err(format!("unknown job kind `{}`", kind)),
+        }
+    }
+
+    async fn run(self, api_context: &Context) -> Result<(), String> {
+        let db = api_context.db.with_audit_source(format!("job:{}", self.kind()));
+        let result = match self {
+            WebhookJob::RFDPush(event) => handle_rfd_push(api_context, &db, event).await,
+            WebhookJob::ShipmentsOutboundCreate(event) => handle_airtable_shipments_outbound_create(api_context, &db, event).await,
+            WebhookJob::InboundEmailRoute(id) => handle_inbound_email_route(&db, id).await,
+            WebhookJob::RevAIJobComplete(job) => handle_revai_job_complete(&db, job).await,
+        };
+
+        result.map(|_| ()).map_err(|e| e.internal_message)
+    }
+}
+
+/// Claim and run jobs from the queue forever.
+pub(crate) async fn run(api_context: Arc<Context>) {
+    loop {
+        let job = match QueuedJob::claim_next(&api_context.db, KINDS) {
+            Ok(Some(job)) => job,
+            Ok(None) => {
+                tokio::time::sleep(POLL_INTERVAL).await;
+                continue;
+            }
+            Err(e) => {
+                println!("[queue] claiming the next job failed: {}", e);
+                tokio::time::sleep(POLL_INTERVAL).await;
+                continue;
+            }
+        };
+
+        run_job(api_context.clone(), job).await;
+    }
+}
+
+async fn run_job(api_context: Arc<Context>, job: QueuedJob) {
+    println!("[queue] running {} job {} (attempt {} of {})", job.kind, job.id, job.attempts, job.max_attempts);
+
+    // Run the job in its own task so a panic fails the job instead of taking the
+    // worker down with it.
+    let queued = job.clone();
+    let context = api_context.clone();
+    let mut handle = tokio::spawn(async move { WebhookJob::from_queued(&queued)?.run(&context).await });
+    let result = match tokio::time::timeout(JOB_TIMEOUT, &mut handle).await {
+        Ok(Ok(result)) => result,
+        Ok(Err(e)) => Err(format!("job panicked: {}", e)),
+        Err(_) => {
+            handle.abort();
+            Err(format!("job did not finish within {} seconds", JOB_TIMEOUT.as_secs()))
+        }
+    };
+
+    let finished = match result {
+        Ok(()) => job.succeed(&api_context.db),
+        Err(e) => {
+            println!("[queue] {} job {} failed: {}", job.kind, job.id, e);
+            job.fail(&api_context.db, &e)
+        }
+    };
+
+    match finished {
+        Ok(j) if j.status == QueuedJobStatus::Dead.to_string() => {
+            sentry::capture_message(
+                &format!("{} job {} failed {} times and will not be retried: {}", j.kind, j.id, j.attempts, j.last_error),
+                sentry::Level::Fatal,
+            );
+        }
+        Ok(j) => println!("[queue] {} job {} is now {}", j.kind, j.id, j.status),
+        Err(e) => println!("[queue] recording the result of {} job {} failed: {}", job.kind, job.id, e),
+    }
+}
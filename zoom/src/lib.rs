@@ -47,7 +47,7 @@ pub struct Zoom {
 
     token: String,
 
-    client: Arc<Client>,
+    client: Arc<cio_http::Client>,
 }
 
 impl Zoom {
@@ -70,7 +70,7 @@ impl Zoom {
                 secret: secret.to_string(),
                 account_id: account_id.to_string(),
                 token,
-                client: Arc::new(c),
+                client: Arc::new(cio_http::Client::new(c)),
             },
             Err(e) => panic!("creating client failed: {:?}", e),
         }
@@ -264,6 +264,28 @@ impl Zoom {
         Ok(())
     }
 
+    /// Deactivate a user.
+    /// The user can no longer sign in and their meetings are no longer hosted.
+    ///
+    /// From: https://marketplace.zoom.us/docs/api-reference/zoom-api/users/userstatus
+    pub async fn deactivate_user(&self, email: String) -> Result<(), APIError> {
+        // Build the request.
+        let request = self.request(Method::PUT, format!("users/{}/status", email), UserStatusOpts { action: "deactivate".to_string() }, None);
+
+        let resp = self.client.execute(request).await.unwrap();
+        match resp.status() {
+            StatusCode::NO_CONTENT => (),
+            s => {
+                return Err(APIError {
+                    status_code: s,
+                    body: resp.text().await.unwrap(),
+                })
+            }
+        };
+
+        Ok(())
+    }
+
     /// List rooms.
     pub async fn list_rooms(&self) -> Result<Vec<Room>, APIError> {
         // Build the request.
@@ -652,6 +674,11 @@ struct UpdateUserOpts {
     pub vanity_name: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+struct UserStatusOpts {
+    pub action: String,
+}
+
 /// A room.
 ///
 /// From: https://marketplace.zoom.us/docs/api-reference/zoom-api/rooms/getzrprofile